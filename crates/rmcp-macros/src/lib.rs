@@ -1,7 +1,10 @@
 #[allow(unused_imports)]
 use proc_macro::TokenStream;
 
+mod prompt;
+mod resource;
 mod tool;
+mod tool_client;
 
 #[proc_macro_attribute]
 pub fn tool(attr: TokenStream, input: TokenStream) -> TokenStream {
@@ -9,3 +12,24 @@ pub fn tool(attr: TokenStream, input: TokenStream) -> TokenStream {
         .unwrap_or_else(|err| err.to_compile_error())
         .into()
 }
+
+#[proc_macro_attribute]
+pub fn prompt(attr: TokenStream, input: TokenStream) -> TokenStream {
+    prompt::prompt(attr.into(), input.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+#[proc_macro_attribute]
+pub fn resource(attr: TokenStream, input: TokenStream) -> TokenStream {
+    resource::resource(attr.into(), input.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+#[proc_macro_attribute]
+pub fn tool_client(attr: TokenStream, input: TokenStream) -> TokenStream {
+    tool_client::tool_client(attr.into(), input.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
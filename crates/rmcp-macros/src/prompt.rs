@@ -0,0 +1,331 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Expr, FnArg, Ident, ItemFn, Token, Type, Visibility, parse::Parse, parse_quote};
+
+use crate::tool::{extract_doc_line, first_type_arg, to_pascal_case};
+
+pub(crate) const PROMPT_IDENT: &str = "prompt";
+
+#[derive(Default)]
+struct PromptFnItemAttrs {
+    name: Option<Expr>,
+    description: Option<Expr>,
+    vis: Option<Visibility>,
+}
+
+impl Parse for PromptFnItemAttrs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut name = None;
+        let mut description = None;
+        let mut vis = None;
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "name" => {
+                    let value: Expr = input.parse()?;
+                    name = Some(value);
+                }
+                "description" => {
+                    let value: Expr = input.parse()?;
+                    description = Some(value);
+                }
+                "vis" => {
+                    let value: Visibility = input.parse()?;
+                    vis = Some(value);
+                }
+                _ => {
+                    return Err(syn::Error::new(key.span(), "unknown attribute"));
+                }
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        Ok(PromptFnItemAttrs {
+            name,
+            description,
+            vis,
+        })
+    }
+}
+
+#[derive(Default)]
+struct PromptFnArgAttrs {
+    description: Option<String>,
+}
+
+impl Parse for PromptFnArgAttrs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut description = None;
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            match key.to_string().as_str() {
+                "description" => {
+                    input.parse::<Token![=]>()?;
+                    let value: syn::LitStr = input.parse()?;
+                    description = Some(value.value());
+                }
+                _ => {
+                    return Err(syn::Error::new(key.span(), "unknown attribute"));
+                }
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        Ok(PromptFnArgAttrs { description })
+    }
+}
+
+struct PromptArgument {
+    ident: Ident,
+    rust_type: Box<Type>,
+    description: Option<String>,
+}
+
+impl PromptArgument {
+    fn required(&self) -> bool {
+        first_type_arg(&self.rust_type, "Option").is_none()
+    }
+}
+
+pub(crate) fn prompt(attr: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
+    let input_fn = syn::parse2::<ItemFn>(input)?;
+    prompt_fn_item(attr, input_fn)
+}
+
+pub(crate) fn prompt_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Result<TokenStream> {
+    let fn_attrs: PromptFnItemAttrs = syn::parse2(attr)?;
+    let mut arguments = Vec::new();
+    for fn_arg in input_fn.sig.inputs.iter_mut() {
+        let FnArg::Typed(pat_type) = fn_arg else {
+            continue;
+        };
+        let mut arg_attrs = PromptFnArgAttrs::default();
+        let mut doc_lines = Vec::new();
+        let raw_attrs: Vec<_> = pat_type.attrs.drain(..).collect();
+        for attr in raw_attrs {
+            match &attr.meta {
+                syn::Meta::List(meta_list) if meta_list.path.is_ident(PROMPT_IDENT) => {
+                    arg_attrs = meta_list.parse_args()?;
+                }
+                // doc comments are not allowed on parameters, take them as description
+                syn::Meta::NameValue(_) if attr.path().is_ident("doc") => {
+                    doc_lines.extend(extract_doc_line(&attr));
+                }
+                _ => {
+                    pat_type.attrs.push(attr);
+                }
+            }
+        }
+        let syn::Pat::Ident(pat_ident) = pat_type.pat.as_ref() else {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "prompt argument must have an ident as name",
+            ));
+        };
+        arguments.push(PromptArgument {
+            ident: pat_ident.ident.clone(),
+            rust_type: pat_type.ty.clone(),
+            description: arg_attrs
+                .description
+                .or_else(|| (!doc_lines.is_empty()).then(|| doc_lines.join("\n"))),
+        });
+    }
+
+    let name = if let Some(expr) = fn_attrs.name {
+        expr
+    } else {
+        let fn_name = &input_fn.sig.ident;
+        parse_quote! {
+            stringify!(#fn_name)
+        }
+    };
+    let description = if let Some(expr) = fn_attrs.description {
+        // Use explicitly provided description if available
+        expr
+    } else {
+        // Try to extract documentation comments
+        let doc_content = input_fn
+            .attrs
+            .iter()
+            .filter_map(extract_doc_line)
+            .collect::<Vec<_>>()
+            .join("\n");
+        parse_quote! {
+                #doc_content.trim().to_string()
+        }
+    };
+
+    // generate get prompt attr function
+    let prompt_attr_fn = {
+        let prompt_attr_fn_ident = Ident::new(
+            &format!("{}_prompt_attr", input_fn.sig.ident),
+            proc_macro2::Span::call_site(),
+        );
+        let arguments_code = if arguments.is_empty() {
+            quote! { None }
+        } else {
+            let items = arguments.iter().map(|arg| {
+                let arg_name = arg.ident.to_string();
+                let arg_name = arg_name.trim_start_matches("r#");
+                let required = arg.required();
+                let description = match &arg.description {
+                    Some(description) => quote! { Some(#description.into()) },
+                    None => quote! { None },
+                };
+                quote! {
+                    rmcp::model::PromptArgument {
+                        name: #arg_name.into(),
+                        description: #description,
+                        required: Some(#required),
+                    }
+                }
+            });
+            quote! { Some(vec![#(#items),*]) }
+        };
+        let input_fn_attrs = &input_fn.attrs;
+        let input_fn_vis = &input_fn.vis;
+        quote! {
+            #(#input_fn_attrs)*
+            #input_fn_vis fn #prompt_attr_fn_ident() -> rmcp::model::Prompt {
+                rmcp::model::Prompt {
+                    name: #name.into(),
+                    description: Some(#description.into()),
+                    arguments: #arguments_code,
+                }
+            }
+        }
+    };
+
+    // generate wrapped get prompt function
+    let prompt_get_fn = {
+        // the wrapper function looks like:
+        // async fn #prompt_prompt_get(context: rmcp::handler::server::prompt::PromptGetContext<'_, Self>)
+        //      -> std::result::Result<rmcp::model::GetPromptResult, rmcp::Error>
+        // {
+        //      use rmcp::handler::server::prompt::*;
+        //      let (__rmcp_prompt_receiver, context) = <&Self>::from_prompt_get_context_part(context)?;
+        //      let (PromptArguments(__#PROMPT_PromptGetParam { arg_0, arg_1, .. }), context) =
+        //          PromptArguments::<__#PROMPT_PromptGetParam>::from_prompt_get_context_part(context)?;
+        //      Self::#prompt_ident(__rmcp_prompt_receiver, arg_0, arg_1, ..).await.into_get_prompt_result()
+        // }
+        let receiver_ident = Ident::new("__rmcp_prompt_receiver", proc_macro2::Span::call_site());
+        let receiver_extraction_part = input_fn.sig.receiver().map(|receiver| {
+            let ty = &receiver.ty;
+            quote! {
+                let (#receiver_ident, context) = <#ty>::from_prompt_get_context_part(context)?;
+            }
+        });
+        let argument_extraction_part = if arguments.is_empty() {
+            quote! {}
+        } else {
+            let pascal_case_prompt_name = to_pascal_case(&input_fn.sig.ident.to_string());
+            let temp_param_type_name = Ident::new(
+                &format!("__{pascal_case_prompt_name}PromptGetParam"),
+                proc_macro2::Span::call_site(),
+            );
+            let fields = arguments.iter().map(|arg| {
+                let PromptArgument {
+                    ident, rust_type, ..
+                } = arg;
+                let default = (!arg.required()).then(|| quote! { #[serde(default)] });
+                quote! {
+                    #default
+                    pub #ident: #rust_type,
+                }
+            });
+            let idents = arguments.iter().map(|arg| &arg.ident);
+            quote! {
+                use rmcp::serde;
+                #[allow(non_camel_case_types)]
+                #[derive(serde::Deserialize)]
+                struct #temp_param_type_name {
+                    #(#fields)*
+                }
+                let (PromptArguments(#temp_param_type_name {
+                    #(#idents,)*
+                }), context) = PromptArguments::<#temp_param_type_name>::from_prompt_get_context_part(context)?;
+            }
+        };
+        let params = input_fn.sig.inputs.iter().map(|fn_arg| match fn_arg {
+            FnArg::Receiver(_) => quote! { #receiver_ident },
+            FnArg::Typed(pat_type) => match pat_type.pat.as_ref() {
+                syn::Pat::Ident(pat_ident) => {
+                    let ident = &pat_ident.ident;
+                    quote! { #ident }
+                }
+                pat => quote! { #pat },
+            },
+        });
+        let raw_fn_ident = &input_fn.sig.ident;
+        let call = if input_fn.sig.asyncness.is_some() {
+            quote! {
+                Self::#raw_fn_ident(#(#params),*).await.into_get_prompt_result()
+            }
+        } else {
+            quote! {
+                Self::#raw_fn_ident(#(#params),*).into_get_prompt_result()
+            }
+        };
+        let prompt_get_fn_ident = Ident::new(
+            &format!("{}_prompt_get", input_fn.sig.ident),
+            proc_macro2::Span::call_site(),
+        );
+        let raw_fn_vis = fn_attrs.vis.as_ref().unwrap_or(&input_fn.vis);
+        let raw_fn_attr = &input_fn
+            .attrs
+            .iter()
+            .filter(|attr| !attr.path().is_ident(PROMPT_IDENT))
+            .collect::<Vec<_>>();
+        quote! {
+            #(#raw_fn_attr)*
+            #raw_fn_vis async fn #prompt_get_fn_ident(context: rmcp::handler::server::prompt::PromptGetContext<'_, Self>)
+                -> std::result::Result<rmcp::model::GetPromptResult, rmcp::Error> {
+                use rmcp::handler::server::prompt::*;
+                #receiver_extraction_part
+                #argument_extraction_part
+                #call
+            }
+        }
+    };
+
+    Ok(quote! {
+        #prompt_attr_fn
+        #prompt_get_fn
+        #input_fn
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_prompt_arguments() -> syn::Result<()> {
+        let attr = quote! {};
+        let input = quote! {
+            async fn greet(
+                &self,
+                /// who to greet
+                name: String,
+                #[prompt(description = "greeting style")]
+                style: Option<String>,
+            ) -> String {
+                format!("hello {name}")
+            }
+        };
+        let output = prompt(attr, input)?.to_string();
+        assert!(output.contains("fn greet_prompt_attr"));
+        assert!(output.contains("fn greet_prompt_get"));
+        assert!(output.contains("\"who to greet\""));
+        assert!(output.contains("\"greeting style\""));
+        assert!(output.contains("required : Some (true)"));
+        assert!(output.contains("required : Some (false)"));
+        assert!(!output.contains("# [prompt"));
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Expr, FnArg, Ident, ItemFn, LitStr, Token, Visibility, parse::Parse, parse_quote};
+
+use crate::tool::extract_doc_line;
+
+pub(crate) const RESOURCE_IDENT: &str = "resource";
+
+struct ResourceFnItemAttrs {
+    uri: LitStr,
+    name: Option<Expr>,
+    description: Option<Expr>,
+    mime_type: Option<Expr>,
+    vis: Option<Visibility>,
+}
+
+impl Parse for ResourceFnItemAttrs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut uri = None;
+        let mut name = None;
+        let mut description = None;
+        let mut mime_type = None;
+        let mut vis = None;
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "uri" => {
+                    let value: LitStr = input.parse()?;
+                    uri = Some(value);
+                }
+                "name" => {
+                    let value: Expr = input.parse()?;
+                    name = Some(value);
+                }
+                "description" => {
+                    let value: Expr = input.parse()?;
+                    description = Some(value);
+                }
+                "mime_type" => {
+                    let value: Expr = input.parse()?;
+                    mime_type = Some(value);
+                }
+                "vis" => {
+                    let value: Visibility = input.parse()?;
+                    vis = Some(value);
+                }
+                _ => {
+                    return Err(syn::Error::new(key.span(), "unknown attribute"));
+                }
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        let Some(uri) = uri else {
+            return Err(syn::Error::new(
+                input.span(),
+                "missing `uri` attribute for resource",
+            ));
+        };
+        Ok(ResourceFnItemAttrs {
+            uri,
+            name,
+            description,
+            mime_type,
+            vis,
+        })
+    }
+}
+
+// collect the variable names of a uri template like `file:///{path}`
+fn uri_template_variables(uri: &str) -> Vec<&str> {
+    let mut variables = Vec::new();
+    let mut rest = uri;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        variables.push(&rest[start + 1..start + len]);
+        rest = &rest[start + len + 1..];
+    }
+    variables
+}
+
+pub(crate) fn resource(attr: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
+    let input_fn = syn::parse2::<ItemFn>(input)?;
+    resource_fn_item(attr, input_fn)
+}
+
+pub(crate) fn resource_fn_item(attr: TokenStream, input_fn: ItemFn) -> syn::Result<TokenStream> {
+    let fn_attrs: ResourceFnItemAttrs = syn::parse2(attr)?;
+    let uri = fn_attrs.uri.value();
+    let variables = uri_template_variables(&uri);
+    let is_template = !variables.is_empty();
+
+    // every typed argument is extracted from the matched uri
+    let mut uri_params = Vec::new();
+    for fn_arg in &input_fn.sig.inputs {
+        let FnArg::Typed(pat_type) = fn_arg else {
+            continue;
+        };
+        let syn::Pat::Ident(pat_ident) = pat_type.pat.as_ref() else {
+            return Err(syn::Error::new_spanned(
+                &pat_type.pat,
+                "resource parameter must have an ident as name",
+            ));
+        };
+        let ident = &pat_ident.ident;
+        if !variables.contains(&ident.to_string().as_str()) {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!("parameter `{ident}` is not a variable of uri template `{uri}`"),
+            ));
+        }
+        uri_params.push((ident.clone(), pat_type.ty.clone()));
+    }
+
+    let name = if let Some(expr) = fn_attrs.name {
+        expr
+    } else {
+        let fn_name = &input_fn.sig.ident;
+        parse_quote! {
+            stringify!(#fn_name)
+        }
+    };
+    let description = if let Some(expr) = fn_attrs.description {
+        // Use explicitly provided description if available
+        expr
+    } else {
+        // Try to extract documentation comments
+        let doc_content = input_fn
+            .attrs
+            .iter()
+            .filter_map(extract_doc_line)
+            .collect::<Vec<_>>()
+            .join("\n");
+        parse_quote! {
+                #doc_content.trim().to_string()
+        }
+    };
+    let mime_type = match &fn_attrs.mime_type {
+        Some(expr) => quote! { Some(#expr.into()) },
+        None => quote! { None },
+    };
+
+    // generate get resource attr function
+    let resource_attr_fn = {
+        let resource_attr_fn_ident = Ident::new(
+            &format!("{}_resource_attr", input_fn.sig.ident),
+            proc_macro2::Span::call_site(),
+        );
+        let uri = &fn_attrs.uri;
+        let attr = if is_template {
+            quote! {
+                rmcp::handler::server::resource::ResourceAttr::Template(
+                    rmcp::model::RawResourceTemplate {
+                        uri_template: #uri.into(),
+                        name: #name.into(),
+                        description: Some(#description.into()),
+                        mime_type: #mime_type,
+                    }
+                    .no_annotation(),
+                )
+            }
+        } else {
+            quote! {
+                rmcp::handler::server::resource::ResourceAttr::Resource(
+                    rmcp::model::RawResource {
+                        uri: #uri.into(),
+                        name: #name.into(),
+                        description: Some(#description.into()),
+                        mime_type: #mime_type,
+                        size: None,
+                    }
+                    .no_annotation(),
+                )
+            }
+        };
+        let input_fn_attrs = &input_fn.attrs;
+        let input_fn_vis = &input_fn.vis;
+        quote! {
+            #(#input_fn_attrs)*
+            #input_fn_vis fn #resource_attr_fn_ident() -> rmcp::handler::server::resource::ResourceAttr {
+                use rmcp::model::AnnotateAble;
+                #attr
+            }
+        }
+    };
+
+    // generate wrapped read resource function
+    let resource_read_fn = {
+        // the wrapper function looks like:
+        // async fn #resource_resource_read(context: rmcp::handler::server::resource::ResourceReadContext<'_, Self>)
+        //      -> std::result::Result<rmcp::model::ReadResourceResult, rmcp::Error>
+        // {
+        //      use rmcp::handler::server::resource::*;
+        //      let (__rmcp_resource_receiver, context) = <&Self>::from_resource_read_context_part(context)?;
+        //      let path = context.uri_param::<PathType>("path")?;
+        //      let __rmcp_resource_uri = context.uri().to_owned();
+        //      Self::#resource_ident(__rmcp_resource_receiver, path).await.into_read_resource_result(__rmcp_resource_uri, mime_type)
+        // }
+        let receiver_ident = Ident::new("__rmcp_resource_receiver", proc_macro2::Span::call_site());
+        let receiver_extraction_part = input_fn.sig.receiver().map(|receiver| {
+            let ty = &receiver.ty;
+            quote! {
+                let (#receiver_ident, context) = <#ty>::from_resource_read_context_part(context)?;
+            }
+        });
+        let uri_param_extraction_part = uri_params.iter().map(|(ident, ty)| {
+            let name = ident.to_string();
+            quote! {
+                let #ident = context.uri_param::<#ty>(#name)?;
+            }
+        });
+        let params = input_fn.sig.inputs.iter().map(|fn_arg| match fn_arg {
+            FnArg::Receiver(_) => quote! { #receiver_ident },
+            FnArg::Typed(pat_type) => match pat_type.pat.as_ref() {
+                syn::Pat::Ident(pat_ident) => {
+                    let ident = &pat_ident.ident;
+                    quote! { #ident }
+                }
+                pat => quote! { #pat },
+            },
+        });
+        let raw_fn_ident = &input_fn.sig.ident;
+        let call = if input_fn.sig.asyncness.is_some() {
+            quote! {
+                Self::#raw_fn_ident(#(#params),*).await
+            }
+        } else {
+            quote! {
+                Self::#raw_fn_ident(#(#params),*)
+            }
+        };
+        let resource_read_fn_ident = Ident::new(
+            &format!("{}_resource_read", input_fn.sig.ident),
+            proc_macro2::Span::call_site(),
+        );
+        let raw_fn_vis = fn_attrs.vis.as_ref().unwrap_or(&input_fn.vis);
+        let raw_fn_attr = &input_fn
+            .attrs
+            .iter()
+            .filter(|attr| !attr.path().is_ident(RESOURCE_IDENT))
+            .collect::<Vec<_>>();
+        quote! {
+            #(#raw_fn_attr)*
+            #raw_fn_vis async fn #resource_read_fn_ident(context: rmcp::handler::server::resource::ResourceReadContext<'_, Self>)
+                -> std::result::Result<rmcp::model::ReadResourceResult, rmcp::Error> {
+                use rmcp::handler::server::resource::*;
+                #receiver_extraction_part
+                #(#uri_param_extraction_part)*
+                let __rmcp_resource_uri = context.uri().to_owned();
+                #call.into_read_resource_result(__rmcp_resource_uri, #mime_type)
+            }
+        }
+    };
+
+    Ok(quote! {
+        #resource_attr_fn
+        #resource_read_fn
+        #input_fn
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resource_template_params() -> syn::Result<()> {
+        let attr = quote! {
+            uri = "file:///{path}", mime_type = "text/plain"
+        };
+        let input = quote! {
+            async fn file(&self, path: String) -> String {
+                path
+            }
+        };
+        let output = resource(attr, input)?.to_string();
+        assert!(output.contains("ResourceAttr :: Template"));
+        assert!(output.contains("uri_param :: < String > (\"path\")"));
+
+        let attr = quote! {
+            uri = "file:///{path}"
+        };
+        let input = quote! {
+            async fn file(&self, name: String) -> String {
+                name
+            }
+        };
+        let error = resource(attr, input).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("is not a variable of uri template")
+        );
+        Ok(())
+    }
+}
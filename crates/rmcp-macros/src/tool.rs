@@ -4,12 +4,15 @@ use proc_macro2::TokenStream;
 use quote::{ToTokens, quote};
 use serde_json::json;
 use syn::{
-    Expr, FnArg, Ident, ItemFn, ItemImpl, MetaList, PatType, Token, Type, Visibility,Lit,
+    Expr, FnArg, Ident, ItemFn, ItemImpl, Lit, LitStr, MetaList, PatType, Token, Type, Visibility,
+    ext::IdentExt,
     parse::{Parse, discouraged::Speculative},
     parse_quote,
     spanned::Spanned,
 };
 
+use crate::{prompt::PROMPT_IDENT, resource::RESOURCE_IDENT};
+
 /// Stores tool annotation attributes
 #[derive(Default, Clone)]
 struct ToolAnnotationAttrs(pub serde_json::Map<String, serde_json::Value>);
@@ -46,15 +49,107 @@ impl Parse for ToolAnnotationAttrs {
 #[derive(Default)]
 pub(crate) struct ToolImplItemAttrs {
     tool_box: Option<Option<Ident>>,
+    prompt_box: Option<Option<Ident>>,
+    resource_box: Option<Option<Ident>>,
     default_build: bool,
     description: Option<Expr>,
+    // `mutability = lock`, serve the handler through `Locked<Self>`
+    lock: bool,
+    // split `list_tools` into pages of this size
+    page_size: Option<syn::LitInt>,
+    // the default `on_error` of the tools returning a `Result`
+    on_error: Option<Expr>,
+    // `capabilities(..)`, enabled on top of the ones of the boxes
+    capabilities: Vec<CapabilityAttr>,
+    server_name: Option<Expr>,
+    version: Option<Expr>,
+}
+
+/// The capabilities `capabilities(..)` accepts, in the order they are enabled
+const SERVER_CAPABILITIES: [&str; 6] = [
+    "tools",
+    "prompts",
+    "resources",
+    "logging",
+    "completions",
+    "experimental",
+];
+
+/// The options a capability of `capabilities(..)` accepts, with the builder method enabling them
+const SERVER_CAPABILITY_OPTIONS: [(&str, &str, &str); 4] = [
+    ("tools", "list_changed", "enable_tool_list_changed"),
+    ("prompts", "list_changed", "enable_prompts_list_changed"),
+    ("resources", "subscribe", "enable_resources_subscribe"),
+    ("resources", "list_changed", "enable_resources_list_changed"),
+];
+
+/// A capability of `capabilities(..)`, with its options: `resources(subscribe, list_changed)`
+struct CapabilityAttr {
+    ident: Ident,
+    options: Vec<Ident>,
+}
+
+impl Parse for CapabilityAttr {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        if !SERVER_CAPABILITIES.contains(&ident.to_string().as_str()) {
+            return Err(syn::Error::new(
+                ident.span(),
+                format!(
+                    "unknown capability `{ident}`, expected one of {}",
+                    SERVER_CAPABILITIES.join(", ")
+                ),
+            ));
+        }
+        let mut options = Vec::new();
+        if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            let idents =
+                syn::punctuated::Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+            let expected = SERVER_CAPABILITY_OPTIONS
+                .iter()
+                .filter(|(capability, _, _)| ident == *capability)
+                .map(|(_, option, _)| *option)
+                .collect::<Vec<_>>();
+            for option in idents {
+                if !expected.contains(&option.to_string().as_str()) {
+                    let message = if expected.is_empty() {
+                        format!("capability `{ident}` has no option `{option}`")
+                    } else {
+                        format!(
+                            "unknown option `{option}` of capability `{ident}`, expected one of {}",
+                            expected.join(", ")
+                        )
+                    };
+                    return Err(syn::Error::new(option.span(), message));
+                }
+                if options.contains(&option) {
+                    return Err(syn::Error::new(
+                        option.span(),
+                        format!("duplicate option `{option}` of capability `{ident}`"),
+                    ));
+                }
+                options.push(option);
+            }
+        }
+        Ok(Self { ident, options })
+    }
 }
 
 impl Parse for ToolImplItemAttrs {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let mut tool_box = None;
+        let mut prompt_box = None;
+        let mut resource_box = None;
+        let mut lock = false;
+        let mut page_size = None;
+        let mut on_error = None;
         let mut default = true;
         let mut description = None;
+        let mut capabilities = Vec::<CapabilityAttr>::new();
+        let mut server_name = None;
+        let mut version = None;
         while !input.is_empty() {
             let key: Ident = input.parse()?;
             match key.to_string().as_str() {
@@ -66,6 +161,22 @@ impl Parse for ToolImplItemAttrs {
                         tool_box = Some(Some(value));
                     }
                 }
+                "prompt_box" => {
+                    prompt_box = Some(None);
+                    if input.lookahead1().peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        let value: Ident = input.parse()?;
+                        prompt_box = Some(Some(value));
+                    }
+                }
+                "resource_box" => {
+                    resource_box = Some(None);
+                    if input.lookahead1().peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        let value: Ident = input.parse()?;
+                        resource_box = Some(Some(value));
+                    }
+                }
                 "default_build" => {
                     if input.lookahead1().peek(Token![=]) {
                         input.parse::<Token![=]>()?;
@@ -92,6 +203,63 @@ impl Parse for ToolImplItemAttrs {
                         description = Some(value);
                     }
                 }
+                "mutability" => {
+                    input.parse::<Token![=]>()?;
+                    let value: Ident = input.parse()?;
+                    if value != "lock" {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "unknown mutability, expected `lock`",
+                        ));
+                    }
+                    lock = true;
+                }
+                "page_size" => {
+                    input.parse::<Token![=]>()?;
+                    let value: syn::LitInt = input.parse()?;
+                    if value.base10_parse::<usize>()? == 0 {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "page_size must be greater than zero",
+                        ));
+                    }
+                    page_size = Some(value);
+                }
+                "on_error" => {
+                    input.parse::<Token![=]>()?;
+                    let value: Expr = input.parse()?;
+                    on_error = Some(value);
+                }
+                "capabilities" => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    let declared =
+                        syn::punctuated::Punctuated::<CapabilityAttr, Token![,]>::parse_terminated(
+                            &content,
+                        )?;
+                    for capability in declared {
+                        if capabilities
+                            .iter()
+                            .any(|declared| declared.ident == capability.ident)
+                        {
+                            return Err(syn::Error::new(
+                                capability.ident.span(),
+                                format!("duplicate capability `{}`", capability.ident),
+                            ));
+                        }
+                        capabilities.push(capability);
+                    }
+                }
+                "server_name" => {
+                    input.parse::<Token![=]>()?;
+                    let value: Expr = input.parse()?;
+                    server_name = Some(value);
+                }
+                "version" => {
+                    input.parse::<Token![=]>()?;
+                    let value: Expr = input.parse()?;
+                    version = Some(value);
+                }
                 _ => {
                     return Err(syn::Error::new(key.span(), "unknown attribute"));
                 }
@@ -104,21 +272,69 @@ impl Parse for ToolImplItemAttrs {
 
         Ok(ToolImplItemAttrs {
             tool_box,
+            prompt_box,
+            resource_box,
             default_build: default,
             description,
+            lock,
+            page_size,
+            on_error,
+            capabilities,
+            server_name,
+            version,
         })
     }
 }
 
 #[derive(Default)]
-struct ToolFnItemAttrs {
-    name: Option<Expr>,
+pub(crate) struct ToolFnItemAttrs {
+    pub(crate) name: Option<Expr>,
     description: Option<Expr>,
     vis: Option<Visibility>,
-    aggr: bool,
+    pub(crate) aggr: bool,
     annotations: Option<ToolAnnotationAttrs>,
+    output: Option<Type>,
+    // evaluated against `&self` by the tool box, the tool is hidden when false
+    enabled_if: Option<Expr>,
+    // a `fn(&self) -> String` describing the tool when it is listed
+    description_fn: Option<Expr>,
+    // other names dispatched to the tool
+    aliases: Vec<Expr>,
+    // list the aliases as tools of their own
+    advertise_aliases: bool,
+    // serde case convention for the keys of individual params
+    pub(crate) rename_all: Option<LitStr>,
+    // advertised in the annotations and sent as a warning before each call
+    deprecated: Option<LitStr>,
+    // a `fn(E) -> Result<CallToolResult, rmcp::Error>` converting the error of the tool
+    on_error: Option<Expr>,
+    // overrides the default timeout installed with `with_tool_timeout`
+    timeout_ms: Option<syn::LitInt>,
+    // calls of this tool running at once, shared by every instance of the server
+    max_concurrent: Option<syn::LitInt>,
+    // a human-readable title, shown by the clients instead of the name
+    title: Option<Expr>,
+    // the `src` of the icons, each `icon = ..` adds one
+    icons: Vec<Expr>,
+    // the flags of `schema(...)`, set on the global `SchemaSettings` of the schemas of the tool
+    schema: Option<Vec<Ident>>,
 }
 
+// the flags accepted by `#[tool(schema(...))]`, the `with_` methods of `SchemaSettings`
+const SCHEMA_FLAGS: &[&str] = &["inline_refs", "strip_formats"];
+
+// the case conventions accepted by `#[serde(rename_all = "...")]`
+const RENAME_ALL_RULES: &[&str] = &[
+    "lowercase",
+    "UPPERCASE",
+    "PascalCase",
+    "camelCase",
+    "snake_case",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "SCREAMING-KEBAB-CASE",
+];
+
 impl Parse for ToolFnItemAttrs {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let mut name = None;
@@ -126,6 +342,19 @@ impl Parse for ToolFnItemAttrs {
         let mut vis = None;
         let mut aggr = false;
         let mut annotations = None;
+        let mut output = None;
+        let mut enabled_if = None;
+        let mut description_fn = None;
+        let mut aliases = Vec::new();
+        let mut advertise_aliases = false;
+        let mut rename_all = None;
+        let mut deprecated = None;
+        let mut on_error = None;
+        let mut timeout_ms = None;
+        let mut max_concurrent = None;
+        let mut title = None;
+        let mut icons = Vec::new();
+        let mut schema = None;
 
         while !input.is_empty() {
             let key: Ident = input.parse()?;
@@ -134,6 +363,28 @@ impl Parse for ToolFnItemAttrs {
                 aggr = true;
                 continue;
             }
+            if key_str == "schema" {
+                let content;
+                syn::parenthesized!(content in input);
+                let flags = content.parse_terminated(Ident::parse, Token![,])?;
+                for flag in &flags {
+                    if !SCHEMA_FLAGS.contains(&flag.to_string().as_str()) {
+                        return Err(syn::Error::new(
+                            flag.span(),
+                            format!(
+                                "unknown schema setting, expected one of {}",
+                                SCHEMA_FLAGS.join(", ")
+                            ),
+                        ));
+                    }
+                }
+                schema = Some(flags.into_iter().collect());
+                if input.is_empty() {
+                    break;
+                }
+                input.parse::<Token![,]>()?;
+                continue;
+            }
             input.parse::<Token![=]>()?;
             match key_str.as_str() {
                 "name" => {
@@ -155,6 +406,75 @@ impl Parse for ToolFnItemAttrs {
                     let value = content.parse()?;
                     annotations = Some(value);
                 }
+                "output" => {
+                    let value: Type = input.parse()?;
+                    output = Some(value);
+                }
+                "enabled_if" => {
+                    let value: Expr = input.parse()?;
+                    enabled_if = Some(value);
+                }
+                "description_fn" => {
+                    let value: Expr = input.parse()?;
+                    description_fn = Some(value);
+                }
+                "aliases" => {
+                    let value: syn::ExprArray = input.parse()?;
+                    aliases = value.elems.into_iter().collect();
+                }
+                "advertise_aliases" => {
+                    let value: syn::LitBool = input.parse()?;
+                    advertise_aliases = value.value;
+                }
+                "rename_all" => {
+                    let value: LitStr = input.parse()?;
+                    if !RENAME_ALL_RULES.contains(&value.value().as_str()) {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            format!(
+                                "unknown rename rule, expected one of {}",
+                                RENAME_ALL_RULES.join(", ")
+                            ),
+                        ));
+                    }
+                    rename_all = Some(value);
+                }
+                "deprecated" => {
+                    let value: LitStr = input.parse()?;
+                    deprecated = Some(value);
+                }
+                "on_error" => {
+                    let value: Expr = input.parse()?;
+                    on_error = Some(value);
+                }
+                "timeout_ms" => {
+                    let value: syn::LitInt = input.parse()?;
+                    if value.base10_parse::<u64>()? == 0 {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "timeout_ms must be greater than 0",
+                        ));
+                    }
+                    timeout_ms = Some(value);
+                }
+                "max_concurrent" => {
+                    let value: syn::LitInt = input.parse()?;
+                    if value.base10_parse::<usize>()? == 0 {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "max_concurrent must be greater than 0",
+                        ));
+                    }
+                    max_concurrent = Some(value);
+                }
+                "title" => {
+                    let value: Expr = input.parse()?;
+                    title = Some(value);
+                }
+                "icon" => {
+                    let value: Expr = input.parse()?;
+                    icons.push(value);
+                }
                 _ => {
                     return Err(syn::Error::new(key.span(), "unknown attribute"));
                 }
@@ -171,15 +491,136 @@ impl Parse for ToolFnItemAttrs {
             vis,
             aggr,
             annotations,
+            output,
+            enabled_if,
+            description_fn,
+            aliases,
+            advertise_aliases,
+            rename_all,
+            deprecated,
+            on_error,
+            timeout_ms,
+            max_concurrent,
+            title,
+            icons,
+            schema,
+        })
+    }
+}
+
+/// Default value of an individual tool parameter
+enum ToolFnParamDefault {
+    /// `#[tool(param, default)]`, use [`Default::default`]
+    Trait,
+    /// `#[tool(param, default = path::to::default_fn)]`
+    Path(syn::Path),
+    /// `#[tool(param, default = 42)]` or `#[tool(param, default = "42")]`
+    Expr(Expr),
+}
+
+impl ToolFnParamDefault {
+    fn from_expr(expr: Expr) -> syn::Result<Self> {
+        // like serde, a string literal holds the tokens of the default
+        let expr = match expr {
+            Expr::Lit(syn::ExprLit {
+                lit: Lit::Str(lit), ..
+            }) => lit.parse::<Expr>()?,
+            expr => expr,
+        };
+        match expr {
+            Expr::Path(syn::ExprPath {
+                qself: None, path, ..
+            }) => Ok(ToolFnParamDefault::Path(path)),
+            expr => Ok(ToolFnParamDefault::Expr(expr)),
+        }
+    }
+}
+
+/// Attributes of `#[tool(...)]` on a function argument
+#[derive(Default)]
+struct ToolFnArgAttrs {
+    marker: Option<ParamMarker>,
+    default: Option<ToolFnParamDefault>,
+    description: Option<String>,
+}
+
+impl Parse for ToolFnArgAttrs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut marker = None;
+        let mut default = None;
+        let mut description = None;
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            match key.to_string().as_str() {
+                PARAM_IDENT => {
+                    marker = Some(ParamMarker::Param);
+                }
+                AGGREGATED_IDENT | REQ_IDENT => {
+                    marker = Some(ParamMarker::Aggregated);
+                }
+                "default" => {
+                    if input.lookahead1().peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        let value: Expr = input.parse()?;
+                        default = Some(ToolFnParamDefault::from_expr(value)?);
+                    } else {
+                        default = Some(ToolFnParamDefault::Trait);
+                    }
+                }
+                "description" => {
+                    input.parse::<Token![=]>()?;
+                    let value: syn::LitStr = input.parse()?;
+                    description = Some(value.value());
+                }
+                _ => {
+                    return Err(syn::Error::new(key.span(), "unknown attribute"));
+                }
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        Ok(ToolFnArgAttrs {
+            marker,
+            default,
+            description,
         })
     }
 }
 
-struct ToolFnParamAttrs {
+pub(crate) struct ToolFnParamAttrs {
     serde_meta: Vec<MetaList>,
     schemars_meta: Vec<MetaList>,
     ident: Ident,
     rust_type: Box<Type>,
+    default: Option<ToolFnParamDefault>,
+    description: Option<String>,
+    // an aggregated param mixed with individual params
+    flatten: bool,
+}
+
+impl ToolFnParamAttrs {
+    fn default_fn_ident(&self) -> Ident {
+        Ident::new(
+            &format!("__rmcp_default_{}", self.ident),
+            proc_macro2::Span::call_site(),
+        )
+    }
+
+    // a function item is required by serde when the default is an expression
+    fn default_fn(&self) -> Option<TokenStream> {
+        let Some(ToolFnParamDefault::Expr(expr)) = &self.default else {
+            return None;
+        };
+        let default_fn_ident = self.default_fn_ident();
+        let rust_type = &self.rust_type;
+        Some(quote! {
+            fn #default_fn_ident() -> #rust_type {
+                #expr
+            }
+        })
+    }
 }
 
 impl ToTokens for ToolFnParamAttrs {
@@ -188,7 +629,27 @@ impl ToTokens for ToolFnParamAttrs {
         let rust_type = &self.rust_type;
         let serde_meta = &self.serde_meta;
         let schemars_meta = &self.schemars_meta;
+        let default = match &self.default {
+            Some(ToolFnParamDefault::Trait) => quote! { #[serde(default)] },
+            Some(ToolFnParamDefault::Path(path)) => {
+                let path = path.to_token_stream().to_string();
+                quote! { #[serde(default = #path)] }
+            }
+            Some(ToolFnParamDefault::Expr(_)) => {
+                let path = self.default_fn_ident().to_string();
+                quote! { #[serde(default = #path)] }
+            }
+            None => quote! {},
+        };
+        let description = self
+            .description
+            .as_ref()
+            .map(|description| quote! { #[schemars(description = #description)] });
+        let flatten = self.flatten.then(|| quote! { #[serde(flatten)] });
         tokens.extend(quote! {
+            #flatten
+            #default
+            #description
             #(#[#serde_meta])*
             #(#[#schemars_meta])*
             pub #ident: #rust_type,
@@ -198,7 +659,7 @@ impl ToTokens for ToolFnParamAttrs {
 
 #[derive(Default)]
 
-enum ToolParams {
+pub(crate) enum ToolParams {
     Aggregated {
         rust_type: PatType,
     },
@@ -214,12 +675,33 @@ struct ToolAttrs {
     fn_item: ToolFnItemAttrs,
     params: ToolParams,
 }
-const TOOL_IDENT: &str = "tool";
+pub(crate) const TOOL_IDENT: &str = "tool";
 const SERDE_IDENT: &str = "serde";
 const SCHEMARS_IDENT: &str = "schemars";
 const PARAM_IDENT: &str = "param";
 const AGGREGATED_IDENT: &str = "aggr";
 const REQ_IDENT: &str = "req";
+// unmarked arguments of these types are extracted from the call context instead of the arguments
+const EXTRACTOR_TYPES: &[&str] = &[
+    "CancellationToken",
+    "Extension",
+    "Peer",
+    "RequestMeta",
+    "Session",
+    "ToolName",
+    "ToolProgress",
+];
+
+fn is_extractor_type(ty: &Type) -> bool {
+    let Type::Path(type_path) = ty else {
+        return false;
+    };
+    type_path
+        .path
+        .segments
+        .last()
+        .is_some_and(|segment| EXTRACTOR_TYPES.contains(&segment.ident.to_string().as_str()))
+}
 
 pub enum ParamMarker {
     Param,
@@ -272,7 +754,11 @@ pub(crate) fn tool(attr: TokenStream, input: TokenStream) -> syn::Result<TokenSt
 
 pub(crate) fn tool_impl_item(attr: TokenStream, mut input: ItemImpl) -> syn::Result<TokenStream> {
     let tool_impl_attr: ToolImplItemAttrs = syn::parse2(attr)?;
+    if let Some(on_error) = &tool_impl_attr.on_error {
+        apply_default_on_error(&mut input.items, on_error);
+    }
     let tool_box_ident = tool_impl_attr.tool_box;
+    let page_size = tool_impl_attr.page_size;
     let mut extend_quote = None;
     let description = if let Some(expr) = tool_impl_attr.description {
         // Use explicitly provided description if available
@@ -289,20 +775,58 @@ pub(crate) fn tool_impl_item(attr: TokenStream, mut input: ItemImpl) -> syn::Res
                 #doc_content.trim().to_string()
         }
     };
-    // get all tool function ident
-    let mut tool_fn_idents = Vec::new();
+    // get all tool, prompt and resource function ident
+    let mut tool_fn_items = Vec::new();
+    let mut prompt_fn_items = Vec::new();
+    let mut resource_fn_items = Vec::new();
     for item in &input.items {
         if let syn::ImplItem::Fn(method) = item {
             for attr in &method.attrs {
                 if attr.path().is_ident(TOOL_IDENT) {
-                    tool_fn_idents.push(method.sig.ident.clone());
+                    tool_fn_items.push(ImplFnItem::new(method));
+                } else if attr.path().is_ident(PROMPT_IDENT) {
+                    prompt_fn_items.push(ImplFnItem::new(method));
+                } else if attr.path().is_ident(RESOURCE_IDENT) {
+                    resource_fn_items.push(ImplFnItem::new(method));
                 }
             }
         }
     }
+    let has_dynamic_tool_names = check_duplicate_tool_names(&input.items)?;
+    let hooks = tool_list_hooks(&input.items);
+    check_tool_mutability(
+        &input,
+        tool_impl_attr.lock,
+        tool_box_ident.is_some(),
+        !prompt_fn_items.is_empty() || !resource_fn_items.is_empty(),
+    )?;
+    check_tool_receivers(&input)?;
+    // prompts and resources in an inherent impl get their box even without the attribute
+    let box_ident = |attr: Option<Option<Ident>>, fn_items: &[ImplFnItem], default: &str| match attr
+    {
+        Some(ident) => {
+            Some(ident.unwrap_or_else(|| Ident::new(default, proc_macro2::Span::call_site())))
+        }
+        None if input.trait_.is_none() && !fn_items.is_empty() => {
+            Some(Ident::new(default, proc_macro2::Span::call_site()))
+        }
+        None => None,
+    };
+    let prompt_box_ident = box_ident(tool_impl_attr.prompt_box, &prompt_fn_items, "prompt_box");
+    let resource_box_ident = box_ident(
+        tool_impl_attr.resource_box,
+        &resource_fn_items,
+        "resource_box",
+    );
 
     // handle different cases
     if input.trait_.is_some() {
+        if tool_box_ident.is_none() && prompt_box_ident.is_none() && resource_box_ident.is_none() {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "tool_box attribute is required for trait implementation",
+            ));
+        }
         if let Some(ident) = tool_box_ident {
             // check if there are generic parameters
             if !input.generics.params.is_empty() {
@@ -323,10 +847,10 @@ pub(crate) fn tool_impl_item(attr: TokenStream, mut input: ItemImpl) -> syn::Res
                 input.items.push(parse_quote! {
                     async fn list_tools(
                         &self,
-                        request: rmcp::model::PaginatedRequestParam,
+                        request: Option<rmcp::model::PaginatedRequestParam>,
                         context: rmcp::service::RequestContext<rmcp::RoleServer>,
                     ) -> Result<rmcp::model::ListToolsResult, rmcp::Error> {
-                        self.list_tools_inner(request, context).await
+                        self.list_tools_inner(request.unwrap_or_default(), context).await
                     }
                 });
             } else {
@@ -335,70 +859,500 @@ pub(crate) fn tool_impl_item(attr: TokenStream, mut input: ItemImpl) -> syn::Res
                     rmcp::tool_box!(@derive #ident);
                 ));
             }
-        } else {
-            return Err(syn::Error::new(
-                proc_macro2::Span::call_site(),
-                "tool_box attribute is required for trait implementation",
-            ));
+        }
+        if let Some(ident) = prompt_box_ident {
+            if !input.generics.params.is_empty() {
+                input.items.push(parse_quote! {
+                    async fn get_prompt(
+                        &self,
+                        request: rmcp::model::GetPromptRequestParam,
+                        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::GetPromptResult, rmcp::Error> {
+                        self.get_prompt_inner(request, context).await
+                    }
+                });
+                input.items.push(parse_quote! {
+                    async fn list_prompts(
+                        &self,
+                        request: Option<rmcp::model::PaginatedRequestParam>,
+                        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::ListPromptsResult, rmcp::Error> {
+                        self.list_prompts_inner(request.unwrap_or_default(), context).await
+                    }
+                });
+            } else {
+                input.items.push(parse_quote!(
+                    rmcp::prompt_box!(@derive #ident);
+                ));
+            }
+        }
+        if let Some(ident) = resource_box_ident {
+            if !input.generics.params.is_empty() {
+                input.items.push(parse_quote! {
+                    async fn read_resource(
+                        &self,
+                        request: rmcp::model::ReadResourceRequestParam,
+                        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::ReadResourceResult, rmcp::Error> {
+                        self.read_resource_inner(request, context).await
+                    }
+                });
+                input.items.push(parse_quote! {
+                    async fn list_resources(
+                        &self,
+                        request: Option<rmcp::model::PaginatedRequestParam>,
+                        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::ListResourcesResult, rmcp::Error> {
+                        self.list_resources_inner(request.unwrap_or_default(), context).await
+                    }
+                });
+                input.items.push(parse_quote! {
+                    async fn list_resource_templates(
+                        &self,
+                        request: Option<rmcp::model::PaginatedRequestParam>,
+                        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::ListResourceTemplatesResult, rmcp::Error> {
+                        self.list_resource_templates_inner(request.unwrap_or_default(), context).await
+                    }
+                });
+            } else {
+                input.items.push(parse_quote!(
+                    rmcp::resource_box!(@derive #ident);
+                ));
+            }
         }
     } else if let Some(ident) = tool_box_ident {
         // if it is a normal impl block
+        let ident = ident.unwrap_or_else(|| Ident::new("tool_box", proc_macro2::Span::call_site()));
+        let enabled_fn = (!hooks.enabled_conditions.is_empty())
+            .then(|| Ident::new(&format!("{ident}_enabled"), proc_macro2::Span::call_site()));
+        if let Some(enabled_fn) = &enabled_fn {
+            let checks = hooks.enabled_conditions.iter().map(|(tool, condition)| {
+                let ImplFnItem { ident, cfg_attrs } = tool;
+                let name_matches = hooks.name_matches(ident);
+                quote! {
+                    #(#cfg_attrs)*
+                    if #name_matches {
+                        return #condition;
+                    }
+                }
+            });
+            input.items.push(parse_quote! {
+                fn #enabled_fn(&self, name: &str) -> bool {
+                    #(#checks)*
+                    true
+                }
+            });
+        }
+        let describe_fn = (!hooks.description_fns.is_empty())
+            .then(|| Ident::new(&format!("{ident}_describe"), proc_macro2::Span::call_site()));
+        if let Some(describe_fn) = &describe_fn {
+            let descriptions = hooks.description_fns.iter().map(|(tool, description_fn)| {
+                let ImplFnItem { ident, cfg_attrs } = tool;
+                let name_matches = hooks.name_matches(ident);
+                quote! {
+                    #(#cfg_attrs)*
+                    if #name_matches {
+                        return Some((#description_fn)(self));
+                    }
+                }
+            });
+            input.items.push(parse_quote! {
+                fn #describe_fn(&self, name: &str) -> Option<String> {
+                    #(#descriptions)*
+                    None
+                }
+            });
+        }
+        let aliases_fn = (!hooks.aliases.is_empty())
+            .then(|| Ident::new(&format!("{ident}_aliases"), proc_macro2::Span::call_site()));
+        let has_prompts = prompt_box_ident.is_some();
+        let has_resources = resource_box_ident.is_some();
+        let declared = &tool_impl_attr.capabilities;
+        // each capability can only be enabled once by the builder
+        let enables = SERVER_CAPABILITIES
+            .iter()
+            .filter(|capability| {
+                let inferred = match **capability {
+                    "tools" => true,
+                    "prompts" => has_prompts,
+                    "resources" => has_resources,
+                    _ => false,
+                };
+                inferred
+                    || declared
+                        .iter()
+                        .any(|declared| declared.ident == *capability)
+            })
+            .map(|capability| {
+                let enable = Ident::new(
+                    &format!("enable_{capability}"),
+                    proc_macro2::Span::call_site(),
+                );
+                // the options are enabled right after their capability, in the order of the table
+                let options = declared
+                    .iter()
+                    .filter(|declared| declared.ident == *capability)
+                    .flat_map(|declared| {
+                        SERVER_CAPABILITY_OPTIONS
+                            .iter()
+                            .filter(move |(of, option, _)| {
+                                of == capability
+                                    && declared.options.iter().any(|ident| ident == option)
+                            })
+                    })
+                    .map(|(_, _, method)| Ident::new(method, proc_macro2::Span::call_site()));
+                quote! { .#enable() #(.#options())* }
+            });
+        let capabilities = quote! {
+            rmcp::model::ServerCapabilities::builder()
+                #(#enables)*
+                .build()
+        };
+        // the missing half of the server info comes from the crate using the macro
+        let server_info = match (&tool_impl_attr.server_name, &tool_impl_attr.version) {
+            (None, None) => None,
+            (server_name, version) => {
+                let name = server_name
+                    .as_ref()
+                    .map(|name| quote! { #name })
+                    .unwrap_or_else(|| quote! { env!("CARGO_CRATE_NAME") });
+                let version = version
+                    .as_ref()
+                    .map(|version| quote! { #version })
+                    .unwrap_or_else(|| quote! { env!("CARGO_PKG_VERSION") });
+                Some(quote! {
+                    server_info: rmcp::model::Implementation {
+                        name: (#name).into(),
+                        version: (#version).into(),
+                    },
+                })
+            }
+        };
         if !input.generics.params.is_empty() {
             // if there are generic parameters, not use tool_box! macro, but generate code directly
 
             // create call code for each tool function
-            let match_arms = tool_fn_idents.iter().map(|ident| {
-                let attr_fn = Ident::new(&format!("{}_tool_attr", ident), ident.span());
+            let match_arms = tool_fn_items.iter().map(|ImplFnItem { ident, cfg_attrs }| {
                 let call_fn = Ident::new(&format!("{}_tool_call", ident), ident.span());
+                let name_matches = hooks.name_matches(ident);
                 quote! {
-                    name if name == Self::#attr_fn().name => {
+                    #(#cfg_attrs)*
+                    name if #name_matches => {
                         Self::#call_fn(tcc).await
                     }
                 }
             });
 
-            let tool_attrs = tool_fn_idents.iter().map(|ident| {
+            let tool_attrs = tool_fn_items.iter().map(|ImplFnItem { ident, cfg_attrs }| {
                 let attr_fn = Ident::new(&format!("{}_tool_attr", ident), ident.span());
-                quote! { Self::#attr_fn() }
+                let advertised_aliases = hooks
+                    .aliases
+                    .iter()
+                    .filter(|(item, _, advertise)| &item.ident == ident && *advertise)
+                    .flat_map(|(_, aliases, _)| aliases);
+                let cfg = quote! { #(#cfg_attrs)* };
+                quote! {
+                    #cfg
+                    tools.push(Self::#attr_fn());
+                    #(
+                        #cfg
+                        tools.push(rmcp::model::Tool {
+                            name: (#advertised_aliases).into(),
+                            ..Self::#attr_fn()
+                        });
+                    )*
+                }
+            });
+            let enabled_check = enabled_fn.as_ref().map(|enabled_fn| {
+                quote! {
+                    if !self.#enabled_fn(&request.name) {
+                        return Err(rmcp::Error::tool_not_found(&request.name));
+                    }
+                }
+            });
+            let enabled_filter = enabled_fn.as_ref().map(|enabled_fn| {
+                quote! {
+                    let tools = tools.filter(|tool| self.#enabled_fn(&tool.name));
+                }
+            });
+            // only changes the descriptions of the listed tools, applied once they are cloned
+            let describe_map = describe_fn.as_ref().map(|describe_fn| {
+                quote! {
+                    let mut result = result;
+                    for tool in &mut result.tools {
+                        if let Some(description) = self.#describe_fn(&tool.name) {
+                            tool.description = Some(description.into());
+                        }
+                    }
+                }
+            });
+            // names which are not literals can only be checked at runtime
+            let duplicate_check = has_dynamic_tool_names.then(|| {
+                quote! {
+                    debug_assert!(
+                        tools
+                            .iter()
+                            .enumerate()
+                            .all(|(index, tool)| tools[..index].iter().all(|prev| prev.name != tool.name)),
+                        "duplicate tool name in tool box"
+                    );
+                }
             });
 
+            let (request_ident, list_result) = match &page_size {
+                Some(page_size) => (
+                    quote! { request },
+                    quote! {
+                        rmcp::handler::server::tool::paginate_tools(
+                            tools,
+                            request.cursor.as_deref(),
+                            #page_size,
+                        )?
+                    },
+                ),
+                None => (
+                    quote! { _ },
+                    quote! {
+                        rmcp::model::ListToolsResult {
+                            next_cursor: None,
+                            tools: tools.cloned().collect(),
+                            meta: None,
+                        }
+                    },
+                ),
+            };
+
             // implement call_tool method
             input.items.push(parse_quote! {
                 async fn call_tool_inner(
                     &self,
                     request: rmcp::model::CallToolRequestParam,
                     context: rmcp::service::RequestContext<rmcp::RoleServer>,
-                ) -> Result<rmcp::model::CallToolResult, rmcp::Error> {
+                ) -> Result<rmcp::model::CallToolResult, rmcp::Error>
+                where
+                    Self: 'static,
+                {
+                    #enabled_check
                     let tcc = rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
-                    match tcc.name() {
-                        #(#match_arms,)*
-                        _ => Err(rmcp::Error::invalid_params("tool not found", None)),
-                    }
+                    rmcp::handler::server::tool::call_with_middleware(tcc, |tcc| async move {
+                        match tcc.name() {
+                            #(#match_arms,)*
+                            _ => Err(rmcp::Error::tool_not_found(tcc.name())),
+                        }
+                    })
+                    .await
                 }
             });
 
-            // implement list_tools method
+            // the tools are cached by the `TypeId` of the server, a generic one has to be 'static
+            let static_bound = (!input.generics.params.is_empty()).then(|| {
+                quote! {
+                    where
+                        Self: 'static,
+                }
+            });
+            // implement list_tools method, the tools are built once for each type of the server
+            // and only the listed ones are cloned, cheaply as they share their strings and schemas
             input.items.push(parse_quote! {
                 async fn list_tools_inner(
                     &self,
-                    _: rmcp::model::PaginatedRequestParam,
+                    #request_ident: rmcp::model::PaginatedRequestParam,
                     _: rmcp::service::RequestContext<rmcp::RoleServer>,
-                ) -> Result<rmcp::model::ListToolsResult, rmcp::Error> {
-                    Ok(rmcp::model::ListToolsResult {
-                        next_cursor: None,
-                        tools: vec![#(#tool_attrs),*],
-                    })
+                ) -> Result<rmcp::model::ListToolsResult, rmcp::Error>
+                #static_bound
+                {
+                    let tools = rmcp::handler::server::tool::cached_tools::<Self>(|| {
+                        let mut tools = Vec::new();
+                        #(#tool_attrs)*
+                        #duplicate_check
+                        tools
+                    });
+                    let tools = tools.iter();
+                    #enabled_filter
+                    let result = #list_result;
+                    #describe_map
+                    Ok(result)
                 }
             });
 
-            if tool_impl_attr.default_build {
-                let struct_name = input.self_ty.clone();
-                let generic = &input.generics;
-                let extend = quote! {
-                    impl #generic rmcp::handler::server::ServerHandler for #struct_name {
-                        async fn call_tool(
-                            &self,
-                            request: rmcp::model::CallToolRequestParam,
+            let prompt_methods = has_prompts.then(|| {
+                let match_arms = prompt_fn_items.iter().map(|ImplFnItem { ident, cfg_attrs }| {
+                    let attr_fn = Ident::new(&format!("{}_prompt_attr", ident), ident.span());
+                    let get_fn = Ident::new(&format!("{}_prompt_get", ident), ident.span());
+                    quote! {
+                        #(#cfg_attrs)*
+                        name if name == Self::#attr_fn().name => {
+                            Self::#get_fn(pgc).await
+                        }
+                    }
+                });
+                let prompt_attrs = prompt_fn_items.iter().map(|ImplFnItem { ident, cfg_attrs }| {
+                    let attr_fn = Ident::new(&format!("{}_prompt_attr", ident), ident.span());
+                    quote! {
+                        #(#cfg_attrs)*
+                        prompts.push(Self::#attr_fn());
+                    }
+                });
+                input.items.push(parse_quote! {
+                    async fn get_prompt_inner(
+                        &self,
+                        request: rmcp::model::GetPromptRequestParam,
+                        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::GetPromptResult, rmcp::Error> {
+                        let pgc = rmcp::handler::server::prompt::PromptGetContext::new(self, request, context);
+                        match pgc.name() {
+                            #(#match_arms,)*
+                            _ => Err(rmcp::Error::prompt_not_found(pgc.name())),
+                        }
+                    }
+                });
+                input.items.push(parse_quote! {
+                    async fn list_prompts_inner(
+                        &self,
+                        _: rmcp::model::PaginatedRequestParam,
+                        _: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::ListPromptsResult, rmcp::Error> {
+                        let mut prompts = Vec::new();
+                        #(#prompt_attrs)*
+                        Ok(rmcp::model::ListPromptsResult {
+                            next_cursor: None,
+                            prompts,
+                            meta: None,
+                        })
+                    }
+                });
+                quote! {
+                    async fn get_prompt(
+                        &self,
+                        request: rmcp::model::GetPromptRequestParam,
+                        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::GetPromptResult, rmcp::Error> {
+                        self.get_prompt_inner(request, context).await
+                    }
+                    async fn list_prompts(
+                        &self,
+                        request: Option<rmcp::model::PaginatedRequestParam>,
+                        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::ListPromptsResult, rmcp::Error> {
+                        self.list_prompts_inner(request.unwrap_or_default(), context).await
+                    }
+                }
+            });
+
+            let resource_methods = has_resources.then(|| {
+                let read_branches = resource_fn_items.iter().map(|ImplFnItem { ident, cfg_attrs }| {
+                    let attr_fn = Ident::new(&format!("{}_resource_attr", ident), ident.span());
+                    let read_fn = Ident::new(&format!("{}_resource_read", ident), ident.span());
+                    quote! {
+                        #(#cfg_attrs)*
+                        if let Some(uri_params) = Self::#attr_fn().match_uri(rrc.uri()) {
+                            return Self::#read_fn(rrc.with_uri_params(uri_params)).await;
+                        }
+                    }
+                });
+                let resource_attrs = resource_fn_items.iter().map(|ImplFnItem { ident, cfg_attrs }| {
+                    let attr_fn = Ident::new(&format!("{}_resource_attr", ident), ident.span());
+                    quote! {
+                        #(#cfg_attrs)*
+                        attrs.push(Self::#attr_fn());
+                    }
+                });
+                input.items.push(parse_quote! {
+                    async fn read_resource_inner(
+                        &self,
+                        request: rmcp::model::ReadResourceRequestParam,
+                        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::ReadResourceResult, rmcp::Error> {
+                        let rrc = rmcp::handler::server::resource::ResourceReadContext::new(self, request, context);
+                        #(#read_branches)*
+                        Err(rmcp::Error::resource_not_found(
+                            format!("resource not found: {}", rrc.uri()),
+                            None,
+                        ))
+                    }
+                });
+                input.items.push(parse_quote! {
+                    fn resource_attrs_inner() -> Vec<rmcp::handler::server::resource::ResourceAttr> {
+                        let mut attrs = Vec::new();
+                        #(#resource_attrs)*
+                        attrs
+                    }
+                });
+                input.items.push(parse_quote! {
+                    async fn list_resources_inner(
+                        &self,
+                        _: rmcp::model::PaginatedRequestParam,
+                        _: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::ListResourcesResult, rmcp::Error> {
+                        use rmcp::handler::server::resource::ResourceAttr;
+                        Ok(rmcp::model::ListResourcesResult {
+                            next_cursor: None,
+                            resources: Self::resource_attrs_inner()
+                                .into_iter()
+                                .filter_map(|attr| match attr {
+                                    ResourceAttr::Resource(resource) => Some(resource),
+                                    ResourceAttr::Template(_) => None,
+                                })
+                                .collect(),
+                            meta: None,
+                        })
+                    }
+                });
+                input.items.push(parse_quote! {
+                    async fn list_resource_templates_inner(
+                        &self,
+                        _: rmcp::model::PaginatedRequestParam,
+                        _: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::ListResourceTemplatesResult, rmcp::Error> {
+                        use rmcp::handler::server::resource::ResourceAttr;
+                        Ok(rmcp::model::ListResourceTemplatesResult {
+                            next_cursor: None,
+                            resource_templates: Self::resource_attrs_inner()
+                                .into_iter()
+                                .filter_map(|attr| match attr {
+                                    ResourceAttr::Template(template) => Some(template),
+                                    ResourceAttr::Resource(_) => None,
+                                })
+                                .collect(),
+                            meta: None,
+                        })
+                    }
+                });
+                quote! {
+                    async fn read_resource(
+                        &self,
+                        request: rmcp::model::ReadResourceRequestParam,
+                        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::ReadResourceResult, rmcp::Error> {
+                        self.read_resource_inner(request, context).await
+                    }
+                    async fn list_resources(
+                        &self,
+                        request: Option<rmcp::model::PaginatedRequestParam>,
+                        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::ListResourcesResult, rmcp::Error> {
+                        self.list_resources_inner(request.unwrap_or_default(), context).await
+                    }
+                    async fn list_resource_templates(
+                        &self,
+                        request: Option<rmcp::model::PaginatedRequestParam>,
+                        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+                    ) -> Result<rmcp::model::ListResourceTemplatesResult, rmcp::Error> {
+                        self.list_resource_templates_inner(request.unwrap_or_default(), context).await
+                    }
+                }
+            });
+
+            if tool_impl_attr.default_build {
+                let struct_name = input.self_ty.clone();
+                // keep the where-clause, the bounds of the impl block are needed by the handler too
+                let (impl_generics, _, where_clause) = input.generics.split_for_impl();
+                let extend = quote! {
+                    impl #impl_generics rmcp::handler::server::ServerHandler for #struct_name #where_clause {
+                        async fn call_tool(
+                            &self,
+                            request: rmcp::model::CallToolRequestParam,
                             context: rmcp::service::RequestContext<rmcp::RoleServer>,
                         ) -> Result<rmcp::model::CallToolResult, rmcp::Error> {
                             self.call_tool_inner(request, context).await
@@ -410,10 +1364,13 @@ pub(crate) fn tool_impl_item(attr: TokenStream, mut input: ItemImpl) -> syn::Res
                         ) -> Result<rmcp::model::ListToolsResult, rmcp::Error> {
                             self.list_tools_inner(request.unwrap_or_default(), context).await
                         }
+                        #prompt_methods
+                        #resource_methods
                         fn get_info(&self) -> rmcp::model::ServerInfo {
                             rmcp::model::ServerInfo {
                                 instructions: Some(#description.into()),
-                                capabilities: rmcp::model::ServerCapabilities::builder().enable_tools().build(),
+                                capabilities: #capabilities,
+                                #server_info
                                 ..Default::default()
                             }
                         }
@@ -424,22 +1381,121 @@ pub(crate) fn tool_impl_item(attr: TokenStream, mut input: ItemImpl) -> syn::Res
         } else {
             // if there are no generic parameters, use the original tool_box! macro
             let this_type_ident = &input.self_ty;
+            let enabled = enabled_fn
+                .as_ref()
+                .map(|enabled_fn| quote! { , enabled = #this_type_ident::#enabled_fn });
+            let describe = describe_fn
+                .as_ref()
+                .map(|describe_fn| quote! { , describe = #this_type_ident::#describe_fn });
+            let page_size = page_size
+                .as_ref()
+                .map(|page_size| quote! { , page_size = #page_size });
+            let aliases = aliases_fn.as_ref().map(|aliases_fn| {
+                let aliases = hooks.aliases.iter().map(|(tool, aliases, advertise)| {
+                    let ImplFnItem { ident, cfg_attrs } = tool;
+                    let attr_fn = Ident::new(&format!("{}_tool_attr", ident), ident.span());
+                    let cfg = quote! { #(#cfg_attrs)* };
+                    quote! {
+                        #(
+                            #cfg
+                            aliases.push(rmcp::handler::server::tool::ToolAlias {
+                                alias: (#aliases).into(),
+                                name: Self::#attr_fn().name,
+                                advertise: #advertise,
+                            });
+                        )*
+                    }
+                });
+                input.items.push(parse_quote! {
+                    fn #aliases_fn() -> Vec<rmcp::handler::server::tool::ToolAlias> {
+                        let mut aliases = Vec::new();
+                        #(#aliases)*
+                        aliases
+                    }
+                });
+                quote! { , aliases = #this_type_ident::#aliases_fn }
+            });
             input.items.push(parse_quote!(
                 rmcp::tool_box!(#this_type_ident {
-                    #(#tool_fn_idents),*
-                } #ident);
+                    #(#tool_fn_items),*
+                } #ident #enabled #describe #aliases #page_size);
             ));
-            if tool_impl_attr.default_build {
+            // checked against the other boxes by `tool_box!(combine ..)`
+            let names_fn = Ident::new(
+                &format!("{ident}_tool_names"),
+                proc_macro2::Span::call_site(),
+            );
+            let const_names = tool_fn_items
+                .iter()
+                .filter(|item| hooks.const_names.contains(&item.ident))
+                .map(|ImplFnItem { ident, cfg_attrs }| {
+                    let name_fn = Ident::new(&format!("{}_tool_name", ident), ident.span());
+                    quote! {
+                        #(#cfg_attrs)*
+                        #this_type_ident::#name_fn()
+                    }
+                });
+            input.items.push(parse_quote! {
+                #[allow(dead_code)]
+                const fn #names_fn() -> &'static [&'static str] {
+                    const NAMES: &[&str] = &[#(#const_names),*];
+                    NAMES
+                }
+            });
+            let prompt_derive = prompt_box_ident.map(|prompt_box_ident| {
+                input.items.push(parse_quote!(
+                    rmcp::prompt_box!(#this_type_ident {
+                        #(#prompt_fn_items),*
+                    } #prompt_box_ident);
+                ));
+                quote! {
+                    rmcp::prompt_box!(@derive #prompt_box_ident);
+                }
+            });
+            let resource_derive = resource_box_ident.map(|resource_box_ident| {
+                input.items.push(parse_quote!(
+                    rmcp::resource_box!(#this_type_ident {
+                        #(#resource_fn_items),*
+                    } #resource_box_ident);
+                ));
+                quote! {
+                    rmcp::resource_box!(@derive #resource_box_ident);
+                }
+            });
+            if tool_impl_attr.default_build && tool_impl_attr.lock {
                 let struct_name = input.self_ty.clone();
-                let generic = &input.generics;
+                // `Locked` is a foreign type, the `ServerHandler` of `Locked<Self>` comes from rmcp
                 let extend = quote! {
-                    impl #generic rmcp::handler::server::ServerHandler for #struct_name {
+                    impl rmcp::handler::server::tool::LockedServer for #struct_name {
+                        fn tools() -> &'static rmcp::handler::server::tool::ToolBox<Self> {
+                            Self::#ident()
+                        }
+
+                        fn info() -> rmcp::model::ServerInfo {
+                            rmcp::model::ServerInfo {
+                                instructions: Some(#description.into()),
+                                capabilities: #capabilities,
+                                #server_info
+                                ..Default::default()
+                            }
+                        }
+                    }
+                };
+                extend_quote.replace(extend);
+            } else if tool_impl_attr.default_build {
+                let struct_name = input.self_ty.clone();
+                let (impl_generics, _, where_clause) = input.generics.split_for_impl();
+                let extend = quote! {
+                    impl #impl_generics rmcp::handler::server::ServerHandler for #struct_name #where_clause {
                         rmcp::tool_box!(@derive #ident);
+                        #prompt_derive
+                        #resource_derive
 
                         fn get_info(&self) -> rmcp::model::ServerInfo {
                             rmcp::model::ServerInfo {
                                 instructions: Some(#description.into()),
-                                capabilities: rmcp::model::ServerCapabilities::builder().enable_tools().build(),
+                                capabilities: #capabilities,
+                                #server_info
                                 ..Default::default()
                             }
                         }
@@ -456,8 +1512,317 @@ pub(crate) fn tool_impl_item(attr: TokenStream, mut input: ItemImpl) -> syn::Res
     })
 }
 
+// add the impl-level `on_error` to the tools returning a `Result` which don't have their own
+fn apply_default_on_error(items: &mut [syn::ImplItem], on_error: &Expr) {
+    for item in items {
+        let syn::ImplItem::Fn(method) = item else {
+            continue;
+        };
+        let returns_result = match &method.sig.output {
+            syn::ReturnType::Type(_, ty) => match ty.as_ref() {
+                Type::Path(type_path) => type_path
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "Result"),
+                _ => false,
+            },
+            syn::ReturnType::Default => false,
+        };
+        if !returns_result {
+            continue;
+        }
+        for attr in method
+            .attrs
+            .iter_mut()
+            .filter(|attr| attr.path().is_ident(TOOL_IDENT))
+        {
+            match &attr.meta {
+                syn::Meta::Path(_) => {
+                    *attr = parse_quote! { #[tool(on_error = #on_error)] };
+                }
+                syn::Meta::List(meta_list) => {
+                    // malformed attributes are reported when expanding the tool function
+                    let Ok(fn_attrs) = meta_list.parse_args::<ToolFnItemAttrs>() else {
+                        continue;
+                    };
+                    if fn_attrs.on_error.is_some() {
+                        continue;
+                    }
+                    let tokens = &meta_list.tokens;
+                    let ends_with_comma = matches!(
+                        tokens.clone().into_iter().last(),
+                        Some(proc_macro2::TokenTree::Punct(punct)) if punct.as_char() == ','
+                    );
+                    *attr = if tokens.is_empty() || ends_with_comma {
+                        parse_quote! { #[tool(#tokens on_error = #on_error)] }
+                    } else {
+                        parse_quote! { #[tool(#tokens, on_error = #on_error)] }
+                    };
+                }
+                syn::Meta::NameValue(_) => {}
+            }
+        }
+    }
+}
+
+// a tool without receiver expands as a free function, which can't be called from inside the impl
+fn check_tool_receivers(input: &ItemImpl) -> syn::Result<()> {
+    for item in &input.items {
+        let syn::ImplItem::Fn(method) = item else {
+            continue;
+        };
+        if method.sig.receiver().is_none()
+            && method
+                .attrs
+                .iter()
+                .any(|attr| attr.path().is_ident(TOOL_IDENT))
+        {
+            return Err(syn::Error::new_spanned(
+                &method.sig,
+                "tools in an impl block need a receiver, declare stateless tools as free functions",
+            ));
+        }
+    }
+    Ok(())
+}
+
+// `&mut self` tools can only be dispatched through the `Locked` handle
+fn check_tool_mutability(
+    input: &ItemImpl,
+    lock: bool,
+    has_tool_box: bool,
+    has_prompts_or_resources: bool,
+) -> syn::Result<()> {
+    if lock {
+        let unsupported = if input.trait_.is_some() {
+            Some("trait implementation")
+        } else if !has_tool_box {
+            Some("impl block without `tool_box`")
+        } else if !input.generics.params.is_empty() {
+            Some("generic impl block")
+        } else if has_prompts_or_resources {
+            Some("impl block with prompts or resources")
+        } else {
+            None
+        };
+        if let Some(unsupported) = unsupported {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!("`mutability = lock` is not supported on {unsupported}"),
+            ));
+        }
+        return Ok(());
+    }
+    if !has_tool_box {
+        return Ok(());
+    }
+    for item in &input.items {
+        let syn::ImplItem::Fn(method) = item else {
+            continue;
+        };
+        if !method
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident(TOOL_IDENT))
+        {
+            continue;
+        }
+        if let Some(receiver) = method.sig.receiver() {
+            if receiver.reference.is_some() && receiver.mutability.is_some() {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "tools with `&mut self` require `#[tool(tool_box, mutability = lock)]` on the impl block",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+// a `#[tool]`, `#[prompt]` or `#[resource]` method of an impl block
+struct ImplFnItem {
+    ident: Ident,
+    // every generated reference to the method is gated like the method itself
+    cfg_attrs: Vec<syn::Attribute>,
+}
+
+impl ImplFnItem {
+    fn new(method: &syn::ImplItemFn) -> Self {
+        ImplFnItem {
+            ident: method.sig.ident.clone(),
+            cfg_attrs: method
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("cfg"))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+// used inside the `tool_box!`, `prompt_box!` and `resource_box!` invocations
+impl ToTokens for ImplFnItem {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let ImplFnItem { ident, cfg_attrs } = self;
+        tokens.extend(quote! {
+            #(#cfg_attrs)*
+            #ident
+        });
+    }
+}
+
+// the tool attributes resolved by the tool box rather than the tool function
+#[derive(Default)]
+struct ToolListHooks {
+    enabled_conditions: Vec<(ImplFnItem, Expr)>,
+    description_fns: Vec<(ImplFnItem, Expr)>,
+    // (tool, aliases, advertise_aliases)
+    aliases: Vec<(ImplFnItem, Vec<Expr>, bool)>,
+    // tools with a `{tool}_tool_name` const fn
+    const_names: HashSet<Ident>,
+}
+
+impl ToolListHooks {
+    // `name` matches the tool or one of its aliases
+    fn name_matches(&self, tool: &Ident) -> TokenStream {
+        let tool_name = if self.const_names.contains(tool) {
+            let name_fn = Ident::new(&format!("{}_tool_name", tool), tool.span());
+            quote! { Self::#name_fn() }
+        } else {
+            let attr_fn = Ident::new(&format!("{}_tool_attr", tool), tool.span());
+            quote! { Self::#attr_fn().name }
+        };
+        let aliases = self
+            .aliases
+            .iter()
+            .filter(|(item, ..)| &item.ident == tool)
+            .flat_map(|(_, aliases, _)| aliases);
+        quote! {
+            name == #tool_name #(|| name == #aliases)*
+        }
+    }
+}
+
+// collect the `enabled_if` conditions, `description_fn`s and aliases of the tools
+fn tool_list_hooks(items: &[syn::ImplItem]) -> ToolListHooks {
+    let mut hooks = ToolListHooks::default();
+    for item in items {
+        let syn::ImplItem::Fn(method) = item else {
+            continue;
+        };
+        for attr in method
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident(TOOL_IDENT))
+        {
+            let fn_attrs = match &attr.meta {
+                syn::Meta::Path(_) => ToolFnItemAttrs::default(),
+                // malformed attributes are reported when expanding the tool function
+                syn::Meta::List(meta_list) => match meta_list.parse_args::<ToolFnItemAttrs>() {
+                    Ok(fn_attrs) => fn_attrs,
+                    Err(_) => continue,
+                },
+                syn::Meta::NameValue(_) => continue,
+            };
+            if fn_attrs.name.as_ref().is_none_or(is_const_name) {
+                hooks.const_names.insert(method.sig.ident.clone());
+            }
+            if let Some(condition) = fn_attrs.enabled_if {
+                hooks
+                    .enabled_conditions
+                    .push((ImplFnItem::new(method), condition));
+            }
+            if let Some(description_fn) = fn_attrs.description_fn {
+                hooks
+                    .description_fns
+                    .push((ImplFnItem::new(method), description_fn));
+            }
+            if !fn_attrs.aliases.is_empty() {
+                hooks.aliases.push((
+                    ImplFnItem::new(method),
+                    fn_attrs.aliases,
+                    fn_attrs.advertise_aliases,
+                ));
+            }
+        }
+    }
+    hooks
+}
+
+// string literals and `stringify!` can be returned from a `const fn`
+fn is_const_name(expr: &Expr) -> bool {
+    match expr {
+        Expr::Macro(expr_macro) => expr_macro.mac.path.is_ident("stringify"),
+        _ => literal_str(expr).is_some(),
+    }
+}
+
+fn literal_str(expr: &Expr) -> Option<(String, proc_macro2::Span)> {
+    match expr {
+        Expr::Lit(syn::ExprLit {
+            lit: Lit::Str(name),
+            ..
+        }) => Some((name.value(), name.span())),
+        _ => None,
+    }
+}
+
+// emit an error on the second tool using the same literal name,
+// returns whether there are names which can't be checked at compile time
+fn check_duplicate_tool_names(items: &[syn::ImplItem]) -> syn::Result<bool> {
+    let mut names = std::collections::HashMap::new();
+    let mut has_dynamic_names = false;
+    for item in items {
+        let syn::ImplItem::Fn(method) = item else {
+            continue;
+        };
+        for attr in method
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident(TOOL_IDENT))
+        {
+            let fn_attrs = match &attr.meta {
+                syn::Meta::List(meta_list) => meta_list.parse_args::<ToolFnItemAttrs>(),
+                _ => Ok(ToolFnItemAttrs::default()),
+            };
+            // malformed attributes are reported when expanding the tool function
+            let Ok(fn_attrs) = fn_attrs else {
+                continue;
+            };
+            let primary = match &fn_attrs.name {
+                None => Some((method.sig.ident.to_string(), method.sig.ident.span())),
+                Some(expr) => literal_str(expr),
+            };
+            let aliases = fn_attrs.aliases.iter().map(literal_str);
+            // tools behind different cfg predicates may share a name
+            let cfg = ImplFnItem::new(method)
+                .cfg_attrs
+                .iter()
+                .map(|attr| attr.to_token_stream().to_string())
+                .collect::<Vec<_>>();
+            for name in std::iter::once(primary).chain(aliases) {
+                // names which are not literals can only be checked at runtime
+                let Some((name, span)) = name else {
+                    has_dynamic_names = true;
+                    continue;
+                };
+                if let Some(first) =
+                    names.insert((name.clone(), cfg.clone()), method.sig.ident.clone())
+                {
+                    return Err(syn::Error::new(
+                        span,
+                        format!("duplicate tool name `{name}`, already used by `{first}`"),
+                    ));
+                }
+            }
+        }
+    }
+    Ok(has_dynamic_names)
+}
+
 // extract doc line from attribute
-fn extract_doc_line(attr: &syn::Attribute) -> Option<String> {
+pub(crate) fn extract_doc_line(attr: &syn::Attribute) -> Option<String> {
     if !attr.path().is_ident("doc") {
         return None;
     }
@@ -479,15 +1844,75 @@ fn extract_doc_line(attr: &syn::Attribute) -> Option<String> {
     (!content.is_empty()).then_some(content)
 }
 
-pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Result<TokenStream> {
-    let mut tool_macro_attrs = ToolAttrs::default();
-    let args: ToolFnItemAttrs = syn::parse2(attr)?;
-    tool_macro_attrs.fn_item = args;
-    // let mut fommated_fn_args: Punctuated<FnArg, Comma> = Punctuated::new();
+// get the first type argument of `Ident<T, ..>`
+pub(crate) fn first_type_arg<'a>(ty: &'a Type, ident: &str) -> Option<&'a Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != ident {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    args.args.iter().find_map(|arg| match arg {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
+// replace every lifetime of the type with `'static`, `None` if it doesn't borrow anything
+fn with_static_lifetimes(ty: &Type) -> Option<Type> {
+    fn replace(tokens: TokenStream, found: &mut bool) -> TokenStream {
+        let mut output = TokenStream::new();
+        let mut tokens = tokens.into_iter();
+        while let Some(token) = tokens.next() {
+            match token {
+                proc_macro2::TokenTree::Punct(punct) if punct.as_char() == '\'' => {
+                    // the name of the lifetime follows the quote
+                    if let Some(name) = tokens.next() {
+                        *found |= name.to_string() != "static";
+                    }
+                    output.extend(quote! { 'static });
+                }
+                proc_macro2::TokenTree::Group(group) => {
+                    let mut replaced =
+                        proc_macro2::Group::new(group.delimiter(), replace(group.stream(), found));
+                    replaced.set_span(group.span());
+                    output.extend([proc_macro2::TokenTree::Group(replaced)]);
+                }
+                token => output.extend([token]),
+            }
+        }
+        output
+    }
+    let mut found = false;
+    let tokens = replace(ty.to_token_stream(), &mut found);
+    found.then(|| syn::parse2(tokens).expect("replacing lifetimes keeps the type valid"))
+}
+
+// infer output type `T` from `Json<T>` or `Result<Json<T>, E>`
+fn infer_output_type(output: &syn::ReturnType) -> Option<&Type> {
+    let syn::ReturnType::Type(_, ty) = output else {
+        return None;
+    };
+    let ty = first_type_arg(ty, "Result").unwrap_or(ty);
+    first_type_arg(ty, "Json")
+}
+
+// take the tool arguments out of `inputs`, with the indexes of their arguments,
+// the other arguments are extracted from the call context
+pub(crate) fn parse_tool_params(
+    inputs: &mut syn::punctuated::Punctuated<FnArg, Token![,]>,
+    aggr: bool,
+) -> syn::Result<(ToolParams, HashSet<usize>)> {
     let mut unextractable_args_indexes = HashSet::new();
-    for (index, mut fn_arg) in input_fn.sig.inputs.iter_mut().enumerate() {
+    let mut params = Vec::new();
+    let mut aggregated = Vec::new();
+    for (index, mut fn_arg) in inputs.iter_mut().enumerate() {
         enum Caught {
-            Param(ToolFnParamAttrs),
+            Param(Box<ToolFnParamAttrs>),
             Aggregated(PatType),
         }
         let mut caught = None;
@@ -498,6 +1923,8 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
             FnArg::Typed(pat_type) => {
                 let mut serde_metas = Vec::new();
                 let mut schemars_metas = Vec::new();
+                let mut arg_attrs = ToolFnArgAttrs::default();
+                let mut doc_lines = Vec::new();
                 let mut arg_ident = match pat_type.pat.as_ref() {
                     syn::Pat::Ident(pat_ident) => Some(pat_ident.ident.clone()),
                     _ => None,
@@ -510,17 +1937,37 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
                                 serde_metas.push(meta_list.clone());
                             } else if meta_list.path.is_ident(SCHEMARS_IDENT) {
                                 schemars_metas.push(meta_list.clone());
+                            } else if meta_list.path.is_ident(TOOL_IDENT) {
+                                arg_attrs = meta_list.parse_args()?;
                             } else {
                                 pat_type.attrs.push(attr);
                             }
                         }
+                        // doc comments are not allowed on parameters, take them as description
+                        syn::Meta::NameValue(_) if attr.path().is_ident("doc") => {
+                            doc_lines.extend(extract_doc_line(&attr));
+                        }
                         _ => {
                             pat_type.attrs.push(attr);
                         }
                     }
                 }
                 let pat_type = pat_type.clone();
-                if tool_macro_attrs.fn_item.aggr {
+                if arg_attrs.marker.is_none() && is_extractor_type(&pat_type.ty) {
+                    continue;
+                }
+                let is_aggregated = match arg_attrs.marker {
+                    Some(ParamMarker::Aggregated) => true,
+                    Some(ParamMarker::Param) => false,
+                    None => aggr,
+                };
+                if is_aggregated {
+                    if arg_attrs.default.is_some() || arg_attrs.description.is_some() {
+                        return Err(syn::Error::new(
+                            pat_type.span(),
+                            "default and description are only supported on individual parameters",
+                        ));
+                    }
                     caught.replace(Caught::Aggregated(pat_type.clone()));
                 } else {
                     let Some(arg_ident) = arg_ident.take() else {
@@ -529,35 +1976,27 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
                             "input param must have an ident as name",
                         ));
                     };
-                    caught.replace(Caught::Param(ToolFnParamAttrs {
+                    caught.replace(Caught::Param(Box::new(ToolFnParamAttrs {
                         serde_meta: Vec::new(),
                         schemars_meta: Vec::new(),
                         ident: arg_ident,
                         rust_type: pat_type.ty.clone(),
-                    }));
+                        default: arg_attrs.default,
+                        description: arg_attrs
+                            .description
+                            .or_else(|| (!doc_lines.is_empty()).then(|| doc_lines.join("\n"))),
+                        flatten: false,
+                    })));
                 }
                 match caught {
                     Some(Caught::Param(mut param)) => {
                         param.serde_meta = serde_metas;
                         param.schemars_meta = schemars_metas;
-                        match &mut tool_macro_attrs.params {
-                            ToolParams::Params { attrs } => {
-                                attrs.push(param);
-                            }
-                            _ => {
-                                tool_macro_attrs.params = ToolParams::Params { attrs: vec![param] };
-                            }
-                        }
+                        params.push(*param);
                         unextractable_args_indexes.insert(index);
                     }
                     Some(Caught::Aggregated(rust_type)) => {
-                        if let ToolParams::Params { .. } = tool_macro_attrs.params {
-                            return Err(syn::Error::new(
-                                rust_type.span(),
-                                "cannot mix aggregated and individual parameters",
-                            ));
-                        }
-                        tool_macro_attrs.params = ToolParams::Aggregated { rust_type };
+                        aggregated.push(rust_type);
                         unextractable_args_indexes.insert(index);
                     }
                     None => {}
@@ -565,8 +2004,53 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
             }
         }
     }
+    // a single aggregated argument takes the whole arguments object,
+    // otherwise aggregated arguments are flattened into the generated request type
+    let tool_params = if params.is_empty() && aggregated.len() <= 1 {
+        match aggregated.pop() {
+            Some(rust_type) => ToolParams::Aggregated { rust_type },
+            None => ToolParams::NoParam,
+        }
+    } else {
+        for rust_type in aggregated {
+            let syn::Pat::Ident(pat_ident) = rust_type.pat.as_ref() else {
+                return Err(syn::Error::new(
+                    rust_type.span(),
+                    "flattened aggregated param must have an ident as name",
+                ));
+            };
+            params.push(ToolFnParamAttrs {
+                serde_meta: Vec::new(),
+                schemars_meta: Vec::new(),
+                ident: pat_ident.ident.clone(),
+                rust_type: rust_type.ty.clone(),
+                default: None,
+                description: None,
+                flatten: true,
+            });
+        }
+        ToolParams::Params { attrs: params }
+    };
+    Ok((tool_params, unextractable_args_indexes))
+}
+
+pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Result<TokenStream> {
+    let mut tool_macro_attrs = ToolAttrs::default();
+    let args: ToolFnItemAttrs = syn::parse2(attr)?;
+    tool_macro_attrs.fn_item = args;
+    let (tool_params, unextractable_args_indexes) =
+        parse_tool_params(&mut input_fn.sig.inputs, tool_macro_attrs.fn_item.aggr)?;
+    tool_macro_attrs.params = tool_params;
 
     // input_fn.sig.inputs = fommated_fn_args;
+    // the schema title of generated request type should be the tool name
+    let schema_title = match &tool_macro_attrs.fn_item.name {
+        Some(Expr::Lit(syn::ExprLit {
+            lit: Lit::Str(name),
+            ..
+        })) => name.value(),
+        _ => input_fn.sig.ident.unraw().to_string(),
+    };
     let name = if let Some(expr) = tool_macro_attrs.fn_item.name {
         expr
     } else {
@@ -580,6 +2064,23 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
         proc_macro2::Span::call_site(),
     );
 
+    // a name known at compile time lets the dispatch skip building the whole `Tool`
+    let tool_name_fn = is_const_name(&name).then(|| {
+        let tool_name_fn_ident = Ident::new(
+            &format!("{}_tool_name", input_fn.sig.ident),
+            proc_macro2::Span::call_site(),
+        );
+        let input_fn_attrs = &input_fn.attrs;
+        let input_fn_vis = &input_fn.vis;
+        quote! {
+            #(#input_fn_attrs)*
+            #[allow(dead_code)]
+            #input_fn_vis const fn #tool_name_fn_ident() -> &'static str {
+                #name
+            }
+        }
+    });
+
     // generate get tool attr function
     let tool_attr_fn = {
         let description = if let Some(expr) = tool_macro_attrs.fn_item.description {
@@ -593,39 +2094,76 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
                 .filter_map(extract_doc_line)
                 .collect::<Vec<_>>()
                 .join("\n");
+            // a literal, the listed tools borrow it instead of allocating it
+            let doc_content = doc_content.trim();
             parse_quote! {
-                    #doc_content.trim().to_string()
+                #doc_content
+            }
+        };
+        // the global settings, with the flags of `schema(...)` turned on
+        let schema_settings = tool_macro_attrs.fn_item.schema.as_ref().map(|flags| {
+            let setters = flags
+                .iter()
+                .map(|flag| Ident::new(&format!("with_{flag}"), flag.span()));
+            quote! {
+                rmcp::handler::server::tool::SchemaSettings::global()#(.#setters(true))*
             }
+        });
+        let cached_schema = |ty: &dyn ToTokens| match &schema_settings {
+            Some(settings) => quote! {
+                rmcp::handler::server::tool::cached_schema_for_type_with::<#ty>(#settings)
+            },
+            None => quote! {
+                rmcp::handler::server::tool::cached_schema_for_type::<#ty>()
+            },
         };
         let schema = match &tool_macro_attrs.params {
             ToolParams::Aggregated { rust_type } => {
-                let ty = &rust_type.ty;
-                let schema = quote! {
-                    rmcp::handler::server::tool::cached_schema_for_type::<#ty>()
-                };
-                schema
+                // the schema is cached by `TypeId`, which requires a `'static` type
+                let ty =
+                    with_static_lifetimes(&rust_type.ty).unwrap_or_else(|| (*rust_type.ty).clone());
+                cached_schema(&ty)
             }
             ToolParams::Params { attrs, .. } => {
-                let (param_type, temp_param_type_name) =
-                    create_request_type(attrs, input_fn.sig.ident.to_string());
-                let schema = quote! {
+                let (param_type, temp_param_type_name) = create_request_type(
+                    attrs,
+                    &input_fn.sig.ident,
+                    &schema_title,
+                    tool_macro_attrs.fn_item.rename_all.as_ref(),
+                );
+                let schema = cached_schema(&temp_param_type_name);
+                quote! {
                     {
                         #param_type
-                        rmcp::handler::server::tool::cached_schema_for_type::<#temp_param_type_name>()
+                        #schema
                     }
-                };
-                schema
-            }
-            ToolParams::NoParam => {
-                quote! {
-                    rmcp::handler::server::tool::cached_schema_for_type::<rmcp::model::EmptyObject>()
                 }
             }
+            ToolParams::NoParam => cached_schema(&quote! { rmcp::model::EmptyObject }),
+        };
+        let output_schema_code = match tool_macro_attrs
+            .fn_item
+            .output
+            .as_ref()
+            .or_else(|| infer_output_type(&input_fn.sig.output))
+        {
+            Some(ty) => {
+                let schema = cached_schema(ty);
+                quote! { Some(#schema) }
+            }
+            None => quote! { None },
         };
         let input_fn_attrs = &input_fn.attrs;
         let input_fn_vis = &input_fn.vis;
 
-        let annotations_code = if let Some(annotations) = &tool_macro_attrs.fn_item.annotations {
+        let mut annotations = tool_macro_attrs.fn_item.annotations.clone();
+        if let Some(deprecated) = &tool_macro_attrs.fn_item.deprecated {
+            annotations
+                .get_or_insert_with(Default::default)
+                .0
+                .insert("deprecated".to_string(), json!(deprecated.value()));
+        }
+        let annotations_code = if let Some(annotations) = &annotations {
             let annotations =
                 serde_json::to_string(&annotations.0).expect("failed to serialize annotations");
             quote! {
@@ -634,15 +2172,28 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
         } else {
             quote! { None }
         };
+        let title_code = match &tool_macro_attrs.fn_item.title {
+            Some(title) => quote! { Some((#title).into()) },
+            None => quote! { None },
+        };
+        let icons = &tool_macro_attrs.fn_item.icons;
+        let icons_code = if icons.is_empty() {
+            quote! { None }
+        } else {
+            quote! { Some(vec![#(rmcp::model::Icon::new(#icons)),*]) }
+        };
 
         quote! {
             #(#input_fn_attrs)*
             #input_fn_vis fn #tool_attr_fn_ident() -> rmcp::model::Tool {
                 rmcp::model::Tool {
                     name: #name.into(),
+                    title: #title_code,
                     description: Some(#description.into()),
                     input_schema: #schema.into(),
+                    output_schema: #output_schema_code,
                     annotations: #annotations_code,
+                    icons: #icons_code,
                 }
             }
         }
@@ -663,8 +2214,8 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
         //      let (tn, context) = <Tn>::from_tool_call_context_part(context)?;
         //      // for params
         //      ... expand helper types here
-        //      let (__rmcp_tool_req, context) = rmcp::model::JsonObject::from_tool_call_context_part(context)?;
-        //      let __#TOOL_ToolCallParam { param_0, param_1, param_2, .. } = parse_json_object(__rmcp_tool_req)?;
+        //      let (__rmcp_tool_req, context) = rmcp::model::ToolArguments::from_tool_call_context_part(context)?;
+        //      let __#TOOL_ToolCallParam { param_0, param_1, param_2, .. } = parse_tool_arguments(__rmcp_tool_req)?;
         //      // for aggr
         //      let (Parameters(aggr), context) = <Parameters<AggrType>>::from_tool_call_context_part(context)?;
         //      Self::#tool_ident(to, param_0, t1, param_1, ..., param_2, tn, aggr).await.into_call_tool_result()
@@ -676,6 +2227,8 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
 
         // for receiver type, name it as __rmcp_tool_receiver
         let is_async = input_fn.sig.asyncness.is_some();
+        // a free function can be called with the context of any service
+        let is_free_fn = input_fn.sig.receiver().is_none();
         let receiver_ident = || Ident::new("__rmcp_tool_receiver", proc_macro2::Span::call_site());
         // generate the extraction part for trivial args
         let trivial_args = input_fn
@@ -696,6 +2249,14 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
                                 let (#pat, context) = <#ty>::from_tool_call_context_part(context)?;
                             }
                         }
+                        // `self` by value is cloned from the shared borrow
+                        FnArg::Receiver(r) if r.reference.is_none() => {
+                            let pat = receiver_ident();
+                            quote! {
+                                let (#pat, context) = <&Self>::from_tool_call_context_part(context)?;
+                                let #pat = ::std::clone::Clone::clone(#pat);
+                            }
+                        }
                         FnArg::Receiver(r) => {
                             let ty = r.ty.clone();
                             let pat = receiver_ident();
@@ -713,21 +2274,55 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
         let processed_arg_extraction_part = match &mut tool_macro_attrs.params {
             ToolParams::Aggregated { rust_type } => {
                 let PatType { pat, ty, .. } = rust_type;
-                quote! {
-                    let (Parameters(#pat), context) = <Parameters<#ty>>::from_tool_call_context_part(context)?;
+                if with_static_lifetimes(ty).is_some() {
+                    // a borrowing type borrows from the arguments, which live until the tool returns
+                    quote! {
+                        let (__rmcp_tool_req, context) = rmcp::model::ToolArguments::from_tool_call_context_part(context)?;
+                        let #pat: #ty = parse_borrowed_tool_arguments(&__rmcp_tool_req)?;
+                    }
+                } else {
+                    quote! {
+                        let (Parameters(#pat), context) = <Parameters<#ty>>::from_tool_call_context_part(context)?;
+                    }
                 }
             }
             ToolParams::Params { attrs } => {
-                let (param_type, temp_param_type_name) =
-                    create_request_type(attrs, input_fn.sig.ident.to_string());
+                let (param_type, temp_param_type_name) = create_request_type(
+                    attrs,
+                    &input_fn.sig.ident,
+                    &schema_title,
+                    tool_macro_attrs.fn_item.rename_all.as_ref(),
+                );
 
                 let params_ident = attrs.iter().map(|attr| &attr.ident).collect::<Vec<_>>();
+                // individual params must not shadow the fields of a flattened param
+                let individual_names = attrs
+                    .iter()
+                    .filter(|attr| !attr.flatten)
+                    .map(|attr| {
+                        let name = attr.ident.unraw().to_string();
+                        match &tool_macro_attrs.fn_item.rename_all {
+                            Some(rule) => apply_rename_rule(&rule.value(), &name),
+                            None => name,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let flatten_checks = attrs
+                    .iter()
+                    .filter(|attr| attr.flatten && !individual_names.is_empty())
+                    .map(|attr| {
+                        let rust_type = &attr.rust_type;
+                        quote! {
+                            check_flattened_fields::<#rust_type>(&[#(#individual_names),*])?;
+                        }
+                    });
                 quote! {
                     #param_type
-                    let (__rmcp_tool_req, context) = rmcp::model::JsonObject::from_tool_call_context_part(context)?;
+                    #(#flatten_checks)*
+                    let (__rmcp_tool_req, context) = rmcp::model::ToolArguments::from_tool_call_context_part(context)?;
                     let #temp_param_type_name {
                         #(#params_ident,)*
-                    } = parse_json_object(__rmcp_tool_req)?;
+                    } = parse_tool_arguments(__rmcp_tool_req)?;
                 }
             }
             ToolParams::NoParam => {
@@ -752,14 +2347,31 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
             })
             .collect::<Vec<_>>();
         let raw_fn_ident = &input_fn.sig.ident;
-        let call = if is_async {
-            quote! {
-                Self::#raw_fn_ident(#(#params),*).await.into_call_tool_result()
-            }
+        let callee = if is_free_fn {
+            quote! { #raw_fn_ident }
         } else {
-            quote! {
-                Self::#raw_fn_ident(#(#params),*).into_call_tool_result()
-            }
+            quote! { Self::#raw_fn_ident }
+        };
+        let await_token = is_async.then(|| quote! { .await });
+        let call = match &tool_macro_attrs.fn_item.on_error {
+            Some(on_error) => quote! {
+                map_tool_error(#callee(#(#params),*)#await_token, #on_error)
+            },
+            None => quote! {
+                #callee(#(#params),*)#await_token.into_call_tool_result()
+            },
+        };
+        // structured content is checked against the advertised output schema
+        let call = match tool_macro_attrs
+            .fn_item
+            .output
+            .as_ref()
+            .or_else(|| infer_output_type(&input_fn.sig.output))
+        {
+            Some(ty) => quote! {
+                validate_structured_content::<#ty>(#call)
+            },
+            None => call,
         };
         // assemble the whole function
         let tool_call_fn_ident = Ident::new(
@@ -776,34 +2388,118 @@ pub(crate) fn tool_fn_item(attr: TokenStream, mut input_fn: ItemFn) -> syn::Resu
             .iter()
             .filter(|attr| !attr.path().is_ident(TOOL_IDENT))
             .collect::<Vec<_>>();
+        // warn before dispatching to a deprecated tool
+        let deprecation_warning = tool_macro_attrs.fn_item.deprecated.as_ref().map(|message| {
+            quote! {
+                context.warn_deprecated(#message).await;
+            }
+        });
+        let (service_generic, service) = if is_free_fn {
+            (quote! { <__RmcpService> }, quote! { __RmcpService })
+        } else {
+            (quote! {}, quote! { Self })
+        };
+        // without `timeout_ms` the default timeout of the service applies, if any
+        let timeout = match &tool_macro_attrs.fn_item.timeout_ms {
+            Some(timeout_ms) => quote! { Some(std::time::Duration::from_millis(#timeout_ms)) },
+            None => quote! { None },
+        };
+        let concurrency_limit = match &tool_macro_attrs.fn_item.max_concurrent {
+            Some(max_concurrent) => quote! {
+                Some({
+                    static LIMIT: ToolConcurrencyLimit = ToolConcurrencyLimit::new(#max_concurrent);
+                    &LIMIT
+                })
+            },
+            None => quote! { None },
+        };
         quote! {
             #(#raw_fn_attr)*
-            #raw_fn_vis async fn #tool_call_fn_ident(context: rmcp::handler::server::tool::ToolCallContext<'_, Self>)
+            #raw_fn_vis async fn #tool_call_fn_ident #service_generic(context: rmcp::handler::server::tool::ToolCallContext<'_, #service>)
                 -> std::result::Result<rmcp::model::CallToolResult, rmcp::Error> {
                 use rmcp::handler::server::tool::*;
-                #trivial_arg_extraction_part
-                #processed_arg_extraction_part
-                #call
+                #deprecation_warning
+                call_with_concurrency_limit(context, #concurrency_limit, |context| {
+                    call_with_timeout(context, #timeout, |context| async move {
+                        #trivial_arg_extraction_part
+                        #processed_arg_extraction_part
+                        #call
+                    })
+                })
+                .await
             }
         }
     };
     Ok(quote! {
         #tool_attr_fn
+        #tool_name_fn
         #tool_call_fn
         #input_fn
     })
 }
 
-fn create_request_type(attrs: &[ToolFnParamAttrs], tool_name: String) -> (TokenStream, Ident) {
-    let pascal_case_tool_name = tool_name.to_ascii_uppercase();
+// `get_weather_forecast` => `GetWeatherForecast`
+pub(crate) fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+// rename a snake_case field name the way serde's `rename_all` does
+fn apply_rename_rule(rule: &str, name: &str) -> String {
+    match rule {
+        "lowercase" => name.to_ascii_lowercase(),
+        "UPPERCASE" => name.to_ascii_uppercase(),
+        "PascalCase" => to_pascal_case(name),
+        "camelCase" => {
+            let pascal = to_pascal_case(name);
+            let mut chars = pascal.chars();
+            match chars.next() {
+                Some(first) => first.to_lowercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+        "SCREAMING_SNAKE_CASE" => name.to_ascii_uppercase(),
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.replace('_', "-").to_ascii_uppercase(),
+        _ => name.to_string(),
+    }
+}
+
+fn create_request_type(
+    attrs: &[ToolFnParamAttrs],
+    fn_ident: &Ident,
+    schema_title: &str,
+    rename_all: Option<&LitStr>,
+) -> (TokenStream, Ident) {
+    let pascal_case_tool_name = to_pascal_case(&fn_ident.unraw().to_string());
     let temp_param_type_name = Ident::new(
         &format!("__{pascal_case_tool_name}ToolCallParam",),
         proc_macro2::Span::call_site(),
     );
+    let default_fns = attrs.iter().filter_map(ToolFnParamAttrs::default_fn);
+    // keep the advertised schema and the deserialization in agreement
+    let rename_all = rename_all.map(|rule| {
+        quote! {
+            #[serde(rename_all = #rule)]
+            #[schemars(rename_all = #rule)]
+        }
+    });
     (
         quote! {
             use rmcp::{serde, schemars};
+            #(#default_fns)*
+            #[allow(non_camel_case_types)]
             #[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+            #[schemars(rename = #schema_title)]
+            #rename_all
             pub struct #temp_param_type_name {
                 #(#attrs)*
             }
@@ -812,6 +2508,47 @@ fn create_request_type(attrs: &[ToolFnParamAttrs], tool_name: String) -> (TokenS
     )
 }
 
+// the arguments of a call to the tool, serialized the same way `create_request_type` deserializes them
+pub(crate) fn create_arguments_value(
+    attrs: &[ToolFnParamAttrs],
+    fn_ident: &Ident,
+    rename_all: Option<&LitStr>,
+) -> TokenStream {
+    let pascal_case_tool_name = to_pascal_case(&fn_ident.unraw().to_string());
+    let temp_param_type_name = Ident::new(
+        &format!("__{pascal_case_tool_name}ToolCallArguments",),
+        proc_macro2::Span::call_site(),
+    );
+    let rename_all = rename_all.map(|rule| quote! { #[serde(rename_all = #rule)] });
+    // defaults and descriptions only matter to the deserialization and the schema
+    let fields = attrs.iter().map(|attr| {
+        let ident = &attr.ident;
+        let rust_type = &attr.rust_type;
+        let serde_meta = &attr.serde_meta;
+        let flatten = attr.flatten.then(|| quote! { #[serde(flatten)] });
+        quote! {
+            #flatten
+            #(#[#serde_meta])*
+            pub #ident: #rust_type,
+        }
+    });
+    let params_ident = attrs.iter().map(|attr| &attr.ident);
+    quote! {
+        {
+            use rmcp::serde;
+            #[allow(non_camel_case_types)]
+            #[derive(serde::Serialize)]
+            #rename_all
+            struct #temp_param_type_name {
+                #(#fields)*
+            }
+            #temp_param_type_name {
+                #(#params_ident,)*
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -833,6 +2570,403 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_request_type_naming() -> syn::Result<()> {
+        let attr = quote! {};
+        let input = quote! {
+            fn get_weather_forecast(&self, city: String) -> String {
+                city
+            }
+        };
+        let result = tool(attr, input)?.to_string();
+        assert!(result.contains("__GetWeatherForecastToolCallParam"));
+        assert!(!result.contains("__GET_WEATHER_FORECASTToolCallParam"));
+        assert!(result.contains("rename = \"get_weather_forecast\""));
+
+        assert_eq!(to_pascal_case("get_weather_forecast"), "GetWeatherForecast");
+        assert_eq!(to_pascal_case("_leading__double_"), "LeadingDouble");
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_all() -> syn::Result<()> {
+        let attr = quote! {
+            rename_all = "camelCase"
+        };
+        let input = quote! {
+            fn search(&self, #[tool(param)] max_results: u32) -> String {
+                max_results.to_string()
+            }
+        };
+        let result = tool(attr, input)?.to_string();
+        assert!(result.contains("# [serde (rename_all = \"camelCase\")]"));
+        assert!(result.contains("# [schemars (rename_all = \"camelCase\")]"));
+
+        let attr = quote! {
+            rename_all = "Camel"
+        };
+        let input = quote! {
+            fn search(&self, #[tool(param)] max_results: u32) -> String {
+                max_results.to_string()
+            }
+        };
+        assert!(tool(attr, input).is_err());
+
+        assert_eq!(apply_rename_rule("camelCase", "max_results"), "maxResults");
+        assert_eq!(
+            apply_rename_rule("kebab-case", "max_results"),
+            "max-results"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_schema_settings() -> syn::Result<()> {
+        let attr = quote! {
+            schema(inline_refs, strip_formats), description = "search"
+        };
+        let input = quote! {
+            fn search(&self, #[tool(param)] max_results: u32) -> Json<Vec<u32>> {
+                Json(vec![max_results])
+            }
+        };
+        let result = tool(attr, input)?.to_string();
+        assert!(result.contains(
+            "cached_schema_for_type_with :: < __SearchToolCallParam > (rmcp :: handler :: server :: tool :: SchemaSettings :: global () . with_inline_refs (true) . with_strip_formats (true))"
+        ));
+        assert!(result.contains("cached_schema_for_type_with :: < Vec < u32 > >"));
+        assert!(!result.contains("cached_schema_for_type :: <"));
+
+        let attr = quote! {
+            schema(inline_definitions)
+        };
+        let input = quote! {
+            fn search(&self) -> String {
+                String::new()
+            }
+        };
+        assert!(tool(attr, input).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_deprecated() -> syn::Result<()> {
+        let attr = quote! {
+            deprecated = "use search_v2 instead"
+        };
+        let input = quote! {
+            fn search(&self, #[tool(param)] query: String) -> String {
+                query
+            }
+        };
+        let result = tool(attr, input)?.to_string();
+        assert!(result.contains("context . warn_deprecated (\"use search_v2 instead\") . await"));
+        assert!(result.contains(r#"{\"deprecated\":\"use search_v2 instead\"}"#));
+        Ok(())
+    }
+
+    #[test]
+    fn test_timeout() -> syn::Result<()> {
+        let input = quote! {
+            async fn slow(&self) -> String {
+                String::new()
+            }
+        };
+        let result = tool(quote! { timeout_ms = 30000 }, input.clone())?.to_string();
+        assert!(result.contains(
+            "call_with_timeout (context , Some (std :: time :: Duration :: from_millis (30000)) ,"
+        ));
+        let result = tool(quote! {}, input.clone())?.to_string();
+        assert!(result.contains("call_with_timeout (context , None ,"));
+        let error = tool(quote! { timeout_ms = 0 }, input).unwrap_err();
+        assert_eq!(error.to_string(), "timeout_ms must be greater than 0");
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_concurrent() -> syn::Result<()> {
+        let input = quote! {
+            async fn query(&self) -> String {
+                String::new()
+            }
+        };
+        let result = tool(quote! { max_concurrent = 2 }, input.clone())?.to_string();
+        assert!(
+            result.contains(
+                "static LIMIT : ToolConcurrencyLimit = ToolConcurrencyLimit :: new (2) ;"
+            )
+        );
+        let result = tool(quote! {}, input.clone())?.to_string();
+        assert!(result.contains("call_with_concurrency_limit (context , None ,"));
+        let error = tool(quote! { max_concurrent = 0 }, input).unwrap_err();
+        assert_eq!(error.to_string(), "max_concurrent must be greater than 0");
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_structured_content() -> syn::Result<()> {
+        let input = quote! {
+            async fn report(&self) -> Result<Json<Report>, String> {
+                Ok(Json(Report::default()))
+            }
+        };
+        let result = tool(quote! {}, input)?.to_string();
+        assert!(result.contains("validate_structured_content :: < Report > ("));
+        let input = quote! {
+            async fn plain(&self) -> String {
+                String::new()
+            }
+        };
+        let result = tool(quote! {}, input)?.to_string();
+        assert!(!result.contains("validate_structured_content"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cfg_gated_tool() -> syn::Result<()> {
+        let attr = quote! {
+            tool_box
+        };
+        let input = quote! {
+            impl Server {
+                #[cfg(feature = "experimental")]
+                #[tool]
+                fn experimental(&self) -> String {
+                    String::new()
+                }
+            }
+        };
+        let result = tool(attr, input)?.to_string();
+        assert!(result.contains("# [cfg (feature = \"experimental\")] experimental"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_const_tool_name() -> syn::Result<()> {
+        let input = quote! {
+            fn search(&self) -> String {
+                String::new()
+            }
+        };
+        let result = tool(quote! { name = "find" }, input.clone())?.to_string();
+        assert!(result.contains("const fn search_tool_name () -> & 'static str { \"find\" }"));
+        let result = tool(quote! {}, input.clone())?.to_string();
+        assert!(
+            result
+                .contains("const fn search_tool_name () -> & 'static str { stringify ! (search) }")
+        );
+        let result = tool(quote! { name = TOOL_NAMES[0] }, input)?.to_string();
+        assert!(!result.contains("search_tool_name"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_free_fn_tool() -> syn::Result<()> {
+        let input = quote! {
+            fn sum(#[tool(param)] a: i32, #[tool(param)] b: i32) -> String {
+                (a + b).to_string()
+            }
+        };
+        let result = tool(quote! {}, input)?.to_string();
+        assert!(result.contains("async fn sum_tool_call < __RmcpService > (context : rmcp :: handler :: server :: tool :: ToolCallContext < '_ , __RmcpService >)"));
+        assert!(result.contains("sum (a , b) . into_call_tool_result ()"));
+        assert!(!result.contains("Self"));
+
+        let input = quote! {
+            impl Server {
+                #[tool]
+                fn sum(#[tool(param)] a: i32, #[tool(param)] b: i32) -> String {
+                    (a + b).to_string()
+                }
+            }
+        };
+        let error = tool(quote! { tool_box }, input).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("tools in an impl block need a receiver")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_on_error() -> syn::Result<()> {
+        let mut input: ItemImpl = parse_quote! {
+            impl Server {
+                #[tool]
+                fn plain(&self) -> Result<String, MyError> {
+                    Ok(String::new())
+                }
+
+                #[tool(description = "own mapper", on_error = Self::strict,)]
+                fn strict(&self) -> Result<String, MyError> {
+                    Ok(String::new())
+                }
+
+                #[tool(description = "infallible",)]
+                fn infallible(&self) -> String {
+                    String::new()
+                }
+            }
+        };
+        apply_default_on_error(&mut input.items, &parse_quote!(map_error));
+        let result = input.to_token_stream().to_string();
+        assert!(result.contains("# [tool (on_error = map_error)] fn plain"));
+        assert!(result.contains(
+            "# [tool (description = \"own mapper\" , on_error = Self :: strict ,)] fn strict"
+        ));
+        assert!(result.contains("# [tool (description = \"infallible\" ,)] fn infallible"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_static_lifetimes() {
+        let ty: Type = parse_quote!(Query<'_, Vec<Cow<'a, str>>>);
+        let replaced = with_static_lifetimes(&ty).unwrap();
+        assert_eq!(
+            replaced.to_token_stream().to_string(),
+            "Query < 'static , Vec < Cow < 'static , str > > >"
+        );
+        assert!(with_static_lifetimes(&parse_quote!(Query<'static>)).is_none());
+        assert!(with_static_lifetimes(&parse_quote!(Query)).is_none());
+    }
+
+    #[test]
+    fn test_generic_where_clause() -> syn::Result<()> {
+        let attr = quote! {
+            tool_box
+        };
+        let input = quote! {
+            impl<'a, S: Storage, const N: usize> Server<'a, S, N>
+            where
+                S::Error: std::fmt::Display,
+            {
+                #[tool]
+                fn get(&self) -> String {
+                    String::new()
+                }
+            }
+        };
+        let result = tool(attr, input)?.to_string();
+        assert!(result.contains(
+            "impl < 'a , S : Storage , const N : usize > rmcp :: handler :: server :: ServerHandler for Server < 'a , S , N > where S :: Error : std :: fmt :: Display"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_server_info_attrs() -> syn::Result<()> {
+        let attr = quote! {
+            tool_box,
+            capabilities(logging, prompts, tools),
+            server_name = "my-server",
+            version = env!("CARGO_PKG_VERSION")
+        };
+        let input = quote! {
+            impl Server {
+                #[tool]
+                fn get(&self) -> String {
+                    String::new()
+                }
+            }
+        };
+        let result = tool(attr, input)?.to_string();
+        let capabilities = quote! {
+            capabilities: rmcp::model::ServerCapabilities::builder()
+                .enable_tools()
+                .enable_prompts()
+                .enable_logging()
+                .build(),
+            server_info: rmcp::model::Implementation {
+                name: ("my-server").into(),
+                version: (env!("CARGO_PKG_VERSION")).into(),
+            },
+            ..Default::default()
+        };
+        assert!(result.contains(&capabilities.to_string()), "{result}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_capability_options() -> syn::Result<()> {
+        let attr = quote! {
+            tool_box,
+            capabilities(resources(list_changed, subscribe), tools(list_changed), logging)
+        };
+        let input = quote! {
+            impl Server {
+                #[tool]
+                fn get(&self) -> String {
+                    String::new()
+                }
+            }
+        };
+        let result = tool(attr, input)?.to_string();
+        let capabilities = quote! {
+            capabilities: rmcp::model::ServerCapabilities::builder()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_resources_list_changed()
+                .enable_logging()
+                .build(),
+        };
+        assert!(result.contains(&capabilities.to_string()), "{result}");
+
+        let attr = quote! {
+            tool_box, capabilities(logging(list_changed))
+        };
+        let input = quote! {
+            impl Server {}
+        };
+        let error = tool(attr, input).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("capability `logging` has no option `list_changed`")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_capability() {
+        let attr = quote! {
+            tool_box, capabilities(tools, sampling)
+        };
+        let input = quote! {
+            impl Server {}
+        };
+        let error = tool(attr, input).unwrap_err();
+        assert!(error.to_string().contains("unknown capability `sampling`"));
+    }
+
+    #[test]
+    fn test_duplicate_tool_alias() {
+        let attr = quote! {
+            tool_box
+        };
+        let input = quote! {
+            impl Server {
+                #[tool(aliases = ["find"])]
+                fn search(&self) -> String {
+                    String::new()
+                }
+
+                #[tool]
+                fn find(&self) -> String {
+                    String::new()
+                }
+            }
+        };
+        let error = tool(attr, input).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("duplicate tool name `find`, already used by `search`")
+        );
+    }
+
     #[test]
     fn test_trait_tool_macro() -> syn::Result<()> {
         let attr = quote! {
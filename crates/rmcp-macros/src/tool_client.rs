@@ -0,0 +1,130 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    FnArg, ItemTrait, ReturnType, TraitItem, TraitItemFn, Type, parse_quote, spanned::Spanned,
+};
+
+use crate::tool::{
+    TOOL_IDENT, ToolFnItemAttrs, ToolParams, create_arguments_value, parse_tool_params,
+};
+
+// implement a trait mirroring the tools of a server for `Peer<RoleClient>`
+pub(crate) fn tool_client(attr: TokenStream, input: TokenStream) -> syn::Result<TokenStream> {
+    if !attr.is_empty() {
+        return Err(syn::Error::new(
+            attr.span(),
+            "tool_client takes no arguments",
+        ));
+    }
+    let mut item_trait: ItemTrait = syn::parse2(input)?;
+    if !item_trait.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item_trait.generics.span(),
+            "tool client traits can't be generic",
+        ));
+    }
+    let mut impl_fns = Vec::new();
+    for item in &mut item_trait.items {
+        let TraitItem::Fn(trait_fn) = item else {
+            return Err(syn::Error::new(
+                item.span(),
+                "tool client traits only contain tool methods",
+            ));
+        };
+        impl_fns.push(tool_client_fn(trait_fn)?);
+    }
+    let trait_ident = &item_trait.ident;
+    Ok(quote! {
+        #item_trait
+
+        impl #trait_ident for rmcp::service::Peer<rmcp::service::RoleClient> {
+            #(#impl_fns)*
+        }
+    })
+}
+
+// `async fn sum(&self, a: i32, b: i32) -> i32;` is declared as
+// `fn sum(&self, a: i32, b: i32) -> impl Future<Output = Result<i32, ToolCallError>> + Send;`
+// and implemented with `call_tool_typed`
+fn tool_client_fn(trait_fn: &mut TraitItemFn) -> syn::Result<TokenStream> {
+    let mut fn_attrs = ToolFnItemAttrs::default();
+    let mut attrs = Vec::new();
+    for attr in trait_fn.attrs.drain(..) {
+        if !attr.path().is_ident(TOOL_IDENT) {
+            attrs.push(attr);
+        } else if let syn::Meta::List(meta_list) = &attr.meta {
+            fn_attrs = meta_list.parse_args()?;
+        }
+    }
+    trait_fn.attrs = attrs;
+    let sig = &mut trait_fn.sig;
+    if let Some(default) = &trait_fn.default {
+        return Err(syn::Error::new(
+            default.span(),
+            "tool client methods must not have a body",
+        ));
+    }
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            sig.fn_token.span(),
+            "tool client methods must be async",
+        ));
+    }
+    match sig.receiver() {
+        Some(receiver) if receiver.reference.is_some() && receiver.mutability.is_none() => {}
+        _ => {
+            return Err(syn::Error::new(
+                sig.span(),
+                "tool client methods must take `&self`",
+            ));
+        }
+    }
+    let (tool_params, args_indexes) = parse_tool_params(&mut sig.inputs, fn_attrs.aggr)?;
+    if let Some(arg) = sig
+        .inputs
+        .iter()
+        .enumerate()
+        .find(|(index, arg)| matches!(arg, FnArg::Typed(_)) && !args_indexes.contains(index))
+        .map(|(_, arg)| arg)
+    {
+        return Err(syn::Error::new(
+            arg.span(),
+            "tool client methods only take the arguments of the tool",
+        ));
+    }
+
+    let fn_ident = sig.ident.clone();
+    let name = fn_attrs
+        .name
+        .unwrap_or_else(|| parse_quote! { stringify!(#fn_ident) });
+    let arguments = match &tool_params {
+        ToolParams::Aggregated { rust_type } => {
+            let pat = &rust_type.pat;
+            quote! { #pat }
+        }
+        ToolParams::Params { attrs } => {
+            create_arguments_value(attrs, &fn_ident, fn_attrs.rename_all.as_ref())
+        }
+        ToolParams::NoParam => quote! { rmcp::model::JsonObject::new() },
+    };
+    let output: Type = match &sig.output {
+        ReturnType::Default => parse_quote! { () },
+        ReturnType::Type(_, ty) => (**ty).clone(),
+    };
+
+    let mut impl_sig = sig.clone();
+    impl_sig.output = parse_quote! {
+        -> ::std::result::Result<#output, rmcp::service::ToolCallError>
+    };
+    sig.asyncness = None;
+    sig.output = parse_quote! {
+        -> impl ::std::future::Future<
+            Output = ::std::result::Result<#output, rmcp::service::ToolCallError>,
+        > + Send
+    };
+    Ok(quote! {
+        #impl_sig {
+            self.call_tool_typed(#name, &#arguments).await
+        }
+    })
+}
@@ -0,0 +1,95 @@
+//cargo bench --bench list_tools --features "server macros"
+//
+// Lists the tools of a server with 100 tools, built for each listing as `list_tools` used to do,
+// and cloned from the tool box built once.
+use std::{
+    hint::black_box,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use rmcp::{handler::server::tool::cached_tools, model::Tool, schemars, tool};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct Query {
+    pub text: String,
+    pub limit: Option<u32>,
+}
+
+macro_rules! server {
+    ($($tool: ident)*) => {
+        #[derive(Debug, Clone, Default)]
+        pub struct ManyToolsServer;
+
+        #[tool(tool_box)]
+        impl ManyToolsServer {
+            $(
+                /// Answer a query, one of the many tools of the server
+                #[tool]
+                async fn $tool(&self, #[tool(aggr)] query: Query) -> String {
+                    query.text
+                }
+            )*
+        }
+
+        impl ManyToolsServer {
+            fn build_tools() -> Vec<Tool> {
+                rmcp::paste! { vec![$(Self::[<$tool _tool_attr>]()),*] }
+            }
+        }
+    };
+}
+
+server! {
+    t00 t01 t02 t03 t04 t05 t06 t07 t08 t09 t10 t11 t12 t13 t14 t15 t16 t17 t18 t19
+    t20 t21 t22 t23 t24 t25 t26 t27 t28 t29 t30 t31 t32 t33 t34 t35 t36 t37 t38 t39
+    t40 t41 t42 t43 t44 t45 t46 t47 t48 t49 t50 t51 t52 t53 t54 t55 t56 t57 t58 t59
+    t60 t61 t62 t63 t64 t65 t66 t67 t68 t69 t70 t71 t72 t73 t74 t75 t76 t77 t78 t79
+    t80 t81 t82 t83 t84 t85 t86 t87 t88 t89 t90 t91 t92 t93 t94 t95 t96 t97 t98 t99
+}
+
+const ITERATIONS: u32 = 10_000;
+
+fn bench(name: &str, mut f: impl FnMut()) -> Duration {
+    // warm up
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let per_iteration = start.elapsed() / ITERATIONS;
+    println!("{name:<32} {:>10} ns/iter", per_iteration.as_nanos());
+    per_iteration
+}
+
+fn main() {
+    let built = bench("build 100 tools", || {
+        black_box(ManyToolsServer::build_tools());
+    });
+    let listed = bench("list 100 tools of the tool box", || {
+        black_box(ManyToolsServer::tool_box().list());
+    });
+    let cached = bench("list 100 cached tools", || {
+        black_box(
+            cached_tools::<ManyToolsServer>(ManyToolsServer::build_tools)
+                .as_ref()
+                .clone(),
+        );
+    });
+
+    // the listed tools share their schemas and borrow their names and descriptions
+    let tools = ManyToolsServer::tool_box().list();
+    assert_eq!(tools.len(), 100);
+    for tool in &tools {
+        assert!(Arc::ptr_eq(&tool.input_schema, &tools[0].input_schema));
+        assert!(matches!(tool.name, std::borrow::Cow::Borrowed(_)));
+        assert!(matches!(
+            tool.description,
+            Some(std::borrow::Cow::Borrowed(_))
+        ));
+    }
+    assert!(listed < built && cached < built);
+}
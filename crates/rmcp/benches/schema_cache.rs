@@ -0,0 +1,99 @@
+//cargo bench --bench schema_cache --features "server macros"
+//
+// Lists the tools of a server many times, the schemas of the listed tools are shared with the
+// cache instead of being generated or cloned for each listing.
+use std::{
+    hint::black_box,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use rmcp::{
+    handler::server::tool::{cached_schema_for_type, precompute, schema_for_type},
+    schemars, tool,
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    Metric,
+    Imperial,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct Location {
+    pub city: String,
+    pub country: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ForecastRequest {
+    pub locations: Vec<Location>,
+    pub days: u32,
+    pub unit: Unit,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WeatherServer;
+
+#[tool(tool_box)]
+impl WeatherServer {
+    #[tool(description = "Forecast the weather")]
+    async fn forecast(&self, #[tool(aggr)] request: ForecastRequest) -> String {
+        format!("{} days", request.days)
+    }
+
+    #[tool(description = "The current weather")]
+    async fn current(&self, #[tool(aggr)] location: Location) -> String {
+        location.city
+    }
+}
+
+const ITERATIONS: u32 = 10_000;
+
+fn bench(name: &str, mut f: impl FnMut()) -> Duration {
+    // warm up
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let per_iteration = start.elapsed() / ITERATIONS;
+    println!("{name:<32} {:>10} ns/iter", per_iteration.as_nanos());
+    per_iteration
+}
+
+fn main() {
+    precompute::<ForecastRequest>();
+    precompute::<Location>();
+
+    let generated = bench("generate schema", || {
+        black_box(schema_for_type::<ForecastRequest>());
+    });
+    let cached = bench("cached schema", || {
+        black_box(cached_schema_for_type::<ForecastRequest>());
+    });
+    let deep_clone = bench("deep clone of a schema", || {
+        black_box(cached_schema_for_type::<ForecastRequest>().as_ref().clone());
+    });
+    bench("list tools", || {
+        black_box(WeatherServer::tool_box().list());
+    });
+
+    // every listing shares the schemas of the cache
+    let schema = cached_schema_for_type::<ForecastRequest>();
+    for _ in 0..3 {
+        let tools = WeatherServer::tool_box().list();
+        let forecast = tools
+            .iter()
+            .find(|tool| tool.name == "forecast")
+            .expect("the forecast tool");
+        assert!(Arc::ptr_eq(&forecast.input_schema, &schema));
+    }
+    assert!(cached < generated && cached < deep_clone);
+}
@@ -0,0 +1,128 @@
+//cargo bench --bench tool_arguments --features "server"
+//
+// Prints the allocations of a tool call with a 10KB argument payload, from the message to the
+// parameters of the tool. It is a measurement to compare runs with, not a check of the code.
+//
+// The message is read from its JSON text by the method of its request, its arguments are kept as
+// JSON text and the parameters are deserialized from it.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rmcp::{
+    handler::server::tool::{parse_borrowed_tool_arguments, parse_tool_arguments},
+    model::{ClientJsonRpcMessage, ClientRequest, JsonRpcMessage, ToolArguments},
+};
+use serde::Deserialize;
+use serde_json::json;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[derive(Debug, Deserialize)]
+pub struct Section {
+    pub heading: String,
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Document {
+    pub title: String,
+    pub sections: Vec<Section>,
+}
+
+/// A document borrowing its text from the arguments
+#[derive(Debug, Deserialize)]
+pub struct BorrowedDocument<'a> {
+    pub title: &'a str,
+    #[serde(borrow)]
+    pub sections: Vec<BorrowedSection<'a>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BorrowedSection<'a> {
+    pub heading: &'a str,
+    pub body: &'a str,
+}
+
+/// Print the allocations and allocated bytes of `f`
+fn count<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let result = black_box(f());
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes;
+    println!("{name:<40} {allocations:>6} allocations {bytes:>8} bytes");
+    result
+}
+
+fn main() {
+    let sections = (0..10)
+        .map(|index| json!({ "heading": format!("section {index}"), "body": "x".repeat(1000) }))
+        .collect::<Vec<_>>();
+    let message = serde_json::to_vec(&json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {
+            "name": "publish",
+            "arguments": { "title": "a 10KB document", "sections": sections }
+        }
+    }))
+    .expect("a message");
+    assert!(message.len() > 10_000);
+
+    let message = count("decode the message", || {
+        serde_json::from_slice::<ClientJsonRpcMessage>(&message).expect("a message")
+    });
+    let JsonRpcMessage::Request(request) = message else {
+        panic!("a request");
+    };
+    let ClientRequest::CallToolRequest(request) = request.request else {
+        panic!("a tool call");
+    };
+    let arguments = request.params.arguments.expect("the arguments");
+    assert!(arguments.as_raw().is_some());
+    let map = arguments.clone().into_object();
+
+    // aggregated parameters are deserialized from the text of the arguments
+    let text = arguments.clone();
+    count("aggregated parameters, from the text", || {
+        parse_tool_arguments::<Document>(text).expect("a document")
+    });
+    // arguments built as a map move their strings into the parameters
+    let object = ToolArguments::from(map);
+    count("aggregated parameters, from a map", || {
+        parse_tool_arguments::<Document>(object).expect("a document")
+    });
+
+    // parameters borrowing from the arguments don't copy the text
+    count("borrowed parameters", || {
+        parse_borrowed_tool_arguments::<BorrowedDocument>(&arguments).expect("a document")
+    });
+
+    // single parameters parse the arguments into a map once, then read their value in place
+    let object = count("arguments parsed into a map", || arguments.as_object());
+    count("single parameter", || {
+        Vec::<Section>::deserialize(&object["sections"]).expect("the sections")
+    });
+}
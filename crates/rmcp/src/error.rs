@@ -15,3 +15,50 @@ impl Display for ErrorData {
 }
 
 impl std::error::Error for ErrorData {}
+
+impl From<std::io::Error> for ErrorData {
+    fn from(error: std::io::Error) -> Self {
+        ErrorData::internal_error(error.to_string(), None)
+    }
+}
+
+impl From<serde_json::Error> for ErrorData {
+    fn from(error: serde_json::Error) -> Self {
+        ErrorData::internal_error(error.to_string(), None)
+    }
+}
+
+#[cfg(feature = "anyhow")]
+#[cfg_attr(docsrs, doc(cfg(feature = "anyhow")))]
+impl From<anyhow::Error> for ErrorData {
+    fn from(error: anyhow::Error) -> Self {
+        // with the causes of the error
+        ErrorData::internal_error(format!("{error:#}"), None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_error_conversion() {
+        fn read(raw: &str) -> Result<serde_json::Value, Error> {
+            let value = serde_json::from_str(raw)?;
+            Ok(value)
+        }
+        let error = read("{").unwrap_err();
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({ "code": -32603, "message": "EOF while parsing an object at line 1 column 1" })
+        );
+
+        let error = Error::from(std::io::Error::other("disk full"));
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({ "code": -32603, "message": "disk full" })
+        );
+    }
+}
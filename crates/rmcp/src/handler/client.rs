@@ -4,6 +4,14 @@ use crate::{
     service::{Peer, RequestContext, RoleClient, Service, ServiceRole},
 };
 
+mod builder;
+pub mod elicitation;
+pub mod roots;
+pub mod sampling;
+pub use builder::{CallbackClientHandler, ClientHandlerBuilder};
+pub use elicitation::{ElicitationHandler, ElicitationResponder};
+pub use sampling::SamplingHandler;
+
 impl<H: ClientHandler> Service<RoleClient> for H {
     async fn handle_request(
         &self,
@@ -20,6 +28,14 @@ impl<H: ClientHandler> Service<RoleClient> for H {
                 .list_roots(context)
                 .await
                 .map(ClientResult::ListRootsResult),
+            ServerRequest::CreateElicitationRequest(request) => self
+                .create_elicitation(request.params, context)
+                .await
+                .map(ClientResult::CreateElicitationResult),
+            ServerRequest::CustomRequest(request) => self
+                .on_custom_request(request, context)
+                .await
+                .map(ClientResult::CustomResult),
         }
     }
 
@@ -49,6 +65,9 @@ impl<H: ClientHandler> Service<RoleClient> for H {
             ServerNotification::PromptListChangedNotification(_notification_no_param) => {
                 self.on_prompt_list_changed().await
             }
+            ServerNotification::CustomNotification(notification) => {
+                self.on_custom_notification(notification).await
+            }
         };
         Ok(())
     }
@@ -84,6 +103,19 @@ pub trait ClientHandler: Sized + Send + Sync + 'static {
         std::future::ready(Ok(ListRootsResult::default()))
     }
 
+    /// Ask the user for the input described by `params.requested_schema`
+    ///
+    /// Advertise the `elicitation` capability in [`ClientHandler::get_info`] when implementing it.
+    fn create_elicitation(
+        &self,
+        params: CreateElicitationRequestParam,
+        context: RequestContext<RoleClient>,
+    ) -> impl Future<Output = Result<CreateElicitationResult, McpError>> + Send + '_ {
+        std::future::ready(Err(McpError::method_not_found::<
+            CreateElicitationRequestMethod,
+        >()))
+    }
+
     fn on_cancelled(
         &self,
         params: CancelledNotificationParam,
@@ -117,6 +149,26 @@ pub trait ClientHandler: Sized + Send + Sync + 'static {
     fn on_prompt_list_changed(&self) -> impl Future<Output = ()> + Send + '_ {
         std::future::ready(())
     }
+    /// Answer a request of a method the SDK doesn't know, method not found by default
+    fn on_custom_request(
+        &self,
+        request: CustomRequest,
+        context: RequestContext<RoleClient>,
+    ) -> impl Future<Output = Result<CustomResult, McpError>> + Send + '_ {
+        std::future::ready(Err(McpError::new(
+            ErrorCode::METHOD_NOT_FOUND,
+            request.method,
+            None,
+        )))
+    }
+    /// Called with a notification of a method the SDK doesn't know, ignored by default
+    fn on_custom_notification(
+        &self,
+        notification: CustomNotification,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        tracing::debug!(method = %notification.method, "unknown notification ignored");
+        std::future::ready(())
+    }
 
     fn get_peer(&self) -> Option<Peer<RoleClient>>;
 
@@ -0,0 +1,272 @@
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+
+use super::{
+    ClientHandler, ElicitationHandler, ElicitationResponder, SamplingHandler,
+    elicitation::DynElicitationHandler, roots::RootsProvider, sampling::DynSamplingHandler,
+};
+use crate::{
+    error::Error as McpError,
+    model::*,
+    service::{
+        NotificationQueueConfig, Peer, RequestContext, RoleClient, Service, ServiceObserver,
+        ServiceRole, SessionData,
+    },
+};
+
+type PeerCallback = Arc<dyn Fn(Peer<RoleClient>) -> BoxFuture<'static, ()> + Send + Sync>;
+type ResourceCallback =
+    Arc<dyn Fn(Peer<RoleClient>, String) -> BoxFuture<'static, ()> + Send + Sync>;
+
+#[derive(Clone, Default)]
+struct Callbacks {
+    tools_changed: Option<PeerCallback>,
+    prompts_changed: Option<PeerCallback>,
+    resources_changed: Option<PeerCallback>,
+    resource_updated: Option<ResourceCallback>,
+    sampling: Option<Arc<dyn DynSamplingHandler>>,
+    elicitation: Option<Arc<dyn DynElicitationHandler>>,
+    roots: Option<RootsProvider>,
+}
+
+/// Build a client handler from closures reacting to the notifications of the server
+///
+/// Every closure receives the [`Peer`] of the connection, so it can list the changed items
+/// right away. The notifications without a closure, and every request but the sampling and
+/// elicitation ones once [`ClientHandlerBuilder::with_sampling`] and
+/// [`ClientHandlerBuilder::with_elicitation`] are set, are handled by the wrapped
+/// [`ClientHandler`], [`ClientInfo::default`] unless set with [`ClientHandlerBuilder::with_handler`].
+///
+/// ```rust,ignore
+/// let client = ClientHandlerBuilder::new()
+///     .on_tools_changed(move |peer| {
+///         let cache = cache.clone();
+///         async move {
+///             if let Ok(tools) = peer.list_all_tools().await {
+///                 cache.replace(tools);
+///             }
+///         }
+///     })
+///     .build()
+///     .serve(transport)
+///     .await?;
+/// ```
+pub struct ClientHandlerBuilder<H = ClientInfo> {
+    handler: H,
+    callbacks: Callbacks,
+}
+
+impl Default for ClientHandlerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientHandlerBuilder {
+    pub fn new() -> Self {
+        Self::with_handler(ClientInfo::default())
+    }
+}
+
+impl<H: ClientHandler> ClientHandlerBuilder<H> {
+    pub fn with_handler(handler: H) -> Self {
+        Self {
+            handler,
+            callbacks: Callbacks::default(),
+        }
+    }
+
+    /// Called on `notifications/tools/list_changed`
+    pub fn on_tools_changed<F, Fut>(mut self, callback: F) -> Self
+    where
+        F: Fn(Peer<RoleClient>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.callbacks.tools_changed = Some(Arc::new(move |peer| Box::pin(callback(peer))));
+        self
+    }
+
+    /// Called on `notifications/prompts/list_changed`
+    pub fn on_prompts_changed<F, Fut>(mut self, callback: F) -> Self
+    where
+        F: Fn(Peer<RoleClient>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.callbacks.prompts_changed = Some(Arc::new(move |peer| Box::pin(callback(peer))));
+        self
+    }
+
+    /// Called on `notifications/resources/list_changed`
+    pub fn on_resources_changed<F, Fut>(mut self, callback: F) -> Self
+    where
+        F: Fn(Peer<RoleClient>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.callbacks.resources_changed = Some(Arc::new(move |peer| Box::pin(callback(peer))));
+        self
+    }
+
+    /// Called on `notifications/resources/updated` with the uri of the updated resource
+    pub fn on_resource_updated<F, Fut>(mut self, callback: F) -> Self
+    where
+        F: Fn(Peer<RoleClient>, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.callbacks.resource_updated =
+            Some(Arc::new(move |peer, uri| Box::pin(callback(peer, uri))));
+        self
+    }
+
+    /// Answer `sampling/createMessage` with `sampling`, advertising the `sampling` capability
+    pub fn with_sampling(mut self, sampling: impl SamplingHandler) -> Self {
+        self.callbacks.sampling = Some(Arc::new(sampling));
+        self
+    }
+
+    /// Answer `elicitation/create` with `elicitation`, advertising the `elicitation` capability
+    pub fn with_elicitation(mut self, elicitation: impl ElicitationHandler) -> Self {
+        self.callbacks.elicitation = Some(Arc::new(elicitation));
+        self
+    }
+
+    /// Answer `roots/list` with `roots`, advertising the `roots` capability unless the wrapped
+    /// handler does
+    ///
+    /// Changes of dynamic roots are notified if the advertised capability has `listChanged`.
+    pub fn with_roots(mut self, roots: impl Into<RootsProvider>) -> Self {
+        self.callbacks.roots = Some(roots.into());
+        self
+    }
+
+    pub fn build(self) -> CallbackClientHandler<H> {
+        CallbackClientHandler {
+            handler: self.handler,
+            callbacks: self.callbacks,
+        }
+    }
+}
+
+/// A client handler built by [`ClientHandlerBuilder`]
+pub struct CallbackClientHandler<H = ClientInfo> {
+    handler: H,
+    callbacks: Callbacks,
+}
+
+impl<H> CallbackClientHandler<H> {
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H: ClientHandler> Service<RoleClient> for CallbackClientHandler<H> {
+    async fn handle_request(
+        &self,
+        request: <RoleClient as ServiceRole>::PeerReq,
+        context: RequestContext<RoleClient>,
+    ) -> Result<<RoleClient as ServiceRole>::Resp, McpError> {
+        match (request, &self.callbacks) {
+            (
+                ServerRequest::CreateMessageRequest(request),
+                Callbacks {
+                    sampling: Some(sampling),
+                    ..
+                },
+            ) => sampling
+                .create_message(request.params)
+                .await
+                .map(ClientResult::CreateMessageResult),
+            (
+                ServerRequest::CreateElicitationRequest(request),
+                Callbacks {
+                    elicitation: Some(elicitation),
+                    ..
+                },
+            ) => elicitation
+                .elicit(ElicitationResponder::new(request.params))
+                .await
+                .map(ClientResult::CreateElicitationResult),
+            (
+                ServerRequest::ListRootsRequest(_),
+                Callbacks {
+                    roots: Some(roots), ..
+                },
+            ) => Ok(ClientResult::ListRootsResult(ListRootsResult {
+                roots: roots.current(),
+                meta: None,
+            })),
+            (request, _) => Service::handle_request(&self.handler, request, context).await,
+        }
+    }
+
+    async fn handle_notification(
+        &self,
+        notification: <RoleClient as ServiceRole>::PeerNot,
+    ) -> Result<(), McpError> {
+        let peer = notification.extensions().get::<Peer<RoleClient>>().cloned();
+        let callback = match (&notification, peer) {
+            (ServerNotification::ToolListChangedNotification(_), Some(peer)) => {
+                self.callbacks.tools_changed.as_ref().map(|f| f(peer))
+            }
+            (ServerNotification::PromptListChangedNotification(_), Some(peer)) => {
+                self.callbacks.prompts_changed.as_ref().map(|f| f(peer))
+            }
+            (ServerNotification::ResourceListChangedNotification(_), Some(peer)) => {
+                self.callbacks.resources_changed.as_ref().map(|f| f(peer))
+            }
+            (ServerNotification::ResourceUpdatedNotification(notification), Some(peer)) => self
+                .callbacks
+                .resource_updated
+                .as_ref()
+                .map(|f| f(peer, notification.params.uri.clone())),
+            _ => None,
+        };
+        match callback {
+            Some(callback) => callback.await,
+            None => tracing::debug!(
+                method = notification.method(),
+                "no callback for notification"
+            ),
+        }
+        Service::handle_notification(&self.handler, notification).await
+    }
+
+    fn get_info(&self) -> <RoleClient as ServiceRole>::Info {
+        let mut info = ClientHandler::get_info(&self.handler);
+        if self.callbacks.sampling.is_some() && info.capabilities.sampling.is_none() {
+            info.capabilities.sampling = Some(JsonObject::default());
+        }
+        if self.callbacks.elicitation.is_some() && info.capabilities.elicitation.is_none() {
+            info.capabilities.elicitation = Some(JsonObject::default());
+        }
+        if let (Some(roots), None) = (&self.callbacks.roots, &info.capabilities.roots) {
+            info.capabilities.roots = Some(roots.capabilities());
+        }
+        info
+    }
+
+    fn observer(&self) -> Option<Arc<dyn ServiceObserver>> {
+        Service::observer(&self.handler)
+    }
+
+    fn notification_queue(&self) -> NotificationQueueConfig {
+        Service::notification_queue(&self.handler)
+    }
+
+    async fn handle_disconnect(&self, session: SessionData) {
+        Service::handle_disconnect(&self.handler, session).await
+    }
+
+    fn bind_peer(&self, peer: &Peer<RoleClient>) {
+        if let Some(roots) = &self.callbacks.roots {
+            if self.get_info().capabilities.supports_roots_list_changed() {
+                roots.spawn_notifier(peer.clone());
+            }
+        }
+        Service::bind_peer(&self.handler, peer)
+    }
+}
@@ -0,0 +1,346 @@
+use futures::future::BoxFuture;
+use serde_json::Value;
+
+use crate::{error::Error as McpError, model::*};
+
+/// Answers the `elicitation/create` requests of the server, usually by asking the user
+///
+/// Installed with [`ClientHandlerBuilder::with_elicitation`](super::ClientHandlerBuilder::with_elicitation),
+/// which also advertises the `elicitation` capability.
+pub trait ElicitationHandler: Send + Sync + 'static {
+    fn elicit(
+        &self,
+        request: ElicitationResponder,
+    ) -> impl Future<Output = Result<CreateElicitationResult, McpError>> + Send + '_;
+}
+
+pub(crate) trait DynElicitationHandler: Send + Sync + 'static {
+    fn elicit(
+        &self,
+        request: ElicitationResponder,
+    ) -> BoxFuture<'_, Result<CreateElicitationResult, McpError>>;
+}
+
+impl<E: ElicitationHandler> DynElicitationHandler for E {
+    fn elicit(
+        &self,
+        request: ElicitationResponder,
+    ) -> BoxFuture<'_, Result<CreateElicitationResult, McpError>> {
+        Box::pin(ElicitationHandler::elicit(self, request))
+    }
+}
+
+/// The content of an accepted elicitation not matching the requested schema
+#[derive(Debug, thiserror::Error)]
+pub enum ElicitationError {
+    #[error("failed to serialize the elicitation content: {0}")]
+    Serialize(#[source] serde_json::Error),
+    #[error("elicitation content doesn't match the requested schema: {}", errors.join(", "))]
+    Invalid { errors: Vec<String> },
+}
+
+impl From<ElicitationError> for McpError {
+    fn from(error: ElicitationError) -> Self {
+        McpError::internal_error(error.to_string(), None)
+    }
+}
+
+/// A field of the form requested by the server
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElicitationField {
+    pub name: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// The json type of the value, `string`, `number`, `integer` or `boolean`
+    pub kind: Option<String>,
+    pub required: bool,
+    /// The values allowed, if restricted
+    pub options: Option<Vec<Value>>,
+}
+
+/// An `elicitation/create` request, answered with one of its methods
+///
+/// The requested schema is a flat object of primitive properties. The content of
+/// [`ElicitationResponder::accept_value`] is checked against it before it is sent: the required
+/// properties, the types, the allowed values and the bounds of the properties.
+#[derive(Debug, Clone)]
+pub struct ElicitationResponder {
+    params: CreateElicitationRequestParam,
+}
+
+impl ElicitationResponder {
+    pub fn new(params: CreateElicitationRequestParam) -> Self {
+        Self { params }
+    }
+
+    /// The message to show to the user
+    pub fn message(&self) -> &str {
+        &self.params.message
+    }
+
+    pub fn requested_schema(&self) -> &JsonObject {
+        &self.params.requested_schema
+    }
+
+    pub fn params(&self) -> &CreateElicitationRequestParam {
+        &self.params
+    }
+
+    /// The fields of the requested form, in the iteration order of the schema's properties
+    pub fn fields(&self) -> Vec<ElicitationField> {
+        let required = self.required();
+        self.properties()
+            .map(|(name, property)| {
+                let property = self.resolve(property);
+                let text = |key: &str| property.get(key).and_then(Value::as_str).map(Into::into);
+                ElicitationField {
+                    name: name.clone(),
+                    title: text("title"),
+                    description: text("description"),
+                    kind: types(property)
+                        .into_iter()
+                        .find(|kind| *kind != "null")
+                        .map(Into::into),
+                    required: required.contains(&name.as_str()),
+                    options: property.get("enum").and_then(Value::as_array).cloned(),
+                }
+            })
+            .collect()
+    }
+
+    /// Accept with `value`, once checked against the requested schema
+    pub fn accept_value(
+        &self,
+        value: &impl serde::Serialize,
+    ) -> Result<CreateElicitationResult, ElicitationError> {
+        match serde_json::to_value(value).map_err(ElicitationError::Serialize)? {
+            Value::Object(content) => self.accept(content),
+            other => Err(ElicitationError::Invalid {
+                errors: vec![format!("expected an object, got {other}")],
+            }),
+        }
+    }
+
+    /// Accept with `content`, once checked against the requested schema
+    pub fn accept(&self, content: JsonObject) -> Result<CreateElicitationResult, ElicitationError> {
+        let errors = self.validate(&content);
+        if errors.is_empty() {
+            Ok(CreateElicitationResult::accept(content))
+        } else {
+            Err(ElicitationError::Invalid { errors })
+        }
+    }
+
+    /// The user explicitly refused to provide the input
+    pub fn decline(&self) -> CreateElicitationResult {
+        CreateElicitationResult::decline()
+    }
+
+    /// The user dismissed the request without choosing
+    pub fn cancel(&self) -> CreateElicitationResult {
+        CreateElicitationResult::cancel()
+    }
+
+    /// The errors of `content` against the requested schema, as `/property: message`
+    pub fn validate(&self, content: &JsonObject) -> Vec<String> {
+        let mut errors = self
+            .required()
+            .into_iter()
+            .filter(|name| !content.contains_key(*name))
+            .map(|name| format!("/{name}: missing required property"))
+            .collect::<Vec<_>>();
+        let additional = self.params.requested_schema.get("additionalProperties");
+        for (name, value) in content {
+            match self.properties().find(|(property, _)| *property == name) {
+                Some((_, property)) => errors.extend(
+                    self.validate_value(property, value)
+                        .into_iter()
+                        .map(|error| format!("/{name}: {error}")),
+                ),
+                None if additional == Some(&Value::Bool(false)) => {
+                    errors.push(format!("/{name}: unknown property"))
+                }
+                None => {}
+            }
+        }
+        errors
+    }
+
+    fn validate_value(&self, property: &Value, value: &Value) -> Vec<String> {
+        let property = self.resolve(property);
+        let mut errors = Vec::new();
+        // `allOf` wraps a `$ref` given a description
+        if let Some(all_of) = property.get("allOf").and_then(Value::as_array) {
+            for schema in all_of {
+                errors.extend(self.validate_value(schema, value));
+            }
+        }
+        let kinds = types(property);
+        if !kinds.is_empty() && !kinds.iter().any(|kind| is_of_type(value, kind)) {
+            errors.push(format!("expected {}, got {value}", kinds.join(" or ")));
+            return errors;
+        }
+        if let Some(options) = property.get("enum").and_then(Value::as_array) {
+            if !options.contains(value) {
+                errors.push(format!(
+                    "{value} is not one of {}",
+                    Value::from(options.clone())
+                ));
+            }
+        }
+        if let Some(text) = value.as_str() {
+            let length = text.chars().count() as u64;
+            if let Some(min) = property.get("minLength").and_then(Value::as_u64) {
+                if length < min {
+                    errors.push(format!("shorter than {min} characters"));
+                }
+            }
+            if let Some(max) = property.get("maxLength").and_then(Value::as_u64) {
+                if length > max {
+                    errors.push(format!("longer than {max} characters"));
+                }
+            }
+        }
+        if let Some(number) = value.as_f64() {
+            if let Some(min) = property.get("minimum").and_then(Value::as_f64) {
+                if number < min {
+                    errors.push(format!("less than {min}"));
+                }
+            }
+            if let Some(max) = property.get("maximum").and_then(Value::as_f64) {
+                if number > max {
+                    errors.push(format!("greater than {max}"));
+                }
+            }
+        }
+        errors
+    }
+
+    fn properties(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.params
+            .requested_schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+    }
+
+    fn required(&self) -> Vec<&str> {
+        self.params
+            .requested_schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect()
+    }
+
+    // follow a `#/definitions/..` or `#/$defs/..` reference of the requested schema
+    fn resolve<'a>(&'a self, property: &'a Value) -> &'a Value {
+        let Some(reference) = property.get("$ref").and_then(Value::as_str) else {
+            return property;
+        };
+        let schema = &self.params.requested_schema;
+        reference
+            .strip_prefix("#/")
+            .and_then(|path| {
+                let (section, rest) = path.split_once('/')?;
+                schema
+                    .get(section)
+                    .and_then(|section| section.pointer(&format!("/{rest}")))
+                    // rmcp schemas point at `#/components/schemas/` but keep them in `definitions`
+                    .or_else(|| schema.get("definitions")?.get(path.rsplit('/').next()?))
+            })
+            .unwrap_or(property)
+    }
+}
+
+fn types(property: &Value) -> Vec<&str> {
+    match property.get("type") {
+        Some(Value::String(kind)) => vec![kind.as_str()],
+        Some(Value::Array(kinds)) => kinds.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn is_of_type(value: &Value, kind: &str) -> bool {
+    match kind {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn responder(schema: Value) -> ElicitationResponder {
+        ElicitationResponder::new(CreateElicitationRequestParam {
+            message: "Who are you?".into(),
+            requested_schema: schema.as_object().cloned().unwrap(),
+        })
+    }
+
+    #[test]
+    fn test_elicitation_content_validation() {
+        let responder = responder(json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 2, "description": "your name" },
+                "age": { "type": ["integer", "null"], "minimum": 0 },
+                "color": { "$ref": "#/definitions/Color" },
+            },
+            "required": ["name", "color"],
+            "definitions": {
+                "Color": { "type": "string", "enum": ["red", "blue"] },
+            },
+        }));
+        let fields = responder.fields();
+        assert_eq!(fields.len(), 3);
+        let field = |name: &str| fields.iter().find(|field| field.name == name).unwrap();
+        assert_eq!(field("name").description.as_deref(), Some("your name"));
+        assert_eq!(field("age").kind.as_deref(), Some("integer"));
+        assert!(!field("age").required);
+        assert_eq!(
+            field("color").options,
+            Some(vec![json!("red"), json!("blue")])
+        );
+
+        let accepted =
+            responder.accept_value(&json!({ "name": "Ann", "age": null, "color": "red" }));
+        assert_eq!(accepted.unwrap().action, ElicitationAction::Accept);
+
+        let Err(ElicitationError::Invalid { errors }) =
+            responder.accept_value(&json!({ "name": "A", "age": -1.5, "color": "green" }))
+        else {
+            panic!("expected the content to be invalid");
+        };
+        assert_eq!(errors.len(), 3);
+        for expected in [
+            "/name: shorter",
+            "/age: expected integer or null",
+            "/color: \"green\" is not one of",
+        ] {
+            assert!(errors.iter().any(|error| error.starts_with(expected)));
+        }
+
+        let Err(ElicitationError::Invalid { errors }) = responder.accept_value(&json!({})) else {
+            panic!("expected the content to be invalid");
+        };
+        assert_eq!(
+            errors,
+            [
+                "/name: missing required property",
+                "/color: missing required property"
+            ]
+        );
+        assert!(responder.accept_value(&42).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use tokio::sync::watch;
+
+use crate::{
+    model::{Root, RootsCapabilities},
+    service::{Peer, RoleClient},
+};
+
+/// The roots a client answers `roots/list` with
+///
+/// Installed with [`ClientHandlerBuilder::with_roots`](super::ClientHandlerBuilder::with_roots),
+/// which advertises the `roots` capability, with `listChanged` for the dynamic roots. Every
+/// change of the dynamic roots is then notified with `notifications/roots/list_changed`.
+///
+/// ```rust
+/// # use rmcp::{handler::client::roots::RootsProvider, model::Root};
+/// let root = |uri: &str| Root {
+///     uri: uri.into(),
+///     name: None,
+/// };
+/// let (roots, provider) = RootsProvider::channel(vec![root("file:///project")]);
+/// roots.send_replace(vec![root("file:///project"), root("file:///library")]);
+/// assert_eq!(provider.current().len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub enum RootsProvider {
+    Static(Vec<Root>),
+    Dynamic(watch::Receiver<Vec<Root>>),
+}
+
+impl RootsProvider {
+    /// Dynamic roots, updated through the returned sender
+    pub fn channel(roots: Vec<Root>) -> (watch::Sender<Vec<Root>>, Self) {
+        let (sender, receiver) = watch::channel(roots);
+        (sender, Self::Dynamic(receiver))
+    }
+
+    /// A snapshot of the roots
+    pub fn current(&self) -> Vec<Root> {
+        match self {
+            RootsProvider::Static(roots) => roots.clone(),
+            RootsProvider::Dynamic(receiver) => receiver.borrow().clone(),
+        }
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        matches!(self, RootsProvider::Dynamic(_))
+    }
+
+    pub(crate) fn capabilities(&self) -> RootsCapabilities {
+        RootsCapabilities {
+            list_changed: Some(self.is_dynamic()),
+        }
+    }
+
+    /// Notify `peer` of every change of the dynamic roots, until the transport or the sender
+    /// of the roots is closed
+    pub(crate) fn spawn_notifier(&self, peer: Peer<RoleClient>) {
+        let RootsProvider::Dynamic(receiver) = self else {
+            return;
+        };
+        let mut receiver = receiver.clone();
+        // the server lists the roots it starts with
+        receiver.borrow_and_update();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    changed = receiver.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                    _ = peer.closed() => break,
+                }
+                if let Err(error) = peer.notify_roots_list_changed().await {
+                    tracing::debug!(%error, "failed to notify the roots list change");
+                    break;
+                }
+            }
+        });
+    }
+}
+
+impl From<Vec<Root>> for RootsProvider {
+    fn from(roots: Vec<Root>) -> Self {
+        RootsProvider::Static(roots)
+    }
+}
+
+impl From<watch::Receiver<Vec<Root>>> for RootsProvider {
+    fn from(receiver: watch::Receiver<Vec<Root>>) -> Self {
+        RootsProvider::Dynamic(receiver)
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use futures::future::BoxFuture;
+
+use crate::{error::Error as McpError, model::*};
+
+/// Answers the `sampling/createMessage` requests of the server with a model hosted by the client
+///
+/// Installed with [`ClientHandlerBuilder::with_sampling`](super::ClientHandlerBuilder::with_sampling),
+/// which also advertises the `sampling` capability.
+pub trait SamplingHandler: Send + Sync + 'static {
+    fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+    ) -> impl Future<Output = Result<CreateMessageResult, McpError>> + Send + '_;
+}
+
+pub(crate) trait DynSamplingHandler: Send + Sync + 'static {
+    fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+    ) -> BoxFuture<'_, Result<CreateMessageResult, McpError>>;
+}
+
+impl<S: SamplingHandler> DynSamplingHandler for S {
+    fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+    ) -> BoxFuture<'_, Result<CreateMessageResult, McpError>> {
+        Box::pin(SamplingHandler::create_message(self, params))
+    }
+}
+
+/// A model the client can sample with, rated from 0 to 1 on each of the priorities of
+/// [`ModelPreferences`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelCandidate {
+    pub name: String,
+    /// How expensive the model is, 1 being the most expensive
+    pub cost: f32,
+    /// How fast the model is, 1 being the fastest
+    pub speed: f32,
+    /// How capable the model is, 1 being the most capable
+    pub intelligence: f32,
+}
+
+impl ModelCandidate {
+    pub fn new(name: impl Into<String>, cost: f32, speed: f32, intelligence: f32) -> Self {
+        Self {
+            name: name.into(),
+            cost,
+            speed,
+            intelligence,
+        }
+    }
+
+    /// The weighted score of the model, cheaper models scoring higher on cost
+    pub fn score(&self, preferences: &ModelPreferences) -> f32 {
+        preferences.cost_priority.unwrap_or_default() * (1.0 - self.cost)
+            + preferences.speed_priority.unwrap_or_default() * self.speed
+            + preferences.intelligence_priority.unwrap_or_default() * self.intelligence
+    }
+}
+
+/// Pick the model of `models` best matching `preferences`
+///
+/// The hints are tried in order, the first one naming part of a model selects it. Otherwise the
+/// model with the best [`ModelCandidate::score`] is selected, the first one on a tie or without
+/// preferences. `None` only if `models` is empty.
+pub fn select_model<'a>(
+    preferences: Option<&ModelPreferences>,
+    models: &'a [ModelCandidate],
+) -> Option<&'a ModelCandidate> {
+    let Some(preferences) = preferences else {
+        return models.first();
+    };
+    let hinted = preferences
+        .hints
+        .iter()
+        .flatten()
+        .filter_map(|hint| hint.name.as_deref())
+        .find_map(|hint| models.iter().find(|model| model.name.contains(hint)));
+    if hinted.is_some() {
+        return hinted;
+    }
+    models.iter().fold(None, |best, model| match best {
+        Some(best) if best.score(preferences) >= model.score(preferences) => Some(best),
+        _ => Some(model),
+    })
+}
+
+#[derive(Debug, Default)]
+struct StaticState {
+    replies: VecDeque<String>,
+    requests: Vec<CreateMessageRequestParam>,
+}
+
+/// A [`SamplingHandler`] answering with canned completions, in the order they were added
+///
+/// The model of the result is selected from [`StaticSamplingHandler::with_models`] with
+/// [`select_model`] if set, and the requests are recorded. Fails once the completions run out.
+/// Clones share the completions and the recorded requests.
+#[derive(Debug, Clone)]
+pub struct StaticSamplingHandler {
+    model: String,
+    models: Vec<ModelCandidate>,
+    state: Arc<Mutex<StaticState>>,
+}
+
+impl StaticSamplingHandler {
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            models: Vec::new(),
+            state: Default::default(),
+        }
+    }
+
+    pub fn with_models(mut self, models: impl IntoIterator<Item = ModelCandidate>) -> Self {
+        self.models = models.into_iter().collect();
+        self
+    }
+
+    pub fn reply(self, text: impl Into<String>) -> Self {
+        self.push_reply(text);
+        self
+    }
+
+    pub fn push_reply(&self, text: impl Into<String>) {
+        self.lock().replies.push_back(text.into());
+    }
+
+    /// The requests answered so far
+    pub fn requests(&self) -> Vec<CreateMessageRequestParam> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, StaticState> {
+        self.state.lock().expect("sampling state lock poisoned")
+    }
+}
+
+impl SamplingHandler for StaticSamplingHandler {
+    async fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+    ) -> Result<CreateMessageResult, McpError> {
+        let model = select_model(params.model_preferences.as_ref(), &self.models)
+            .map_or_else(|| self.model.clone(), |model| model.name.clone());
+        let mut state = self.lock();
+        let text = state
+            .replies
+            .pop_front()
+            .ok_or_else(|| McpError::internal_error("no canned completion left", None))?;
+        state.requests.push(params);
+        Ok(CreateMessageResult {
+            model,
+            stop_reason: Some(CreateMessageResult::STOP_REASON_END_TURN.into()),
+            message: SamplingMessage::assistant_text(text),
+            meta: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn models() -> Vec<ModelCandidate> {
+        vec![
+            ModelCandidate::new("claude-3-haiku", 0.1, 0.9, 0.4),
+            ModelCandidate::new("claude-3-5-sonnet", 0.5, 0.5, 0.9),
+            ModelCandidate::new("claude-3-opus", 0.9, 0.2, 1.0),
+        ]
+    }
+
+    fn preferences(hints: &[&str], cost: f32, speed: f32, intelligence: f32) -> ModelPreferences {
+        ModelPreferences {
+            hints: Some(
+                hints
+                    .iter()
+                    .map(|hint| ModelHint {
+                        name: Some(hint.to_string()),
+                    })
+                    .collect(),
+            ),
+            cost_priority: Some(cost),
+            speed_priority: Some(speed),
+            intelligence_priority: Some(intelligence),
+        }
+    }
+
+    fn selected(preferences: Option<&ModelPreferences>) -> Option<&'static str> {
+        let models = models();
+        select_model(preferences, &models).map(|model| match model.name.as_str() {
+            "claude-3-haiku" => "haiku",
+            "claude-3-5-sonnet" => "sonnet",
+            _ => "opus",
+        })
+    }
+
+    #[test]
+    fn test_select_model() {
+        // hints win over the priorities, in order
+        let hinted = preferences(&["gpt-4", "sonnet", "haiku"], 1.0, 1.0, 0.0);
+        assert_eq!(selected(Some(&hinted)), Some("sonnet"));
+
+        assert_eq!(
+            selected(Some(&preferences(&[], 1.0, 0.0, 0.0))),
+            Some("haiku")
+        );
+        assert_eq!(
+            selected(Some(&preferences(&[], 0.0, 0.0, 1.0))),
+            Some("opus")
+        );
+        assert_eq!(
+            selected(Some(&preferences(&["gemini"], 0.3, 0.0, 0.7))),
+            Some("sonnet")
+        );
+
+        assert_eq!(selected(None), Some("haiku"));
+        assert!(select_model(None, &[]).is_none());
+    }
+}
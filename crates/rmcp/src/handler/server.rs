@@ -1,23 +1,35 @@
 use crate::{
     error::Error as McpError,
     model::*,
-    service::{Peer, RequestContext, RoleServer, Service, ServiceRole},
+    service::{
+        NotificationQueueConfig, Peer, RequestContext, RoleServer, Service, ServiceObserver,
+        ServiceRole, SessionData,
+    },
 };
 
-mod resource;
+pub mod completion;
+pub mod list_changed;
+pub mod logging;
+pub mod prompt;
+pub mod rate_limit;
+pub mod resource;
+pub mod roots;
+pub mod subscription;
 pub mod tool;
 pub mod wrapper;
 impl<H: ServerHandler> Service<RoleServer> for H {
     async fn handle_request(
         &self,
         request: <RoleServer as ServiceRole>::PeerReq,
-        context: RequestContext<RoleServer>,
+        mut context: RequestContext<RoleServer>,
     ) -> Result<<RoleServer as ServiceRole>::Resp, McpError> {
         match request {
-            ClientRequest::InitializeRequest(request) => self
-                .initialize(request.params, context)
-                .await
-                .map(ServerResult::InitializeResult),
+            ClientRequest::InitializeRequest(request) => {
+                self.validate_initialize(&request.params)?;
+                self.initialize(request.params, context)
+                    .await
+                    .map(ServerResult::InitializeResult)
+            }
             ClientRequest::PingRequest(_request) => {
                 self.ping(context).await.map(ServerResult::empty)
             }
@@ -57,14 +69,24 @@ impl<H: ServerHandler> Service<RoleServer> for H {
                 .unsubscribe(request.params, context)
                 .await
                 .map(ServerResult::empty),
-            ClientRequest::CallToolRequest(request) => self
-                .call_tool(request.params, context)
-                .await
-                .map(ServerResult::CallToolResult),
+            ClientRequest::CallToolRequest(request) => {
+                // lets deprecated tools warn the client through `notifications/message`
+                if context.peer.logging_enabled(|| self.get_info()) {
+                    context.extensions.insert(tool::LoggingEnabled);
+                }
+                let version = context.peer.protocol_version().clone();
+                self.call_tool(request.params, context)
+                    .await
+                    .map(|result| ServerResult::CallToolResult(result.for_version(&version)))
+            }
             ClientRequest::ListToolsRequest(request) => self
                 .list_tools(request.params, context)
                 .await
                 .map(ServerResult::ListToolsResult),
+            ClientRequest::CustomRequest(request) => self
+                .on_custom_request(request, context)
+                .await
+                .map(ServerResult::CustomResult),
         }
     }
 
@@ -79,12 +101,21 @@ impl<H: ServerHandler> Service<RoleServer> for H {
             ClientNotification::ProgressNotification(notification) => {
                 self.on_progress(notification.params).await
             }
-            ClientNotification::InitializedNotification(_notification) => {
+            ClientNotification::InitializedNotification(notification) => {
+                let session = notification
+                    .extensions
+                    .get::<SessionData>()
+                    .cloned()
+                    .unwrap_or_default();
+                self.on_session_initialized(session).await;
                 self.on_initialized().await
             }
             ClientNotification::RootsListChangedNotification(_notification) => {
                 self.on_roots_list_changed().await
             }
+            ClientNotification::CustomNotification(notification) => {
+                self.on_custom_notification(notification).await
+            }
         };
         Ok(())
     }
@@ -92,6 +123,10 @@ impl<H: ServerHandler> Service<RoleServer> for H {
     fn get_info(&self) -> <RoleServer as ServiceRole>::Info {
         self.get_info()
     }
+
+    fn handle_disconnect(&self, session: SessionData) -> impl Future<Output = ()> + Send + '_ {
+        self.on_disconnect(session)
+    }
 }
 
 #[allow(unused_variables)]
@@ -102,6 +137,12 @@ pub trait ServerHandler: Sized + Send + Sync + 'static {
     ) -> impl Future<Output = Result<(), McpError>> + Send + '_ {
         std::future::ready(Ok(()))
     }
+    /// Check the client before answering `initialize`, e.g. its capabilities or protocol version
+    ///
+    /// An error is sent as the response to `initialize` and the session is closed.
+    fn validate_initialize(&self, request: &InitializeRequestParam) -> Result<(), McpError> {
+        Ok(())
+    }
     // handle requests
     fn initialize(
         &self,
@@ -110,12 +151,15 @@ pub trait ServerHandler: Sized + Send + Sync + 'static {
     ) -> impl Future<Output = Result<InitializeResult, McpError>> + Send + '_ {
         std::future::ready(Ok(self.get_info()))
     }
+    /// Complete an argument of a prompt or resource template, no completions by default
+    ///
+    /// See [`completion::CompletionRouter`] to register completions per argument.
     fn complete(
         &self,
         request: CompleteRequestParam,
         context: RequestContext<RoleServer>,
     ) -> impl Future<Output = Result<CompleteResult, McpError>> + Send + '_ {
-        std::future::ready(Err(McpError::method_not_found::<CompleteRequestMethod>()))
+        std::future::ready(Ok(CompleteResult::default()))
     }
     fn set_level(
         &self,
@@ -202,13 +246,42 @@ pub trait ServerHandler: Sized + Send + Sync + 'static {
     ) -> impl Future<Output = ()> + Send + '_ {
         std::future::ready(())
     }
+    /// Called once the client is initialized, before [`on_initialized`](Self::on_initialized),
+    /// to set up the values of the session
+    fn on_session_initialized(&self, session: SessionData) -> impl Future<Output = ()> + Send + '_ {
+        std::future::ready(())
+    }
     fn on_initialized(&self) -> impl Future<Output = ()> + Send + '_ {
         tracing::info!("client initialized");
         std::future::ready(())
     }
+    /// Called once the connection is closed, before the values of the session are dropped
+    fn on_disconnect(&self, session: SessionData) -> impl Future<Output = ()> + Send + '_ {
+        std::future::ready(())
+    }
     fn on_roots_list_changed(&self) -> impl Future<Output = ()> + Send + '_ {
         std::future::ready(())
     }
+    /// Answer a request of a method the SDK doesn't know, method not found by default
+    fn on_custom_request(
+        &self,
+        request: CustomRequest,
+        context: RequestContext<RoleServer>,
+    ) -> impl Future<Output = Result<CustomResult, McpError>> + Send + '_ {
+        std::future::ready(Err(McpError::new(
+            ErrorCode::METHOD_NOT_FOUND,
+            request.method,
+            None,
+        )))
+    }
+    /// Called with a notification of a method the SDK doesn't know, ignored by default
+    fn on_custom_notification(
+        &self,
+        notification: CustomNotification,
+    ) -> impl Future<Output = ()> + Send + '_ {
+        tracing::debug!(method = %notification.method, "unknown notification ignored");
+        std::future::ready(())
+    }
 
     fn get_peer(&self) -> Option<Peer<RoleServer>> {
         None
@@ -221,4 +294,289 @@ pub trait ServerHandler: Sized + Send + Sync + 'static {
     fn get_info(&self) -> ServerInfo {
         ServerInfo::default()
     }
+
+    /// Insert a value into the extensions of every request, see [`tool::Extension`]
+    fn with_extension<T: Clone + Send + Sync + 'static>(self, value: T) -> WithExtensions<Self> {
+        WithExtensions::new(self).with_extension(value)
+    }
+
+    /// Run every tool call through `middleware`, see [`tool::ToolMiddleware`]
+    fn with_tool_middleware(
+        self,
+        middleware: impl tool::ToolMiddleware<Self>,
+    ) -> WithExtensions<Self> {
+        WithExtensions::new(self).with_tool_middleware(middleware)
+    }
+
+    /// Fail tool calls taking longer than `timeout`, unless the tool has its own `#[tool(timeout_ms = ..)]`
+    fn with_tool_timeout(self, timeout: std::time::Duration) -> WithExtensions<Self> {
+        WithExtensions::new(self).with_tool_timeout(timeout)
+    }
+
+    /// Limit the tool calls running at once, see [`tool::ToolConcurrency`]
+    fn with_tool_concurrency(self, concurrency: tool::ToolConcurrency) -> WithExtensions<Self> {
+        WithExtensions::new(self).with_tool_concurrency(concurrency)
+    }
+
+    /// Reject the requests of a session over `limits`, see [`rate_limit::RateLimits`]
+    fn with_rate_limits(self, limits: rate_limit::RateLimits) -> WithExtensions<Self> {
+        WithExtensions::new(self).with_rate_limits(limits)
+    }
+
+    /// Keep `watcher` up to date with the client's roots, see [`roots::RootsWatcher`]
+    fn with_roots_watcher(self, watcher: roots::RootsWatcher) -> WithExtensions<Self> {
+        WithExtensions::new(self).with_roots_watcher(watcher)
+    }
+
+    /// Answer `logging/setLevel` and send log messages through `logging`, see [`logging::LoggingSupport`]
+    fn with_logging(self, logging: logging::LoggingSupport) -> WithExtensions<Self> {
+        WithExtensions::new(self).with_logging(logging)
+    }
+
+    /// Answer `resources/subscribe` and `resources/unsubscribe` with `subscriptions`,
+    /// see [`subscription::SubscriptionManager`]
+    fn with_subscriptions(
+        self,
+        subscriptions: subscription::SubscriptionManager,
+    ) -> WithExtensions<Self> {
+        WithExtensions::new(self).with_subscriptions(subscriptions)
+    }
+
+    /// Answer `completion/complete` with `completions`, see [`completion::CompletionRouter`]
+    fn with_completions(self, completions: completion::CompletionRouter) -> WithExtensions<Self> {
+        WithExtensions::new(self).with_completions(completions)
+    }
+
+    /// Bind `notifier` to the client once initialized, see [`list_changed::ListChangedNotifier`]
+    fn with_list_changed_notifier(
+        self,
+        notifier: list_changed::ListChangedNotifier,
+    ) -> WithExtensions<Self> {
+        WithExtensions::new(self).with_list_changed_notifier(notifier)
+    }
+}
+
+/// A service with extensions inserted into the context of every request
+///
+/// Extensions carried by the request itself take precedence.
+pub struct WithExtensions<S> {
+    service: S,
+    extensions: Extensions,
+}
+
+impl<S> WithExtensions<S> {
+    pub fn new(service: S) -> Self {
+        Self {
+            service,
+            extensions: Extensions::new(),
+        }
+    }
+
+    pub fn with_extension<T: Clone + Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.extensions.insert(value);
+        self
+    }
+
+    /// Add a tool middleware inside the ones already installed
+    pub fn with_tool_middleware(mut self, middleware: impl tool::ToolMiddleware<S>) -> Self
+    where
+        S: 'static,
+    {
+        let middlewares = self
+            .extensions
+            .get::<tool::ToolMiddlewares<S>>()
+            .cloned()
+            .unwrap_or_default()
+            .push(middleware);
+        self.extensions.insert(middlewares);
+        self
+    }
+
+    /// Set the default timeout of tool calls, see [`ServerHandler::with_tool_timeout`]
+    pub fn with_tool_timeout(self, timeout: std::time::Duration) -> Self {
+        self.with_extension(tool::ToolTimeout(timeout))
+    }
+
+    /// Limit the tool calls running at once, see [`ServerHandler::with_tool_concurrency`]
+    pub fn with_tool_concurrency(self, concurrency: tool::ToolConcurrency) -> Self {
+        self.with_extension(concurrency)
+    }
+
+    /// Reject the requests over `limits`, see [`ServerHandler::with_rate_limits`]
+    pub fn with_rate_limits(self, limits: rate_limit::RateLimits) -> Self {
+        self.with_extension(limits)
+    }
+
+    /// Keep `watcher` up to date with the client's roots, see [`ServerHandler::with_roots_watcher`]
+    pub fn with_roots_watcher(self, watcher: roots::RootsWatcher) -> Self {
+        self.with_extension(watcher)
+    }
+
+    /// Answer `logging/setLevel` with `logging`, see [`ServerHandler::with_logging`]
+    pub fn with_logging(self, logging: logging::LoggingSupport) -> Self {
+        self.with_extension(logging)
+    }
+
+    /// Track resource subscriptions with `subscriptions`, see [`ServerHandler::with_subscriptions`]
+    pub fn with_subscriptions(self, subscriptions: subscription::SubscriptionManager) -> Self {
+        self.with_extension(subscriptions)
+    }
+
+    /// Answer `completion/complete` with `completions`, see [`ServerHandler::with_completions`]
+    pub fn with_completions(self, completions: completion::CompletionRouter) -> Self {
+        self.with_extension(completions)
+    }
+
+    /// Bind `notifier` to the client, see [`ServerHandler::with_list_changed_notifier`]
+    pub fn with_list_changed_notifier(self, notifier: list_changed::ListChangedNotifier) -> Self {
+        self.with_extension(notifier)
+    }
+
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    pub fn into_inner(self) -> S {
+        self.service
+    }
+
+    /// Advertise the capabilities of the installed helpers
+    fn add_capabilities(&self, capabilities: &mut ServerCapabilities) {
+        if self.extensions.get::<logging::LoggingSupport>().is_some() {
+            capabilities.logging.get_or_insert_with(Default::default);
+        }
+        if self
+            .extensions
+            .get::<completion::CompletionRouter>()
+            .is_some()
+        {
+            capabilities
+                .completions
+                .get_or_insert_with(Default::default);
+        }
+        if self
+            .extensions
+            .get::<subscription::SubscriptionManager>()
+            .is_some()
+        {
+            capabilities
+                .resources
+                .get_or_insert_with(Default::default)
+                .subscribe = Some(true);
+        }
+    }
+}
+
+impl<S: Service<RoleServer>> Service<RoleServer> for WithExtensions<S> {
+    async fn handle_request(
+        &self,
+        request: <RoleServer as ServiceRole>::PeerReq,
+        mut context: RequestContext<RoleServer>,
+    ) -> Result<<RoleServer as ServiceRole>::Resp, McpError> {
+        let logging = self.extensions.get::<logging::LoggingSupport>();
+        let peer = context.peer.clone();
+        if let ClientRequest::InitializeRequest(_) = &request {
+            if let Some(watcher) = self.extensions.get::<roots::RootsWatcher>() {
+                watcher.bind_peer(peer.clone());
+            }
+            if let Some(logging) = logging {
+                logging.bind_peer(peer.clone());
+            }
+        }
+        if let Some(limits) = self.extensions.get::<rate_limit::RateLimits>() {
+            limits.check(&peer, request.method())?;
+        }
+        if let (ClientRequest::SetLevelRequest(request), Some(logging)) = (&request, logging) {
+            logging.set_level(request.params.level);
+            return Ok(ServerResult::empty(()));
+        }
+        if let (ClientRequest::CompleteRequest(request), Some(completions)) = (
+            &request,
+            self.extensions.get::<completion::CompletionRouter>(),
+        ) {
+            return completions
+                .complete(request.params.clone())
+                .await
+                .map(ServerResult::CompleteResult);
+        }
+        if let Some(subscriptions) = self.extensions.get::<subscription::SubscriptionManager>() {
+            match &request {
+                ClientRequest::SubscribeRequest(request) => {
+                    subscriptions.subscribe(&peer, request.params.uri.clone());
+                    return Ok(ServerResult::empty(()));
+                }
+                ClientRequest::UnsubscribeRequest(request) => {
+                    subscriptions.unsubscribe(&peer, &request.params.uri);
+                    return Ok(ServerResult::empty(()));
+                }
+                _ => {}
+            }
+        }
+        // held until the call is done, a call cancelled while it waits never runs
+        let _permit = match (&request, self.extensions.get::<tool::ToolConcurrency>()) {
+            (ClientRequest::CallToolRequest(request), Some(concurrency)) => {
+                concurrency
+                    .acquire(&request.params.name, &context.ct)
+                    .await?
+            }
+            _ => None,
+        };
+        let mut extensions = self.extensions.clone();
+        extensions.extend(std::mem::take(&mut context.extensions));
+        context.extensions = extensions;
+        if logging.is_some() {
+            context.extensions.insert(tool::LoggingEnabled);
+        }
+        let mut result = self.service.handle_request(request, context).await;
+        if let Ok(ServerResult::InitializeResult(result)) = &mut result {
+            self.add_capabilities(&mut result.capabilities);
+            if let Some(notifier) = self.extensions.get::<list_changed::ListChangedNotifier>() {
+                notifier.bind(peer, &result.capabilities);
+            }
+        }
+        result
+    }
+
+    async fn handle_notification(
+        &self,
+        notification: <RoleServer as ServiceRole>::PeerNot,
+    ) -> Result<(), McpError> {
+        if let (
+            ClientNotification::InitializedNotification(_)
+            | ClientNotification::RootsListChangedNotification(_),
+            Some(watcher),
+        ) = (&notification, self.extensions.get::<roots::RootsWatcher>())
+        {
+            // the initialized notification is handled before the service loop runs,
+            // waiting for the listing here would block the response
+            watcher.spawn_refresh();
+        }
+        self.service.handle_notification(notification).await
+    }
+
+    fn get_info(&self) -> <RoleServer as ServiceRole>::Info {
+        let mut info = self.service.get_info();
+        self.add_capabilities(&mut info.capabilities);
+        info
+    }
+
+    fn observer(&self) -> Option<std::sync::Arc<dyn ServiceObserver>> {
+        self.service.observer()
+    }
+
+    fn notification_queue(&self) -> NotificationQueueConfig {
+        self.service.notification_queue()
+    }
+
+    async fn handle_disconnect(&self, session: SessionData) {
+        self.service.handle_disconnect(session).await
+    }
+
+    fn bind_peer(&self, peer: &Peer<RoleServer>) {
+        self.service.bind_peer(peer)
+    }
 }
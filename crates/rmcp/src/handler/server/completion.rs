@@ -0,0 +1,130 @@
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
+
+use futures::future::BoxFuture;
+
+use crate::model::{
+    CompleteRequestParam, CompleteResult, CompletionInfo, PromptReference, Reference,
+    ResourceReference,
+};
+
+type CompletionProvider =
+    Arc<dyn Fn(String) -> BoxFuture<'static, Result<CompletionInfo, crate::Error>> + Send + Sync>;
+
+/// Completion providers keyed by the prompt or resource template and the argument they complete
+///
+/// Installed with [`ServerHandler::with_completions`](super::ServerHandler::with_completions), it
+/// answers `completion/complete` and advertises the completions capability. A provider gets the
+/// value typed so far; arguments without a provider complete to nothing.
+///
+/// ```rust,ignore
+/// let completions = CompletionRouter::new()
+///     .prompt_argument("code_review", "language", |value| async move {
+///         Ok(CompletionInfo::matching(["python", "rust", "typescript"], &value))
+///     })
+///     .resource_argument("file:///{path}", "path", |value| list_files(value));
+/// ```
+#[derive(Clone, Default)]
+pub struct CompletionRouter {
+    providers: HashMap<(Reference, Cow<'static, str>), CompletionProvider>,
+}
+
+impl std::fmt::Debug for CompletionRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompletionRouter")
+            .field("arguments", &self.providers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl CompletionRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Complete `argument` of the prompt `prompt`
+    pub fn prompt_argument<F, Fut, T>(
+        self,
+        prompt: impl Into<String>,
+        argument: impl Into<Cow<'static, str>>,
+        provider: F,
+    ) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, crate::Error>> + Send + 'static,
+        T: Into<CompletionInfo> + 'static,
+    {
+        let reference = Reference::Prompt(PromptReference {
+            name: prompt.into(),
+        });
+        self.route(reference, argument.into(), provider)
+    }
+
+    /// Complete `argument` of the resource template `uri_template`
+    pub fn resource_argument<F, Fut, T>(
+        self,
+        uri_template: impl Into<String>,
+        argument: impl Into<Cow<'static, str>>,
+        provider: F,
+    ) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, crate::Error>> + Send + 'static,
+        T: Into<CompletionInfo> + 'static,
+    {
+        let reference = Reference::Resource(ResourceReference {
+            uri: uri_template.into(),
+        });
+        self.route(reference, argument.into(), provider)
+    }
+
+    fn route<F, Fut, T>(
+        mut self,
+        reference: Reference,
+        argument: Cow<'static, str>,
+        provider: F,
+    ) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, crate::Error>> + Send + 'static,
+        T: Into<CompletionInfo> + 'static,
+    {
+        let provider: CompletionProvider = Arc::new(move |value| {
+            let completion = provider(value);
+            Box::pin(async move { completion.await.map(Into::into) })
+        });
+        self.providers.insert((reference, argument), provider);
+        self
+    }
+
+    /// Whether a provider completes `argument` of `reference`
+    pub fn contains(&self, reference: &Reference, argument: &str) -> bool {
+        self.providers
+            .contains_key(&(reference.clone(), Cow::Owned(argument.to_string())))
+    }
+
+    pub async fn complete(
+        &self,
+        CompleteRequestParam { r#ref, argument }: CompleteRequestParam,
+    ) -> Result<CompleteResult, crate::Error> {
+        let provider = self
+            .providers
+            .get(&(r#ref, Cow::Owned(argument.name)))
+            .cloned();
+        let Some(provider) = provider else {
+            return Ok(CompleteResult::default());
+        };
+        let mut completion = provider(argument.value).await?;
+        // a provider building the completion itself may exceed the limit of the spec
+        if completion.values.len() > CompletionInfo::MAX_VALUES {
+            let total = completion.total.unwrap_or(completion.values.len() as u32);
+            completion = CompletionInfo {
+                total: Some(total),
+                ..CompletionInfo::from_values(completion.values)
+            };
+        }
+        Ok(CompleteResult {
+            completion,
+            meta: None,
+        })
+    }
+}
@@ -0,0 +1,147 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    model::ServerCapabilities,
+    service::{Peer, RoleServer},
+};
+
+#[derive(Debug, Clone, Copy)]
+enum ListKind {
+    Tools,
+    Prompts,
+    Resources,
+}
+
+#[derive(Debug, Default)]
+struct ListState {
+    advertised: bool,
+    pending: bool,
+}
+
+#[derive(Default)]
+struct NotifierState {
+    peer: Option<Peer<RoleServer>>,
+    tools: ListState,
+    prompts: ListState,
+    resources: ListState,
+}
+
+impl NotifierState {
+    fn list(&mut self, kind: ListKind) -> &mut ListState {
+        match kind {
+            ListKind::Tools => &mut self.tools,
+            ListKind::Prompts => &mut self.prompts,
+            ListKind::Resources => &mut self.resources,
+        }
+    }
+}
+
+/// Sends `notifications/*/list_changed`, at most once per list and interval
+///
+/// Changes marked within the interval after the first one are sent as a single notification at
+/// its end. Lists whose `list_changed` capability the server didn't advertise are never notified.
+/// Clones share the bound peer and the pending notifications.
+#[derive(Clone)]
+pub struct ListChangedNotifier {
+    interval: Duration,
+    state: Arc<std::sync::Mutex<NotifierState>>,
+}
+
+impl Default for ListChangedNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ListChangedNotifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.lock();
+        f.debug_struct("ListChangedNotifier")
+            .field("interval", &self.interval)
+            .field("bound", &state.peer.is_some())
+            .field("tools", &state.tools)
+            .field("prompts", &state.prompts)
+            .field("resources", &state.resources)
+            .finish()
+    }
+}
+
+impl ListChangedNotifier {
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(100);
+
+    pub fn new() -> Self {
+        Self::with_interval(Self::DEFAULT_INTERVAL)
+    }
+
+    pub fn with_interval(interval: Duration) -> Self {
+        Self {
+            interval,
+            state: Default::default(),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Notify `peer` from now on, for the lists `capabilities` advertises as changing
+    pub fn bind(&self, peer: Peer<RoleServer>, capabilities: &ServerCapabilities) {
+        let mut state = self.lock();
+        state.peer = Some(peer);
+        state.tools.advertised = capabilities.supports_tools_list_changed();
+        state.prompts.advertised = capabilities.supports_prompts_list_changed();
+        state.resources.advertised = capabilities.supports_resources_list_changed();
+    }
+
+    pub fn mark_tools_changed(&self) {
+        self.mark_changed(ListKind::Tools);
+    }
+
+    pub fn mark_prompts_changed(&self) {
+        self.mark_changed(ListKind::Prompts);
+    }
+
+    pub fn mark_resources_changed(&self) {
+        self.mark_changed(ListKind::Resources);
+    }
+
+    fn mark_changed(&self, kind: ListKind) {
+        {
+            let mut state = self.lock();
+            if state.peer.is_none() {
+                return;
+            }
+            let list = state.list(kind);
+            if !list.advertised || list.pending {
+                return;
+            }
+            list.pending = true;
+        }
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(notifier.interval).await;
+            let peer = {
+                let mut state = notifier.lock();
+                state.list(kind).pending = false;
+                state.peer.clone()
+            };
+            let Some(peer) = peer else {
+                return;
+            };
+            let result = match kind {
+                ListKind::Tools => peer.notify_tool_list_changed().await,
+                ListKind::Prompts => peer.notify_prompt_list_changed().await,
+                ListKind::Resources => peer.notify_resource_list_changed().await,
+            };
+            if let Err(error) = result {
+                tracing::warn!("failed to send {kind:?} list changed notification: {error}");
+            }
+        });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, NotifierState> {
+        self.state
+            .lock()
+            .expect("list changed notifier lock poisoned")
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::{
+    model::{LoggingLevel, LoggingMessageNotificationParam},
+    service::{Peer, RoleServer, ServiceError},
+};
+
+/// Sends `notifications/message` to the client at or above the level it asked for
+///
+/// Installed with [`ServerHandler::with_logging`](super::ServerHandler::with_logging), it answers
+/// `logging/setLevel`, advertises the logging capability and is available to tools through an
+/// `Extension<LoggingSupport>` argument. Clones share the level and the peer, so a handler can
+/// keep one for itself.
+#[derive(Clone)]
+pub struct LoggingSupport {
+    level: Arc<std::sync::RwLock<LoggingLevel>>,
+    peer: Arc<std::sync::RwLock<Option<Peer<RoleServer>>>>,
+}
+
+impl Default for LoggingSupport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for LoggingSupport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoggingSupport")
+            .field("level", &self.level())
+            .finish()
+    }
+}
+
+impl LoggingSupport {
+    /// Logs at [`LoggingLevel::Info`] and above until the client sets a level
+    pub fn new() -> Self {
+        Self::with_default_level(LoggingLevel::Info)
+    }
+
+    pub fn with_default_level(level: LoggingLevel) -> Self {
+        Self {
+            level: Arc::new(std::sync::RwLock::new(level)),
+            peer: Default::default(),
+        }
+    }
+
+    /// The minimum level sent to the client
+    pub fn level(&self) -> LoggingLevel {
+        *self.level.read().expect("logging lock poisoned")
+    }
+
+    pub fn set_level(&self, level: LoggingLevel) {
+        *self.level.write().expect("logging lock poisoned") = level;
+    }
+
+    /// Whether a message at `level` would be sent
+    pub fn enabled(&self, level: LoggingLevel) -> bool {
+        level >= self.level() && self.peer.read().expect("logging lock poisoned").is_some()
+    }
+
+    /// Send a log message to the client, does nothing below the current level
+    pub async fn log(
+        &self,
+        level: LoggingLevel,
+        logger: impl Into<String>,
+        data: Value,
+    ) -> Result<(), ServiceError> {
+        if level < self.level() {
+            return Ok(());
+        }
+        let peer = self.peer.read().expect("logging lock poisoned").clone();
+        let Some(peer) = peer else {
+            return Ok(());
+        };
+        peer.notify_logging_message(LoggingMessageNotificationParam {
+            level,
+            logger: Some(logger.into()),
+            data,
+        })
+        .await
+    }
+
+    /// Send log messages to `peer` from now on
+    pub fn bind_peer(&self, peer: Peer<RoleServer>) {
+        *self.peer.write().expect("logging lock poisoned") = Some(peer);
+    }
+}
+
+/// A `tracing` layer forwarding events to the client through a [`LoggingSupport`]
+///
+/// Events of the `rmcp` crate itself are never forwarded, sending a notification traces too.
+#[cfg(feature = "tracing-layer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing-layer")))]
+#[derive(Debug, Clone)]
+pub struct LoggingLayer {
+    logging: LoggingSupport,
+}
+
+#[cfg(feature = "tracing-layer")]
+impl LoggingLayer {
+    pub fn new(logging: LoggingSupport) -> Self {
+        Self { logging }
+    }
+}
+
+#[cfg(feature = "tracing-layer")]
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for LoggingLayer {
+    fn on_event(
+        &self,
+        event: &tracing::Event<'_>,
+        _context: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let metadata = event.metadata();
+        let target = metadata.target();
+        if target == "rmcp" || target.starts_with("rmcp::") {
+            return;
+        }
+        let level = match *metadata.level() {
+            tracing::Level::ERROR => LoggingLevel::Error,
+            tracing::Level::WARN => LoggingLevel::Warning,
+            tracing::Level::INFO => LoggingLevel::Info,
+            tracing::Level::DEBUG | tracing::Level::TRACE => LoggingLevel::Debug,
+        };
+        if !self.logging.enabled(level) {
+            return;
+        }
+        // logging from outside a runtime has nowhere to send the notification
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let mut fields = JsonVisitor::default();
+        event.record(&mut fields);
+        let logging = self.logging.clone();
+        let logger = target.to_string();
+        runtime.spawn(async move {
+            // the client may be gone, there is no one left to tell
+            let _ = logging.log(level, logger, Value::Object(fields.0)).await;
+        });
+    }
+}
+
+#[cfg(feature = "tracing-layer")]
+#[derive(Default)]
+struct JsonVisitor(serde_json::Map<String, Value>);
+
+#[cfg(feature = "tracing-layer")]
+impl tracing::field::Visit for JsonVisitor {
+    fn record_f64(&mut self, field: &tracing::field::Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &tracing::field::Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &tracing::field::Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}").into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_threshold() {
+        let logging = LoggingSupport::new();
+        assert_eq!(logging.level(), LoggingLevel::Info);
+        // nothing is sent before a client is bound
+        assert!(!logging.enabled(LoggingLevel::Emergency));
+
+        assert!(LoggingLevel::Debug < LoggingLevel::Info);
+        assert!(LoggingLevel::Warning < LoggingLevel::Error);
+        assert!(LoggingLevel::Alert < LoggingLevel::Emergency);
+
+        let shared = logging.clone();
+        shared.set_level(LoggingLevel::Error);
+        assert_eq!(logging.level(), LoggingLevel::Error);
+    }
+}
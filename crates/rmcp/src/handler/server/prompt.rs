@@ -0,0 +1,247 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use futures::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    RoleServer,
+    model::{
+        GetPromptRequestParam, GetPromptResult, JsonObject, Prompt, PromptMessage,
+        PromptMessageRole, parse_prompt_arguments,
+    },
+    service::RequestContext,
+};
+
+pub struct PromptGetContext<'service, S> {
+    request_context: RequestContext<RoleServer>,
+    service: &'service S,
+    name: String,
+    arguments: Option<JsonObject>,
+}
+
+impl<'service, S> PromptGetContext<'service, S> {
+    pub fn new(
+        service: &'service S,
+        GetPromptRequestParam { name, arguments }: GetPromptRequestParam,
+        request_context: RequestContext<RoleServer>,
+    ) -> Self {
+        Self {
+            request_context,
+            service,
+            name,
+            arguments,
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+pub trait FromPromptGetContextPart<'a, S>: Sized {
+    fn from_prompt_get_context_part(
+        context: PromptGetContext<'a, S>,
+    ) -> Result<(Self, PromptGetContext<'a, S>), crate::Error>;
+}
+
+impl<'a, S> FromPromptGetContextPart<'a, S> for &'a S {
+    fn from_prompt_get_context_part(
+        context: PromptGetContext<'a, S>,
+    ) -> Result<(Self, PromptGetContext<'a, S>), crate::Error> {
+        Ok((context.service, context))
+    }
+}
+
+impl<'a, S> FromPromptGetContextPart<'a, S> for CancellationToken {
+    fn from_prompt_get_context_part(
+        context: PromptGetContext<'a, S>,
+    ) -> Result<(Self, PromptGetContext<'a, S>), crate::Error> {
+        Ok((context.request_context.ct.clone(), context))
+    }
+}
+
+impl<'a, S> FromPromptGetContextPart<'a, S> for JsonObject {
+    fn from_prompt_get_context_part(
+        mut context: PromptGetContext<'a, S>,
+    ) -> Result<(Self, PromptGetContext<'a, S>), crate::Error> {
+        let object = context.arguments.take().unwrap_or_default();
+        Ok((object, context))
+    }
+}
+
+/// Deserialize the arguments of the prompt into `T`
+///
+/// Missing or unknown arguments are reported as `invalid_params`, see
+/// [`GetPromptRequestParam::parse_arguments`].
+pub struct PromptArguments<T>(pub T);
+
+impl<'a, S, T: DeserializeOwned> FromPromptGetContextPart<'a, S> for PromptArguments<T> {
+    fn from_prompt_get_context_part(
+        mut context: PromptGetContext<'a, S>,
+    ) -> Result<(Self, PromptGetContext<'a, S>), crate::Error> {
+        let object = context.arguments.take().unwrap_or_default();
+        let arguments = parse_prompt_arguments(object)?;
+        Ok((PromptArguments(arguments), context))
+    }
+}
+
+/// Types that can be converted into a [`GetPromptResult`]
+///
+/// A plain string is taken as a single user message.
+pub trait IntoGetPromptResult {
+    fn into_get_prompt_result(self) -> Result<GetPromptResult, crate::Error>;
+}
+
+impl IntoGetPromptResult for GetPromptResult {
+    fn into_get_prompt_result(self) -> Result<GetPromptResult, crate::Error> {
+        Ok(self)
+    }
+}
+
+impl IntoGetPromptResult for Vec<PromptMessage> {
+    fn into_get_prompt_result(self) -> Result<GetPromptResult, crate::Error> {
+        Ok(GetPromptResult {
+            description: None,
+            messages: self,
+            meta: None,
+        })
+    }
+}
+
+impl IntoGetPromptResult for PromptMessage {
+    fn into_get_prompt_result(self) -> Result<GetPromptResult, crate::Error> {
+        vec![self].into_get_prompt_result()
+    }
+}
+
+impl IntoGetPromptResult for String {
+    fn into_get_prompt_result(self) -> Result<GetPromptResult, crate::Error> {
+        PromptMessage::new_text(PromptMessageRole::User, self).into_get_prompt_result()
+    }
+}
+
+impl<T: IntoGetPromptResult> IntoGetPromptResult for Result<T, crate::Error> {
+    fn into_get_prompt_result(self) -> Result<GetPromptResult, crate::Error> {
+        self.and_then(IntoGetPromptResult::into_get_prompt_result)
+    }
+}
+
+pub type DynPromptGetHandler<S> = dyn Fn(PromptGetContext<'_, S>) -> BoxFuture<'_, Result<GetPromptResult, crate::Error>>
+    + Send
+    + Sync;
+
+pub struct PromptBoxItem<S> {
+    #[allow(clippy::type_complexity)]
+    pub get: Box<DynPromptGetHandler<S>>,
+    pub attr: Prompt,
+}
+
+impl<S: Send + Sync + 'static + Clone> PromptBoxItem<S> {
+    pub fn new<G>(attr: Prompt, get: G) -> Self
+    where
+        G: Fn(PromptGetContext<'_, S>) -> BoxFuture<'_, Result<GetPromptResult, crate::Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self {
+            get: Box::new(get),
+            attr,
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.attr.name
+    }
+}
+
+#[derive(Default)]
+pub struct PromptBox<S> {
+    #[allow(clippy::type_complexity)]
+    pub map: HashMap<Cow<'static, str>, PromptBoxItem<S>>,
+}
+
+impl<S> PromptBox<S> {
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+    pub fn add(&mut self, item: PromptBoxItem<S>) {
+        self.map.insert(item.attr.name.clone().into(), item);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.map.remove(name);
+    }
+
+    pub async fn get(
+        &self,
+        context: PromptGetContext<'_, S>,
+    ) -> Result<GetPromptResult, crate::Error> {
+        let item = self
+            .map
+            .get(context.name())
+            .ok_or_else(|| crate::Error::prompt_not_found(context.name()))?;
+        (item.get)(context).await
+    }
+
+    pub fn list(&self) -> Vec<Prompt> {
+        self.map.values().map(|item| item.attr.clone()).collect()
+    }
+}
+
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+#[macro_export]
+macro_rules! prompt_box {
+    (@pin_add $callee: ident, $attr: expr, $f: expr) => {
+        $callee.add(PromptBoxItem::new($attr, |context| Box::pin($f(context))));
+    };
+    ($server: ident { $($(#[$meta: meta])* $prompt: ident),* $(,)?} ) => {
+        $crate::prompt_box!($server { $($(#[$meta])* $prompt),* }  prompt_box);
+    };
+    ($server: ident { $($(#[$meta: meta])* $prompt: ident),* $(,)?} $prompt_box: ident) => {
+        fn $prompt_box() -> &'static $crate::handler::server::prompt::PromptBox<$server> {
+            use $crate::handler::server::prompt::{PromptBox, PromptBoxItem};
+            static PROMPT_BOX: std::sync::OnceLock<PromptBox<$server>> = std::sync::OnceLock::new();
+            PROMPT_BOX.get_or_init(|| {
+                let mut prompt_box = PromptBox::new();
+                $crate::paste!{
+                    $(
+                        $(#[$meta])*
+                        {
+                            $crate::prompt_box!(@pin_add prompt_box, $server::[< $prompt _prompt_attr>](), $server::[<$prompt _prompt_get>]);
+                        }
+                    )*
+                }
+                prompt_box
+            })
+        }
+    };
+    (@derive) => {
+        $crate::prompt_box!(@derive prompt_box);
+    };
+
+    (@derive $prompt_box:ident) => {
+        async fn list_prompts(
+            &self,
+            _: Option<$crate::model::PaginatedRequestParam>,
+            _: $crate::service::RequestContext<$crate::service::RoleServer>,
+        ) -> Result<$crate::model::ListPromptsResult, $crate::Error> {
+            Ok($crate::model::ListPromptsResult {
+                next_cursor: None,
+                prompts: Self::$prompt_box().list(),
+                meta: None,
+            })
+        }
+
+        async fn get_prompt(
+            &self,
+            get_prompt_request_param: $crate::model::GetPromptRequestParam,
+            context: $crate::service::RequestContext<$crate::service::RoleServer>,
+        ) -> Result<$crate::model::GetPromptResult, $crate::Error> {
+            let context = $crate::handler::server::prompt::PromptGetContext::new(self, get_prompt_request_param, context);
+            Self::$prompt_box().get(context).await
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::{
+    model::{ErrorCode, ErrorData},
+    service::{Peer, RoleServer},
+};
+
+/// Requests limited by [`RateLimits::other_requests`] only when listed explicitly
+const EXEMPT_METHODS: [&str; 2] = ["initialize", "ping"];
+
+/// A token bucket: up to `capacity` requests at once, refilled at `capacity` per `per`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub per: Duration,
+}
+
+impl RateLimit {
+    pub fn new(capacity: u32, per: Duration) -> Self {
+        assert!(capacity > 0, "rate limit capacity must be greater than 0");
+        assert!(!per.is_zero(), "rate limit period must be greater than 0");
+        Self { capacity, per }
+    }
+
+    fn tokens_per_second(&self) -> f64 {
+        f64::from(self.capacity) / self.per.as_secs_f64()
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.capacity),
+            updated: now,
+        }
+    }
+
+    /// Take a token, or tell how long until the next one is available
+    fn take(&mut self, limit: RateLimit, now: Instant) -> Result<(), Duration> {
+        let rate = limit.tokens_per_second();
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(f64::from(limit.capacity));
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / rate))
+        }
+    }
+}
+
+/// A group of methods sharing a bucket, `None` for the other requests
+type BucketKey = Option<usize>;
+
+struct Session {
+    peer: Peer<RoleServer>,
+    buckets: HashMap<BucketKey, Bucket>,
+}
+
+/// Token bucket limits of the requests of each session, per group of methods
+///
+/// Installed with [`ServerHandler::with_rate_limits`](super::ServerHandler::with_rate_limits),
+/// a request over its limit fails with [`ErrorCode::RATE_LIMITED`] and the milliseconds until
+/// it can be retried as `retryAfterMs` in the error data. Notifications are never limited.
+///
+/// Clones share the buckets, so one instance can serve every session of a server while each
+/// session gets its own buckets. Sessions whose transport closed are dropped.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use rmcp::handler::server::rate_limit::RateLimits;
+/// let limits = RateLimits::new()
+///     .tools_call(10, Duration::from_secs(1))
+///     .other_requests(100, Duration::from_secs(1));
+/// ```
+#[derive(Clone, Default)]
+pub struct RateLimits {
+    groups: Vec<RateLimit>,
+    methods: HashMap<&'static str, usize>,
+    other_requests: Option<RateLimit>,
+    sessions: Arc<Mutex<Vec<Session>>>,
+}
+
+impl std::fmt::Debug for RateLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimits")
+            .field("methods", &self.methods)
+            .field("groups", &self.groups)
+            .field("other_requests", &self.other_requests)
+            .field("sessions", &self.lock().len())
+            .finish()
+    }
+}
+
+impl RateLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit `methods` together, replacing the limits they had
+    pub fn methods(
+        mut self,
+        methods: impl IntoIterator<Item = &'static str>,
+        capacity: u32,
+        per: Duration,
+    ) -> Self {
+        let group = self.groups.len();
+        self.groups.push(RateLimit::new(capacity, per));
+        self.methods
+            .extend(methods.into_iter().map(|method| (method, group)));
+        self
+    }
+
+    pub fn method(self, method: &'static str, capacity: u32, per: Duration) -> Self {
+        self.methods([method], capacity, per)
+    }
+
+    /// Limit `tools/call`
+    pub fn tools_call(self, capacity: u32, per: Duration) -> Self {
+        self.method("tools/call", capacity, per)
+    }
+
+    /// Limit `resources/read`
+    pub fn resources_read(self, capacity: u32, per: Duration) -> Self {
+        self.method("resources/read", capacity, per)
+    }
+
+    /// Limit `prompts/get`
+    pub fn prompts_get(self, capacity: u32, per: Duration) -> Self {
+        self.method("prompts/get", capacity, per)
+    }
+
+    /// Limit the listings of tools, prompts, resources and resource templates together
+    pub fn lists(self, capacity: u32, per: Duration) -> Self {
+        self.methods(
+            [
+                "tools/list",
+                "prompts/list",
+                "resources/list",
+                "resources/templates/list",
+            ],
+            capacity,
+            per,
+        )
+    }
+
+    /// Limit the requests without a limit of their own together, except `initialize` and `ping`
+    pub fn other_requests(mut self, capacity: u32, per: Duration) -> Self {
+        self.other_requests = Some(RateLimit::new(capacity, per));
+        self
+    }
+
+    /// Count a request of the session of `peer`, failing if it is over its limit
+    pub fn check(&self, peer: &Peer<RoleServer>, method: &str) -> Result<(), ErrorData> {
+        let (key, limit) = match (self.methods.get(method), self.other_requests) {
+            (Some(&group), _) => (Some(group), self.groups[group]),
+            (None, Some(limit)) if !EXEMPT_METHODS.contains(&method) => (None, limit),
+            (None, _) => return Ok(()),
+        };
+        let now = Instant::now();
+        let mut sessions = self.lock();
+        sessions.retain(|session| !session.peer.is_transport_closed());
+        let index = match sessions
+            .iter()
+            .position(|session| session.peer.is_same_session(peer))
+        {
+            Some(index) => index,
+            None => {
+                sessions.push(Session {
+                    peer: peer.clone(),
+                    buckets: HashMap::new(),
+                });
+                sessions.len() - 1
+            }
+        };
+        let bucket = sessions[index]
+            .buckets
+            .entry(key)
+            .or_insert_with(|| Bucket::full(limit, now));
+        bucket.take(limit, now).map_err(|retry_after| {
+            let retry_after_ms = (retry_after.as_secs_f64() * 1000.0).ceil() as u64;
+            ErrorData::new(
+                ErrorCode::RATE_LIMITED,
+                format!("rate limit exceeded for {method}"),
+                Some(serde_json::json!({ "retryAfterMs": retry_after_ms })),
+            )
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Session>> {
+        self.sessions.lock().expect("rate limits lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_refill() {
+        let limit = RateLimit::new(2, Duration::from_secs(1));
+        let start = Instant::now();
+        let mut bucket = Bucket::full(limit, start);
+        assert!(bucket.take(limit, start).is_ok());
+        assert!(bucket.take(limit, start).is_ok());
+        assert_eq!(bucket.take(limit, start), Err(Duration::from_millis(500)));
+
+        // half a token refilled, the other half is still missing
+        let later = start + Duration::from_millis(250);
+        assert_eq!(bucket.take(limit, later), Err(Duration::from_millis(250)));
+        let later = start + Duration::from_millis(500);
+        assert!(bucket.take(limit, later).is_ok());
+
+        // never more than the capacity
+        let later = start + Duration::from_secs(10);
+        assert!(bucket.take(limit, later).is_ok());
+        assert!(bucket.take(limit, later).is_ok());
+        assert!(bucket.take(limit, later).is_err());
+    }
+}
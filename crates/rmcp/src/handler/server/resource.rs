@@ -1 +1,659 @@
+use std::{collections::HashMap, fmt, str::FromStr};
 
+use futures::future::BoxFuture;
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    RoleServer,
+    model::{
+        RawResourceTemplate, ReadResourceRequestParam, ReadResourceResult, Resource,
+        ResourceContents, ResourceTemplate,
+    },
+    service::RequestContext,
+};
+
+/// The declaration of a resource generated by the `#[resource]` macro
+///
+/// A uri containing `{name}` placeholders is declared as a resource template.
+#[derive(Debug, Clone)]
+pub enum ResourceAttr {
+    Resource(Resource),
+    Template(ResourceTemplate),
+}
+
+impl ResourceAttr {
+    pub fn uri(&self) -> &str {
+        match self {
+            ResourceAttr::Resource(resource) => &resource.uri,
+            ResourceAttr::Template(template) => &template.uri_template,
+        }
+    }
+    /// Match a requested uri, returning the extracted template parameters on success
+    pub fn match_uri(&self, uri: &str) -> Option<HashMap<String, String>> {
+        match self {
+            ResourceAttr::Resource(resource) => (resource.uri == uri).then(HashMap::new),
+            ResourceAttr::Template(template) => match_uri_template(&template.uri_template, uri),
+        }
+    }
+}
+
+enum UriTemplatePart<'t> {
+    Literal(&'t str),
+    Variable(&'t str),
+}
+
+fn parse_uri_template(template: &str) -> Vec<UriTemplatePart<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        if start > 0 {
+            parts.push(UriTemplatePart::Literal(&rest[..start]));
+        }
+        parts.push(UriTemplatePart::Variable(&rest[start + 1..start + len]));
+        rest = &rest[start + len + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(UriTemplatePart::Literal(rest));
+    }
+    parts
+}
+
+/// Match a uri against a simple uri template like `file:///{path}`
+///
+/// A variable matches everything up to the next literal part of the template,
+/// the last variable matches the rest of the uri. Variables never match an empty string,
+/// and their percent-encoded octets are decoded.
+pub fn match_uri_template(template: &str, uri: &str) -> Option<HashMap<String, String>> {
+    let parts = parse_uri_template(template);
+    let mut params = HashMap::new();
+    let mut rest = uri;
+    let mut parts = parts.iter().peekable();
+    while let Some(part) = parts.next() {
+        match part {
+            UriTemplatePart::Literal(literal) => {
+                rest = rest.strip_prefix(literal)?;
+            }
+            UriTemplatePart::Variable(name) => {
+                let end = match parts.peek() {
+                    Some(UriTemplatePart::Literal(literal)) => rest.find(literal)?,
+                    _ => rest.len(),
+                };
+                if end == 0 {
+                    return None;
+                }
+                params.insert(name.to_string(), percent_decode(&rest[..end])?);
+                rest = &rest[end..];
+            }
+        }
+    }
+    rest.is_empty().then_some(params)
+}
+
+/// Decode the percent-encoded octets of a value, `None` if they are malformed or not utf-8
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = value.get(index + 1..index + 3)?;
+            if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// An error in the syntax of a [`UriTemplate`]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum UriTemplateError {
+    #[error("unclosed expression at {0}")]
+    Unclosed(usize),
+    #[error("unexpected `}}` at {0}")]
+    UnexpectedClose(usize),
+    #[error("unsupported expression {{{0}}}, only simple variables like {{name}} are supported")]
+    Unsupported(String),
+    #[error("invalid variable name {0:?}")]
+    InvalidVariable(String),
+    #[error("duplicate variable {0}")]
+    DuplicateVariable(String),
+    #[error("variables {0} and {1} must be separated by a literal")]
+    AdjacentVariables(String, String),
+}
+
+/// A [RFC 6570] level 1 uri template, like `file:///{path}` or `db://{table}/{id}`
+///
+/// The same template can be advertised with [`UriTemplate::resource_template`] and used to
+/// match the requested uris with [`UriTemplate::matches`], see [`match_uri_template`] for the
+/// matching rules.
+///
+/// [RFC 6570]: https://www.rfc-editor.org/rfc/rfc6570
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UriTemplate {
+    template: String,
+}
+
+impl UriTemplate {
+    pub fn new(template: impl Into<String>) -> Result<Self, UriTemplateError> {
+        let template = template.into();
+        let mut variables = Vec::new();
+        let mut previous_variable: Option<&str> = None;
+        let mut index = 0;
+        while let Some(offset) = template[index..].find(['{', '}']) {
+            let start = index + offset;
+            if template[start..].starts_with('}') {
+                return Err(UriTemplateError::UnexpectedClose(start));
+            }
+            let end = match template[start + 1..].find(['{', '}']) {
+                Some(len) if template[start + 1 + len..].starts_with('}') => start + 1 + len,
+                _ => return Err(UriTemplateError::Unclosed(start)),
+            };
+            let variable = parse_uri_template_variable(&template[start + 1..end])?;
+            if offset > 0 {
+                previous_variable = None;
+            }
+            if let Some(previous) = previous_variable {
+                return Err(UriTemplateError::AdjacentVariables(
+                    previous.to_owned(),
+                    variable.to_owned(),
+                ));
+            }
+            if variables.contains(&variable) {
+                return Err(UriTemplateError::DuplicateVariable(variable.to_owned()));
+            }
+            variables.push(variable);
+            previous_variable = Some(variable);
+            index = end + 1;
+        }
+        Ok(Self { template })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// The names of the variables, in the order of the template
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        parse_uri_template(&self.template)
+            .into_iter()
+            .filter_map(|part| match part {
+                UriTemplatePart::Variable(name) => Some(name),
+                UriTemplatePart::Literal(_) => None,
+            })
+    }
+
+    /// Match a uri, returning the decoded values of the variables on success
+    pub fn matches(&self, uri: &str) -> Option<UriParams> {
+        match_uri_template(&self.template, uri).map(UriParams)
+    }
+
+    /// Declare a resource template with this uri template
+    pub fn resource_template(&self, name: impl Into<String>) -> RawResourceTemplate {
+        RawResourceTemplate {
+            uri_template: self.template.clone(),
+            name: name.into(),
+            description: None,
+            mime_type: None,
+        }
+    }
+}
+
+fn parse_uri_template_variable(expression: &str) -> Result<&str, UriTemplateError> {
+    const OPERATORS: [char; 12] = ['+', '#', '.', '/', ';', '?', '&', '=', ',', '!', '@', '|'];
+    // operators, lists and modifiers belong to the levels above 1
+    if expression.starts_with(OPERATORS) || expression.contains([',', ':', '*']) {
+        return Err(UriTemplateError::Unsupported(expression.to_owned()));
+    }
+    let valid = !expression.is_empty()
+        && expression
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '%'));
+    if !valid {
+        return Err(UriTemplateError::InvalidVariable(expression.to_owned()));
+    }
+    Ok(expression)
+}
+
+impl FromStr for UriTemplate {
+    type Err = UriTemplateError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        Self::new(template)
+    }
+}
+
+impl fmt::Display for UriTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+/// The values of the variables of a matched [`UriTemplate`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UriParams(HashMap<String, String>);
+
+impl UriParams {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    /// Parse the value of a variable, failing with `invalid_params`
+    pub fn parse<T: FromStr>(&self, name: &str) -> Result<T, crate::Error> {
+        parse_uri_param(&self.0, name)
+    }
+
+    pub fn into_inner(self) -> HashMap<String, String> {
+        self.0
+    }
+}
+
+fn parse_uri_param<T: FromStr>(
+    params: &HashMap<String, String>,
+    name: &str,
+) -> Result<T, crate::Error> {
+    let value = params.get(name).ok_or_else(|| {
+        crate::Error::invalid_params(format!("missing uri parameter {name}"), None)
+    })?;
+    value.parse().map_err(|_| {
+        crate::Error::invalid_params(format!("invalid uri parameter {name}: {value}"), None)
+    })
+}
+
+pub struct ResourceReadContext<'service, S> {
+    request_context: RequestContext<RoleServer>,
+    service: &'service S,
+    uri: String,
+    uri_params: HashMap<String, String>,
+}
+
+impl<'service, S> ResourceReadContext<'service, S> {
+    pub fn new(
+        service: &'service S,
+        ReadResourceRequestParam { uri }: ReadResourceRequestParam,
+        request_context: RequestContext<RoleServer>,
+    ) -> Self {
+        Self {
+            request_context,
+            service,
+            uri,
+            uri_params: HashMap::new(),
+        }
+    }
+    pub fn with_uri_params(mut self, uri_params: HashMap<String, String>) -> Self {
+        self.uri_params = uri_params;
+        self
+    }
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+    /// Parse a parameter extracted from the uri template
+    pub fn uri_param<T: FromStr>(&self, name: &str) -> Result<T, crate::Error> {
+        parse_uri_param(&self.uri_params, name)
+    }
+}
+
+pub trait FromResourceReadContextPart<'a, S>: Sized {
+    fn from_resource_read_context_part(
+        context: ResourceReadContext<'a, S>,
+    ) -> Result<(Self, ResourceReadContext<'a, S>), crate::Error>;
+}
+
+impl<'a, S> FromResourceReadContextPart<'a, S> for &'a S {
+    fn from_resource_read_context_part(
+        context: ResourceReadContext<'a, S>,
+    ) -> Result<(Self, ResourceReadContext<'a, S>), crate::Error> {
+        Ok((context.service, context))
+    }
+}
+
+impl<'a, S> FromResourceReadContextPart<'a, S> for CancellationToken {
+    fn from_resource_read_context_part(
+        context: ResourceReadContext<'a, S>,
+    ) -> Result<(Self, ResourceReadContext<'a, S>), crate::Error> {
+        Ok((context.request_context.ct.clone(), context))
+    }
+}
+
+/// Types that can be converted into a [`ReadResourceResult`]
+///
+/// A plain string is taken as the text content of the requested uri.
+pub trait IntoReadResourceResult {
+    fn into_read_resource_result(
+        self,
+        uri: String,
+        mime_type: Option<String>,
+    ) -> Result<ReadResourceResult, crate::Error>;
+}
+
+impl IntoReadResourceResult for ReadResourceResult {
+    fn into_read_resource_result(
+        self,
+        _uri: String,
+        _mime_type: Option<String>,
+    ) -> Result<ReadResourceResult, crate::Error> {
+        Ok(self)
+    }
+}
+
+impl IntoReadResourceResult for Vec<ResourceContents> {
+    fn into_read_resource_result(
+        self,
+        _uri: String,
+        _mime_type: Option<String>,
+    ) -> Result<ReadResourceResult, crate::Error> {
+        Ok(ReadResourceResult {
+            contents: self,
+            meta: None,
+        })
+    }
+}
+
+impl IntoReadResourceResult for ResourceContents {
+    fn into_read_resource_result(
+        self,
+        uri: String,
+        mime_type: Option<String>,
+    ) -> Result<ReadResourceResult, crate::Error> {
+        vec![self].into_read_resource_result(uri, mime_type)
+    }
+}
+
+impl IntoReadResourceResult for String {
+    fn into_read_resource_result(
+        self,
+        uri: String,
+        mime_type: Option<String>,
+    ) -> Result<ReadResourceResult, crate::Error> {
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri,
+                mime_type: mime_type.or_else(|| Some("text".into())),
+                text: self,
+            }],
+            meta: None,
+        })
+    }
+}
+
+impl<T: IntoReadResourceResult> IntoReadResourceResult for Result<T, crate::Error> {
+    fn into_read_resource_result(
+        self,
+        uri: String,
+        mime_type: Option<String>,
+    ) -> Result<ReadResourceResult, crate::Error> {
+        self.and_then(|value| value.into_read_resource_result(uri, mime_type))
+    }
+}
+
+pub type DynResourceReadHandler<S> = dyn Fn(ResourceReadContext<'_, S>) -> BoxFuture<'_, Result<ReadResourceResult, crate::Error>>
+    + Send
+    + Sync;
+
+pub struct ResourceBoxItem<S> {
+    #[allow(clippy::type_complexity)]
+    pub read: Box<DynResourceReadHandler<S>>,
+    pub attr: ResourceAttr,
+}
+
+impl<S: Send + Sync + 'static + Clone> ResourceBoxItem<S> {
+    pub fn new<R>(attr: ResourceAttr, read: R) -> Self
+    where
+        R: Fn(
+                ResourceReadContext<'_, S>,
+            ) -> BoxFuture<'_, Result<ReadResourceResult, crate::Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self {
+            read: Box::new(read),
+            attr,
+        }
+    }
+    pub fn uri(&self) -> &str {
+        self.attr.uri()
+    }
+}
+
+#[derive(Default)]
+pub struct ResourceBox<S> {
+    pub items: Vec<ResourceBoxItem<S>>,
+}
+
+impl<S> ResourceBox<S> {
+    pub fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+    pub fn add(&mut self, item: ResourceBoxItem<S>) {
+        self.items
+            .retain(|exist| exist.attr.uri() != item.attr.uri());
+        self.items.push(item);
+    }
+
+    pub fn remove(&mut self, uri: &str) {
+        self.items.retain(|item| item.attr.uri() != uri);
+    }
+
+    /// Read a resource, static resources take precedence over templates
+    pub async fn read(
+        &self,
+        context: ResourceReadContext<'_, S>,
+    ) -> Result<ReadResourceResult, crate::Error> {
+        let statics = self
+            .items
+            .iter()
+            .filter(|item| matches!(item.attr, ResourceAttr::Resource(_)));
+        let templates = self
+            .items
+            .iter()
+            .filter(|item| matches!(item.attr, ResourceAttr::Template(_)));
+        for item in statics.chain(templates) {
+            if let Some(uri_params) = item.attr.match_uri(context.uri()) {
+                return (item.read)(context.with_uri_params(uri_params)).await;
+            }
+        }
+        Err(crate::Error::resource_not_found(
+            format!("resource not found: {}", context.uri()),
+            None,
+        ))
+    }
+
+    pub fn list(&self) -> Vec<Resource> {
+        self.items
+            .iter()
+            .filter_map(|item| match &item.attr {
+                ResourceAttr::Resource(resource) => Some(resource.clone()),
+                ResourceAttr::Template(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn list_templates(&self) -> Vec<ResourceTemplate> {
+        self.items
+            .iter()
+            .filter_map(|item| match &item.attr {
+                ResourceAttr::Template(template) => Some(template.clone()),
+                ResourceAttr::Resource(_) => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+#[macro_export]
+macro_rules! resource_box {
+    (@pin_add $callee: ident, $attr: expr, $f: expr) => {
+        $callee.add(ResourceBoxItem::new($attr, |context| Box::pin($f(context))));
+    };
+    ($server: ident { $($(#[$meta: meta])* $resource: ident),* $(,)?} ) => {
+        $crate::resource_box!($server { $($(#[$meta])* $resource),* }  resource_box);
+    };
+    ($server: ident { $($(#[$meta: meta])* $resource: ident),* $(,)?} $resource_box: ident) => {
+        fn $resource_box() -> &'static $crate::handler::server::resource::ResourceBox<$server> {
+            use $crate::handler::server::resource::{ResourceBox, ResourceBoxItem};
+            static RESOURCE_BOX: std::sync::OnceLock<ResourceBox<$server>> = std::sync::OnceLock::new();
+            RESOURCE_BOX.get_or_init(|| {
+                let mut resource_box = ResourceBox::new();
+                $crate::paste!{
+                    $(
+                        $(#[$meta])*
+                        {
+                            $crate::resource_box!(@pin_add resource_box, $server::[< $resource _resource_attr>](), $server::[<$resource _resource_read>]);
+                        }
+                    )*
+                }
+                resource_box
+            })
+        }
+    };
+    (@derive) => {
+        $crate::resource_box!(@derive resource_box);
+    };
+
+    (@derive $resource_box:ident) => {
+        async fn list_resources(
+            &self,
+            _: Option<$crate::model::PaginatedRequestParam>,
+            _: $crate::service::RequestContext<$crate::service::RoleServer>,
+        ) -> Result<$crate::model::ListResourcesResult, $crate::Error> {
+            Ok($crate::model::ListResourcesResult {
+                next_cursor: None,
+                resources: Self::$resource_box().list(),
+                meta: None,
+            })
+        }
+
+        async fn list_resource_templates(
+            &self,
+            _: Option<$crate::model::PaginatedRequestParam>,
+            _: $crate::service::RequestContext<$crate::service::RoleServer>,
+        ) -> Result<$crate::model::ListResourceTemplatesResult, $crate::Error> {
+            Ok($crate::model::ListResourceTemplatesResult {
+                next_cursor: None,
+                resource_templates: Self::$resource_box().list_templates(),
+                meta: None,
+            })
+        }
+
+        async fn read_resource(
+            &self,
+            read_resource_request_param: $crate::model::ReadResourceRequestParam,
+            context: $crate::service::RequestContext<$crate::service::RoleServer>,
+        ) -> Result<$crate::model::ReadResourceResult, $crate::Error> {
+            let context = $crate::handler::server::resource::ResourceReadContext::new(self, read_resource_request_param, context);
+            Self::$resource_box().read(context).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_uri_template() {
+        let params = match_uri_template("file:///{path}", "file:///etc/hosts").unwrap();
+        assert_eq!(params["path"], "etc/hosts");
+
+        let params = match_uri_template(
+            "repo://{owner}/{repo}/readme",
+            "repo://rust-lang/rust/readme",
+        )
+        .unwrap();
+        assert_eq!(params["owner"], "rust-lang");
+        assert_eq!(params["repo"], "rust");
+
+        assert!(match_uri_template("file:///{path}", "file:///").is_none());
+        assert!(match_uri_template("repo://{owner}/readme", "repo://rust/license").is_none());
+        assert!(
+            match_uri_template("config://app", "config://app")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_uri_template_matches() {
+        let template = UriTemplate::new("db://{table}/{id}").unwrap();
+        assert_eq!(template.variables().collect::<Vec<_>>(), ["table", "id"]);
+        let params = template.matches("db://users/42").unwrap();
+        assert_eq!(params.get("table"), Some("users"));
+        assert_eq!(params.parse::<u64>("id").unwrap(), 42);
+        assert_eq!(
+            params.parse::<u64>("table").unwrap_err().code,
+            crate::model::ErrorCode::INVALID_PARAMS
+        );
+        assert!(params.parse::<String>("name").is_err());
+    }
+
+    #[test]
+    fn test_uri_template_percent_encoding() {
+        let template = UriTemplate::new("db://{table}/{id}").unwrap();
+        // an encoded slash stays in its segment
+        let params = template.matches("db://order%20items/a%2Fb").unwrap();
+        assert_eq!(params.get("table"), Some("order items"));
+        assert_eq!(params.get("id"), Some("a/b"));
+        let params = template.matches("db://caf%C3%A9/1").unwrap();
+        assert_eq!(params.get("table"), Some("café"));
+
+        assert!(template.matches("db://users/%2").is_none());
+        assert!(template.matches("db://users/%zz").is_none());
+        assert!(template.matches("db://users/%FF").is_none());
+    }
+
+    #[test]
+    fn test_uri_template_no_match() {
+        let template = UriTemplate::new("db://{table}/{id}").unwrap();
+        assert!(template.matches("db://users").is_none());
+        assert!(template.matches("db://users/").is_none());
+        assert!(template.matches("file:///users/42").is_none());
+
+        let template = UriTemplate::new("file:///{path}").unwrap();
+        assert!(template.matches("file:///").is_none());
+        assert_eq!(
+            template.matches("file:///etc/hosts").unwrap().get("path"),
+            Some("etc/hosts")
+        );
+    }
+
+    #[test]
+    fn test_uri_template_errors() {
+        use UriTemplateError::*;
+        let error = |template: &str| UriTemplate::new(template).unwrap_err();
+        assert_eq!(error("file:///{path"), Unclosed(8));
+        assert_eq!(error("file:///{pa{th}"), Unclosed(8));
+        assert_eq!(error("file:///path}"), UnexpectedClose(12));
+        assert_eq!(error("file:///{+path}"), Unsupported("+path".into()));
+        assert_eq!(error("db://{table,id}"), Unsupported("table,id".into()));
+        assert_eq!(error("db://{table:3}"), Unsupported("table:3".into()));
+        assert_eq!(error("db://{}"), InvalidVariable("".into()));
+        assert_eq!(error("db://{ta-ble}"), InvalidVariable("ta-ble".into()));
+        assert_eq!(error("db://{id}/{id}"), DuplicateVariable("id".into()));
+        assert_eq!(
+            error("db://{table}{id}"),
+            AdjacentVariables("table".into(), "id".into())
+        );
+        assert!("config://app".parse::<UriTemplate>().is_ok());
+    }
+
+    #[test]
+    fn test_uri_template_resource_template() {
+        let template = UriTemplate::new("db://{table}/{id}").unwrap();
+        let resource_template = template.resource_template("rows");
+        assert_eq!(resource_template.uri_template, template.to_string());
+        assert_eq!(resource_template.name, "rows");
+        let advertised = UriTemplate::new(resource_template.uri_template).unwrap();
+        assert!(advertised.matches("db://users/42").is_some());
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use tokio::sync::{Mutex, watch};
+
+use crate::{
+    model::Root,
+    service::{Peer, RoleServer, ServiceError},
+};
+
+/// A cached view of the client's roots
+///
+/// Installed with [`ServerHandler::with_roots_watcher`](super::ServerHandler::with_roots_watcher),
+/// it fetches `roots/list` once the client is initialized and again on every
+/// `notifications/roots/list_changed`. Tools can reach it through an `Extension<RootsWatcher>` argument.
+#[derive(Clone)]
+pub struct RootsWatcher {
+    roots: Arc<watch::Sender<Vec<Root>>>,
+    peer: Arc<std::sync::RwLock<Option<Peer<RoleServer>>>>,
+    // refreshes publish in order, an older listing never overwrites a newer one
+    refreshing: Arc<Mutex<()>>,
+}
+
+impl Default for RootsWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for RootsWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RootsWatcher")
+            .field("roots", &*self.roots.borrow())
+            .finish()
+    }
+}
+
+impl RootsWatcher {
+    pub fn new() -> Self {
+        Self {
+            roots: Arc::new(watch::channel(Vec::new()).0),
+            peer: Default::default(),
+            refreshing: Default::default(),
+        }
+    }
+
+    /// The roots of the last listing, empty before the first one
+    pub fn current(&self) -> Vec<Root> {
+        self.roots.borrow().clone()
+    }
+
+    /// Wait for the next listing and return its roots
+    ///
+    /// Listings published after this call resolve the future, even if it is polled later.
+    pub fn changed(&self) -> impl Future<Output = Vec<Root>> + Send + 'static {
+        let mut receiver = self.roots.subscribe();
+        async move {
+            // only fails once every watcher is dropped, the last roots are still valid then
+            let _ = receiver.changed().await;
+            receiver.borrow_and_update().clone()
+        }
+    }
+
+    /// List the roots from the client and publish them
+    ///
+    /// Does nothing if no peer is bound or the client didn't advertise the roots capability.
+    pub async fn refresh(&self) -> Result<Vec<Root>, ServiceError> {
+        let peer = self
+            .peer
+            .read()
+            .expect("roots watcher lock poisoned")
+            .clone();
+        let Some(peer) = peer else {
+            return Ok(self.current());
+        };
+        if !peer.peer_info().capabilities.supports_roots() {
+            return Ok(self.current());
+        }
+        let _guard = self.refreshing.lock().await;
+        let roots = peer.list_roots().await?.roots;
+        self.roots.send_replace(roots.clone());
+        Ok(roots)
+    }
+
+    /// List the roots from `peer` from now on
+    pub fn bind_peer(&self, peer: Peer<RoleServer>) {
+        *self.peer.write().expect("roots watcher lock poisoned") = Some(peer);
+    }
+
+    pub(crate) fn spawn_refresh(&self) {
+        let watcher = self.clone();
+        tokio::spawn(async move {
+            if let Err(error) = watcher.refresh().await {
+                tracing::warn!("failed to refresh roots: {error}");
+            }
+        });
+    }
+}
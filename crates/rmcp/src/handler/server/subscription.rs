@@ -0,0 +1,129 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    model::ResourceUpdatedNotificationParam,
+    service::{Peer, RoleServer},
+};
+
+struct Subscriber {
+    peer: Peer<RoleServer>,
+    uris: HashSet<String>,
+}
+
+/// Tracks the resources each session subscribed to
+///
+/// Installed with [`ServerHandler::with_subscriptions`](super::ServerHandler::with_subscriptions),
+/// it answers `resources/subscribe` and `resources/unsubscribe` and advertises the subscribe
+/// capability. Clones share the subscriptions, so one manager can serve every session of a server
+/// and [`notify_updated`](SubscriptionManager::notify_updated) reaches all of them.
+///
+/// Sessions whose transport closed are dropped with their subscriptions.
+#[derive(Clone, Default)]
+pub struct SubscriptionManager {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl std::fmt::Debug for SubscriptionManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubscriptionManager")
+            .field("sessions", &self.lock().len())
+            .finish()
+    }
+}
+
+impl SubscriptionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe the session of `peer` to `uri`, subscribing twice is the same as once
+    pub fn subscribe(&self, peer: &Peer<RoleServer>, uri: impl Into<String>) {
+        let mut subscribers = self.lock();
+        subscribers.retain(|subscriber| !subscriber.peer.is_transport_closed());
+        let uri = uri.into();
+        match subscribers
+            .iter_mut()
+            .find(|subscriber| subscriber.peer.is_same_session(peer))
+        {
+            Some(subscriber) => {
+                subscriber.uris.insert(uri);
+            }
+            None => subscribers.push(Subscriber {
+                peer: peer.clone(),
+                uris: HashSet::from([uri]),
+            }),
+        }
+    }
+
+    /// Unsubscribe the session of `peer` from `uri`, returns whether it was subscribed
+    pub fn unsubscribe(&self, peer: &Peer<RoleServer>, uri: &str) -> bool {
+        let mut subscribers = self.lock();
+        let Some(index) = subscribers
+            .iter()
+            .position(|subscriber| subscriber.peer.is_same_session(peer))
+        else {
+            return false;
+        };
+        let removed = subscribers[index].uris.remove(uri);
+        if subscribers[index].uris.is_empty() {
+            subscribers.swap_remove(index);
+        }
+        removed
+    }
+
+    /// Drop every subscription of the session of `peer`
+    pub fn remove_session(&self, peer: &Peer<RoleServer>) {
+        self.lock()
+            .retain(|subscriber| !subscriber.peer.is_same_session(peer));
+    }
+
+    /// The number of open sessions subscribed to `uri`
+    pub fn subscriber_count(&self, uri: &str) -> usize {
+        self.lock()
+            .iter()
+            .filter(|subscriber| {
+                !subscriber.peer.is_transport_closed() && subscriber.uris.contains(uri)
+            })
+            .count()
+    }
+
+    /// Send `notifications/resources/updated` for `uri` to every subscribed session
+    ///
+    /// Returns the number of sessions notified, sessions that went away meanwhile are dropped.
+    pub async fn notify_updated(&self, uri: &str) -> usize {
+        let peers = {
+            let mut subscribers = self.lock();
+            subscribers.retain(|subscriber| !subscriber.peer.is_transport_closed());
+            subscribers
+                .iter()
+                .filter(|subscriber| subscriber.uris.contains(uri))
+                .map(|subscriber| subscriber.peer.clone())
+                .collect::<Vec<_>>()
+        };
+        let mut notified = 0;
+        for peer in peers {
+            let result = peer
+                .notify_resource_updated(ResourceUpdatedNotificationParam {
+                    uri: uri.to_string(),
+                })
+                .await;
+            match result {
+                Ok(()) => notified += 1,
+                Err(error) => {
+                    tracing::debug!(uri, "dropping subscriptions of a closed session: {error}");
+                    self.remove_session(&peer);
+                }
+            }
+        }
+        notified
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Subscriber>> {
+        self.subscribers
+            .lock()
+            .expect("subscription manager lock poisoned")
+    }
+}
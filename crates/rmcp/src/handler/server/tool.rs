@@ -1,56 +1,335 @@
 use std::{
-    any::TypeId, borrow::Cow, collections::HashMap, future::Ready, marker::PhantomData, sync::Arc,
+    any::TypeId,
+    borrow::Cow,
+    collections::HashMap,
+    future::Ready,
+    marker::PhantomData,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
 use futures::future::BoxFuture;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tokio::sync::{AcquireError, OwnedSemaphorePermit, Semaphore, TryAcquireError};
 use tokio_util::sync::CancellationToken;
 
+use super::list_changed::ListChangedNotifier;
 use crate::{
     RoleServer,
-    model::{CallToolRequestParam, CallToolResult, ConstString, IntoContents, JsonObject},
-    service::RequestContext,
+    model::{
+        CallToolRequestParam, CallToolResult, ClientCapabilities, ClientInfo, ConstString,
+        Implementation, IntoContents, JsonObject, ListToolsResult, LoggingLevel,
+        LoggingMessageNotificationParam, Meta, PaginatedRequestParam, ProgressNotificationParam,
+        ProgressToken, ProtocolVersion, RequestId, ServerCapabilities, ToolArguments,
+    },
+    service::{Peer, RequestContext, ServiceError},
 };
+/// How the JSON schemas of tool parameters and results are generated
+///
+/// The defaults keep `$ref`s to the `definitions` of the schema and every `format` emitted by
+/// schemars. Some clients can't resolve references or reject formats like `uint32`, a tool can
+/// opt out with `#[tool(schema(inline_refs, strip_formats))]`, or every tool with
+/// [`SchemaSettings::set_global`].
+///
+/// ```rust
+/// # use rmcp::handler::server::tool::SchemaSettings;
+/// SchemaSettings::set_global(SchemaSettings::new().with_inline_refs(true));
+/// assert!(SchemaSettings::global().inline_refs);
+/// # SchemaSettings::set_global(SchemaSettings::new());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub struct SchemaSettings {
+    /// Inline every subschema instead of referencing it, and drop the emptied `definitions`
+    ///
+    /// The schemas of recursive types keep their references.
+    pub inline_refs: bool,
+    /// Remove the `format`s which are not defined by JSON Schema, like `uint32` or `double`
+    pub strip_formats: bool,
+}
+
+static GLOBAL_SCHEMA_SETTINGS: std::sync::RwLock<SchemaSettings> =
+    std::sync::RwLock::new(SchemaSettings::new());
+
+impl SchemaSettings {
+    pub const fn new() -> Self {
+        Self {
+            inline_refs: false,
+            strip_formats: false,
+        }
+    }
+
+    pub const fn with_inline_refs(mut self, inline_refs: bool) -> Self {
+        self.inline_refs = inline_refs;
+        self
+    }
+
+    pub const fn with_strip_formats(mut self, strip_formats: bool) -> Self {
+        self.strip_formats = strip_formats;
+        self
+    }
+
+    /// The settings of the tools without a `#[tool(schema(...))]` of their own
+    pub fn global() -> Self {
+        *GLOBAL_SCHEMA_SETTINGS
+            .read()
+            .expect("schema settings lock poisoned")
+    }
+
+    /// Replace the [global](SchemaSettings::global) settings
+    ///
+    /// Schemas are generated when the tools are listed, set them before serving.
+    pub fn set_global(settings: Self) {
+        *GLOBAL_SCHEMA_SETTINGS
+            .write()
+            .expect("schema settings lock poisoned") = settings;
+    }
+}
+
+// the formats of the JSON Schema specification, others are schemars' own
+const STANDARD_FORMATS: &[&str] = &[
+    "date-time",
+    "date",
+    "time",
+    "duration",
+    "email",
+    "idn-email",
+    "hostname",
+    "idn-hostname",
+    "ipv4",
+    "ipv6",
+    "uri",
+    "uri-reference",
+    "iri",
+    "iri-reference",
+    "uuid",
+    "uri-template",
+    "json-pointer",
+    "relative-json-pointer",
+    "regex",
+];
+
+#[derive(Debug, Clone)]
+struct StripFormats;
+
+impl schemars::visit::Visitor for StripFormats {
+    fn visit_schema_object(&mut self, schema: &mut schemars::schema::SchemaObject) {
+        if schema
+            .format
+            .as_deref()
+            .is_some_and(|format| !STANDARD_FORMATS.contains(&format))
+        {
+            schema.format = None;
+        }
+        schemars::visit::visit_schema_object(self, schema);
+    }
+}
+
 /// A shortcut for generating a JSON schema for a type.
+///
+/// Doc comments on a `#[derive(JsonSchema)]` type become the schema's top-level
+/// `description`, and doc comments on its fields become property descriptions.
 pub fn schema_for_type<T: JsonSchema>() -> JsonObject {
-    let mut settings = schemars::r#gen::SchemaSettings::default();
-    settings.option_nullable = true;
-    settings.option_add_null_type = false;
-    settings.definitions_path = "#/components/schemas/".to_owned();
-    settings.meta_schema = None;
-    settings.visitors = Vec::default();
-    settings.inline_subschemas = false;
-    let generator = settings.into_generator();
+    schema_for_type_with::<T>(SchemaSettings::global())
+}
+
+/// Generate the JSON schema of a type with `settings`
+pub fn schema_for_type_with<T: JsonSchema>(settings: SchemaSettings) -> JsonObject {
+    let mut generator_settings = schemars::r#gen::SchemaSettings::default();
+    generator_settings.option_nullable = true;
+    generator_settings.option_add_null_type = false;
+    generator_settings.definitions_path = "#/components/schemas/".to_owned();
+    generator_settings.meta_schema = None;
+    generator_settings.visitors = Vec::default();
+    if settings.strip_formats {
+        generator_settings.visitors.push(Box::new(StripFormats));
+    }
+    generator_settings.inline_subschemas = settings.inline_refs;
+    let generator = generator_settings.into_generator();
     let schema = generator.into_root_schema_for::<T>();
     let object = serde_json::to_value(schema).expect("failed to serialize schema");
-    match object {
+    let mut object = match object {
         serde_json::Value::Object(object) => object,
         _ => panic!("unexpected schema value"),
+    };
+    if object
+        .get("definitions")
+        .and_then(serde_json::Value::as_object)
+        .is_some_and(serde_json::Map::is_empty)
+    {
+        object.remove("definitions");
     }
+    object
 }
 
 /// Call [`schema_for_type`] with a cache
 pub fn cached_schema_for_type<T: JsonSchema + std::any::Any>() -> Arc<JsonObject> {
-    thread_local! {
-        static CACHE_FOR_TYPE: std::sync::RwLock<HashMap<TypeId, Arc<JsonObject>>> = Default::default();
-    };
-    CACHE_FOR_TYPE.with(|cache| {
-        if let Some(x) = cache
+    cached_schema_for_type_with::<T>(SchemaSettings::global())
+}
+
+/// Call [`schema_for_type_with`] with the [global](SchemaCache::global) cache, the schemas of a
+/// type with different settings are cached apart
+pub fn cached_schema_for_type_with<T: JsonSchema + std::any::Any>(
+    settings: SchemaSettings,
+) -> Arc<JsonObject> {
+    SchemaCache::global().get_or_generate::<T>(settings)
+}
+
+/// Generate the schema of `T` with the global settings into the cache, so that the first
+/// `tools/list` doesn't pay for it
+pub fn precompute<T: JsonSchema + std::any::Any>() {
+    precompute_with::<T>(SchemaSettings::global());
+}
+
+/// Generate the schema of `T` with `settings` into the cache, like a tool with
+/// `#[tool(schema(...))]` would
+pub fn precompute_with<T: JsonSchema + std::any::Any>(settings: SchemaSettings) {
+    SchemaCache::global().get_or_generate::<T>(settings);
+}
+
+/// The schemas of types, by type and [`SchemaSettings`]
+///
+/// The schemas are shared, every tool of a type gets the same [`Arc`]. The cache is unbounded
+/// unless given a capacity, then the least recently used schemas are evicted.
+///
+/// ```rust
+/// # use rmcp::handler::server::tool::{SchemaCache, SchemaSettings};
+/// let cache = SchemaCache::new().with_capacity(Some(1));
+/// let first = cache.get_or_generate::<String>(SchemaSettings::new());
+/// cache.get_or_generate::<u32>(SchemaSettings::new());
+/// assert_eq!(cache.len(), 1);
+/// // generated again
+/// let second = cache.get_or_generate::<String>(SchemaSettings::new());
+/// assert!(!std::sync::Arc::ptr_eq(&first, &second));
+/// ```
+#[derive(Debug, Default)]
+pub struct SchemaCache {
+    entries: std::sync::RwLock<HashMap<(TypeId, SchemaSettings), SchemaCacheEntry>>,
+    capacity: std::sync::RwLock<Option<usize>>,
+    // ticks on each use, the entry with the oldest tick is evicted first
+    clock: AtomicU64,
+}
+
+#[derive(Debug)]
+struct SchemaCacheEntry {
+    schema: Arc<JsonObject>,
+    last_used: AtomicU64,
+}
+
+impl SchemaCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cache of [`cached_schema_for_type`], shared by every thread
+    pub fn global() -> &'static SchemaCache {
+        static CACHE: std::sync::OnceLock<SchemaCache> = std::sync::OnceLock::new();
+        CACHE.get_or_init(SchemaCache::new)
+    }
+
+    /// Keep at most `capacity` schemas, `None` for no limit
+    pub fn with_capacity(self, capacity: Option<usize>) -> Self {
+        self.set_capacity(capacity);
+        self
+    }
+
+    /// Keep at most `capacity` schemas, evicting the least recently used ones over it
+    pub fn set_capacity(&self, capacity: Option<usize>) {
+        *self.capacity.write().expect("schema cache lock poisoned") = capacity;
+        self.evict(&mut self.entries.write().expect("schema cache lock poisoned"));
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        *self.capacity.read().expect("schema cache lock poisoned")
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
             .read()
             .expect("schema cache lock poisoned")
-            .get(&TypeId::of::<T>())
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries
+            .write()
+            .expect("schema cache lock poisoned")
+            .clear();
+    }
+
+    /// The cached schema of `T` with `settings`, generated on a miss
+    ///
+    /// Schemas are generated without holding the lock, when several threads miss at once the
+    /// first schema inserted is the one they all get.
+    pub fn get_or_generate<T: JsonSchema + std::any::Any>(
+        &self,
+        settings: SchemaSettings,
+    ) -> Arc<JsonObject> {
+        let key = (TypeId::of::<T>(), settings);
+        if let Some(entry) = self
+            .entries
+            .read()
+            .expect("schema cache lock poisoned")
+            .get(&key)
         {
-            x.clone()
-        } else {
-            let schema = schema_for_type::<T>();
-            let schema = Arc::new(schema);
-            cache
-                .write()
-                .expect("schema cache lock poisoned")
-                .insert(TypeId::of::<T>(), schema.clone());
-            schema
+            entry.last_used.store(self.tick(), Ordering::Relaxed);
+            return entry.schema.clone();
+        }
+        let schema = Arc::new(schema_for_type_with::<T>(settings));
+        let mut entries = self.entries.write().expect("schema cache lock poisoned");
+        let schema = entries
+            .entry(key)
+            .or_insert_with(|| SchemaCacheEntry {
+                schema,
+                last_used: AtomicU64::new(0),
+            })
+            .schema
+            .clone();
+        entries[&key]
+            .last_used
+            .store(self.tick(), Ordering::Relaxed);
+        self.evict(&mut entries);
+        schema
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn evict(&self, entries: &mut HashMap<(TypeId, SchemaSettings), SchemaCacheEntry>) {
+        let Some(capacity) = self.capacity() else {
+            return;
+        };
+        while entries.len() > capacity {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+            entries.remove(&oldest);
         }
+    }
+}
+
+/// Deserialize a JSON value into a type, which may borrow from the value
+pub fn parse_json_value<'de, T: Deserialize<'de>>(
+    input: &'de serde_json::Value,
+) -> Result<T, crate::Error> {
+    T::deserialize(input).map_err(|e| {
+        crate::Error::invalid_params(
+            format!("failed to deserialize parameters: {error}", error = e),
+            None,
+        )
     })
 }
 
@@ -63,11 +342,125 @@ pub fn parse_json_object<T: DeserializeOwned>(input: JsonObject) -> Result<T, cr
         )
     })
 }
+
+/// Deserialize the arguments of a tool call into a type, from their text when they have one
+pub fn parse_tool_arguments<T: DeserializeOwned>(input: ToolArguments) -> Result<T, crate::Error> {
+    input.deserialize_into().map_err(|e| {
+        crate::Error::invalid_params(
+            format!("failed to deserialize parameters: {error}", error = e),
+            None,
+        )
+    })
+}
+
+/// Deserialize the arguments of a tool call into a type, which may borrow from the arguments
+pub fn parse_borrowed_tool_arguments<'de, T: Deserialize<'de>>(
+    input: &'de ToolArguments,
+) -> Result<T, crate::Error> {
+    input.deserialize().map_err(|e| {
+        crate::Error::invalid_params(
+            format!("failed to deserialize parameters: {error}", error = e),
+            None,
+        )
+    })
+}
+enum ServiceRef<'service, S> {
+    Shared(&'service S),
+    Exclusive(&'service mut S),
+    Taken,
+}
+
+/// Check that individual parameters don't collide with the fields of a flattened aggregated parameter
+pub fn check_flattened_fields<T: JsonSchema + std::any::Any>(
+    params: &[&str],
+) -> Result<(), crate::Error> {
+    let schema = cached_schema_for_type::<T>();
+    let Some(properties) = schema
+        .get("properties")
+        .and_then(serde_json::Value::as_object)
+    else {
+        return Ok(());
+    };
+    match params.iter().find(|param| properties.contains_key(**param)) {
+        Some(param) => Err(crate::Error::invalid_params(
+            format!(
+                "parameter `{param}` conflicts with a field of flattened parameter `{}`",
+                std::any::type_name::<T>()
+            ),
+            None,
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The tools of the servers of type `S`, built by `build` on the first call
+///
+/// The `#[tool(tool_box)]` of a generic server lists its tools with it, a `static` in a generic
+/// function being shared by all its types.
+pub fn cached_tools<S: 'static>(
+    build: impl FnOnce() -> Vec<crate::model::Tool>,
+) -> Arc<Vec<crate::model::Tool>> {
+    type ToolsByType = std::sync::RwLock<HashMap<TypeId, Arc<Vec<crate::model::Tool>>>>;
+    static TOOLS: std::sync::OnceLock<ToolsByType> = std::sync::OnceLock::new();
+    let cache = TOOLS.get_or_init(Default::default);
+    if let Some(tools) = cache
+        .read()
+        .expect("tools cache lock poisoned")
+        .get(&TypeId::of::<S>())
+    {
+        return tools.clone();
+    }
+    let tools = Arc::new(build());
+    cache
+        .write()
+        .expect("tools cache lock poisoned")
+        .entry(TypeId::of::<S>())
+        .or_insert(tools)
+        .clone()
+}
+
+/// Take the page of tools starting at `cursor`, tools are sorted by name so pages are stable
+///
+/// Only the tools of the page are cloned.
+pub fn paginate_tools<'a>(
+    tools: impl IntoIterator<Item = &'a crate::model::Tool>,
+    cursor: Option<&str>,
+    page_size: usize,
+) -> Result<ListToolsResult, crate::Error> {
+    use base64::engine::{Engine, general_purpose::URL_SAFE_NO_PAD};
+    let mut tools = tools.into_iter().collect::<Vec<_>>();
+    let offset = match cursor {
+        Some(cursor) => URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|offset| String::from_utf8(offset).ok())
+            .and_then(|offset| offset.parse::<usize>().ok())
+            .filter(|offset| *offset < tools.len())
+            .ok_or_else(|| crate::Error::invalid_params("invalid cursor", None))?,
+        None => 0,
+    };
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    let end = offset.saturating_add(page_size).min(tools.len());
+    let next_cursor = (end < tools.len()).then(|| URL_SAFE_NO_PAD.encode(end.to_string()));
+    Ok(ListToolsResult {
+        next_cursor,
+        tools: tools[offset..end]
+            .iter()
+            .map(|&tool| tool.clone())
+            .collect(),
+        meta: None,
+    })
+}
+
+/// Marks a tool call to a server that advertises the logging capability
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LoggingEnabled;
+
 pub struct ToolCallContext<'service, S> {
     request_context: RequestContext<RoleServer>,
-    service: &'service S,
+    service: ServiceRef<'service, S>,
     name: Cow<'static, str>,
-    arguments: Option<JsonObject>,
+    arguments: Option<ToolArguments>,
 }
 
 impl<'service, S> ToolCallContext<'service, S> {
@@ -78,7 +471,20 @@ impl<'service, S> ToolCallContext<'service, S> {
     ) -> Self {
         Self {
             request_context,
-            service,
+            service: ServiceRef::Shared(service),
+            name,
+            arguments,
+        }
+    }
+    /// Create a context with exclusive access to the service, required by `&mut self` tools
+    pub fn new_mut(
+        service: &'service mut S,
+        CallToolRequestParam { name, arguments }: CallToolRequestParam,
+        request_context: RequestContext<RoleServer>,
+    ) -> Self {
+        Self {
+            request_context,
+            service: ServiceRef::Exclusive(service),
             name,
             arguments,
         }
@@ -86,6 +492,78 @@ impl<'service, S> ToolCallContext<'service, S> {
     pub fn name(&self) -> &str {
         &self.name
     }
+    pub fn arguments(&self) -> Option<&JsonObject> {
+        self.arguments.as_deref()
+    }
+    /// Warn the client that the called tool is deprecated, see `#[tool(deprecated = "..")]`
+    ///
+    /// The warning is sent as a `notifications/message` only if the server advertises the logging capability.
+    pub async fn warn_deprecated(&self, message: &str) {
+        if self
+            .request_context
+            .extensions
+            .get::<LoggingEnabled>()
+            .is_none()
+        {
+            return;
+        }
+        let notification = LoggingMessageNotificationParam {
+            level: LoggingLevel::Warning,
+            logger: Some("rmcp".to_string()),
+            data: serde_json::json!({
+                "message": format!("tool `{}` is deprecated: {message}", self.name),
+                "tool": self.name,
+            }),
+        };
+        if let Err(error) = self
+            .request_context
+            .peer
+            .notify_logging_message(notification)
+            .await
+        {
+            tracing::warn!("failed to send deprecation warning: {error}");
+        }
+    }
+    /// Peek at the service without taking it out of the context
+    pub fn service_ref(&self) -> Option<&S> {
+        match &self.service {
+            ServiceRef::Shared(service) => Some(*service),
+            ServiceRef::Exclusive(service) => Some(&**service),
+            ServiceRef::Taken => None,
+        }
+    }
+    /// Borrow the service, an exclusive borrow is downgraded to a shared one
+    pub fn service(&mut self) -> Result<&'service S, crate::Error> {
+        let service: &'service S = match std::mem::replace(&mut self.service, ServiceRef::Taken) {
+            ServiceRef::Shared(service) => service,
+            ServiceRef::Exclusive(service) => service,
+            ServiceRef::Taken => {
+                return Err(crate::Error::internal_error(
+                    "service is already mutably borrowed",
+                    None,
+                ));
+            }
+        };
+        self.service = ServiceRef::Shared(service);
+        Ok(service)
+    }
+    /// Take the exclusive borrow of the service
+    pub fn service_mut(&mut self) -> Result<&'service mut S, crate::Error> {
+        match std::mem::replace(&mut self.service, ServiceRef::Taken) {
+            ServiceRef::Exclusive(service) => Ok(service),
+            ServiceRef::Shared(service) => {
+                self.service = ServiceRef::Shared(service);
+                Err(crate::Error::internal_error(
+                    "tool requires mutable access to the service, serve it with `Locked`",
+                    None,
+                ))
+            }
+            ServiceRef::Taken => Err(crate::Error::internal_error(
+                "service is already mutably borrowed",
+                None,
+            )),
+        }
+    }
 }
 
 pub trait FromToolCallContextPart<'a, S>: Sized {
@@ -94,14 +572,20 @@ pub trait FromToolCallContextPart<'a, S>: Sized {
     ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error>;
 }
 
+/// The values a tool can return
+///
+/// - any [`IntoContents`]: `()`, `String`, `&str`, `serde_json::Value`, [`Content`] and
+///   `Vec<Content>`, as a successful result
+/// - `Result<T, E>` with `T: IntoContents` and `E: Display`, the error is a failed result with
+///   its text, which the model sees and can recover from
+/// - [`CallToolResult`] as is, and `Result<CallToolResult, rmcp::Error>` whose error is a
+///   protocol error
+/// - [`Json<T>`](super::wrapper::Json) and `Result<Json<T>, E>`, as structured content
+///
+/// [`Content`]: crate::model::Content
 pub trait IntoCallToolResult {
     fn into_call_tool_result(self) -> Result<CallToolResult, crate::Error>;
 }
-impl IntoCallToolResult for () {
-    fn into_call_tool_result(self) -> Result<CallToolResult, crate::Error> {
-        Ok(CallToolResult::success(vec![]))
-    }
-}
 
 impl<T: IntoContents> IntoCallToolResult for T {
     fn into_call_tool_result(self) -> Result<CallToolResult, crate::Error> {
@@ -109,11 +593,11 @@ impl<T: IntoContents> IntoCallToolResult for T {
     }
 }
 
-impl<T: IntoContents, E: IntoContents> IntoCallToolResult for Result<T, E> {
+impl<T: IntoContents, E: std::fmt::Display> IntoCallToolResult for Result<T, E> {
     fn into_call_tool_result(self) -> Result<CallToolResult, crate::Error> {
         match self {
             Ok(value) => Ok(CallToolResult::success(value.into_contents())),
-            Err(error) => Ok(CallToolResult::error(error.into_contents())),
+            Err(error) => Ok(CallToolResult::error_text(error)),
         }
     }
 }
@@ -159,49 +643,620 @@ impl IntoCallToolResult for Result<CallToolResult, crate::Error> {
     }
 }
 
-pub trait CallToolHandler<'a, S, A> {
-    type Fut: Future<Output = Result<CallToolResult, crate::Error>> + Send + 'a;
-    fn call(self, context: ToolCallContext<'a, S>) -> Self::Fut;
-}
+impl IntoCallToolResult for CallToolResult {
+    fn into_call_tool_result(self) -> Result<CallToolResult, crate::Error> {
+        Ok(self)
+    }
+}
+
+/// Whether no name appears twice across the given lists of tool names
+pub const fn tool_names_are_unique(names: &[&[&str]]) -> bool {
+    const fn str_eq(a: &str, b: &str) -> bool {
+        let (a, b) = (a.as_bytes(), b.as_bytes());
+        if a.len() != b.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+    let mut list = 0;
+    while list < names.len() {
+        let mut index = 0;
+        while index < names[list].len() {
+            let name = names[list][index];
+            // compare with every name after this one
+            let mut other_list = list;
+            let mut other_index = index + 1;
+            while other_list < names.len() {
+                while other_index < names[other_list].len() {
+                    if str_eq(name, names[other_list][other_index]) {
+                        return false;
+                    }
+                    other_index += 1;
+                }
+                other_list += 1;
+                other_index = 0;
+            }
+            index += 1;
+        }
+        list += 1;
+    }
+    true
+}
+
+/// Check the structured content of a tool result against the output schema of `T`
+///
+/// Only checked with the `schema-validation` feature, a mismatch becomes an internal error.
+pub fn validate_structured_content<T: JsonSchema + std::any::Any>(
+    result: Result<CallToolResult, crate::Error>,
+) -> Result<CallToolResult, crate::Error> {
+    #[cfg(feature = "schema-validation")]
+    if let Ok(CallToolResult {
+        structured_content: Some(value),
+        ..
+    }) = &result
+    {
+        let validator = cached_validator_for_type::<T>()?;
+        let errors = validator
+            .iter_errors(value)
+            .map(|error| format!("{}: {error}", error.instance_path))
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return Err(crate::Error::internal_error(
+                "structured content doesn't match the output schema",
+                Some(serde_json::json!({ "errors": errors })),
+            ));
+        }
+    }
+    result
+}
+
+#[cfg(feature = "schema-validation")]
+fn cached_validator_for_type<T: JsonSchema + std::any::Any>()
+-> Result<Arc<jsonschema::Validator>, crate::Error> {
+    type Validators =
+        std::sync::RwLock<HashMap<(TypeId, SchemaSettings), Arc<jsonschema::Validator>>>;
+    // shared by every thread, a validator is costly to build
+    static CACHE_FOR_TYPE: std::sync::OnceLock<Validators> = std::sync::OnceLock::new();
+    let cache = CACHE_FOR_TYPE.get_or_init(Default::default);
+    let key = (TypeId::of::<T>(), SchemaSettings::global());
+    if let Some(validator) = cache
+        .read()
+        .expect("validator cache lock poisoned")
+        .get(&key)
+    {
+        return Ok(validator.clone());
+    }
+    let schema =
+        crate::model::resolvable_schema(cached_schema_for_type_with::<T>(key.1).as_ref().clone());
+    let validator = jsonschema::validator_for(&schema)
+        .map_err(|e| crate::Error::internal_error(format!("invalid output schema: {e}"), None))?;
+    let validator = Arc::new(validator);
+    cache
+        .write()
+        .expect("validator cache lock poisoned")
+        .insert(key, validator.clone());
+    Ok(validator)
+}
+
+/// Convert the result of a tool, routing its error through `#[tool(on_error = ..)]`
+pub fn map_tool_error<T, E, F>(
+    result: Result<T, E>,
+    on_error: F,
+) -> Result<CallToolResult, crate::Error>
+where
+    T: IntoCallToolResult,
+    F: FnOnce(E) -> Result<CallToolResult, crate::Error>,
+{
+    match result {
+        Ok(value) => value.into_call_tool_result(),
+        Err(error) => on_error(error),
+    }
+}
+
+/// Hooks around every tool call, installed with [`ServerHandler::with_tool_middleware`]
+///
+/// Middlewares run in the order they were installed before the call, and in reverse order after it.
+/// Tools of a server served as [`Locked`] see the middlewares installed for the inner server type.
+///
+/// [`ServerHandler::with_tool_middleware`]: crate::ServerHandler::with_tool_middleware
+#[allow(unused_variables)]
+pub trait ToolMiddleware<S>: Send + Sync + 'static {
+    /// Called before the tool, an error skips the tool and the remaining middlewares
+    fn before(&self, context: &ToolCallContext<'_, S>) -> Result<(), crate::Error> {
+        Ok(())
+    }
+    /// Called with the result of the tool, which may be rewritten
+    ///
+    /// Only called if `before` of this middleware succeeded.
+    fn after(
+        &self,
+        name: &str,
+        result: &mut Result<CallToolResult, crate::Error>,
+        elapsed: Duration,
+    ) {
+    }
+}
+
+/// The middleware stack of a service, carried in the request extensions
+pub struct ToolMiddlewares<S>(Arc<Vec<Arc<dyn ToolMiddleware<S>>>>);
+
+impl<S> Clone for ToolMiddlewares<S> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<S> Default for ToolMiddlewares<S> {
+    fn default() -> Self {
+        Self(Arc::new(Vec::new()))
+    }
+}
+
+impl<S> ToolMiddlewares<S> {
+    /// A stack with `middleware` added on the inside
+    pub fn push(&self, middleware: impl ToolMiddleware<S>) -> Self {
+        let mut stack = self.0.as_ref().clone();
+        stack.push(Arc::new(middleware));
+        Self(Arc::new(stack))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Call a tool through the middlewares installed for the service
+pub async fn call_with_middleware<'s, S, F, Fut>(
+    context: ToolCallContext<'s, S>,
+    call: F,
+) -> Result<CallToolResult, crate::Error>
+where
+    S: 'static,
+    F: FnOnce(ToolCallContext<'s, S>) -> Fut,
+    Fut: Future<Output = Result<CallToolResult, crate::Error>>,
+{
+    let Some(ToolMiddlewares(stack)) = context
+        .request_context
+        .extensions
+        .get::<ToolMiddlewares<S>>()
+        .cloned()
+    else {
+        return call(context).await;
+    };
+    let name = context.name.clone();
+    let start = Instant::now();
+    let mut entered = 0;
+    let mut result = Ok(());
+    for middleware in stack.iter() {
+        result = middleware.before(&context);
+        if result.is_err() {
+            break;
+        }
+        entered += 1;
+    }
+    let mut result = match result {
+        Ok(()) => call(context).await,
+        Err(error) => Err(error),
+    };
+    let elapsed = start.elapsed();
+    for middleware in stack[..entered].iter().rev() {
+        middleware.after(&name, &mut result, elapsed);
+    }
+    result
+}
+
+/// The default timeout of tool calls, installed with [`ServerHandler::with_tool_timeout`]
+///
+/// [`ServerHandler::with_tool_timeout`]: crate::ServerHandler::with_tool_timeout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolTimeout(pub Duration);
+
+/// Call a tool with a deadline, `timeout` of `#[tool(timeout_ms = ..)]` or else the [`ToolTimeout`] of the service
+///
+/// When the deadline passes, the request's cancellation token is cancelled so tasks of the tool can clean up.
+pub async fn call_with_timeout<'s, S, F, Fut>(
+    context: ToolCallContext<'s, S>,
+    timeout: Option<Duration>,
+    call: F,
+) -> Result<CallToolResult, crate::Error>
+where
+    F: FnOnce(ToolCallContext<'s, S>) -> Fut,
+    Fut: Future<Output = Result<CallToolResult, crate::Error>>,
+{
+    let timeout = timeout.or_else(|| {
+        context
+            .request_context
+            .extensions
+            .get::<ToolTimeout>()
+            .map(|ToolTimeout(timeout)| *timeout)
+    });
+    let Some(timeout) = timeout else {
+        return call(context).await;
+    };
+    let name = context.name.clone();
+    let ct = context.request_context.ct.clone();
+    let start = Instant::now();
+    match tokio::time::timeout(timeout, call(context)).await {
+        Ok(result) => result,
+        Err(_) => {
+            ct.cancel();
+            Err(crate::Error::internal_error(
+                "tool timed out",
+                Some(serde_json::json!({
+                    "tool": name,
+                    "elapsed_ms": start.elapsed().as_millis() as u64,
+                    "timeout_ms": timeout.as_millis() as u64,
+                })),
+            ))
+        }
+    }
+}
+
+/// What happens to a tool call over a concurrency limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConcurrencyPolicy {
+    /// Wait for a running call to finish
+    #[default]
+    Queue,
+    /// Fail the call right away
+    Reject,
+}
+
+/// The limit of concurrent tool calls of a service, installed with [`ServerHandler::with_tool_concurrency`]
+///
+/// Its policy also applies to the limits of `#[tool(max_concurrent = ..)]`.
+///
+/// [`ServerHandler::with_tool_concurrency`]: crate::ServerHandler::with_tool_concurrency
+#[derive(Debug, Clone, Default)]
+pub struct ToolConcurrency {
+    semaphore: Option<Arc<Semaphore>>,
+    max_concurrent: usize,
+    policy: ConcurrencyPolicy,
+}
+
+impl ToolConcurrency {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            semaphore: Some(Arc::new(Semaphore::new(max_concurrent))),
+            max_concurrent,
+            policy: ConcurrencyPolicy::default(),
+        }
+    }
+
+    /// No limit of the service, only the ones of the tools
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policy(mut self, policy: ConcurrencyPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> ConcurrencyPolicy {
+        self.policy
+    }
+
+    /// The calls which may still start right away, `None` without a limit
+    pub fn available(&self) -> Option<usize> {
+        self.semaphore
+            .as_ref()
+            .map(|semaphore| semaphore.available_permits())
+    }
+
+    pub(crate) async fn acquire(
+        &self,
+        tool: &str,
+        ct: &CancellationToken,
+    ) -> Result<Option<OwnedSemaphorePermit>, crate::Error> {
+        let Some(semaphore) = &self.semaphore else {
+            return Ok(None);
+        };
+        let permit = acquire_permit(
+            semaphore.clone().acquire_owned(),
+            semaphore.clone().try_acquire_owned(),
+            self.policy,
+            ct,
+        )
+        .await;
+        permit.map(Some).map_err(|reason| {
+            concurrency_error(
+                reason,
+                tool,
+                "max_concurrent_tool_calls",
+                self.max_concurrent,
+            )
+        })
+    }
+}
+
+/// The limit of `#[tool(max_concurrent = ..)]`, shared by every instance of the server
+#[derive(Debug)]
+pub struct ToolConcurrencyLimit {
+    semaphore: Semaphore,
+    max_concurrent: usize,
+}
+
+impl ToolConcurrencyLimit {
+    pub const fn new(max_concurrent: usize) -> Self {
+        Self {
+            semaphore: Semaphore::const_new(max_concurrent),
+            max_concurrent,
+        }
+    }
+}
+
+enum PermitDenied {
+    Rejected,
+    Cancelled,
+}
+
+async fn acquire_permit<P>(
+    acquire: impl Future<Output = Result<P, AcquireError>>,
+    try_acquire: Result<P, TryAcquireError>,
+    policy: ConcurrencyPolicy,
+    ct: &CancellationToken,
+) -> Result<P, PermitDenied> {
+    match (try_acquire, policy) {
+        (Ok(permit), _) => Ok(permit),
+        (Err(_), ConcurrencyPolicy::Reject) => Err(PermitDenied::Rejected),
+        (Err(_), ConcurrencyPolicy::Queue) => tokio::select! {
+            // the semaphores are never closed
+            permit = acquire => permit.map_err(|_| PermitDenied::Rejected),
+            _ = ct.cancelled() => Err(PermitDenied::Cancelled),
+        },
+    }
+}
+
+fn concurrency_error(
+    reason: PermitDenied,
+    tool: &str,
+    limit: &str,
+    max_concurrent: usize,
+) -> crate::Error {
+    let message = match reason {
+        PermitDenied::Rejected => "too many concurrent tool calls",
+        PermitDenied::Cancelled => "tool call cancelled while queued",
+    };
+    crate::Error::internal_error(
+        message,
+        Some(serde_json::json!({
+            "tool": tool,
+            (limit): max_concurrent,
+        })),
+    )
+}
+
+/// Call a tool once `limit` of `#[tool(max_concurrent = ..)]` allows it
+///
+/// Calls over the limit wait or fail as the [`ConcurrencyPolicy`] of the service's
+/// [`ToolConcurrency`] says, a call cancelled while it waits never runs.
+pub async fn call_with_concurrency_limit<'s, S, F, Fut>(
+    context: ToolCallContext<'s, S>,
+    limit: Option<&'static ToolConcurrencyLimit>,
+    call: F,
+) -> Result<CallToolResult, crate::Error>
+where
+    F: FnOnce(ToolCallContext<'s, S>) -> Fut,
+    Fut: Future<Output = Result<CallToolResult, crate::Error>>,
+{
+    let Some(limit) = limit else {
+        return call(context).await;
+    };
+    let policy = context
+        .request_context
+        .extensions
+        .get::<ToolConcurrency>()
+        .map(ToolConcurrency::policy)
+        .unwrap_or_default();
+    let _permit = acquire_permit(
+        limit.semaphore.acquire(),
+        limit.semaphore.try_acquire(),
+        policy,
+        &context.request_context.ct,
+    )
+    .await
+    .map_err(|reason| {
+        concurrency_error(
+            reason,
+            &context.name,
+            "max_concurrent",
+            limit.max_concurrent,
+        )
+    })?;
+    call(context).await
+}
+
+/// Logs every tool call with its outcome and duration
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingMiddleware;
+
+impl<S> ToolMiddleware<S> for TracingMiddleware {
+    fn before(&self, context: &ToolCallContext<'_, S>) -> Result<(), crate::Error> {
+        tracing::info!(tool = context.name(), "calling tool");
+        Ok(())
+    }
+
+    fn after(
+        &self,
+        name: &str,
+        result: &mut Result<CallToolResult, crate::Error>,
+        elapsed: Duration,
+    ) {
+        match result {
+            Ok(result) if result.is_error == Some(true) => {
+                tracing::warn!(tool = name, ?elapsed, "tool returned an error")
+            }
+            Ok(_) => tracing::info!(tool = name, ?elapsed, "tool call finished"),
+            Err(error) => tracing::warn!(tool = name, ?elapsed, %error, "tool call failed"),
+        }
+    }
+}
+
+pub trait CallToolHandler<'a, S, A> {
+    type Fut: Future<Output = Result<CallToolResult, crate::Error>> + Send + 'a;
+    fn call(self, context: ToolCallContext<'a, S>) -> Self::Fut;
+}
+
+pub type DynCallToolHandler<S> = dyn Fn(ToolCallContext<'_, S>) -> BoxFuture<'_, Result<CallToolResult, crate::Error>>
+    + Send
+    + Sync;
+/// Parameter Extractor
+pub struct Parameter<K: ConstString, V>(pub K, pub V);
+
+/// Parameter Extractor
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Parameters<P>(pub P);
+
+impl<P: JsonSchema> JsonSchema for Parameters<P> {
+    fn schema_name() -> String {
+        P::schema_name()
+    }
+
+    fn json_schema(generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        P::json_schema(generator)
+    }
+}
+
+/// Callee Extractor
+pub struct Callee<'a, S>(pub &'a S);
+
+/// Cancellation extractor, the token is cancelled when the client cancels the request
+/// with `notifications/cancelled` or when the tool times out
+impl<'a, S> FromToolCallContextPart<'a, S> for CancellationToken {
+    fn from_tool_call_context_part(
+        context: ToolCallContext<'a, S>,
+    ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
+        Ok((context.request_context.ct.clone(), context))
+    }
+}
+
+impl<'a, S> FromToolCallContextPart<'a, S> for Callee<'a, S> {
+    fn from_tool_call_context_part(
+        mut context: ToolCallContext<'a, S>,
+    ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
+        Ok((Callee(context.service()?), context))
+    }
+}
+
+/// Extension extractor, clones a value inserted with [`ServerHandler::with_extension`]
+///
+/// [`ServerHandler::with_extension`]: crate::ServerHandler::with_extension
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Extension<T>(pub T);
+
+impl<'a, S, T> FromToolCallContextPart<'a, S> for Extension<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    fn from_tool_call_context_part(
+        context: ToolCallContext<'a, S>,
+    ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
+        let value = context
+            .request_context
+            .extensions
+            .get::<T>()
+            .cloned()
+            .ok_or_else(|| {
+                crate::Error::internal_error(
+                    format!("missing extension `{}`", std::any::type_name::<T>()),
+                    None,
+                )
+            })?;
+        Ok((Extension(value), context))
+    }
+}
+
+/// Session data extractor, clones a value of the [`SessionData`] of the connection
+///
+/// [`SessionData`]: crate::service::SessionData
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Session<T>(pub T);
 
-pub type DynCallToolHandler<S> = dyn Fn(ToolCallContext<'_, S>) -> BoxFuture<'_, Result<CallToolResult, crate::Error>>
-    + Send
-    + Sync;
-/// Parameter Extractor
-pub struct Parameter<K: ConstString, V>(pub K, pub V);
+impl<'a, S, T> FromToolCallContextPart<'a, S> for Session<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    fn from_tool_call_context_part(
+        context: ToolCallContext<'a, S>,
+    ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
+        let value = context
+            .request_context
+            .session()
+            .get::<T>()
+            .ok_or_else(|| {
+                crate::Error::internal_error(
+                    format!("missing session data `{}`", std::any::type_name::<T>()),
+                    None,
+                )
+            })?;
+        Ok((Session(value), context))
+    }
+}
 
-/// Parameter Extractor
+/// Request metadata extractor
 ///
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(transparent)]
-pub struct Parameters<P>(pub P);
+/// Tells who is calling: the client info with the negotiated protocol version,
+/// the JSON-RPC request id and the `_meta` object of the request params.
+#[derive(Debug, Clone)]
+pub struct RequestMeta {
+    request_id: RequestId,
+    peer_info: ClientInfo,
+    meta: Meta,
+}
 
-impl<P: JsonSchema> JsonSchema for Parameters<P> {
-    fn schema_name() -> String {
-        P::schema_name()
+impl RequestMeta {
+    pub fn request_id(&self) -> &RequestId {
+        &self.request_id
     }
-
-    fn json_schema(generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
-        P::json_schema(generator)
+    pub fn client_info(&self) -> &Implementation {
+        &self.peer_info.client_info
+    }
+    pub fn protocol_version(&self) -> &ProtocolVersion {
+        &self.peer_info.protocol_version
+    }
+    pub fn capabilities(&self) -> &ClientCapabilities {
+        &self.peer_info.capabilities
+    }
+    /// The `_meta` object of the request params
+    pub fn meta(&self) -> &Meta {
+        &self.meta
     }
 }
 
-/// Callee Extractor
-pub struct Callee<'a, S>(pub &'a S);
-
-impl<'a, S> FromToolCallContextPart<'a, S> for CancellationToken {
+impl<'a, S> FromToolCallContextPart<'a, S> for RequestMeta {
     fn from_tool_call_context_part(
         context: ToolCallContext<'a, S>,
     ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
-        Ok((context.request_context.ct.clone(), context))
+        let request_context = &context.request_context;
+        let meta = RequestMeta {
+            request_id: request_context.id.clone(),
+            peer_info: request_context.peer.peer_info().clone(),
+            meta: request_context.meta.clone(),
+        };
+        Ok((meta, context))
     }
 }
 
-impl<'a, S> FromToolCallContextPart<'a, S> for Callee<'a, S> {
+/// Peer extractor, e.g. to ask the client's LLM for a message with [`Peer::create_message`]
+impl<'a, S> FromToolCallContextPart<'a, S> for Peer<RoleServer> {
     fn from_tool_call_context_part(
         context: ToolCallContext<'a, S>,
     ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
-        Ok((Callee(context.service), context))
+        Ok((context.request_context.peer.clone(), context))
     }
 }
 
@@ -215,11 +1270,87 @@ impl<'a, S> FromToolCallContextPart<'a, S> for ToolName {
     }
 }
 
-impl<'a, S> FromToolCallContextPart<'a, S> for &'a S {
+/// Progress reporter extractor
+///
+/// Sends `notifications/progress` for the progress token of the request,
+/// reporting is a no-op when the client didn't send a progress token.
+#[derive(Clone)]
+pub struct ToolProgress {
+    progress_token: Option<ProgressToken>,
+    peer: Peer<RoleServer>,
+    strict: bool,
+    last: Arc<std::sync::Mutex<Option<f64>>>,
+}
+
+impl ToolProgress {
+    pub fn progress_token(&self) -> Option<&ProgressToken> {
+        self.progress_token.as_ref()
+    }
+
+    /// Assert in debug builds that the progress reported increases every time, as the spec asks
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    pub async fn report(
+        &self,
+        progress: f64,
+        total: Option<f64>,
+        message: Option<String>,
+    ) -> Result<(), ServiceError> {
+        if self.strict {
+            let mut last = self
+                .last
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            debug_assert!(
+                last.is_none_or(|last| progress > last),
+                "progress {progress} reported after {last:?}"
+            );
+            *last = Some(progress);
+        }
+        let Some(progress_token) = self.progress_token.clone() else {
+            return Ok(());
+        };
+        self.peer
+            .notify_progress(ProgressNotificationParam {
+                progress_token,
+                progress,
+                total,
+                message,
+            })
+            .await
+    }
+}
+
+impl<'a, S> FromToolCallContextPart<'a, S> for ToolProgress {
     fn from_tool_call_context_part(
         context: ToolCallContext<'a, S>,
     ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
-        Ok((context.service, context))
+        let progress = ToolProgress {
+            progress_token: context.request_context.progress_token(),
+            peer: context.request_context.peer.clone(),
+            strict: false,
+            last: Default::default(),
+        };
+        Ok((progress, context))
+    }
+}
+
+impl<'a, S> FromToolCallContextPart<'a, S> for &'a S {
+    fn from_tool_call_context_part(
+        mut context: ToolCallContext<'a, S>,
+    ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
+        Ok((context.service()?, context))
+    }
+}
+
+impl<'a, S> FromToolCallContextPart<'a, S> for &'a mut S {
+    fn from_tool_call_context_part(
+        mut context: ToolCallContext<'a, S>,
+    ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
+        Ok((context.service_mut()?, context))
     }
 }
 
@@ -231,26 +1362,27 @@ where
     fn from_tool_call_context_part(
         context: ToolCallContext<'a, S>,
     ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
-        let arguments = context
+        // missing arguments are no arguments, a missing parameter is null for an `Option`; the
+        // arguments are parsed into a map once, for all the single parameters
+        let value = context
             .arguments
             .as_ref()
-            .ok_or(crate::Error::invalid_params(
-                format!("missing parameter {field}", field = K::VALUE),
-                None,
-            ))?;
-        let value = arguments.get(K::VALUE).ok_or(crate::Error::invalid_params(
-            format!("missing parameter {field}", field = K::VALUE),
-            None,
-        ))?;
-        let value: V = serde_json::from_value(value.clone()).map_err(|e| {
-            crate::Error::invalid_params(
-                format!(
+            .and_then(|arguments| arguments.get(K::VALUE));
+        // deserialized in place, the value isn't cloned first
+        let result = match value {
+            Some(value) => V::deserialize(value),
+            None => V::deserialize(serde_json::Value::Null),
+        };
+        let value: V = result.map_err(|e| {
+            let message = match value {
+                Some(_) => format!(
                     "failed to deserialize parameter {field}: {error}",
                     field = K::VALUE,
                     error = e
                 ),
-                None,
-            )
+                None => format!("missing parameter {field}", field = K::VALUE),
+            };
+            crate::Error::invalid_params(message, None)
         })?;
         Ok((Parameter(K::default(), value), context))
     }
@@ -263,14 +1395,9 @@ where
     fn from_tool_call_context_part(
         mut context: ToolCallContext<'a, S>,
     ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
+        // deserialized from the text of the arguments, they aren't parsed into a map first
         let arguments = context.arguments.take().unwrap_or_default();
-        let value: P =
-            serde_json::from_value(serde_json::Value::Object(arguments)).map_err(|e| {
-                crate::Error::invalid_params(
-                    format!("failed to deserialize parameters: {error}", error = e),
-                    None,
-                )
-            })?;
+        let value: P = parse_tool_arguments(arguments)?;
         Ok((Parameters(value), context))
     }
 }
@@ -279,11 +1406,24 @@ impl<'a, S> FromToolCallContextPart<'a, S> for JsonObject {
     fn from_tool_call_context_part(
         mut context: ToolCallContext<'a, S>,
     ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
-        let object = context.arguments.take().unwrap_or_default();
+        let object = context
+            .arguments
+            .take()
+            .map(ToolArguments::into_object)
+            .unwrap_or_default();
         Ok((object, context))
     }
 }
 
+impl<'a, S> FromToolCallContextPart<'a, S> for ToolArguments {
+    fn from_tool_call_context_part(
+        mut context: ToolCallContext<'a, S>,
+    ) -> Result<(Self, ToolCallContext<'a, S>), crate::Error> {
+        let arguments = context.arguments.take().unwrap_or_default();
+        Ok((arguments, context))
+    }
+}
+
 impl<'s, S> ToolCallContext<'s, S> {
     pub fn invoke<H, A>(self, h: H) -> H::Fut
     where
@@ -394,18 +1534,100 @@ impl<S: Send + Sync + 'static + Clone> ToolBoxItem<S> {
     }
 }
 
+/// A shared handle to a server whose tools take `&mut self`
+///
+/// Each tool call locks the server, so calls are handled one at a time.
+/// `#[tool(tool_box, mutability = lock)]` implements [`LockedServer`] for `Self`, which makes
+/// `Locked<Self>` a `ServerHandler`.
+#[derive(Debug, Default)]
+pub struct Locked<S>(Arc<tokio::sync::Mutex<S>>);
+
+impl<S> Clone for Locked<S> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<S> Locked<S> {
+    pub fn new(service: S) -> Self {
+        Self(Arc::new(tokio::sync::Mutex::new(service)))
+    }
+    pub async fn lock(&self) -> tokio::sync::MutexGuard<'_, S> {
+        self.0.lock().await
+    }
+}
+
+/// A server whose tools are called through [`Locked`]
+///
+/// Implemented by `#[tool(tool_box, mutability = lock)]`, the `ServerHandler` of `Locked<S>`
+/// locks the server to list and call its tools.
+pub trait LockedServer: Sized + Send + Sync + 'static {
+    fn tools() -> &'static ToolBox<Self>;
+    fn info() -> crate::model::ServerInfo;
+}
+
+impl<S: LockedServer> super::ServerHandler for Locked<S> {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, crate::Error> {
+        let service = self.lock().await;
+        S::tools().list_page(&service, request)
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, crate::Error> {
+        let mut service = self.lock().await;
+        let context = ToolCallContext::new_mut(&mut *service, request, context);
+        S::tools().call(context).await
+    }
+
+    fn get_info(&self) -> crate::model::ServerInfo {
+        S::info()
+    }
+}
+
+/// Another name a tool can be called by, see `#[tool(aliases = [..])]`
+#[derive(Debug, Clone)]
+pub struct ToolAlias {
+    pub alias: Cow<'static, str>,
+    /// The primary name of the tool
+    pub name: Cow<'static, str>,
+    /// Whether the alias is listed as a tool of its own
+    pub advertise: bool,
+}
+
 #[derive(Default)]
 pub struct ToolBox<S> {
     #[allow(clippy::type_complexity)]
     pub map: std::collections::HashMap<Cow<'static, str>, ToolBoxItem<S>>,
+    /// Decide whether a tool is exposed by the given service, see `#[tool(enabled_if = ..)]`
+    pub enabled: Option<fn(&S, &str) -> bool>,
+    /// Describe a tool at list time, see `#[tool(description_fn = ..)]`
+    pub describe: Option<fn(&S, &str) -> Option<String>>,
+    pub aliases: std::collections::HashMap<Cow<'static, str>, ToolAlias>,
+    /// Split `list_tools` into pages, see `#[tool(tool_box, page_size = ..)]`
+    pub page_size: Option<usize>,
 }
 
 impl<S> ToolBox<S> {
     pub fn new() -> Self {
         Self {
             map: std::collections::HashMap::new(),
+            enabled: None,
+            describe: None,
+            aliases: std::collections::HashMap::new(),
+            page_size: None,
         }
     }
+
+    pub fn is_enabled(&self, service: &S, name: &str) -> bool {
+        self.enabled.is_none_or(|enabled| enabled(service, name))
+    }
     pub fn add(&mut self, item: ToolBoxItem<S>) {
         self.map.insert(item.attr.name.clone(), item);
     }
@@ -414,20 +1636,282 @@ impl<S> ToolBox<S> {
         self.map.remove(name);
     }
 
+    pub fn add_alias(&mut self, alias: ToolAlias) {
+        self.aliases.insert(alias.alias.clone(), alias);
+    }
+
+    /// Add the tools and aliases of another tool box, see `tool_box!(combine ..)`
+    pub fn extend_from(&mut self, other: &'static ToolBox<S>)
+    where
+        S: Send + Sync + 'static + Clone,
+    {
+        for item in other.map.values() {
+            // names computed at runtime can't be checked when combining the boxes
+            debug_assert!(
+                !self.map.contains_key(item.name()),
+                "duplicate tool name `{}` in combined tool box",
+                item.name()
+            );
+            self.add(ToolBoxItem::new(item.attr.clone(), move |context| {
+                (item.call)(context)
+            }));
+        }
+        for alias in other.aliases.values() {
+            self.add_alias(alias.clone());
+        }
+    }
+
+    /// Resolve an alias to the primary name of its tool
+    pub fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        self.aliases
+            .get(name)
+            .map(|alias| alias.name.as_ref())
+            .unwrap_or(name)
+    }
+
     pub async fn call(
         &self,
         context: ToolCallContext<'_, S>,
-    ) -> Result<CallToolResult, crate::Error> {
-        let item = self
-            .map
-            .get(context.name())
-            .ok_or_else(|| crate::Error::invalid_params("tool not found", None))?;
-        (item.call)(context).await
+    ) -> Result<CallToolResult, crate::Error>
+    where
+        S: 'static,
+    {
+        let name = self.resolve(context.name());
+        let enabled = context
+            .service_ref()
+            .is_none_or(|service| self.is_enabled(service, name));
+        let item = self.map.get(name).filter(|_| enabled);
+        call_with_middleware(context, |context| async move {
+            let item = item.ok_or_else(|| crate::Error::tool_not_found(context.name()))?;
+            (item.call)(context).await
+        })
+        .await
     }
 
     pub fn list(&self) -> Vec<crate::model::Tool> {
         self.map.values().map(|item| item.attr.clone()).collect()
     }
+
+    /// List the tools enabled for the given service, with their descriptions resolved against it
+    pub fn list_for(&self, service: &S) -> Vec<crate::model::Tool> {
+        let advertised_aliases = self
+            .aliases
+            .values()
+            .filter(|alias| alias.advertise)
+            .map(|alias| (alias.alias.clone(), alias.name.as_ref()));
+        self.map
+            .keys()
+            .map(|name| (name.clone(), name.as_ref()))
+            .chain(advertised_aliases)
+            .filter(|(_, name)| self.is_enabled(service, name))
+            .filter_map(|(listed_name, name)| {
+                let mut tool = self.map.get(name)?.attr.clone();
+                tool.name = listed_name;
+                if let Some(description) =
+                    self.describe.and_then(|describe| describe(service, name))
+                {
+                    tool.description = Some(description.into());
+                }
+                Some(tool)
+            })
+            .collect()
+    }
+
+    /// List the page of tools requested by `list_tools`
+    pub fn list_page(
+        &self,
+        service: &S,
+        request: Option<PaginatedRequestParam>,
+    ) -> Result<ListToolsResult, crate::Error> {
+        let tools = self.list_for(service);
+        match self.page_size {
+            Some(page_size) => paginate_tools(
+                &tools,
+                request
+                    .as_ref()
+                    .and_then(|request| request.cursor.as_deref()),
+                page_size,
+            ),
+            None => Ok(ListToolsResult {
+                next_cursor: None,
+                tools,
+                meta: None,
+            }),
+        }
+    }
+}
+
+/// Tools registered at runtime, e.g. loaded from plugins or configuration
+///
+/// The router is a shared handle, clones route to the same tools. Tools generated by
+/// `#[tool]` can be registered next to dynamic ones with [`ToolRouter::add`]:
+///
+/// ```rust,ignore
+/// router.add(ToolBoxItem::new(Server::sum_tool_attr(), |context| {
+///     Box::pin(Server::sum_tool_call(context))
+/// }));
+/// ```
+///
+/// Once a peer is bound, adding or removing tools sends it `notifications/tools/list_changed`
+/// through the router's [`ListChangedNotifier`], coalescing changes made in quick succession.
+/// `tool_box!(@derive_router ..)` binds the peer listing the tools.
+pub struct ToolRouter<S> {
+    #[allow(clippy::type_complexity)]
+    routes: Arc<std::sync::RwLock<HashMap<Cow<'static, str>, Arc<ToolBoxItem<S>>>>>,
+    notifier: ListChangedNotifier,
+}
+
+impl<S> Clone for ToolRouter<S> {
+    fn clone(&self) -> Self {
+        Self {
+            routes: self.routes.clone(),
+            notifier: self.notifier.clone(),
+        }
+    }
+}
+
+impl<S: 'static> Default for ToolRouter<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> std::fmt::Debug for ToolRouter<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let routes = self.routes.read().expect("tool router lock poisoned");
+        f.debug_struct("ToolRouter")
+            .field("tools", &routes.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<S: 'static> ToolRouter<S> {
+    pub fn new() -> Self {
+        Self::with_notifier(ListChangedNotifier::new())
+    }
+
+    /// A router sending its list changes through `notifier`, e.g. one shared with other registries
+    pub fn with_notifier(notifier: ListChangedNotifier) -> Self {
+        Self {
+            routes: Default::default(),
+            notifier,
+        }
+    }
+
+    pub fn notifier(&self) -> &ListChangedNotifier {
+        &self.notifier
+    }
+
+    /// Route calls of the tool `name` to `handler`, replacing any tool of the same name
+    pub fn route<C>(
+        &self,
+        name: impl Into<Cow<'static, str>>,
+        mut tool: crate::model::Tool,
+        handler: C,
+    ) where
+        S: Send + Sync + 'static + Clone,
+        C: Fn(ToolCallContext<'_, S>) -> BoxFuture<'_, Result<CallToolResult, crate::Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        tool.name = name.into();
+        // generated tools apply the default timeout themselves
+        let handler = Arc::new(handler);
+        self.add(ToolBoxItem::new(tool, move |context| {
+            let handler = handler.clone();
+            Box::pin(call_with_timeout(context, None, move |context| {
+                handler(context)
+            }))
+        }));
+    }
+
+    /// Register a tool, such as the `*_tool_attr` and `*_tool_call` pair generated by `#[tool]`
+    pub fn add(&self, item: ToolBoxItem<S>) {
+        self.write_routes()
+            .insert(item.attr.name.clone(), Arc::new(item));
+        self.notify_list_changed();
+    }
+
+    /// Register the tools of a tool box generated by `#[tool(tool_box)]`
+    ///
+    /// Aliases, `enabled_if` and `description_fn` of the tool box don't apply to the router.
+    pub fn extend_from(&self, tool_box: &'static ToolBox<S>)
+    where
+        S: Send + Sync + 'static + Clone,
+    {
+        let mut routes = self.write_routes();
+        for item in tool_box.map.values() {
+            let item = ToolBoxItem::new(item.attr.clone(), move |context| (item.call)(context));
+            routes.insert(item.attr.name.clone(), Arc::new(item));
+        }
+        drop(routes);
+        self.notify_list_changed();
+    }
+
+    /// Move the tools of another router into this one, tools of `other` win on name clashes
+    pub fn merge(&self, other: ToolRouter<S>) {
+        if Arc::ptr_eq(&self.routes, &other.routes) {
+            return;
+        }
+        let other_routes = std::mem::take(&mut *other.write_routes());
+        self.write_routes().extend(other_routes);
+        other.notify_list_changed();
+        self.notify_list_changed();
+    }
+
+    /// Remove a tool, returning its definition if it was registered
+    pub fn remove(&self, name: &str) -> Option<crate::model::Tool> {
+        let removed = self.write_routes().remove(name)?;
+        self.notify_list_changed();
+        Some(removed.attr.clone())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.read_routes().contains_key(name)
+    }
+
+    pub fn list(&self) -> Vec<crate::model::Tool> {
+        self.read_routes()
+            .values()
+            .map(|item| item.attr.clone())
+            .collect()
+    }
+
+    pub async fn call(
+        &self,
+        context: ToolCallContext<'_, S>,
+    ) -> Result<CallToolResult, crate::Error> {
+        // release the lock before calling, the tool may register or remove tools itself
+        let item = self.read_routes().get(context.name()).cloned();
+        call_with_middleware(context, |context| async move {
+            let item = item.ok_or_else(|| crate::Error::tool_not_found(context.name()))?;
+            (item.call)(context).await
+        })
+        .await
+    }
+
+    /// Send `notifications/tools/list_changed` to `peer` whenever the tools change,
+    /// if `capabilities` advertises it
+    pub fn bind_peer(&self, peer: Peer<RoleServer>, capabilities: &ServerCapabilities) {
+        self.notifier.bind(peer, capabilities);
+    }
+
+    fn notify_list_changed(&self) {
+        self.notifier.mark_tools_changed();
+    }
+
+    fn read_routes(
+        &self,
+    ) -> std::sync::RwLockReadGuard<'_, HashMap<Cow<'static, str>, Arc<ToolBoxItem<S>>>> {
+        self.routes.read().expect("tool router lock poisoned")
+    }
+
+    fn write_routes(
+        &self,
+    ) -> std::sync::RwLockWriteGuard<'_, HashMap<Cow<'static, str>, Arc<ToolBoxItem<S>>>> {
+        self.routes.write().expect("tool router lock poisoned")
+    }
 }
 
 #[cfg(feature = "macros")]
@@ -435,20 +1919,91 @@ impl<S> ToolBox<S> {
 #[macro_export]
 macro_rules! tool_box {
     (@pin_add $callee: ident, $attr: expr, $f: expr) => {
-        $callee.add(ToolBoxItem::new($attr, |context| Box::pin($f(context))));
+        let item = ToolBoxItem::new($attr, |context| Box::pin($f(context)));
+        // literal names are checked by the macro, this catches names computed at runtime
+        debug_assert!(
+            !$callee.map.contains_key(item.name()),
+            "duplicate tool name `{}` in tool box",
+            item.name()
+        );
+        $callee.add(item);
+    };
+    // a tool box merging the tool boxes of other impl blocks
+    (combine $server: ident { $($part: ident),* $(,)? } $tool_box: ident $(, page_size = $page_size: expr)?) => {
+        fn $tool_box() -> &'static $crate::handler::server::tool::ToolBox<$server> {
+            use $crate::handler::server::tool::ToolBox;
+            $crate::paste!{
+                // tool names known at compile time must be unique across the boxes
+                const _: () = assert!(
+                    $crate::handler::server::tool::tool_names_are_unique(&[$($server::[<$part _tool_names>]()),*]),
+                    concat!("duplicate tool name in the tool boxes combined into `", stringify!($server), "::", stringify!($tool_box), "`"),
+                );
+            }
+            static TOOL_BOX: std::sync::OnceLock<ToolBox<$server>> = std::sync::OnceLock::new();
+            TOOL_BOX.get_or_init(|| {
+                let mut tool_box = ToolBox::new();
+                $(tool_box.extend_from($server::$part());)*
+                // each tool is enabled and described by the box it comes from
+                let enabled: fn(&$server, &str) -> bool = |service, name| {
+                    $(
+                        if $server::$part().map.contains_key(name) {
+                            return $server::$part().is_enabled(service, name);
+                        }
+                    )*
+                    true
+                };
+                let describe: fn(&$server, &str) -> Option<String> = |service, name| {
+                    $(
+                        if $server::$part().map.contains_key(name) {
+                            return $server::$part().describe.and_then(|describe| describe(service, name));
+                        }
+                    )*
+                    None
+                };
+                tool_box.enabled = Some(enabled);
+                tool_box.describe = Some(describe);
+                $(tool_box.page_size = Some($page_size);)?
+                tool_box
+            })
+        }
     };
-    ($server: ident { $($tool: ident),* $(,)?} ) => {
-        $crate::tool_box!($server { $($tool),* }  tool_box);
+    // free function tools, usable by any service
+    (fns: [$($(#[$meta: meta])* $tool: ident),* $(,)?]) => {{
+        use $crate::handler::server::tool::{ToolBox, ToolBoxItem};
+        let mut tool_box = ToolBox::new();
+        $crate::paste!{
+            $(
+                $(#[$meta])*
+                {
+                    $crate::tool_box!(@pin_add tool_box, [< $tool _tool_attr>](), [<$tool _tool_call>]);
+                }
+            )*
+        }
+        tool_box
+    }};
+    ($server: ident { $($(#[$meta: meta])* $tool: ident),* $(,)?} ) => {
+        $crate::tool_box!($server { $($(#[$meta])* $tool),* }  tool_box);
     };
-    ($server: ident { $($tool: ident),* $(,)?} $tool_box: ident) => {
+    ($server: ident { $($(#[$meta: meta])* $tool: ident),* $(,)?} $tool_box: ident $(, enabled = $enabled: path)? $(, describe = $describe: path)? $(, aliases = $aliases: path)? $(, page_size = $page_size: expr)?) => {
         fn $tool_box() -> &'static $crate::handler::server::tool::ToolBox<$server> {
             use $crate::handler::server::tool::{ToolBox, ToolBoxItem};
             static TOOL_BOX: std::sync::OnceLock<ToolBox<$server>> = std::sync::OnceLock::new();
             TOOL_BOX.get_or_init(|| {
                 let mut tool_box = ToolBox::new();
+                $(tool_box.enabled = Some($enabled);)?
+                $(tool_box.describe = Some($describe);)?
+                $(
+                    for alias in $aliases() {
+                        tool_box.add_alias(alias);
+                    }
+                )?
+                $(tool_box.page_size = Some($page_size);)?
                 $crate::paste!{
                     $(
-                        $crate::tool_box!(@pin_add tool_box, $server::[< $tool _tool_attr>](), $server::[<$tool _tool_call>]);
+                        $(#[$meta])*
+                        {
+                            $crate::tool_box!(@pin_add tool_box, $server::[< $tool _tool_attr>](), $server::[<$tool _tool_call>]);
+                        }
                     )*
                 }
                 tool_box
@@ -459,18 +2014,44 @@ macro_rules! tool_box {
         $crate::tool_box!(@derive tool_box);
     };
 
-    (@derive $tool_box:ident) => {
+    // delegate to a `ToolRouter` field of the server
+    (@derive_router $router: ident) => {
         async fn list_tools(
             &self,
             _: Option<$crate::model::PaginatedRequestParam>,
-            _: $crate::service::RequestContext<$crate::service::RoleServer>,
+            context: $crate::service::RequestContext<$crate::service::RoleServer>,
         ) -> Result<$crate::model::ListToolsResult, $crate::Error> {
+            // the peer listing the tools is notified when they change
+            self.$router.bind_peer(
+                context.peer,
+                &<Self as $crate::ServerHandler>::get_info(self).capabilities,
+            );
             Ok($crate::model::ListToolsResult {
                 next_cursor: None,
-                tools: Self::tool_box().list(),
+                tools: self.$router.list(),
+                meta: None,
             })
         }
 
+        async fn call_tool(
+            &self,
+            call_tool_request_param: $crate::model::CallToolRequestParam,
+            context: $crate::service::RequestContext<$crate::service::RoleServer>,
+        ) -> Result<$crate::model::CallToolResult, $crate::Error> {
+            let context = $crate::handler::server::tool::ToolCallContext::new(self, call_tool_request_param, context);
+            self.$router.call(context).await
+        }
+    };
+
+    (@derive $tool_box:ident) => {
+        async fn list_tools(
+            &self,
+            request: Option<$crate::model::PaginatedRequestParam>,
+            _: $crate::service::RequestContext<$crate::service::RoleServer>,
+        ) -> Result<$crate::model::ListToolsResult, $crate::Error> {
+            Self::$tool_box().list_page(self, request)
+        }
+
         async fn call_tool(
             &self,
             call_tool_request_param: $crate::model::CallToolRequestParam,
@@ -481,3 +2062,87 @@ macro_rules! tool_box {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::model::{Content, ErrorCode};
+
+    fn texts(result: &CallToolResult) -> Vec<&str> {
+        result
+            .content
+            .iter()
+            .map(|content| content.as_text().expect("text content").text.as_str())
+            .collect()
+    }
+
+    fn convert(value: impl IntoCallToolResult) -> CallToolResult {
+        value.into_call_tool_result().expect("a tool result")
+    }
+
+    #[test]
+    fn test_into_contents_results() {
+        for result in [
+            convert("borrowed"),
+            convert("owned".to_string()),
+            convert(Content::text("content")),
+            convert(vec![Content::text("a"), Content::text("b")]),
+            convert(json!({ "answer": 42 })),
+            convert(()),
+        ] {
+            assert_eq!(result.is_error, Some(false));
+            assert!(result.structured_content.is_none());
+        }
+        assert_eq!(texts(&convert("borrowed")), ["borrowed"]);
+        assert_eq!(
+            texts(&convert(vec![Content::text("a"), Content::text("b")])),
+            ["a", "b"]
+        );
+        assert_eq!(
+            texts(&convert(json!({ "answer": 42 }))),
+            [r#"{"answer":42}"#]
+        );
+        assert!(convert(()).content.is_empty());
+    }
+
+    #[test]
+    fn test_result_errors() {
+        let ok: Result<&str, std::io::Error> = Ok("done");
+        assert_eq!(convert(ok), CallToolResult::text("done"));
+
+        // any displayable error is reported to the model
+        let failed: Result<String, std::io::Error> = Err(std::io::Error::other("disk full"));
+        let result = convert(failed);
+        assert_eq!(result.is_error, Some(true));
+        assert_eq!(texts(&result), ["disk full"]);
+
+        let failed: Result<(), crate::Error> = Err(crate::Error::invalid_params("bad key", None));
+        assert_eq!(texts(&convert(failed)), ["-32602: bad key"]);
+
+        // but a `CallToolResult` error stays a protocol error
+        let failed: Result<CallToolResult, crate::Error> =
+            Err(crate::Error::invalid_params("bad key", None));
+        let error = failed.into_call_tool_result().unwrap_err();
+        assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_call_tool_result_constructors() {
+        assert_eq!(texts(&CallToolResult::text(42)), ["42"]);
+        let error = CallToolResult::error_text("not found");
+        assert_eq!(error.is_error, Some(true));
+        assert_eq!(texts(&error), ["not found"]);
+
+        let result = CallToolResult::json(&json!({ "city": "Paris", "high": 21 })).unwrap();
+        assert_eq!(
+            result.structured_content,
+            Some(json!({ "city": "Paris", "high": 21 }))
+        );
+        assert_eq!(
+            texts(&result),
+            ["{\n  \"city\": \"Paris\",\n  \"high\": 21\n}"]
+        );
+    }
+}
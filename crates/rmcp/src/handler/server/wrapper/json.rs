@@ -1,28 +1,38 @@
 use serde::Serialize;
 
-use crate::model::IntoContents;
+use crate::{handler::server::tool::IntoCallToolResult, model::CallToolResult};
 
 /// Json wrapper
 ///
-/// This is used to tell the SDK to serialize the inner value into json
+/// This is used to tell the SDK to serialize the inner value into json.
+/// A tool returning `Json<T>` sends it as `structuredContent`, with a text fallback for older clients,
+/// and advertises the schema of `T` as its output schema.
 pub struct Json<T>(pub T);
 
-impl<T> IntoContents for Json<T>
+impl<T> IntoCallToolResult for Json<T>
 where
     T: Serialize,
 {
-    fn into_contents(self) -> Vec<crate::model::Content> {
-        let result = crate::model::Content::json(self.0);
-        debug_assert!(
-            result.is_ok(),
-            "Json wrapped content should be able to serialized into json"
-        );
-        match result {
-            Ok(content) => vec![content],
-            Err(e) => {
-                tracing::error!("failed to convert json content: {e}");
-                vec![]
-            }
+    fn into_call_tool_result(self) -> Result<CallToolResult, crate::Error> {
+        let value = serde_json::to_value(self.0).map_err(|e| {
+            crate::Error::internal_error(
+                format!("failed to serialize structured content: {e}"),
+                None,
+            )
+        })?;
+        Ok(CallToolResult::structured(value))
+    }
+}
+
+impl<T, E> IntoCallToolResult for Result<Json<T>, E>
+where
+    T: Serialize,
+    E: std::fmt::Display,
+{
+    fn into_call_tool_result(self) -> Result<CallToolResult, crate::Error> {
+        match self {
+            Ok(value) => value.into_call_tool_result(),
+            Err(error) => Ok(CallToolResult::error_text(error)),
         }
     }
 }
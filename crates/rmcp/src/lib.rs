@@ -78,7 +78,7 @@
 //!     let tool_result = service
 //!         .call_tool(CallToolRequestParam {
 //!             name: "git_status".into(),
-//!             arguments: serde_json::json!({ "repo_path": "." }).as_object().cloned(),
+//!             arguments: serde_json::json!({ "repo_path": "." }).as_object().cloned().map(Into::into),
 //!         })
 //!         .await?;
 //!     println!("Tool result: {tool_result:#?}");
@@ -120,7 +120,16 @@ pub mod transport;
 pub use paste::paste;
 #[cfg(all(feature = "macros", feature = "server"))]
 #[cfg_attr(docsrs, doc(cfg(all(feature = "macros", feature = "server"))))]
+pub use rmcp_macros::prompt;
+#[cfg(all(feature = "macros", feature = "server"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "macros", feature = "server"))))]
+pub use rmcp_macros::resource;
+#[cfg(all(feature = "macros", feature = "server"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "macros", feature = "server"))))]
 pub use rmcp_macros::tool;
+#[cfg(all(feature = "macros", feature = "client"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "macros", feature = "client"))))]
+pub use rmcp_macros::tool_client;
 #[cfg(all(feature = "macros", feature = "server"))]
 #[cfg_attr(docsrs, doc(cfg(all(feature = "macros", feature = "server"))))]
 pub use schemars;
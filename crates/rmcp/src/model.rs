@@ -1,17 +1,23 @@
 use std::{borrow::Cow, sync::Arc};
 mod annotated;
+mod arguments;
 mod capabilities;
 mod content;
 mod extension;
+#[cfg(feature = "base64")]
+mod image;
 mod meta;
 mod prompt;
 mod resource;
 mod serde_impl;
 mod tool;
 pub use annotated::*;
+pub use arguments::*;
 pub use capabilities::*;
 pub use content::*;
 pub use extension::*;
+#[cfg(feature = "base64")]
+pub use image::*;
 pub use meta::*;
 pub use prompt::*;
 pub use resource::*;
@@ -45,10 +51,49 @@ macro_rules! object {
         })
     };
 }
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Copy, Eq)]
+/// An object without fields, deserialized from any object or `null`, ignoring the keys
+#[derive(Debug, Serialize, Clone, PartialEq, Copy, Eq)]
 #[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
 pub struct EmptyObject {}
 
+impl<'de> Deserialize<'de> for EmptyObject {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct EmptyObjectVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for EmptyObjectVisitor {
+            type Value = EmptyObject;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("an object or null")
+            }
+
+            fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+                Ok(EmptyObject {})
+            }
+
+            fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+                Ok(EmptyObject {})
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                while map
+                    .next_entry::<serde::de::IgnoredAny, serde::de::IgnoredAny>()?
+                    .is_some()
+                {}
+                Ok(EmptyObject {})
+            }
+        }
+
+        deserializer.deserialize_any(EmptyObjectVisitor)
+    }
+}
+
 pub trait ConstString: Default {
     const VALUE: &str;
 }
@@ -110,9 +155,18 @@ macro_rules! const_string {
 
 const_string!(JsonRpcVersion2_0 = "2.0");
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash, PartialOrd)]
-#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
-pub struct ProtocolVersion(Cow<'static, str>);
+/// A revision of the protocol, ordered by date
+///
+/// A revision this SDK doesn't speak is kept as [`ProtocolVersion::Other`], to be negotiated.
+/// Versions are compared by their wire string, whichever variant holds it.
+#[derive(Debug, Clone)]
+#[allow(non_camel_case_types)]
+pub enum ProtocolVersion {
+    V_2024_11_05,
+    V_2025_03_26,
+    V_2025_06_18,
+    Other(Cow<'static, str>),
+}
 
 impl Default for ProtocolVersion {
     fn default() -> Self {
@@ -122,14 +176,119 @@ impl Default for ProtocolVersion {
 
 impl std::fmt::Display for ProtocolVersion {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
+        self.as_str().fmt(f)
+    }
+}
+
+impl PartialEq for ProtocolVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for ProtocolVersion {}
+
+impl std::hash::Hash for ProtocolVersion {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl PartialOrd for ProtocolVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The revisions are dates, the latest is the greatest
+impl Ord for ProtocolVersion {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl std::str::FromStr for ProtocolVersion {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(s.to_owned()))
+    }
+}
+
+impl From<String> for ProtocolVersion {
+    fn from(version: String) -> Self {
+        match version.as_str() {
+            "2024-11-05" => Self::V_2024_11_05,
+            "2025-03-26" => Self::V_2025_03_26,
+            "2025-06-18" => Self::V_2025_06_18,
+            _ => Self::Other(Cow::Owned(version)),
+        }
     }
 }
 
 impl ProtocolVersion {
-    pub const V_2025_03_26: Self = Self(Cow::Borrowed("2025-03-26"));
-    pub const V_2024_11_05: Self = Self(Cow::Borrowed("2024-11-05"));
-    pub const LATEST: Self = Self::V_2025_03_26;
+    pub const LATEST: Self = Self::V_2025_06_18;
+    /// The revisions this SDK speaks, oldest first
+    pub const SUPPORTED: &'static [Self] =
+        &[Self::V_2024_11_05, Self::V_2025_03_26, Self::V_2025_06_18];
+
+    /// The wire string, e.g. `2025-03-26`
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::V_2024_11_05 => "2024-11-05",
+            Self::V_2025_03_26 => "2025-03-26",
+            Self::V_2025_06_18 => "2025-06-18",
+            Self::Other(version) => version,
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        Self::SUPPORTED.contains(self)
+    }
+
+    /// The version a server speaking up to `self` answers a client requesting `requested` with:
+    /// the requested one when both support it, `self` otherwise
+    pub fn negotiate(&self, requested: &Self) -> Self {
+        if requested.is_supported() && requested <= self {
+            requested.clone()
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Whether a client requesting this version can be answered: a supported revision, or a
+    /// later one which is answered with the latest supported
+    pub fn is_negotiable(&self) -> bool {
+        let is_date = |version: &str| {
+            version.len() == 10
+                && version.char_indices().all(|(i, c)| match i {
+                    4 | 7 => c == '-',
+                    _ => c.is_ascii_digit(),
+                })
+        };
+        self.is_supported() || (is_date(self.as_str()) && *self > Self::LATEST)
+    }
+
+    /// The error answering a client requesting a version which can't be negotiated
+    pub fn unsupported_error(&self) -> ErrorData {
+        ErrorData::invalid_params(
+            "Unsupported protocol version",
+            Some(serde_json::json!({
+                "supported": Self::SUPPORTED,
+                "requested": self,
+            })),
+        )
+    }
+
+    /// Audio contents, from `2025-03-26`
+    pub fn supports_audio_content(&self) -> bool {
+        *self >= Self::V_2025_03_26
+    }
+
+    /// The `structuredContent` of tool results, from `2025-06-18`
+    pub fn supports_structured_content(&self) -> bool {
+        *self >= Self::V_2025_06_18
+    }
 }
 
 impl Serialize for ProtocolVersion {
@@ -137,7 +296,7 @@ impl Serialize for ProtocolVersion {
     where
         S: serde::Serializer,
     {
-        self.0.serialize(serializer)
+        self.as_str().serialize(serializer)
     }
 }
 
@@ -146,20 +305,32 @@ impl<'de> Deserialize<'de> for ProtocolVersion {
     where
         D: serde::Deserializer<'de>,
     {
-        let s: String = Deserialize::deserialize(deserializer)?;
-        #[allow(clippy::single_match)]
-        match s.as_str() {
-            "2024-11-05" => return Ok(ProtocolVersion::V_2024_11_05),
-            "2025-03-26" => return Ok(ProtocolVersion::V_2025_03_26),
-            _ => {}
-        }
-        Ok(ProtocolVersion(Cow::Owned(s)))
+        let version: String = Deserialize::deserialize(deserializer)?;
+        Ok(Self::from(version))
     }
 }
 
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for ProtocolVersion {
+    fn schema_name() -> String {
+        "ProtocolVersion".to_string()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::schema::Schema {
+        schemars::schema::Schema::Object(schemars::schema::SchemaObject {
+            instance_type: Some(schemars::schema::InstanceType::String.into()),
+            ..Default::default()
+        })
+    }
+}
+
+/// A JSON-RPC id, kept as the number or the string it was received as
+///
+/// A response must carry the id of its request in the same representation, `1` and `"1"` are two
+/// different ids.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum NumberOrString {
-    Number(u32),
+    Number(i64),
     String(Arc<str>),
 }
 
@@ -172,6 +343,24 @@ impl NumberOrString {
     }
 }
 
+impl From<i64> for NumberOrString {
+    fn from(value: i64) -> Self {
+        NumberOrString::Number(value)
+    }
+}
+
+impl From<&str> for NumberOrString {
+    fn from(value: &str) -> Self {
+        NumberOrString::String(value.into())
+    }
+}
+
+impl From<String> for NumberOrString {
+    fn from(value: String) -> Self {
+        NumberOrString::String(value.into())
+    }
+}
+
 impl std::fmt::Display for NumberOrString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -200,10 +389,10 @@ impl<'de> Deserialize<'de> for NumberOrString {
     {
         let value: Value = Deserialize::deserialize(deserializer)?;
         match value {
-            Value::Number(n) => Ok(NumberOrString::Number(
-                n.as_u64()
-                    .ok_or(serde::de::Error::custom("Expect an integer"))? as u32,
-            )),
+            // an id out of range would be answered with another id, it is rejected instead
+            Value::Number(n) => Ok(NumberOrString::Number(n.as_i64().ok_or(
+                serde::de::Error::custom("Expect an integer in the range of i64"),
+            )?)),
             Value::String(s) => Ok(NumberOrString::String(s.into())),
             _ => Err(serde::de::Error::custom("Expect number or string")),
         }
@@ -242,6 +431,24 @@ pub type RequestId = NumberOrString;
 #[serde(transparent)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ProgressToken(pub NumberOrString);
+
+impl From<u32> for ProgressToken {
+    fn from(value: u32) -> Self {
+        Self(NumberOrString::Number(value.into()))
+    }
+}
+
+impl From<&str> for ProgressToken {
+    fn from(value: &str) -> Self {
+        Self(NumberOrString::String(value.into()))
+    }
+}
+
+impl From<String> for ProgressToken {
+    fn from(value: String) -> Self {
+        Self(NumberOrString::String(value.into()))
+    }
+}
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Request<M = String, P = JsonObject> {
@@ -319,7 +526,7 @@ pub struct NotificationNoParam<M = String> {
     pub extensions: Extensions,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct JsonRpcRequest<R = Request> {
     pub jsonrpc: JsonRpcVersion2_0,
@@ -345,7 +552,7 @@ pub struct JsonRpcError {
     pub error: ErrorData,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct JsonRpcNotification<N = Notification> {
     pub jsonrpc: JsonRpcVersion2_0,
@@ -361,11 +568,60 @@ pub struct ErrorCode(pub i32);
 
 impl ErrorCode {
     pub const RESOURCE_NOT_FOUND: Self = Self(-32002);
+    /// The request exceeded a rate limit of the server, it can be retried later
+    pub const RATE_LIMITED: Self = Self(-32029);
     pub const INVALID_REQUEST: Self = Self(-32600);
     pub const METHOD_NOT_FOUND: Self = Self(-32601);
     pub const INVALID_PARAMS: Self = Self(-32602);
     pub const INTERNAL_ERROR: Self = Self(-32603);
     pub const PARSE_ERROR: Self = Self(-32700);
+    /// The codes reserved for implementation-defined server errors
+    pub const SERVER_ERROR_RANGE: std::ops::RangeInclusive<i32> = -32099..=-32000;
+
+    pub fn kind(self) -> ErrorKind {
+        match self {
+            Self::PARSE_ERROR => ErrorKind::ParseError,
+            Self::INVALID_REQUEST => ErrorKind::InvalidRequest,
+            Self::METHOD_NOT_FOUND => ErrorKind::MethodNotFound,
+            Self::INVALID_PARAMS => ErrorKind::InvalidParams,
+            Self::INTERNAL_ERROR => ErrorKind::InternalError,
+            Self(code) if Self::SERVER_ERROR_RANGE.contains(&code) => ErrorKind::ServerError(code),
+            Self(code) => ErrorKind::Other(code),
+        }
+    }
+}
+
+/// An [`ErrorCode`] told apart as one of the standard JSON-RPC codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    ParseError,
+    InvalidRequest,
+    MethodNotFound,
+    InvalidParams,
+    InternalError,
+    /// A code of [`ErrorCode::SERVER_ERROR_RANGE`], e.g. [`ErrorCode::RESOURCE_NOT_FOUND`]
+    ServerError(i32),
+    /// A code defined by the application
+    Other(i32),
+}
+
+impl From<ErrorKind> for ErrorCode {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::ParseError => Self::PARSE_ERROR,
+            ErrorKind::InvalidRequest => Self::INVALID_REQUEST,
+            ErrorKind::MethodNotFound => Self::METHOD_NOT_FOUND,
+            ErrorKind::InvalidParams => Self::INVALID_PARAMS,
+            ErrorKind::InternalError => Self::INTERNAL_ERROR,
+            ErrorKind::ServerError(code) | ErrorKind::Other(code) => Self(code),
+        }
+    }
+}
+
+impl From<ErrorCode> for ErrorKind {
+    fn from(code: ErrorCode) -> Self {
+        code.kind()
+    }
 }
 
 /// Error information for JSON-RPC error responses.
@@ -414,9 +670,60 @@ impl ErrorData {
     pub fn internal_error(message: impl Into<Cow<'static, str>>, data: Option<Value>) -> Self {
         Self::new(ErrorCode::INTERNAL_ERROR, message, data)
     }
+    /// A call of a tool the server doesn't have, or has disabled, with `{ "name": name }` as data
+    pub fn tool_not_found(name: &str) -> Self {
+        Self::invalid_params("tool not found", Some(serde_json::json!({ "name": name })))
+    }
+    /// A get of a prompt the server doesn't have, with `{ "name": name }` as data
+    pub fn prompt_not_found(name: &str) -> Self {
+        Self::invalid_params(
+            "prompt not found",
+            Some(serde_json::json!({ "name": name })),
+        )
+    }
+    /// A server error, of a code in [`ErrorCode::SERVER_ERROR_RANGE`]
+    ///
+    /// The code is kept as given, a code out of the range panics in debug builds only: use
+    /// [`ErrorData::new`] for the codes defined elsewhere.
+    pub fn server_error(
+        code: i32,
+        message: impl Into<Cow<'static, str>>,
+        data: Option<Value>,
+    ) -> Self {
+        debug_assert!(
+            ErrorCode::SERVER_ERROR_RANGE.contains(&code),
+            "server error code {code} out of {:?}",
+            ErrorCode::SERVER_ERROR_RANGE
+        );
+        Self::new(ErrorCode(code), message, data)
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.code.kind()
+    }
+
+    /// Set the data to `data` serialized
+    ///
+    /// If `data` doesn't serialize the failure is logged and the data is left as it was.
+    pub fn with_data<T: Serialize>(mut self, data: T) -> Self {
+        match serde_json::to_value(data) {
+            Ok(data) => self.data = Some(data),
+            Err(error) => {
+                tracing::warn!(%error, code = self.code.0, "fail to serialize error data")
+            }
+        }
+        self
+    }
+
+    /// The data read as a `T`, `None` if there is none or it isn't a `T`
+    pub fn data_as<T: serde::de::DeserializeOwned>(&self) -> Option<T> {
+        self.data
+            .as_ref()
+            .and_then(|data| T::deserialize(data).ok())
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Clone, PartialEq)]
 #[serde(untagged)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum JsonRpcBatchRequestItem<Req, Not> {
@@ -450,7 +757,7 @@ impl<Resp> JsonRpcBatchResponseItem<Resp> {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Clone, PartialEq)]
 #[serde(untagged)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum JsonRpcMessage<Req = Request, Resp = DefaultResponse, Noti = Notification> {
@@ -518,6 +825,22 @@ impl<Req, Resp, Not> JsonRpcMessage<Req, Resp, Not> {
             _ => None,
         }
     }
+    /// The item of a batch request, for a request or a notification
+    pub fn into_batch_request_item(self) -> Result<JsonRpcBatchRequestItem<Req, Not>, Self> {
+        match self {
+            JsonRpcMessage::Request(r) => Ok(JsonRpcBatchRequestItem::Request(r)),
+            JsonRpcMessage::Notification(n) => Ok(JsonRpcBatchRequestItem::Notification(n)),
+            message => Err(message),
+        }
+    }
+    /// The item of a batch response, for a response or an error
+    pub fn into_batch_response_item(self) -> Result<JsonRpcBatchResponseItem<Resp>, Self> {
+        match self {
+            JsonRpcMessage::Response(r) => Ok(JsonRpcBatchResponseItem::Response(r)),
+            JsonRpcMessage::Error(e) => Ok(JsonRpcBatchResponseItem::Error(e)),
+            message => Err(message),
+        }
+    }
     pub fn into_result(self) -> Option<(Result<Resp, ErrorData>, RequestId)> {
         match self {
             JsonRpcMessage::Response(r) => Some((Ok(r.result), r.id)),
@@ -589,6 +912,8 @@ pub struct InitializeResult {
     pub server_info: Implementation,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub instructions: Option<String>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
 pub type ServerInfo = InitializeResult;
@@ -601,6 +926,7 @@ impl Default for ServerInfo {
             capabilities: ServerCapabilities::default(),
             server_info: Implementation::from_build_env(),
             instructions: None,
+            meta: None,
         }
     }
 }
@@ -615,6 +941,42 @@ impl Default for ClientInfo {
     }
 }
 
+/// Building the `initialize` params of a client, which serve as a client handler too
+///
+/// ```rust
+/// # use rmcp::model::{ClientCapabilities, ClientInfo, Implementation};
+/// let info = ClientInfo::new(Implementation::new("my-client", "1.0.0"))
+///     .with_capabilities(ClientCapabilities::builder().enable_roots().build())
+///     .with_experimental("my-extension", Default::default());
+/// ```
+impl ClientInfo {
+    pub fn new(client_info: Implementation) -> Self {
+        ClientInfo {
+            client_info,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_protocol_version(mut self, protocol_version: ProtocolVersion) -> Self {
+        self.protocol_version = protocol_version;
+        self
+    }
+
+    pub fn with_capabilities(mut self, capabilities: impl Into<ClientCapabilities>) -> Self {
+        self.capabilities = capabilities.into();
+        self
+    }
+
+    /// Declare the experimental capability `name`, keeping the other capabilities
+    pub fn with_experimental(mut self, name: impl Into<String>, value: JsonObject) -> Self {
+        self.capabilities
+            .experimental
+            .get_or_insert_with(Default::default)
+            .insert(name.into(), value);
+        self
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Implementation {
@@ -629,6 +991,13 @@ impl Default for Implementation {
 }
 
 impl Implementation {
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Implementation {
+            name: name.into(),
+            version: version.into(),
+        }
+    }
+
     pub fn from_build_env() -> Self {
         Implementation {
             name: env!("CARGO_CRATE_NAME").to_owned(),
@@ -654,10 +1023,10 @@ const_string!(ProgressNotificationMethod = "notifications/progress");
 pub struct ProgressNotificationParam {
     pub progress_token: ProgressToken,
     /// The progress thus far. This should increase every time progress is made, even if the total is unknown.
-    pub progress: u32,
+    pub progress: f64,
     /// Total number of items to process (or total progress required), if known
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub total: Option<u32>,
+    pub total: Option<f64>,
     /// An optional message describing the current progress.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
@@ -678,6 +1047,8 @@ macro_rules! paginated_result {
             #[serde(skip_serializing_if = "Option::is_none")]
             pub next_cursor: Option<Cursor>,
             pub $i_item: $t_item,
+            #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+            pub meta: Option<Meta>,
         }
     };
 }
@@ -708,6 +1079,8 @@ pub struct ReadResourceRequestParam {
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ReadResourceResult {
     pub contents: Vec<ResourceContents>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
 pub type ReadResourceRequest = Request<ReadResourceRequestMethod, ReadResourceRequestParam>;
@@ -767,7 +1140,8 @@ pub type PromptListChangedNotification = NotificationNoParam<PromptListChangedNo
 const_string!(ToolListChangedNotificationMethod = "notifications/tools/list_changed");
 pub type ToolListChangedNotification = NotificationNoParam<ToolListChangedNotificationMethod>;
 // 日志相关
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Copy)]
+/// Severity of a log message, ordered from the least to the most severe
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Copy)]
 #[serde(rename_all = "lowercase")] //match spec
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum LoggingLevel {
@@ -821,6 +1195,21 @@ pub struct SamplingMessage {
     pub content: Content,
 }
 
+impl SamplingMessage {
+    pub fn user_text(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: Content::text(text.into()),
+        }
+    }
+    pub fn assistant_text(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::Assistant,
+            content: Content::text(text.into()),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum ContextInclusion {
@@ -852,6 +1241,33 @@ pub struct CreateMessageRequestParam {
     pub metadata: Option<Value>,
 }
 
+impl CreateMessageRequestParam {
+    pub fn new(messages: Vec<SamplingMessage>, max_tokens: u32) -> Self {
+        Self {
+            messages,
+            model_preferences: None,
+            system_prompt: None,
+            include_context: None,
+            temperature: None,
+            max_tokens,
+            stop_sequences: None,
+            metadata: None,
+        }
+    }
+    pub fn with_model_preferences(mut self, model_preferences: ModelPreferences) -> Self {
+        self.model_preferences = Some(model_preferences);
+        self
+    }
+    pub fn with_system_prompt(mut self, system_prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -883,7 +1299,7 @@ pub struct CompleteRequestParam {
 
 pub type CompleteRequest = Request<CompleteRequestMethod, CompleteRequestParam>;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CompletionInfo {
@@ -894,14 +1310,53 @@ pub struct CompletionInfo {
     pub has_more: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+impl CompletionInfo {
+    /// The most values a completion may carry
+    pub const MAX_VALUES: usize = 100;
+
+    /// A completion of `values`, cut to [`Self::MAX_VALUES`] with `total` and `has_more` set
+    pub fn from_values(mut values: Vec<String>) -> Self {
+        let total = values.len();
+        values.truncate(Self::MAX_VALUES);
+        Self {
+            has_more: Some(total > values.len()),
+            total: Some(total as u32),
+            values,
+        }
+    }
+
+    /// A completion of the `candidates` starting with `prefix`
+    pub fn matching<I>(candidates: I, prefix: &str) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self::from_values(
+            candidates
+                .into_iter()
+                .map(Into::into)
+                .filter(|candidate: &String| candidate.starts_with(prefix))
+                .collect(),
+        )
+    }
+}
+
+impl From<Vec<String>> for CompletionInfo {
+    fn from(values: Vec<String>) -> Self {
+        Self::from_values(values)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CompleteResult {
     pub completion: CompletionInfo,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[serde(tag = "type")]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Reference {
@@ -911,13 +1366,13 @@ pub enum Reference {
     Prompt(PromptReference),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ResourceReference {
     pub uri: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PromptReference {
     pub name: String,
@@ -949,6 +1404,8 @@ pub type ListRootsRequest = RequestNoParam<ListRootsRequestMethod>;
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ListRootsResult {
     pub roots: Vec<Root>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
 const_string!(RootsListChangedNotificationMethod = "notifications/roots/list_changed");
@@ -961,6 +1418,11 @@ pub struct CallToolResult {
     pub content: Vec<Content>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
+    /// Typed result of a tool with an output schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
 impl CallToolResult {
@@ -968,13 +1430,62 @@ impl CallToolResult {
         CallToolResult {
             content,
             is_error: Some(false),
+            structured_content: None,
+            meta: None,
         }
     }
     pub fn error(content: Vec<Content>) -> Self {
         CallToolResult {
             content,
             is_error: Some(true),
+            structured_content: None,
+            meta: None,
+        }
+    }
+    /// A successful result with structured content, and its json text as a fallback for older clients
+    pub fn structured(value: Value) -> Self {
+        CallToolResult {
+            content: vec![Content::text(value.to_string())],
+            is_error: Some(false),
+            structured_content: Some(value),
+            meta: None,
+        }
+    }
+    /// A successful result with one text content
+    pub fn text(text: impl std::fmt::Display) -> Self {
+        Self::success(vec![Content::text(text.to_string())])
+    }
+    /// A failed result with one text content, reported to the model instead of as a protocol error
+    pub fn error_text(text: impl std::fmt::Display) -> Self {
+        Self::error(vec![Content::text(text.to_string())])
+    }
+    /// A successful result with `value` as structured content, pretty-printed as its text
+    pub fn json<T: Serialize + ?Sized>(value: &T) -> Result<Self, crate::Error> {
+        let value = serde_json::to_value(value).map_err(|e| {
+            crate::Error::internal_error(
+                format!("failed to serialize structured content: {e}"),
+                None,
+            )
+        })?;
+        let text = serde_json::to_string_pretty(&value).expect("a json value always serializes");
+        Ok(CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: Some(false),
+            structured_content: Some(value),
+            meta: None,
+        })
+    }
+    /// The result as a client speaking `version` reads it: without the structured content or the
+    /// audio contents it doesn't know yet
+    pub fn for_version(mut self, version: &ProtocolVersion) -> Self {
+        if !version.supports_structured_content() {
+            self.structured_content = None;
         }
+        if !version.supports_audio_content() {
+            self.content
+                .retain(|content| !matches!(content.raw, RawContent::Audio(_)));
+        }
+        self
     }
 }
 
@@ -993,7 +1504,7 @@ const_string!(CallToolRequestMethod = "tools/call");
 pub struct CallToolRequestParam {
     pub name: Cow<'static, str>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub arguments: Option<JsonObject>,
+    pub arguments: Option<ToolArguments>,
 }
 
 pub type CallToolRequest = Request<CallToolRequestMethod, CallToolRequestParam>;
@@ -1007,6 +1518,8 @@ pub struct CreateMessageResult {
     pub stop_reason: Option<String>,
     #[serde(flatten)]
     pub message: SamplingMessage,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
 impl CreateMessageResult {
@@ -1015,6 +1528,63 @@ impl CreateMessageResult {
     pub const STOP_REASON_END_MAX_TOKEN: &str = "maxTokens";
 }
 
+// 信息征询相关
+const_string!(CreateElicitationRequestMethod = "elicitation/create");
+pub type CreateElicitationRequest =
+    Request<CreateElicitationRequestMethod, CreateElicitationRequestParam>;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CreateElicitationRequestParam {
+    pub message: String,
+    pub requested_schema: JsonObject,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum ElicitationAction {
+    Accept,
+    Decline,
+    Cancel,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CreateElicitationResult {
+    pub action: ElicitationAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<JsonObject>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl CreateElicitationResult {
+    pub fn accept(content: JsonObject) -> Self {
+        Self {
+            action: ElicitationAction::Accept,
+            content: Some(content),
+            meta: None,
+        }
+    }
+    pub fn decline() -> Self {
+        Self {
+            action: ElicitationAction::Decline,
+            content: None,
+            meta: None,
+        }
+    }
+    pub fn cancel() -> Self {
+        Self {
+            action: ElicitationAction::Cancel,
+            content: None,
+            meta: None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -1022,6 +1592,123 @@ pub struct GetPromptResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub messages: Vec<PromptMessage>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// The methods of the requests the SDK knows, in both directions
+const KNOWN_REQUEST_METHODS: [&str; 16] = [
+    PingRequestMethod::VALUE,
+    InitializeResultMethod::VALUE,
+    CompleteRequestMethod::VALUE,
+    SetLevelRequestMethod::VALUE,
+    GetPromptRequestMethod::VALUE,
+    ListPromptsRequestMethod::VALUE,
+    ListResourcesRequestMethod::VALUE,
+    ListResourceTemplatesRequestMethod::VALUE,
+    ReadResourceRequestMethod::VALUE,
+    SubscribeRequestMethod::VALUE,
+    UnsubscribeRequestMethod::VALUE,
+    CallToolRequestMethod::VALUE,
+    ListToolsRequestMethod::VALUE,
+    CreateMessageRequestMethod::VALUE,
+    ListRootsRequestMethod::VALUE,
+    CreateElicitationRequestMethod::VALUE,
+];
+
+/// The methods of the notifications the SDK knows, in both directions
+const KNOWN_NOTIFICATION_METHODS: [&str; 9] = [
+    CancelledNotificationMethod::VALUE,
+    ProgressNotificationMethod::VALUE,
+    InitializedNotificationMethod::VALUE,
+    RootsListChangedNotificationMethod::VALUE,
+    LoggingMessageNotificationMethod::VALUE,
+    ResourceUpdatedNotificationMethod::VALUE,
+    ResourceListChangedNotificationMethod::VALUE,
+    ToolListChangedNotificationMethod::VALUE,
+    PromptListChangedNotificationMethod::VALUE,
+];
+
+pub(crate) fn is_known_request_method(method: &str) -> bool {
+    KNOWN_REQUEST_METHODS.contains(&method)
+}
+
+pub(crate) fn is_known_notification_method(method: &str) -> bool {
+    KNOWN_NOTIFICATION_METHODS.contains(&method)
+}
+
+/// A request of a method the SDK doesn't know, e.g. sent by a newer peer
+///
+/// Its params are kept as they were received, `_meta` included, which is also read in the
+/// [`Meta`] of its extensions; the [`Meta`] of its extensions is merged in `_meta` when sent. The method
+/// can't be one the SDK knows.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CustomRequest {
+    pub method: String,
+    pub params: Option<Value>,
+    #[cfg_attr(feature = "schemars", schemars(skip))]
+    pub extensions: Extensions,
+}
+
+impl CustomRequest {
+    pub fn new(method: impl Into<String>, params: Option<Value>) -> Self {
+        Self {
+            method: method.into(),
+            params,
+            extensions: Extensions::default(),
+        }
+    }
+}
+
+/// A notification of a method the SDK doesn't know, e.g. sent by a newer peer
+///
+/// Its params are kept as they were received, `_meta` included, which is also read in the
+/// [`Meta`] of its extensions; the [`Meta`] of its extensions is merged in `_meta` when sent. The method
+/// can't be one the SDK knows.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CustomNotification {
+    pub method: String,
+    pub params: Option<Value>,
+    #[cfg_attr(feature = "schemars", schemars(skip))]
+    pub extensions: Extensions,
+}
+
+impl CustomNotification {
+    pub fn new(method: impl Into<String>, params: Option<Value>) -> Self {
+        Self {
+            method: method.into(),
+            params,
+            extensions: Extensions::default(),
+        }
+    }
+}
+
+/// The result of a [`CustomRequest`]
+///
+/// A result received is read as one of the known results first, then as a `CustomResult` if it
+/// has fields an [`EmptyResult`] would drop: `null`, `{}` and an object with only `_meta` are
+/// empty results.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(transparent)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CustomResult(pub Value);
+
+impl<'de> Deserialize<'de> for CustomResult {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        match &value {
+            Value::Null => Err(serde::de::Error::custom("an empty result")),
+            Value::Object(object) if object.keys().all(|key| key == "_meta") => {
+                Err(serde::de::Error::custom("an empty result"))
+            }
+            _ => Ok(CustomResult(value)),
+        }
+    }
 }
 
 macro_rules! ts_union {
@@ -1038,7 +1725,49 @@ macro_rules! ts_union {
     };
 }
 
-ts_union!(
+/// A union of requests or notifications, told apart by their method
+///
+/// It is serialized as an untagged enum. It is deserialized from the JSON text of the message by
+/// the variants which accept its method, tried in order, so the params are read from the text
+/// instead of a buffered copy of the message.
+macro_rules! method_union {
+    (
+        export type $U: ident =
+            $(|)?$($V: ident)|*;
+    ) => {
+        #[derive(Debug, Serialize, Clone)]
+        #[serde(untagged)]
+        #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+        pub enum $U {
+            $($V($V),)*
+        }
+
+        impl<'de> Deserialize<'de> for $U {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                use serde_impl::FromRawMessage;
+                let raw = Box::<serde_json::value::RawValue>::deserialize(deserializer)?;
+                if let Some(method) = serde_impl::method_of(&raw) {
+                    $(
+                        if $V::accepts(&method) {
+                            if let Ok(message) = $V::from_raw(&raw) {
+                                return Ok($U::$V(message));
+                            }
+                        }
+                    )*
+                }
+                Err(serde::de::Error::custom(concat!(
+                    "data did not match any variant of untagged enum ",
+                    stringify!($U)
+                )))
+            }
+        }
+    };
+}
+
+method_union!(
     export type ClientRequest =
     | PingRequest
     | InitializeRequest
@@ -1052,19 +1781,26 @@ ts_union!(
     | SubscribeRequest
     | UnsubscribeRequest
     | CallToolRequest
-    | ListToolsRequest;
+    | ListToolsRequest
+    | CustomRequest;
 );
 
-ts_union!(
+method_union!(
     export type ClientNotification =
     | CancelledNotification
     | ProgressNotification
     | InitializedNotification
-    | RootsListChangedNotification;
+    | RootsListChangedNotification
+    | CustomNotification;
 );
 
 ts_union!(
-    export type ClientResult = CreateMessageResult | ListRootsResult | EmptyResult;
+    export type ClientResult =
+    | CreateMessageResult
+    | ListRootsResult
+    | CreateElicitationResult
+    | CustomResult
+    | EmptyResult;
 );
 
 impl ClientResult {
@@ -1075,14 +1811,16 @@ impl ClientResult {
 
 pub type ClientJsonRpcMessage = JsonRpcMessage<ClientRequest, ClientResult, ClientNotification>;
 
-ts_union!(
+method_union!(
     export type ServerRequest =
     | PingRequest
     | CreateMessageRequest
-    | ListRootsRequest;
+    | ListRootsRequest
+    | CreateElicitationRequest
+    | CustomRequest;
 );
 
-ts_union!(
+method_union!(
     export type ServerNotification =
     | CancelledNotification
     | ProgressNotification
@@ -1090,7 +1828,8 @@ ts_union!(
     | ResourceUpdatedNotification
     | ResourceListChangedNotification
     | ToolListChangedNotification
-    | PromptListChangedNotification;
+    | PromptListChangedNotification
+    | CustomNotification;
 );
 
 ts_union!(
@@ -1104,6 +1843,7 @@ ts_union!(
     | ReadResourceResult
     | CallToolResult
     | ListToolsResult
+    | CustomResult
     | EmptyResult
     ;
 );
@@ -1149,12 +1889,100 @@ impl From<CancelledNotification> for ClientNotification {
     }
 }
 
+impl TryInto<ProgressNotification> for ServerNotification {
+    type Error = ServerNotification;
+    fn try_into(self) -> Result<ProgressNotification, Self::Error> {
+        if let ServerNotification::ProgressNotification(t) = self {
+            Ok(t)
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl TryInto<ProgressNotification> for ClientNotification {
+    type Error = ClientNotification;
+    fn try_into(self) -> Result<ProgressNotification, Self::Error> {
+        if let ClientNotification::ProgressNotification(t) = self {
+            Ok(t)
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl From<ProgressNotification> for ServerNotification {
+    fn from(value: ProgressNotification) -> Self {
+        ServerNotification::ProgressNotification(value)
+    }
+}
+
+impl From<ProgressNotification> for ClientNotification {
+    fn from(value: ProgressNotification) -> Self {
+        ClientNotification::ProgressNotification(value)
+    }
+}
+
+impl From<PingRequest> for ServerRequest {
+    fn from(value: PingRequest) -> Self {
+        ServerRequest::PingRequest(value)
+    }
+}
+
+impl From<PingRequest> for ClientRequest {
+    fn from(value: PingRequest) -> Self {
+        ClientRequest::PingRequest(value)
+    }
+}
+
+impl From<CustomRequest> for ServerRequest {
+    fn from(value: CustomRequest) -> Self {
+        ServerRequest::CustomRequest(value)
+    }
+}
+
+impl From<CustomRequest> for ClientRequest {
+    fn from(value: CustomRequest) -> Self {
+        ClientRequest::CustomRequest(value)
+    }
+}
+
+impl From<CustomNotification> for ServerNotification {
+    fn from(value: CustomNotification) -> Self {
+        ServerNotification::CustomNotification(value)
+    }
+}
+
+impl From<CustomNotification> for ClientNotification {
+    fn from(value: CustomNotification) -> Self {
+        ClientNotification::CustomNotification(value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
 
     use super::*;
 
+    #[test]
+    fn test_empty_object_tolerance() {
+        for value in [json!({}), json!(null), json!({ "unexpected": [1, 2] })] {
+            let empty: EmptyObject = serde_json::from_value(value).expect("an empty object");
+            assert_eq!(empty, EmptyObject {});
+        }
+        assert!(serde_json::from_value::<EmptyObject>(json!("text")).is_err());
+        assert_eq!(serde_json::to_value(EmptyObject {}).unwrap(), json!({}));
+
+        // missing or null arguments of a tool call
+        let param: CallToolRequestParam =
+            serde_json::from_value(json!({ "name": "ping", "arguments": null })).unwrap();
+        assert_eq!(param.arguments, None);
+        let param: CallToolRequestParam =
+            serde_json::from_value(json!({ "name": "ping" })).unwrap();
+        assert_eq!(param.arguments, None);
+    }
+
     #[test]
     fn test_notification_serde() {
         let raw = json!( {
@@ -1280,6 +2108,7 @@ mod tests {
                 capabilities,
                 server_info,
                 instructions,
+                ..
             }) => {
                 assert_eq!(capabilities.logging.unwrap().len(), 0);
                 assert_eq!(capabilities.prompts.unwrap().list_changed, Some(true));
@@ -1307,4 +2136,318 @@ mod tests {
         let v2 = ProtocolVersion::V_2025_03_26;
         assert!(v1 < v2);
     }
+
+    #[test]
+    fn test_protocol_version_negotiation() {
+        let parse =
+            |version: &str| -> ProtocolVersion { serde_json::from_value(json!(version)).unwrap() };
+        assert_eq!(parse("2025-03-26"), ProtocolVersion::V_2025_03_26);
+        assert_eq!(
+            serde_json::to_value(parse("2099-01-01")).unwrap(),
+            json!("2099-01-01")
+        );
+
+        let server = ProtocolVersion::LATEST;
+        for version in ProtocolVersion::SUPPORTED {
+            assert_eq!(server.negotiate(version), *version);
+        }
+        // a later version gets the latest one, an older server answers with its own
+        assert_eq!(server.negotiate(&parse("2099-01-01")), server);
+        assert_eq!(
+            ProtocolVersion::V_2024_11_05.negotiate(&ProtocolVersion::V_2025_03_26),
+            ProtocolVersion::V_2024_11_05
+        );
+
+        assert!(parse("2099-01-01").is_negotiable());
+        assert!(!parse("2024-01-01").is_negotiable());
+        assert!(!parse("1.0.0").is_negotiable());
+        assert!(!ProtocolVersion::V_2024_11_05.supports_audio_content());
+        assert!(ProtocolVersion::V_2025_03_26.supports_audio_content());
+        assert!(!ProtocolVersion::V_2025_03_26.supports_structured_content());
+    }
+
+    #[test]
+    fn test_protocol_version_equality() {
+        assert_eq!(ProtocolVersion::LATEST.as_str(), "2025-06-18");
+        assert_eq!(
+            ClientInfo::default().protocol_version,
+            ProtocolVersion::LATEST
+        );
+        // the same wire string is the same version, consistently with the order
+        let other = ProtocolVersion::Other(Cow::Borrowed("2025-03-26"));
+        assert_eq!(other, ProtocolVersion::V_2025_03_26);
+        assert_eq!(
+            other.cmp(&ProtocolVersion::V_2025_03_26),
+            std::cmp::Ordering::Equal
+        );
+        assert!(other.is_supported());
+        let versions = std::collections::HashSet::from([other, ProtocolVersion::V_2025_03_26]);
+        assert_eq!(versions.len(), 1);
+    }
+
+    #[test]
+    fn test_result_meta_round_trip() {
+        fn round_trip<T>(value: serde_json::Value) -> T
+        where
+            T: Serialize + serde::de::DeserializeOwned,
+        {
+            let result: T = serde_json::from_value(value.clone()).unwrap();
+            assert_eq!(serde_json::to_value(&result).unwrap(), value);
+            result
+        }
+        let meta = json!({ "traceId": "4bf92f3577b34da6", "progressToken": 7 });
+        let with_meta = |mut value: serde_json::Value| {
+            value["_meta"] = meta.clone();
+            value
+        };
+
+        let result: InitializeResult = round_trip(with_meta(json!({
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "serverInfo": { "name": "server", "version": "1.0.0" },
+        })));
+        assert_eq!(
+            result.meta.unwrap().get::<String>("traceId").unwrap(),
+            "4bf92f3577b34da6"
+        );
+        let result: CallToolResult = round_trip(with_meta(json!({
+            "content": [{ "type": "text", "text": "42" }],
+            "isError": false,
+        })));
+        assert_eq!(
+            result.meta.unwrap().progress_token(),
+            Some(ProgressToken(NumberOrString::Number(7)))
+        );
+        let _: ListToolsResult = round_trip(with_meta(json!({ "tools": [] })));
+        let _: ListPromptsResult = round_trip(with_meta(json!({ "prompts": [] })));
+        let _: ListResourcesResult = round_trip(with_meta(json!({ "resources": [] })));
+        let _: ListResourceTemplatesResult =
+            round_trip(with_meta(json!({ "resourceTemplates": [] })));
+        let _: ReadResourceResult = round_trip(with_meta(json!({ "contents": [] })));
+        let _: CompleteResult = round_trip(with_meta(json!({ "completion": { "values": [] } })));
+        let _: GetPromptResult = round_trip(with_meta(json!({ "messages": [] })));
+        let _: ListRootsResult = round_trip(with_meta(json!({ "roots": [] })));
+        let _: CreateElicitationResult = round_trip(with_meta(json!({ "action": "decline" })));
+        let _: CreateMessageResult = round_trip(with_meta(json!({
+            "model": "mock-model",
+            "role": "assistant",
+            "content": { "type": "text", "text": "hi" },
+        })));
+
+        // without a `_meta`, none is written
+        let result = CallToolResult::text("42");
+        assert!(result.meta.is_none());
+        assert!(
+            serde_json::to_value(&result)
+                .unwrap()
+                .get("_meta")
+                .is_none()
+        );
+
+        // a result read as one of the union keeps its `_meta`
+        let result: ServerResult =
+            serde_json::from_value(with_meta(json!({ "content": [] }))).unwrap();
+        let ServerResult::CallToolResult(result) = result else {
+            panic!("expected a call tool result");
+        };
+        assert_eq!(result.meta, Some(Meta(object(meta.clone()))));
+    }
+
+    #[test]
+    fn test_request_meta_round_trip() {
+        let raw = json!({
+            "method": "tools/call",
+            "params": {
+                "name": "whoami",
+                "arguments": { "verbose": true },
+                "_meta": { "progressToken": "call-1", "traceId": "4bf92f3577b34da6" },
+            },
+        });
+        let request: CallToolRequest = serde_json::from_value(raw.clone()).unwrap();
+        let meta = request.extensions.get::<Meta>().unwrap();
+        assert_eq!(
+            meta.progress_token(),
+            Some(ProgressToken(NumberOrString::String("call-1".into())))
+        );
+        // the `_meta` isn't mixed into the arguments
+        assert_eq!(
+            request.params.arguments,
+            Some(object(json!({ "verbose": true })).into())
+        );
+        assert_eq!(serde_json::to_value(&request).unwrap(), raw);
+    }
+
+    #[test]
+    fn test_meta_accessors() {
+        let mut meta = Meta::new();
+        assert_eq!(meta.progress_token(), None);
+        meta.set_progress_token(ProgressToken(NumberOrString::Number(3)));
+        assert_eq!(meta.get::<u32>("progressToken"), Some(3));
+
+        assert_eq!(meta.insert("retries", 2u8).unwrap(), None);
+        assert_eq!(meta.insert("retries", 5u8).unwrap(), Some(json!(2)));
+        assert_eq!(meta.get::<u8>("retries"), Some(5));
+        // a field of another type reads as missing
+        assert_eq!(meta.get::<String>("retries"), None);
+        assert_eq!(meta.get::<u8>("missing"), None);
+    }
+
+    #[test]
+    fn test_error_constructors_wire_format() {
+        let wire = |error: ErrorData| serde_json::to_value(error).unwrap();
+        assert_eq!(
+            wire(ErrorData::tool_not_found("add")),
+            json!({ "code": -32602, "message": "tool not found", "data": { "name": "add" } })
+        );
+        assert_eq!(
+            wire(ErrorData::prompt_not_found("greet")),
+            json!({ "code": -32602, "message": "prompt not found", "data": { "name": "greet" } })
+        );
+        assert_eq!(
+            wire(ErrorData::parse_error("bad json", None)),
+            json!({ "code": -32700, "message": "bad json" })
+        );
+        assert_eq!(
+            wire(ErrorData::invalid_request("no method", None)),
+            json!({ "code": -32600, "message": "no method" })
+        );
+        assert_eq!(
+            wire(ErrorData::method_not_found::<CallToolRequestMethod>()),
+            json!({ "code": -32601, "message": "tools/call" })
+        );
+        assert_eq!(
+            wire(ErrorData::internal_error("oops", Some(json!("trace")))),
+            json!({ "code": -32603, "message": "oops", "data": "trace" })
+        );
+        assert_eq!(
+            wire(ErrorData::resource_not_found("no file", None)),
+            json!({ "code": -32002, "message": "no file" })
+        );
+        assert_eq!(
+            wire(ErrorData::server_error(-32050, "busy", None).with_data(vec![1, 2])),
+            json!({ "code": -32050, "message": "busy", "data": [1, 2] })
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "server error code -1 out of")]
+    fn test_server_error_out_of_range() {
+        let _ = ErrorData::server_error(-1, "busy", None);
+    }
+
+    #[test]
+    fn test_error_kind_and_data() {
+        assert_eq!(ErrorCode::PARSE_ERROR.kind(), ErrorKind::ParseError);
+        assert_eq!(ErrorCode::INVALID_REQUEST.kind(), ErrorKind::InvalidRequest);
+        assert_eq!(
+            ErrorCode::METHOD_NOT_FOUND.kind(),
+            ErrorKind::MethodNotFound
+        );
+        assert_eq!(ErrorCode::INVALID_PARAMS.kind(), ErrorKind::InvalidParams);
+        assert_eq!(ErrorCode::INTERNAL_ERROR.kind(), ErrorKind::InternalError);
+        assert_eq!(
+            ErrorCode::RESOURCE_NOT_FOUND.kind(),
+            ErrorKind::ServerError(-32002)
+        );
+        assert_eq!(ErrorCode(42).kind(), ErrorKind::Other(42));
+        assert_eq!(
+            ErrorCode::from(ErrorKind::InvalidParams),
+            ErrorCode::INVALID_PARAMS
+        );
+
+        // the data of an error received reads back as sent
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Name {
+            name: String,
+        }
+        let raw = serde_json::to_string(&ErrorData::tool_not_found("add")).unwrap();
+        let error: ErrorData = serde_json::from_str(&raw).unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidParams);
+        assert_eq!(error.data_as::<Name>(), Some(Name { name: "add".into() }));
+        assert_eq!(error.data_as::<u32>(), None);
+    }
+
+    #[test]
+    fn test_progress_notification_serde() {
+        for (token, raw_token) in [
+            (ProgressToken::from(7), json!(7)),
+            (ProgressToken::from("upload-1"), json!("upload-1")),
+        ] {
+            let raw = json!({
+                "method": "notifications/progress",
+                "params": {
+                    "progressToken": raw_token.clone(),
+                    "progress": 2.5,
+                    "total": 10.0,
+                    "message": "uploading",
+                },
+            });
+            let notification: ProgressNotification = serde_json::from_value(raw.clone()).unwrap();
+            assert_eq!(notification.params.progress_token, token);
+            assert_eq!(notification.params.progress, 2.5);
+            assert_eq!(notification.params.total, Some(10.0));
+            assert_eq!(serde_json::to_value(&notification).unwrap(), raw);
+
+            let mut meta = Meta::new();
+            meta.set_progress_token(token.clone());
+            assert_eq!(
+                serde_json::to_value(&meta).unwrap(),
+                json!({ "progressToken": raw_token })
+            );
+            assert_eq!(meta.progress_token(), Some(token));
+        }
+
+        // integers are read as progress too
+        let params: ProgressNotificationParam =
+            serde_json::from_value(json!({ "progressToken": 1, "progress": 3 })).unwrap();
+        assert_eq!(params.progress, 3.0);
+        assert_eq!(params.total, None);
+    }
+
+    #[test]
+    fn test_request_id_representation() {
+        for (id, raw_id) in [
+            (RequestId::from(1), json!(1)),
+            (RequestId::from("1"), json!("1")),
+            (RequestId::from(-1), json!(-1)),
+            (
+                RequestId::from(9_007_199_254_740_993),
+                json!(9_007_199_254_740_993_i64),
+            ),
+            (RequestId::from("req-7f3a".to_owned()), json!("req-7f3a")),
+        ] {
+            let raw = json!({ "jsonrpc": "2.0", "id": raw_id, "method": "ping" });
+            let message: ClientJsonRpcMessage = serde_json::from_value(raw.clone()).unwrap();
+            let (_, received) = message.clone().into_request().expect("a request");
+            assert_eq!(received, id);
+            assert_eq!(serde_json::to_value(&message).unwrap(), raw);
+
+            let response = ServerJsonRpcMessage::response(ServerResult::empty(()), received);
+            assert_eq!(
+                serde_json::to_value(&response).unwrap(),
+                json!({ "jsonrpc": "2.0", "id": raw_id, "result": {} })
+            );
+        }
+        assert_ne!(RequestId::from(1), RequestId::from("1"));
+
+        // ids which can't be echoed as they were received
+        for raw_id in [json!(u64::MAX), json!(1.5), json!(true)] {
+            assert!(serde_json::from_value::<RequestId>(raw_id).is_err());
+        }
+    }
+
+    #[test]
+    fn test_notification_with_null_id() {
+        let message: ClientJsonRpcMessage = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "id": null,
+            "method": "notifications/initialized",
+        }))
+        .unwrap();
+        assert!(matches!(
+            message.into_notification(),
+            Some(ClientNotification::InitializedNotification(_))
+        ));
+    }
 }
@@ -1,26 +1,37 @@
 use std::ops::{Deref, DerefMut};
 
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 
 use super::{
     RawAudioContent, RawContent, RawEmbeddedResource, RawImageContent, RawResource,
     RawResourceTemplate, RawTextContent, Role,
 };
 
+/// Hints to the clients about how to use or display an object
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Annotations {
+    /// Who the object is intended for, the user, the assistant or both
     #[serde(skip_serializing_if = "Option::is_none")]
     pub audience: Option<Vec<Role>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// How important the object is, from 0 (optional) to 1 (required)
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_priority"
+    )]
     pub priority: Option<f32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub timestamp: Option<DateTime<Utc>>,
+    /// When the object was last modified, `timestamp` is accepted from older peers
+    #[serde(skip_serializing_if = "Option::is_none", alias = "timestamp")]
+    pub last_modified: Option<DateTime<Utc>>,
 }
 
 impl Annotations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
     /// Creates a new Annotations instance specifically for resources
     /// optional priority, and a timestamp (defaults to now if None)
     pub fn for_resource(priority: f32, timestamp: DateTime<Utc>) -> Self {
@@ -30,10 +41,50 @@ impl Annotations {
         );
         Annotations {
             priority: Some(priority),
-            timestamp: Some(timestamp),
+            last_modified: Some(timestamp),
             audience: None,
         }
     }
+
+    /// `audience` is a role, or a list of them
+    pub fn with_audience(self, audience: impl Into<Vec<Role>>) -> Self {
+        Annotations {
+            audience: Some(audience.into()),
+            ..self
+        }
+    }
+
+    pub fn with_priority(self, priority: f32) -> Self {
+        Annotations {
+            priority: Some(priority),
+            ..self
+        }
+    }
+
+    pub fn with_last_modified(self, last_modified: DateTime<Utc>) -> Self {
+        Annotations {
+            last_modified: Some(last_modified),
+            ..self
+        }
+    }
+}
+
+// written like JavaScript numbers, `1` instead of `1.0`
+fn serialize_priority<S: Serializer>(
+    priority: &Option<f32>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match priority {
+        Some(priority) if priority.fract() == 0.0 => serializer.serialize_some(&(*priority as i64)),
+        Some(priority) => serializer.serialize_some(priority),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl From<Role> for Vec<Role> {
+    fn from(role: Role) -> Self {
+        vec![role]
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -71,83 +122,39 @@ impl<T: AnnotateAble> Annotated<T> {
     pub fn priority(&self) -> Option<f32> {
         self.annotations.as_ref().and_then(|a| a.priority)
     }
+    pub fn last_modified(&self) -> Option<DateTime<Utc>> {
+        self.annotations.as_ref().and_then(|a| a.last_modified)
+    }
+    #[deprecated(note = "use `last_modified`")]
     pub fn timestamp(&self) -> Option<DateTime<Utc>> {
-        self.annotations.as_ref().and_then(|a| a.timestamp)
+        self.last_modified()
     }
-    pub fn with_audience(self, audience: Vec<Role>) -> Annotated<T>
-    where
-        Self: Sized,
-    {
-        if let Some(annotations) = self.annotations {
-            Annotated {
-                raw: self.raw,
-                annotations: Some(Annotations {
-                    audience: Some(audience),
-                    ..annotations
-                }),
-            }
-        } else {
-            Annotated {
-                raw: self.raw,
-                annotations: Some(Annotations {
-                    audience: Some(audience),
-                    priority: None,
-                    timestamp: None,
-                }),
-            }
-        }
+    /// `audience` is a role, or a list of them
+    pub fn with_audience(self, audience: impl Into<Vec<Role>>) -> Annotated<T> {
+        self.map_annotations(|annotations| annotations.with_audience(audience))
     }
-    pub fn with_priority(self, priority: f32) -> Annotated<T>
-    where
-        Self: Sized,
-    {
-        if let Some(annotations) = self.annotations {
-            Annotated {
-                raw: self.raw,
-                annotations: Some(Annotations {
-                    priority: Some(priority),
-                    ..annotations
-                }),
-            }
-        } else {
-            Annotated {
-                raw: self.raw,
-                annotations: Some(Annotations {
-                    priority: Some(priority),
-                    timestamp: None,
-                    audience: None,
-                }),
-            }
-        }
+    pub fn with_priority(self, priority: f32) -> Annotated<T> {
+        self.map_annotations(|annotations| annotations.with_priority(priority))
     }
-    pub fn with_timestamp(self, timestamp: DateTime<Utc>) -> Annotated<T>
-    where
-        Self: Sized,
-    {
-        if let Some(annotations) = self.annotations {
-            Annotated {
-                raw: self.raw,
-                annotations: Some(Annotations {
-                    timestamp: Some(timestamp),
-                    ..annotations
-                }),
-            }
-        } else {
-            Annotated {
-                raw: self.raw,
-                annotations: Some(Annotations {
-                    timestamp: Some(timestamp),
-                    priority: None,
-                    audience: None,
-                }),
-            }
-        }
+    pub fn with_last_modified(self, last_modified: DateTime<Utc>) -> Annotated<T> {
+        self.map_annotations(|annotations| annotations.with_last_modified(last_modified))
     }
-    pub fn with_timestamp_now(self) -> Annotated<T>
-    where
-        Self: Sized,
-    {
-        self.with_timestamp(Utc::now())
+    pub fn with_last_modified_now(self) -> Annotated<T> {
+        self.with_last_modified(Utc::now())
+    }
+    #[deprecated(note = "use `with_last_modified`")]
+    pub fn with_timestamp(self, timestamp: DateTime<Utc>) -> Annotated<T> {
+        self.with_last_modified(timestamp)
+    }
+    #[deprecated(note = "use `with_last_modified_now`")]
+    pub fn with_timestamp_now(self) -> Annotated<T> {
+        self.with_last_modified_now()
+    }
+    fn map_annotations(self, f: impl FnOnce(Annotations) -> Annotations) -> Annotated<T> {
+        Annotated {
+            raw: self.raw,
+            annotations: Some(f(self.annotations.unwrap_or_default())),
+        }
     }
 }
 
@@ -188,37 +195,43 @@ pub trait AnnotateAble: sealed::Sealed {
     {
         Annotated::new(self, None)
     }
-    fn with_audience(self, audience: Vec<Role>) -> Annotated<Self>
+    /// `audience` is a role, or a list of them
+    fn with_audience(self, audience: impl Into<Vec<Role>>) -> Annotated<Self>
     where
         Self: Sized,
     {
-        self.annotate(Annotations {
-            audience: Some(audience),
-            ..Default::default()
-        })
+        self.annotate(Annotations::new().with_audience(audience))
     }
     fn with_priority(self, priority: f32) -> Annotated<Self>
     where
         Self: Sized,
     {
-        self.annotate(Annotations {
-            priority: Some(priority),
-            ..Default::default()
-        })
+        self.annotate(Annotations::new().with_priority(priority))
+    }
+    fn with_last_modified(self, last_modified: DateTime<Utc>) -> Annotated<Self>
+    where
+        Self: Sized,
+    {
+        self.annotate(Annotations::new().with_last_modified(last_modified))
+    }
+    fn with_last_modified_now(self) -> Annotated<Self>
+    where
+        Self: Sized,
+    {
+        self.with_last_modified(Utc::now())
     }
+    #[deprecated(note = "use `with_last_modified`")]
     fn with_timestamp(self, timestamp: DateTime<Utc>) -> Annotated<Self>
     where
         Self: Sized,
     {
-        self.annotate(Annotations {
-            timestamp: Some(timestamp),
-            ..Default::default()
-        })
+        self.with_last_modified(timestamp)
     }
+    #[deprecated(note = "use `with_last_modified_now`")]
     fn with_timestamp_now(self) -> Annotated<Self>
     where
         Self: Sized,
     {
-        self.with_timestamp(Utc::now())
+        self.with_last_modified_now()
     }
 }
@@ -0,0 +1,220 @@
+use std::{borrow::Cow, sync::OnceLock};
+
+use serde::{
+    Deserialize, Serialize,
+    de::{DeserializeOwned, IntoDeserializer},
+};
+use serde_json::{Value, value::RawValue};
+
+use super::JsonObject;
+
+/// The arguments of a tool call
+///
+/// Arguments decoded from a message are kept as JSON text, so the parameters of a tool are
+/// deserialized from it directly, without building a [`JsonObject`] first. They are parsed into
+/// one the first time they are read as a map, e.g. to get a single parameter, and changing them
+/// drops the text.
+///
+/// They are deserialized by `serde_json` only, from text or a [`Value`], and not from content
+/// buffered by serde, e.g. in an untagged enum.
+///
+/// ```rust
+/// # use rmcp::model::{JsonObject, ToolArguments};
+/// let arguments: ToolArguments = serde_json::from_str(r#"{"a":1,"b":2}"#).unwrap();
+/// assert_eq!(arguments.as_raw().unwrap().get(), r#"{"a":1,"b":2}"#);
+/// assert_eq!(arguments["a"], 1);
+///
+/// let arguments = ToolArguments::from(JsonObject::from_iter([("a".into(), 1.into())]));
+/// assert!(arguments.as_raw().is_none());
+/// ```
+#[derive(Clone, Default)]
+pub struct ToolArguments {
+    raw: Option<Box<RawValue>>,
+    object: OnceLock<JsonObject>,
+}
+
+impl ToolArguments {
+    /// The arguments as a map, parsed from their text the first time
+    pub fn as_object(&self) -> &JsonObject {
+        self.object.get_or_init(|| match &self.raw {
+            // the text is an object, see `Deserialize`
+            Some(raw) => serde_json::from_str(raw.get()).expect("the arguments are an object"),
+            None => JsonObject::new(),
+        })
+    }
+
+    pub fn into_object(mut self) -> JsonObject {
+        self.as_object();
+        self.object.take().unwrap_or_default()
+    }
+
+    /// The JSON text of arguments decoded from a message, unless they were changed since
+    pub fn as_raw(&self) -> Option<&RawValue> {
+        self.raw.as_deref()
+    }
+
+    /// Deserialize `T` from the text of the arguments, or from the map if they have none
+    ///
+    /// `T` may borrow from the arguments. A `&str` can't borrow a string escaped in the text, so
+    /// when `T` doesn't deserialize from the text it is deserialized from the map.
+    pub fn deserialize<'de, T: Deserialize<'de>>(&'de self) -> Result<T, serde_json::Error> {
+        if let Some(Ok(value)) = self.raw.as_ref().map(|raw| serde_json::from_str(raw.get())) {
+            return Ok(value);
+        }
+        T::deserialize(self.as_object().into_deserializer())
+    }
+
+    /// Deserialize `T` from the text of the arguments, or by moving the values out of the map
+    pub fn deserialize_into<T: DeserializeOwned>(self) -> Result<T, serde_json::Error> {
+        match &self.raw {
+            Some(raw) => serde_json::from_str(raw.get()),
+            None => serde_json::from_value(Value::Object(self.into_object())),
+        }
+    }
+}
+
+impl From<JsonObject> for ToolArguments {
+    fn from(object: JsonObject) -> Self {
+        Self {
+            raw: None,
+            object: OnceLock::from(object),
+        }
+    }
+}
+
+impl From<ToolArguments> for JsonObject {
+    fn from(arguments: ToolArguments) -> Self {
+        arguments.into_object()
+    }
+}
+
+impl std::ops::Deref for ToolArguments {
+    type Target = JsonObject;
+
+    fn deref(&self) -> &JsonObject {
+        self.as_object()
+    }
+}
+
+impl std::ops::DerefMut for ToolArguments {
+    fn deref_mut(&mut self) -> &mut JsonObject {
+        self.as_object();
+        self.raw = None;
+        self.object.get_mut().expect("the arguments are parsed")
+    }
+}
+
+impl PartialEq for ToolArguments {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_object() == other.as_object()
+    }
+}
+
+impl std::fmt::Debug for ToolArguments {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.raw {
+            Some(raw) => f.debug_tuple("ToolArguments").field(raw).finish(),
+            None => f
+                .debug_tuple("ToolArguments")
+                .field(self.as_object())
+                .finish(),
+        }
+    }
+}
+
+impl Serialize for ToolArguments {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.raw {
+            Some(raw) => raw.serialize(serializer),
+            None => self.as_object().serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ToolArguments {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        if !raw.get().starts_with('{') {
+            return Err(serde::de::Error::invalid_type(
+                serde::de::Unexpected::Other("arguments which are not an object"),
+                &"an object",
+            ));
+        }
+        Ok(Self {
+            raw: Some(raw),
+            object: OnceLock::new(),
+        })
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for ToolArguments {
+    fn schema_name() -> String {
+        JsonObject::schema_name()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        JsonObject::schema_id()
+    }
+
+    fn is_referenceable() -> bool {
+        JsonObject::is_referenceable()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::schema::Schema {
+        JsonObject::json_schema(generator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_arguments_round_trip() {
+        let json = r#"{"text":"a \"quoted\"\nline","count":-3,"ratio":0.5,"flags":[true,null],"nested":{"empty":{},"list":[]}}"#;
+        let arguments: ToolArguments = serde_json::from_str(json).expect("arguments");
+        assert_eq!(arguments.as_raw().map(RawValue::get), Some(json));
+        assert_eq!(serde_json::to_string(&arguments).expect("json"), json);
+        assert_eq!(
+            Value::Object(arguments.as_object().clone()),
+            serde_json::from_str::<Value>(json).expect("json")
+        );
+    }
+
+    #[test]
+    fn test_arguments_are_an_object() {
+        assert!(serde_json::from_str::<ToolArguments>("[1, 2]").is_err());
+        assert!(serde_json::from_str::<ToolArguments>("\"a\"").is_err());
+    }
+
+    #[test]
+    fn test_changed_arguments_drop_the_text() {
+        let mut arguments: ToolArguments = serde_json::from_str(r#"{"a":1}"#).expect("arguments");
+        arguments.insert("b".into(), 2.into());
+        assert!(arguments.as_raw().is_none());
+        assert_eq!(
+            serde_json::to_value(&arguments).expect("json"),
+            json!({ "a": 1, "b": 2 })
+        );
+    }
+
+    #[test]
+    fn test_deserialize_borrowing_from_the_text() {
+        #[derive(Deserialize)]
+        struct Borrowed<'a> {
+            name: &'a str,
+        }
+        let arguments: ToolArguments =
+            serde_json::from_str(r#"{"name":"rmcp"}"#).expect("arguments");
+        let borrowed: Borrowed = arguments.deserialize().expect("borrowed");
+        assert_eq!(borrowed.name, "rmcp");
+        // an escaped string is borrowed from the map
+        let arguments: ToolArguments =
+            serde_json::from_str(r#"{"name":"\u0072mcp"}"#).expect("arguments");
+        let borrowed: Borrowed = arguments.deserialize().expect("borrowed");
+        assert_eq!(borrowed.name, "rmcp");
+    }
+}
@@ -1,7 +1,7 @@
 use std::{collections::BTreeMap, marker::PhantomData};
 
 use paste::paste;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 use super::JsonObject;
 pub type ExperimentalCapabilities = BTreeMap<String, JsonObject>;
@@ -36,9 +36,48 @@ pub struct ToolsCapability {
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct RootsCapabilities {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub list_changed: Option<bool>,
 }
 
+impl PromptsCapability {
+    /// Advertise `notifications/prompts/list_changed`
+    pub fn with_list_changed(mut self) -> Self {
+        self.list_changed = Some(true);
+        self
+    }
+}
+
+impl ResourcesCapability {
+    /// Advertise `resources/subscribe` and `resources/unsubscribe`
+    pub fn with_subscribe(mut self) -> Self {
+        self.subscribe = Some(true);
+        self
+    }
+
+    /// Advertise `notifications/resources/list_changed`
+    pub fn with_list_changed(mut self) -> Self {
+        self.list_changed = Some(true);
+        self
+    }
+}
+
+impl ToolsCapability {
+    /// Advertise `notifications/tools/list_changed`
+    pub fn with_list_changed(mut self) -> Self {
+        self.list_changed = Some(true);
+        self
+    }
+}
+
+impl RootsCapabilities {
+    /// Advertise `notifications/roots/list_changed`
+    pub fn with_list_changed(mut self) -> Self {
+        self.list_changed = Some(true);
+        self
+    }
+}
+
 ///
 /// # Builder
 /// ```rust
@@ -58,20 +97,63 @@ pub struct ClientCapabilities {
     pub roots: Option<RootsCapabilities>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sampling: Option<JsonObject>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elicitation: Option<JsonObject>,
+}
+
+impl ClientCapabilities {
+    /// Whether the client answers `sampling/createMessage`
+    pub fn supports_sampling(&self) -> bool {
+        self.sampling.is_some()
+    }
+
+    /// Whether the client answers `elicitation/create`
+    pub fn supports_elicitation(&self) -> bool {
+        self.elicitation.is_some()
+    }
+
+    /// Whether the client answers `roots/list`
+    pub fn supports_roots(&self) -> bool {
+        self.roots.is_some()
+    }
+
+    /// Whether the client sends `notifications/roots/list_changed`
+    pub fn supports_roots_list_changed(&self) -> bool {
+        self.roots
+            .as_ref()
+            .and_then(|roots| roots.list_changed)
+            .unwrap_or(false)
+    }
+
+    /// Whether the client advertised the experimental capability `name`
+    pub fn supports_experimental(&self, name: &str) -> bool {
+        self.experimental
+            .as_ref()
+            .is_some_and(|experimental| experimental.contains_key(name))
+    }
+
+    /// The settings of the experimental capability `name` the client advertised, as a `T`
+    ///
+    /// `None` if the capability is missing or its settings don't deserialize to `T`.
+    pub fn experimental_get<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let settings = self.experimental.as_ref()?.get(name)?;
+        T::deserialize(serde_json::Value::Object(settings.clone())).ok()
+    }
 }
 
 ///
 /// ## Builder
 /// ```rust
-/// # use rmcp::model::ServerCapabilities;
+/// # use rmcp::model::{ResourcesCapability, ServerCapabilities};
 /// let cap = ServerCapabilities::builder()
 ///     .enable_logging()
 ///     .enable_experimental()
 ///     .enable_prompts()
-///     .enable_resources()
+///     .enable_resources_with(ResourcesCapability::default().with_subscribe().with_list_changed())
 ///     .enable_tools()
 ///     .enable_tool_list_changed()
 ///     .build();
+/// assert!(cap.supports_resource_subscribe());
 /// ```
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
@@ -91,6 +173,80 @@ pub struct ServerCapabilities {
     pub tools: Option<ToolsCapability>,
 }
 
+impl ServerCapabilities {
+    /// Whether the server answers `tools/list` and `tools/call`
+    pub fn supports_tools(&self) -> bool {
+        self.tools.is_some()
+    }
+
+    /// Whether the server sends `notifications/tools/list_changed`
+    pub fn supports_tools_list_changed(&self) -> bool {
+        self.tools
+            .as_ref()
+            .and_then(|tools| tools.list_changed)
+            .unwrap_or(false)
+    }
+
+    /// Whether the server answers `prompts/list` and `prompts/get`
+    pub fn supports_prompts(&self) -> bool {
+        self.prompts.is_some()
+    }
+
+    /// Whether the server sends `notifications/prompts/list_changed`
+    pub fn supports_prompts_list_changed(&self) -> bool {
+        self.prompts
+            .as_ref()
+            .and_then(|prompts| prompts.list_changed)
+            .unwrap_or(false)
+    }
+
+    /// Whether the server answers `resources/list` and `resources/read`
+    pub fn supports_resources(&self) -> bool {
+        self.resources.is_some()
+    }
+
+    /// Whether the server answers `resources/subscribe` and `resources/unsubscribe`
+    pub fn supports_resource_subscribe(&self) -> bool {
+        self.resources
+            .as_ref()
+            .and_then(|resources| resources.subscribe)
+            .unwrap_or(false)
+    }
+
+    /// Whether the server sends `notifications/resources/list_changed`
+    pub fn supports_resources_list_changed(&self) -> bool {
+        self.resources
+            .as_ref()
+            .and_then(|resources| resources.list_changed)
+            .unwrap_or(false)
+    }
+
+    /// Whether the server answers `logging/setLevel` and sends `notifications/message`
+    pub fn supports_logging(&self) -> bool {
+        self.logging.is_some()
+    }
+
+    /// Whether the server answers `completion/complete`
+    pub fn supports_completions(&self) -> bool {
+        self.completions.is_some()
+    }
+
+    /// Whether the server advertised the experimental capability `name`
+    pub fn supports_experimental(&self, name: &str) -> bool {
+        self.experimental
+            .as_ref()
+            .is_some_and(|experimental| experimental.contains_key(name))
+    }
+
+    /// The settings of the experimental capability `name` the server advertised, as a `T`
+    ///
+    /// `None` if the capability is missing or its settings don't deserialize to `T`.
+    pub fn experimental_get<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let settings = self.experimental.as_ref()?.get(name)?;
+        T::deserialize(serde_json::Value::Object(settings.clone())).ok()
+    }
+}
+
 macro_rules! builder {
     ($Target: ident {$($f: ident: $T: ty),* $(,)?}) => {
         paste! {
@@ -207,6 +363,35 @@ builder! {
     }
 }
 
+impl<const E: bool, const L: bool, const C: bool, const P: bool, const R: bool, const T: bool>
+    ServerCapabilitiesBuilder<ServerCapabilitiesBuilderState<E, L, C, P, R, T>>
+{
+    /// Advertise the experimental capability `name` with its `settings`, alongside the others
+    ///
+    /// # Panic
+    /// This will panic when the settings don't serialize to an object in debug mode.
+    pub fn experimental(
+        self,
+        name: impl Into<String>,
+        settings: impl Serialize,
+    ) -> ServerCapabilitiesBuilder<ServerCapabilitiesBuilderState<true, L, C, P, R, T>> {
+        let mut experimental = self.experimental.unwrap_or_default();
+        experimental.insert(
+            name.into(),
+            super::object(serde_json::to_value(settings).unwrap_or_default()),
+        );
+        ServerCapabilitiesBuilder {
+            experimental: Some(experimental),
+            logging: self.logging,
+            completions: self.completions,
+            prompts: self.prompts,
+            resources: self.resources,
+            tools: self.tools,
+            state: PhantomData,
+        }
+    }
+}
+
 impl<const E: bool, const L: bool, const C: bool, const P: bool, const R: bool>
     ServerCapabilitiesBuilder<ServerCapabilitiesBuilderState<E, L, C, P, R, true>>
 {
@@ -252,11 +437,39 @@ builder! {
         experimental: ExperimentalCapabilities,
         roots: RootsCapabilities,
         sampling: JsonObject,
+        elicitation: JsonObject,
+    }
+}
+
+impl<const E: bool, const RO: bool, const S: bool, const EL: bool>
+    ClientCapabilitiesBuilder<ClientCapabilitiesBuilderState<E, RO, S, EL>>
+{
+    /// Advertise the experimental capability `name` with its `settings`, alongside the others
+    ///
+    /// # Panic
+    /// This will panic when the settings don't serialize to an object in debug mode.
+    pub fn experimental(
+        self,
+        name: impl Into<String>,
+        settings: impl Serialize,
+    ) -> ClientCapabilitiesBuilder<ClientCapabilitiesBuilderState<true, RO, S, EL>> {
+        let mut experimental = self.experimental.unwrap_or_default();
+        experimental.insert(
+            name.into(),
+            super::object(serde_json::to_value(settings).unwrap_or_default()),
+        );
+        ClientCapabilitiesBuilder {
+            experimental: Some(experimental),
+            roots: self.roots,
+            sampling: self.sampling,
+            elicitation: self.elicitation,
+            state: PhantomData,
+        }
     }
 }
 
-impl<const E: bool, const S: bool>
-    ClientCapabilitiesBuilder<ClientCapabilitiesBuilderState<E, true, S>>
+impl<const E: bool, const S: bool, const EL: bool>
+    ClientCapabilitiesBuilder<ClientCapabilitiesBuilderState<E, true, S, EL>>
 {
     pub fn enable_roots_list_changed(mut self) -> Self {
         if let Some(c) = self.roots.as_mut() {
@@ -295,7 +508,8 @@ mod test {
             .enable_experimental()
             .enable_roots()
             .enable_roots_list_changed()
-            .enable_sampling();
+            .enable_sampling()
+            .enable_elicitation();
         assert_eq!(
             client_builder.experimental,
             Some(ExperimentalCapabilities::default())
@@ -306,5 +520,227 @@ mod test {
                 list_changed: Some(true),
             })
         );
+        assert_eq!(client_builder.elicitation, Some(JsonObject::default()));
+    }
+
+    #[test]
+    fn test_server_capabilities_wire_format() {
+        let capabilities = ServerCapabilities::builder()
+            .enable_experimental()
+            .enable_logging()
+            .enable_completions()
+            .enable_prompts()
+            .enable_prompts_list_changed()
+            .enable_resources()
+            .enable_resources_subscribe()
+            .enable_resources_list_changed()
+            .enable_tools()
+            .enable_tool_list_changed()
+            .build();
+        let expected = serde_json::json!({
+            "experimental": {},
+            "logging": {},
+            "completions": {},
+            "prompts": { "listChanged": true },
+            "resources": { "subscribe": true, "listChanged": true },
+            "tools": { "listChanged": true },
+        });
+        assert_eq!(serde_json::to_value(&capabilities).unwrap(), expected);
+        assert_eq!(
+            serde_json::from_value::<ServerCapabilities>(expected).unwrap(),
+            capabilities
+        );
+        assert_eq!(
+            ServerCapabilities::builder()
+                .enable_prompts_with(PromptsCapability::default().with_list_changed())
+                .enable_resources_with(
+                    ResourcesCapability::default()
+                        .with_subscribe()
+                        .with_list_changed()
+                )
+                .enable_tools_with(ToolsCapability::default().with_list_changed())
+                .build(),
+            ServerCapabilities {
+                experimental: None,
+                logging: None,
+                completions: None,
+                ..capabilities
+            }
+        );
+
+        let capabilities = ServerCapabilities::builder()
+            .enable_resources()
+            .enable_tools()
+            .build();
+        assert_eq!(
+            serde_json::to_value(&capabilities).unwrap(),
+            serde_json::json!({ "resources": {}, "tools": {} })
+        );
+        assert_eq!(
+            serde_json::to_value(ServerCapabilities::default()).unwrap(),
+            serde_json::json!({})
+        );
+    }
+
+    #[test]
+    fn test_client_capabilities_wire_format() {
+        let capabilities = ClientCapabilities::builder()
+            .enable_experimental()
+            .enable_roots()
+            .enable_roots_list_changed()
+            .enable_sampling()
+            .enable_elicitation()
+            .build();
+        let expected = serde_json::json!({
+            "experimental": {},
+            "roots": { "listChanged": true },
+            "sampling": {},
+            "elicitation": {},
+        });
+        assert_eq!(serde_json::to_value(&capabilities).unwrap(), expected);
+        assert_eq!(
+            serde_json::from_value::<ClientCapabilities>(expected).unwrap(),
+            capabilities
+        );
+
+        let capabilities = ClientCapabilities::builder()
+            .enable_roots()
+            .enable_sampling()
+            .build();
+        assert_eq!(
+            serde_json::to_value(&capabilities).unwrap(),
+            serde_json::json!({ "roots": {}, "sampling": {} })
+        );
+    }
+
+    #[test]
+    fn test_server_supports() {
+        let capabilities = ServerCapabilities::default();
+        assert!(!capabilities.supports_tools());
+        assert!(!capabilities.supports_prompts());
+        assert!(!capabilities.supports_resources());
+        assert!(!capabilities.supports_resource_subscribe());
+        assert!(!capabilities.supports_logging());
+        assert!(!capabilities.supports_completions());
+        assert!(!capabilities.supports_experimental("tracing"));
+
+        let capabilities = ServerCapabilities::builder()
+            .enable_logging()
+            .enable_resources()
+            .enable_resources_subscribe()
+            .enable_tools()
+            .build();
+        assert!(capabilities.supports_tools());
+        assert!(!capabilities.supports_tools_list_changed());
+        assert!(!capabilities.supports_prompts());
+        assert!(!capabilities.supports_prompts_list_changed());
+        assert!(capabilities.supports_resources());
+        assert!(capabilities.supports_resource_subscribe());
+        assert!(!capabilities.supports_resources_list_changed());
+        assert!(capabilities.supports_logging());
+        assert!(!capabilities.supports_completions());
+
+        let capabilities = ServerCapabilities::builder()
+            .enable_experimental_with(ExperimentalCapabilities::from([(
+                "tracing".to_owned(),
+                JsonObject::new(),
+            )]))
+            .enable_completions()
+            .enable_prompts()
+            .enable_prompts_list_changed()
+            .enable_resources()
+            .enable_resources_list_changed()
+            .enable_tools()
+            .enable_tool_list_changed()
+            .build();
+        assert!(capabilities.supports_tools_list_changed());
+        assert!(capabilities.supports_prompts_list_changed());
+        assert!(capabilities.supports_resources_list_changed());
+        assert!(!capabilities.supports_resource_subscribe());
+        assert!(capabilities.supports_completions());
+        assert!(capabilities.supports_experimental("tracing"));
+        assert!(!capabilities.supports_experimental("other"));
+    }
+
+    #[test]
+    fn test_client_supports() {
+        let capabilities = ClientCapabilities::default();
+        assert!(!capabilities.supports_sampling());
+        assert!(!capabilities.supports_elicitation());
+        assert!(!capabilities.supports_roots());
+        assert!(!capabilities.supports_roots_list_changed());
+        assert!(!capabilities.supports_experimental("tracing"));
+
+        let capabilities = ClientCapabilities::builder()
+            .enable_roots()
+            .enable_sampling()
+            .build();
+        assert!(capabilities.supports_sampling());
+        assert!(!capabilities.supports_elicitation());
+        assert!(capabilities.supports_roots());
+        assert!(!capabilities.supports_roots_list_changed());
+
+        let capabilities = ClientCapabilities::builder()
+            .enable_experimental_with(ExperimentalCapabilities::from([(
+                "tracing".to_owned(),
+                JsonObject::new(),
+            )]))
+            .enable_roots()
+            .enable_roots_list_changed()
+            .enable_elicitation()
+            .build();
+        assert!(capabilities.supports_roots_list_changed());
+        assert!(capabilities.supports_elicitation());
+        assert!(capabilities.supports_experimental("tracing"));
+        assert!(!capabilities.supports_experimental("other"));
+    }
+
+    #[test]
+    fn test_experimental() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        #[serde(rename_all = "camelCase")]
+        struct BatchTools {
+            max_batch_size: u32,
+        }
+
+        let capabilities = ServerCapabilities::builder()
+            .enable_tools()
+            .experimental("myorg/batch-tools", BatchTools { max_batch_size: 8 })
+            .experimental("myorg/tracing", serde_json::json!({}))
+            .build();
+        assert_eq!(
+            serde_json::to_value(&capabilities).unwrap(),
+            serde_json::json!({
+                "experimental": {
+                    "myorg/batch-tools": { "maxBatchSize": 8 },
+                    "myorg/tracing": {}
+                },
+                "tools": {}
+            })
+        );
+        assert_eq!(
+            capabilities.experimental_get("myorg/batch-tools"),
+            Some(BatchTools { max_batch_size: 8 })
+        );
+        assert_eq!(
+            capabilities.experimental_get::<BatchTools>("myorg/tracing"),
+            None
+        );
+        assert_eq!(capabilities.experimental_get::<BatchTools>("other"), None);
+
+        let capabilities = ClientCapabilities::builder()
+            .enable_experimental_with(ExperimentalCapabilities::from([(
+                "tracing".to_owned(),
+                JsonObject::new(),
+            )]))
+            .enable_roots()
+            .experimental("myorg/batch-tools", BatchTools { max_batch_size: 2 })
+            .build();
+        assert!(capabilities.supports_experimental("tracing"));
+        assert_eq!(
+            capabilities.experimental_get("myorg/batch-tools"),
+            Some(BatchTools { max_batch_size: 2 })
+        );
+        assert!(capabilities.supports_roots());
     }
 }
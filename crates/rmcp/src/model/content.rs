@@ -1,10 +1,15 @@
 //! Content sent around agents, extensions, and LLMs
 //! The various content types can be display to humans but also understood by models
 //! They include optional annotations used to help inform agent usage
+#[cfg(feature = "base64")]
+use base64::engine::{Engine, general_purpose::STANDARD as BASE64_STANDARD};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-use super::{AnnotateAble, Annotated, resource::ResourceContents};
+use super::{
+    AnnotateAble, Annotated,
+    resource::{RawResource, ResourceContents},
+};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -32,6 +37,35 @@ pub struct RawEmbeddedResource {
 pub type EmbeddedResource = Annotated<RawEmbeddedResource>;
 
 impl EmbeddedResource {
+    /// An embedded text resource
+    pub fn text(
+        uri: impl Into<String>,
+        mime_type: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        RawEmbeddedResource {
+            resource: ResourceContents::TextResourceContents {
+                uri: uri.into(),
+                mime_type: Some(mime_type.into()),
+                text: text.into(),
+            },
+        }
+        .no_annotation()
+    }
+
+    /// An embedded binary resource, `data` is encoded with base64
+    #[cfg(feature = "base64")]
+    pub fn blob(uri: impl Into<String>, mime_type: impl Into<String>, data: &[u8]) -> Self {
+        RawEmbeddedResource {
+            resource: ResourceContents::BlobResourceContents {
+                uri: uri.into(),
+                mime_type: Some(mime_type.into()),
+                blob: BASE64_STANDARD.encode(data),
+            },
+        }
+        .no_annotation()
+    }
+
     pub fn get_text(&self) -> String {
         match &self.resource {
             ResourceContents::TextResourceContents { text, .. } => text.clone(),
@@ -44,12 +78,30 @@ impl EmbeddedResource {
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct RawAudioContent {
+    /// The base64-encoded audio
     pub data: String,
     pub mime_type: String,
 }
 
 pub type AudioContent = Annotated<RawAudioContent>;
 
+impl RawAudioContent {
+    /// Encode `data` with base64
+    #[cfg(feature = "base64")]
+    pub fn new(data: impl Into<Vec<u8>>, mime_type: impl Into<String>) -> Self {
+        RawAudioContent {
+            data: BASE64_STANDARD.encode(data.into()),
+            mime_type: mime_type.into(),
+        }
+    }
+
+    /// Decode the base64-encoded audio
+    #[cfg(feature = "base64")]
+    pub fn decode(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        BASE64_STANDARD.decode(&self.data)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -57,7 +109,12 @@ pub enum RawContent {
     Text(RawTextContent),
     Image(RawImageContent),
     Resource(RawEmbeddedResource),
-    Audio(AudioContent),
+    Audio(RawAudioContent),
+    #[serde(rename = "resource_link")]
+    ResourceLink(RawResource),
+    /// A content of a type the SDK doesn't know, kept as it was received
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
 }
 
 pub type Content = Annotated<RawContent>;
@@ -90,6 +147,17 @@ impl RawContent {
         RawContent::Resource(RawEmbeddedResource { resource })
     }
 
+    /// Create an audio content, `data` is encoded with base64
+    #[cfg(feature = "base64")]
+    pub fn audio(data: impl Into<Vec<u8>>, mime_type: &str) -> Self {
+        RawContent::Audio(RawAudioContent::new(data, mime_type))
+    }
+
+    /// Create a link to a resource, which the client can read with `resources/read`
+    pub fn resource_link(resource: RawResource) -> Self {
+        RawContent::ResourceLink(resource)
+    }
+
     pub fn embedded_text<S: Into<String>, T: Into<String>>(uri: S, content: T) -> Self {
         RawContent::Resource(RawEmbeddedResource {
             resource: ResourceContents::TextResourceContents {
@@ -123,6 +191,22 @@ impl RawContent {
             _ => None,
         }
     }
+
+    /// Get the audio content if this is an AudioContent variant
+    pub fn as_audio(&self) -> Option<&RawAudioContent> {
+        match self {
+            RawContent::Audio(audio) => Some(audio),
+            _ => None,
+        }
+    }
+
+    /// Get the linked resource if this is a ResourceLink variant
+    pub fn as_resource_link(&self) -> Option<&RawResource> {
+        match self {
+            RawContent::ResourceLink(resource) => Some(resource),
+            _ => None,
+        }
+    }
 }
 
 impl Content {
@@ -138,6 +222,17 @@ impl Content {
         RawContent::resource(resource).no_annotation()
     }
 
+    /// Create an audio content, `data` is encoded with base64
+    #[cfg(feature = "base64")]
+    pub fn audio(data: impl Into<Vec<u8>>, mime_type: &str) -> Self {
+        RawContent::audio(data, mime_type).no_annotation()
+    }
+
+    /// Create a link to a resource, which the client can read with `resources/read`
+    pub fn resource_link(resource: RawResource) -> Self {
+        RawContent::resource_link(resource).no_annotation()
+    }
+
     pub fn embedded_text<S: Into<String>, T: Into<String>>(uri: S, content: T) -> Self {
         RawContent::embedded_text(uri, content).no_annotation()
     }
@@ -147,21 +242,63 @@ impl Content {
     }
 }
 
+impl From<ImageContent> for Content {
+    fn from(image: ImageContent) -> Self {
+        Content::new(RawContent::Image(image.raw), image.annotations)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct JsonContent<S: Serialize>(S);
 /// Types that can be converted into a list of contents
+///
+/// A tool returning one of these gets a successful result with the contents, see
+/// `IntoCallToolResult`. Implementing it for `Result`, [`CallToolResult`](super::CallToolResult)
+/// or the `Json` wrapper would conflict with their own conversions.
 pub trait IntoContents {
     fn into_contents(self) -> Vec<Content>;
 }
 
+/// No content
+impl IntoContents for () {
+    fn into_contents(self) -> Vec<Content> {
+        vec![]
+    }
+}
+
+impl IntoContents for Vec<Content> {
+    fn into_contents(self) -> Vec<Content> {
+        self
+    }
+}
+
 impl IntoContents for Content {
     fn into_contents(self) -> Vec<Content> {
         vec![self]
     }
 }
 
+impl IntoContents for ImageContent {
+    fn into_contents(self) -> Vec<Content> {
+        vec![self.into()]
+    }
+}
+
 impl IntoContents for String {
     fn into_contents(self) -> Vec<Content> {
         vec![Content::text(self)]
     }
 }
+
+impl IntoContents for &str {
+    fn into_contents(self) -> Vec<Content> {
+        vec![Content::text(self)]
+    }
+}
+
+/// One text content with the json of the value
+impl IntoContents for serde_json::Value {
+    fn into_contents(self) -> Vec<Content> {
+        vec![Content::text(self.to_string())]
+    }
+}
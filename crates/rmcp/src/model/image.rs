@@ -0,0 +1,312 @@
+//! Images encoded with base64 into an [`ImageContent`], and decoded back
+use std::str::FromStr;
+
+use base64::engine::{Engine, general_purpose::STANDARD as BASE64_STANDARD};
+
+use super::{AnnotateAble, ImageContent, RawImageContent};
+
+/// An image which can't be encoded, or a data uri which isn't an image
+#[derive(Debug, thiserror::Error)]
+pub enum ImageError {
+    #[error("the encoded image would be {size} bytes, more than the maximum of {max}")]
+    TooLarge { size: usize, max: usize },
+    #[error("`{0}` is not an image mime type")]
+    NotAnImage(String),
+    #[error("unknown image type")]
+    UnknownType,
+    #[error("invalid data uri: {0}")]
+    InvalidDataUri(&'static str),
+    #[error("invalid base64: {0}")]
+    Decode(#[from] base64::DecodeError),
+    #[error("failed to read the image: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<ImageError> for crate::Error {
+    fn from(error: ImageError) -> Self {
+        crate::Error::internal_error(error.to_string(), None)
+    }
+}
+
+/// Encodes images with base64, refusing those too large to be sent in a message
+///
+/// ```rust
+/// # use rmcp::model::ImageEncoder;
+/// let encoder = ImageEncoder::new().with_max_encoded_size(1024);
+/// assert!(encoder.encode(&[0; 2048], "image/png").is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageEncoder {
+    max_encoded_size: usize,
+}
+
+impl Default for ImageEncoder {
+    fn default() -> Self {
+        Self {
+            max_encoded_size: Self::DEFAULT_MAX_ENCODED_SIZE,
+        }
+    }
+}
+
+impl ImageEncoder {
+    /// 10 MiB of base64, about 7.5 MiB of image
+    pub const DEFAULT_MAX_ENCODED_SIZE: usize = 10 * 1024 * 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refuse the images whose base64 is longer than `max_encoded_size` bytes
+    pub fn with_max_encoded_size(mut self, max_encoded_size: usize) -> Self {
+        self.max_encoded_size = max_encoded_size;
+        self
+    }
+
+    pub fn max_encoded_size(&self) -> usize {
+        self.max_encoded_size
+    }
+
+    /// Encode `data`, an image of type `mime_type`
+    pub fn encode(
+        &self,
+        data: &[u8],
+        mime_type: impl Into<String>,
+    ) -> Result<ImageContent, ImageError> {
+        let mime_type = mime_type.into();
+        if !mime_type.starts_with("image/") {
+            return Err(ImageError::NotAnImage(mime_type));
+        }
+        self.check_size(data.len())?;
+        Ok(RawImageContent {
+            data: BASE64_STANDARD.encode(data),
+            mime_type,
+        }
+        .no_annotation())
+    }
+
+    /// Read and encode the image at `path`
+    ///
+    /// Its type is sniffed from its first bytes, then from the extension of `path`. A file too
+    /// large is refused before being read.
+    #[cfg(feature = "image-fs")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "image-fs")))]
+    pub async fn encode_file(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<ImageContent, ImageError> {
+        let path = path.as_ref();
+        let len = tokio::fs::metadata(path).await?.len();
+        self.check_size(usize::try_from(len).unwrap_or(usize::MAX))?;
+        let data = tokio::fs::read(path).await?;
+        let mime_type = sniff_mime_type(&data)
+            .or_else(|| {
+                let extension = path.extension()?.to_str()?;
+                mime_type_of_extension(extension)
+            })
+            .ok_or(ImageError::UnknownType)?;
+        self.encode(&data, mime_type)
+    }
+
+    fn check_size(&self, len: usize) -> Result<(), ImageError> {
+        let size = base64::encoded_len(len, true).unwrap_or(usize::MAX);
+        if size > self.max_encoded_size {
+            return Err(ImageError::TooLarge {
+                size,
+                max: self.max_encoded_size,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The mime type of an image, read from its magic bytes
+#[cfg(any(feature = "image-fs", test))]
+pub(crate) fn sniff_mime_type(data: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"BM", "image/bmp"),
+        (b"\x00\x00\x01\x00", "image/x-icon"),
+        (b"II*\x00", "image/tiff"),
+        (b"MM\x00*", "image/tiff"),
+    ];
+    if let Some((_, mime_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| data.starts_with(signature))
+    {
+        return Some(mime_type);
+    }
+    match data.get(..12)? {
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P'] => Some("image/webp"),
+        [_, _, _, _, b'f', b't', b'y', b'p', b'a', b'v', b'i', b'f'] => Some("image/avif"),
+        _ => None,
+    }
+}
+
+/// The mime type of an image, guessed from the extension of its file
+#[cfg(any(feature = "image-fs", test))]
+pub(crate) fn mime_type_of_extension(extension: &str) -> Option<&'static str> {
+    let mime_type = match extension.to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "tif" | "tiff" => "image/tiff",
+        "svg" => "image/svg+xml",
+        _ => return None,
+    };
+    Some(mime_type)
+}
+
+impl RawImageContent {
+    /// Decode the base64-encoded image
+    pub fn to_bytes(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        BASE64_STANDARD.decode(&self.data)
+    }
+
+    /// The image as a `data:` uri, e.g. `data:image/png;base64,iVBORw0KGgo...`
+    pub fn to_data_uri(&self) -> String {
+        format!("data:{};base64,{}", self.mime_type, self.data)
+    }
+}
+
+/// Parse a base64 `data:` uri of an image, e.g. `data:image/png;base64,iVBORw0KGgo...`
+///
+/// Whitespace in the base64, like the line breaks of a wrapped uri, is removed.
+impl FromStr for RawImageContent {
+    type Err = ImageError;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let uri = uri
+            .trim()
+            .strip_prefix("data:")
+            .ok_or(ImageError::InvalidDataUri("missing `data:` scheme"))?;
+        let (header, data) = uri
+            .split_once(',')
+            .ok_or(ImageError::InvalidDataUri("missing `,` before the data"))?;
+        let mime_type = header
+            .strip_suffix(";base64")
+            .ok_or(ImageError::InvalidDataUri("the data is not base64"))?;
+        // parameters like `;charset=` are not part of the mime type
+        let mime_type = mime_type.split(';').next().unwrap_or_default().trim();
+        if !mime_type.starts_with("image/") {
+            return Err(ImageError::NotAnImage(mime_type.to_owned()));
+        }
+        let data: String = data.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+        BASE64_STANDARD.decode(&data)?;
+        Ok(RawImageContent {
+            data,
+            mime_type: mime_type.to_owned(),
+        })
+    }
+}
+
+impl FromStr for ImageContent {
+    type Err = ImageError;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        RawImageContent::from_str(uri).map(AnnotateAble::no_annotation)
+    }
+}
+
+impl ImageContent {
+    /// Encode `data` with the default [`ImageEncoder`]
+    pub fn from_bytes(data: &[u8], mime_type: impl Into<String>) -> Result<Self, ImageError> {
+        ImageEncoder::default().encode(data, mime_type)
+    }
+
+    /// Read and encode the image at `path` with the default [`ImageEncoder`], see
+    /// [`ImageEncoder::encode_file`]
+    #[cfg(feature = "image-fs")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "image-fs")))]
+    pub async fn from_path(path: impl AsRef<std::path::Path>) -> Result<Self, ImageError> {
+        ImageEncoder::default().encode_file(path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIXEL: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f,
+        0x15, 0xc4, 0x89,
+    ];
+
+    #[test]
+    fn test_sniff_mime_type() {
+        assert_eq!(sniff_mime_type(PIXEL), Some("image/png"));
+        assert_eq!(sniff_mime_type(b"\xff\xd8\xff\xe0"), Some("image/jpeg"));
+        assert_eq!(sniff_mime_type(b"GIF89a..."), Some("image/gif"));
+        assert_eq!(
+            sniff_mime_type(b"RIFF\x10\x00\x00\x00WEBPVP8 "),
+            Some("image/webp")
+        );
+        assert_eq!(sniff_mime_type(b"RIFF\x10\x00\x00\x00WAVEfmt "), None);
+        assert_eq!(sniff_mime_type(b"<svg"), None);
+        assert_eq!(sniff_mime_type(b""), None);
+        assert_eq!(mime_type_of_extension("JPG"), Some("image/jpeg"));
+        assert_eq!(mime_type_of_extension("svg"), Some("image/svg+xml"));
+        assert_eq!(mime_type_of_extension("txt"), None);
+    }
+
+    #[test]
+    fn test_encode() {
+        let image = ImageContent::from_bytes(PIXEL, "image/png").unwrap();
+        assert_eq!(image.mime_type, "image/png");
+        assert!(!image.data.contains('\n'));
+        assert_eq!(image.to_bytes().unwrap(), PIXEL);
+
+        assert!(matches!(
+            ImageContent::from_bytes(PIXEL, "png"),
+            Err(ImageError::NotAnImage(mime_type)) if mime_type == "png"
+        ));
+        // 33 bytes are 44 bytes of base64
+        let encoder = ImageEncoder::new().with_max_encoded_size(44);
+        assert!(encoder.encode(PIXEL, "image/png").is_ok());
+        let encoder = encoder.with_max_encoded_size(43);
+        assert!(matches!(
+            encoder.encode(PIXEL, "image/png"),
+            Err(ImageError::TooLarge { size: 44, max: 43 })
+        ));
+    }
+
+    #[test]
+    fn test_data_uri() {
+        let image = ImageContent::from_bytes(PIXEL, "image/png").unwrap();
+        let uri = image.to_data_uri();
+        assert!(uri.starts_with("data:image/png;base64,iVBORw0KGgo"));
+        let parsed: ImageContent = uri.parse().unwrap();
+        assert_eq!(parsed, image);
+
+        // a wrapped uri
+        let (head, tail) = uri.split_at(40);
+        let parsed: RawImageContent = format!("{head}\n  {tail}\n").parse().unwrap();
+        assert_eq!(parsed.to_bytes().unwrap(), PIXEL);
+
+        for (uri, expected) in [
+            ("image/png;base64,AAAA", "missing `data:` scheme"),
+            ("data:image/png;base64", "missing `,` before the data"),
+            ("data:image/png,AAAA", "the data is not base64"),
+        ] {
+            assert!(
+                matches!(uri.parse::<RawImageContent>(), Err(ImageError::InvalidDataUri(error)) if error == expected),
+                "{uri}"
+            );
+        }
+        assert!(matches!(
+            "data:text/plain;base64,AAAA".parse::<RawImageContent>(),
+            Err(ImageError::NotAnImage(_))
+        ));
+        assert!(matches!(
+            "data:image/png;base64,not base64!".parse::<RawImageContent>(),
+            Err(ImageError::Decode(_))
+        ));
+    }
+}
@@ -1,11 +1,11 @@
 use std::ops::{Deref, DerefMut};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::Value;
 
 use super::{
-    ClientNotification, ClientRequest, Extensions, JsonObject, JsonRpcMessage, NumberOrString,
-    ProgressToken, ServerNotification, ServerRequest,
+    ClientNotification, ClientRequest, ConstString, Extensions, JsonObject, JsonRpcMessage,
+    NumberOrString, ProgressToken, ServerNotification, ServerRequest,
 };
 
 pub trait GetMeta {
@@ -18,6 +18,28 @@ pub trait GetExtensions {
     fn extensions_mut(&mut self) -> &mut Extensions;
 }
 
+/// The JSON-RPC method of a request or notification, e.g. `tools/call`
+pub trait GetMethod {
+    fn method(&self) -> &str;
+}
+
+/// The method field of a request or notification, a constant unless the method is unknown
+trait MethodName {
+    fn method_name(&self) -> &str;
+}
+
+impl<M: ConstString> MethodName for M {
+    fn method_name(&self) -> &str {
+        M::VALUE
+    }
+}
+
+impl MethodName for String {
+    fn method_name(&self) -> &str {
+        self
+    }
+}
+
 macro_rules! variant_extension {
     (
         $Enum: ident {
@@ -40,6 +62,15 @@ macro_rules! variant_extension {
                 }
             }
         }
+        impl GetMethod for $Enum {
+            fn method(&self) -> &str {
+                match self {
+                    $(
+                        $Enum::$variant(v) => v.method.method_name(),
+                    )*
+                }
+            }
+        }
         impl GetMeta for $Enum {
             fn get_meta_mut(&mut self) -> &mut Meta {
                 self.extensions_mut().get_or_insert_default()
@@ -66,6 +97,7 @@ variant_extension! {
         UnsubscribeRequest
         CallToolRequest
         ListToolsRequest
+        CustomRequest
     }
 }
 
@@ -74,6 +106,8 @@ variant_extension! {
         PingRequest
         CreateMessageRequest
         ListRootsRequest
+        CreateElicitationRequest
+        CustomRequest
     }
 }
 
@@ -83,6 +117,7 @@ variant_extension! {
         ProgressNotification
         InitializedNotification
         RootsListChangedNotification
+        CustomNotification
     }
 }
 
@@ -95,10 +130,16 @@ variant_extension! {
         ResourceListChangedNotification
         ToolListChangedNotification
         PromptListChangedNotification
+        CustomNotification
     }
 }
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+/// The `_meta` object of request params, notification params and results
+///
+/// Reserved by the spec for things like progress tokens and tracing baggage, it is passed along
+/// untouched by the service layer.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 #[serde(transparent)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Meta(pub JsonObject);
 const PROGRESS_TOKEN_FIELD: &str = "progressToken";
 impl Meta {
@@ -111,16 +152,20 @@ impl Meta {
         EMPTY.get_or_init(Default::default)
     }
 
-    pub fn get_progress_token(&self) -> Option<ProgressToken> {
+    /// The progress token of a request, if the requester asked for progress notifications
+    pub fn progress_token(&self) -> Option<ProgressToken> {
         self.0.get(PROGRESS_TOKEN_FIELD).and_then(|v| match v {
             Value::String(s) => Some(ProgressToken(NumberOrString::String(s.to_string().into()))),
-            Value::Number(n) => n
-                .as_u64()
-                .map(|n| ProgressToken(NumberOrString::Number(n as u32))),
+            Value::Number(n) => n.as_i64().map(|n| ProgressToken(NumberOrString::Number(n))),
             _ => None,
         })
     }
 
+    #[deprecated(note = "use `progress_token`")]
+    pub fn get_progress_token(&self) -> Option<ProgressToken> {
+        self.progress_token()
+    }
+
     pub fn set_progress_token(&mut self, token: ProgressToken) {
         match token.0 {
             NumberOrString::String(ref s) => self.0.insert(
@@ -133,6 +178,21 @@ impl Meta {
         };
     }
 
+    /// The field `key` read as a `T`, `None` if it is missing or isn't a `T`
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.0.get(key).and_then(|v| T::deserialize(v).ok())
+    }
+
+    /// Set the field `key` to `value`, returning the previous value of the field
+    pub fn insert<T: Serialize>(
+        &mut self,
+        key: impl Into<String>,
+        value: T,
+    ) -> Result<Option<Value>, serde_json::Error> {
+        let value = serde_json::to_value(value)?;
+        Ok(self.0.insert(key.into(), value))
+    }
+
     pub fn extend(&mut self, other: Meta) {
         for (k, v) in other.0.into_iter() {
             self.0.insert(k, v);
@@ -1,10 +1,12 @@
+#[cfg(feature = "base64")]
 use base64::engine::{Engine, general_purpose::STANDARD as BASE64_STANDARD};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 use super::{
-    AnnotateAble, Annotations, RawEmbeddedResource, RawImageContent,
-    content::{EmbeddedResource, ImageContent},
-    resource::ResourceContents,
+    AnnotateAble, Annotations, ErrorData, GetPromptRequestParam, GetPromptResult, RawAudioContent,
+    RawEmbeddedResource, RawImageContent,
+    content::{AudioContent, EmbeddedResource, ImageContent},
+    resource::{RawResource, Resource, ResourceContents},
 };
 
 /// A prompt that can be used to generate text from a model
@@ -22,23 +24,46 @@ pub struct Prompt {
     pub arguments: Option<Vec<PromptArgument>>,
 }
 
+/// Building a prompt of a list response
+///
+/// ```rust
+/// # use rmcp::model::Prompt;
+/// let prompt = Prompt::new("summarize")
+///     .with_description("Summarize a file")
+///     .arg("path", "file to read", true)
+///     .arg("length", "words of the summary", false);
+/// ```
 impl Prompt {
-    /// Create a new prompt with the given name, description and arguments
-    pub fn new<N, D>(
-        name: N,
-        description: Option<D>,
-        arguments: Option<Vec<PromptArgument>>,
-    ) -> Self
-    where
-        N: Into<String>,
-        D: Into<String>,
-    {
+    /// A prompt without description nor arguments
+    pub fn new(name: impl Into<String>) -> Self {
         Prompt {
             name: name.into(),
-            description: description.map(Into::into),
-            arguments,
+            description: None,
+            arguments: None,
         }
     }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Add an argument, after the ones added before
+    pub fn arg(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        required: bool,
+    ) -> Self {
+        self.arguments
+            .get_or_insert_with(Vec::new)
+            .push(PromptArgument {
+                name: name.into(),
+                description: Some(description.into()),
+                required: Some(required),
+            });
+        self
+    }
 }
 
 /// Represents a prompt argument that can be passed to customize the prompt
@@ -70,19 +95,145 @@ pub enum PromptMessageRole {
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum PromptMessageContent {
     /// Plain text content
-    Text { text: String },
+    Text {
+        text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
     /// Image content with base64-encoded data
     Image {
         #[serde(flatten)]
         image: ImageContent,
     },
+    /// Audio content with base64-encoded data
+    Audio {
+        #[serde(flatten)]
+        audio: AudioContent,
+    },
     /// Embedded server-side resource
-    Resource { resource: EmbeddedResource },
+    Resource {
+        #[serde(flatten)]
+        resource: EmbeddedResource,
+    },
+    /// Link to a resource the client can read
+    #[serde(rename = "resource_link")]
+    ResourceLink {
+        #[serde(flatten)]
+        link: Resource,
+    },
+    /// A content of a type the SDK doesn't know, kept as it was received
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
 }
 
 impl PromptMessageContent {
     pub fn text(text: impl Into<String>) -> Self {
-        Self::Text { text: text.into() }
+        Self::Text {
+            text: text.into(),
+            annotations: None,
+        }
+    }
+
+    pub fn annotations(&self) -> Option<&Annotations> {
+        match self {
+            Self::Text { annotations, .. } => annotations.as_ref(),
+            Self::Image { image } => image.annotations.as_ref(),
+            Self::Audio { audio } => audio.annotations.as_ref(),
+            Self::Resource { resource } => resource.annotations.as_ref(),
+            Self::ResourceLink { link } => link.annotations.as_ref(),
+            Self::Unknown(_) => None,
+        }
+    }
+
+    /// Replace the annotations of the content, an unknown content is left as it is
+    pub fn annotate(mut self, annotations: Annotations) -> Self {
+        let slot = match &mut self {
+            Self::Text { annotations, .. } => annotations,
+            Self::Image { image } => &mut image.annotations,
+            Self::Audio { audio } => &mut audio.annotations,
+            Self::Resource { resource } => &mut resource.annotations,
+            Self::ResourceLink { link } => &mut link.annotations,
+            Self::Unknown(_) => return self,
+        };
+        *slot = Some(annotations);
+        self
+    }
+}
+
+/// A value which can be the content of a [`PromptMessage`]
+pub trait IntoPromptContent {
+    fn into_prompt_content(self) -> PromptMessageContent;
+}
+
+impl IntoPromptContent for PromptMessageContent {
+    fn into_prompt_content(self) -> PromptMessageContent {
+        self
+    }
+}
+
+impl IntoPromptContent for String {
+    fn into_prompt_content(self) -> PromptMessageContent {
+        PromptMessageContent::text(self)
+    }
+}
+
+impl IntoPromptContent for &str {
+    fn into_prompt_content(self) -> PromptMessageContent {
+        PromptMessageContent::text(self)
+    }
+}
+
+impl IntoPromptContent for ImageContent {
+    fn into_prompt_content(self) -> PromptMessageContent {
+        PromptMessageContent::Image { image: self }
+    }
+}
+
+impl IntoPromptContent for RawImageContent {
+    fn into_prompt_content(self) -> PromptMessageContent {
+        self.no_annotation().into_prompt_content()
+    }
+}
+
+impl IntoPromptContent for AudioContent {
+    fn into_prompt_content(self) -> PromptMessageContent {
+        PromptMessageContent::Audio { audio: self }
+    }
+}
+
+impl IntoPromptContent for RawAudioContent {
+    fn into_prompt_content(self) -> PromptMessageContent {
+        self.no_annotation().into_prompt_content()
+    }
+}
+
+impl IntoPromptContent for EmbeddedResource {
+    fn into_prompt_content(self) -> PromptMessageContent {
+        PromptMessageContent::Resource { resource: self }
+    }
+}
+
+impl IntoPromptContent for RawEmbeddedResource {
+    fn into_prompt_content(self) -> PromptMessageContent {
+        self.no_annotation().into_prompt_content()
+    }
+}
+
+impl IntoPromptContent for ResourceContents {
+    fn into_prompt_content(self) -> PromptMessageContent {
+        RawEmbeddedResource { resource: self }.into_prompt_content()
+    }
+}
+
+impl IntoPromptContent for Resource {
+    fn into_prompt_content(self) -> PromptMessageContent {
+        PromptMessageContent::ResourceLink { link: self }
+    }
+}
+
+impl IntoPromptContent for RawResource {
+    fn into_prompt_content(self) -> PromptMessageContent {
+        self.no_annotation().into_prompt_content()
     }
 }
 
@@ -97,11 +248,36 @@ pub struct PromptMessage {
 }
 
 impl PromptMessage {
+    pub fn new(role: PromptMessageRole, content: impl IntoPromptContent) -> Self {
+        Self {
+            role,
+            content: content.into_prompt_content(),
+        }
+    }
+
+    /// A message of the user, e.g. `PromptMessage::user("Review this code")`
+    pub fn user(content: impl IntoPromptContent) -> Self {
+        Self::new(PromptMessageRole::User, content)
+    }
+
+    /// A message of the assistant
+    pub fn assistant(content: impl IntoPromptContent) -> Self {
+        Self::new(PromptMessageRole::Assistant, content)
+    }
+
     /// Create a new text message with the given role and text content
     pub fn new_text<S: Into<String>>(role: PromptMessageRole, text: S) -> Self {
         Self {
             role,
-            content: PromptMessageContent::Text { text: text.into() },
+            content: PromptMessageContent::text(text),
+        }
+    }
+
+    /// Replace the annotations of the content
+    pub fn annotate(self, annotations: Annotations) -> Self {
+        Self {
+            content: self.content.annotate(annotations),
+            ..self
         }
     }
     #[cfg(feature = "base64")]
@@ -127,6 +303,21 @@ impl PromptMessage {
         }
     }
 
+    #[cfg(feature = "base64")]
+    pub fn new_audio(
+        role: PromptMessageRole,
+        data: &[u8],
+        mime_type: &str,
+        annotations: Option<Annotations>,
+    ) -> Self {
+        Self {
+            role,
+            content: PromptMessageContent::Audio {
+                audio: RawAudioContent::new(data, mime_type).optional_annotate(annotations),
+            },
+        }
+    }
+
     /// Create a new resource message
     pub fn new_resource(
         role: PromptMessageRole,
@@ -168,3 +359,219 @@ pub struct PromptArgumentTemplate {
     pub description: Option<String>,
     pub required: Option<bool>,
 }
+
+impl GetPromptRequestParam {
+    /// Deserialize the arguments of the prompt, no arguments are taken as an empty object
+    ///
+    /// A missing or unknown argument fails with `invalid_params`, with its name as the
+    /// `argument` of the error data.
+    pub fn parse_arguments<T: DeserializeOwned>(&self) -> Result<T, ErrorData> {
+        parse_prompt_arguments(self.arguments.clone().unwrap_or_default())
+    }
+}
+
+pub(crate) fn parse_prompt_arguments<T: DeserializeOwned>(
+    arguments: super::JsonObject,
+) -> Result<T, ErrorData> {
+    serde_json::from_value(serde_json::Value::Object(arguments)).map_err(|error| {
+        let message = error.to_string();
+        // serde quotes the name, e.g. "missing field `language`"
+        let argument = ["missing field `", "unknown field `"]
+            .iter()
+            .find_map(|prefix| message.strip_prefix(prefix)?.split('`').next());
+        ErrorData::invalid_params(
+            format!("invalid prompt arguments: {message}"),
+            argument.map(|argument| serde_json::json!({ "argument": argument })),
+        )
+    })
+}
+
+impl GetPromptResult {
+    pub fn builder() -> GetPromptResultBuilder {
+        GetPromptResultBuilder::default()
+    }
+}
+
+/// Builds a [`GetPromptResult`] message by message
+#[derive(Debug, Clone, Default)]
+pub struct GetPromptResultBuilder {
+    description: Option<String>,
+    messages: Vec<PromptMessage>,
+}
+
+impl GetPromptResultBuilder {
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn message(mut self, message: PromptMessage) -> Self {
+        self.messages.push(message);
+        self
+    }
+
+    pub fn user_text(self, text: impl Into<String>) -> Self {
+        self.message(PromptMessage::new_text(PromptMessageRole::User, text))
+    }
+
+    pub fn assistant_text(self, text: impl Into<String>) -> Self {
+        self.message(PromptMessage::new_text(PromptMessageRole::Assistant, text))
+    }
+
+    pub fn embedded_resource(self, role: PromptMessageRole, resource: ResourceContents) -> Self {
+        self.message(PromptMessage {
+            role,
+            content: PromptMessageContent::Resource {
+                resource: RawEmbeddedResource { resource }.no_annotation(),
+            },
+        })
+    }
+
+    /// Add an image, `data` is encoded with base64
+    #[cfg(feature = "base64")]
+    pub fn image(self, role: PromptMessageRole, data: &[u8], mime_type: &str) -> Self {
+        self.message(PromptMessage::new_image(role, data, mime_type, None))
+    }
+
+    pub fn resource_link(self, role: PromptMessageRole, resource: RawResource) -> Self {
+        self.message(PromptMessage {
+            role,
+            content: PromptMessageContent::ResourceLink {
+                link: resource.no_annotation(),
+            },
+        })
+    }
+
+    /// Add an audio clip, `data` is encoded with base64
+    #[cfg(feature = "base64")]
+    pub fn audio(self, role: PromptMessageRole, data: &[u8], mime_type: &str) -> Self {
+        self.message(PromptMessage::new_audio(role, data, mime_type, None))
+    }
+
+    pub fn build(self) -> GetPromptResult {
+        GetPromptResult {
+            description: self.description,
+            messages: self.messages,
+            meta: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::model::{ErrorCode, object};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(deny_unknown_fields)]
+    struct ReviewArguments {
+        language: String,
+        style: Option<String>,
+    }
+
+    fn param(arguments: Option<serde_json::Value>) -> GetPromptRequestParam {
+        GetPromptRequestParam {
+            name: "code_review".into(),
+            arguments: arguments.map(object),
+        }
+    }
+
+    #[test]
+    fn test_parse_arguments() {
+        let arguments: ReviewArguments = param(Some(json!({ "language": "rust" })))
+            .parse_arguments()
+            .unwrap();
+        assert_eq!(
+            arguments,
+            ReviewArguments {
+                language: "rust".into(),
+                style: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_arguments_missing() {
+        let error = param(None)
+            .parse_arguments::<ReviewArguments>()
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+        assert_eq!(error.data, Some(json!({ "argument": "language" })));
+    }
+
+    #[test]
+    fn test_parse_arguments_unknown() {
+        let error = param(Some(json!({ "language": "rust", "tone": "friendly" })))
+            .parse_arguments::<ReviewArguments>()
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+        assert_eq!(error.data, Some(json!({ "argument": "tone" })));
+    }
+
+    #[test]
+    fn test_parse_arguments_invalid_type() {
+        let error = param(Some(json!({ "language": 1 })))
+            .parse_arguments::<ReviewArguments>()
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+        assert_eq!(error.data, None);
+    }
+
+    #[test]
+    fn test_builder_text() {
+        let result = GetPromptResult::builder()
+            .description("A code review")
+            .user_text("Review this code")
+            .assistant_text("Sure, send it over")
+            .build();
+        assert_eq!(result.description.as_deref(), Some("A code review"));
+        assert_eq!(
+            result.messages,
+            vec![
+                PromptMessage::new_text(PromptMessageRole::User, "Review this code"),
+                PromptMessage::new_text(PromptMessageRole::Assistant, "Sure, send it over"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builder_embedded_resource() {
+        let result = GetPromptResult::builder()
+            .embedded_resource(
+                PromptMessageRole::User,
+                ResourceContents::text("fn main() {}", "file:///main.rs"),
+            )
+            .build();
+        assert_eq!(result.description, None);
+        let [message] = result.messages.as_slice() else {
+            panic!("expected one message");
+        };
+        assert_eq!(message.role, PromptMessageRole::User);
+        let PromptMessageContent::Resource { resource } = &message.content else {
+            panic!("expected a resource");
+        };
+        assert_eq!(
+            resource.resource,
+            ResourceContents::text("fn main() {}", "file:///main.rs")
+        );
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_builder_image() {
+        let result = GetPromptResult::builder()
+            .image(PromptMessageRole::Assistant, b"png", "image/png")
+            .build();
+        let [message] = result.messages.as_slice() else {
+            panic!("expected one message");
+        };
+        assert_eq!(message.role, PromptMessageRole::Assistant);
+        let PromptMessageContent::Image { image } = &message.content else {
+            panic!("expected an image");
+        };
+        assert_eq!(image.data, "cG5n");
+        assert_eq!(image.mime_type, "image/png");
+    }
+}
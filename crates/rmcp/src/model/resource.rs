@@ -1,6 +1,8 @@
+#[cfg(feature = "base64")]
+use base64::engine::{Engine, general_purpose::STANDARD as BASE64_STANDARD};
 use serde::{Deserialize, Serialize};
 
-use super::Annotated;
+use super::{Annotated, ReadResourceResult};
 
 /// Represents a resource in the extension with metadata
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -47,13 +49,13 @@ pub type ResourceTemplate = Annotated<RawResourceTemplate>;
 pub enum ResourceContents {
     TextResourceContents {
         uri: String,
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
         mime_type: Option<String>,
         text: String,
     },
     BlobResourceContents {
         uri: String,
-        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
         mime_type: Option<String>,
         blob: String,
     },
@@ -81,3 +83,66 @@ impl RawResource {
         }
     }
 }
+
+impl ReadResourceResult {
+    /// The text content of a resource
+    pub fn text(
+        uri: impl Into<String>,
+        mime_type: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        Self {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri: uri.into(),
+                mime_type: Some(mime_type.into()),
+                text: text.into(),
+            }],
+            meta: None,
+        }
+    }
+
+    /// The binary content of a resource, `data` is encoded with base64
+    #[cfg(feature = "base64")]
+    pub fn blob(uri: impl Into<String>, mime_type: impl Into<String>, data: &[u8]) -> Self {
+        Self {
+            contents: vec![ResourceContents::BlobResourceContents {
+                uri: uri.into(),
+                mime_type: Some(mime_type.into()),
+                blob: BASE64_STANDARD.encode(data),
+            }],
+            meta: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_resource_result_text() {
+        let result = ReadResourceResult::text("file:///notes.md", "text/markdown", "# Notes");
+        assert_eq!(
+            result.contents,
+            vec![ResourceContents::TextResourceContents {
+                uri: "file:///notes.md".into(),
+                mime_type: Some("text/markdown".into()),
+                text: "# Notes".into(),
+            }]
+        );
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_read_resource_result_blob() {
+        let result = ReadResourceResult::blob("file:///logo.png", "image/png", &[0x89, b'P', b'N']);
+        assert_eq!(
+            result.contents,
+            vec![ResourceContents::BlobResourceContents {
+                uri: "file:///logo.png".into(),
+                mime_type: Some("image/png".into()),
+                blob: "iVBO".into(),
+            }]
+        );
+    }
+}
@@ -1,12 +1,18 @@
 use std::borrow::Cow;
 
-use serde::{Deserialize, Serialize};
+use serde::{
+    Deserialize, Serialize,
+    de::{DeserializeOwned, IgnoredAny},
+};
+use serde_json::{Value, value::RawValue};
 
 use super::{
-    Extensions, Meta, Notification, NotificationNoParam, Request, RequestNoParam,
-    RequestOptionalParam,
+    ConstString, CustomNotification, CustomRequest, Extensions, JsonRpcBatchRequestItem,
+    JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcVersion2_0, Meta, Notification,
+    NotificationNoParam, Request, RequestId, RequestNoParam, RequestOptionalParam,
+    is_known_notification_method, is_known_request_method,
 };
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize)]
 struct WithMeta<'a, P> {
     #[serde(skip_serializing_if = "Option::is_none")]
     _meta: Option<Cow<'a, Meta>>,
@@ -14,21 +20,107 @@ struct WithMeta<'a, P> {
     _rest: P,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize)]
 struct Proxy<'a, M, P> {
     method: M,
     params: WithMeta<'a, P>,
 }
 
 #[derive(Serialize, Deserialize)]
-struct ProxyOptionalParam<'a, M, P> {
+struct ProxyNoParam<M> {
     method: M,
-    params: Option<WithMeta<'a, P>>,
 }
 
-#[derive(Serialize, Deserialize)]
-struct ProxyNoParam<M> {
+/// A message read from its JSON text, its params are read once the `_meta` is split out
+#[derive(Deserialize)]
+struct RawProxy<'a, M> {
     method: M,
+    #[serde(borrow, default)]
+    params: Option<&'a RawValue>,
+}
+
+/// Whether params have a `_meta`, and what it is
+#[derive(Deserialize)]
+struct MetaOf {
+    #[serde(default, deserialize_with = "present")]
+    _meta: Option<Option<Meta>>,
+}
+
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// The members of an object, their values kept as JSON text
+struct Members<'a>(Vec<(String, &'a RawValue)>);
+
+impl<'de> Deserialize<'de> for Members<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct MembersVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for MembersVisitor {
+            type Value = Members<'de>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("an object")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut members = Vec::new();
+                while let Some(member) = map.next_entry()? {
+                    members.push(member);
+                }
+                Ok(Members(members))
+            }
+        }
+
+        deserializer.deserialize_map(MembersVisitor)
+    }
+}
+
+/// Split the `_meta` out of params, the text of the others is borrowed unless there is one
+fn split_meta(params: &RawValue) -> serde_json::Result<(Option<Meta>, Cow<'_, str>)> {
+    let json = params.get();
+    if !json.starts_with('{') {
+        return Err(serde::de::Error::invalid_type(
+            serde::de::Unexpected::Other("params which are not an object"),
+            &"an object",
+        ));
+    }
+    let Some(_meta) = serde_json::from_str::<MetaOf>(json)?._meta else {
+        return Ok((None, Cow::Borrowed(json)));
+    };
+    let Members(members) = serde_json::from_str(json)?;
+    let mut rest = Vec::with_capacity(json.len());
+    rest.push(b'{');
+    for (index, (key, value)) in members.iter().filter(|(key, _)| key != "_meta").enumerate() {
+        if index > 0 {
+            rest.push(b',');
+        }
+        serde_json::to_writer(&mut rest, key)?;
+        rest.push(b':');
+        rest.extend_from_slice(value.get().as_bytes());
+    }
+    rest.push(b'}');
+    let rest = String::from_utf8(rest).expect("written from strings");
+    Ok((_meta, Cow::Owned(rest)))
+}
+
+fn meta_extensions(_meta: Option<Meta>) -> Extensions {
+    let mut extensions = Extensions::new();
+    if let Some(meta) = _meta {
+        extensions.insert(meta);
+    }
+    extensions
 }
 
 impl<M, R> Serialize for Request<M, R>
@@ -57,27 +149,35 @@ where
 
 impl<'de, M, R> Deserialize<'de> for Request<M, R>
 where
-    M: Deserialize<'de>,
-    R: Deserialize<'de>,
+    M: DeserializeOwned,
+    R: DeserializeOwned,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        let body = Proxy::deserialize(deserializer)?;
-        let _meta = body.params._meta.map(|m| m.into_owned());
-        let mut extensions = Extensions::new();
-        if let Some(meta) = _meta {
-            extensions.insert(meta);
-        }
-        Ok(Request {
-            extensions,
-            method: body.method,
-            params: body.params._rest,
-        })
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        request_from_raw(&raw).map_err(serde::de::Error::custom)
     }
 }
 
+fn request_from_raw<M, R>(raw: &RawValue) -> serde_json::Result<Request<M, R>>
+where
+    M: DeserializeOwned,
+    R: DeserializeOwned,
+{
+    let body: RawProxy<M> = serde_json::from_str(raw.get())?;
+    let params = body
+        .params
+        .ok_or_else(|| serde::de::Error::missing_field("params"))?;
+    let (_meta, params) = split_meta(params)?;
+    Ok(Request {
+        extensions: meta_extensions(_meta),
+        method: body.method,
+        params: serde_json::from_str(&params)?,
+    })
+}
+
 impl<M, R> Serialize for RequestOptionalParam<M, R>
 where
     M: Serialize,
@@ -104,32 +204,41 @@ where
 
 impl<'de, M, R> Deserialize<'de> for RequestOptionalParam<M, R>
 where
-    M: Deserialize<'de>,
-    R: Deserialize<'de>,
+    M: DeserializeOwned,
+    R: DeserializeOwned,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        let body = ProxyOptionalParam::<'_, _, Option<R>>::deserialize(deserializer)?;
-        let mut params = None;
-        let mut _meta = None;
-        if let Some(body_params) = body.params {
-            params = body_params._rest;
-            _meta = body_params._meta.map(|m| m.into_owned());
-        }
-        let mut extensions = Extensions::new();
-        if let Some(meta) = _meta {
-            extensions.insert(meta);
-        }
-        Ok(RequestOptionalParam {
-            extensions,
-            method: body.method,
-            params,
-        })
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        request_optional_param_from_raw(&raw).map_err(serde::de::Error::custom)
     }
 }
 
+/// Params which don't deserialize are read as none, their `_meta` is kept
+fn request_optional_param_from_raw<M, R>(
+    raw: &RawValue,
+) -> serde_json::Result<RequestOptionalParam<M, R>>
+where
+    M: DeserializeOwned,
+    R: DeserializeOwned,
+{
+    let body: RawProxy<M> = serde_json::from_str(raw.get())?;
+    let mut params = None;
+    let mut _meta = None;
+    if let Some(body_params) = body.params {
+        let (meta, body_params) = split_meta(body_params)?;
+        params = serde_json::from_str(&body_params).ok();
+        _meta = meta;
+    }
+    Ok(RequestOptionalParam {
+        extensions: meta_extensions(_meta),
+        method: body.method,
+        params,
+    })
+}
+
 impl<M> Serialize for RequestNoParam<M>
 where
     M: Serialize,
@@ -192,27 +301,35 @@ where
 
 impl<'de, M, R> Deserialize<'de> for Notification<M, R>
 where
-    M: Deserialize<'de>,
-    R: Deserialize<'de>,
+    M: DeserializeOwned,
+    R: DeserializeOwned,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        let body = Proxy::deserialize(deserializer)?;
-        let _meta = body.params._meta.map(|m| m.into_owned());
-        let mut extensions = Extensions::new();
-        if let Some(meta) = _meta {
-            extensions.insert(meta);
-        }
-        Ok(Notification {
-            extensions,
-            method: body.method,
-            params: body.params._rest,
-        })
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        notification_from_raw(&raw).map_err(serde::de::Error::custom)
     }
 }
 
+fn notification_from_raw<M, R>(raw: &RawValue) -> serde_json::Result<Notification<M, R>>
+where
+    M: DeserializeOwned,
+    R: DeserializeOwned,
+{
+    let body: RawProxy<M> = serde_json::from_str(raw.get())?;
+    let params = body
+        .params
+        .ok_or_else(|| serde::de::Error::missing_field("params"))?;
+    let (_meta, params) = split_meta(params)?;
+    Ok(Notification {
+        extensions: meta_extensions(_meta),
+        method: body.method,
+        params: serde_json::from_str(&params)?,
+    })
+}
+
 impl<M> Serialize for NotificationNoParam<M>
 where
     M: Serialize,
@@ -249,11 +366,378 @@ where
     }
 }
 
+#[derive(Serialize, Deserialize)]
+struct ProxyCustom<'a> {
+    method: Cow<'a, str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    params: Option<Cow<'a, Value>>,
+}
+
+/// The params of a custom message with the [`Meta`] of its extensions merged in their `_meta`
+///
+/// Params which are not an object can't carry a `_meta`, they are sent as they are.
+fn custom_params<'a>(params: &'a Option<Value>, extensions: &Extensions) -> Option<Cow<'a, Value>> {
+    let Some(Meta(meta)) = extensions.get::<Meta>().filter(|meta| !meta.0.is_empty()) else {
+        return params.as_ref().map(Cow::Borrowed);
+    };
+    let mut params = match params {
+        Some(Value::Object(params)) => params.clone(),
+        None => Default::default(),
+        Some(params) => return Some(Cow::Borrowed(params)),
+    };
+    let params_meta = params
+        .entry("_meta")
+        .or_insert_with(|| Value::Object(Default::default()));
+    if let Value::Object(params_meta) = params_meta {
+        params_meta.extend(meta.clone());
+    }
+    Some(Cow::Owned(Value::Object(params)))
+}
+
+/// The method of a custom message received, which can't be one the SDK knows: a known request or
+/// notification whose params don't parse isn't a custom one
+fn custom_method<E: serde::de::Error>(method: Cow<'_, str>) -> Result<String, E> {
+    if is_known_request_method(&method) || is_known_notification_method(&method) {
+        return Err(E::custom(format!("invalid params for {method}")));
+    }
+    Ok(method.into_owned())
+}
+
+/// The extensions of a custom message received, with the `_meta` of its params
+fn custom_extensions(params: Option<&Value>) -> Extensions {
+    let mut extensions = Extensions::new();
+    if let Some(meta) = params
+        .and_then(|params| params.get("_meta"))
+        .and_then(|meta| Meta::deserialize(meta).ok())
+    {
+        extensions.insert(meta);
+    }
+    extensions
+}
+
+impl Serialize for CustomRequest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        ProxyCustom {
+            method: Cow::Borrowed(&self.method),
+            params: custom_params(&self.params, &self.extensions),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CustomRequest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let body = ProxyCustom::deserialize(deserializer)?;
+        let params = body.params.map(Cow::into_owned);
+        Ok(CustomRequest {
+            extensions: custom_extensions(params.as_ref()),
+            method: custom_method(body.method)?,
+            params,
+        })
+    }
+}
+
+impl Serialize for CustomNotification {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        ProxyCustom {
+            method: Cow::Borrowed(&self.method),
+            params: custom_params(&self.params, &self.extensions),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CustomNotification {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let body = ProxyCustom::deserialize(deserializer)?;
+        let params = body.params.map(Cow::into_owned);
+        Ok(CustomNotification {
+            extensions: custom_extensions(params.as_ref()),
+            method: custom_method(body.method)?,
+            params,
+        })
+    }
+}
+
+/// A request or notification read from the JSON text of a message by a union of them, see
+/// `method_union!`
+pub(crate) trait FromRawMessage: Sized {
+    /// Whether a message of `method` may be a `Self`
+    fn accepts(method: &str) -> bool;
+    fn from_raw(raw: &RawValue) -> serde_json::Result<Self>;
+}
+
+impl<M, R> FromRawMessage for Request<M, R>
+where
+    M: ConstString + DeserializeOwned,
+    R: DeserializeOwned,
+{
+    fn accepts(method: &str) -> bool {
+        method == M::VALUE
+    }
+
+    fn from_raw(raw: &RawValue) -> serde_json::Result<Self> {
+        request_from_raw(raw)
+    }
+}
+
+impl<M, R> FromRawMessage for RequestOptionalParam<M, R>
+where
+    M: ConstString + DeserializeOwned,
+    R: DeserializeOwned,
+{
+    fn accepts(method: &str) -> bool {
+        method == M::VALUE
+    }
+
+    fn from_raw(raw: &RawValue) -> serde_json::Result<Self> {
+        request_optional_param_from_raw(raw)
+    }
+}
+
+impl<M> FromRawMessage for RequestNoParam<M>
+where
+    M: ConstString + DeserializeOwned,
+{
+    fn accepts(method: &str) -> bool {
+        method == M::VALUE
+    }
+
+    fn from_raw(raw: &RawValue) -> serde_json::Result<Self> {
+        serde_json::from_str(raw.get())
+    }
+}
+
+impl<M, R> FromRawMessage for Notification<M, R>
+where
+    M: ConstString + DeserializeOwned,
+    R: DeserializeOwned,
+{
+    fn accepts(method: &str) -> bool {
+        method == M::VALUE
+    }
+
+    fn from_raw(raw: &RawValue) -> serde_json::Result<Self> {
+        notification_from_raw(raw)
+    }
+}
+
+impl<M> FromRawMessage for NotificationNoParam<M>
+where
+    M: ConstString + DeserializeOwned,
+{
+    fn accepts(method: &str) -> bool {
+        method == M::VALUE
+    }
+
+    fn from_raw(raw: &RawValue) -> serde_json::Result<Self> {
+        serde_json::from_str(raw.get())
+    }
+}
+
+/// A custom message can't be of a known method, which its `Deserialize` checks
+impl FromRawMessage for CustomRequest {
+    fn accepts(_method: &str) -> bool {
+        true
+    }
+
+    fn from_raw(raw: &RawValue) -> serde_json::Result<Self> {
+        serde_json::from_str(raw.get())
+    }
+}
+
+impl FromRawMessage for CustomNotification {
+    fn accepts(_method: &str) -> bool {
+        true
+    }
+
+    fn from_raw(raw: &RawValue) -> serde_json::Result<Self> {
+        serde_json::from_str(raw.get())
+    }
+}
+
+#[derive(Deserialize)]
+struct MethodOf<'a> {
+    #[serde(borrow)]
+    method: Cow<'a, str>,
+}
+
+/// The method of a message, `None` if it has none
+pub(crate) fn method_of(raw: &RawValue) -> Option<Cow<'_, str>> {
+    serde_json::from_str::<MethodOf>(raw.get())
+        .ok()
+        .map(|body| body.method)
+}
+
+#[derive(Deserialize)]
+struct RequestHeader {
+    jsonrpc: JsonRpcVersion2_0,
+    id: RequestId,
+}
+
+#[derive(Deserialize)]
+struct NotificationHeader {
+    jsonrpc: JsonRpcVersion2_0,
+}
+
+/// The request is read from the same text as the header, it ignores `jsonrpc` and `id`
+fn json_rpc_request_from_raw<R: DeserializeOwned>(
+    raw: &RawValue,
+) -> serde_json::Result<JsonRpcRequest<R>> {
+    let RequestHeader { jsonrpc, id } = serde_json::from_str(raw.get())?;
+    Ok(JsonRpcRequest {
+        jsonrpc,
+        id,
+        request: serde_json::from_str(raw.get())?,
+    })
+}
+
+fn json_rpc_notification_from_raw<N: DeserializeOwned>(
+    raw: &RawValue,
+) -> serde_json::Result<JsonRpcNotification<N>> {
+    let NotificationHeader { jsonrpc } = serde_json::from_str(raw.get())?;
+    Ok(JsonRpcNotification {
+        jsonrpc,
+        notification: serde_json::from_str(raw.get())?,
+    })
+}
+
+impl<'de, R: DeserializeOwned> Deserialize<'de> for JsonRpcRequest<R> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        json_rpc_request_from_raw(&raw).map_err(serde::de::Error::custom)
+    }
+}
+
+impl<'de, N: DeserializeOwned> Deserialize<'de> for JsonRpcNotification<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        json_rpc_notification_from_raw(&raw).map_err(serde::de::Error::custom)
+    }
+}
+
+impl<'de, Req, Not> Deserialize<'de> for JsonRpcBatchRequestItem<Req, Not>
+where
+    Req: DeserializeOwned,
+    Not: DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        json_rpc_request_from_raw(&raw)
+            .map(JsonRpcBatchRequestItem::Request)
+            .or_else(|_| {
+                json_rpc_notification_from_raw(&raw).map(JsonRpcBatchRequestItem::Notification)
+            })
+            .map_err(|_| {
+                serde::de::Error::custom(
+                    "data did not match any variant of untagged enum JsonRpcBatchRequestItem",
+                )
+            })
+    }
+}
+
+/// The members which tell the kinds of message apart, their values are skipped
+#[derive(Deserialize)]
+struct MessageMembers {
+    #[serde(default, deserialize_with = "present")]
+    id: Option<IgnoredAny>,
+    #[serde(default, deserialize_with = "present")]
+    method: Option<IgnoredAny>,
+    #[serde(default, deserialize_with = "present")]
+    result: Option<IgnoredAny>,
+    #[serde(default, deserialize_with = "present")]
+    error: Option<IgnoredAny>,
+}
+
+/// A message is read from its text once for each kind it may be, in the order of the variants
+fn json_rpc_message_from_raw<Req, Resp, Not>(
+    raw: &RawValue,
+) -> Option<JsonRpcMessage<Req, Resp, Not>>
+where
+    Req: DeserializeOwned,
+    Resp: DeserializeOwned,
+    Not: DeserializeOwned,
+{
+    let json = raw.get();
+    if json.starts_with('[') {
+        return serde_json::from_str(json)
+            .map(JsonRpcMessage::BatchRequest)
+            .or_else(|_| serde_json::from_str(json).map(JsonRpcMessage::BatchResponse))
+            .ok();
+    }
+    let members: MessageMembers = serde_json::from_str(json).ok()?;
+    let (id, method) = (members.id.is_some(), members.method.is_some());
+    if id && method {
+        if let Ok(request) = json_rpc_request_from_raw(raw) {
+            return Some(JsonRpcMessage::Request(request));
+        }
+    }
+    if id && members.result.is_some() {
+        if let Ok(response) = serde_json::from_str(json) {
+            return Some(JsonRpcMessage::Response(response));
+        }
+    }
+    if method {
+        if let Ok(notification) = json_rpc_notification_from_raw(raw) {
+            return Some(JsonRpcMessage::Notification(notification));
+        }
+    }
+    if id && members.error.is_some() {
+        if let Ok(error) = serde_json::from_str(json) {
+            return Some(JsonRpcMessage::Error(error));
+        }
+    }
+    None
+}
+
+impl<'de, Req, Resp, Not> Deserialize<'de> for JsonRpcMessage<Req, Resp, Not>
+where
+    Req: DeserializeOwned,
+    Resp: DeserializeOwned,
+    Not: DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        json_rpc_message_from_raw(&raw).ok_or_else(|| {
+            serde::de::Error::custom(
+                "data did not match any variant of untagged enum JsonRpcMessage",
+            )
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use serde_json::json;
 
-    use crate::model::ListToolsRequest;
+    use crate::model::{
+        ClientJsonRpcMessage, ClientRequest, CustomNotification, CustomRequest, JsonRpcMessage,
+        ListToolsRequest, Meta, ProgressToken,
+    };
 
     #[test]
     fn test_deserialize_lost_tools_request() {
@@ -264,4 +748,93 @@ mod test {
         ))
         .unwrap();
     }
+
+    #[test]
+    fn test_custom_request_is_lossless() {
+        let message = json!({
+            "method": "x-demo/echo",
+            "params": { "text": "hi", "_meta": { "traceId": "4bf9" } }
+        });
+        let request: CustomRequest = serde_json::from_value(message.clone()).unwrap();
+        assert_eq!(request.method, "x-demo/echo");
+        assert_eq!(request.params, Some(message["params"].clone()));
+        let meta = request.extensions.get::<Meta>().unwrap();
+        assert_eq!(meta.0.get("traceId"), Some(&json!("4bf9")));
+        assert_eq!(serde_json::to_value(&request).unwrap(), message);
+
+        // the meta set when sending is merged in the params
+        let mut request = CustomRequest::new("x-demo/echo", Some(json!({ "text": "hi" })));
+        let mut meta = Meta::new();
+        meta.set_progress_token(ProgressToken::from("progress-1"));
+        request.extensions.insert(meta);
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "method": "x-demo/echo",
+                "params": { "text": "hi", "_meta": { "progressToken": "progress-1" } }
+            })
+        );
+        request.params = None;
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "method": "x-demo/echo",
+                "params": { "_meta": { "progressToken": "progress-1" } }
+            })
+        );
+        // but params which are not an object are sent as they are
+        request.params = Some(json!([1, 2]));
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({ "method": "x-demo/echo", "params": [1, 2] })
+        );
+
+        let notification: CustomNotification =
+            serde_json::from_value(json!({ "method": "x-demo/tick" })).unwrap();
+        assert_eq!(notification.params, None);
+        assert_eq!(
+            serde_json::to_value(&notification).unwrap(),
+            json!({ "method": "x-demo/tick" })
+        );
+    }
+
+    #[test]
+    fn test_tool_call_arguments_are_read_as_text() {
+        let message = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"echo","_meta":{"progressToken":"p-1"},"arguments":{"text":"a \"quoted\" line","count":2}}}"#;
+        let message: ClientJsonRpcMessage = serde_json::from_str(message).unwrap();
+        let JsonRpcMessage::Request(request) = message else {
+            panic!("a request");
+        };
+        let ClientRequest::CallToolRequest(request) = request.request else {
+            panic!("a tool call");
+        };
+        let meta = request.extensions.get::<Meta>().unwrap();
+        assert_eq!(meta.0.get("progressToken"), Some(&json!("p-1")));
+        let arguments = request.params.arguments.unwrap();
+        assert_eq!(
+            arguments.as_raw().map(|raw| raw.get()),
+            Some(r#"{"text":"a \"quoted\" line","count":2}"#)
+        );
+
+        // a message built as a value is read the same way
+        let message: ClientJsonRpcMessage = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": { "name": "echo", "arguments": { "count": 2 } }
+        }))
+        .unwrap();
+        let JsonRpcMessage::Request(request) = message else {
+            panic!("a request");
+        };
+        let ClientRequest::CallToolRequest(request) = request.request else {
+            panic!("a tool call");
+        };
+        assert!(request.extensions.get::<Meta>().is_none());
+        let arguments = request.params.arguments.unwrap();
+        assert_eq!(
+            arguments.as_raw().map(|raw| raw.get()),
+            Some(r#"{"count":2}"#)
+        );
+    }
 }
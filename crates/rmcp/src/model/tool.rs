@@ -14,14 +14,62 @@ use super::JsonObject;
 pub struct Tool {
     /// The name of the tool
     pub name: Cow<'static, str>,
+    /// A human-readable title for the tool, shown instead of the name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<Cow<'static, str>>,
     /// A description of what the tool does
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<Cow<'static, str>>,
     /// A JSON Schema object defining the expected parameters for the tool
     pub input_schema: Arc<JsonObject>,
+    /// An optional JSON Schema object defining the structure of the tool's output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Arc<JsonObject>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Optional additional tool information.
     pub annotations: Option<ToolAnnotations>,
+    /// Icons the clients can display for the tool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icons: Option<Vec<Icon>>,
+}
+
+/// An icon of a tool, loaded from a URL or embedded as a `data:` URI
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Icon {
+    /// The URI of the icon
+    pub src: String,
+    /// The MIME type of the icon, when it can't be told from the URI
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// The sizes the icon is available in, such as `48x48` or `any` for scalable formats
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sizes: Option<Vec<String>>,
+}
+
+impl Icon {
+    pub fn new(src: impl Into<String>) -> Self {
+        Icon {
+            src: src.into(),
+            mime_type: None,
+            sizes: None,
+        }
+    }
+
+    pub fn with_mime_type(self, mime_type: impl Into<String>) -> Self {
+        Icon {
+            mime_type: Some(mime_type.into()),
+            ..self
+        }
+    }
+
+    pub fn with_sizes<S: Into<String>>(self, sizes: impl IntoIterator<Item = S>) -> Self {
+        Icon {
+            sizes: Some(sizes.into_iter().map(Into::into).collect()),
+            ..self
+        }
+    }
 }
 
 /// Additional properties describing a Tool to clients.
@@ -73,6 +121,10 @@ pub struct ToolAnnotations {
     /// Default: true
     #[serde(skip_serializing_if = "Option::is_none")]
     pub open_world_hint: Option<bool>,
+
+    /// If set, the tool is deprecated and this message tells what to use instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
 }
 
 impl ToolAnnotations {
@@ -113,6 +165,16 @@ impl ToolAnnotations {
         }
     }
 
+    pub fn deprecated<T>(self, message: T) -> Self
+    where
+        T: Into<String>,
+    {
+        ToolAnnotations {
+            deprecated: Some(message.into()),
+            ..self
+        }
+    }
+
     /// If not set, defaults to true.
     pub fn is_destructive(&self) -> bool {
         self.destructive_hint.unwrap_or(true)
@@ -134,9 +196,39 @@ impl Tool {
     {
         Tool {
             name: name.into(),
+            title: None,
             description: Some(description.into()),
             input_schema: input_schema.into(),
+            output_schema: None,
             annotations: None,
+            icons: None,
+        }
+    }
+
+    pub fn with_title<T>(self, title: T) -> Self
+    where
+        T: Into<Cow<'static, str>>,
+    {
+        Tool {
+            title: Some(title.into()),
+            ..self
+        }
+    }
+
+    pub fn with_icons(self, icons: impl IntoIterator<Item = Icon>) -> Self {
+        Tool {
+            icons: Some(icons.into_iter().collect()),
+            ..self
+        }
+    }
+
+    pub fn with_output_schema<S>(self, output_schema: S) -> Self
+    where
+        S: Into<Arc<JsonObject>>,
+    {
+        Tool {
+            output_schema: Some(output_schema.into()),
+            ..self
         }
     }
 
@@ -151,4 +243,113 @@ impl Tool {
     pub fn schema_as_json_value(&self) -> Value {
         Value::Object(self.input_schema.as_ref().clone())
     }
+
+    /// The title to display: the title of the tool, or of its annotations, or the name
+    pub fn display_title(&self) -> &str {
+        self.title
+            .as_deref()
+            .or_else(|| self.annotations.as_ref()?.title.as_deref())
+            .unwrap_or(&self.name)
+    }
+}
+
+/// Make the `$ref`s of a schema generated by rmcp resolve
+///
+/// Its definitions are under `definitions`, but referenced as `#/components/schemas/..`.
+#[cfg(any(feature = "schema-validation", feature = "client-schema-validation"))]
+pub(crate) fn resolvable_schema(mut schema: JsonObject) -> Value {
+    if let Some(definitions) = schema.get("definitions").cloned() {
+        schema
+            .entry("components")
+            .or_insert_with(|| serde_json::json!({ "schemas": definitions }));
+    }
+    Value::Object(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::model::object;
+
+    // the `tools/list` example of the specification
+    fn weather_fixture() -> Value {
+        json!({
+            "name": "get_weather",
+            "title": "Weather Information Provider",
+            "description": "Get current weather information for a location",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "location": {
+                        "type": "string",
+                        "description": "City name or zip code"
+                    }
+                },
+                "required": ["location"]
+            },
+            "icons": [
+                {
+                    "src": "https://example.com/weather-icon.png",
+                    "mimeType": "image/png",
+                    "sizes": ["48x48"]
+                }
+            ]
+        })
+    }
+
+    fn weather_tool() -> Tool {
+        let input_schema = object(json!({
+            "type": "object",
+            "properties": {
+                "location": {
+                    "type": "string",
+                    "description": "City name or zip code"
+                }
+            },
+            "required": ["location"]
+        }));
+        Tool::new(
+            "get_weather",
+            "Get current weather information for a location",
+            input_schema,
+        )
+    }
+
+    #[test]
+    fn test_title_and_icons_serialization() {
+        let tool = weather_tool()
+            .with_title("Weather Information Provider")
+            .with_icons([Icon::new("https://example.com/weather-icon.png")
+                .with_mime_type("image/png")
+                .with_sizes(["48x48"])]);
+        assert_eq!(serde_json::to_value(&tool).unwrap(), weather_fixture());
+        let deserialized: Tool = serde_json::from_value(weather_fixture()).unwrap();
+        assert_eq!(deserialized, tool);
+    }
+
+    #[test]
+    fn test_title_and_icons_are_optional() {
+        let value = serde_json::to_value(weather_tool()).unwrap();
+        assert!(value.get("title").is_none());
+        assert!(value.get("icons").is_none());
+
+        let mut fixture = weather_fixture();
+        let fixture_object = fixture.as_object_mut().unwrap();
+        fixture_object.remove("title");
+        fixture_object.remove("icons");
+        let tool: Tool = serde_json::from_value(fixture).unwrap();
+        assert_eq!(tool, weather_tool());
+    }
+
+    #[test]
+    fn test_display_title() {
+        let tool = weather_tool();
+        assert_eq!(tool.display_title(), "get_weather");
+        let tool = tool.annotate(ToolAnnotations::with_title("Weather"));
+        assert_eq!(tool.display_title(), "Weather");
+        let tool = tool.with_title("Weather Information Provider");
+        assert_eq!(tool.display_title(), "Weather Information Provider");
+    }
 }
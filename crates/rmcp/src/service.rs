@@ -4,25 +4,54 @@ use thiserror::Error;
 use crate::{
     error::Error as McpError,
     model::{
-        CancelledNotification, CancelledNotificationParam, Extensions, GetExtensions, GetMeta,
-        JsonRpcBatchRequestItem, JsonRpcBatchResponseItem, JsonRpcError, JsonRpcMessage,
-        JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, Meta, NumberOrString, ProgressToken,
-        RequestId, ServerJsonRpcMessage,
+        CancelledNotification, CancelledNotificationParam, CustomNotification, CustomRequest,
+        Extensions, GetExtensions, GetMeta, GetMethod, JsonRpcBatchRequestItem,
+        JsonRpcBatchResponseItem, JsonRpcError, JsonRpcMessage, JsonRpcNotification,
+        JsonRpcRequest, JsonRpcResponse, Meta, PingRequest, ProgressNotification,
+        ProgressNotificationParam, ProgressToken, RequestId, ServerJsonRpcMessage,
     },
-    transport::{IntoTransport, Transport},
+    transport::{IntoTransport, Transport, TransportStatus},
 };
+mod batch;
+mod keepalive;
+pub use keepalive::*;
+mod notification_queue;
+use notification_queue::NotificationQueue;
+pub use notification_queue::{NotificationQueueConfig, OverflowPolicy, WithNotificationQueue};
+mod observer;
+pub use observer::*;
+mod progress;
+pub use progress::ProgressHandler;
+mod retry;
+pub use retry::RetryPolicy;
+mod session;
+pub use session::*;
+mod state;
+pub use state::*;
 #[cfg(feature = "client")]
 #[cfg_attr(docsrs, doc(cfg(feature = "client")))]
 mod client;
 #[cfg(feature = "client")]
 #[cfg_attr(docsrs, doc(cfg(feature = "client")))]
 pub use client::*;
+#[cfg(feature = "client-schema-validation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "client-schema-validation")))]
+mod tool_validation;
+#[cfg(feature = "client-schema-validation")]
+#[cfg_attr(docsrs, doc(cfg(feature = "client-schema-validation")))]
+pub use tool_validation::*;
 #[cfg(feature = "server")]
 #[cfg_attr(docsrs, doc(cfg(feature = "server")))]
 mod server;
 #[cfg(feature = "server")]
 #[cfg_attr(docsrs, doc(cfg(feature = "server")))]
 pub use server::*;
+#[cfg(feature = "server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+mod runner;
+#[cfg(feature = "server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+pub use runner::{LiveSession, ServerRunner, SessionInfo};
 #[cfg(feature = "tower")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
 mod tower;
@@ -46,6 +75,17 @@ pub enum ServiceError {
     Cancelled { reason: Option<String> },
     #[error("request timeout after {}", chrono::Duration::from_std(*timeout).unwrap_or_default())]
     Timeout { timeout: Duration },
+    #[error("{method} returned the cursor {cursor:?} more than once")]
+    RepeatedCursor {
+        method: &'static str,
+        cursor: String,
+    },
+    #[error("{method} had more results after {pages} pages")]
+    TooManyPages { method: &'static str, pages: usize },
+    #[cfg(feature = "client-schema-validation")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "client-schema-validation")))]
+    #[error(transparent)]
+    SchemaViolation(SchemaViolation),
 }
 
 impl ServiceError {}
@@ -67,15 +107,28 @@ impl<T> TransferObject for T where
 
 #[allow(private_bounds, reason = "there's no the third implementation")]
 pub trait ServiceRole: std::fmt::Debug + Send + Sync + 'static + Copy + Clone {
-    type Req: TransferObject + GetMeta + GetExtensions;
+    type Req: TransferObject
+        + GetMeta
+        + GetExtensions
+        + GetMethod
+        + From<PingRequest>
+        + From<CustomRequest>;
     type Resp: TransferObject;
     type Not: TryInto<CancelledNotification, Error = Self::Not>
         + From<CancelledNotification>
+        + TryInto<ProgressNotification, Error = Self::Not>
+        + From<ProgressNotification>
+        + From<CustomNotification>
+        + GetMethod
         + TransferObject;
-    type PeerReq: TransferObject + GetMeta + GetExtensions;
+    type PeerReq: TransferObject + GetMeta + GetExtensions + GetMethod;
     type PeerResp: TransferObject;
     type PeerNot: TryInto<CancelledNotification, Error = Self::PeerNot>
         + From<CancelledNotification>
+        + TryInto<ProgressNotification, Error = Self::PeerNot>
+        + From<ProgressNotification>
+        + GetExtensions
+        + GetMethod
         + TransferObject;
     type InitializeError<E>;
     const IS_CLIENT: bool;
@@ -102,6 +155,23 @@ pub trait Service<R: ServiceRole>: Send + Sync + 'static {
         notification: R::PeerNot,
     ) -> impl Future<Output = Result<(), McpError>> + Send + '_;
     fn get_info(&self) -> R::Info;
+    /// The observer of the requests and notifications, see [`ServiceExt::with_observer`]
+    fn observer(&self) -> Option<Arc<dyn ServiceObserver>> {
+        None
+    }
+    /// The queue of the notifications sent to the peer, see [`ServiceExt::with_notification_queue`]
+    fn notification_queue(&self) -> NotificationQueueConfig {
+        NotificationQueueConfig::default()
+    }
+    /// Called once the connection is closed, before the values of `session` are dropped
+    fn handle_disconnect(&self, session: SessionData) -> impl Future<Output = ()> + Send + '_ {
+        drop(session);
+        std::future::ready(())
+    }
+    /// Called with the peer once the session is initialized, before any message is handled
+    fn bind_peer(&self, peer: &Peer<R>) {
+        let _ = peer;
+    }
 }
 
 pub trait ServiceExt<R: ServiceRole>: Service<R> + Sized {
@@ -111,6 +181,17 @@ pub trait ServiceExt<R: ServiceRole>: Service<R> + Sized {
     fn into_dyn(self) -> Box<dyn DynService<R>> {
         Box::new(self)
     }
+    /// Report the requests and notifications of the service to `observer`, see [`ServiceObserver`]
+    fn with_observer(self, observer: impl ServiceObserver) -> Observed<Self> {
+        Observed::new(self, observer)
+    }
+    /// Bound the notifications waiting for the transport, see [`NotificationQueueConfig`]
+    fn with_notification_queue(
+        self,
+        config: NotificationQueueConfig,
+    ) -> WithNotificationQueue<Self> {
+        WithNotificationQueue::new(self, config)
+    }
     fn serve<T, E, A>(
         self,
         transport: T,
@@ -152,6 +233,22 @@ impl<R: ServiceRole> Service<R> for Box<dyn DynService<R>> {
     fn get_info(&self) -> R::Info {
         DynService::get_info(self.as_ref())
     }
+
+    fn observer(&self) -> Option<Arc<dyn ServiceObserver>> {
+        DynService::observer(self.as_ref())
+    }
+
+    fn notification_queue(&self) -> NotificationQueueConfig {
+        DynService::notification_queue(self.as_ref())
+    }
+
+    fn handle_disconnect(&self, session: SessionData) -> impl Future<Output = ()> + Send + '_ {
+        DynService::handle_disconnect(self.as_ref(), session)
+    }
+
+    fn bind_peer(&self, peer: &Peer<R>) {
+        DynService::bind_peer(self.as_ref(), peer)
+    }
 }
 
 pub trait DynService<R: ServiceRole>: Send + Sync {
@@ -162,6 +259,10 @@ pub trait DynService<R: ServiceRole>: Send + Sync {
     ) -> BoxFuture<Result<R::Resp, McpError>>;
     fn handle_notification(&self, notification: R::PeerNot) -> BoxFuture<Result<(), McpError>>;
     fn get_info(&self) -> R::Info;
+    fn observer(&self) -> Option<Arc<dyn ServiceObserver>>;
+    fn notification_queue(&self) -> NotificationQueueConfig;
+    fn handle_disconnect(&self, session: SessionData) -> BoxFuture<()>;
+    fn bind_peer(&self, peer: &Peer<R>);
 }
 
 impl<R: ServiceRole, S: Service<R>> DynService<R> for S {
@@ -178,16 +279,31 @@ impl<R: ServiceRole, S: Service<R>> DynService<R> for S {
     fn get_info(&self) -> R::Info {
         self.get_info()
     }
+    fn observer(&self) -> Option<Arc<dyn ServiceObserver>> {
+        self.observer()
+    }
+    fn notification_queue(&self) -> NotificationQueueConfig {
+        self.notification_queue()
+    }
+    fn handle_disconnect(&self, session: SessionData) -> BoxFuture<()> {
+        Box::pin(self.handle_disconnect(session))
+    }
+    fn bind_peer(&self, peer: &Peer<R>) {
+        self.bind_peer(peer)
+    }
 }
 
 use std::{
     collections::{HashMap, VecDeque},
     ops::Deref,
-    sync::{Arc, atomic::AtomicU32},
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicU32, AtomicUsize},
+    },
     time::Duration,
 };
 
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 
 pub trait RequestIdProvider: Send + Sync + 'static {
     fn next_request_id(&self) -> RequestId;
@@ -207,15 +323,17 @@ pub struct AtomicU32Provider {
 
 impl RequestIdProvider for AtomicU32Provider {
     fn next_request_id(&self) -> RequestId {
-        RequestId::Number(self.id.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
+        RequestId::Number(
+            self.id
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+                .into(),
+        )
     }
 }
 
 impl ProgressTokenProvider for AtomicU32Provider {
     fn next_progress_token(&self) -> ProgressToken {
-        ProgressToken(NumberOrString::Number(
-            self.id.fetch_add(1, std::sync::atomic::Ordering::SeqCst),
-        ))
+        ProgressToken::from(self.id.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
     }
 }
 
@@ -226,6 +344,9 @@ type Responder<T> = tokio::sync::oneshot::Sender<T>;
 /// You can cancel it by call [`RequestHandle::cancel`] with a reason,
 ///
 /// or wait for response by call [`RequestHandle::await_response`]
+///
+/// To cancel the request while waiting for its response, take a [`RequestCanceller`] first
+/// with [`RequestHandle::canceller`]. Dropping the handle doesn't cancel the request.
 #[derive(Debug)]
 pub struct RequestHandle<R: ServiceRole> {
     pub rx: tokio::sync::oneshot::Receiver<Result<R::PeerResp, ServiceError>>,
@@ -267,16 +388,60 @@ impl<R: ServiceRole> RequestHandle<R> {
 
     /// Cancel this request
     pub async fn cancel(self, reason: Option<String>) -> Result<(), ServiceError> {
+        self.canceller().cancel(reason).await
+    }
+
+    /// Stop waiting for this request without telling the peer, see [`RequestCanceller::abort`]
+    pub async fn abort(self) -> Result<(), ServiceError> {
+        self.canceller().abort().await
+    }
+
+    /// A [`RequestCanceller`] which can cancel this request while its response is awaited
+    pub fn canceller(&self) -> RequestCanceller<R> {
+        RequestCanceller {
+            peer: self.peer.clone(),
+            id: self.id.clone(),
+        }
+    }
+}
+
+/// Cancels a request sent with [`Peer::send_cancellable_request`], from anywhere
+///
+/// The pending [`RequestHandle::await_response`] resolves with [`ServiceError::Cancelled`],
+/// a response arriving later is ignored. Cancelling an answered request does nothing.
+#[derive(Debug, Clone)]
+pub struct RequestCanceller<R: ServiceRole> {
+    peer: Peer<R>,
+    id: RequestId,
+}
+
+impl<R: ServiceRole> RequestCanceller<R> {
+    pub fn id(&self) -> &RequestId {
+        &self.id
+    }
+
+    /// Send `notifications/cancelled` for the request, and stop waiting for it
+    pub async fn cancel(&self, reason: Option<String>) -> Result<(), ServiceError> {
         let notification = CancelledNotification {
             params: CancelledNotificationParam {
-                request_id: self.id,
+                request_id: self.id.clone(),
                 reason,
             },
             method: crate::model::CancelledNotificationMethod,
             extensions: Default::default(),
         };
-        self.peer.send_notification(notification.into()).await?;
-        Ok(())
+        self.peer.send_notification(notification.into()).await
+    }
+
+    /// Stop waiting for the request, without notifying the peer
+    pub async fn abort(&self) -> Result<(), ServiceError> {
+        self.peer
+            .tx
+            .send(PeerSinkMessage::Abort {
+                id: self.id.clone(),
+            })
+            .await
+            .map_err(|_m| ServiceError::TransportClosed)
     }
 }
 
@@ -286,11 +451,15 @@ pub(crate) enum PeerSinkMessage<R: ServiceRole> {
         request: R::Req,
         id: RequestId,
         responder: Responder<Result<R::PeerResp, ServiceError>>,
+        progress: Option<(ProgressToken, ProgressHandler)>,
     },
     Notification {
         notification: R::Not,
         responder: Responder<Result<(), ServiceError>>,
     },
+    Abort {
+        id: RequestId,
+    },
 }
 
 /// An interface to fetch the remote client or server
@@ -301,9 +470,16 @@ pub(crate) enum PeerSinkMessage<R: ServiceRole> {
 #[derive(Clone)]
 pub struct Peer<R: ServiceRole> {
     tx: mpsc::Sender<PeerSinkMessage<R>>,
+    notifications: Arc<NotificationQueue<R>>,
     request_id_provider: Arc<dyn RequestIdProvider>,
     progress_token_provider: Arc<dyn ProgressTokenProvider>,
     info: Arc<R::PeerInfo>,
+    /// whether the local server advertises the logging capability, asked once per connection
+    logging_enabled: Arc<OnceLock<bool>>,
+    session: SessionData,
+    state: Arc<watch::Sender<ConnectionState<R>>>,
+    timeout: Option<Duration>,
+    retry: Option<Arc<RetryPolicy>>,
 }
 
 impl<R: ServiceRole> std::fmt::Debug for Peer<R> {
@@ -321,12 +497,30 @@ type ProxyOutbound<R> = mpsc::Receiver<PeerSinkMessage<R>>;
 pub struct PeerRequestOptions {
     pub timeout: Option<Duration>,
     pub meta: Option<Meta>,
+    /// Called with the `notifications/progress` of the request until it is answered
+    pub on_progress: Option<ProgressHandler>,
 }
 
 impl PeerRequestOptions {
     pub fn no_options() -> Self {
         Self::default()
     }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Call `on_progress` with the progress the peer reports for the request
+    ///
+    /// Progress reported after the response, or not above the last one, is ignored.
+    pub fn with_progress(
+        mut self,
+        on_progress: impl Fn(ProgressNotificationParam) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_progress = Some(ProgressHandler::new(on_progress));
+        self
+    }
 }
 
 impl<R: ServiceRole> Peer<R> {
@@ -339,9 +533,15 @@ impl<R: ServiceRole> Peer<R> {
         (
             Self {
                 tx,
+                notifications: Default::default(),
                 request_id_provider,
                 progress_token_provider: Arc::new(AtomicU32ProgressTokenProvider::default()),
                 info: peer_info.into(),
+                logging_enabled: Default::default(),
+                session: SessionData::new(),
+                state: Arc::new(watch::channel(ConnectionState::Initializing).0),
+                timeout: None,
+                retry: None,
             },
             rx,
         )
@@ -357,13 +557,128 @@ impl<R: ServiceRole> Peer<R> {
             .map_err(|_m| ServiceError::TransportClosed)?;
         receiver.await.map_err(|_e| ServiceError::TransportClosed)?
     }
+    /// Queue `notification` for the peer, see [`NotificationQueueConfig`]
+    ///
+    /// Returns once it is queued, so unlike [`send_notification`](Self::send_notification) the
+    /// errors writing it are only logged. Cancellations are not queued, they are sent right away.
+    pub async fn queue_notification(&self, notification: R::Not) -> Result<(), ServiceError> {
+        match notification.try_into() {
+            Ok::<CancelledNotification, _>(cancelled) => {
+                self.send_notification(cancelled.into()).await
+            }
+            Err(notification) => self.notifications.push(notification).await,
+        }
+    }
     pub async fn send_request(&self, request: R::Req) -> Result<R::PeerResp, ServiceError> {
-        self.send_request_with_option(request, PeerRequestOptions::no_options())
+        let Some(retry) = self
+            .retry
+            .as_deref()
+            .filter(|retry| retry.allows(request.method()))
+        else {
+            return self.send_request_once(request).await;
+        };
+        let mut attempt = 1;
+        loop {
+            match self.send_request_once(request.clone()).await {
+                Err(error) if attempt < retry.max_attempts && RetryPolicy::is_transient(&error) => {
+                    let backoff = retry.backoff(attempt);
+                    tracing::debug!(%error, attempt, ?backoff, method = request.method(), "retry request");
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Send a request of a method the SDK doesn't know, reading its result as a `T`
+    ///
+    /// Extensions name their methods under a namespace, e.g. `myorg/batch-tools/run`, and tell
+    /// they are supported with an experimental capability of the same name. `params` serializing
+    /// to `null` are left out; a result not deserializing to `T` is a
+    /// [`ServiceError::UnexpectedResponse`]. The peer answers it in its `on_custom_request` hook.
+    pub async fn send_custom_request<T: serde::de::DeserializeOwned>(
+        &self,
+        method: impl Into<String>,
+        params: impl serde::Serialize,
+    ) -> Result<T, ServiceError> {
+        let request = CustomRequest::new(method, custom_params(params)?);
+        let result = self.send_request(request.into()).await?;
+        serde_json::to_value(result)
+            .and_then(serde_json::from_value)
+            .map_err(|_e| ServiceError::UnexpectedResponse)
+    }
+
+    /// Send a notification of a method the SDK doesn't know, see [`Peer::send_custom_request`]
+    pub async fn send_custom_notification(
+        &self,
+        method: impl Into<String>,
+        params: impl serde::Serialize,
+    ) -> Result<(), ServiceError> {
+        let notification = CustomNotification::new(method, custom_params(params)?);
+        self.send_notification(notification.into()).await
+    }
+
+    async fn send_request_once(&self, request: R::Req) -> Result<R::PeerResp, ServiceError> {
+        let options = PeerRequestOptions {
+            timeout: self.timeout,
+            ..PeerRequestOptions::no_options()
+        };
+        self.send_request_with_option(request, options)
             .await?
             .await_response()
             .await
     }
 
+    /// The same peer, failing its requests with [`ServiceError::Timeout`] after `timeout`
+    ///
+    /// The requests timing out are cancelled with `notifications/cancelled`, so the other side
+    /// can stop working on them. Only the requests sent through the returned peer are concerned,
+    /// e.g. `peer.with_timeout(Duration::from_secs(5)).list_tools(None)`.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self.clone()
+        }
+    }
+
+    /// The same peer, without a timeout for its requests
+    pub fn without_timeout(&self) -> Self {
+        Self {
+            timeout: None,
+            ..self.clone()
+        }
+    }
+
+    /// The timeout of the requests sent through this peer, see [`Peer::with_timeout`]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// The same peer, retrying its requests failing with a transient error as `retry` tells
+    ///
+    /// Only [`Peer::send_request`] and the methods built on it retry, not the requests sent with
+    /// [`Peer::send_request_with_option`].
+    pub fn with_retry(&self, retry: RetryPolicy) -> Self {
+        Self {
+            retry: Some(Arc::new(retry)),
+            ..self.clone()
+        }
+    }
+
+    /// The same peer, never retrying its requests
+    pub fn without_retry(&self) -> Self {
+        Self {
+            retry: None,
+            ..self.clone()
+        }
+    }
+
+    /// The retry policy of the requests sent through this peer, see [`Peer::with_retry`]
+    pub fn retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry.as_deref()
+    }
+
     pub async fn send_cancellable_request(
         &self,
         request: R::Req,
@@ -391,6 +706,10 @@ impl<R: ServiceRole> Peer<R> {
                 request,
                 id: id.clone(),
                 responder,
+                progress: options
+                    .on_progress
+                    .clone()
+                    .map(|on_progress| (progress_token.clone(), on_progress)),
             })
             .await
             .map_err(|_m| ServiceError::TransportClosed)?;
@@ -402,13 +721,68 @@ impl<R: ServiceRole> Peer<R> {
             peer: self.clone(),
         })
     }
+    /// What the peer told about itself in the handshake
+    ///
+    /// For a client, the [`InitializeResult`](crate::model::InitializeResult) of the server,
+    /// with its capabilities, instructions and protocol version; for a server, the `initialize`
+    /// params of the client.
     pub fn peer_info(&self) -> &R::PeerInfo {
         &self.info
     }
 
+    /// The values of the session, see [`SessionData`]
+    pub fn session(&self) -> &SessionData {
+        &self.session
+    }
+
+    /// Follow the state of the connection, see [`ConnectionState`]
+    ///
+    /// A server has one per session, its handlers reach it through [`RequestContext::peer`].
+    pub fn state_watch(&self) -> watch::Receiver<ConnectionState<R>> {
+        self.state.subscribe()
+    }
+
+    /// The same peer with its info replaced, e.g. by the negotiated protocol version
+    pub(crate) fn with_peer_info(&self, info: R::PeerInfo) -> Self {
+        Self {
+            info: info.into(),
+            ..self.clone()
+        }
+    }
+
+    /// The same peer allocating request ids from another provider
+    pub(crate) fn with_request_id_provider(
+        &self,
+        request_id_provider: Arc<dyn RequestIdProvider>,
+    ) -> Self {
+        Self {
+            request_id_provider,
+            ..self.clone()
+        }
+    }
+
     pub fn is_transport_closed(&self) -> bool {
         self.tx.is_closed()
     }
+
+    /// Wait until the transport is closed
+    pub async fn closed(&self) {
+        self.tx.closed().await
+    }
+
+    /// Whether both peers send to the same session
+    pub fn is_same_session(&self, other: &Self) -> bool {
+        self.tx.same_channel(&other.tx)
+    }
+}
+
+/// The params of a custom request or notification, `None` for `null`
+fn custom_params(params: impl serde::Serialize) -> Result<Option<serde_json::Value>, ServiceError> {
+    match serde_json::to_value(params) {
+        Ok(serde_json::Value::Null) => Ok(None),
+        Ok(params) => Ok(Some(params)),
+        Err(error) => Err(ServiceError::McpError(error.into())),
+    }
 }
 
 #[derive(Debug)]
@@ -418,6 +792,11 @@ pub struct RunningService<R: ServiceRole, S: Service<R>> {
     handle: tokio::task::JoinHandle<QuitReason>,
     /// cancellation token with drop guard
     dg: DropGuard,
+    /// cancelled by the keepalive task when the peer stops answering pings
+    unresponsive_ct: CancellationToken,
+    /// starts draining the serve loop with a deadline
+    shutdown_tx: tokio::sync::oneshot::Sender<Duration>,
+    in_flight: Arc<AtomicUsize>,
 }
 impl<R: ServiceRole, S: Service<R>> Deref for RunningService<R, S> {
     type Target = Peer<R>;
@@ -436,6 +815,10 @@ impl<R: ServiceRole, S: Service<R>> RunningService<R, S> {
     pub fn service(&self) -> &S {
         self.service.as_ref()
     }
+    /// Follow the state of the connection, see [`ConnectionState`]
+    pub fn state_watch(&self) -> watch::Receiver<ConnectionState<R>> {
+        self.peer.state_watch()
+    }
     pub async fn waiting(self) -> Result<QuitReason, tokio::task::JoinError> {
         self.handle.await
     }
@@ -444,13 +827,33 @@ impl<R: ServiceRole, S: Service<R>> RunningService<R, S> {
         dg.disarm().cancel();
         handle.await
     }
-}
 
-#[derive(Debug)]
-pub enum QuitReason {
-    Cancelled,
-    Closed,
-    JoinError(tokio::task::JoinError),
+    /// Requests handled by the service plus requests waiting for a response of the peer
+    pub fn in_flight_requests(&self) -> usize {
+        self.in_flight.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Stop the service once the in-flight requests are done
+    ///
+    /// New requests from the peer are rejected, the responses and notifications of the
+    /// in-flight ones are still sent before the transport is closed. Handlers which are still
+    /// running when `deadline` has passed are cancelled.
+    pub async fn shutdown_graceful(
+        self,
+        deadline: Duration,
+    ) -> Result<QuitReason, tokio::task::JoinError> {
+        let RunningService {
+            dg,
+            handle,
+            shutdown_tx,
+            ..
+        } = self;
+        // the serve loop may have quit already
+        let _ = shutdown_tx.send(deadline);
+        let quit_reason = handle.await;
+        dg.disarm().cancel();
+        quit_reason
+    }
 }
 
 /// Request execution context
@@ -465,6 +868,19 @@ pub struct RequestContext<R: ServiceRole> {
     pub peer: Peer<R>,
 }
 
+impl<R: ServiceRole> RequestContext<R> {
+    /// The values of the session of the request, see [`SessionData`]
+    pub fn session(&self) -> &SessionData {
+        self.peer.session()
+    }
+
+    /// The token to send the `notifications/progress` of the request with, if the peer asked
+    /// for them
+    pub fn progress_token(&self) -> Option<ProgressToken> {
+        self.meta.progress_token()
+    }
+}
+
 /// Use this function to skip initialization process
 pub async fn serve_directly<R, S, T, E, A>(
     service: S,
@@ -494,15 +910,18 @@ where
     E: std::error::Error + Send + Sync + 'static,
 {
     let (peer, peer_rx) = Peer::new(Arc::new(AtomicU32RequestIdProvider::default()), peer_info);
-    serve_inner(service, transport, peer, peer_rx, ct).await
+    serve_inner(service, transport, peer, peer_rx, VecDeque::new(), ct).await
 }
 
+/// Run the service, `pending` are the messages received during the initialization and not
+/// handled yet
 #[instrument(skip_all)]
 async fn serve_inner<R, S, T, E, A>(
     service: S,
     transport: T,
     peer: Peer<R>,
     mut peer_rx: tokio::sync::mpsc::Receiver<PeerSinkMessage<R>>,
+    pending: VecDeque<RxJsonRpcMessage<R>>,
     ct: CancellationToken,
 ) -> RunningService<R, S>
 where
@@ -514,7 +933,7 @@ where
     const SINK_PROXY_BUFFER_SIZE: usize = 64;
     let (sink_proxy_tx, mut sink_proxy_rx) =
         tokio::sync::mpsc::channel::<TxJsonRpcMessage<R>>(SINK_PROXY_BUFFER_SIZE);
-    let peer_info = peer.peer_info();
+    let peer_info = peer.peer_info().clone();
     if R::IS_CLIENT {
         tracing::info!(?peer_info, "Service initialized as client");
     } else {
@@ -525,16 +944,28 @@ where
         HashMap::<RequestId, Responder<Result<R::PeerResp, ServiceError>>>::new();
     let mut local_ct_pool = HashMap::<RequestId, CancellationToken>::new();
     let shared_service = Arc::new(service);
+    shared_service.bind_peer(&peer);
+    peer.state
+        .send_replace(ConnectionState::Ready(peer_info.clone()));
+    let observer = shared_service.observer();
+    peer.notifications
+        .configure(shared_service.notification_queue(), observer.clone());
     // for return
     let service = shared_service.clone();
 
     // let message_sink = tokio::sync::
     // let mut stream = std::pin::pin!(stream);
     let serve_loop_ct = ct.child_token();
+    let unresponsive_ct = CancellationToken::new();
+    let serve_loop_unresponsive_ct = unresponsive_ct.clone();
+    let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel::<Duration>();
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let serve_loop_in_flight = in_flight.clone();
     let peer_return: Peer<R> = peer.clone();
     let handle = tokio::spawn(async move {
         let mut transport = transport.into_transport();
-        let mut batch_messages = VecDeque::<RxJsonRpcMessage<R>>::new();
+        let mut transport_status = transport.status();
+        let mut batch_messages = pending;
         let mut send_task_set = tokio::task::JoinSet::<SendTaskResult<E>>::new();
         #[derive(Debug)]
         enum SendTaskResult<E> {
@@ -547,16 +978,59 @@ where
                 cancellation_param: Option<CancelledNotificationParam>,
                 result: Result<(), E>,
             },
+            Response {
+                result: Result<(), E>,
+            },
+            QueuedNotification {
+                result: Result<(), E>,
+            },
         }
         #[derive(Debug)]
         enum Event<R: ServiceRole, E> {
             ProxyMessage(PeerSinkMessage<R>),
+            QueuedNotification(R::Not),
             PeerMessage(RxJsonRpcMessage<R>),
             ToSink(TxJsonRpcMessage<R>),
             SendTaskResult(SendTaskResult<E>),
         }
 
+        // method and start of the requests sent to the peer, only kept with an observer
+        let mut outbound_requests = HashMap::<RequestId, (String, std::time::Instant)>::new();
+        let end_outbound_request =
+            |outbound_requests: &mut HashMap<RequestId, (String, std::time::Instant)>,
+             id: &RequestId,
+             ok: bool| {
+                if let (Some(observer), Some((method, start))) =
+                    (&observer, outbound_requests.remove(id))
+                {
+                    observer.on_request_end(
+                        &method,
+                        id,
+                        MessageDirection::Outbound,
+                        start.elapsed(),
+                        ok,
+                    );
+                }
+            };
+        let mut progress_routes = progress::ProgressRoutes::default();
+        let mut inbound_batches = batch::InboundBatches::<R>::default();
+        let mut shutdown_requested = false;
+        let mut drain_deadline = None::<tokio::time::Instant>;
+        // queued notifications being sent, the next one waits for them
+        let mut notifications_sending = 0usize;
         let quit_reason = loop {
+            if drain_deadline.is_some()
+                && batch_messages.is_empty()
+                && local_ct_pool.is_empty()
+                && local_responder_pool.is_empty()
+                && send_task_set.is_empty()
+                && sink_proxy_rx.is_empty()
+                && peer_rx.is_empty()
+                && peer.notifications.is_empty()
+            {
+                tracing::info!("in-flight requests drained");
+                break QuitReason::Graceful;
+            }
             let evt = if let Some(m) = batch_messages.pop_front() {
                 Event::PeerMessage(m)
             } else {
@@ -573,8 +1047,16 @@ where
                             Event::PeerMessage(m)
                         } else {
                             // input stream closed
-                            tracing::info!("input stream terminated");
-                            break QuitReason::Closed
+                            match transport_status.as_ref().map(|status| status.borrow().clone()) {
+                                Some(TransportStatus::Failed(error)) => {
+                                    tracing::error!(%error, "input stream failed");
+                                    break QuitReason::ProtocolError(error)
+                                }
+                                _ => {
+                                    tracing::info!("input stream terminated");
+                                    break QuitReason::Closed
+                                }
+                            }
                         }
                     }
                     m = peer_rx.recv(), if !peer_rx.is_closed() => {
@@ -584,6 +1066,9 @@ where
                             continue
                         }
                     }
+                    notification = peer.notifications.next(), if notifications_sending == 0 => {
+                        Event::QueuedNotification(notification)
+                    }
                     m = send_task_set.join_next(), if !send_task_set.is_empty() => {
                         let Some(result) = m else {
                             continue
@@ -592,7 +1077,7 @@ where
                             Err(e) => {
                                 // join error, which is serious, we should quit.
                                 tracing::error!(%e, "send request task encounter a tokio join error");
-                                break QuitReason::JoinError(e)
+                                break QuitReason::JoinError(Arc::new(e))
                             }
                             Ok(result) => {
                                 Event::SendTaskResult(result)
@@ -603,16 +1088,96 @@ where
                         tracing::info!("task cancelled");
                         break QuitReason::Cancelled
                     }
+                    _ = serve_loop_unresponsive_ct.cancelled() => {
+                        tracing::warn!("peer is unresponsive");
+                        break QuitReason::Unresponsive
+                    }
+                    changed = async {
+                        match &mut transport_status {
+                            Some(status) => status.changed().await,
+                            None => std::future::pending().await,
+                        }
+                    }, if transport_status.is_some() => {
+                        let status = match (changed, &mut transport_status) {
+                            (Ok(()), Some(status)) => status.borrow_and_update().clone(),
+                            _ => {
+                                // the transport doesn't report anymore
+                                transport_status = None;
+                                continue
+                            }
+                        };
+                        match status {
+                            TransportStatus::Connected => {
+                                tracing::info!("transport recovered");
+                                peer.state.send_replace(ConnectionState::Ready(peer_info.clone()));
+                            }
+                            TransportStatus::Degraded(reason) => {
+                                tracing::warn!(%reason, "transport degraded");
+                                peer.state.send_replace(ConnectionState::Degraded(reason));
+                            }
+                            // the transport ends its input right after
+                            TransportStatus::Failed(_) => {}
+                        }
+                        continue
+                    }
+                    deadline = &mut shutdown_rx, if !shutdown_requested => {
+                        shutdown_requested = true;
+                        if let Ok(deadline) = deadline {
+                            tracing::info!(?deadline, "shutting down, draining in-flight requests");
+                            drain_deadline = Some(tokio::time::Instant::now() + deadline);
+                        }
+                        continue
+                    }
+                    _ = tokio::time::sleep_until(
+                        drain_deadline.unwrap_or_else(tokio::time::Instant::now)
+                    ), if drain_deadline.is_some() => {
+                        tracing::warn!(
+                            requests = local_ct_pool.len() + local_responder_pool.len(),
+                            "shutdown deadline passed before in-flight requests finished"
+                        );
+                        break QuitReason::Cancelled
+                    }
                 }
             };
 
             tracing::trace!(?evt, "new event");
+            // what can't be dropped goes after the notifications queued before it
+            if matches!(
+                evt,
+                Event::ToSink(_)
+                    | Event::ProxyMessage(
+                        PeerSinkMessage::Request { .. } | PeerSinkMessage::Notification { .. }
+                    )
+            ) {
+                for notification in peer.notifications.drain() {
+                    if let Some(observer) = &observer {
+                        observer.on_notification(notification.method(), MessageDirection::Outbound);
+                    }
+                    notifications_sending += 1;
+                    let send = transport.send(JsonRpcMessage::notification(notification));
+                    send_task_set
+                        .spawn(send.map(|result| SendTaskResult::QueuedNotification { result }));
+                }
+            }
             match evt {
                 Event::SendTaskResult(SendTaskResult::Request { id, result }) => {
                     if let Err(e) = result {
                         if let Some(responder) = local_responder_pool.remove(&id) {
                             let _ = responder.send(Err(ServiceError::TransportSend(Box::new(e))));
                         }
+                        end_outbound_request(&mut outbound_requests, &id, false);
+                        progress_routes.remove_request(&id);
+                    }
+                }
+                Event::SendTaskResult(SendTaskResult::Response { result }) => {
+                    if let Err(error) = result {
+                        tracing::error!(%error, "fail to response message");
+                    }
+                }
+                Event::SendTaskResult(SendTaskResult::QueuedNotification { result }) => {
+                    notifications_sending -= 1;
+                    if let Err(error) = result {
+                        tracing::error!(%error, "fail to send notification");
                     }
                 }
                 Event::SendTaskResult(SendTaskResult::Notification {
@@ -633,6 +1198,8 @@ where
                                 reason: param.reason.clone(),
                             }));
                         }
+                        end_outbound_request(&mut outbound_requests, &param.request_id, false);
+                        progress_routes.remove_request(&param.request_id);
                     }
                 }
                 // response and error
@@ -645,20 +1212,28 @@ where
                         if let Some(ct) = local_ct_pool.remove(id) {
                             ct.cancel();
                         }
-                        let send = transport.send(m);
-                        tokio::spawn(async move {
-                            let send_result = send.await;
-                            if let Err(error) = send_result {
-                                tracing::error!(%error, "fail to response message");
-                            }
-                        });
+                        if let Some(m) = inbound_batches.answer(m) {
+                            let send = transport.send(m);
+                            send_task_set
+                                .spawn(send.map(|result| SendTaskResult::Response { result }));
+                        }
                     }
                 }
                 Event::ProxyMessage(PeerSinkMessage::Request {
                     request,
                     id,
                     responder,
+                    progress,
                 }) => {
+                    if let Some((token, on_progress)) = progress {
+                        progress_routes.insert(token, id.clone(), on_progress);
+                    }
+                    if let Some(observer) = &observer {
+                        let method = request.method();
+                        observer.on_request_start(method, &id, MessageDirection::Outbound);
+                        outbound_requests
+                            .insert(id.clone(), (method.to_owned(), std::time::Instant::now()));
+                    }
                     local_responder_pool.insert(id.clone(), responder);
                     let send = transport.send(JsonRpcMessage::request(request, id.clone()));
                     {
@@ -671,6 +1246,9 @@ where
                     notification,
                     responder,
                 }) => {
+                    if let Some(observer) = &observer {
+                        observer.on_notification(notification.method(), MessageDirection::Outbound);
+                    }
                     // catch cancellation notification
                     let mut cancellation_param = None;
                     let notification = match notification.try_into() {
@@ -687,16 +1265,55 @@ where
                         result,
                     }));
                 }
+                Event::QueuedNotification(notification) => {
+                    if let Some(observer) = &observer {
+                        observer.on_notification(notification.method(), MessageDirection::Outbound);
+                    }
+                    notifications_sending += 1;
+                    let send = transport.send(JsonRpcMessage::notification(notification));
+                    send_task_set
+                        .spawn(send.map(|result| SendTaskResult::QueuedNotification { result }));
+                }
+                Event::ProxyMessage(PeerSinkMessage::Abort { id }) => {
+                    if let Some(responder) = local_responder_pool.remove(&id) {
+                        tracing::info!(%id, "aborted");
+                        let _response_result =
+                            responder.send(Err(ServiceError::Cancelled { reason: None }));
+                        end_outbound_request(&mut outbound_requests, &id, false);
+                        progress_routes.remove_request(&id);
+                    }
+                }
                 Event::PeerMessage(JsonRpcMessage::Request(JsonRpcRequest {
                     id, request, ..
                 })) => {
                     tracing::debug!(%id, ?request, "received request");
-                    {
+                    let method = request.method().to_owned();
+                    if let Some(observer) = &observer {
+                        observer.on_request_start(&method, &id, MessageDirection::Inbound);
+                    }
+                    if drain_deadline.is_some() {
+                        tracing::debug!(%id, "shutting down, request rejected");
+                        if let Some(observer) = &observer {
+                            observer.on_request_end(
+                                &method,
+                                &id,
+                                MessageDirection::Inbound,
+                                Duration::ZERO,
+                                false,
+                            );
+                        }
+                        let error = McpError::invalid_request("service is shutting down", None);
+                        if let Some(m) = inbound_batches.answer(JsonRpcMessage::error(error, id)) {
+                            let send = transport.send(m);
+                            send_task_set
+                                .spawn(send.map(|result| SendTaskResult::Response { result }));
+                        }
+                    } else {
                         let service = shared_service.clone();
                         let sink = sink_proxy_tx.clone();
                         let request_ct = serve_loop_ct.child_token();
                         let context_ct = request_ct.child_token();
-                        local_ct_pool.insert(id.clone(), request_ct);
+                        local_ct_pool.insert(id.clone(), request_ct.clone());
                         let context = RequestContext {
                             ct: context_ct,
                             id: id.clone(),
@@ -704,8 +1321,25 @@ where
                             meta: request.get_meta().clone(),
                             extensions: request.extensions().clone(),
                         };
+                        let observer = observer.clone();
                         tokio::spawn(async move {
+                            let start = std::time::Instant::now();
                             let result = service.handle_request(request, context).await;
+                            if let Some(observer) = &observer {
+                                observer.on_request_end(
+                                    &method,
+                                    &id,
+                                    MessageDirection::Inbound,
+                                    start.elapsed(),
+                                    result.is_ok(),
+                                );
+                            }
+                            // the peer cancelled the request and won't wait for a response,
+                            // the handler cancelling its own token doesn't count
+                            if request_ct.is_cancelled() {
+                                tracing::debug!(%id, "request cancelled, response dropped");
+                                return;
+                            }
                             let response = match result {
                                 Ok(result) => {
                                     tracing::debug!(%id, ?result, "response message");
@@ -725,17 +1359,41 @@ where
                     ..
                 })) => {
                     tracing::info!(?notification, "received notification");
+                    if let Some(observer) = &observer {
+                        observer.on_notification(notification.method(), MessageDirection::Inbound);
+                    }
                     // catch cancelled notification
                     let notification = match notification.try_into() {
                         Ok::<CancelledNotification, _>(cancelled) => {
                             if let Some(ct) = local_ct_pool.remove(&cancelled.params.request_id) {
                                 tracing::info!(id = %cancelled.params.request_id, reason = cancelled.params.reason, "cancelled");
                                 ct.cancel();
+                                // the rest of its batch is answered without it
+                                if let Some(m) =
+                                    inbound_batches.forget(&cancelled.params.request_id)
+                                {
+                                    let send = transport.send(m);
+                                    send_task_set.spawn(
+                                        send.map(|result| SendTaskResult::Response { result }),
+                                    );
+                                }
                             }
                             cancelled.into()
                         }
                         Err(notification) => notification,
                     };
+                    let mut notification = match notification.try_into() {
+                        Ok::<ProgressNotification, _>(progress) => {
+                            progress_routes.dispatch(&progress.params);
+                            progress.into()
+                        }
+                        Err(notification) => notification,
+                    };
+                    // the input schemas validated by the client may have changed
+                    #[cfg(feature = "client-schema-validation")]
+                    ToolSchemaCache::on_notification(peer.session(), notification.method());
+                    notification.extensions_mut().insert(peer.session().clone());
+                    notification.extensions_mut().insert(peer.clone());
                     {
                         let service = shared_service.clone();
                         tokio::spawn(async move {
@@ -751,22 +1409,37 @@ where
                     id,
                     ..
                 })) => {
+                    end_outbound_request(&mut outbound_requests, &id, true);
+                    progress_routes.remove_request(&id);
                     if let Some(responder) = local_responder_pool.remove(&id) {
                         let response_result = responder.send(Ok(result));
                         if let Err(_error) = response_result {
                             tracing::warn!(%id, "Error sending response");
                         }
+                    } else {
+                        // ids are compared as sent, a peer answering `"1"` to `1` ends up here
+                        tracing::debug!(?id, "response to an unknown request");
                     }
                 }
                 Event::PeerMessage(JsonRpcMessage::Error(JsonRpcError { error, id, .. })) => {
+                    end_outbound_request(&mut outbound_requests, &id, false);
+                    progress_routes.remove_request(&id);
                     if let Some(responder) = local_responder_pool.remove(&id) {
                         let _response_result = responder.send(Err(ServiceError::McpError(error)));
                         if let Err(_error) = _response_result {
                             tracing::warn!(%id, "Error sending response");
                         }
+                    } else {
+                        tracing::debug!(?id, "error response to an unknown request");
                     }
                 }
                 Event::PeerMessage(JsonRpcMessage::BatchRequest(batch)) => {
+                    let (batch, rejected) =
+                        inbound_batches.insert(batch, |id| local_ct_pool.contains_key(id));
+                    if let Some(m) = rejected {
+                        let send = transport.send(m);
+                        send_task_set.spawn(send.map(|result| SendTaskResult::Response { result }));
+                    }
                     batch_messages.extend(
                         batch
                             .into_iter()
@@ -781,11 +1454,22 @@ where
                     );
                 }
             }
+            serve_loop_in_flight.store(
+                local_ct_pool.len() + local_responder_pool.len(),
+                std::sync::atomic::Ordering::Relaxed,
+            );
         };
+        peer.notifications.close();
         let sink_close_result = transport.close().await;
         if let Err(e) = sink_close_result {
             tracing::error!(%e, "fail to close sink");
         }
+        shared_service
+            .handle_disconnect(peer.session().clone())
+            .await;
+        peer.session().clear();
+        peer.state
+            .send_replace(ConnectionState::Closed(quit_reason.clone()));
         tracing::info!(?quit_reason, "serve finished");
         quit_reason
     });
@@ -794,5 +1478,8 @@ where
         peer: peer_return,
         handle,
         dg: ct.drop_guard(),
+        unresponsive_ct,
+        shutdown_tx,
+        in_flight,
     }
 }
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use super::{ServiceRole, TxJsonRpcMessage};
+use crate::model::{
+    ErrorData, JsonRpcBatchRequestItem, JsonRpcBatchResponseItem, JsonRpcError, JsonRpcMessage,
+    JsonRpcVersion2_0, RequestId,
+};
+
+struct Batch<R: ServiceRole> {
+    /// requests of the batch still running
+    waiting: usize,
+    responses: Vec<JsonRpcBatchResponseItem<R::Resp>>,
+}
+
+/// The requests received in a batch, answered with one batch once they are all done
+pub(crate) struct InboundBatches<R: ServiceRole> {
+    next_batch: u64,
+    batch_of: HashMap<RequestId, u64>,
+    batches: HashMap<u64, Batch<R>>,
+}
+
+impl<R: ServiceRole> Default for InboundBatches<R> {
+    fn default() -> Self {
+        Self {
+            next_batch: 0,
+            batch_of: HashMap::new(),
+            batches: HashMap::new(),
+        }
+    }
+}
+
+impl<R: ServiceRole> InboundBatches<R> {
+    /// Answer the requests of `batch` together, returning the messages to handle
+    ///
+    /// A request reusing the id of a request still running, see `in_flight`, or of another
+    /// request of the batch isn't handled, it is answered with an invalid request error in the
+    /// batch response. That response is returned right away if no other request is left.
+    pub(crate) fn insert<Req, Not>(
+        &mut self,
+        batch: Vec<JsonRpcBatchRequestItem<Req, Not>>,
+        in_flight: impl Fn(&RequestId) -> bool,
+    ) -> (
+        Vec<JsonRpcBatchRequestItem<Req, Not>>,
+        Option<TxJsonRpcMessage<R>>,
+    ) {
+        let batch_id = self.next_batch;
+        let mut waiting = 0;
+        let mut responses = Vec::new();
+        let mut items = Vec::with_capacity(batch.len());
+        for item in batch {
+            if let JsonRpcBatchRequestItem::Request(request) = &item {
+                let id = &request.id;
+                if in_flight(id) || self.batch_of.contains_key(id) {
+                    tracing::warn!(%id, "request id already in use");
+                    responses.push(JsonRpcBatchResponseItem::Error(JsonRpcError {
+                        jsonrpc: JsonRpcVersion2_0,
+                        id: id.clone(),
+                        error: ErrorData::invalid_request(
+                            format!("request id {id} is already in use"),
+                            None,
+                        ),
+                    }));
+                    continue;
+                }
+                self.batch_of.insert(id.clone(), batch_id);
+                waiting += 1;
+            }
+            items.push(item);
+        }
+        if waiting > 0 {
+            self.next_batch += 1;
+            self.batches.insert(batch_id, Batch { waiting, responses });
+            return (items, None);
+        }
+        let response = (!responses.is_empty()).then_some(JsonRpcMessage::BatchResponse(responses));
+        (items, response)
+    }
+
+    /// What to send for `message`: the message itself unless it answers a batched request, the
+    /// batch response once the last request of its batch is answered
+    pub(crate) fn answer(&mut self, message: TxJsonRpcMessage<R>) -> Option<TxJsonRpcMessage<R>> {
+        let id = match &message {
+            JsonRpcMessage::Response(response) => &response.id,
+            JsonRpcMessage::Error(error) => &error.id,
+            _ => return Some(message),
+        };
+        let Some(batch) = self.batch_of.remove(id) else {
+            return Some(message);
+        };
+        if let (Some(pending), Ok(item)) = (
+            self.batches.get_mut(&batch),
+            message.into_batch_response_item(),
+        ) {
+            pending.responses.push(item);
+        }
+        self.complete(batch)
+    }
+
+    /// The batched request `id` was cancelled and won't be answered, the batch response may be
+    /// complete without it
+    pub(crate) fn forget(&mut self, id: &RequestId) -> Option<TxJsonRpcMessage<R>> {
+        let batch = self.batch_of.remove(id)?;
+        self.complete(batch)
+    }
+
+    fn complete(&mut self, batch: u64) -> Option<TxJsonRpcMessage<R>> {
+        let pending = self.batches.get_mut(&batch)?;
+        pending.waiting -= 1;
+        if pending.waiting > 0 {
+            return None;
+        }
+        let responses = self.batches.remove(&batch)?.responses;
+        // a batch of cancelled requests has no response
+        (!responses.is_empty()).then_some(JsonRpcMessage::BatchResponse(responses))
+    }
+}
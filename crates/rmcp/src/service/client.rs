@@ -1,20 +1,22 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::HashSet};
 
+use futures::{Stream, TryStreamExt};
 use thiserror::Error;
 
 use super::*;
 use crate::model::{
     CallToolRequest, CallToolRequestParam, CallToolResult, CancelledNotification,
     CancelledNotificationParam, ClientInfo, ClientJsonRpcMessage, ClientNotification,
-    ClientRequest, ClientResult, CompleteRequest, CompleteRequestParam, CompleteResult,
+    ClientRequest, ClientResult, CompleteRequest, CompleteRequestParam, CompleteResult, ErrorData,
     GetPromptRequest, GetPromptRequestParam, GetPromptResult, InitializeRequest,
-    InitializedNotification, JsonRpcResponse, ListPromptsRequest, ListPromptsResult,
+    InitializedNotification, JsonRpcError, JsonRpcResponse, ListPromptsRequest, ListPromptsResult,
     ListResourceTemplatesRequest, ListResourceTemplatesResult, ListResourcesRequest,
     ListResourcesResult, ListToolsRequest, ListToolsResult, PaginatedRequestParam,
-    ProgressNotification, ProgressNotificationParam, ReadResourceRequest, ReadResourceRequestParam,
-    ReadResourceResult, RequestId, RootsListChangedNotification, ServerInfo, ServerJsonRpcMessage,
-    ServerNotification, ServerRequest, ServerResult, SetLevelRequest, SetLevelRequestParam,
-    SubscribeRequest, SubscribeRequestParam, UnsubscribeRequest, UnsubscribeRequestParam,
+    ProgressNotification, ProgressNotificationParam, ProtocolVersion, ReadResourceRequest,
+    ReadResourceRequestParam, ReadResourceResult, RequestId, RootsListChangedNotification,
+    ServerCapabilities, ServerInfo, ServerJsonRpcMessage, ServerNotification, ServerRequest,
+    ServerResult, SetLevelRequest, SetLevelRequestParam, SubscribeRequest, SubscribeRequestParam,
+    UnsubscribeRequest, UnsubscribeRequestParam,
 };
 
 /// It represents the error that may occur when serving the client.
@@ -31,9 +33,15 @@ pub enum ClientInitializeError<E> {
     #[error("conflict initialized response id: expected {0}, got {1}")]
     ConflictInitResponseId(RequestId, RequestId),
 
+    #[error("initialize rejected by the server: {0}")]
+    InitializeRejected(ErrorData),
+
     #[error("connection closed: {0}")]
     ConnectionClosed(String),
 
+    #[error("the server answered with the unsupported protocol version {0}")]
+    UnsupportedProtocolVersion(ProtocolVersion),
+
     #[error("Send message error {error}, when {context}")]
     TransportError {
         error: E,
@@ -41,6 +49,34 @@ pub enum ClientInitializeError<E> {
     },
 }
 
+/// The error of [`Peer<RoleClient>::call_tool_typed`]
+#[derive(Error, Debug)]
+pub enum ToolCallError {
+    /// The request could not be sent, or the server answered with an error response
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+
+    /// The parameters did not serialize into a json object
+    #[error("failed to serialize the tool arguments: {0}")]
+    Serialize(#[source] serde_json::Error),
+
+    /// The tool ran and reported a failure with `isError: true`
+    #[error("tool call failed: {text}")]
+    Tool {
+        /// The text contents of the result, joined with new lines
+        text: String,
+        result: CallToolResult,
+    },
+
+    /// Neither the structured content nor the first text content matched the expected type
+    #[error("failed to deserialize the tool result: {error}")]
+    Deserialize {
+        #[source]
+        error: serde_json::Error,
+        result: CallToolResult,
+    },
+}
+
 /// Helper function to get the next message from the stream
 async fn expect_next_message<T, E>(
     transport: &mut T,
@@ -67,6 +103,9 @@ where
 
     match msg {
         ServerJsonRpcMessage::Response(JsonRpcResponse { id, result, .. }) => Ok((result, id)),
+        ServerJsonRpcMessage::Error(JsonRpcError { error, .. }) => {
+            Err(ClientInitializeError::InitializeRejected(error))
+        }
         _ => Err(ClientInitializeError::ExpectedInitResponse(Some(msg))),
     }
 }
@@ -131,23 +170,36 @@ where
 
     // service
     let id = id_provider.next_request_id();
-    let init_request = InitializeRequest {
+    let init_request = ClientRequest::InitializeRequest(InitializeRequest {
         method: Default::default(),
         params: service.get_info(),
         extensions: Default::default(),
-    };
+    });
+    let method = init_request.method().to_owned();
+    let observer = service.observer();
+    if let Some(observer) = &observer {
+        observer.on_request_start(&method, &id, MessageDirection::Outbound);
+    }
+    let start = std::time::Instant::now();
     transport
-        .send(ClientJsonRpcMessage::request(
-            ClientRequest::InitializeRequest(init_request),
-            id.clone(),
-        ))
+        .send(ClientJsonRpcMessage::request(init_request, id.clone()))
         .await
         .map_err(|error| ClientInitializeError::TransportError {
             error,
             context: "send initialize request".into(),
         })?;
 
-    let (response, response_id) = expect_response(&mut transport, "initialize response").await?;
+    let response = expect_response(&mut transport, "initialize response").await;
+    if let Some(observer) = &observer {
+        observer.on_request_end(
+            &method,
+            &id,
+            MessageDirection::Outbound,
+            start.elapsed(),
+            response.is_ok(),
+        );
+    }
+    let (response, response_id) = response?;
 
     if id != response_id {
         return Err(ClientInitializeError::ConflictInitResponseId(
@@ -159,23 +211,33 @@ where
     let ServerResult::InitializeResult(initialize_result) = response else {
         return Err(ClientInitializeError::ExpectedInitResult(Some(response)));
     };
+    // the server couldn't negotiate a version the client speaks
+    if !initialize_result.protocol_version.is_supported() {
+        if let Err(error) = transport.close().await {
+            tracing::warn!(%error, "fail to close the transport of an unsupported server");
+        }
+        return Err(ClientInitializeError::UnsupportedProtocolVersion(
+            initialize_result.protocol_version,
+        ));
+    }
 
     // send notification
-    let notification = ClientJsonRpcMessage::notification(
-        ClientNotification::InitializedNotification(InitializedNotification {
-            method: Default::default(),
-            extensions: Default::default(),
-        }),
-    );
+    let notification = ClientNotification::InitializedNotification(InitializedNotification {
+        method: Default::default(),
+        extensions: Default::default(),
+    });
+    if let Some(observer) = &observer {
+        observer.on_notification(notification.method(), MessageDirection::Outbound);
+    }
     transport
-        .send(notification)
+        .send(ClientJsonRpcMessage::notification(notification))
         .await
         .map_err(|error| ClientInitializeError::TransportError {
             error,
             context: "send initialized notification".into(),
         })?;
     let (peer, peer_rx) = Peer::new(id_provider, initialize_result);
-    Ok(serve_inner(service, transport, peer, peer_rx, ct).await)
+    Ok(serve_inner(service, transport, peer, peer_rx, Default::default(), ct).await)
 }
 
 macro_rules! method {
@@ -232,7 +294,7 @@ macro_rules! method {
                 }))
                 .await?;
             match result {
-                ServerResult::EmptyResult(_) => Ok(()),
+                ServerResult::EmptyResult(_) | ServerResult::CustomResult(_) => Ok(()),
                 _ => Err(ServiceError::UnexpectedResponse),
             }
         }
@@ -262,6 +324,16 @@ macro_rules! method {
 }
 
 impl Peer<RoleClient> {
+    /// The protocol version negotiated with the server
+    pub fn protocol_version(&self) -> &ProtocolVersion {
+        &self.peer_info().protocol_version
+    }
+
+    /// The capabilities the server advertised in the handshake
+    pub fn peer_capabilities(&self) -> &ServerCapabilities {
+        &self.peer_info().capabilities
+    }
+
     method!(peer_req complete CompleteRequest(CompleteRequestParam) => CompleteResult);
     method!(peer_req set_level SetLevelRequest(SetLevelRequestParam));
     method!(peer_req get_prompt GetPromptRequest(GetPromptRequestParam) => GetPromptResult);
@@ -271,7 +343,6 @@ impl Peer<RoleClient> {
     method!(peer_req read_resource ReadResourceRequest(ReadResourceRequestParam) => ReadResourceResult);
     method!(peer_req subscribe SubscribeRequest(SubscribeRequestParam) );
     method!(peer_req unsubscribe UnsubscribeRequest(UnsubscribeRequestParam));
-    method!(peer_req call_tool CallToolRequest(CallToolRequestParam) => CallToolResult);
     method!(peer_req list_tools ListToolsRequest(PaginatedRequestParam)? => ListToolsResult);
 
     method!(peer_not notify_cancelled CancelledNotification(CancelledNotificationParam));
@@ -280,82 +351,290 @@ impl Peer<RoleClient> {
     method!(peer_not notify_roots_list_changed RootsListChangedNotification);
 }
 
+/// The number of pages the `list_all_*` methods of [`Peer<RoleClient>`] read before failing
+/// with [`ServiceError::TooManyPages`]
+pub const DEFAULT_MAX_PAGES: usize = 1000;
+
+/// Follow the cursors of a paginated listing, flattening its pages
+///
+/// Fails if the server returns a cursor it already returned, or still has more results
+/// after `max_pages` pages.
+fn paginate<T, F, Fut>(
+    method: &'static str,
+    max_pages: usize,
+    fetch: F,
+) -> impl Stream<Item = Result<T, ServiceError>> + Send
+where
+    T: Send,
+    F: FnMut(Option<String>) -> Fut + Send,
+    Fut: Future<Output = Result<(Vec<T>, Option<String>), ServiceError>> + Send,
+{
+    struct State<F> {
+        fetch: F,
+        cursor: Option<String>,
+        seen: HashSet<String>,
+        pages: usize,
+        done: bool,
+    }
+    let state = State {
+        fetch,
+        cursor: None,
+        seen: HashSet::new(),
+        pages: 0,
+        done: false,
+    };
+    futures::stream::try_unfold(state, move |mut state| async move {
+        if state.done {
+            return Ok(None);
+        }
+        if state.pages == max_pages {
+            return Err(ServiceError::TooManyPages {
+                method,
+                pages: state.pages,
+            });
+        }
+        let (items, next_cursor) = (state.fetch)(state.cursor.take()).await?;
+        state.pages += 1;
+        match next_cursor {
+            Some(cursor) if !state.seen.insert(cursor.clone()) => {
+                return Err(ServiceError::RepeatedCursor { method, cursor });
+            }
+            Some(cursor) => state.cursor = Some(cursor),
+            None => state.done = true,
+        }
+        Ok(Some((
+            futures::stream::iter(items.into_iter().map(Ok::<T, ServiceError>)),
+            state,
+        )))
+    })
+    .try_flatten()
+}
+
 impl Peer<RoleClient> {
-    /// A wrapper method for [`Peer<RoleClient>::list_tools`].
+    /// Call a tool
     ///
-    /// This function will call [`Peer<RoleClient>::list_tools`] multiple times until all tools are listed.
-    pub async fn list_all_tools(&self) -> Result<Vec<crate::model::Tool>, ServiceError> {
-        let mut tools = Vec::new();
-        let mut cursor = None;
-        loop {
-            let result = self
-                .list_tools(Some(PaginatedRequestParam { cursor }))
-                .await?;
-            tools.extend(result.tools);
-            cursor = result.next_cursor;
-            if cursor.is_none() {
-                break;
-            }
+    /// With the `client-schema-validation` feature, the arguments are checked against the input
+    /// schema of the tool first once `Peer::enable_tool_validation` was called.
+    pub async fn call_tool(
+        &self,
+        params: CallToolRequestParam,
+    ) -> Result<CallToolResult, ServiceError> {
+        self.validate_tool_call(&params).await?;
+        let result = self
+            .send_request(ClientRequest::CallToolRequest(CallToolRequest {
+                method: Default::default(),
+                params,
+                extensions: Default::default(),
+            }))
+            .await?;
+        match result {
+            ServerResult::CallToolResult(result) => Ok(result),
+            _ => Err(ServiceError::UnexpectedResponse),
         }
-        Ok(tools)
     }
 
-    /// A wrapper method for [`Peer<RoleClient>::list_resources`].
+    #[allow(unused_variables)]
+    async fn validate_tool_call(&self, params: &CallToolRequestParam) -> Result<(), ServiceError> {
+        #[cfg(feature = "client-schema-validation")]
+        if let Some(cache) = self.session().get::<super::ToolSchemaCache>() {
+            cache.validate(self, params).await?;
+        }
+        Ok(())
+    }
+
+    /// [`Peer<RoleClient>::call_tool`] with request options, e.g. to follow its progress
     ///
-    /// This function will call [`Peer<RoleClient>::list_resources`] multiple times until all resources are listed.
-    pub async fn list_all_prompts(&self) -> Result<Vec<crate::model::Prompt>, ServiceError> {
-        let mut prompts = Vec::new();
-        let mut cursor = None;
-        loop {
-            let result = self
-                .list_prompts(Some(PaginatedRequestParam { cursor }))
-                .await?;
-            prompts.extend(result.prompts);
-            cursor = result.next_cursor;
-            if cursor.is_none() {
-                break;
+    /// The timeout of the peer applies unless `options` has its own.
+    ///
+    /// ```rust,ignore
+    /// let options = PeerRequestOptions::no_options().with_progress(|progress| {
+    ///     println!("{}/{}", progress.progress, progress.total.unwrap_or(100.0));
+    /// });
+    /// let result = client.call_tool_with_options(params, options).await?;
+    /// ```
+    pub async fn call_tool_with_options(
+        &self,
+        params: CallToolRequestParam,
+        mut options: PeerRequestOptions,
+    ) -> Result<CallToolResult, ServiceError> {
+        options.timeout = options.timeout.or(self.timeout());
+        self.validate_tool_call(&params).await?;
+        let request = ClientRequest::CallToolRequest(CallToolRequest {
+            method: Default::default(),
+            params,
+            extensions: Default::default(),
+        });
+        let result = self
+            .send_request_with_option(request, options)
+            .await?
+            .await_response()
+            .await?;
+        match result {
+            ServerResult::CallToolResult(result) => Ok(result),
+            _ => Err(ServiceError::UnexpectedResponse),
+        }
+    }
+
+    /// A wrapper method for [`Peer<RoleClient>::call_tool`] with typed arguments and result.
+    ///
+    /// `params` must serialize into a json object, or `null` for no arguments. The result is
+    /// deserialized from the structured content when there is one, otherwise from the first
+    /// text content parsed as json.
+    pub async fn call_tool_typed<P, R>(
+        &self,
+        name: impl Into<Cow<'static, str>>,
+        params: &P,
+    ) -> Result<R, ToolCallError>
+    where
+        P: serde::Serialize + ?Sized,
+        R: serde::de::DeserializeOwned,
+    {
+        use serde::ser::Error as _;
+        let arguments = match serde_json::to_value(params).map_err(ToolCallError::Serialize)? {
+            serde_json::Value::Object(arguments) => Some(arguments.into()),
+            serde_json::Value::Null => None,
+            other => {
+                return Err(ToolCallError::Serialize(serde_json::Error::custom(
+                    format!("expected an object, got {other}"),
+                )));
             }
+        };
+        let result = self
+            .call_tool(CallToolRequestParam {
+                name: name.into(),
+                arguments,
+            })
+            .await?;
+        if result.is_error == Some(true) {
+            let text = result
+                .content
+                .iter()
+                .filter_map(|content| content.as_text())
+                .map(|content| content.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            return Err(ToolCallError::Tool { text, result });
         }
-        Ok(prompts)
+        let parsed = match &result.structured_content {
+            Some(value) => R::deserialize(value),
+            None => match result.content.iter().find_map(|content| content.as_text()) {
+                Some(content) => serde_json::from_str(&content.text),
+                None => R::deserialize(serde_json::Value::Null),
+            },
+        };
+        parsed.map_err(|error| ToolCallError::Deserialize { error, result })
+    }
+
+    /// A wrapper method for [`Peer<RoleClient>::list_tools`].
+    ///
+    /// This function will call [`Peer<RoleClient>::list_tools`] multiple times until all tools are listed,
+    /// reading at most [`DEFAULT_MAX_PAGES`] pages.
+    pub async fn list_all_tools(&self) -> Result<Vec<crate::model::Tool>, ServiceError> {
+        self.list_tools_stream(DEFAULT_MAX_PAGES)
+            .try_collect()
+            .await
+    }
+
+    /// A wrapper method for [`Peer<RoleClient>::list_prompts`].
+    ///
+    /// This function will call [`Peer<RoleClient>::list_prompts`] multiple times until all prompts are listed,
+    /// reading at most [`DEFAULT_MAX_PAGES`] pages.
+    pub async fn list_all_prompts(&self) -> Result<Vec<crate::model::Prompt>, ServiceError> {
+        self.list_prompts_stream(DEFAULT_MAX_PAGES)
+            .try_collect()
+            .await
     }
 
     /// A wrapper method for [`Peer<RoleClient>::list_resources`].
     ///
-    /// This function will call [`Peer<RoleClient>::list_resources`] multiple times until all resources are listed.
+    /// This function will call [`Peer<RoleClient>::list_resources`] multiple times until all resources are listed,
+    /// reading at most [`DEFAULT_MAX_PAGES`] pages.
     pub async fn list_all_resources(&self) -> Result<Vec<crate::model::Resource>, ServiceError> {
-        let mut resources = Vec::new();
-        let mut cursor = None;
-        loop {
-            let result = self
-                .list_resources(Some(PaginatedRequestParam { cursor }))
-                .await?;
-            resources.extend(result.resources);
-            cursor = result.next_cursor;
-            if cursor.is_none() {
-                break;
-            }
-        }
-        Ok(resources)
+        self.list_resources_stream(DEFAULT_MAX_PAGES)
+            .try_collect()
+            .await
     }
 
     /// A wrapper method for [`Peer<RoleClient>::list_resource_templates`].
     ///
-    /// This function will call [`Peer<RoleClient>::list_resource_templates`] multiple times until all resource templates are listed.
+    /// This function will call [`Peer<RoleClient>::list_resource_templates`] multiple times until all resource templates are listed,
+    /// reading at most [`DEFAULT_MAX_PAGES`] pages.
     pub async fn list_all_resource_templates(
         &self,
     ) -> Result<Vec<crate::model::ResourceTemplate>, ServiceError> {
-        let mut resource_templates = Vec::new();
-        let mut cursor = None;
-        loop {
-            let result = self
-                .list_resource_templates(Some(PaginatedRequestParam { cursor }))
-                .await?;
-            resource_templates.extend(result.resource_templates);
-            cursor = result.next_cursor;
-            if cursor.is_none() {
-                break;
+        self.list_resource_templates_stream(DEFAULT_MAX_PAGES)
+            .try_collect()
+            .await
+    }
+
+    /// Stream the tools of [`Peer<RoleClient>::list_tools`], requesting the next page once the
+    /// previous one is consumed, and reading at most `max_pages` pages.
+    pub fn list_tools_stream(
+        &self,
+        max_pages: usize,
+    ) -> impl Stream<Item = Result<crate::model::Tool, ServiceError>> + Send + use<> {
+        let peer = self.clone();
+        paginate("tools/list", max_pages, move |cursor| {
+            let peer = peer.clone();
+            async move {
+                let result = peer
+                    .list_tools(Some(PaginatedRequestParam { cursor }))
+                    .await?;
+                Ok((result.tools, result.next_cursor))
             }
-        }
-        Ok(resource_templates)
+        })
+    }
+
+    /// Stream the prompts of [`Peer<RoleClient>::list_prompts`], see [`Peer<RoleClient>::list_tools_stream`]
+    pub fn list_prompts_stream(
+        &self,
+        max_pages: usize,
+    ) -> impl Stream<Item = Result<crate::model::Prompt, ServiceError>> + Send + use<> {
+        let peer = self.clone();
+        paginate("prompts/list", max_pages, move |cursor| {
+            let peer = peer.clone();
+            async move {
+                let result = peer
+                    .list_prompts(Some(PaginatedRequestParam { cursor }))
+                    .await?;
+                Ok((result.prompts, result.next_cursor))
+            }
+        })
+    }
+
+    /// Stream the resources of [`Peer<RoleClient>::list_resources`], see [`Peer<RoleClient>::list_tools_stream`]
+    pub fn list_resources_stream(
+        &self,
+        max_pages: usize,
+    ) -> impl Stream<Item = Result<crate::model::Resource, ServiceError>> + Send + use<> {
+        let peer = self.clone();
+        paginate("resources/list", max_pages, move |cursor| {
+            let peer = peer.clone();
+            async move {
+                let result = peer
+                    .list_resources(Some(PaginatedRequestParam { cursor }))
+                    .await?;
+                Ok((result.resources, result.next_cursor))
+            }
+        })
+    }
+
+    /// Stream the resource templates of [`Peer<RoleClient>::list_resource_templates`], see
+    /// [`Peer<RoleClient>::list_tools_stream`]
+    pub fn list_resource_templates_stream(
+        &self,
+        max_pages: usize,
+    ) -> impl Stream<Item = Result<crate::model::ResourceTemplate, ServiceError>> + Send + use<>
+    {
+        let peer = self.clone();
+        paginate("resources/templates/list", max_pages, move |cursor| {
+            let peer = peer.clone();
+            async move {
+                let result = peer
+                    .list_resource_templates(Some(PaginatedRequestParam { cursor }))
+                    .await?;
+                Ok((result.resource_templates, result.next_cursor))
+            }
+        })
     }
 }
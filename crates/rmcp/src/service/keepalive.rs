@@ -0,0 +1,166 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    Peer, PeerRequestOptions, RequestIdProvider, RunningService, Service, ServiceError, ServiceRole,
+};
+use crate::model::{NumberOrString, PingRequest, PingRequestMethod, RequestId};
+
+/// Allocates `keepalive-N` string ids, so pings never take a number from the normal requests
+#[derive(Debug, Default)]
+struct KeepaliveIdProvider {
+    id: AtomicU32,
+}
+
+impl RequestIdProvider for KeepaliveIdProvider {
+    fn next_request_id(&self) -> RequestId {
+        let id = self.id.fetch_add(1, Ordering::SeqCst);
+        NumberOrString::String(format!("keepalive-{id}").into())
+    }
+}
+
+type UnresponsiveCallback = Arc<dyn Fn(u32) + Send + Sync>;
+
+/// Configuration of the keepalive task started by [`RunningService::enable_keepalive_with`]
+#[derive(Clone)]
+pub struct KeepaliveConfig {
+    /// Time between two pings, must be non-zero
+    pub interval: Duration,
+    /// How long to wait for the response of a ping
+    pub timeout: Duration,
+    /// Consecutive failed pings after which the peer is considered unresponsive
+    pub max_failures: u32,
+    on_unresponsive: Option<UnresponsiveCallback>,
+}
+
+impl std::fmt::Debug for KeepaliveConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeepaliveConfig")
+            .field("interval", &self.interval)
+            .field("timeout", &self.timeout)
+            .field("max_failures", &self.max_failures)
+            .field("on_unresponsive", &self.on_unresponsive.is_some())
+            .finish()
+    }
+}
+
+impl KeepaliveConfig {
+    pub const DEFAULT_MAX_FAILURES: u32 = 3;
+
+    pub fn new(interval: Duration, timeout: Duration) -> Self {
+        Self {
+            interval,
+            timeout,
+            max_failures: Self::DEFAULT_MAX_FAILURES,
+            on_unresponsive: None,
+        }
+    }
+
+    pub fn with_max_failures(mut self, max_failures: u32) -> Self {
+        self.max_failures = max_failures.max(1);
+        self
+    }
+
+    /// Call `callback` with the number of failed pings instead of cancelling the service
+    /// once the peer is unresponsive
+    pub fn on_unresponsive(mut self, callback: impl Fn(u32) + Send + Sync + 'static) -> Self {
+        self.on_unresponsive = Some(Arc::new(callback));
+        self
+    }
+}
+
+/// Handle of a keepalive task
+///
+/// Dropping the handle leaves the task running, call [`KeepaliveHandle::stop`] to stop it.
+#[derive(Debug, Clone)]
+pub struct KeepaliveHandle {
+    ct: CancellationToken,
+}
+
+impl KeepaliveHandle {
+    pub fn stop(&self) {
+        self.ct.cancel();
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.ct.is_cancelled()
+    }
+}
+
+impl<R: ServiceRole, S: Service<R>> RunningService<R, S> {
+    /// Ping the peer every `interval`, the service quits with [`QuitReason::Unresponsive`]
+    /// after [`KeepaliveConfig::DEFAULT_MAX_FAILURES`] consecutive pings got no response
+    /// within `timeout`.
+    ///
+    /// [`QuitReason::Unresponsive`]: super::QuitReason::Unresponsive
+    pub fn enable_keepalive(&self, interval: Duration, timeout: Duration) -> KeepaliveHandle {
+        self.enable_keepalive_with(KeepaliveConfig::new(interval, timeout))
+    }
+
+    pub fn enable_keepalive_with(&self, config: KeepaliveConfig) -> KeepaliveHandle {
+        let peer = self
+            .peer
+            .with_request_id_provider(Arc::new(KeepaliveIdProvider::default()));
+        let ct = CancellationToken::new();
+        tokio::spawn(keepalive(
+            peer,
+            config,
+            self.unresponsive_ct.clone(),
+            ct.clone(),
+        ));
+        KeepaliveHandle { ct }
+    }
+}
+
+async fn keepalive<R: ServiceRole>(
+    peer: Peer<R>,
+    config: KeepaliveConfig,
+    unresponsive_ct: CancellationToken,
+    ct: CancellationToken,
+) {
+    let mut interval = tokio::time::interval(config.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // the first tick completes immediately
+    interval.tick().await;
+    let mut failures = 0;
+    loop {
+        tokio::select! {
+            _ = ct.cancelled() => return,
+            _ = unresponsive_ct.cancelled() => return,
+            _ = interval.tick() => {}
+        }
+        let ping = PingRequest {
+            method: PingRequestMethod,
+            extensions: Default::default(),
+        };
+        let options = PeerRequestOptions::no_options().with_timeout(config.timeout);
+        let result = match peer.send_request_with_option(ping.into(), options).await {
+            Ok(handle) => handle.await_response().await.map(drop),
+            Err(e) => Err(e),
+        };
+        match result {
+            // an error response still proves the peer is alive
+            Ok(()) | Err(ServiceError::McpError(_)) => failures = 0,
+            // the service has stopped
+            Err(ServiceError::TransportClosed) => return,
+            Err(e) => {
+                failures += 1;
+                tracing::warn!(%e, failures, "keepalive ping failed");
+                if failures >= config.max_failures {
+                    match &config.on_unresponsive {
+                        Some(callback) => callback(failures),
+                        None => unresponsive_ct.cancel(),
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
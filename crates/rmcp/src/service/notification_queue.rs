@@ -0,0 +1,333 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use tokio::sync::Notify;
+
+use super::{
+    Peer, RequestContext, Service, ServiceError, ServiceObserver, ServiceRole, SessionData,
+};
+use crate::{
+    error::Error as McpError,
+    model::{GetMethod, ProgressNotification},
+};
+
+/// What happens to a notification sent while the queue is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Wait for room in the queue
+    #[default]
+    Block,
+    /// Drop the notification queued the longest
+    DropOldest,
+    /// Drop the notification being sent
+    DropNewest,
+    /// Merge a progress notification into the last queued one when both are for the same
+    /// token, wait for room otherwise
+    CoalesceProgress,
+}
+
+/// Bounds the notifications waiting for the transport, see [`ServiceExt::with_notification_queue`]
+///
+/// Sending a notification returns once it is queued, the queue is flushed one notification at a
+/// time. Requests, responses and cancellations are never queued: the queued notifications are
+/// sent right before them, so they keep their order.
+///
+/// [`ServiceExt::with_notification_queue`]: super::ServiceExt::with_notification_queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NotificationQueueConfig {
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl NotificationQueueConfig {
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    /// A queue of `capacity` notifications, at least one, blocking the senders when full
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            overflow: OverflowPolicy::Block,
+        }
+    }
+
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
+impl Default for NotificationQueueConfig {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+/// A service with its own [`NotificationQueueConfig`], see
+/// [`ServiceExt::with_notification_queue`]
+///
+/// [`ServiceExt::with_notification_queue`]: super::ServiceExt::with_notification_queue
+pub struct WithNotificationQueue<S> {
+    service: S,
+    config: NotificationQueueConfig,
+}
+
+impl<S> WithNotificationQueue<S> {
+    pub fn new(service: S, config: NotificationQueueConfig) -> Self {
+        Self { service, config }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.service
+    }
+}
+
+impl<R: ServiceRole, S: Service<R>> Service<R> for WithNotificationQueue<S> {
+    fn handle_request(
+        &self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+    ) -> impl Future<Output = Result<R::Resp, McpError>> + Send + '_ {
+        self.service.handle_request(request, context)
+    }
+
+    fn handle_notification(
+        &self,
+        notification: R::PeerNot,
+    ) -> impl Future<Output = Result<(), McpError>> + Send + '_ {
+        self.service.handle_notification(notification)
+    }
+
+    fn get_info(&self) -> R::Info {
+        self.service.get_info()
+    }
+
+    fn observer(&self) -> Option<Arc<dyn ServiceObserver>> {
+        self.service.observer()
+    }
+
+    fn notification_queue(&self) -> NotificationQueueConfig {
+        self.config
+    }
+
+    fn handle_disconnect(&self, session: SessionData) -> impl Future<Output = ()> + Send + '_ {
+        self.service.handle_disconnect(session)
+    }
+
+    fn bind_peer(&self, peer: &Peer<R>) {
+        self.service.bind_peer(peer)
+    }
+}
+
+/// The notifications of a peer waiting for the transport, flushed by the serve loop
+pub(crate) struct NotificationQueue<R: ServiceRole> {
+    state: Mutex<QueueState<R>>,
+    /// a notification was queued
+    queued: Notify,
+    /// room was made in the queue, or it was closed
+    room: Notify,
+}
+
+struct QueueState<R: ServiceRole> {
+    notifications: VecDeque<R::Not>,
+    config: NotificationQueueConfig,
+    observer: Option<Arc<dyn ServiceObserver>>,
+    closed: bool,
+}
+
+impl<R: ServiceRole> std::fmt::Debug for NotificationQueue<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.lock();
+        f.debug_struct("NotificationQueue")
+            .field("depth", &state.notifications.len())
+            .field("config", &state.config)
+            .field("closed", &state.closed)
+            .finish()
+    }
+}
+
+impl<R: ServiceRole> Default for NotificationQueue<R> {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                notifications: VecDeque::new(),
+                config: NotificationQueueConfig::default(),
+                observer: None,
+                closed: false,
+            }),
+            queued: Notify::new(),
+            room: Notify::new(),
+        }
+    }
+}
+
+impl<R: ServiceRole> NotificationQueue<R> {
+    fn lock(&self) -> MutexGuard<'_, QueueState<R>> {
+        self.state.lock().expect("poisoned")
+    }
+
+    pub(crate) fn configure(
+        &self,
+        config: NotificationQueueConfig,
+        observer: Option<Arc<dyn ServiceObserver>>,
+    ) {
+        let mut state = self.lock();
+        state.config = NotificationQueueConfig::new(config.capacity).with_overflow(config.overflow);
+        state.observer = observer;
+    }
+
+    /// Queue `notification`, waiting for room unless the policy drops or merges it
+    pub(crate) async fn push(&self, notification: R::Not) -> Result<(), ServiceError> {
+        let mut notification = notification;
+        loop {
+            let room = self.room.notified();
+            let mut room = std::pin::pin!(room);
+            // registered before looking at the queue, so no room made meanwhile is missed
+            room.as_mut().enable();
+            {
+                let mut state = self.lock();
+                if state.closed {
+                    return Err(ServiceError::TransportClosed);
+                }
+                match state.try_push(notification) {
+                    Ok(()) => {
+                        drop(state);
+                        self.queued.notify_one();
+                        return Ok(());
+                    }
+                    Err(full) => notification = full,
+                }
+            }
+            room.await;
+        }
+    }
+
+    /// Wait for the next notification to send, cancel safe
+    pub(crate) async fn next(&self) -> R::Not {
+        loop {
+            let queued = self.queued.notified();
+            let mut queued = std::pin::pin!(queued);
+            queued.as_mut().enable();
+            if let Some(notification) = self.pop() {
+                return notification;
+            }
+            queued.await;
+        }
+    }
+
+    fn pop(&self) -> Option<R::Not> {
+        let mut state = self.lock();
+        let notification = state.notifications.pop_front()?;
+        state.report_depth();
+        drop(state);
+        self.room.notify_waiters();
+        Some(notification)
+    }
+
+    /// Take all the queued notifications, to send them before a message which can't wait
+    pub(crate) fn drain(&self) -> Vec<R::Not> {
+        let mut state = self.lock();
+        if state.notifications.is_empty() {
+            return Vec::new();
+        }
+        let notifications = state.notifications.drain(..).collect();
+        state.report_depth();
+        drop(state);
+        self.room.notify_waiters();
+        notifications
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.lock().notifications.is_empty()
+    }
+
+    /// Drop the queued notifications and fail the next ones, once the serve loop is done
+    pub(crate) fn close(&self) {
+        let mut state = self.lock();
+        state.closed = true;
+        if !state.notifications.is_empty() {
+            tracing::debug!(
+                count = state.notifications.len(),
+                "queued notifications dropped"
+            );
+            state.notifications.clear();
+            state.report_depth();
+        }
+        drop(state);
+        self.room.notify_waiters();
+    }
+}
+
+impl<R: ServiceRole> QueueState<R> {
+    /// Queue or drop `notification` as the policy says, give it back when it must wait
+    fn try_push(&mut self, notification: R::Not) -> Result<(), R::Not> {
+        let policy = self.config.overflow;
+        let notification = match policy {
+            OverflowPolicy::CoalesceProgress => match self.coalesce(notification) {
+                Ok(()) => return Ok(()),
+                Err(notification) => notification,
+            },
+            _ => notification,
+        };
+        if self.notifications.len() < self.config.capacity {
+            self.notifications.push_back(notification);
+            self.report_depth();
+            return Ok(());
+        }
+        match policy {
+            OverflowPolicy::DropOldest => {
+                if let Some(oldest) = self.notifications.pop_front() {
+                    self.report_dropped(&oldest);
+                }
+                self.notifications.push_back(notification);
+                Ok(())
+            }
+            OverflowPolicy::DropNewest => {
+                self.report_dropped(&notification);
+                Ok(())
+            }
+            OverflowPolicy::Block | OverflowPolicy::CoalesceProgress => Err(notification),
+        }
+    }
+
+    /// Replace the last queued notification with `notification` when both are progress of the
+    /// same token
+    fn coalesce(&mut self, notification: R::Not) -> Result<(), R::Not> {
+        let progress: ProgressNotification = notification.try_into()?;
+        let Some(last) = self.notifications.pop_back() else {
+            return Err(progress.into());
+        };
+        match last.try_into() {
+            Ok::<ProgressNotification, _>(last)
+                if last.params.progress_token == progress.params.progress_token =>
+            {
+                let last: R::Not = last.into();
+                self.report_dropped(&last);
+                self.notifications.push_back(progress.into());
+                Ok(())
+            }
+            Ok(last) => {
+                self.notifications.push_back(last.into());
+                Err(progress.into())
+            }
+            Err(last) => {
+                self.notifications.push_back(last);
+                Err(progress.into())
+            }
+        }
+    }
+
+    fn report_depth(&self) {
+        if let Some(observer) = &self.observer {
+            observer.on_notification_queue_depth(self.notifications.len());
+        }
+    }
+
+    fn report_dropped(&self, notification: &R::Not) {
+        tracing::debug!(method = notification.method(), "notification dropped");
+        if let Some(observer) = &self.observer {
+            observer.on_notification_dropped(notification.method());
+        }
+    }
+}
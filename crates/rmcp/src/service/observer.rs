@@ -0,0 +1,183 @@
+use std::{sync::Arc, time::Duration};
+
+use super::{NotificationQueueConfig, RequestContext, Service, ServiceRole, SessionData};
+use crate::{error::Error as McpError, model::RequestId};
+
+/// Whether a message was received from the peer or sent to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageDirection {
+    Inbound,
+    Outbound,
+}
+
+impl MessageDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageDirection::Inbound => "inbound",
+            MessageDirection::Outbound => "outbound",
+        }
+    }
+}
+
+/// Observes the requests and notifications of a running service, installed with
+/// [`ServiceExt::with_observer`]
+///
+/// Inbound requests are the ones handled by the service, outbound requests the ones sent
+/// through its [`Peer`], their ids are unique per direction only. The callbacks run on the
+/// service loop, the ones of the notification queue on the tasks sending notifications too, and
+/// should return quickly.
+///
+/// [`ServiceExt::with_observer`]: super::ServiceExt::with_observer
+/// [`Peer`]: super::Peer
+pub trait ServiceObserver: Send + Sync + 'static {
+    fn on_request_start(&self, method: &str, id: &RequestId, direction: MessageDirection) {
+        let _ = (method, id, direction);
+    }
+
+    /// `ok` is false for error responses, and for outbound requests which failed to be sent
+    /// or were cancelled
+    fn on_request_end(
+        &self,
+        method: &str,
+        id: &RequestId,
+        direction: MessageDirection,
+        duration: Duration,
+        ok: bool,
+    ) {
+        let _ = (method, id, direction, duration, ok);
+    }
+
+    fn on_notification(&self, method: &str, direction: MessageDirection) {
+        let _ = (method, direction);
+    }
+
+    /// The number of outbound notifications waiting for the transport, whenever it changes
+    fn on_notification_queue_depth(&self, depth: usize) {
+        let _ = depth;
+    }
+
+    /// An outbound notification was dropped or merged into a later one, see
+    /// [`OverflowPolicy`](super::OverflowPolicy)
+    fn on_notification_dropped(&self, method: &str) {
+        let _ = method;
+    }
+}
+
+/// A service with a [`ServiceObserver`], see [`ServiceExt::with_observer`]
+///
+/// [`ServiceExt::with_observer`]: super::ServiceExt::with_observer
+pub struct Observed<S> {
+    service: S,
+    observer: Arc<dyn ServiceObserver>,
+}
+
+impl<S> Observed<S> {
+    pub fn new(service: S, observer: impl ServiceObserver) -> Self {
+        Self {
+            service,
+            observer: Arc::new(observer),
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.service
+    }
+}
+
+impl<R: ServiceRole, S: Service<R>> Service<R> for Observed<S> {
+    fn handle_request(
+        &self,
+        request: R::PeerReq,
+        context: RequestContext<R>,
+    ) -> impl Future<Output = Result<R::Resp, McpError>> + Send + '_ {
+        self.service.handle_request(request, context)
+    }
+
+    fn handle_notification(
+        &self,
+        notification: R::PeerNot,
+    ) -> impl Future<Output = Result<(), McpError>> + Send + '_ {
+        self.service.handle_notification(notification)
+    }
+
+    fn get_info(&self) -> R::Info {
+        self.service.get_info()
+    }
+
+    fn observer(&self) -> Option<Arc<dyn ServiceObserver>> {
+        Some(self.observer.clone())
+    }
+
+    fn notification_queue(&self) -> NotificationQueueConfig {
+        self.service.notification_queue()
+    }
+
+    fn handle_disconnect(&self, session: SessionData) -> impl Future<Output = ()> + Send + '_ {
+        self.service.handle_disconnect(session)
+    }
+
+    fn bind_peer(&self, peer: &super::Peer<R>) {
+        self.service.bind_peer(peer)
+    }
+}
+
+/// Records the requests and notifications with the [`metrics`] facade
+///
+/// - `mcp_requests_total` counter, labeled with `method`, `direction` and `status` (`ok` or `error`)
+/// - `mcp_request_duration_seconds` histogram, labeled with `method` and `direction`
+/// - `mcp_notifications_total` counter, labeled with `method` and `direction`
+/// - `mcp_notification_queue_depth` gauge, the outbound notifications waiting for the transport
+/// - `mcp_notifications_dropped_total` counter, labeled with `method`
+#[cfg(feature = "metrics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricsObserver;
+
+#[cfg(feature = "metrics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+impl ServiceObserver for MetricsObserver {
+    fn on_request_end(
+        &self,
+        method: &str,
+        _id: &RequestId,
+        direction: MessageDirection,
+        duration: Duration,
+        ok: bool,
+    ) {
+        let status = if ok { "ok" } else { "error" };
+        let requests = metrics::counter!(
+            "mcp_requests_total",
+            "method" => method.to_owned(),
+            "direction" => direction.as_str(),
+            "status" => status
+        );
+        requests.increment(1);
+        let duration_seconds = metrics::histogram!(
+            "mcp_request_duration_seconds",
+            "method" => method.to_owned(),
+            "direction" => direction.as_str()
+        );
+        duration_seconds.record(duration.as_secs_f64());
+    }
+
+    fn on_notification(&self, method: &str, direction: MessageDirection) {
+        let notifications = metrics::counter!(
+            "mcp_notifications_total",
+            "method" => method.to_owned(),
+            "direction" => direction.as_str()
+        );
+        notifications.increment(1);
+    }
+
+    fn on_notification_queue_depth(&self, depth: usize) {
+        metrics::gauge!("mcp_notification_queue_depth").set(depth as f64);
+    }
+
+    fn on_notification_dropped(&self, method: &str) {
+        let dropped = metrics::counter!(
+            "mcp_notifications_dropped_total",
+            "method" => method.to_owned()
+        );
+        dropped.increment(1);
+    }
+}
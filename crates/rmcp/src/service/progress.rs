@@ -0,0 +1,117 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::model::{ProgressNotificationParam, ProgressToken, RequestId};
+
+/// Called with the `notifications/progress` of one request, see [`PeerRequestOptions::with_progress`]
+///
+/// It runs on the service loop and should return quickly.
+///
+/// [`PeerRequestOptions::with_progress`]: super::PeerRequestOptions::with_progress
+#[derive(Clone)]
+pub struct ProgressHandler(Arc<dyn Fn(ProgressNotificationParam) + Send + Sync>);
+
+impl ProgressHandler {
+    pub fn new(handler: impl Fn(ProgressNotificationParam) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(handler))
+    }
+}
+
+impl std::fmt::Debug for ProgressHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ProgressHandler").finish_non_exhaustive()
+    }
+}
+
+struct Route {
+    request_id: RequestId,
+    handler: ProgressHandler,
+    last: Option<f64>,
+}
+
+/// The progress handlers of the requests in flight, by progress token
+#[derive(Default)]
+pub(crate) struct ProgressRoutes {
+    routes: HashMap<ProgressToken, Route>,
+}
+
+impl ProgressRoutes {
+    pub(crate) fn insert(
+        &mut self,
+        token: ProgressToken,
+        request_id: RequestId,
+        handler: ProgressHandler,
+    ) {
+        let route = Route {
+            request_id,
+            handler,
+            last: None,
+        };
+        self.routes.insert(token, route);
+    }
+
+    /// Call the handler of the request, ignoring the progress of unknown or finished requests
+    /// and progress not above the last one
+    pub(crate) fn dispatch(&mut self, params: &ProgressNotificationParam) {
+        let Some(route) = self.routes.get_mut(&params.progress_token) else {
+            tracing::debug!(token = ?params.progress_token, "progress of an unknown request");
+            return;
+        };
+        if route.last.is_some_and(|last| params.progress <= last) {
+            tracing::debug!(token = ?params.progress_token, "out of order progress ignored");
+            return;
+        }
+        route.last = Some(params.progress);
+        (route.handler.0)(params.clone());
+    }
+
+    /// Forget the handler once the request is answered, failed or cancelled
+    pub(crate) fn remove_request(&mut self, request_id: &RequestId) {
+        if !self.routes.is_empty() {
+            self.routes
+                .retain(|_, route| &route.request_id != request_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::model::NumberOrString;
+
+    fn progress(token: u32, progress: f64) -> ProgressNotificationParam {
+        ProgressNotificationParam {
+            progress_token: ProgressToken(NumberOrString::Number(token.into())),
+            progress,
+            total: Some(100.0),
+            message: None,
+        }
+    }
+
+    #[test]
+    fn test_progress_routes() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut routes = ProgressRoutes::default();
+        routes.insert(
+            ProgressToken(NumberOrString::Number(1)),
+            RequestId::Number(7),
+            ProgressHandler::new({
+                let received = received.clone();
+                move |params| received.lock().unwrap().push(params.progress)
+            }),
+        );
+
+        routes.dispatch(&progress(1, 0.0));
+        routes.dispatch(&progress(2, 10.0));
+        routes.dispatch(&progress(1, 50.0));
+        routes.dispatch(&progress(1, 50.0));
+        routes.dispatch(&progress(1, 20.0));
+        routes.dispatch(&progress(1, 100.0));
+        routes.dispatch(&progress(1, 100.5));
+        routes.remove_request(&RequestId::Number(7));
+        routes.dispatch(&progress(1, 101.0));
+
+        assert_eq!(*received.lock().unwrap(), [0.0, 50.0, 100.0, 100.5]);
+    }
+}
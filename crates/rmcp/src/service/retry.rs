@@ -0,0 +1,154 @@
+use std::{
+    collections::HashSet,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use super::ServiceError;
+
+/// When and how often [`Peer::send_request`](super::Peer::send_request) retries a request
+///
+/// Only the requests failing with a transient error, i.e. [`ServiceError::TransportSend`] or
+/// [`ServiceError::Timeout`], are retried, and only for the allowed methods. By default they
+/// are the idempotent ones: the lists, `prompts/get`, `resources/read` and `ping`. `tools/call`
+/// must be allowed explicitly with [`RetryPolicy::allow_method`], for the tools which are safe
+/// to call twice.
+///
+/// Every attempt is a new request with a fresh id, the failed ones are never answered.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first one
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled for each next one
+    pub initial_backoff: Duration,
+    /// Upper bound of the wait between two attempts
+    pub max_backoff: Duration,
+    /// Wait a random duration between the half and the whole of the backoff
+    pub jitter: bool,
+    methods: HashSet<&'static str>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+            initial_backoff: Self::DEFAULT_INITIAL_BACKOFF,
+            max_backoff: Self::DEFAULT_MAX_BACKOFF,
+            jitter: true,
+            methods: Self::IDEMPOTENT_METHODS.into_iter().collect(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+    pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+    pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(5);
+    /// The methods retried by default
+    pub const IDEMPOTENT_METHODS: [&'static str; 7] = [
+        "ping",
+        "tools/list",
+        "prompts/list",
+        "prompts/get",
+        "resources/list",
+        "resources/templates/list",
+        "resources/read",
+    ];
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    pub fn without_jitter(mut self) -> Self {
+        self.jitter = false;
+        self
+    }
+
+    /// Retry the requests of `method` too, e.g. `tools/call`
+    pub fn allow_method(mut self, method: &'static str) -> Self {
+        self.methods.insert(method);
+        self
+    }
+
+    /// Never retry the requests of `method`
+    pub fn deny_method(mut self, method: &str) -> Self {
+        self.methods.remove(method);
+        self
+    }
+
+    pub fn allows(&self, method: &str) -> bool {
+        self.max_attempts > 1 && self.methods.contains(method)
+    }
+
+    /// Whether a request failing with `error` may succeed when sent again
+    pub fn is_transient(error: &ServiceError) -> bool {
+        matches!(
+            error,
+            ServiceError::TransportSend(_) | ServiceError::Timeout { .. }
+        )
+    }
+
+    /// The wait before the attempt following the `attempt`th one
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff);
+        if self.jitter {
+            let random = std::collections::hash_map::RandomState::new()
+                .build_hasher()
+                .finish();
+            backoff / 2 + backoff.mul_f64((random % 1024) as f64 / 2048.0)
+        } else {
+            backoff
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy::new()
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(300))
+            .without_jitter();
+        assert!(policy.allows("tools/list"));
+        assert!(!policy.allows("tools/call"));
+        assert!(
+            policy
+                .clone()
+                .allow_method("tools/call")
+                .allows("tools/call")
+        );
+        assert!(!policy.clone().deny_method("ping").allows("ping"));
+        assert!(!policy.clone().with_max_attempts(1).allows("ping"));
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(300));
+        assert_eq!(policy.backoff(40), Duration::from_millis(300));
+
+        let jittered = RetryPolicy {
+            jitter: true,
+            ..policy
+        };
+        for attempt in 1..5 {
+            let backoff = jittered.backoff(attempt);
+            let max = jittered.clone().without_jitter().backoff(attempt);
+            assert!(backoff >= max / 2 && backoff <= max);
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Serving many clients from one process
+//!
+//! A [`ServerRunner`] makes a service for every transport it is given, keeps the running
+//! sessions in a registry until they end, and reaches all of them at once: to list them, to
+//! broadcast a notification after the tools changed, or to shut them down.
+//!
+//! ```rust,ignore
+//! let runner = ServerRunner::new(|| Counter::new());
+//! runner.serve_all(sse_server);
+//! // after a plugin reload
+//! let notification = ToolListChangedNotification {
+//!     method: Default::default(),
+//!     extensions: Default::default(),
+//! };
+//! runner
+//!     .broadcast_notification(ServerNotification::ToolListChangedNotification(notification))
+//!     .await;
+//! runner.shutdown_all(Duration::from_secs(10)).await;
+//! ```
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use futures::{Stream, StreamExt};
+use tokio::{sync::oneshot, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    ConnectionState, Peer, QuitReason, RoleServer, ServerInitializeError, Service, ServiceError,
+    ServiceExt,
+};
+use crate::{model::ServerNotification, transport::IntoTransport};
+
+/// What a [`ServerRunner`] knows of a session before it is served
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionInfo {
+    /// Unique within its runner, in the order the sessions started
+    pub id: u64,
+}
+
+/// A running session of a [`ServerRunner`]
+#[derive(Debug, Clone)]
+pub struct LiveSession {
+    pub info: SessionInfo,
+    /// The client of the session, with its info and connection state
+    pub peer: Peer<RoleServer>,
+}
+
+struct Entry {
+    session: LiveSession,
+    shutdown_tx: Option<oneshot::Sender<Duration>>,
+    handle: Option<JoinHandle<QuitReason>>,
+}
+
+type Registry = Arc<Mutex<HashMap<u64, Entry>>>;
+
+/// Serves each transport with its own service, and keeps track of the running sessions
+///
+/// The sessions leave the registry as soon as they end, whatever the reason. Clones share
+/// the factory and the sessions.
+pub struct ServerRunner<S> {
+    factory: Arc<dyn Fn(&SessionInfo) -> S + Send + Sync>,
+    registry: Registry,
+    next_id: Arc<AtomicU64>,
+    /// cancelled when the runner stops accepting sessions
+    accept_ct: CancellationToken,
+    /// the parent of the sessions
+    ct: CancellationToken,
+}
+
+impl<S> Clone for ServerRunner<S> {
+    fn clone(&self) -> Self {
+        Self {
+            factory: self.factory.clone(),
+            registry: self.registry.clone(),
+            next_id: self.next_id.clone(),
+            accept_ct: self.accept_ct.clone(),
+            ct: self.ct.clone(),
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for ServerRunner<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerRunner")
+            .field("sessions", &self.registry.lock().expect("poisoned").len())
+            .field("accepting", &!self.accept_ct.is_cancelled())
+            .finish()
+    }
+}
+
+impl<S: Service<RoleServer>> ServerRunner<S> {
+    /// Make a service for every session with `factory`
+    pub fn new(factory: impl Fn() -> S + Send + Sync + 'static) -> Self {
+        Self::with_session_info(move |_| factory())
+    }
+
+    /// Make the service of every session with `factory`, from what is known of the session
+    pub fn with_session_info(factory: impl Fn(&SessionInfo) -> S + Send + Sync + 'static) -> Self {
+        Self {
+            factory: Arc::new(factory),
+            registry: Default::default(),
+            next_id: Default::default(),
+            accept_ct: CancellationToken::new(),
+            ct: CancellationToken::new(),
+        }
+    }
+
+    /// Initialize a session over `transport`, and keep it until it ends
+    ///
+    /// Fails once the runner is shut down.
+    pub async fn serve<T, E, A>(
+        &self,
+        transport: T,
+    ) -> Result<SessionInfo, ServerInitializeError<E>>
+    where
+        T: IntoTransport<RoleServer, E, A>,
+        E: std::error::Error + From<std::io::Error> + Send + Sync + 'static,
+    {
+        if self.accept_ct.is_cancelled() {
+            return Err(ServerInitializeError::ConnectionClosed(
+                "the server runner is shut down".into(),
+            ));
+        }
+        let info = SessionInfo {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+        };
+        let service = (self.factory)(&info);
+        let running = service
+            .serve_with_ct(transport, self.ct.child_token())
+            .await?;
+        let session = LiveSession {
+            info: info.clone(),
+            peer: running.peer().clone(),
+        };
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<Duration>();
+        let mut state = running.state_watch();
+        let registry = self.registry.clone();
+        let id = info.id;
+        // hold the registry while spawning, so the session can't leave it before it is in
+        let mut sessions = self.registry.lock().expect("poisoned");
+        let handle = tokio::spawn(async move {
+            let quit_reason = tokio::select! {
+                Ok(deadline) = &mut shutdown_rx => running.shutdown_graceful(deadline).await,
+                // drop the `watch::Ref` in the arm, it isn't `Send`
+                _ = async {
+                    let _ = state.wait_for(ConnectionState::is_closed).await;
+                } => running.waiting().await,
+            };
+            registry.lock().expect("poisoned").remove(&id);
+            let quit_reason = quit_reason.unwrap_or_else(|e| QuitReason::JoinError(Arc::new(e)));
+            tracing::debug!(id, ?quit_reason, "session ended");
+            quit_reason
+        });
+        sessions.insert(
+            id,
+            Entry {
+                session,
+                shutdown_tx: Some(shutdown_tx),
+                handle: Some(handle),
+            },
+        );
+        Ok(info)
+    }
+
+    /// Serve every transport of `transports`, until the stream ends or the runner is shut down
+    ///
+    /// The sessions are initialized on their own tasks, a slow client doesn't hold back the
+    /// others. An `SseServer` is such a stream, the transports of other servers can be given to
+    /// [`serve`](Self::serve) one by one.
+    pub fn serve_all<St, T, E, A>(&self, transports: St) -> JoinHandle<()>
+    where
+        St: Stream<Item = T> + Send + 'static,
+        T: IntoTransport<RoleServer, E, A>,
+        E: std::error::Error + From<std::io::Error> + Send + Sync + 'static,
+        A: Send + 'static,
+    {
+        let runner = self.clone();
+        tokio::spawn(async move {
+            let mut transports = std::pin::pin!(transports);
+            loop {
+                let transport = tokio::select! {
+                    transport = transports.next() => transport,
+                    _ = runner.accept_ct.cancelled() => break,
+                };
+                let Some(transport) = transport else {
+                    break;
+                };
+                let runner = runner.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = runner.serve(transport).await {
+                        tracing::warn!("fail to initialize session: {e}");
+                    }
+                });
+            }
+        })
+    }
+
+    /// The running sessions, by id
+    pub fn sessions(&self) -> Vec<LiveSession> {
+        let mut sessions: Vec<_> = self
+            .registry
+            .lock()
+            .expect("poisoned")
+            .values()
+            .map(|entry| entry.session.clone())
+            .collect();
+        sessions.sort_by_key(|session| session.info.id);
+        sessions
+    }
+
+    pub fn session(&self, id: u64) -> Option<LiveSession> {
+        let sessions = self.registry.lock().expect("poisoned");
+        sessions.get(&id).map(|entry| entry.session.clone())
+    }
+
+    /// Send `notification` to every running session, returning the sessions it failed for
+    pub async fn broadcast_notification(
+        &self,
+        notification: ServerNotification,
+    ) -> Vec<(SessionInfo, ServiceError)> {
+        let sent = self.sessions().into_iter().map(|session| {
+            let notification = notification.clone();
+            async move {
+                let result = session.peer.send_notification(notification).await;
+                result.err().map(|error| (session.info, error))
+            }
+        });
+        futures::future::join_all(sent)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Stop serving, and shut every session down gracefully
+    ///
+    /// No transport is accepted anymore, then each session finishes its in-flight requests
+    /// within `deadline`, see [`RunningService::shutdown_graceful`](super::RunningService::shutdown_graceful).
+    /// Sessions still initializing are cancelled.
+    pub async fn shutdown_all(&self, deadline: Duration) -> Vec<(SessionInfo, QuitReason)> {
+        self.accept_ct.cancel();
+        let entries: Vec<_> = {
+            let mut sessions = self.registry.lock().expect("poisoned");
+            sessions
+                .values_mut()
+                .map(|entry| {
+                    if let Some(shutdown_tx) = entry.shutdown_tx.take() {
+                        let _ = shutdown_tx.send(deadline);
+                    }
+                    (entry.session.info.clone(), entry.handle.take())
+                })
+                .collect()
+        };
+        let mut quit_reasons = Vec::with_capacity(entries.len());
+        for (info, handle) in entries {
+            // another shutdown is waiting for it already
+            let Some(handle) = handle else {
+                continue;
+            };
+            let quit_reason = handle
+                .await
+                .unwrap_or_else(|e| QuitReason::JoinError(Arc::new(e)));
+            quit_reasons.push((info, quit_reason));
+        }
+        self.ct.cancel();
+        quit_reasons.sort_by_key(|(info, _)| info.id);
+        quit_reasons
+    }
+}
@@ -4,13 +4,15 @@ use thiserror::Error;
 
 use super::*;
 use crate::model::{
-    CancelledNotification, CancelledNotificationParam, ClientInfo, ClientJsonRpcMessage,
-    ClientNotification, ClientRequest, ClientResult, CreateMessageRequest,
-    CreateMessageRequestParam, CreateMessageResult, ErrorData, ListRootsRequest, ListRootsResult,
-    LoggingMessageNotification, LoggingMessageNotificationParam, ProgressNotification,
-    ProgressNotificationParam, PromptListChangedNotification, ProtocolVersion,
-    ResourceListChangedNotification, ResourceUpdatedNotification, ResourceUpdatedNotificationParam,
-    ServerInfo, ServerNotification, ServerRequest, ServerResult, ToolListChangedNotification,
+    CancelledNotification, CancelledNotificationParam, ClientCapabilities, ClientInfo,
+    ClientJsonRpcMessage, ClientNotification, ClientRequest, ClientResult,
+    CreateElicitationRequest, CreateElicitationRequestParam, CreateElicitationResult,
+    CreateMessageRequest, CreateMessageRequestParam, CreateMessageResult, ElicitationAction,
+    ErrorData, ListRootsRequest, ListRootsResult, LoggingMessageNotification,
+    LoggingMessageNotificationParam, ProgressNotification, ProgressNotificationParam,
+    PromptListChangedNotification, ProtocolVersion, ResourceListChangedNotification,
+    ResourceUpdatedNotification, ResourceUpdatedNotificationParam, ServerInfo, ServerNotification,
+    ServerRequest, ServerResult, ToolListChangedNotification,
 };
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -62,6 +64,17 @@ pub enum ServerInitializeError<E> {
 
 pub type ClientSink = Peer<RoleServer>;
 
+/// How the user answered [`Peer::elicit`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ElicitationOutcome<T> {
+    /// The user submitted the requested input
+    Accepted(T),
+    /// The user explicitly refused to provide the input
+    Declined,
+    /// The user dismissed the request without choosing
+    Cancelled,
+}
+
 impl<S: Service<RoleServer>> ServiceExt<RoleServer> for S {
     fn serve_with_ct<T, E, A>(
         self,
@@ -119,20 +132,75 @@ where
         )))
 }
 
-/// Helper function to expect a notification from the stream
-async fn expect_notification<T, E>(
+/// Helper function to expect the initialized notification from the stream
+///
+/// The client may batch other messages with it, they are returned to be served next.
+async fn expect_initialized_notification<T, E>(
     transport: &mut T,
     context: &str,
-) -> Result<ClientNotification, ServerInitializeError<E>>
+) -> Result<(ClientNotification, VecDeque<ClientJsonRpcMessage>), ServerInitializeError<E>>
 where
     T: Transport<RoleServer>,
 {
     let msg = expect_next_message(transport, context).await?;
+    let (msg, pending) = match msg {
+        ClientJsonRpcMessage::BatchRequest(mut items) => {
+            let initialized = items.iter().position(|item| {
+                matches!(
+                    item,
+                    JsonRpcBatchRequestItem::Notification(JsonRpcNotification {
+                        notification: ClientNotification::InitializedNotification(_),
+                        ..
+                    })
+                )
+            });
+            match initialized {
+                Some(index) => {
+                    let initialized = items.remove(index).into_non_batch_message();
+                    // the rest stays a batch, its requests are answered together
+                    let pending = if items.is_empty() {
+                        VecDeque::new()
+                    } else {
+                        VecDeque::from([ClientJsonRpcMessage::BatchRequest(items)])
+                    };
+                    (initialized, pending)
+                }
+                None => (ClientJsonRpcMessage::BatchRequest(items), VecDeque::new()),
+            }
+        }
+        msg => (msg, VecDeque::new()),
+    };
     let msg_clone = msg.clone();
-    msg.into_notification()
-        .ok_or(ServerInitializeError::ExpectedInitializedNotification(
-            Some(msg_clone),
-        ))
+    let notification =
+        msg.into_notification()
+            .ok_or(ServerInitializeError::ExpectedInitializedNotification(
+                Some(msg_clone),
+            ))?;
+    Ok((notification, pending))
+}
+
+/// Answer the `initialize` request with `error`, and close the transport
+async fn reject_client<T, E>(
+    transport: &mut T,
+    error: ErrorData,
+    id: RequestId,
+) -> Result<(), ServerInitializeError<E>>
+where
+    T: Transport<RoleServer, Error = E>,
+    E: std::error::Error,
+{
+    transport
+        .send(ServerJsonRpcMessage::error(error, id))
+        .await
+        .map_err(|error| ServerInitializeError::TransportError {
+            error,
+            context: "sending error response".into(),
+        })?;
+    // the client is rejected, don't leave the session half initialized
+    if let Err(error) = transport.close().await {
+        tracing::warn!(%error, "fail to close the transport of a rejected client");
+    }
+    Ok(())
 }
 
 pub async fn serve_server_with_ct<S, T, E, A>(
@@ -156,7 +224,19 @@ where
             ClientJsonRpcMessage::request(request, id),
         )));
     };
+    let requested_version = &peer_info.params.protocol_version;
+    if !requested_version.is_negotiable() {
+        let error = requested_version.unsupported_error();
+        reject_client(&mut transport, error, id).await?;
+        return Err(ServerInitializeError::UnsupportedProtocolVersion(
+            requested_version.clone(),
+        ));
+    }
     let (peer, peer_rx) = Peer::new(id_provider, peer_info.params.clone());
+    // the principal authenticated by the transport lives as long as the session
+    if let Some(identity) = request.extensions().get::<Identity>() {
+        peer.session().insert(identity.clone());
+    }
     let context = RequestContext {
         ct: ct.child_token(),
         id: id.clone(),
@@ -164,34 +244,42 @@ where
         extensions: request.extensions().clone(),
         peer: peer.clone(),
     };
+    let observer = service.observer();
+    if let Some(observer) = &observer {
+        observer.on_request_start(request.method(), &id, MessageDirection::Inbound);
+    }
     // Send initialize response
+    let start = std::time::Instant::now();
     let init_response = service.handle_request(request.clone(), context).await;
+    if let Some(observer) = &observer {
+        observer.on_request_end(
+            request.method(),
+            &id,
+            MessageDirection::Inbound,
+            start.elapsed(),
+            init_response.is_ok(),
+        );
+    }
     let mut init_response = match init_response {
         Ok(ServerResult::InitializeResult(init_response)) => init_response,
         Ok(result) => {
             return Err(ServerInitializeError::UnexpectedInitializeResponse(result));
         }
         Err(e) => {
-            transport
-                .send(ServerJsonRpcMessage::error(e.clone(), id))
-                .await
-                .map_err(|error| ServerInitializeError::TransportError {
-                    error,
-                    context: "sending error response".into(),
-                })?;
+            reject_client(&mut transport, e.clone(), id).await?;
             return Err(ServerInitializeError::InitializeFailed(e));
         }
     };
-    let peer_protocol_version = peer_info.params.protocol_version.clone();
-    let protocol_version = match peer_protocol_version
-        .partial_cmp(&init_response.protocol_version)
-        .ok_or(ServerInitializeError::UnsupportedProtocolVersion(
-            peer_protocol_version,
-        ))? {
-        std::cmp::Ordering::Less => peer_info.params.protocol_version.clone(),
-        _ => init_response.protocol_version,
-    };
-    init_response.protocol_version = protocol_version;
+    // the version of the server info is the latest the server speaks
+    let protocol_version = init_response
+        .protocol_version
+        .negotiate(&peer_info.params.protocol_version);
+    init_response.protocol_version = protocol_version.clone();
+    // requests are served with the negotiated version in the peer info
+    let peer = peer.with_peer_info(ClientInfo {
+        protocol_version,
+        ..peer_info.params.clone()
+    });
     transport
         .send(ServerJsonRpcMessage::response(
             ServerResult::InitializeResult(init_response),
@@ -204,15 +292,20 @@ where
         })?;
 
     // Wait for initialize notification
-    let notification = expect_notification(&mut transport, "initialize notification").await?;
+    let (mut notification, pending) =
+        expect_initialized_notification(&mut transport, "initialize notification").await?;
     let ClientNotification::InitializedNotification(_) = notification else {
         return Err(ServerInitializeError::ExpectedInitializedNotification(
             Some(ClientJsonRpcMessage::notification(notification)),
         ));
     };
+    if let Some(observer) = &observer {
+        observer.on_notification(notification.method(), MessageDirection::Inbound);
+    }
+    notification.extensions_mut().insert(peer.session().clone());
     let _ = service.handle_notification(notification).await;
     // Continue processing service
-    Ok(serve_inner(service, transport, peer, peer_rx, ct).await)
+    Ok(serve_inner(service, transport, peer, peer_rx, pending, ct).await)
 }
 
 macro_rules! method {
@@ -258,7 +351,7 @@ macro_rules! method {
                     }))
                     .await?;
                 match result {
-                    ClientResult::EmptyResult(_) => Ok(()),
+                    ClientResult::EmptyResult(_) | ClientResult::CustomResult(_) => Ok(()),
                     _ => Err(ServiceError::UnexpectedResponse),
                 }
             }
@@ -289,9 +382,118 @@ macro_rules! method {
 }
 
 impl Peer<RoleServer> {
-    method!(peer_req create_message CreateMessageRequest(CreateMessageRequestParam) => CreateMessageResult);
+    /// The protocol version negotiated with the client
+    pub fn protocol_version(&self) -> &ProtocolVersion {
+        &self.peer_info().protocol_version
+    }
+
+    /// The capabilities the client advertised in the handshake
+    pub fn peer_capabilities(&self) -> &ClientCapabilities {
+        &self.peer_info().capabilities
+    }
+
+    /// Ask the client's LLM for a message, `sampling/createMessage`
+    ///
+    /// Fails without sending the request if the client didn't advertise the sampling capability.
+    pub async fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+    ) -> Result<CreateMessageResult, ErrorData> {
+        if !self.peer_info().capabilities.supports_sampling() {
+            return Err(ErrorData::invalid_request(
+                "client doesn't support sampling",
+                Some(serde_json::json!({ "capability": "sampling" })),
+            ));
+        }
+        let result = self
+            .send_request(ServerRequest::CreateMessageRequest(CreateMessageRequest {
+                method: Default::default(),
+                params,
+                extensions: Default::default(),
+            }))
+            .await;
+        match result {
+            Ok(ClientResult::CreateMessageResult(result)) => Ok(result),
+            Ok(_) => Err(ErrorData::internal_error(
+                "unexpected response to sampling/createMessage",
+                None,
+            )),
+            Err(ServiceError::McpError(error)) => Err(error),
+            Err(error) => Err(ErrorData::internal_error(error.to_string(), None)),
+        }
+    }
     method!(peer_req list_roots ListRootsRequest() => ListRootsResult);
 
+    /// Ask the user for structured input through the client, `elicitation/create`
+    ///
+    /// Fails without sending the request if the client didn't advertise the elicitation capability.
+    pub async fn create_elicitation(
+        &self,
+        params: CreateElicitationRequestParam,
+    ) -> Result<CreateElicitationResult, ErrorData> {
+        if !self.peer_info().capabilities.supports_elicitation() {
+            return Err(ErrorData::invalid_request(
+                "client doesn't support elicitation",
+                Some(serde_json::json!({ "capability": "elicitation" })),
+            ));
+        }
+        let result = self
+            .send_request(ServerRequest::CreateElicitationRequest(
+                CreateElicitationRequest {
+                    method: Default::default(),
+                    params,
+                    extensions: Default::default(),
+                },
+            ))
+            .await;
+        match result {
+            Ok(ClientResult::CreateElicitationResult(result)) => Ok(result),
+            Ok(_) => Err(ErrorData::internal_error(
+                "unexpected response to elicitation/create",
+                None,
+            )),
+            Err(ServiceError::McpError(error)) => Err(error),
+            Err(error) => Err(ErrorData::internal_error(error.to_string(), None)),
+        }
+    }
+
+    /// Ask the user for a `T` through the client, with the schema of `T` as the requested schema
+    ///
+    /// The user declining or cancelling is an outcome, not an error.
+    pub async fn elicit<T>(
+        &self,
+        message: impl Into<String>,
+    ) -> Result<ElicitationOutcome<T>, ErrorData>
+    where
+        T: schemars::JsonSchema + serde::de::DeserializeOwned + 'static,
+    {
+        let mut requested_schema =
+            (*crate::handler::server::tool::cached_schema_for_type::<T>()).clone();
+        // the requested schema is a flat object, it has no use for a title
+        requested_schema.remove("title");
+        let result = self
+            .create_elicitation(CreateElicitationRequestParam {
+                message: message.into(),
+                requested_schema,
+            })
+            .await?;
+        match result.action {
+            ElicitationAction::Accept => {
+                let content = result.content.unwrap_or_default();
+                serde_json::from_value(serde_json::Value::Object(content))
+                    .map(ElicitationOutcome::Accepted)
+                    .map_err(|error| {
+                        ErrorData::invalid_params(
+                            format!("elicitation content doesn't match the schema: {error}"),
+                            None,
+                        )
+                    })
+            }
+            ElicitationAction::Decline => Ok(ElicitationOutcome::Declined),
+            ElicitationAction::Cancel => Ok(ElicitationOutcome::Cancelled),
+        }
+    }
+
     method!(peer_not notify_cancelled CancelledNotification(CancelledNotificationParam));
     method!(peer_not notify_progress ProgressNotification(ProgressNotificationParam));
     method!(peer_not notify_logging_message LoggingMessageNotification(LoggingMessageNotificationParam));
@@ -299,4 +501,11 @@ impl Peer<RoleServer> {
     method!(peer_not notify_resource_list_changed ResourceListChangedNotification);
     method!(peer_not notify_tool_list_changed ToolListChangedNotification);
     method!(peer_not notify_prompt_list_changed PromptListChangedNotification);
+
+    /// Whether the server advertises the logging capability, `get_info` is only called once
+    pub(crate) fn logging_enabled(&self, get_info: impl FnOnce() -> ServerInfo) -> bool {
+        *self
+            .logging_enabled
+            .get_or_init(|| get_info().capabilities.logging.is_some())
+    }
 }
@@ -0,0 +1,112 @@
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::model::Extensions;
+
+/// Values attached to one connection, shared by every request of the session
+///
+/// Each running service has its own, reachable through [`Peer::session`] and
+/// [`RequestContext::session`], and the tools of a server can extract a value with
+/// [`Session`](crate::handler::server::tool::Session). A server handler sets it up in
+/// [`ServerHandler::on_session_initialized`] and tears it down in [`ServerHandler::on_disconnect`],
+/// the values are dropped once the connection is closed.
+///
+/// Clones share the values.
+///
+/// [`Peer::session`]: super::Peer::session
+/// [`RequestContext::session`]: super::RequestContext::session
+/// [`ServerHandler::on_session_initialized`]: crate::ServerHandler::on_session_initialized
+/// [`ServerHandler::on_disconnect`]: crate::ServerHandler::on_disconnect
+#[derive(Clone, Default)]
+pub struct SessionData {
+    values: Arc<RwLock<Extensions>>,
+}
+
+impl std::fmt::Debug for SessionData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionData")
+            .field("len", &self.read().len())
+            .finish()
+    }
+}
+
+impl SessionData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a value, returning the previous value of the same type
+    pub fn insert<T: Clone + Send + Sync + 'static>(&self, value: T) -> Option<T> {
+        self.write().insert(value)
+    }
+
+    /// Clone the value of type `T`
+    pub fn get<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        self.read().get::<T>().cloned()
+    }
+
+    /// Update the value of type `T` in place, returning `None` if there is none
+    pub fn update<T: Send + Sync + 'static, U>(&self, f: impl FnOnce(&mut T) -> U) -> Option<U> {
+        self.write().get_mut::<T>().map(f)
+    }
+
+    pub fn remove<T: Send + Sync + 'static>(&self) -> Option<T> {
+        self.write().remove::<T>()
+    }
+
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.read().get::<T>().is_some()
+    }
+
+    /// Drop every value
+    pub fn clear(&self) {
+        self.write().clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Extensions> {
+        self.values.read().expect("session data lock poisoned")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Extensions> {
+        self.values.write().expect("session data lock poisoned")
+    }
+}
+
+/// The authenticated principal of a connection
+///
+/// A network transport that authenticates its clients, such as the
+/// [`AuthnLayer`](crate::transport::authn::AuthnLayer) of the HTTP servers, attaches it to
+/// every message. The server copies the one of the initialize request into the
+/// [`SessionData`], so tools can take it as `Extension<Identity>` or `Session<Identity>`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Identity {
+    /// Who the client is, e.g. the user of a token or the name of an API key
+    pub subject: String,
+    /// What the client may do
+    pub scopes: Vec<String>,
+}
+
+impl Identity {
+    pub fn new(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn with_scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
@@ -0,0 +1,70 @@
+use std::{borrow::Cow, sync::Arc};
+
+use super::ServiceRole;
+
+/// Where a running service is in its lifecycle
+///
+/// Followed with [`Peer::state_watch`](super::Peer::state_watch), on the client as on each
+/// session of a server. The transport reports [`Degraded`](ConnectionState::Degraded) while it
+/// keeps serving with trouble, see [`TransportStatus`](crate::transport::TransportStatus), and
+/// the last state is always [`Closed`](ConnectionState::Closed).
+#[derive(Debug, Clone)]
+pub enum ConnectionState<R: ServiceRole> {
+    /// Before the serve loop starts
+    Initializing,
+    /// Serving, with the info the peer sent at initialization
+    Ready(R::PeerInfo),
+    /// Still serving, but the transport is struggling
+    Degraded(Cow<'static, str>),
+    Closed(QuitReason),
+}
+
+impl<R: ServiceRole> ConnectionState<R> {
+    pub fn is_ready(&self) -> bool {
+        matches!(self, ConnectionState::Ready(_))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        matches!(self, ConnectionState::Closed(_))
+    }
+}
+
+/// The peer broke the protocol, so the transport can't go on
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{reason}")]
+pub struct ProtocolError {
+    pub reason: String,
+    /// What was received, maybe cut short
+    pub payload: Option<String>,
+}
+
+impl ProtocolError {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+            payload: None,
+        }
+    }
+
+    pub fn with_payload(mut self, payload: impl Into<String>) -> Self {
+        self.payload = Some(payload.into());
+        self
+    }
+}
+
+/// Why the serve loop of a running service quit
+#[derive(Debug, Clone)]
+pub enum QuitReason {
+    /// Cancelled, or a graceful shutdown passed its deadline
+    Cancelled,
+    /// The transport reached its end
+    Closed,
+    /// A graceful shutdown finished the in-flight requests, see
+    /// [`RunningService::shutdown_graceful`](super::RunningService::shutdown_graceful)
+    Graceful,
+    /// The transport failed on what the peer sent
+    ProtocolError(ProtocolError),
+    /// The peer didn't answer the keepalive pings, see [`RunningService::enable_keepalive`](super::RunningService::enable_keepalive)
+    Unresponsive,
+    JoinError(Arc<tokio::task::JoinError>),
+}
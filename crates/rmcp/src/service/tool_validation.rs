@@ -0,0 +1,176 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use super::{Peer, RoleClient, ServiceError, SessionData};
+use crate::model::{
+    CallToolRequestParam, ConstString, ToolListChangedNotificationMethod, resolvable_schema,
+};
+
+/// The arguments of a tool call not matching the input schema of the tool
+#[derive(Debug, Clone, thiserror::Error)]
+#[error(
+    "arguments of the tool {tool} don't match its input schema: {}",
+    join_errors(errors)
+)]
+pub struct SchemaViolation {
+    pub tool: String,
+    pub errors: Vec<SchemaViolationError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{path}: {message}")]
+pub struct SchemaViolationError {
+    /// The json pointer of the failing value in the arguments, empty for the arguments themselves
+    pub path: String,
+    pub message: String,
+}
+
+fn join_errors(errors: &[SchemaViolationError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+type Validators = HashMap<String, Arc<jsonschema::Validator>>;
+
+#[derive(Default)]
+struct CacheState {
+    validators: Option<Arc<Validators>>,
+    // bumped by each invalidation, so a listing racing with one isn't cached
+    generation: u64,
+}
+
+/// The validators of the input schemas of the tools of a server
+///
+/// Installed in the session of a client with [`Peer::enable_tool_validation`], it lists the
+/// tools on the first call and checks the arguments of every call against their schema,
+/// failing with [`ServiceError::SchemaViolation`] without sending the request. The tools are
+/// listed again after `notifications/tools/list_changed`. Calls of tools missing from the list,
+/// or whose schema doesn't compile, are sent as they are.
+#[derive(Clone, Default)]
+pub struct ToolSchemaCache {
+    state: Arc<Mutex<CacheState>>,
+}
+
+impl std::fmt::Debug for ToolSchemaCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state();
+        f.debug_struct("ToolSchemaCache")
+            .field(
+                "tools",
+                &state.validators.as_ref().map(|validators| validators.len()),
+            )
+            .field("generation", &state.generation)
+            .finish()
+    }
+}
+
+impl ToolSchemaCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the schemas, they are listed again before the next call
+    pub fn invalidate(&self) {
+        let mut state = self.state();
+        state.validators = None;
+        state.generation += 1;
+    }
+
+    // called by the service loop with the method of every notification received
+    pub(crate) fn on_notification(session: &SessionData, method: &str) {
+        if method == ToolListChangedNotificationMethod::VALUE {
+            if let Some(cache) = session.get::<Self>() {
+                cache.invalidate();
+            }
+        }
+    }
+
+    /// Check the arguments of a call, listing the tools of `peer` if they aren't cached
+    pub async fn validate(
+        &self,
+        peer: &Peer<RoleClient>,
+        params: &CallToolRequestParam,
+    ) -> Result<(), ServiceError> {
+        let validators = self.validators(peer).await?;
+        let Some(validator) = validators.get(params.name.as_ref()) else {
+            return Ok(());
+        };
+        let arguments =
+            serde_json::Value::Object(params.arguments.as_deref().cloned().unwrap_or_default());
+        let errors = validator
+            .iter_errors(&arguments)
+            .map(|error| SchemaViolationError {
+                path: error.instance_path.to_string(),
+                message: error.to_string(),
+            })
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ServiceError::SchemaViolation(SchemaViolation {
+                tool: params.name.to_string(),
+                errors,
+            }))
+        }
+    }
+
+    async fn validators(&self, peer: &Peer<RoleClient>) -> Result<Arc<Validators>, ServiceError> {
+        let generation = {
+            let state = self.state();
+            if let Some(validators) = &state.validators {
+                return Ok(validators.clone());
+            }
+            state.generation
+        };
+        let validators = peer
+            .list_all_tools()
+            .await?
+            .into_iter()
+            .filter_map(|tool| {
+                let schema = resolvable_schema(tool.input_schema.as_ref().clone());
+                match jsonschema::validator_for(&schema) {
+                    Ok(validator) => Some((tool.name.into_owned(), Arc::new(validator))),
+                    Err(error) => {
+                        tracing::warn!(tool = %tool.name, %error, "invalid input schema, calls are not validated");
+                        None
+                    }
+                }
+            })
+            .collect::<Validators>();
+        let validators = Arc::new(validators);
+        let mut state = self.state();
+        if state.generation == generation {
+            state.validators = Some(validators.clone());
+        }
+        Ok(validators)
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().expect("tool schema cache lock poisoned")
+    }
+}
+
+impl Peer<RoleClient> {
+    /// Validate the arguments of the tool calls of this session against the input schemas
+    ///
+    /// Every clone of the peer validates its calls, see [`ToolSchemaCache`].
+    pub fn enable_tool_validation(&self) {
+        if !self.session().contains::<ToolSchemaCache>() {
+            self.session().insert(ToolSchemaCache::new());
+        }
+    }
+
+    /// Send the tool calls of this session without validating them
+    pub fn disable_tool_validation(&self) {
+        self.session().remove::<ToolSchemaCache>();
+    }
+
+    pub fn is_tool_validation_enabled(&self) -> bool {
+        self.session().contains::<ToolSchemaCache>()
+    }
+}
@@ -2,13 +2,16 @@
 //! The transport type must implemented [`Transport`] trait, which allow it send message concurrently and receive message sequentially.
 //！
 //! ## Standard Transport Types
-//! There are 3 pairs of standard transport types:
+//! There are 6 pairs of standard transport types:
 //!
 //! | transport         | client                                                    | server                                                |
 //! |:-:                |:-:                                                        |:-:                                                    |
 //! | std IO            | [`child_process::TokioChildProcess`]                      | [`io::stdio`]                                         |
 //! | streamable http   | [`streamable_http_client::StreamableHttpClientTransport`] | [`streamable_http_server::session::create_session`]   |
 //! | sse               | [`sse_client::SseClientTransport`]                        | [`sse_server::SseServer`]                             |
+//! | websocket         | [`ws::connect`]                                           | [`ws::serve_upgrade`]                                 |
+//! | local socket      | [`unix_socket::connect_client`]                           | [`unix_socket::serve_server`]                         |
+//! | tcp               | [`tcp::connect_client`]                                   | [`tcp::serve_server`]                                 |
 //!
 //！## Helper Transport Types
 //! Thers are several helper transport types that can help you to create transport quickly.
@@ -28,6 +31,20 @@
 //!
 //! This could be very helpful when you want to create a transport from a duplex object stream, such as a websocket connection.
 //!
+//! ### [In-Process Transport](`in_process::pair`)
+//! A pair of connected transports in the same process, with configurable latency, drops and corruption.
+//!
+//! This could be very helpful in tests, or to embed a server in its client.
+//!
+//! ## Custom Transports
+//! Any carrier of messages, a message bus or a queue, can carry MCP: either implement
+//! [`Transport`] for it, or turn it into a [`futures::Sink`] of the messages sent and a
+//! [`futures::Stream`] of the messages received, which are a transport through
+//! [`IntoTransport`]. A client sends [`ClientJsonRpcMessage`](crate::model::ClientJsonRpcMessage)s
+//! and receives [`ServerJsonRpcMessage`](crate::model::ServerJsonRpcMessage)s, a server the other
+//! way around. See [`Transport`] for what the services expect of it, and
+//! [`conformance::check_transport`] to test a transport against it.
+//!
 //! ## [IntoTransport](`IntoTransport`) trait
 //! [`IntoTransport`] is a helper trait that implicitly convert a type into a transport type.
 //!
@@ -64,10 +81,20 @@
 //! }
 //! ```
 
-use crate::service::{RxJsonRpcMessage, ServiceRole, TxJsonRpcMessage};
+use std::borrow::Cow;
+
+use crate::service::{ProtocolError, RxJsonRpcMessage, ServiceRole, TxJsonRpcMessage};
 
 pub mod sink_stream;
 
+#[cfg(all(feature = "client", feature = "server"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "client", feature = "server"))))]
+pub mod in_process;
+
+#[cfg(all(feature = "client", feature = "server"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "client", feature = "server"))))]
+pub mod conformance;
+
 #[cfg(feature = "transport-async-rw")]
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-async-rw")))]
 pub mod async_rw;
@@ -84,7 +111,9 @@ pub use worker::WorkerTransport;
 pub mod child_process;
 #[cfg(feature = "transport-child-process")]
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-child-process")))]
-pub use child_process::{ConfigureCommandExt, TokioChildProcess};
+pub use child_process::{
+    ChildProcessBuilder, ConfigureCommandExt, StderrLines, StderrPolicy, TokioChildProcess,
+};
 
 #[cfg(feature = "transport-io")]
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-io")))]
@@ -93,6 +122,20 @@ pub mod io;
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-io")))]
 pub use io::stdio;
 
+#[cfg(feature = "transport-unix-socket")]
+#[cfg_attr(docsrs, doc(cfg(feature = "transport-unix-socket")))]
+pub mod unix_socket;
+#[cfg(feature = "transport-unix-socket")]
+#[cfg_attr(docsrs, doc(cfg(feature = "transport-unix-socket")))]
+pub use unix_socket::UnixSocketServer;
+
+#[cfg(feature = "transport-tcp")]
+#[cfg_attr(docsrs, doc(cfg(feature = "transport-tcp")))]
+pub mod tcp;
+#[cfg(feature = "transport-tcp")]
+#[cfg_attr(docsrs, doc(cfg(feature = "transport-tcp")))]
+pub use tcp::TcpServer;
+
 #[cfg(feature = "transport-sse-client")]
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-sse-client")))]
 pub mod sse_client;
@@ -100,6 +143,25 @@ pub mod sse_client;
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-sse-client")))]
 pub use sse_client::SseClientTransport;
 
+#[cfg(all(
+    feature = "__reqwest",
+    any(
+        feature = "transport-sse-client",
+        feature = "transport-streamable-http-client"
+    )
+))]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+pub mod http_client;
+#[cfg(all(
+    feature = "__reqwest",
+    any(
+        feature = "transport-sse-client",
+        feature = "transport-streamable-http-client"
+    )
+))]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+pub use http_client::{HttpClient, HttpClientBuilder};
+
 #[cfg(feature = "transport-sse-server")]
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-sse-server")))]
 pub mod sse_server;
@@ -107,6 +169,19 @@ pub mod sse_server;
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-sse-server")))]
 pub use sse_server::SseServer;
 
+#[cfg(any(
+    feature = "transport-streamable-http-server",
+    feature = "transport-sse-server"
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(
+        feature = "transport-streamable-http-server",
+        feature = "transport-sse-server"
+    )))
+)]
+pub mod authn;
+
 #[cfg(feature = "auth")]
 #[cfg_attr(docsrs, doc(cfg(feature = "auth")))]
 pub mod auth;
@@ -114,12 +189,12 @@ pub mod auth;
 #[cfg_attr(docsrs, doc(cfg(feature = "auth")))]
 pub use auth::{AuthError, AuthorizationManager, AuthorizationSession, AuthorizedHttpClient};
 
-// #[cfg(feature = "transport-ws")]
-// #[cfg_attr(docsrs, doc(cfg(feature = "transport-ws")))]
-// pub mod ws;
 #[cfg(feature = "transport-streamable-http-server-session")]
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-streamable-http-server-session")))]
 pub mod streamable_http_server;
+#[cfg(feature = "transport-ws")]
+#[cfg_attr(docsrs, doc(cfg(feature = "transport-ws")))]
+pub mod ws;
 #[cfg(feature = "transport-streamable-http-server")]
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-streamable-http-server")))]
 pub use streamable_http_server::axum::StreamableHttpServer;
@@ -131,9 +206,33 @@ pub mod streamable_http_client;
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-streamable-http-client")))]
 pub use streamable_http_client::StreamableHttpClientTransport;
 
+#[cfg(feature = "transport-reconnect")]
+#[cfg_attr(docsrs, doc(cfg(feature = "transport-reconnect")))]
+pub mod reconnect;
+#[cfg(feature = "transport-reconnect")]
+#[cfg_attr(docsrs, doc(cfg(feature = "transport-reconnect")))]
+pub use reconnect::{ConnectionState, ReconnectPolicy, ReconnectingTransport};
+
 /// Common use codes
 pub mod common;
 
+/// Carries the messages of a service to its peer, and the messages of the peer back
+///
+/// What the service layer expects:
+///
+/// - **Order.** [`send`](Transport::send) is called in the order of the messages, but the
+///   returned futures run concurrently on other tasks. Messages whose sends complete one after
+///   the other must arrive in that order; a transport keeping the order of concurrent sends must
+///   queue the message in `send` itself rather than in the future.
+/// - **Backpressure.** A pending send holds back its own message only: the service keeps
+///   receiving while it waits, so `receive` must not wait for the sends. The responses of the
+///   handlers wait for a bounded queue, a peer that stops reading ends up slowing the handlers
+///   down.
+/// - **Cancellation.** `receive` is raced against the other events of the service, its future is
+///   dropped when another one comes first. Dropping it must not lose a message.
+/// - **End.** `receive` returns `None` once the peer is gone, and isn't called anymore. Closing or
+///   dropping the transport must end the input of the peer.
+/// - [`close`](Transport::close) is called once, when the service quits.
 pub trait Transport<R>: Send
 where
     R: ServiceRole,
@@ -150,10 +249,31 @@ where
     ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static;
 
     /// Receive a message from the transport, this operation is sequential.
+    ///
+    /// Must be cancel safe, see [`Transport`].
     fn receive(&mut self) -> impl Future<Output = Option<RxJsonRpcMessage<R>>> + Send;
 
     /// Close the transport
     fn close(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Follow the health of the transport, `None` if it doesn't report any
+    ///
+    /// The serve loop turns it into the [`ConnectionState`](crate::service::ConnectionState)
+    /// of the service.
+    fn status(&self) -> Option<tokio::sync::watch::Receiver<TransportStatus>> {
+        None
+    }
+}
+
+/// The health of a transport, see [`Transport::status`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum TransportStatus {
+    #[default]
+    Connected,
+    /// Still working, e.g. skipping invalid messages or reconnecting
+    Degraded(Cow<'static, str>),
+    /// Set right before the transport ends its input because of the peer
+    Failed(ProtocolError),
 }
 
 pub trait IntoTransport<R, E, A>: Send + 'static
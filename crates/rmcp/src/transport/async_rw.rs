@@ -2,19 +2,22 @@ use std::{marker::PhantomData, sync::Arc};
 
 // use crate::schema::*;
 use futures::{SinkExt, StreamExt};
-use serde::{Serialize, de::DeserializeOwned};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use thiserror::Error;
 use tokio::{
     io::{AsyncRead, AsyncWrite},
-    sync::Mutex,
+    sync::{Mutex, watch},
 };
 use tokio_util::{
     bytes::{Buf, BufMut, BytesMut},
     codec::{Decoder, Encoder, FramedRead, FramedWrite},
 };
 
-use super::{IntoTransport, Transport};
-use crate::service::{RxJsonRpcMessage, ServiceRole, TxJsonRpcMessage};
+use super::{IntoTransport, Transport, TransportStatus};
+use crate::{
+    model::{ErrorData, RequestId, is_known_request_method},
+    service::{ProtocolError, RxJsonRpcMessage, ServiceRole, TxJsonRpcMessage},
+};
 
 pub enum TransportAdapterAsyncRW {}
 
@@ -43,8 +46,11 @@ where
 }
 
 pub struct AsyncRwTransport<Role: ServiceRole, R: AsyncRead, W: AsyncWrite> {
-    read: FramedRead<R, JsonRpcMessageCodec<RxJsonRpcMessage<Role>>>,
+    read: FramedRead<R, InboundCodec<RxJsonRpcMessage<Role>>>,
     write: Arc<Mutex<FramedWrite<W, JsonRpcMessageCodec<TxJsonRpcMessage<Role>>>>>,
+    status: watch::Sender<TransportStatus>,
+    /// The invalid lines skipped when the status was last updated
+    reported_invalid_messages: usize,
 }
 
 impl<Role: ServiceRole, R, W> AsyncRwTransport<Role, R, W>
@@ -55,13 +61,24 @@ where
     pub fn new(read: R, write: W) -> Self {
         let read = FramedRead::new(
             read,
-            JsonRpcMessageCodec::<RxJsonRpcMessage<Role>>::default(),
+            InboundCodec(JsonRpcMessageCodec::<RxJsonRpcMessage<Role>>::default()),
         );
         let write = Arc::new(Mutex::new(FramedWrite::new(
             write,
             JsonRpcMessageCodec::<TxJsonRpcMessage<Role>>::default(),
         )));
-        Self { read, write }
+        Self {
+            read,
+            write,
+            status: watch::Sender::default(),
+            reported_invalid_messages: 0,
+        }
+    }
+
+    /// Bound what is accepted from the peer, see [`CodecLimits`]
+    pub fn with_limits(mut self, limits: CodecLimits) -> Self {
+        *self.read.decoder_mut() = InboundCodec(JsonRpcMessageCodec::with_limits(limits));
+        self
     }
 }
 
@@ -107,29 +124,82 @@ where
         }
     }
 
-    fn receive(&mut self) -> impl Future<Output = Option<RxJsonRpcMessage<Role>>> {
-        let next = self.read.next();
-        async {
-            next.await.and_then(|e| {
-                e.inspect_err(|e| {
+    async fn receive(&mut self) -> Option<RxJsonRpcMessage<Role>> {
+        loop {
+            let next = self.read.next().await;
+            let skipped = self.read.decoder().0.invalid_messages;
+            if skipped > self.reported_invalid_messages {
+                self.reported_invalid_messages = skipped;
+                self.status.send_replace(TransportStatus::Degraded(
+                    format!("skipped {skipped} lines that are no JSON-RPC message").into(),
+                ));
+            }
+            return match next? {
+                Ok(Inbound::Message(message)) => Some(message),
+                Ok(Inbound::Invalid(id, error)) => {
+                    // the service never sees it, it is answered here
+                    let response = TxJsonRpcMessage::<Role>::error(error, id);
+                    if let Err(error) = self.send(response).await {
+                        tracing::error!(%error, "fail to answer an invalid request");
+                    }
+                    continue;
+                }
+                Err(e) => {
                     tracing::error!("Error reading from stream: {}", e);
-                })
-                .ok()
-            })
+                    if let Some(error) = e.protocol_error() {
+                        self.status.send_replace(TransportStatus::Failed(error));
+                    }
+                    None
+                }
+            };
         }
     }
 
     async fn close(&mut self) -> Result<(), Self::Error> {
         Ok(())
     }
+
+    fn status(&self) -> Option<watch::Receiver<TransportStatus>> {
+        Some(self.status.subscribe())
+    }
+}
+
+/// The default of [`CodecLimits::max_message_size`], 8 MiB
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 8 * 1024 * 1024;
+/// The default of [`CodecLimits::max_invalid_messages`]
+pub const DEFAULT_MAX_INVALID_MESSAGES: usize = 16;
+
+/// Bounds of what [`JsonRpcMessageCodec`] accepts from the peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodecLimits {
+    /// The size of the longest line, a longer one ends the stream with
+    /// [`JsonRpcMessageCodecError::MaxLineLengthExceeded`]
+    pub max_message_size: usize,
+    /// How many lines that are no JSON-RPC message are skipped with a warning, the next
+    /// one ends the stream with [`JsonRpcMessageCodecError::TooManyInvalidMessages`]
+    pub max_invalid_messages: usize,
+}
+
+impl Default for CodecLimits {
+    fn default() -> Self {
+        Self {
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            max_invalid_messages: DEFAULT_MAX_INVALID_MESSAGES,
+        }
+    }
 }
 
+/// Newline delimited JSON
+///
+/// `\r\n` line endings, byte order marks and blank lines are tolerated when decoding.
 #[derive(Debug, Clone)]
 pub struct JsonRpcMessageCodec<T> {
     _marker: PhantomData<fn() -> T>,
     next_index: usize,
     max_length: usize,
     is_discarding: bool,
+    max_invalid_messages: usize,
+    invalid_messages: usize,
 }
 
 impl<T> Default for JsonRpcMessageCodec<T> {
@@ -140,12 +210,7 @@ impl<T> Default for JsonRpcMessageCodec<T> {
 
 impl<T> JsonRpcMessageCodec<T> {
     pub fn new() -> Self {
-        Self {
-            _marker: PhantomData,
-            next_index: 0,
-            max_length: usize::MAX,
-            is_discarding: false,
-        }
+        Self::with_limits(CodecLimits::default())
     }
 
     pub fn new_with_max_length(max_length: usize) -> Self {
@@ -155,33 +220,97 @@ impl<T> JsonRpcMessageCodec<T> {
         }
     }
 
+    pub fn with_limits(limits: CodecLimits) -> Self {
+        Self {
+            _marker: PhantomData,
+            next_index: 0,
+            max_length: limits.max_message_size,
+            is_discarding: false,
+            max_invalid_messages: limits.max_invalid_messages,
+            invalid_messages: 0,
+        }
+    }
+
     pub fn max_length(&self) -> usize {
         self.max_length
     }
-}
 
-fn without_carriage_return(s: &[u8]) -> &[u8] {
-    if let Some(&b'\r') = s.last() {
-        &s[..s.len() - 1]
-    } else {
-        s
+    /// Parse a line without its `\n`, `None` if it is skipped
+    fn decode_line(&mut self, line: &[u8]) -> Result<Option<Inbound<T>>, JsonRpcMessageCodecError>
+    where
+        T: DeserializeOwned,
+    {
+        let line = line.strip_prefix(BOM).unwrap_or(line).trim_ascii();
+        if line.is_empty() {
+            return Ok(None);
+        }
+        let error = match serde_json::from_slice(line) {
+            Ok(item) => return Ok(Some(Inbound::Message(item))),
+            Err(error) => error,
+        };
+        // a request the SDK knows with invalid params, rather than no JSON-RPC message
+        if let Some(invalid) = Inbound::invalid_request(line) {
+            tracing::warn!(%error, "invalid params of a request");
+            return Ok(Some(invalid));
+        }
+        if self.invalid_messages < self.max_invalid_messages {
+            self.invalid_messages += 1;
+            tracing::warn!(
+                %error,
+                skipped = self.invalid_messages,
+                "skip a line that is no JSON-RPC message"
+            );
+            return Ok(None);
+        }
+        Err(JsonRpcMessageCodecError::TooManyInvalidMessages {
+            count: self.invalid_messages + 1,
+            last: error,
+            excerpt: String::from_utf8_lossy(&line[..line.len().min(EXCERPT_SIZE)]).into_owned(),
+        })
     }
 }
 
+const BOM: &[u8] = "\u{feff}".as_bytes();
+/// How much of an invalid line is kept in [`JsonRpcMessageCodecError::TooManyInvalidMessages`]
+const EXCERPT_SIZE: usize = 256;
+
 #[derive(Debug, Error)]
 pub enum JsonRpcMessageCodecError {
-    #[error("max line length exceeded")]
-    MaxLineLengthExceeded,
+    #[error("message exceeds the max size of {max_length} bytes")]
+    MaxLineLengthExceeded { max_length: usize },
+    #[error("{count} lines are no JSON-RPC message, the last one: {last}")]
+    TooManyInvalidMessages {
+        count: usize,
+        last: serde_json::Error,
+        /// The start of the last line
+        excerpt: String,
+    },
     #[error("serde error {0}")]
     Serde(#[from] serde_json::Error),
     #[error("io error {0}")]
     Io(#[from] std::io::Error),
 }
 
+impl JsonRpcMessageCodecError {
+    /// The error ending the stream because of what the peer sent
+    pub fn protocol_error(&self) -> Option<ProtocolError> {
+        match self {
+            JsonRpcMessageCodecError::MaxLineLengthExceeded { .. } => {
+                Some(ProtocolError::new(self.to_string()))
+            }
+            JsonRpcMessageCodecError::TooManyInvalidMessages { excerpt, .. } => {
+                Some(ProtocolError::new(self.to_string()).with_payload(excerpt.clone()))
+            }
+            JsonRpcMessageCodecError::Serde(_) | JsonRpcMessageCodecError::Io(_) => None,
+        }
+    }
+}
+
 impl From<JsonRpcMessageCodecError> for std::io::Error {
     fn from(value: JsonRpcMessageCodecError) -> Self {
         match value {
-            JsonRpcMessageCodecError::MaxLineLengthExceeded => {
+            JsonRpcMessageCodecError::MaxLineLengthExceeded { .. }
+            | JsonRpcMessageCodecError::TooManyInvalidMessages { .. } => {
                 std::io::Error::new(std::io::ErrorKind::InvalidData, value)
             }
             JsonRpcMessageCodecError::Serde(e) => e.into(),
@@ -190,15 +319,11 @@ impl From<JsonRpcMessageCodecError> for std::io::Error {
     }
 }
 
-impl<T: DeserializeOwned> Decoder for JsonRpcMessageCodec<T> {
-    type Item = T;
-
-    type Error = JsonRpcMessageCodecError;
-
-    fn decode(
+impl<T: DeserializeOwned> JsonRpcMessageCodec<T> {
+    fn decode_inbound(
         &mut self,
         buf: &mut BytesMut,
-    ) -> Result<Option<Self::Item>, JsonRpcMessageCodecError> {
+    ) -> Result<Option<Inbound<T>>, JsonRpcMessageCodecError> {
         loop {
             // Determine how far into the buffer we'll search for a newline. If
             // there's no max_length set, we'll read to the end of the buffer.
@@ -232,18 +357,18 @@ impl<T: DeserializeOwned> Decoder for JsonRpcMessageCodec<T> {
                     let newline_index = offset + self.next_index;
                     self.next_index = 0;
                     let line = buf.split_to(newline_index + 1);
-                    let line = &line[..line.len() - 1];
-                    let line = without_carriage_return(line);
-                    let item =
-                        serde_json::from_slice(line).map_err(JsonRpcMessageCodecError::Serde)?;
-                    return Ok(Some(item));
+                    if let Some(item) = self.decode_line(&line[..line.len() - 1])? {
+                        return Ok(Some(item));
+                    }
                 }
                 (false, None) if buf.len() > self.max_length => {
                     // Reached the maximum length without finding a
                     // newline, return an error and start discarding on the
                     // next call.
                     self.is_discarding = true;
-                    return Err(JsonRpcMessageCodecError::MaxLineLengthExceeded);
+                    return Err(JsonRpcMessageCodecError::MaxLineLengthExceeded {
+                        max_length: self.max_length,
+                    });
                 }
                 (false, None) => {
                     // We didn't find a line or reach the length limit, so the next
@@ -255,26 +380,95 @@ impl<T: DeserializeOwned> Decoder for JsonRpcMessageCodec<T> {
         }
     }
 
-    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, JsonRpcMessageCodecError> {
-        Ok(match self.decode(buf)? {
+    fn decode_eof_inbound(
+        &mut self,
+        buf: &mut BytesMut,
+    ) -> Result<Option<Inbound<T>>, JsonRpcMessageCodecError> {
+        Ok(match self.decode_inbound(buf)? {
             Some(frame) => Some(frame),
             None => {
                 self.next_index = 0;
                 // No terminating newline - return remaining data, if any
-                if buf.is_empty() || buf == &b"\r"[..] {
+                if self.is_discarding {
+                    buf.clear();
                     None
                 } else {
                     let line = buf.split_to(buf.len());
-                    let line = without_carriage_return(&line);
-                    let item =
-                        serde_json::from_slice(line).map_err(JsonRpcMessageCodecError::Serde)?;
-                    Some(item)
+                    self.decode_line(&line)?
                 }
             }
         })
     }
 }
 
+impl<T: DeserializeOwned> Decoder for JsonRpcMessageCodec<T> {
+    type Item = T;
+
+    type Error = JsonRpcMessageCodecError;
+
+    fn decode(
+        &mut self,
+        buf: &mut BytesMut,
+    ) -> Result<Option<Self::Item>, JsonRpcMessageCodecError> {
+        // the requests with invalid params are skipped, nothing answers them
+        while let Some(inbound) = self.decode_inbound(buf)? {
+            if let Inbound::Message(item) = inbound {
+                return Ok(Some(item));
+            }
+        }
+        Ok(None)
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, JsonRpcMessageCodecError> {
+        while let Some(inbound) = self.decode_eof_inbound(buf)? {
+            if let Inbound::Message(item) = inbound {
+                return Ok(Some(item));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// A line read from the peer
+enum Inbound<T> {
+    Message(T),
+    /// A request the SDK knows whose params don't parse, answered with this error
+    Invalid(RequestId, ErrorData),
+}
+
+impl<T> Inbound<T> {
+    /// The error answering `line` if it is a request of a known method, which didn't parse
+    fn invalid_request(line: &[u8]) -> Option<Self> {
+        #[derive(Deserialize)]
+        struct Envelope {
+            id: RequestId,
+            method: String,
+        }
+        let Envelope { id, method } = serde_json::from_slice(line).ok()?;
+        is_known_request_method(&method).then(|| {
+            let message = format!("invalid params for {method}");
+            Inbound::Invalid(id, ErrorData::invalid_params(message, None))
+        })
+    }
+}
+
+/// Decodes the requests with invalid params too, for [`AsyncRwTransport`] to answer them
+struct InboundCodec<T>(JsonRpcMessageCodec<T>);
+
+impl<T: DeserializeOwned> Decoder for InboundCodec<T> {
+    type Item = Inbound<T>;
+
+    type Error = JsonRpcMessageCodecError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Inbound<T>>, Self::Error> {
+        self.0.decode_inbound(buf)
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Inbound<T>>, Self::Error> {
+        self.0.decode_eof_inbound(buf)
+    }
+}
+
 impl<T: Serialize> Encoder<T> for JsonRpcMessageCodec<T> {
     type Error = JsonRpcMessageCodecError;
 
@@ -379,4 +573,139 @@ mod test {
         // Make sure there are no extra lines
         assert!(lines.next().is_none());
     }
+
+    fn decode_all(
+        codec: &mut JsonRpcMessageCodec<serde_json::Value>,
+        data: &[u8],
+    ) -> Result<Vec<serde_json::Value>, JsonRpcMessageCodecError> {
+        let mut buf = BytesMut::from(data);
+        let mut items = Vec::new();
+        while let Some(item) = codec.decode(&mut buf)? {
+            items.push(item);
+        }
+        items.extend(codec.decode_eof(&mut buf)?);
+        Ok(items)
+    }
+
+    #[test]
+    fn test_tolerated_framing() {
+        let data = "\u{feff}{\"id\":1}\r\n\r\n  \n\n\u{feff}{\"id\":2}\r\n{\"id\":3}";
+        let items = decode_all(&mut JsonRpcMessageCodec::default(), data.as_bytes()).unwrap();
+        assert_eq!(
+            items,
+            [
+                serde_json::json!({ "id": 1 }),
+                serde_json::json!({ "id": 2 }),
+                serde_json::json!({ "id": 3 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_messages_budget() {
+        let limits = CodecLimits {
+            max_invalid_messages: 2,
+            ..Default::default()
+        };
+        let mut codec = JsonRpcMessageCodec::with_limits(limits);
+        let mut buf =
+            BytesMut::from(&b"garbage\n{\"id\":1}\n{not json\n{\"id\":2}\n\xff\xfe\n"[..]);
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(serde_json::json!({ "id": 1 }))
+        );
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(serde_json::json!({ "id": 2 }))
+        );
+        assert!(matches!(
+            codec.decode(&mut buf),
+            Err(JsonRpcMessageCodecError::TooManyInvalidMessages { count: 3, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_max_message_size() {
+        use futures::StreamExt;
+
+        // a peer writing a line that never ends
+        let reader = tokio::io::repeat(b'x');
+        let codec = JsonRpcMessageCodec::<serde_json::Value>::new_with_max_length(1024);
+        let mut stream = FramedRead::new(reader, codec);
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            error,
+            JsonRpcMessageCodecError::MaxLineLengthExceeded { max_length: 1024 }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "message exceeds the max size of 1024 bytes"
+        );
+        assert!(stream.read_buffer().len() <= 1024 + 8 * 1024);
+    }
+
+    /// xorshift, to generate the same streams on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next_u64() % n as u64) as usize
+        }
+    }
+
+    #[test]
+    fn test_malformed_streams() {
+        const MAX_LENGTH: usize = 256;
+        const PIECES: &[&[u8]] = &[
+            b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}",
+            b"{\"jsonrpc\":",
+            b"\n",
+            b"\r\n",
+            b"\r",
+            b"\xef\xbb\xbf",
+            b"\xef\xbb",
+            b"   ",
+            b"}}}]]",
+            b"\xff\x00\x80",
+            b"null",
+        ];
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..500 {
+            let mut data = Vec::new();
+            for _ in 0..rng.below(64) {
+                if rng.below(8) == 0 {
+                    // a run of noise, sometimes longer than a message may be
+                    data.extend((0..rng.below(2 * MAX_LENGTH)).map(|_| rng.next_u64() as u8));
+                } else {
+                    data.extend_from_slice(PIECES[rng.below(PIECES.len())]);
+                }
+            }
+            let limits = CodecLimits {
+                max_message_size: MAX_LENGTH,
+                max_invalid_messages: rng.below(8),
+            };
+            let mut codec = JsonRpcMessageCodec::<serde_json::Value>::with_limits(limits);
+            let mut buf = BytesMut::new();
+            // fed in chunks of any size, the way a socket delivers them
+            'stream: for chunk in data.chunks(rng.below(64) + 1) {
+                buf.extend_from_slice(chunk);
+                loop {
+                    match codec.decode(&mut buf) {
+                        Ok(Some(_)) => {}
+                        Ok(None) => break,
+                        Err(_) => break 'stream,
+                    }
+                }
+                assert!(buf.len() <= MAX_LENGTH + chunk.len());
+            }
+            let _ = codec.decode_eof(&mut buf);
+        }
+    }
 }
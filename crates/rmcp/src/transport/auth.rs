@@ -1,9 +1,31 @@
+//! OAuth 2.1 authorization of the HTTP client transports
+//!
+//! An [`AuthorizationManager`] discovers the authorization server of an MCP server, registers
+//! the client when the server allows it, and runs the authorization code flow with PKCE. The
+//! browser part of the flow is left to an [`AuthorizationHandler`], and the tokens are kept by a
+//! [`CredentialStore`]. An [`AuthClient`] attaches the access token to every request of a
+//! transport, and on a 401 refreshes it, or authorizes again, before retrying once.
+//!
+//! ```rust,ignore
+//! let mut manager = AuthorizationManager::new("https://mcp.example.com/mcp").await?;
+//! manager.with_credential_store(FileStore::new("credentials.json"));
+//! manager.with_authorization_handler(|authorization_url: String| async move {
+//!     open_browser(&authorization_url)?;
+//!     wait_for_redirect().await
+//! });
+//! manager.authorize(&["mcp"], "http://localhost:8080/callback").await?;
+//! let transport = StreamableHttpClientTransport::with_client(
+//!     AuthClient::new(reqwest::Client::default(), manager),
+//!     StreamableHttpClientTransportConfig::with_uri("https://mcp.example.com/mcp"),
+//! );
+//! ```
 use std::{
     collections::HashMap,
     sync::Arc,
     time::{Duration, Instant},
 };
 
+use futures::future::BoxFuture;
 use oauth2::{
     AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, EmptyExtraTokenFields,
     PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, RefreshToken, Scope, StandardTokenResponse,
@@ -47,6 +69,27 @@ impl<C> AuthClient<C> {
         let auth_manager = self.auth_manager.clone();
         async move { auth_manager.lock().await.get_access_token().await }
     }
+
+    /// A new access token, after the server refused `rejected_token`
+    pub fn reauthorize(
+        &self,
+        rejected_token: String,
+    ) -> impl Future<Output = Result<String, AuthError>> + Send {
+        let auth_manager = self.auth_manager.clone();
+        async move { auth_manager.lock().await.reauthorize(&rejected_token).await }
+    }
+}
+
+/// Whether the server answered `error` with a 401, the token being expired or revoked
+#[cfg(any(
+    feature = "transport-sse-client",
+    feature = "transport-streamable-http-client"
+))]
+pub(crate) fn is_unauthorized(error: &(dyn std::error::Error + 'static)) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .and_then(reqwest::Error::status)
+        == Some(StatusCode::UNAUTHORIZED)
 }
 
 /// Auth error
@@ -100,6 +143,8 @@ pub enum AuthError {
 pub struct AuthorizationMetadata {
     pub authorization_endpoint: String,
     pub token_endpoint: String,
+    /// empty when the server doesn't support dynamic client registration
+    #[serde(default)]
     pub registration_endpoint: String,
     pub issuer: Option<String>,
     pub jwks_uri: Option<String>,
@@ -109,6 +154,16 @@ pub struct AuthorizationMetadata {
     pub additional_fields: HashMap<String, serde_json::Value>,
 }
 
+/// The metadata of an OAuth protected resource, as defined by RFC 9728
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProtectedResourceMetadata {
+    pub resource: Option<String>,
+    #[serde(default)]
+    pub authorization_servers: Vec<String>,
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, serde_json::Value>,
+}
+
 /// oauth2 client config
 #[derive(Debug, Clone)]
 pub struct OAuthClientConfig {
@@ -139,6 +194,127 @@ type OAuthClient = oauth2::Client<
 >;
 type Credentials = (String, Option<OAuthTokenResponse>);
 
+/// The registered client and its tokens, kept between two runs of an application
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredCredentials {
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub token_response: Option<OAuthTokenResponse>,
+}
+
+/// Keeps the credentials of an [`AuthorizationManager`], saved every time they change
+pub trait CredentialStore: Send + Sync + 'static {
+    fn load(
+        &self,
+    ) -> impl Future<Output = Result<Option<StoredCredentials>, AuthError>> + Send + '_;
+    fn save(
+        &self,
+        credentials: StoredCredentials,
+    ) -> impl Future<Output = Result<(), AuthError>> + Send + '_;
+    fn clear(&self) -> impl Future<Output = Result<(), AuthError>> + Send + '_;
+}
+
+trait DynCredentialStore: Send + Sync + 'static {
+    fn load(&self) -> BoxFuture<'_, Result<Option<StoredCredentials>, AuthError>>;
+    fn save(&self, credentials: StoredCredentials) -> BoxFuture<'_, Result<(), AuthError>>;
+    fn clear(&self) -> BoxFuture<'_, Result<(), AuthError>>;
+}
+
+impl<S: CredentialStore> DynCredentialStore for S {
+    fn load(&self) -> BoxFuture<'_, Result<Option<StoredCredentials>, AuthError>> {
+        Box::pin(CredentialStore::load(self))
+    }
+    fn save(&self, credentials: StoredCredentials) -> BoxFuture<'_, Result<(), AuthError>> {
+        Box::pin(CredentialStore::save(self, credentials))
+    }
+    fn clear(&self) -> BoxFuture<'_, Result<(), AuthError>> {
+        Box::pin(CredentialStore::clear(self))
+    }
+}
+
+/// Keeps the credentials for the lifetime of the process, the default store
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryCredentialStore {
+    credentials: Arc<RwLock<Option<StoredCredentials>>>,
+}
+
+impl InMemoryCredentialStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CredentialStore for InMemoryCredentialStore {
+    async fn load(&self) -> Result<Option<StoredCredentials>, AuthError> {
+        Ok(self.credentials.read().await.clone())
+    }
+
+    async fn save(&self, credentials: StoredCredentials) -> Result<(), AuthError> {
+        *self.credentials.write().await = Some(credentials);
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), AuthError> {
+        *self.credentials.write().await = None;
+        Ok(())
+    }
+}
+
+/// The parameters the authorization server redirected the user agent with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorizationCallback {
+    pub code: String,
+    pub state: String,
+}
+
+/// Sends the user to the authorization url, usually in a browser, and waits for the
+/// authorization server to redirect back
+pub trait AuthorizationHandler: Send + Sync + 'static {
+    fn authorize(
+        &self,
+        authorization_url: String,
+    ) -> impl Future<Output = Result<AuthorizationCallback, AuthError>> + Send + '_;
+}
+
+impl<F, Fut> AuthorizationHandler for F
+where
+    F: Fn(String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<AuthorizationCallback, AuthError>> + Send + 'static,
+{
+    fn authorize(
+        &self,
+        authorization_url: String,
+    ) -> impl Future<Output = Result<AuthorizationCallback, AuthError>> + Send + '_ {
+        self(authorization_url)
+    }
+}
+
+trait DynAuthorizationHandler: Send + Sync + 'static {
+    fn authorize(
+        &self,
+        authorization_url: String,
+    ) -> BoxFuture<'_, Result<AuthorizationCallback, AuthError>>;
+}
+
+impl<H: AuthorizationHandler> DynAuthorizationHandler for H {
+    fn authorize(
+        &self,
+        authorization_url: String,
+    ) -> BoxFuture<'_, Result<AuthorizationCallback, AuthError>> {
+        Box::pin(AuthorizationHandler::authorize(self, authorization_url))
+    }
+}
+
+/// `url` with `/.well-known/<suffix>` inserted before its path, as RFC 8414 and RFC 9728 do
+fn well_known_url(url: &Url, suffix: &str) -> Url {
+    let mut well_known = url.clone();
+    let path = url.path().trim_end_matches('/');
+    well_known.set_path(&format!("/.well-known/{suffix}{path}"));
+    well_known.set_query(None);
+    well_known.set_fragment(None);
+    well_known
+}
+
 /// oauth2 auth manager
 pub struct AuthorizationManager {
     http_client: HttpClient,
@@ -148,6 +324,12 @@ pub struct AuthorizationManager {
     pkce_verifier: RwLock<Option<PkceCodeVerifier>>,
     expires_at: RwLock<Option<Instant>>,
     base_url: Url,
+    client_config: Option<OAuthClientConfig>,
+    csrf_token: RwLock<Option<CsrfToken>>,
+    credential_store: Arc<dyn DynCredentialStore>,
+    authorization_handler: Option<Arc<dyn DynAuthorizationHandler>>,
+    /// the scopes given to [`AuthorizationManager::authorize`], to authorize again with
+    scopes: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -187,6 +369,11 @@ impl AuthorizationManager {
             pkce_verifier: RwLock::new(None),
             expires_at: RwLock::new(None),
             base_url,
+            client_config: None,
+            csrf_token: RwLock::new(None),
+            credential_store: Arc::new(InMemoryCredentialStore::new()),
+            authorization_handler: None,
+            scopes: Vec::new(),
         };
 
         Ok(manager)
@@ -197,11 +384,59 @@ impl AuthorizationManager {
         Ok(())
     }
 
+    /// keep the credentials in `store` instead of in memory
+    pub fn with_credential_store(&mut self, store: impl CredentialStore) {
+        self.credential_store = Arc::new(store);
+    }
+
+    /// let `handler` take the user through the authorization, needed by
+    /// [`AuthorizationManager::authorize`]
+    pub fn with_authorization_handler(&mut self, handler: impl AuthorizationHandler) {
+        self.authorization_handler = Some(Arc::new(handler));
+    }
+
+    /// find the authorization server in the protected resource metadata of the MCP server
+    async fn discover_authorization_server(&self) -> Option<Url> {
+        let mut root = self.base_url.clone();
+        root.set_path("/");
+        let candidates = [
+            well_known_url(&self.base_url, "oauth-protected-resource"),
+            well_known_url(&root, "oauth-protected-resource"),
+        ];
+        for discovery_url in candidates {
+            debug!("protected resource discovery url: {:?}", discovery_url);
+            let Ok(response) = self.http_client.get(discovery_url).send().await else {
+                continue;
+            };
+            if response.status() != StatusCode::OK {
+                continue;
+            }
+            let Ok(metadata) = response.json::<ProtectedResourceMetadata>().await else {
+                continue;
+            };
+            if let Some(server) = metadata.authorization_servers.first() {
+                match Url::parse(server) {
+                    Ok(server) => return Some(server),
+                    Err(e) => error!("invalid authorization server {server}: {e}"),
+                }
+            }
+        }
+        None
+    }
+
     /// discover oauth2 metadata
     pub async fn discover_metadata(&self) -> Result<AuthorizationMetadata, AuthError> {
-        // according to the specification, the metadata should be located at "/.well-known/oauth-authorization-server"
-        let mut discovery_url = self.base_url.clone();
-        discovery_url.set_path("/.well-known/oauth-authorization-server");
+        // the protected resource metadata names the authorization server, otherwise the MCP
+        // server is its own authorization server
+        let discovery_url = match self.discover_authorization_server().await {
+            Some(server) => well_known_url(&server, "oauth-authorization-server"),
+            None => {
+                // according to the specification, the metadata should be located at "/.well-known/oauth-authorization-server"
+                let mut discovery_url = self.base_url.clone();
+                discovery_url.set_path("/.well-known/oauth-authorization-server");
+                discovery_url
+            }
+        };
         debug!("discovery url: {:?}", discovery_url);
         let response = self
             .http_client
@@ -263,6 +498,7 @@ impl AuthorizationManager {
             .map_err(|e| AuthError::OAuthError(format!("Invalid token URL: {}", e)))?;
 
         // debug!("token url: {:?}", token_url);
+        self.client_config = Some(config.clone());
         let client_id = ClientId::new(config.client_id);
         let redirect_url = RedirectUrl::new(config.redirect_uri.clone())
             .map_err(|e| AuthError::OAuthError(format!("Invalid re URL: {}", e)))?;
@@ -396,10 +632,11 @@ impl AuthorizationManager {
             auth_request = auth_request.add_scope(Scope::new(scope.to_string()));
         }
 
-        let (auth_url, _csrf_token) = auth_request.url();
+        let (auth_url, csrf_token) = auth_request.url();
 
-        // store pkce verifier for later use
+        // store pkce verifier and csrf token for later use
         *self.pkce_verifier.write().await = Some(pkce_verifier);
+        *self.csrf_token.write().await = Some(csrf_token);
         debug!("set pkce verifier: {:?}", self.pkce_verifier.read().await);
 
         Ok(auth_url.to_string())
@@ -447,10 +684,119 @@ impl AuthorizationManager {
         debug!("exchange token result: {:?}", token_result);
         // store credentials
         *self.credentials.write().await = Some(token_result.clone());
+        self.save_credentials().await?;
 
         Ok(token_result)
     }
 
+    /// save the client and its tokens to the credential store
+    async fn save_credentials(&self) -> Result<(), AuthError> {
+        let Some(config) = &self.client_config else {
+            return Ok(());
+        };
+        let credentials = StoredCredentials {
+            client_id: config.client_id.clone(),
+            client_secret: config.client_secret.clone(),
+            token_response: self.credentials.read().await.clone(),
+        };
+        self.credential_store.save(credentials).await
+    }
+
+    /// Authorize the client, with the stored credentials if any, otherwise through the
+    /// authorization handler
+    ///
+    /// The metadata are discovered and the client registered when needed. The scopes are kept to
+    /// authorize again once the refresh token is revoked.
+    pub async fn authorize(
+        &mut self,
+        scopes: &[&str],
+        redirect_uri: &str,
+    ) -> Result<(), AuthError> {
+        self.scopes = scopes.iter().map(|scope| scope.to_string()).collect();
+        if self.metadata.is_none() {
+            self.metadata = Some(self.discover_metadata().await?);
+        }
+        if let Some(stored) = self.credential_store.load().await? {
+            debug!("use stored client {}", stored.client_id);
+            self.configure_client(OAuthClientConfig {
+                client_id: stored.client_id,
+                client_secret: stored.client_secret,
+                scopes: self.scopes.clone(),
+                redirect_uri: redirect_uri.to_string(),
+            })?;
+            if let Some(token_response) = stored.token_response {
+                // the expiry is unknown, an expired token is refreshed on its first 401
+                *self.credentials.write().await = Some(token_response);
+                *self.expires_at.write().await = None;
+                return Ok(());
+            }
+        } else if self.oauth_client.is_none() {
+            let supports_registration = self
+                .metadata
+                .as_ref()
+                .is_some_and(|metadata| !metadata.registration_endpoint.is_empty());
+            if !supports_registration {
+                return Err(AuthError::RegistrationFailed(
+                    "the server doesn't support dynamic client registration, configure a client id"
+                        .to_string(),
+                ));
+            }
+            self.register_client("MCP Client", redirect_uri).await?;
+            self.save_credentials().await?;
+        }
+        self.authorize_with_handler().await
+    }
+
+    /// run the authorization code flow through the authorization handler
+    async fn authorize_with_handler(&self) -> Result<(), AuthError> {
+        let handler = self
+            .authorization_handler
+            .clone()
+            .ok_or(AuthError::AuthorizationRequired)?;
+        let scopes = self.scopes.iter().map(String::as_str).collect::<Vec<_>>();
+        let authorization_url = self.get_authorization_url(&scopes).await?;
+        let callback = handler.authorize(authorization_url).await?;
+        let expected_state = self.csrf_token.write().await.take();
+        if expected_state.as_ref().map(|state| state.secret()) != Some(&callback.state) {
+            return Err(AuthError::AuthorizationFailed(
+                "the state of the callback doesn't match the authorization request".to_string(),
+            ));
+        }
+        self.exchange_code_for_token(&callback.code).await?;
+        Ok(())
+    }
+
+    /// A new access token, after the server refused `rejected_token`
+    ///
+    /// The token is refreshed, and when the refresh token is refused too, the client is
+    /// authorized again through the authorization handler.
+    pub async fn reauthorize(&self, rejected_token: &str) -> Result<String, AuthError> {
+        // another request may have got a new token already
+        if let Some(credentials) = self.credentials.read().await.as_ref() {
+            let current = credentials.access_token().secret();
+            if current != rejected_token {
+                return Ok(current.clone());
+            }
+        }
+        match self.refresh_token().await {
+            Ok(token_response) => return Ok(token_response.access_token().secret().to_string()),
+            Err(e) => debug!("refresh failed, authorize again: {e}"),
+        }
+        *self.credentials.write().await = None;
+        self.authorize_with_handler().await?;
+        self.get_access_token().await
+    }
+
+    /// Forget the tokens, here and in the credential store
+    ///
+    /// The client stays configured, the next [`authorize`](Self::authorize) goes through the
+    /// authorization handler.
+    pub async fn logout(&self) -> Result<(), AuthError> {
+        *self.credentials.write().await = None;
+        *self.expires_at.write().await = None;
+        self.credential_store.clear().await
+    }
+
     /// get access token, if expired, refresh it automatically
     pub async fn get_access_token(&self) -> Result<String, AuthError> {
         let credentials = self.credentials.read().await;
@@ -493,14 +839,19 @@ impl AuthorizationManager {
         })?;
         debug!("refresh token: {:?}", refresh_token);
         // refresh token
-        let token_result = oauth_client
+        let mut token_result = oauth_client
             .exchange_refresh_token(&RefreshToken::new(refresh_token.secret().to_string()))
             .request_async(&self.http_client)
             .await
             .map_err(|e| AuthError::TokenRefreshFailed(e.to_string()))?;
+        // the server may keep the refresh token
+        if token_result.refresh_token().is_none() {
+            token_result.set_refresh_token(Some(refresh_token.clone()));
+        }
 
         // store new credentials
         *self.credentials.write().await = Some(token_result.clone());
+        self.save_credentials().await?;
 
         // get expires_in from token response
         let expires_in = token_result.expires_in();
@@ -0,0 +1,341 @@
+//! Authentication of the clients of the HTTP servers
+//!
+//! [`AuthnLayer`] validates the headers of every request to the endpoints it wraps, rejects
+//! the request with `401`/`403` and a `WWW-Authenticate` challenge, or attaches the resulting
+//! [`Identity`] to it. The SSE and streamable HTTP servers pass the identity on to the
+//! messages and to the session, so tools can take it as `Extension<Identity>` or
+//! `Session<Identity>`.
+//!
+//! ```rust,no_run
+//! use rmcp::transport::{
+//!     authn::{ApiKeyValidator, AuthnLayer},
+//!     sse_server::SseServerConfig,
+//! };
+//! use rmcp::service::Identity;
+//!
+//! let authn = AuthnLayer::new(ApiKeyValidator::new().with_key("secret", Identity::new("alice")));
+//! let config = SseServerConfig::default().with_authn(authn);
+//! ```
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, Method, Request, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use tower_layer::Layer;
+use tower_service::Service;
+
+pub use crate::service::Identity;
+
+/// The header [`ApiKeyValidator`] reads besides `Authorization: Bearer`
+pub const HEADER_API_KEY: &str = "X-API-Key";
+
+/// Why a request is rejected
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum AuthnError {
+    /// The request carries no credentials, answered with `401`
+    #[error("missing credentials")]
+    Missing,
+    /// The credentials are unknown or expired, answered with `401`
+    #[error("invalid credentials: {0}")]
+    Invalid(String),
+    /// The credentials are valid but not enough for this server, answered with `403`
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+}
+
+impl AuthnError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AuthnError::Missing | AuthnError::Invalid(_) => StatusCode::UNAUTHORIZED,
+            AuthnError::Forbidden(_) => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+/// Checks the credentials in the headers of a request
+///
+/// Implemented by `Fn(&HeaderMap) -> Future<Output = Result<Identity, AuthnError>>`,
+/// the future can't borrow the headers.
+pub trait AuthnValidator: Send + Sync + 'static {
+    fn validate<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+    ) -> impl Future<Output = Result<Identity, AuthnError>> + Send + 'a;
+}
+
+impl<F, Fut> AuthnValidator for F
+where
+    F: Fn(&HeaderMap) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Identity, AuthnError>> + Send + 'static,
+{
+    fn validate<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+    ) -> impl Future<Output = Result<Identity, AuthnError>> + Send + 'a {
+        self(headers)
+    }
+}
+
+trait DynAuthnValidator: Send + Sync + 'static {
+    fn validate<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+    ) -> BoxFuture<'a, Result<Identity, AuthnError>>;
+}
+
+impl<V: AuthnValidator> DynAuthnValidator for V {
+    fn validate<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+    ) -> BoxFuture<'a, Result<Identity, AuthnError>> {
+        Box::pin(AuthnValidator::validate(self, headers))
+    }
+}
+
+/// A validator of static API keys
+///
+/// The key is read from `Authorization: Bearer <key>` or from the `X-API-Key` header.
+#[derive(Clone, Default)]
+pub struct ApiKeyValidator {
+    keys: HashMap<String, Identity>,
+}
+
+impl std::fmt::Debug for ApiKeyValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKeyValidator")
+            .field("keys", &self.keys.len())
+            .finish()
+    }
+}
+
+impl ApiKeyValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept `key` as `identity`
+    pub fn with_key(mut self, key: impl Into<String>, identity: Identity) -> Self {
+        self.keys.insert(key.into(), identity);
+        self
+    }
+
+    fn check(&self, headers: &HeaderMap) -> Result<Identity, AuthnError> {
+        let key = bearer_token(headers)
+            .or_else(|| headers.get(HEADER_API_KEY)?.to_str().ok())
+            .ok_or(AuthnError::Missing)?;
+        // compare every key in full, so the time taken doesn't tell how much of a key matched
+        self.keys
+            .iter()
+            .fold(None, |found, (candidate, identity)| {
+                if constant_time_eq(candidate.as_bytes(), key.as_bytes()) {
+                    Some(identity)
+                } else {
+                    found
+                }
+            })
+            .cloned()
+            .ok_or_else(|| AuthnError::Invalid("unknown API key".into()))
+    }
+}
+
+impl AuthnValidator for ApiKeyValidator {
+    fn validate<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+    ) -> impl Future<Output = Result<Identity, AuthnError>> + Send + 'a {
+        std::future::ready(self.check(headers))
+    }
+}
+
+/// The token of an `Authorization: Bearer <token>` header
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then(|| token.trim())
+        .filter(|token| !token.is_empty())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// A tower layer authenticating every request with an [`AuthnValidator`]
+///
+/// Set it on [`SseServerConfig`](crate::transport::sse_server::SseServerConfig) or
+/// [`StreamableHttpServerConfig`](crate::transport::streamable_http_server::StreamableHttpServerConfig)
+/// to protect both endpoints of a server, or add it to any axum router. `OPTIONS` requests
+/// pass through unauthenticated for CORS preflights.
+#[derive(Clone)]
+pub struct AuthnLayer {
+    validator: Arc<dyn DynAuthnValidator>,
+    realm: Arc<str>,
+    resource_metadata: Option<Arc<str>>,
+}
+
+impl std::fmt::Debug for AuthnLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthnLayer")
+            .field("realm", &self.realm)
+            .field("resource_metadata", &self.resource_metadata)
+            .finish()
+    }
+}
+
+impl AuthnLayer {
+    pub fn new(validator: impl AuthnValidator) -> Self {
+        Self {
+            validator: Arc::new(validator),
+            realm: "mcp".into(),
+            resource_metadata: None,
+        }
+    }
+
+    /// The realm of the `WWW-Authenticate` challenge, `mcp` by default
+    pub fn with_realm(mut self, realm: impl Into<String>) -> Self {
+        self.realm = realm.into().into();
+        self
+    }
+
+    /// Point the clients to the protected resource metadata of this server
+    ///
+    /// The url is sent as `resource_metadata` in the `WWW-Authenticate` challenge, so an
+    /// OAuth client can discover the authorization server.
+    pub fn with_resource_metadata(mut self, url: impl Into<String>) -> Self {
+        self.resource_metadata = Some(url.into().into());
+        self
+    }
+
+    fn challenge(&self, error: &AuthnError) -> String {
+        let mut challenge = format!("Bearer realm=\"{}\"", self.realm);
+        if let Some(url) = &self.resource_metadata {
+            challenge.push_str(&format!(", resource_metadata=\"{url}\""));
+        }
+        let (code, description) = match error {
+            AuthnError::Missing => return challenge,
+            AuthnError::Invalid(description) => ("invalid_token", description),
+            AuthnError::Forbidden(description) => ("insufficient_scope", description),
+        };
+        let description = description.replace(['"', '\\'], "");
+        challenge.push_str(&format!(
+            ", error=\"{code}\", error_description=\"{description}\""
+        ));
+        challenge
+    }
+
+    fn reject(&self, error: AuthnError) -> Response {
+        tracing::debug!(%error, "request rejected");
+        let mut response = (error.status(), error.to_string()).into_response();
+        if let Ok(challenge) = HeaderValue::from_str(&self.challenge(&error)) {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, challenge);
+        }
+        response
+    }
+}
+
+impl<S> Layer<S> for AuthnLayer {
+    type Service = Authn<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Authn {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// The service of [`AuthnLayer`]
+#[derive(Debug, Clone)]
+pub struct Authn<S> {
+    inner: S,
+    layer: AuthnLayer,
+}
+
+impl<S> Service<Request<Body>> for Authn<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<Body>) -> Self::Future {
+        // the ready service must be the one that is called
+        let ready = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, ready);
+        let layer = self.layer.clone();
+        Box::pin(async move {
+            if request.method() == Method::OPTIONS {
+                return inner.call(request).await;
+            }
+            let validated = layer.validator.validate(request.headers()).await;
+            match validated {
+                Ok(identity) => {
+                    request.extensions_mut().insert(identity);
+                    inner.call(request).await
+                }
+                Err(error) => Ok(layer.reject(error)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(name: &'static str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn api_key_from_either_header() {
+        let validator = ApiKeyValidator::new().with_key("secret", Identity::new("alice"));
+        let identity = validator
+            .check(&headers("authorization", "Bearer secret"))
+            .unwrap();
+        assert_eq!(identity.subject, "alice");
+        let identity = validator.check(&headers("x-api-key", "secret")).unwrap();
+        assert_eq!(identity.subject, "alice");
+        assert!(matches!(
+            validator.check(&headers("authorization", "Bearer secrets")),
+            Err(AuthnError::Invalid(_))
+        ));
+        assert!(matches!(
+            validator.check(&headers("authorization", "Basic secret")),
+            Err(AuthnError::Missing)
+        ));
+    }
+
+    #[test]
+    fn challenge_carries_error_and_metadata() {
+        let layer = AuthnLayer::new(ApiKeyValidator::new())
+            .with_resource_metadata("https://example.com/.well-known/oauth-protected-resource");
+        assert_eq!(
+            layer.challenge(&AuthnError::Missing),
+            "Bearer realm=\"mcp\", resource_metadata=\"https://example.com/.well-known/oauth-protected-resource\""
+        );
+        assert!(
+            layer
+                .challenge(&AuthnError::Forbidden("no \"admin\"".into()))
+                .ends_with("error=\"insufficient_scope\", error_description=\"no admin\"")
+        );
+    }
+}
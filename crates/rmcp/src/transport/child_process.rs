@@ -1,7 +1,24 @@
+//! Run a server as a child process, and talk with it over its stdin and stdout
+//!
+//! [`TokioChildProcess::new`] spawns a command as it is, while [`ChildProcessBuilder`] also
+//! configures the environment of the child, what happens to its stderr, and how it's stopped.
+//!
+//! ```rust,ignore
+//! let (transport, _) = ChildProcessBuilder::new("uvx")
+//!     .arg("mcp-server-git")
+//!     .env("GIT_DIR", "/path/to/repo/.git")
+//!     .on_stderr(|line| eprintln!("[git] {line}"))
+//!     .kill_grace_period(Duration::from_secs(1))
+//!     .spawn()?;
+//! let client = ().serve(transport).await?;
+//! ```
+use std::{ffi::OsStr, path::Path, sync::Arc, time::Duration};
+
+use futures::Stream;
 use process_wrap::tokio::{TokioChildWrapper, TokioCommandWrap};
 use tokio::{
-    io::AsyncRead,
-    process::{ChildStdin, ChildStdout},
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::{ChildStderr, ChildStdin, ChildStdout},
 };
 
 use super::{IntoTransport, Transport};
@@ -27,18 +44,60 @@ pub struct TokioChildProcess {
     child_stdout: ChildStdout,
 }
 
+/// How often a child given a grace period is checked for exit
+const CHILD_EXIT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
 pub struct ChildWithCleanup {
-    inner: Box<dyn TokioChildWrapper>,
+    inner: Option<Box<dyn TokioChildWrapper>>,
+    grace_period: Option<Duration>,
 }
 
-impl Drop for ChildWithCleanup {
-    fn drop(&mut self) {
-        if let Err(e) = self.inner.start_kill() {
+impl ChildWithCleanup {
+    fn kill(mut child: Box<dyn TokioChildWrapper>) {
+        if let Err(e) = child.start_kill() {
             tracing::warn!("Failed to kill child process: {e}");
         }
     }
 }
 
+impl Drop for ChildWithCleanup {
+    fn drop(&mut self) {
+        let Some(mut child) = self.inner.take() else {
+            return;
+        };
+        let runtime = tokio::runtime::Handle::try_current();
+        let (Some(grace_period), Ok(runtime)) = (self.grace_period, runtime) else {
+            Self::kill(child);
+            return;
+        };
+        // the stdin of the child is closed with the transport, which is the signal for a stdio
+        // server to exit on its own
+        runtime.spawn(async move {
+            let deadline = tokio::time::Instant::now() + grace_period;
+            loop {
+                match child.try_wait() {
+                    Ok(Some(status)) => {
+                        tracing::debug!("child process exited with {status}");
+                        return;
+                    }
+                    Ok(None) if tokio::time::Instant::now() < deadline => {
+                        tokio::time::sleep(CHILD_EXIT_POLL_INTERVAL).await;
+                    }
+                    Ok(None) => {
+                        tracing::debug!("child process didn't exit in {grace_period:?}");
+                        break;
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to wait for child process: {e}");
+                        break;
+                    }
+                }
+            }
+            Self::kill(child);
+        });
+    }
+}
+
 // we hold the child process with stdout, for it's easier to implement AsyncRead
 pin_project_lite::pin_project! {
     pub struct TokioChildProcessOut {
@@ -59,21 +118,19 @@ impl AsyncRead for TokioChildProcessOut {
 }
 
 impl TokioChildProcess {
-    pub fn new(mut command: tokio::process::Command) -> std::io::Result<Self> {
-        command
-            .stdin(std::process::Stdio::piped())
-            .stdout(std::process::Stdio::piped());
-        let mut command_wrap = TokioCommandWrap::from(command);
-        #[cfg(unix)]
-        command_wrap.wrap(process_wrap::tokio::ProcessGroup::leader());
-        #[cfg(windows)]
-        command_wrap.wrap(process_wrap::tokio::JobObject);
-        let (child, (child_stdout, child_stdin)) = child_process(command_wrap.spawn()?)?;
-        Ok(Self {
-            child: ChildWithCleanup { inner: child },
-            child_stdin,
-            child_stdout,
-        })
+    /// Spawn `command` in a process group of its own, with the stderr inherited
+    pub fn new(command: tokio::process::Command) -> std::io::Result<Self> {
+        let (process, _) = ChildProcessBuilder::from_command(command).spawn()?;
+        Ok(process)
+    }
+
+    pub fn builder(program: impl AsRef<OsStr>) -> ChildProcessBuilder {
+        ChildProcessBuilder::new(program)
+    }
+
+    /// The OS-assigned process id of the child, `None` once it has been reaped
+    pub fn id(&self) -> Option<u32> {
+        self.child.inner.as_ref().and_then(|child| child.id())
     }
 
     pub fn split(self) -> (TokioChildProcessOut, ChildStdin) {
@@ -110,3 +167,231 @@ impl ConfigureCommandExt for tokio::process::Command {
         self
     }
 }
+
+/// What becomes of the stderr of a child process
+#[derive(Clone, Default)]
+pub enum StderrPolicy {
+    /// Write to the stderr of this process
+    #[default]
+    Inherit,
+    Discard,
+    /// Hand the lines out as the [`StderrLines`] returned by [`ChildProcessBuilder::spawn`]
+    Capture,
+    /// Call the callback with every line
+    Callback(Arc<dyn Fn(String) + Send + Sync>),
+    /// Log every line with `tracing`, at the info level
+    Tracing,
+}
+
+impl std::fmt::Debug for StderrPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Inherit => f.write_str("Inherit"),
+            Self::Discard => f.write_str("Discard"),
+            Self::Capture => f.write_str("Capture"),
+            Self::Callback(_) => f.write_str("Callback"),
+            Self::Tracing => f.write_str("Tracing"),
+        }
+    }
+}
+
+/// The lines a child process writes to its stderr
+#[derive(Debug)]
+pub struct StderrLines {
+    lines: tokio::io::Lines<BufReader<ChildStderr>>,
+}
+
+impl StderrLines {
+    fn new(stderr: ChildStderr) -> Self {
+        Self {
+            lines: BufReader::new(stderr).lines(),
+        }
+    }
+
+    /// The next line, `None` once the child has closed its stderr
+    pub async fn next_line(&mut self) -> std::io::Result<Option<String>> {
+        self.lines.next_line().await
+    }
+
+    async fn forward(mut self, f: impl Fn(String)) {
+        loop {
+            match self.next_line().await {
+                Ok(Some(line)) => f(line),
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::warn!("Failed to read stderr of child process: {e}");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Stream for StderrLines {
+    type Item = std::io::Result<String>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        std::pin::Pin::new(&mut self.lines)
+            .poll_next_line(cx)
+            .map(Result::transpose)
+    }
+}
+
+/// Spawn a child process with more control than [`TokioChildProcess::new`]
+///
+/// By default the child inherits the environment and the stderr of this process, and is put in a
+/// process group of its own, so that a Ctrl-C in the terminal doesn't stop it before the client
+/// does. On windows the child is always assigned to a Job Object, so that it's killed with its
+/// own children.
+#[derive(Debug)]
+pub struct ChildProcessBuilder {
+    command: tokio::process::Command,
+    stderr: StderrPolicy,
+    process_group: bool,
+    kill_grace_period: Option<Duration>,
+}
+
+impl ChildProcessBuilder {
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        Self::from_command(tokio::process::Command::new(program))
+    }
+
+    pub fn from_command(command: tokio::process::Command) -> Self {
+        Self {
+            command,
+            stderr: StderrPolicy::default(),
+            process_group: true,
+            kill_grace_period: None,
+        }
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.command.arg(arg);
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.command.args(args);
+        self
+    }
+
+    pub fn current_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.command.current_dir(dir);
+        self
+    }
+
+    /// Set a variable on top of the inherited environment
+    pub fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.command.env(key, value);
+        self
+    }
+
+    pub fn envs<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.command.envs(vars);
+        self
+    }
+
+    pub fn env_remove(mut self, key: impl AsRef<OsStr>) -> Self {
+        self.command.env_remove(key);
+        self
+    }
+
+    /// Don't inherit the environment of this process, only the variables set on the builder
+    /// after this call are passed to the child
+    pub fn env_clear(mut self) -> Self {
+        self.command.env_clear();
+        self
+    }
+
+    /// Whether the child leads a process group of its own, `true` by default. Ignored on windows
+    pub fn process_group(mut self, process_group: bool) -> Self {
+        self.process_group = process_group;
+        self
+    }
+
+    pub fn stderr(mut self, policy: StderrPolicy) -> Self {
+        self.stderr = policy;
+        self
+    }
+
+    pub fn on_stderr(self, f: impl Fn(String) + Send + Sync + 'static) -> Self {
+        self.stderr(StderrPolicy::Callback(Arc::new(f)))
+    }
+
+    /// Once the transport is dropped, give the child this long to exit on its own after its
+    /// stdin is closed, before killing it. Without a grace period the child is killed at once
+    pub fn kill_grace_period(mut self, grace_period: Duration) -> Self {
+        self.kill_grace_period = Some(grace_period);
+        self
+    }
+
+    /// Spawn the child, the [`StderrLines`] are only returned with [`StderrPolicy::Capture`]
+    ///
+    /// The callback and tracing policies read the stderr on a task of their own, so this must
+    /// be called within a tokio runtime.
+    pub fn spawn(self) -> std::io::Result<(TokioChildProcess, Option<StderrLines>)> {
+        let Self {
+            mut command,
+            stderr,
+            process_group,
+            kill_grace_period,
+        } = self;
+        let stderr_stdio = match stderr {
+            StderrPolicy::Inherit => std::process::Stdio::inherit(),
+            StderrPolicy::Discard => std::process::Stdio::null(),
+            _ => std::process::Stdio::piped(),
+        };
+        command
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(stderr_stdio);
+        let mut command_wrap = TokioCommandWrap::from(command);
+        #[cfg(unix)]
+        if process_group {
+            command_wrap.wrap(process_wrap::tokio::ProcessGroup::leader());
+        }
+        #[cfg(windows)]
+        {
+            let _ = process_group;
+            command_wrap.wrap(process_wrap::tokio::JobObject);
+        }
+        let mut child = command_wrap.spawn()?;
+        let stderr_lines = child.inner_mut().stderr().take().map(StderrLines::new);
+        let (child, (child_stdout, child_stdin)) = child_process(child)?;
+        let stderr_lines = match (stderr, stderr_lines) {
+            (StderrPolicy::Callback(f), Some(lines)) => {
+                tokio::spawn(lines.forward(move |line| f(line)));
+                None
+            }
+            (StderrPolicy::Tracing, Some(lines)) => {
+                let pid = child.id();
+                tokio::spawn(lines.forward(move |line| {
+                    tracing::info!(target: "rmcp::child_process::stderr", ?pid, "{line}")
+                }));
+                None
+            }
+            (_, stderr_lines) => stderr_lines,
+        };
+        let process = TokioChildProcess {
+            child: ChildWithCleanup {
+                inner: Some(child),
+                grace_period: kill_grace_period,
+            },
+            child_stdin,
+            child_stdout,
+        };
+        Ok((process, stderr_lines))
+    }
+}
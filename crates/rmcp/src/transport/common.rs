@@ -8,7 +8,11 @@ pub mod http_header;
 
 #[cfg(feature = "__reqwest")]
 #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
-mod reqwest;
+pub(crate) mod reqwest;
+
+#[cfg(feature = "compression")]
+#[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+pub mod compression;
 
 #[cfg(feature = "client-side-sse")]
 #[cfg_attr(docsrs, doc(cfg(feature = "client-side-sse")))]
@@ -1,9 +1,17 @@
 use http::Uri;
 
 use crate::transport::{
-    auth::AuthClient,
+    auth::{AuthClient, is_unauthorized},
     sse_client::{SseClient, SseTransportError},
 };
+
+impl<E: std::error::Error + Send + Sync + 'static> SseTransportError<E> {
+    fn is_unauthorized(&self) -> bool {
+        matches!(self, SseTransportError::Client(e) if is_unauthorized(e))
+    }
+}
+
+/// Without a token of the caller, the request is retried once with a new token on a 401
 impl<C> SseClient for AuthClient<C>
 where
     C: SseClient,
@@ -14,32 +22,62 @@ where
         &self,
         uri: Uri,
         message: crate::model::ClientJsonRpcMessage,
-        mut auth_token: Option<String>,
+        auth_token: Option<String>,
     ) -> Result<(), SseTransportError<Self::Error>> {
-        if auth_token.is_none() {
-            auth_token = Some(self.get_access_token().await?);
+        if auth_token.is_some() {
+            return self
+                .http_client
+                .post_message(uri, message, auth_token)
+                .await
+                .map_err(SseTransportError::Client);
+        }
+        let token = self.get_access_token().await?;
+        let result = self
+            .http_client
+            .post_message(uri.clone(), message.clone(), Some(token.clone()))
+            .await;
+        match result {
+            Err(e) if e.is_unauthorized() => {
+                let token = self.reauthorize(token).await?;
+                self.http_client
+                    .post_message(uri, message, Some(token))
+                    .await
+                    .map_err(SseTransportError::Client)
+            }
+            result => result.map_err(SseTransportError::Client),
         }
-        self.http_client
-            .post_message(uri, message, auth_token)
-            .await
-            .map_err(SseTransportError::Client)
     }
 
     async fn get_stream(
         &self,
         uri: Uri,
         last_event_id: Option<String>,
-        mut auth_token: Option<String>,
+        auth_token: Option<String>,
     ) -> Result<
         crate::transport::common::client_side_sse::BoxedSseResponse,
         SseTransportError<Self::Error>,
     > {
-        if auth_token.is_none() {
-            auth_token = Some(self.get_access_token().await?);
+        if auth_token.is_some() {
+            return self
+                .http_client
+                .get_stream(uri, last_event_id, auth_token)
+                .await
+                .map_err(SseTransportError::Client);
+        }
+        let token = self.get_access_token().await?;
+        let result = self
+            .http_client
+            .get_stream(uri.clone(), last_event_id.clone(), Some(token.clone()))
+            .await;
+        match result {
+            Err(e) if e.is_unauthorized() => {
+                let token = self.reauthorize(token).await?;
+                self.http_client
+                    .get_stream(uri, last_event_id, Some(token))
+                    .await
+                    .map_err(SseTransportError::Client)
+            }
+            result => result.map_err(SseTransportError::Client),
         }
-        self.http_client
-            .get_stream(uri, last_event_id, auth_token)
-            .await
-            .map_err(SseTransportError::Client)
     }
 }
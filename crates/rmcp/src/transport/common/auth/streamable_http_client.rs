@@ -1,7 +1,15 @@
 use crate::transport::{
-    auth::AuthClient,
+    auth::{AuthClient, is_unauthorized},
     streamable_http_client::{StreamableHttpClient, StreamableHttpError},
 };
+
+impl<E: std::error::Error + Send + Sync + 'static> StreamableHttpError<E> {
+    fn is_unauthorized(&self) -> bool {
+        matches!(self, StreamableHttpError::Client(e) if is_unauthorized(e))
+    }
+}
+
+/// Without a token of the caller, the request is retried once with a new token on a 401
 impl<C> StreamableHttpClient for AuthClient<C>
 where
     C: StreamableHttpClient + Send + Sync,
@@ -12,14 +20,15 @@ where
         &self,
         uri: std::sync::Arc<str>,
         session_id: std::sync::Arc<str>,
-        mut auth_token: Option<String>,
+        auth_token: Option<String>,
     ) -> Result<(), crate::transport::streamable_http_client::StreamableHttpError<Self::Error>>
     {
-        if auth_token.is_none() {
-            auth_token = Some(self.get_access_token().await?);
-        }
+        let token = match auth_token {
+            Some(token) => token,
+            None => self.get_access_token().await?,
+        };
         self.http_client
-            .delete_session(uri, session_id, auth_token)
+            .delete_session(uri, session_id, Some(token))
             .await
             .map_err(StreamableHttpError::Client)
     }
@@ -29,18 +38,38 @@ where
         uri: std::sync::Arc<str>,
         session_id: std::sync::Arc<str>,
         last_event_id: Option<String>,
-        mut auth_token: Option<String>,
+        auth_token: Option<String>,
     ) -> Result<
         futures::stream::BoxStream<'static, Result<sse_stream::Sse, sse_stream::Error>>,
         crate::transport::streamable_http_client::StreamableHttpError<Self::Error>,
     > {
-        if auth_token.is_none() {
-            auth_token = Some(self.get_access_token().await?);
+        if auth_token.is_some() {
+            return self
+                .http_client
+                .get_stream(uri, session_id, last_event_id, auth_token)
+                .await
+                .map_err(StreamableHttpError::Client);
+        }
+        let token = self.get_access_token().await?;
+        let result = self
+            .http_client
+            .get_stream(
+                uri.clone(),
+                session_id.clone(),
+                last_event_id.clone(),
+                Some(token.clone()),
+            )
+            .await;
+        match result {
+            Err(e) if e.is_unauthorized() => {
+                let token = self.reauthorize(token).await?;
+                self.http_client
+                    .get_stream(uri, session_id, last_event_id, Some(token))
+                    .await
+                    .map_err(StreamableHttpError::Client)
+            }
+            result => result.map_err(StreamableHttpError::Client),
         }
-        self.http_client
-            .get_stream(uri, session_id, last_event_id, auth_token)
-            .await
-            .map_err(StreamableHttpError::Client)
     }
 
     async fn post_message(
@@ -48,17 +77,37 @@ where
         uri: std::sync::Arc<str>,
         message: crate::model::ClientJsonRpcMessage,
         session_id: Option<std::sync::Arc<str>>,
-        mut auth_token: Option<String>,
+        auth_token: Option<String>,
     ) -> Result<
         crate::transport::streamable_http_client::StreamableHttpPostResponse,
         StreamableHttpError<Self::Error>,
     > {
-        if auth_token.is_none() {
-            auth_token = Some(self.get_access_token().await?);
+        if auth_token.is_some() {
+            return self
+                .http_client
+                .post_message(uri, message, session_id, auth_token)
+                .await
+                .map_err(StreamableHttpError::Client);
+        }
+        let token = self.get_access_token().await?;
+        let result = self
+            .http_client
+            .post_message(
+                uri.clone(),
+                message.clone(),
+                session_id.clone(),
+                Some(token.clone()),
+            )
+            .await;
+        match result {
+            Err(e) if e.is_unauthorized() => {
+                let token = self.reauthorize(token).await?;
+                self.http_client
+                    .post_message(uri, message, session_id, Some(token))
+                    .await
+                    .map_err(StreamableHttpError::Client)
+            }
+            result => result.map_err(StreamableHttpError::Client),
         }
-        self.http_client
-            .post_message(uri, message, session_id, auth_token)
-            .await
-            .map_err(StreamableHttpError::Client)
     }
 }
@@ -0,0 +1,357 @@
+//! gzip and deflate bodies for the HTTP transports
+//!
+//! The bodies are compressed below the JSON layer, the messages don't change. The
+//! [`HttpClient`](crate::transport::HttpClient) accepts compressed responses and may compress
+//! the messages it posts, the servers compress the responses of the clients accepting it once
+//! a [`CompressionConfig`] is set on them. An event stream is flushed after every event, so
+//! compressing it doesn't hold the messages back.
+use std::io::{self, Write};
+
+use flate2::{
+    Compression,
+    write::{GzEncoder, ZlibEncoder},
+};
+
+/// A content coding both sides of the transports support
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    /// The zlib format, which is what `deflate` means in HTTP
+    Deflate,
+}
+
+impl Encoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+
+    /// Parse a `Content-Encoding`, `None` if it is none of ours
+    pub fn from_content_encoding(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("gzip") || value.eq_ignore_ascii_case("x-gzip") {
+            Some(Encoding::Gzip)
+        } else if value.eq_ignore_ascii_case("deflate") {
+            Some(Encoding::Deflate)
+        } else {
+            None
+        }
+    }
+
+    /// The encoding to answer an `Accept-Encoding` with, gzip before deflate
+    pub fn negotiate(accept_encoding: &str) -> Option<Self> {
+        let (mut gzip, mut deflate) = (false, false);
+        for entry in accept_encoding.split(',') {
+            let mut params = entry.split(';');
+            let coding = params.next().unwrap_or_default().trim();
+            let quality = params
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality <= 0.0 {
+                continue;
+            }
+            match Encoding::from_content_encoding(coding) {
+                Some(Encoding::Gzip) => gzip = true,
+                Some(Encoding::Deflate) => deflate = true,
+                None if coding == "*" => gzip = true,
+                None => {}
+            }
+        }
+        if gzip {
+            Some(Encoding::Gzip)
+        } else if deflate {
+            Some(Encoding::Deflate)
+        } else {
+            None
+        }
+    }
+}
+
+/// How a server compresses its responses and decompresses the requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionConfig {
+    /// The smallest JSON response worth compressing, event streams are always compressed
+    pub min_size: usize,
+    /// The largest request body once decompressed, larger ones are answered with a 413
+    pub max_request_size: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            min_size: 1024,
+            max_request_size: 2 * 1024 * 1024,
+        }
+    }
+}
+
+#[cfg(all(
+    feature = "__reqwest",
+    any(
+        feature = "transport-sse-client",
+        feature = "transport-streamable-http-client"
+    )
+))]
+pub(crate) fn compress(encoding: Encoding, data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = StreamEncoder::new(encoding);
+    encoder.writer().write_all(data)?;
+    encoder.finish()
+}
+
+/// Compresses a body chunk by chunk
+pub(crate) enum StreamEncoder {
+    Gzip(GzEncoder<Vec<u8>>),
+    Deflate(ZlibEncoder<Vec<u8>>),
+}
+
+impl StreamEncoder {
+    pub(crate) fn new(encoding: Encoding) -> Self {
+        match encoding {
+            Encoding::Gzip => {
+                StreamEncoder::Gzip(GzEncoder::new(Vec::new(), Compression::default()))
+            }
+            Encoding::Deflate => {
+                StreamEncoder::Deflate(ZlibEncoder::new(Vec::new(), Compression::default()))
+            }
+        }
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            StreamEncoder::Gzip(encoder) => encoder,
+            StreamEncoder::Deflate(encoder) => encoder,
+        }
+    }
+
+    /// Take what is compressed so far
+    fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(match self {
+            StreamEncoder::Gzip(encoder) => encoder.get_mut(),
+            StreamEncoder::Deflate(encoder) => encoder.get_mut(),
+        })
+    }
+
+    /// The end of the compressed body
+    pub(crate) fn finish(&mut self) -> io::Result<Vec<u8>> {
+        match self {
+            StreamEncoder::Gzip(encoder) => encoder.try_finish()?,
+            StreamEncoder::Deflate(encoder) => encoder.try_finish()?,
+        }
+        Ok(self.take_output())
+    }
+}
+
+#[cfg(any(
+    feature = "transport-streamable-http-server",
+    feature = "transport-sse-server"
+))]
+pub(crate) use server::compression;
+
+#[cfg(any(
+    feature = "transport-streamable-http-server",
+    feature = "transport-sse-server"
+))]
+mod server {
+    use std::io::Read;
+
+    use axum::{
+        body::{Body, HttpBody},
+        extract::{Request, State},
+        http::{
+            HeaderValue, StatusCode,
+            header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, VARY},
+        },
+        middleware::Next,
+        response::{IntoResponse, Response},
+    };
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::transport::common::http_header::{EVENT_STREAM_MIME_TYPE, JSON_MIME_TYPE};
+
+    /// The middleware of the servers with a [`CompressionConfig`]
+    pub(crate) async fn compression(
+        State(config): State<CompressionConfig>,
+        request: Request,
+        next: Next,
+    ) -> Response {
+        let request = match decompress_request(request, config.max_request_size).await {
+            Ok(request) => request,
+            Err(response) => return response,
+        };
+        let accepted = request
+            .headers()
+            .get(ACCEPT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Encoding::negotiate);
+        let response = next.run(request).await;
+        match accepted {
+            Some(encoding) if should_compress(&response, config.min_size) => {
+                compress_response(response, encoding)
+            }
+            _ => response,
+        }
+    }
+
+    async fn decompress_request(request: Request, limit: usize) -> Result<Request, Response> {
+        let Some(value) = request.headers().get(CONTENT_ENCODING) else {
+            return Ok(request);
+        };
+        let value = value.to_str().unwrap_or_default().trim();
+        if value.eq_ignore_ascii_case("identity") {
+            return Ok(request);
+        }
+        let Some(encoding) = Encoding::from_content_encoding(value) else {
+            return Err((
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("unsupported content encoding {value}"),
+            )
+                .into_response());
+        };
+        let (mut parts, body) = request.into_parts();
+        let compressed = axum::body::to_bytes(body, limit)
+            .await
+            .map_err(|e| (StatusCode::PAYLOAD_TOO_LARGE, e.to_string()).into_response())?;
+        let body = decompress(encoding, &compressed, limit).map_err(|e| {
+            let status = match e.kind() {
+                io::ErrorKind::FileTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            (status, e.to_string()).into_response()
+        })?;
+        parts.headers.remove(CONTENT_ENCODING);
+        parts.headers.remove(CONTENT_LENGTH);
+        Ok(Request::from_parts(parts, Body::from(body)))
+    }
+
+    /// Only the messages are compressed, event streams whatever their size
+    fn should_compress(response: &Response, min_size: usize) -> bool {
+        if response.headers().contains_key(CONTENT_ENCODING) {
+            return false;
+        }
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .map(HeaderValue::as_bytes)
+            .unwrap_or_default();
+        if content_type.starts_with(EVENT_STREAM_MIME_TYPE.as_bytes()) {
+            return true;
+        }
+        content_type.starts_with(JSON_MIME_TYPE.as_bytes())
+            && response
+                .body()
+                .size_hint()
+                .exact()
+                .is_none_or(|size| size >= min_size as u64)
+    }
+
+    fn compress_response(response: Response, encoding: Encoding) -> Response {
+        let (mut parts, body) = response.into_parts();
+        parts.headers.insert(
+            CONTENT_ENCODING,
+            HeaderValue::from_static(encoding.as_str()),
+        );
+        parts.headers.remove(CONTENT_LENGTH);
+        parts
+            .headers
+            .append(VARY, HeaderValue::from_static("accept-encoding"));
+        let mut encoder = StreamEncoder::new(encoding);
+        let chunks = body
+            .into_data_stream()
+            .map(Some)
+            .chain(futures::stream::once(async { None }));
+        let body = chunks.map(move |chunk| match chunk {
+            Some(Ok(chunk)) => encoder.encode(&chunk).map_err(axum::Error::new),
+            Some(Err(e)) => Err(e),
+            None => encoder.finish().map_err(axum::Error::new),
+        });
+        Response::from_parts(parts, Body::from_stream(body))
+    }
+
+    /// Decompress `data`, failing with [`io::ErrorKind::FileTooLarge`] past `limit` bytes
+    fn decompress(encoding: Encoding, data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+        let decoder: Box<dyn Read + '_> = match encoding {
+            Encoding::Gzip => Box::new(GzDecoder::new(data)),
+            Encoding::Deflate => Box::new(ZlibDecoder::new(data)),
+        };
+        let mut body = Vec::new();
+        decoder.take(limit as u64 + 1).read_to_end(&mut body)?;
+        if body.len() > limit {
+            return Err(io::Error::new(
+                io::ErrorKind::FileTooLarge,
+                format!("the decompressed body exceeds {limit} bytes"),
+            ));
+        }
+        Ok(body)
+    }
+
+    impl StreamEncoder {
+        /// Compress `chunk`, flushed so the peer can decompress it without waiting for the next
+        fn encode(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+            let writer = self.writer();
+            writer.write_all(chunk)?;
+            writer.flush()?;
+            Ok(self.take_output())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_flushed_chunks_decompress_on_their_own() {
+            for encoding in [Encoding::Gzip, Encoding::Deflate] {
+                let mut encoder = StreamEncoder::new(encoding);
+                let first = encoder.encode(b"data: first\n\n").unwrap();
+                // a deflate stream cut after a flush is still readable up to the flush
+                let mut decoded = Vec::new();
+                let decoder: Box<dyn Read + '_> = match encoding {
+                    Encoding::Gzip => Box::new(GzDecoder::new(first.as_slice())),
+                    Encoding::Deflate => Box::new(ZlibDecoder::new(first.as_slice())),
+                };
+                let _ = decoder.take(13).read_to_end(&mut decoded);
+                assert_eq!(decoded, b"data: first\n\n");
+
+                let mut body = first;
+                body.extend(encoder.encode(b"data: second\n\n").unwrap());
+                body.extend(encoder.finish().unwrap());
+                assert_eq!(
+                    decompress(encoding, &body, 1024).unwrap(),
+                    b"data: first\n\ndata: second\n\n"
+                );
+            }
+        }
+
+        #[test]
+        fn test_decompress_limit() {
+            let mut encoder = StreamEncoder::new(Encoding::Gzip);
+            let mut body = encoder.encode(&[b'a'; 4096]).unwrap();
+            body.extend(encoder.finish().unwrap());
+            assert_eq!(decompress(Encoding::Gzip, &body, 4096).unwrap().len(), 4096);
+            let error = decompress(Encoding::Gzip, &body, 4095).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::FileTooLarge);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(Encoding::negotiate("gzip, deflate"), Some(Encoding::Gzip));
+        assert_eq!(Encoding::negotiate("deflate"), Some(Encoding::Deflate));
+        assert_eq!(
+            Encoding::negotiate("gzip;q=0, deflate;q=0.5"),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(Encoding::negotiate("*"), Some(Encoding::Gzip));
+        assert_eq!(Encoding::negotiate("br, identity"), None);
+    }
+}
@@ -1,7 +1,71 @@
 #[cfg(feature = "transport-streamable-http-client")]
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-streamable-http-client")))]
-mod streamable_http_client;
+pub(crate) mod streamable_http_client;
 
 #[cfg(feature = "transport-sse-client")]
 #[cfg_attr(docsrs, doc(cfg(feature = "transport-sse-client")))]
-mod sse_client;
+pub(crate) mod sse_client;
+
+/// Set `message` as the JSON body of `request`, gzipped when longer than `compress_above`
+#[cfg(any(
+    feature = "transport-sse-client",
+    feature = "transport-streamable-http-client"
+))]
+pub(crate) fn json_body<T: serde::Serialize>(
+    request: ::reqwest::RequestBuilder,
+    message: &T,
+    compress_above: Option<usize>,
+) -> ::reqwest::RequestBuilder {
+    #[cfg(feature = "compression")]
+    if let Some(threshold) = compress_above {
+        use ::reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+
+        use super::{
+            compression::{Encoding, compress},
+            http_header::JSON_MIME_TYPE,
+        };
+        // a message that doesn't serialize is reported by reqwest
+        let Ok(json) = serde_json::to_vec(message) else {
+            return request.json(message);
+        };
+        let request = request.header(CONTENT_TYPE, JSON_MIME_TYPE);
+        if json.len() <= threshold {
+            return request.body(json);
+        }
+        return match compress(Encoding::Gzip, &json) {
+            Ok(body) => request
+                .header(CONTENT_ENCODING, Encoding::Gzip.as_str())
+                .body(body),
+            Err(error) => {
+                tracing::warn!(%error, "fail to compress a message, send it as is");
+                request.body(json)
+            }
+        };
+    }
+    #[cfg(not(feature = "compression"))]
+    let _ = compress_above;
+    request.json(message)
+}
+
+/// The event stream of `response`, its chunks joined while they're ready
+///
+/// The parser polls the body again after every chunk not ending an event, the many small
+/// chunks of a decompressed body would overflow the stack.
+#[cfg(any(
+    feature = "transport-sse-client",
+    feature = "transport-streamable-http-client"
+))]
+pub(crate) fn event_stream(
+    response: ::reqwest::Response,
+) -> super::client_side_sse::BoxedSseResponse {
+    use futures::StreamExt;
+
+    let body = response.bytes_stream().ready_chunks(256).map(|chunks| {
+        let mut joined = Vec::new();
+        for chunk in chunks {
+            joined.extend_from_slice(&chunk?);
+        }
+        Ok::<_, ::reqwest::Error>(std::io::Cursor::new(joined))
+    });
+    sse_stream::SseStream::from_byte_stream(body).boxed()
+}
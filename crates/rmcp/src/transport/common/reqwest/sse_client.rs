@@ -1,9 +1,7 @@
 use std::sync::Arc;
 
-use futures::StreamExt;
 use http::Uri;
 use reqwest::header::ACCEPT;
-use sse_stream::SseStream;
 
 use crate::transport::{
     SseClientTransport,
@@ -20,16 +18,7 @@ impl SseClient for reqwest::Client {
         message: crate::model::ClientJsonRpcMessage,
         auth_token: Option<String>,
     ) -> Result<(), SseTransportError<Self::Error>> {
-        let mut request_builder = self.post(uri.to_string()).json(&message);
-        if let Some(auth_header) = auth_token {
-            request_builder = request_builder.bearer_auth(auth_header);
-        }
-        request_builder
-            .send()
-            .await
-            .and_then(|resp| resp.error_for_status())
-            .map_err(SseTransportError::from)
-            .map(drop)
+        post_message(self, uri, message, auth_token, None).await
     }
 
     async fn get_stream(
@@ -62,11 +51,31 @@ impl SseClient for reqwest::Client {
                 return Err(SseTransportError::UnexpectedContentType(None));
             }
         }
-        let event_stream = SseStream::from_byte_stream(response.bytes_stream()).boxed();
-        Ok(event_stream)
+        Ok(super::event_stream(response))
     }
 }
 
+/// [`SseClient::post_message`], gzipping the messages longer than `compress_above`
+pub(crate) async fn post_message(
+    client: &reqwest::Client,
+    uri: Uri,
+    message: crate::model::ClientJsonRpcMessage,
+    auth_token: Option<String>,
+    compress_above: Option<usize>,
+) -> Result<(), SseTransportError<reqwest::Error>> {
+    let mut request_builder =
+        super::json_body(client.post(uri.to_string()), &message, compress_above);
+    if let Some(auth_header) = auth_token {
+        request_builder = request_builder.bearer_auth(auth_header);
+    }
+    request_builder
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(SseTransportError::from)
+        .map(drop)
+}
+
 impl SseClientTransport<reqwest::Client> {
     pub async fn start(
         uri: impl Into<Arc<str>>,
@@ -1,8 +1,8 @@
 use std::sync::Arc;
 
-use futures::{StreamExt, stream::BoxStream};
+use futures::stream::BoxStream;
 use reqwest::header::ACCEPT;
-use sse_stream::{Sse, SseStream};
+use sse_stream::Sse;
 
 use crate::{
     model::{ClientJsonRpcMessage, ServerJsonRpcMessage},
@@ -51,8 +51,7 @@ impl StreamableHttpClient for reqwest::Client {
                 return Err(StreamableHttpError::UnexpectedContentType(None));
             }
         }
-        let event_stream = SseStream::from_byte_stream(response.bytes_stream()).boxed();
-        Ok(event_stream)
+        Ok(super::event_stream(response))
     }
 
     async fn delete_session(
@@ -86,41 +85,56 @@ impl StreamableHttpClient for reqwest::Client {
         session_id: Option<Arc<str>>,
         auth_token: Option<String>,
     ) -> Result<StreamableHttpPostResponse, StreamableHttpError<Self::Error>> {
-        let mut request = self
-            .post(uri.as_ref())
-            .header(ACCEPT, EVENT_STREAM_MIME_TYPE)
-            .header(ACCEPT, JSON_MIME_TYPE);
-        if let Some(auth_header) = auth_token {
-            request = request.bearer_auth(auth_header);
-        }
-        if let Some(session_id) = session_id {
-            request = request.header(HEADER_SESSION_ID, session_id.as_ref());
+        post_message(self, uri, message, session_id, auth_token, None).await
+    }
+}
+
+/// [`StreamableHttpClient::post_message`], gzipping the messages longer than `compress_above`
+pub(crate) async fn post_message(
+    client: &reqwest::Client,
+    uri: Arc<str>,
+    message: ClientJsonRpcMessage,
+    session_id: Option<Arc<str>>,
+    auth_token: Option<String>,
+    compress_above: Option<usize>,
+) -> Result<StreamableHttpPostResponse, StreamableHttpError<reqwest::Error>> {
+    let mut request = client
+        .post(uri.as_ref())
+        .header(ACCEPT, EVENT_STREAM_MIME_TYPE)
+        .header(ACCEPT, JSON_MIME_TYPE);
+    if let Some(auth_header) = auth_token {
+        request = request.bearer_auth(auth_header);
+    }
+    if let Some(session_id) = session_id {
+        request = request.header(HEADER_SESSION_ID, session_id.as_ref());
+    }
+    let response = super::json_body(request, &message, compress_above)
+        .send()
+        .await?;
+    if response.status() == reqwest::StatusCode::ACCEPTED {
+        return Ok(StreamableHttpPostResponse::Accepted);
+    }
+    let response = response.error_for_status()?;
+    let content_type = response.headers().get(reqwest::header::CONTENT_TYPE);
+    let session_id = response.headers().get(HEADER_SESSION_ID);
+    let session_id = session_id
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+    match content_type {
+        Some(ct) if ct.as_bytes().starts_with(EVENT_STREAM_MIME_TYPE.as_bytes()) => {
+            let event_stream = super::event_stream(response);
+            Ok(StreamableHttpPostResponse::Sse(event_stream, session_id))
         }
-        let response = request.json(&message).send().await?;
-        if response.status() == reqwest::StatusCode::ACCEPTED {
-            return Ok(StreamableHttpPostResponse::Accepted);
+        Some(ct) if ct.as_bytes().starts_with(JSON_MIME_TYPE.as_bytes()) => {
+            let message: ServerJsonRpcMessage = response.json().await?;
+            Ok(StreamableHttpPostResponse::Json(message, session_id))
         }
-        let content_type = response.headers().get(reqwest::header::CONTENT_TYPE);
-        let session_id = response.headers().get(HEADER_SESSION_ID);
-        let session_id = session_id
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.to_string());
-        match content_type {
-            Some(ct) if ct.as_bytes().starts_with(EVENT_STREAM_MIME_TYPE.as_bytes()) => {
-                let event_stream = SseStream::from_byte_stream(response.bytes_stream()).boxed();
-                Ok(StreamableHttpPostResponse::Sse(event_stream, session_id))
-            }
-            Some(ct) if ct.as_bytes().starts_with(JSON_MIME_TYPE.as_bytes()) => {
-                let message: ServerJsonRpcMessage = response.json().await?;
-                Ok(StreamableHttpPostResponse::Json(message, session_id))
-            }
-            _ => {
-                // unexpected content type
-                tracing::error!("unexpected content type: {:?}", content_type);
-                Err(StreamableHttpError::UnexpectedContentType(
-                    content_type.map(|ct| String::from_utf8_lossy(ct.as_bytes()).to_string()),
-                ))
-            }
+        _ => {
+            // unexpected content type
+            tracing::error!("unexpected content type: {:?}", content_type);
+            Err(StreamableHttpError::UnexpectedContentType(
+                content_type.map(|ct| String::from_utf8_lossy(ct.as_bytes()).to_string()),
+            ))
         }
     }
 }
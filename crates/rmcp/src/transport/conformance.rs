@@ -0,0 +1,330 @@
+//! A test suite for custom transports
+//!
+//! [`check_transport`] runs a pair of connected transports through what the service layer
+//! expects of them, see [`Transport`]:
+//!
+//! - messages sent one after the other arrive in the same order,
+//! - sends made concurrently all arrive,
+//! - a `receive` future dropped before it completes doesn't lose a message,
+//! - a client and a server complete the handshake and exchange large and concurrent requests,
+//! - the end of one side ends the input of the other.
+//!
+//! ```rust,ignore
+//! #[tokio::test]
+//! async fn test_conformance() {
+//!     check_transport(|| async { my_bus::pair().await }).await.unwrap();
+//! }
+//! ```
+use std::{future::Future, time::Duration};
+
+use futures::FutureExt;
+
+use super::{IntoTransport, Transport};
+use crate::{
+    RoleClient, RoleServer, ServerHandler, ServiceExt,
+    model::{
+        CallToolRequestParam, CallToolResult, JsonObject, JsonRpcMessage, JsonRpcRequest,
+        NumberOrString, PingRequest, RequestId,
+    },
+    service::{QuitReason, RequestContext, RxJsonRpcMessage, ServiceRole, TxJsonRpcMessage},
+};
+
+/// How many messages the ordering checks send
+const MESSAGES: i64 = 64;
+/// The size of the text of the large request
+const LARGE_MESSAGE_SIZE: usize = 1024 * 1024;
+const CONCURRENT_REQUESTS: usize = 16;
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A check [`check_transport`] failed
+#[derive(Debug, thiserror::Error)]
+#[error("{check}: {reason}")]
+pub struct ConformanceError {
+    pub check: &'static str,
+    pub reason: String,
+}
+
+/// Run the conformance checks on the pairs of transports made by `connect`
+///
+/// `connect` is called once per check, with both sides of a new connection.
+pub async fn check_transport<F, Fut, S, C, ES, EC, AS, AC>(
+    mut connect: F,
+) -> Result<(), ConformanceError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = (S, C)>,
+    S: IntoTransport<RoleServer, ES, AS>,
+    C: IntoTransport<RoleClient, EC, AC>,
+    ES: std::error::Error + From<std::io::Error> + Send + Sync + 'static,
+    EC: std::error::Error + From<std::io::Error> + Send + Sync + 'static,
+{
+    let (server, client) = connect().await;
+    let (mut server, mut client) = (server.into_transport(), client.into_transport());
+    check(
+        "sequential order",
+        sequential_order(&mut client, &mut server),
+    )
+    .await?;
+    check(
+        "sequential order",
+        sequential_order(&mut server, &mut client),
+    )
+    .await?;
+    close(server, client).await;
+
+    let (server, client) = connect().await;
+    let (mut server, mut client) = (server.into_transport(), client.into_transport());
+    check(
+        "concurrent sends",
+        concurrent_sends(&mut client, &mut server),
+    )
+    .await?;
+    check(
+        "concurrent sends",
+        concurrent_sends(&mut server, &mut client),
+    )
+    .await?;
+    close(server, client).await;
+
+    let (server, client) = connect().await;
+    let (mut server, mut client) = (server.into_transport(), client.into_transport());
+    check(
+        "cancelled receive",
+        cancelled_receive(&mut client, &mut server),
+    )
+    .await?;
+    check(
+        "cancelled receive",
+        cancelled_receive(&mut server, &mut client),
+    )
+    .await?;
+    close(server, client).await;
+
+    let (server, client) = connect().await;
+    check("service", service(server, client)).await
+}
+
+async fn check(
+    name: &'static str,
+    check: impl Future<Output = Result<(), String>>,
+) -> Result<(), ConformanceError> {
+    let error = |reason| ConformanceError {
+        check: name,
+        reason,
+    };
+    tokio::time::timeout(TIMEOUT, check)
+        .await
+        .map_err(|_| error(format!("not done within {TIMEOUT:?}")))?
+        .map_err(error)
+}
+
+async fn close(mut server: impl Transport<RoleServer>, mut client: impl Transport<RoleClient>) {
+    let _ = server.close().await;
+    let _ = client.close().await;
+}
+
+fn ping<R: ServiceRole>(id: i64) -> TxJsonRpcMessage<R> {
+    let ping = PingRequest {
+        method: Default::default(),
+        extensions: Default::default(),
+    };
+    JsonRpcMessage::request(ping.into(), RequestId::Number(id))
+}
+
+fn ping_id<R: ServiceRole>(message: RxJsonRpcMessage<R>) -> Result<i64, String> {
+    match message {
+        JsonRpcMessage::Request(JsonRpcRequest {
+            id: NumberOrString::Number(id),
+            ..
+        }) => Ok(id),
+        message => Err(format!("received {message:?} instead of a ping")),
+    }
+}
+
+/// Receive [`MESSAGES`] pings, in the order they arrive
+async fn receive_pings<R: ServiceRole>(rx: &mut impl Transport<R>) -> Result<Vec<i64>, String> {
+    let mut ids = Vec::new();
+    while ids.len() < MESSAGES as usize {
+        let message = rx
+            .receive()
+            .await
+            .ok_or_else(|| format!("the input ended after {} messages", ids.len()))?;
+        ids.push(ping_id::<R>(message)?);
+    }
+    Ok(ids)
+}
+
+async fn sequential_order<A, B, TA, TB>(tx: &mut TA, rx: &mut TB) -> Result<(), String>
+where
+    A: ServiceRole,
+    B: ServiceRole,
+    TA: Transport<A>,
+    TA::Error: std::fmt::Display,
+    TB: Transport<B>,
+{
+    let send = async {
+        for id in 0..MESSAGES {
+            tx.send(ping::<A>(id))
+                .await
+                .map_err(|e| format!("fail to send message {id}: {e}"))?;
+        }
+        Ok::<_, String>(())
+    };
+    let (sent, received) = tokio::join!(send, receive_pings(rx));
+    sent?;
+    let received = received?;
+    if !received.iter().copied().eq(0..MESSAGES) {
+        return Err(format!("received out of order: {received:?}"));
+    }
+    Ok(())
+}
+
+async fn concurrent_sends<A, B, TA, TB>(tx: &mut TA, rx: &mut TB) -> Result<(), String>
+where
+    A: ServiceRole,
+    B: ServiceRole,
+    TA: Transport<A>,
+    TA::Error: std::fmt::Display + Send + 'static,
+    TB: Transport<B>,
+{
+    // as the service does: the sends are made in order, their futures run concurrently
+    let mut sends = tokio::task::JoinSet::new();
+    for id in 0..MESSAGES {
+        sends.spawn(tx.send(ping::<A>(id)));
+    }
+    let send = async {
+        while let Some(result) = sends.join_next().await {
+            result
+                .map_err(|e| format!("fail to join a send: {e}"))?
+                .map_err(|e| format!("fail to send: {e}"))?;
+        }
+        Ok::<_, String>(())
+    };
+    let (sent, received) = tokio::join!(send, receive_pings(rx));
+    sent?;
+    let mut received = received?;
+    received.sort_unstable();
+    if !received.iter().copied().eq(0..MESSAGES) {
+        return Err(format!("messages lost or duplicated: {received:?}"));
+    }
+    Ok(())
+}
+
+async fn cancelled_receive<A, B, TA, TB>(tx: &mut TA, rx: &mut TB) -> Result<(), String>
+where
+    A: ServiceRole,
+    B: ServiceRole,
+    TA: Transport<A>,
+    TA::Error: std::fmt::Display,
+    TB: Transport<B>,
+{
+    let send = async {
+        for id in 0..MESSAGES {
+            tx.send(ping::<A>(id))
+                .await
+                .map_err(|e| format!("fail to send message {id}: {e}"))?;
+            if id % 8 == 0 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        }
+        Ok::<_, String>(())
+    };
+    // every receive is polled once and dropped unless it is ready, as in a `select!`
+    let receive = async {
+        let mut ids = Vec::new();
+        while ids.len() < MESSAGES as usize {
+            match rx.receive().now_or_never() {
+                Some(Some(message)) => ids.push(ping_id::<B>(message)?),
+                Some(None) => return Err(format!("the input ended after {} messages", ids.len())),
+                None => tokio::time::sleep(Duration::from_millis(1)).await,
+            }
+        }
+        Ok(ids)
+    };
+    let (sent, received) = tokio::join!(send, receive);
+    sent?;
+    let received = received?;
+    if !received.iter().copied().eq(0..MESSAGES) {
+        return Err(format!("received out of order or lost: {received:?}"));
+    }
+    Ok(())
+}
+
+/// Answers the `echo` tool with its `text` argument
+#[derive(Debug, Clone)]
+struct Echo;
+
+impl ServerHandler for Echo {
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, crate::Error> {
+        let text = request
+            .arguments
+            .as_ref()
+            .and_then(|arguments| arguments.get("text")?.as_str())
+            .unwrap_or_default();
+        Ok(CallToolResult::text(text))
+    }
+}
+
+async fn service<S, C, ES, EC, AS, AC>(server: S, client: C) -> Result<(), String>
+where
+    S: IntoTransport<RoleServer, ES, AS>,
+    C: IntoTransport<RoleClient, EC, AC>,
+    ES: std::error::Error + From<std::io::Error> + Send + Sync + 'static,
+    EC: std::error::Error + From<std::io::Error> + Send + Sync + 'static,
+{
+    let (server, client) = tokio::join!(Echo.serve(server), ().serve(client));
+    let server = server.map_err(|e| format!("the server failed to initialize: {e}"))?;
+    let client = client.map_err(|e| format!("the client failed to initialize: {e}"))?;
+    let echo = |text: String| {
+        let client = client.peer().clone();
+        async move {
+            let result = client
+                .call_tool(CallToolRequestParam {
+                    name: "echo".into(),
+                    arguments: Some(
+                        JsonObject::from_iter([("text".to_owned(), text.clone().into())]).into(),
+                    ),
+                })
+                .await
+                .map_err(|e| format!("fail to call the tool: {e}"))?;
+            let echoed = result
+                .content
+                .first()
+                .and_then(|content| content.as_text())
+                .map(|content| content.text.clone())
+                .unwrap_or_default();
+            Ok::<_, String>((text, echoed))
+        }
+    };
+
+    let (text, echoed) = echo("x".repeat(LARGE_MESSAGE_SIZE)).await?;
+    if text != echoed {
+        return Err(format!("a message of {} bytes was altered", text.len()));
+    }
+    let calls = (0..CONCURRENT_REQUESTS).map(|i| echo(format!("request {i}")));
+    for result in futures::future::join_all(calls).await {
+        let (text, echoed) = result?;
+        if text != echoed {
+            return Err(format!("{text:?} was answered with {echoed:?}"));
+        }
+    }
+
+    client
+        .cancel()
+        .await
+        .map_err(|e| format!("fail to stop the client: {e}"))?;
+    let quit_reason = server
+        .waiting()
+        .await
+        .map_err(|e| format!("the server failed: {e}"))?;
+    match quit_reason {
+        QuitReason::Closed => Ok(()),
+        quit_reason => Err(format!(
+            "the server should see the end of its input once the client is gone, it quit with {quit_reason:?}"
+        )),
+    }
+}
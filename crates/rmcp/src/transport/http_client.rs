@@ -0,0 +1,308 @@
+//! A reqwest client for the SSE and streamable HTTP client transports, applying its headers,
+//! authorization, proxy and timeouts to the event streams and the posted messages alike
+//!
+//! ```rust,ignore
+//! let client = HttpClient::builder()
+//!     .header(HeaderName::from_static("x-tenant"), HeaderValue::from_static("acme"))
+//!     .token_provider(move || {
+//!         let tokens = tokens.clone();
+//!         async move { tokens.current().await }
+//!     })
+//!     .proxy("http://proxy.internal:3128")
+//!     .build()?;
+//! let transport = StreamableHttpClientTransport::with_client(
+//!     client,
+//!     StreamableHttpClientTransportConfig::with_uri("https://mcp.example.com/"),
+//! );
+//! ```
+use std::{sync::Arc, time::Duration};
+
+use futures::future::BoxFuture;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
+
+/// Gives the bearer token of every request, to refresh the tokens before they expire
+pub type TokenProvider = Arc<dyn Fn() -> BoxFuture<'static, String> + Send + Sync>;
+
+#[derive(Clone)]
+enum BearerToken {
+    Static(String),
+    Dynamic(TokenProvider),
+}
+
+/// A [`reqwest::Client`] with the bearer token of its [`HttpClientBuilder`]
+#[derive(Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    bearer_token: Option<BearerToken>,
+    compress_above: Option<usize>,
+}
+
+impl std::fmt::Debug for HttpClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpClient")
+            .field("client", &self.client)
+            .field("bearer_token", &self.bearer_token.as_ref().map(|_| "..."))
+            .field("compress_above", &self.compress_above)
+            .finish()
+    }
+}
+
+impl HttpClient {
+    pub fn builder() -> HttpClientBuilder {
+        HttpClientBuilder::new()
+    }
+
+    pub fn reqwest_client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// A token given by the transport, as the one of an OAuth2 authorized client, goes before
+    /// ours
+    async fn bearer_token(&self, auth_token: Option<String>) -> Option<String> {
+        if auth_token.is_some() {
+            return auth_token;
+        }
+        match self.bearer_token.as_ref()? {
+            BearerToken::Static(token) => Some(token.clone()),
+            BearerToken::Dynamic(provider) => Some(provider().await),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct HttpClientBuilder {
+    headers: HeaderMap,
+    bearer_token: Option<BearerToken>,
+    proxy: Option<String>,
+    timeout: Option<Duration>,
+    #[cfg(any(feature = "reqwest", feature = "reqwest-tls-no-provider"))]
+    accept_invalid_certs: bool,
+    #[cfg(feature = "compression")]
+    accept_compressed: Option<bool>,
+    compress_above: Option<usize>,
+}
+
+impl std::fmt::Debug for HttpClientBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpClientBuilder")
+            .field("headers", &self.headers)
+            .field("proxy", &self.proxy)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HttpClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send `name: value` with every request, replacing the previous value of `name`
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    pub fn bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.headers.remove(AUTHORIZATION);
+        self.bearer_token = Some(BearerToken::Static(token.into()));
+        self
+    }
+
+    /// Ask `provider` for the bearer token of every request
+    pub fn token_provider<F, Fut>(mut self, provider: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = String> + Send + 'static,
+    {
+        self.headers.remove(AUTHORIZATION);
+        self.bearer_token = Some(BearerToken::Dynamic(Arc::new(move || Box::pin(provider()))));
+        self
+    }
+
+    pub fn basic_auth(mut self, username: &str, password: &str) -> Self {
+        use base64::Engine;
+        let credentials =
+            base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"));
+        let mut value = HeaderValue::try_from(format!("Basic {credentials}"))
+            .expect("base64 is a valid header value");
+        value.set_sensitive(true);
+        self.bearer_token = None;
+        self.header(AUTHORIZATION, value)
+    }
+
+    /// Send every request through the HTTP proxy at `url`
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Give up on connecting, or on a response going silent, after `timeout`
+    ///
+    /// The event streams are idle between two messages, `timeout` has to be longer than the
+    /// keep-alive interval of the server.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Accept any certificate from the server, which leaves the connection open to
+    /// man-in-the-middle attacks. Only meant for tests and local development
+    #[cfg(any(feature = "reqwest", feature = "reqwest-tls-no-provider"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "reqwest", feature = "reqwest-tls-no-provider")))
+    )]
+    pub fn danger_accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Self {
+        self.accept_invalid_certs = accept_invalid_certs;
+        self
+    }
+
+    /// Send `Accept-Encoding: gzip, deflate` and decompress the responses, on by default
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    pub fn accept_compressed(mut self, accept_compressed: bool) -> Self {
+        self.accept_compressed = Some(accept_compressed);
+        self
+    }
+
+    /// Gzip the posted messages longer than `threshold` bytes
+    ///
+    /// The server has to decompress them, as the ones with a
+    /// [`CompressionConfig`](crate::transport::common::compression::CompressionConfig) do.
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    pub fn compress_requests_above(mut self, threshold: usize) -> Self {
+        self.compress_above = Some(threshold);
+        self
+    }
+
+    pub fn build(self) -> Result<HttpClient, reqwest::Error> {
+        let mut builder = reqwest::Client::builder().default_headers(self.headers);
+        if let Some(proxy) = self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.connect_timeout(timeout).read_timeout(timeout);
+        }
+        #[cfg(any(feature = "reqwest", feature = "reqwest-tls-no-provider"))]
+        {
+            builder = builder.danger_accept_invalid_certs(self.accept_invalid_certs);
+        }
+        #[cfg(feature = "compression")]
+        if let Some(accept_compressed) = self.accept_compressed {
+            builder = builder.gzip(accept_compressed).deflate(accept_compressed);
+        }
+        Ok(HttpClient {
+            client: builder.build()?,
+            bearer_token: self.bearer_token,
+            compress_above: self.compress_above,
+        })
+    }
+}
+
+#[cfg(feature = "transport-sse-client")]
+impl crate::transport::sse_client::SseClient for HttpClient {
+    type Error = reqwest::Error;
+
+    async fn post_message(
+        &self,
+        uri: http::Uri,
+        message: crate::model::ClientJsonRpcMessage,
+        auth_token: Option<String>,
+    ) -> Result<(), crate::transport::sse_client::SseTransportError<Self::Error>> {
+        let auth_token = self.bearer_token(auth_token).await;
+        crate::transport::common::reqwest::sse_client::post_message(
+            &self.client,
+            uri,
+            message,
+            auth_token,
+            self.compress_above,
+        )
+        .await
+    }
+
+    async fn get_stream(
+        &self,
+        uri: http::Uri,
+        last_event_id: Option<String>,
+        auth_token: Option<String>,
+    ) -> Result<
+        crate::transport::common::client_side_sse::BoxedSseResponse,
+        crate::transport::sse_client::SseTransportError<Self::Error>,
+    > {
+        let auth_token = self.bearer_token(auth_token).await;
+        crate::transport::sse_client::SseClient::get_stream(
+            &self.client,
+            uri,
+            last_event_id,
+            auth_token,
+        )
+        .await
+    }
+}
+
+#[cfg(feature = "transport-streamable-http-client")]
+impl crate::transport::streamable_http_client::StreamableHttpClient for HttpClient {
+    type Error = reqwest::Error;
+
+    async fn post_message(
+        &self,
+        uri: Arc<str>,
+        message: crate::model::ClientJsonRpcMessage,
+        session_id: Option<Arc<str>>,
+        auth_token: Option<String>,
+    ) -> Result<
+        crate::transport::streamable_http_client::StreamableHttpPostResponse,
+        crate::transport::streamable_http_client::StreamableHttpError<Self::Error>,
+    > {
+        let auth_token = self.bearer_token(auth_token).await;
+        crate::transport::common::reqwest::streamable_http_client::post_message(
+            &self.client,
+            uri,
+            message,
+            session_id,
+            auth_token,
+            self.compress_above,
+        )
+        .await
+    }
+
+    async fn delete_session(
+        &self,
+        uri: Arc<str>,
+        session_id: Arc<str>,
+        auth_token: Option<String>,
+    ) -> Result<(), crate::transport::streamable_http_client::StreamableHttpError<Self::Error>>
+    {
+        let auth_token = self.bearer_token(auth_token).await;
+        crate::transport::streamable_http_client::StreamableHttpClient::delete_session(
+            &self.client,
+            uri,
+            session_id,
+            auth_token,
+        )
+        .await
+    }
+
+    async fn get_stream(
+        &self,
+        uri: Arc<str>,
+        session_id: Arc<str>,
+        last_event_id: Option<String>,
+        auth_token: Option<String>,
+    ) -> Result<
+        futures::stream::BoxStream<'static, Result<sse_stream::Sse, sse_stream::Error>>,
+        crate::transport::streamable_http_client::StreamableHttpError<Self::Error>,
+    > {
+        let auth_token = self.bearer_token(auth_token).await;
+        crate::transport::streamable_http_client::StreamableHttpClient::get_stream(
+            &self.client,
+            uri,
+            session_id,
+            last_event_id,
+            auth_token,
+        )
+        .await
+    }
+}
@@ -0,0 +1,403 @@
+//! Two transport halves connected in memory, for tests and for embedding a server in its client
+//!
+//! The messages are passed as they are, without any IO or serialization, and arrive in the
+//! order they were sent. A [`LinkConfig`] adds latency to one direction, or loses and corrupts
+//! its messages, to see how the services behave on a faulty network.
+//!
+//! ```rust,ignore
+//! let (server, client) = in_process::serve_directly(Counter::new(), ()).await?;
+//! let tools = client.list_all_tools().await?;
+//!
+//! // the responses of the server are lost
+//! let config = InProcessConfig::default().to_client(LinkConfig::new().with_drop_rate(1.0));
+//! let (server, client) = in_process::serve_directly_with(config, Counter::new(), ()).await?;
+//! ```
+use std::{
+    hash::{BuildHasher, Hasher},
+    sync::Arc,
+    time::Duration,
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+use tokio::{sync::mpsc, time::Instant};
+
+use super::Transport;
+use crate::{
+    RoleClient, RoleServer,
+    service::{RxJsonRpcMessage, ServiceRole, TxJsonRpcMessage},
+};
+
+/// What becomes of a message, told by a [`LinkConfig::injector`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    Deliver,
+    /// The message is lost
+    Drop,
+    /// One byte of the serialized message is changed, see [`LinkConfig::corrupt_rate`]
+    Corrupt,
+    /// The message, and the ones sent after it, arrive this much later
+    Delay(Duration),
+}
+
+pub type FaultInjector = Arc<dyn Fn(&serde_json::Value) -> Fault + Send + Sync>;
+
+/// How the messages go in one direction
+#[derive(Clone, Default)]
+pub struct LinkConfig {
+    /// The time every message takes to arrive
+    pub latency: Duration,
+    /// The probability for a message to be lost, from 0 to 1
+    pub drop_rate: f64,
+    /// The probability for one byte of a message to be changed, from 0 to 1. A corrupted
+    /// message which is no longer valid JSON-RPC is dropped, as a stream transport would
+    pub corrupt_rate: f64,
+    /// Decide what becomes of every message from its JSON. Any other fault than
+    /// [`Fault::Deliver`] takes precedence over the rates
+    pub injector: Option<FaultInjector>,
+}
+
+impl std::fmt::Debug for LinkConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LinkConfig")
+            .field("latency", &self.latency)
+            .field("drop_rate", &self.drop_rate)
+            .field("corrupt_rate", &self.corrupt_rate)
+            .field("injector", &self.injector.is_some())
+            .finish()
+    }
+}
+
+impl LinkConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn with_drop_rate(mut self, drop_rate: f64) -> Self {
+        self.drop_rate = drop_rate;
+        self
+    }
+
+    pub fn with_corrupt_rate(mut self, corrupt_rate: f64) -> Self {
+        self.corrupt_rate = corrupt_rate;
+        self
+    }
+
+    pub fn with_injector(
+        mut self,
+        injector: impl Fn(&serde_json::Value) -> Fault + Send + Sync + 'static,
+    ) -> Self {
+        self.injector = Some(Arc::new(injector));
+        self
+    }
+
+    fn fault(&self, message: &impl Serialize) -> Fault {
+        if let Some(injector) = &self.injector {
+            match serde_json::to_value(message) {
+                Ok(value) => match injector(&value) {
+                    Fault::Deliver => {}
+                    fault => return fault,
+                },
+                Err(e) => tracing::warn!("fail to serialize message for the fault injector: {e}"),
+            }
+        }
+        if chance(self.drop_rate) {
+            Fault::Drop
+        } else if chance(self.corrupt_rate) {
+            Fault::Corrupt
+        } else {
+            Fault::Deliver
+        }
+    }
+}
+
+/// The links between the two halves of a [`pair_with`]
+#[derive(Debug, Clone, Default)]
+pub struct InProcessConfig {
+    /// The messages of the client to the server
+    pub to_server: LinkConfig,
+    /// The messages of the server to the client
+    pub to_client: LinkConfig,
+}
+
+impl InProcessConfig {
+    pub fn to_server(mut self, link: LinkConfig) -> Self {
+        self.to_server = link;
+        self
+    }
+
+    pub fn to_client(mut self, link: LinkConfig) -> Self {
+        self.to_client = link;
+        self
+    }
+}
+
+fn random() -> u64 {
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
+
+fn chance(probability: f64) -> bool {
+    let unit = (random() >> 11) as f64 / (1u64 << 53) as f64;
+    probability > 0.0 && unit < probability
+}
+
+/// Change one byte of the serialized message, `None` if it can't be decoded anymore
+fn corrupt<M: Serialize + DeserializeOwned>(message: &M) -> Option<M> {
+    let mut bytes = serde_json::to_vec(message).ok()?;
+    let index = (random() % bytes.len() as u64) as usize;
+    bytes[index] ^= 1 << (random() % 7);
+    serde_json::from_slice(&bytes).ok()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InProcessError {
+    #[error("The other half of the transport is closed")]
+    Closed,
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+struct Delivery<M> {
+    at: Option<Instant>,
+    message: M,
+}
+
+/// One half of a [`pair`]
+pub struct InProcessTransport<R: ServiceRole> {
+    tx: Option<mpsc::UnboundedSender<Delivery<TxJsonRpcMessage<R>>>>,
+    rx: mpsc::UnboundedReceiver<Delivery<RxJsonRpcMessage<R>>>,
+    /// The link of the messages sent by this half
+    link: LinkConfig,
+    last_delivery: Option<Instant>,
+    /// Received, waiting for its delivery time; kept here for `receive` to be cancel safe
+    pending: Option<Delivery<RxJsonRpcMessage<R>>>,
+}
+
+impl<R: ServiceRole> std::fmt::Debug for InProcessTransport<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InProcessTransport")
+            .field("link", &self.link)
+            .field("closed", &self.tx.is_none())
+            .finish()
+    }
+}
+
+impl<R: ServiceRole> InProcessTransport<R> {
+    fn route(&mut self, mut message: TxJsonRpcMessage<R>) -> Result<(), InProcessError> {
+        let tx = self.tx.as_ref().ok_or(InProcessError::Closed)?;
+        let mut delay = self.link.latency;
+        match self.link.fault(&message) {
+            Fault::Deliver => {}
+            Fault::Drop => {
+                tracing::debug!(?message, "drop message");
+                return Ok(());
+            }
+            Fault::Corrupt => match corrupt(&message) {
+                Some(corrupted) => {
+                    tracing::debug!(?message, ?corrupted, "corrupt message");
+                    message = corrupted;
+                }
+                None => {
+                    tracing::debug!(?message, "drop message corrupted beyond decoding");
+                    return Ok(());
+                }
+            },
+            Fault::Delay(extra) => delay += extra,
+        }
+        // a message never overtakes the ones sent before it
+        let now = Instant::now();
+        let at = match self.last_delivery {
+            Some(last) if last > now => Some(last.max(now + delay)),
+            _ if delay.is_zero() => None,
+            _ => Some(now + delay),
+        };
+        if at.is_some() {
+            self.last_delivery = at;
+        }
+        tx.send(Delivery { at, message })
+            .map_err(|_| InProcessError::Closed)
+    }
+}
+
+impl<R: ServiceRole> Transport<R> for InProcessTransport<R> {
+    type Error = InProcessError;
+
+    fn send(
+        &mut self,
+        item: TxJsonRpcMessage<R>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        // routed at once, for the messages to keep the order of the calls
+        let result = self.route(item);
+        std::future::ready(result)
+    }
+
+    async fn receive(&mut self) -> Option<RxJsonRpcMessage<R>> {
+        if self.pending.is_none() {
+            self.pending = Some(self.rx.recv().await?);
+        }
+        if let Some(at) = self.pending.as_ref().and_then(|delivery| delivery.at) {
+            tokio::time::sleep_until(at).await;
+        }
+        self.pending.take().map(|delivery| delivery.message)
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        self.tx.take();
+        self.rx.close();
+        Ok(())
+    }
+}
+
+/// Two connected halves, without any fault
+pub fn pair() -> (
+    InProcessTransport<RoleServer>,
+    InProcessTransport<RoleClient>,
+) {
+    pair_with(InProcessConfig::default())
+}
+
+pub fn pair_with(
+    config: InProcessConfig,
+) -> (
+    InProcessTransport<RoleServer>,
+    InProcessTransport<RoleClient>,
+) {
+    let (to_client_tx, to_client_rx) = mpsc::unbounded_channel();
+    let (to_server_tx, to_server_rx) = mpsc::unbounded_channel();
+    let server = InProcessTransport {
+        tx: Some(to_client_tx),
+        rx: to_server_rx,
+        link: config.to_client,
+        last_delivery: None,
+        pending: None,
+    };
+    let client = InProcessTransport {
+        tx: Some(to_server_tx),
+        rx: to_client_rx,
+        link: config.to_server,
+        last_delivery: None,
+        pending: None,
+    };
+    (server, client)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InProcessServeError {
+    #[error("Server failed to initialize: {0}")]
+    Server(#[from] crate::service::ServerInitializeError<InProcessError>),
+    #[error("Client failed to initialize: {0}")]
+    Client(#[from] crate::service::ClientInitializeError<InProcessError>),
+}
+
+/// Connect `server` and `client` with a [`pair`], and initialize them
+///
+/// Unlike [`service::serve_directly`](crate::service::serve_directly), both services go
+/// through the `initialize` handshake.
+pub async fn serve_directly<S, C>(
+    server: S,
+    client: C,
+) -> Result<
+    (
+        crate::service::RunningService<RoleServer, S>,
+        crate::service::RunningService<RoleClient, C>,
+    ),
+    InProcessServeError,
+>
+where
+    S: crate::Service<RoleServer>,
+    C: crate::Service<RoleClient>,
+{
+    serve_directly_with(InProcessConfig::default(), server, client).await
+}
+
+/// [`serve_directly`] over the links of `config`
+pub async fn serve_directly_with<S, C>(
+    config: InProcessConfig,
+    server: S,
+    client: C,
+) -> Result<
+    (
+        crate::service::RunningService<RoleServer, S>,
+        crate::service::RunningService<RoleClient, C>,
+    ),
+    InProcessServeError,
+>
+where
+    S: crate::Service<RoleServer>,
+    C: crate::Service<RoleClient>,
+{
+    use crate::service::ServiceExt;
+    let (server_transport, client_transport) = pair_with(config);
+    let (server, client) = tokio::join!(
+        server.serve(server_transport),
+        client.serve(client_transport)
+    );
+    Ok((server?, client?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{
+        ClientJsonRpcMessage, ClientNotification, InitializedNotification,
+        InitializedNotificationMethod, NumberOrString, PingRequest, PingRequestMethod,
+        ServerJsonRpcMessage,
+    };
+
+    fn ping(id: u32) -> ClientJsonRpcMessage {
+        let ping = PingRequest {
+            method: PingRequestMethod,
+            extensions: Default::default(),
+        };
+        ClientJsonRpcMessage::request(ping.into(), NumberOrString::Number(id.into()))
+    }
+
+    #[tokio::test]
+    async fn test_order_with_latency() {
+        let config =
+            InProcessConfig::default().to_server(LinkConfig::new().with_injector(|message| {
+                match message["id"].as_u64() {
+                    // the first message is slow, the others must wait for it
+                    Some(0) => Fault::Delay(Duration::from_millis(50)),
+                    Some(2) => Fault::Drop,
+                    _ => Fault::Deliver,
+                }
+            }));
+        let (mut server, mut client) = pair_with(config);
+        for id in 0..4 {
+            Transport::<RoleClient>::send(&mut client, ping(id))
+                .await
+                .unwrap();
+        }
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            let message = Transport::<RoleServer>::receive(&mut server).await.unwrap();
+            received.push(message.into_request().unwrap().1);
+        }
+        assert_eq!(received, [0, 1, 3].map(NumberOrString::Number));
+
+        Transport::<RoleServer>::close(&mut server).await.unwrap();
+        assert!(
+            Transport::<RoleClient>::send(
+                &mut client,
+                ClientJsonRpcMessage::notification(ClientNotification::InitializedNotification(
+                    InitializedNotification {
+                        method: InitializedNotificationMethod,
+                        extensions: Default::default(),
+                    }
+                ))
+            )
+            .await
+            .is_err()
+        );
+        let message: Option<ServerJsonRpcMessage> =
+            Transport::<RoleClient>::receive(&mut client).await;
+        assert!(message.is_none());
+    }
+}
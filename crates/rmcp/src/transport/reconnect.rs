@@ -0,0 +1,552 @@
+//! Reconnect a client transport when its connection is lost
+//!
+//! The [`ReconnectingWorker`] makes a new connection with a [`Connector`], usually a closure
+//! starting a network transport, whenever the current one closes. Once reconnected, the
+//! `initialize` request and the `notifications/initialized` notification the client sent first
+//! are sent again, and their answers are kept from the client, so the service goes on as if the
+//! connection had never been lost.
+//!
+//! ```rust,ignore
+//! let (transport, mut state) = ReconnectingTransport::with_connector(
+//!     || SseClientTransport::start("http://localhost:8000/sse"),
+//!     ReconnectPolicy::default().with_in_flight(InFlightPolicy::ReplayIdempotent),
+//! );
+//! let client = ().serve(transport).await?;
+//! while state.changed().await.is_ok() {
+//!     println!("connection: {:?}", *state.borrow());
+//! }
+//! ```
+//!
+//! A failed send is reported to the client as is, only a closed receiving side is taken as a
+//! lost connection.
+use std::{collections::HashMap, time::Duration};
+
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    Transport, TransportStatus,
+    worker::{Worker, WorkerConfig, WorkerContext, WorkerQuitReason, WorkerSendRequest},
+};
+use crate::{
+    RoleClient,
+    model::{
+        ClientJsonRpcMessage, ClientNotification, ClientRequest, ErrorData, GetMethod,
+        JsonRpcBatchResponseItem, JsonRpcMessage, JsonRpcRequest, RequestId, ServerJsonRpcMessage,
+    },
+    service::RetryPolicy,
+    transport::worker::WorkerTransport,
+};
+
+/// The state of the connection of a [`ReconnectingWorker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The first connection is being made
+    Connecting,
+    Connected,
+    /// The connection was lost, `attempt` counts the reconnections tried since
+    Reconnecting {
+        attempt: u32,
+    },
+    /// No reconnection succeeded within the [`ReconnectPolicy`], the transport is closed
+    GaveUp,
+}
+
+/// What becomes of the requests waiting for a response when the connection is lost
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InFlightPolicy {
+    /// Answer them with an error as soon as the connection is lost
+    #[default]
+    Fail,
+    /// Send the requests allowed by [`ReconnectPolicy::retry`] again once reconnected, fail the
+    /// others
+    ReplayIdempotent,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Reconnections tried in a row before giving up, unlimited if `None`
+    pub max_retries: Option<u32>,
+    /// Time spent reconnecting before giving up, unlimited if `None`
+    pub max_elapsed: Option<Duration>,
+    /// The backoff between two reconnections, and the methods replayed with
+    /// [`InFlightPolicy::ReplayIdempotent`]
+    pub retry: RetryPolicy,
+    pub in_flight: InFlightPolicy,
+    /// How long a new connection has to answer the `initialize` request sent again
+    pub handshake_timeout: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: Some(Self::DEFAULT_MAX_RETRIES),
+            max_elapsed: None,
+            retry: RetryPolicy::default(),
+            in_flight: InFlightPolicy::default(),
+            handshake_timeout: Self::DEFAULT_HANDSHAKE_TIMEOUT,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub const DEFAULT_MAX_RETRIES: u32 = 10;
+    pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    pub fn with_unlimited_retries(mut self) -> Self {
+        self.max_retries = None;
+        self
+    }
+
+    pub fn with_max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_in_flight(mut self, in_flight: InFlightPolicy) -> Self {
+        self.in_flight = in_flight;
+        self
+    }
+
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
+
+    /// Whether the `attempt`th reconnection is not to be tried, `elapsed` after the connection
+    /// was lost
+    pub fn gives_up(&self, attempt: u32, elapsed: Duration) -> bool {
+        self.max_retries.is_some_and(|max| attempt > max)
+            || self.max_elapsed.is_some_and(|max| elapsed >= max)
+    }
+
+    /// Whether a request of `method`, waiting for a response when the connection is lost, is
+    /// sent again once reconnected
+    pub fn replays(&self, method: &str) -> bool {
+        // the first initialize is always sent again, the client can't go on without its answer
+        method == "initialize"
+            || (self.in_flight == InFlightPolicy::ReplayIdempotent && self.retry.allows(method))
+    }
+}
+
+/// Makes a new connection to the server
+pub trait Connector: Send + 'static {
+    type Transport: Transport<RoleClient, Error: std::error::Error + Send + Sync + 'static>
+        + 'static;
+    type Error: std::error::Error + Send + Sync + 'static;
+    fn connect(&mut self) -> impl Future<Output = Result<Self::Transport, Self::Error>> + Send;
+}
+
+impl<F, Fut, T, E> Connector for F
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<T, E>> + Send,
+    T: Transport<RoleClient, Error: std::error::Error + Send + Sync + 'static> + 'static,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    type Transport = T;
+    type Error = E;
+    fn connect(&mut self) -> impl Future<Output = Result<Self::Transport, Self::Error>> + Send {
+        self()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReconnectError {
+    #[error("Transport error: {0}")]
+    Transport(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("Initialize rejected by the new connection: {0}")]
+    Initialize(ErrorData),
+    #[error("The new connection didn't answer initialize in time")]
+    HandshakeTimeout,
+    #[error("Transport closed")]
+    Closed,
+    #[error("Join error: {0}")]
+    Join(#[from] tokio::task::JoinError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl ReconnectError {
+    fn transport(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self::Transport(Box::new(error))
+    }
+}
+
+/// What has to be sent again on a new connection
+#[derive(Debug, Default)]
+struct Session {
+    /// The requests sent to the server and not answered yet
+    in_flight: HashMap<RequestId, JsonRpcRequest<ClientRequest>>,
+    /// The `initialize` request, once answered
+    initialize: Option<JsonRpcRequest<ClientRequest>>,
+    initialized: Option<ClientNotification>,
+}
+
+impl Session {
+    fn on_send(&mut self, message: &ClientJsonRpcMessage) {
+        match message {
+            JsonRpcMessage::Request(request) => {
+                self.in_flight.insert(request.id.clone(), request.clone());
+            }
+            JsonRpcMessage::Notification(notification)
+                if matches!(
+                    notification.notification,
+                    ClientNotification::InitializedNotification(_)
+                ) =>
+            {
+                self.initialized = Some(notification.notification.clone());
+            }
+            _ => {}
+        }
+    }
+
+    fn on_receive(&mut self, message: &ServerJsonRpcMessage) {
+        match message {
+            JsonRpcMessage::Response(response) => self.answered(&response.id, true),
+            JsonRpcMessage::Error(error) => self.answered(&error.id, false),
+            JsonRpcMessage::BatchResponse(items) => {
+                for item in items {
+                    match item {
+                        JsonRpcBatchResponseItem::Response(response) => {
+                            self.answered(&response.id, true)
+                        }
+                        JsonRpcBatchResponseItem::Error(error) => self.answered(&error.id, false),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn answered(&mut self, id: &RequestId, ok: bool) {
+        if let Some(request) = self.in_flight.remove(id) {
+            if ok && matches!(request.request, ClientRequest::InitializeRequest(_)) {
+                self.initialize = Some(request);
+            }
+        }
+    }
+
+    /// Take the in-flight requests which won't be sent again
+    fn take_lost(&mut self, policy: &ReconnectPolicy) -> Vec<RequestId> {
+        let lost = self
+            .in_flight
+            .iter()
+            .filter(|(_, request)| !policy.replays(request.request.method()))
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+        for id in &lost {
+            self.in_flight.remove(id);
+        }
+        lost
+    }
+}
+
+/// A client transport worker, reconnecting with its [`Connector`] when the connection is lost
+pub struct ReconnectingWorker<C> {
+    connector: C,
+    policy: ReconnectPolicy,
+    state: watch::Sender<ConnectionState>,
+    status: watch::Sender<TransportStatus>,
+}
+
+impl<C: std::fmt::Debug> std::fmt::Debug for ReconnectingWorker<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReconnectingWorker")
+            .field("connector", &self.connector)
+            .field("policy", &self.policy)
+            .field("state", &*self.state.borrow())
+            .finish()
+    }
+}
+
+pub type ReconnectingTransport<C> = WorkerTransport<ReconnectingWorker<C>>;
+
+impl<C: Connector> ReconnectingTransport<C> {
+    /// Spawn the worker, returning the transport and the state of its connection
+    pub fn with_connector(
+        connector: C,
+        policy: ReconnectPolicy,
+    ) -> (Self, watch::Receiver<ConnectionState>) {
+        let worker = ReconnectingWorker::new(connector, policy);
+        let state = worker.state();
+        (WorkerTransport::spawn(worker), state)
+    }
+}
+
+impl<C: Connector> ReconnectingWorker<C> {
+    pub fn new(connector: C, policy: ReconnectPolicy) -> Self {
+        let (state, _) = watch::channel(ConnectionState::Connecting);
+        Self {
+            connector,
+            policy,
+            state,
+            status: watch::Sender::default(),
+        }
+    }
+
+    fn set_state(&self, state: ConnectionState) {
+        if let ConnectionState::Reconnecting { attempt } = state {
+            self.status.send_replace(TransportStatus::Degraded(
+                format!("reconnecting, attempt {attempt}").into(),
+            ));
+        } else if state == ConnectionState::Connected {
+            self.status.send_replace(TransportStatus::Connected);
+        }
+        self.state.send_replace(state);
+    }
+
+    /// Follow the state of the connection
+    pub fn state(&self) -> watch::Receiver<ConnectionState> {
+        self.state.subscribe()
+    }
+
+    /// Connect, and bring the new connection to where the lost one was
+    async fn connect(&mut self, session: &Session) -> Result<C::Transport, ReconnectError> {
+        let mut transport = self
+            .connector
+            .connect()
+            .await
+            .map_err(ReconnectError::transport)?;
+        if let Some(initialize) = session.initialize.clone() {
+            let id = initialize.id.clone();
+            transport
+                .send(JsonRpcMessage::Request(initialize))
+                .await
+                .map_err(ReconnectError::transport)?;
+            let handshake = async {
+                loop {
+                    match transport.receive().await {
+                        Some(JsonRpcMessage::Response(response)) if response.id == id => {
+                            return Ok(());
+                        }
+                        Some(JsonRpcMessage::Error(error)) if error.id == id => {
+                            return Err(ReconnectError::Initialize(error.error));
+                        }
+                        Some(message) => {
+                            tracing::debug!(?message, "ignore message before initialized");
+                        }
+                        None => return Err(ReconnectError::Closed),
+                    }
+                }
+            };
+            tokio::time::timeout(self.policy.handshake_timeout, handshake)
+                .await
+                .map_err(|_| ReconnectError::HandshakeTimeout)??;
+            if let Some(initialized) = session.initialized.clone() {
+                transport
+                    .send(JsonRpcMessage::notification(initialized))
+                    .await
+                    .map_err(ReconnectError::transport)?;
+            }
+        }
+        for request in session.in_flight.values() {
+            tracing::debug!(id = %request.id, method = request.request.method(), "replay request");
+            transport
+                .send(JsonRpcMessage::Request(request.clone()))
+                .await
+                .map_err(ReconnectError::transport)?;
+        }
+        Ok(transport)
+    }
+
+    /// Connect with the backoff of the policy, until connected or given up
+    async fn reconnect(
+        &mut self,
+        session: &Session,
+        ct: &CancellationToken,
+    ) -> Result<C::Transport, WorkerQuitReason> {
+        let started = tokio::time::Instant::now();
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            if self.policy.gives_up(attempt, started.elapsed()) {
+                self.set_state(ConnectionState::GaveUp);
+                return Err(WorkerQuitReason::fatal(
+                    "gave up reconnecting",
+                    format!("after {} attempts", attempt - 1),
+                ));
+            }
+            self.set_state(ConnectionState::Reconnecting { attempt });
+            let backoff = self.policy.retry.backoff(attempt);
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = ct.cancelled() => return Err(WorkerQuitReason::Cancelled),
+            }
+            let connected = tokio::select! {
+                connected = self.connect(session) => connected,
+                _ = ct.cancelled() => return Err(WorkerQuitReason::Cancelled),
+            };
+            match connected {
+                Ok(transport) => {
+                    tracing::info!(attempt, "reconnected");
+                    self.set_state(ConnectionState::Connected);
+                    return Ok(transport);
+                }
+                Err(e) => tracing::warn!(attempt, ?backoff, "fail to reconnect: {e}"),
+            }
+        }
+    }
+}
+
+async fn fail_requests(
+    context: &mut WorkerContext<impl Worker<Role = RoleClient>>,
+    ids: impl IntoIterator<Item = RequestId>,
+) -> Result<(), WorkerQuitReason> {
+    for id in ids {
+        let error = ErrorData::internal_error("connection to the server lost", None);
+        context
+            .send_to_handler(ServerJsonRpcMessage::error(error, id))
+            .await?;
+    }
+    Ok(())
+}
+
+impl<C: Connector> Worker for ReconnectingWorker<C> {
+    type Error = ReconnectError;
+    type Role = RoleClient;
+    fn err_closed() -> Self::Error {
+        ReconnectError::Closed
+    }
+    fn err_join(e: tokio::task::JoinError) -> Self::Error {
+        ReconnectError::Join(e)
+    }
+    fn config(&self) -> WorkerConfig {
+        WorkerConfig {
+            name: Some("ReconnectingWorker".into()),
+            ..Default::default()
+        }
+    }
+    fn status(&self) -> Option<watch::Receiver<TransportStatus>> {
+        Some(self.status.subscribe())
+    }
+    async fn run(mut self, mut context: WorkerContext<Self>) -> Result<(), WorkerQuitReason> {
+        let ct = context.cancellation_token.clone();
+        let mut session = Session::default();
+        let mut transport = match self.connect(&session).await {
+            Ok(transport) => {
+                self.set_state(ConnectionState::Connected);
+                transport
+            }
+            Err(e) => {
+                tracing::warn!("fail to connect: {e}");
+                self.reconnect(&session, &ct).await?
+            }
+        };
+        enum Event<W: Worker> {
+            ToServer(WorkerSendRequest<W>),
+            Sent {
+                id: Option<RequestId>,
+                responder: tokio::sync::oneshot::Sender<Result<(), ReconnectError>>,
+                result: Result<(), ReconnectError>,
+            },
+            FromServer(Option<ServerJsonRpcMessage>),
+        }
+        let mut sends = tokio::task::JoinSet::new();
+        loop {
+            let event = tokio::select! {
+                _ = ct.cancelled() => {
+                    if let Err(e) = transport.close().await {
+                        tracing::debug!("fail to close transport: {e}");
+                    }
+                    return Err(WorkerQuitReason::Cancelled);
+                }
+                request = context.recv_from_handler() => Event::ToServer(request?),
+                Some(sent) = sends.join_next(), if !sends.is_empty() => {
+                    let (id, responder, result) = sent?;
+                    Event::Sent { id, responder, result }
+                }
+                message = transport.receive() => Event::FromServer(message),
+            };
+            match event {
+                Event::ToServer(WorkerSendRequest { message, responder }) => {
+                    session.on_send(&message);
+                    let id = match &message {
+                        JsonRpcMessage::Request(request) => Some(request.id.clone()),
+                        _ => None,
+                    };
+                    let send = transport.send(message);
+                    sends.spawn(async move {
+                        let result = send.await.map_err(ReconnectError::transport);
+                        (id, responder, result)
+                    });
+                    continue;
+                }
+                Event::Sent {
+                    id,
+                    responder,
+                    result,
+                } => {
+                    if let (Err(e), Some(id)) = (&result, id) {
+                        tracing::debug!(%id, "fail to send request: {e}");
+                        session.in_flight.remove(&id);
+                    }
+                    let _ = responder.send(result);
+                    continue;
+                }
+                Event::FromServer(Some(message)) => {
+                    session.on_receive(&message);
+                    context.send_to_handler(message).await?;
+                    continue;
+                }
+                Event::FromServer(None) => {}
+            }
+            tracing::info!(in_flight = session.in_flight.len(), "connection lost");
+            if let Err(e) = transport.close().await {
+                tracing::debug!("fail to close lost transport: {e}");
+            }
+            fail_requests(&mut context, session.take_lost(&self.policy)).await?;
+            transport = match self.reconnect(&session, &ct).await {
+                Ok(transport) => transport,
+                Err(quit) => {
+                    if !matches!(quit, WorkerQuitReason::Cancelled) {
+                        let lost = session.in_flight.drain().map(|(id, _)| id);
+                        fail_requests(&mut context, lost.collect::<Vec<_>>()).await?;
+                    }
+                    return Err(quit);
+                }
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_policy() {
+        let policy = ReconnectPolicy::new()
+            .with_max_retries(3)
+            .with_max_elapsed(Duration::from_secs(10));
+        assert!(!policy.gives_up(3, Duration::from_secs(1)));
+        assert!(policy.gives_up(4, Duration::from_secs(1)));
+        assert!(policy.gives_up(1, Duration::from_secs(10)));
+        assert!(
+            !policy
+                .clone()
+                .with_unlimited_retries()
+                .gives_up(100, Duration::ZERO)
+        );
+
+        assert!(policy.replays("initialize"));
+        assert!(!policy.replays("tools/list"));
+        let replaying = policy.with_in_flight(InFlightPolicy::ReplayIdempotent);
+        assert!(replaying.replays("tools/list"));
+        assert!(!replaying.replays("tools/call"));
+    }
+}
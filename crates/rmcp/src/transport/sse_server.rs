@@ -1,29 +1,48 @@
-use std::{collections::HashMap, io, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    io,
+    net::{Ipv6Addr, SocketAddr, SocketAddrV6},
+    sync::Arc,
+    time::Duration,
+};
 
 use axum::{
     Json, Router,
     extract::{Query, State},
-    http::{StatusCode, request::Parts},
+    http::{HeaderValue, Method, StatusCode, header, request::Parts},
     response::{
-        Response,
+        IntoResponse, Response,
         sse::{Event, KeepAlive, Sse},
     },
     routing::{get, post},
 };
 use futures::{Sink, SinkExt, Stream};
+use tokio::sync::Notify;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_util::sync::{CancellationToken, PollSender};
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tracing::Instrument;
 
+#[cfg(feature = "compression")]
+use crate::transport::common::compression::CompressionConfig;
 use crate::{
     RoleServer, Service,
-    model::ClientJsonRpcMessage,
-    service::{RxJsonRpcMessage, TxJsonRpcMessage},
-    transport::common::axum::{DEFAULT_AUTO_PING_INTERVAL, SessionId, session_id},
+    model::{ClientJsonRpcMessage, ErrorData, RequestId},
+    service::{Identity, RxJsonRpcMessage, TxJsonRpcMessage},
+    transport::{
+        authn::AuthnLayer,
+        common::axum::{DEFAULT_AUTO_PING_INTERVAL, SessionId, session_id},
+    },
 };
 
-type TxStore =
-    Arc<tokio::sync::RwLock<HashMap<SessionId, tokio::sync::mpsc::Sender<ClientJsonRpcMessage>>>>;
+#[derive(Debug, Clone)]
+struct SessionEntry {
+    tx: tokio::sync::mpsc::Sender<ClientJsonRpcMessage>,
+    /// Notified on every message of the client, to keep the session from idling out
+    activity: Arc<Notify>,
+}
+
+type TxStore = Arc<tokio::sync::RwLock<HashMap<SessionId, SessionEntry>>>;
 pub type TransportReceiver = ReceiverStream<RxJsonRpcMessage<RoleServer>>;
 
 #[derive(Clone)]
@@ -32,12 +51,13 @@ struct App {
     transport_tx: tokio::sync::mpsc::UnboundedSender<SseServerTransport>,
     post_path: Arc<str>,
     sse_ping_interval: Duration,
+    session_idle_timeout: Option<Duration>,
+    max_sessions: Option<usize>,
 }
 
 impl App {
     pub fn new(
-        post_path: String,
-        sse_ping_interval: Duration,
+        config: &SseServerConfig,
     ) -> (
         Self,
         tokio::sync::mpsc::UnboundedReceiver<SseServerTransport>,
@@ -47,14 +67,26 @@ impl App {
             Self {
                 txs: Default::default(),
                 transport_tx,
-                post_path: post_path.into(),
-                sse_ping_interval,
+                post_path: config.post_path.as_str().into(),
+                sse_ping_interval: config.sse_keep_alive.unwrap_or(DEFAULT_AUTO_PING_INTERVAL),
+                session_idle_timeout: config.session_idle_timeout,
+                max_sessions: config.max_sessions,
             },
             transport_rx,
         )
     }
 }
 
+/// A 404 with a JSON-RPC error as body, answering the messages of an unknown or expired session
+fn session_not_found(id: Option<RequestId>) -> Response {
+    let body = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": ErrorData::invalid_request("session not found or expired", None),
+    });
+    (StatusCode::NOT_FOUND, Json(body)).into_response()
+}
+
 #[derive(Debug, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PostEventQuery {
@@ -66,18 +98,25 @@ async fn post_event_handler(
     Query(PostEventQuery { session_id }): Query<PostEventQuery>,
     parts: Parts,
     Json(mut message): Json<ClientJsonRpcMessage>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, Response> {
     tracing::debug!(session_id, ?message, "new client message");
-    let tx = {
-        let rg = app.txs.read().await;
-        rg.get(session_id.as_str())
-            .ok_or(StatusCode::NOT_FOUND)?
-            .clone()
+    let id = match &message {
+        ClientJsonRpcMessage::Request(request) => Some(request.id.clone()),
+        _ => None,
+    };
+    let Some(entry) = app.txs.read().await.get(session_id.as_str()).cloned() else {
+        tracing::debug!(session_id, "message for an unknown session");
+        return Err(session_not_found(id));
     };
+    entry.activity.notify_one();
+    if let Some(identity) = parts.extensions.get::<Identity>().cloned() {
+        message.insert_extension(identity);
+    }
     message.insert_extension(parts);
-    if tx.send(message).await.is_err() {
-        tracing::error!("send message error");
-        return Err(StatusCode::GONE);
+    if entry.tx.send(message).await.is_err() {
+        tracing::warn!(session_id, "the service of the session is gone");
+        app.txs.write().await.remove(session_id.as_str());
+        return Err(session_not_found(id));
     }
     Ok(StatusCode::ACCEPTED)
 }
@@ -87,17 +126,30 @@ async fn sse_handler(
 ) -> Result<Sse<impl Stream<Item = Result<Event, io::Error>>>, Response<String>> {
     let session = session_id();
     tracing::info!(%session, "sse connection");
-    use tokio_stream::{StreamExt, wrappers::ReceiverStream};
+    use futures::StreamExt;
+    use tokio_stream::wrappers::ReceiverStream;
     use tokio_util::sync::PollSender;
     let (from_client_tx, from_client_rx) = tokio::sync::mpsc::channel(64);
     let (to_client_tx, to_client_rx) = tokio::sync::mpsc::channel(64);
     let to_client_tx_clone = to_client_tx.clone();
+    let activity = Arc::new(Notify::new());
 
-    app.txs
-        .write()
-        .await
-        .insert(session.clone(), from_client_tx);
-    let session = session.clone();
+    {
+        let mut txs = app.txs.write().await;
+        if app.max_sessions.is_some_and(|max| txs.len() >= max) {
+            tracing::warn!(sessions = txs.len(), "too many sse sessions");
+            let mut response = Response::new("too many sessions".to_string());
+            *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            return Err(response);
+        }
+        txs.insert(
+            session.clone(),
+            SessionEntry {
+                tx: from_client_tx,
+                activity: activity.clone(),
+            },
+        );
+    }
     let stream = ReceiverStream::new(from_client_rx);
     let sink = PollSender::new(to_client_tx);
     let transport = SseServerTransport {
@@ -116,28 +168,42 @@ async fn sse_handler(
     }
     let post_path = app.post_path.as_ref();
     let ping_interval = app.sse_ping_interval;
+    let session_ct = CancellationToken::new();
+    let stream_ct = session_ct.clone();
     let stream = futures::stream::once(futures::future::ok(
         Event::default()
             .event("endpoint")
             .data(format!("{post_path}?sessionId={session}")),
     ))
-    .chain(ReceiverStream::new(to_client_rx).map(|message| {
-        match serde_json::to_string(&message) {
+    .chain(
+        ReceiverStream::new(to_client_rx).map(|message| match serde_json::to_string(&message) {
             Ok(bytes) => Ok(Event::default().event("message").data(&bytes)),
             Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
-        }
-    }));
+        }),
+    )
+    .take_until(async move { stream_ct.cancelled().await });
 
+    let idle_timeout = app.session_idle_timeout;
     tokio::spawn(async move {
-        // Wait for connection closure
-        to_client_tx_clone.closed().await;
-
-        // Clean up session
-        let session_id = session.clone();
-        let tx_store = app.txs.clone();
-        let mut txs = tx_store.write().await;
-        txs.remove(&session_id);
-        tracing::debug!(%session_id, "Closed session and cleaned up resources");
+        let idled_out = async {
+            let Some(idle_timeout) = idle_timeout else {
+                return futures::future::pending().await;
+            };
+            while tokio::time::timeout(idle_timeout, activity.notified())
+                .await
+                .is_ok()
+            {}
+        };
+        tokio::select! {
+            // Wait for connection closure
+            _ = to_client_tx_clone.closed() => {}
+            _ = idled_out => tracing::info!(%session, "sse session idled out"),
+        }
+
+        // Clean up session, the service stops with the stream of the client messages
+        session_ct.cancel();
+        app.txs.write().await.remove(&session);
+        tracing::debug!(%session, "Closed session and cleaned up resources");
     });
 
     Ok(Sse::new(stream).keep_alive(KeepAlive::new().interval(ping_interval)))
@@ -218,6 +284,71 @@ pub struct SseServerConfig {
     pub post_path: String,
     pub ct: CancellationToken,
     pub sse_keep_alive: Option<Duration>,
+    /// The CORS layer of both endpoints, see [`SseServerConfig::with_allowed_origins`]
+    pub cors: Option<CorsLayer>,
+    /// Tear a session and its service down when the client posts nothing for this long
+    pub session_idle_timeout: Option<Duration>,
+    /// Refuse the new event streams with a 503 while this many sessions are open
+    pub max_sessions: Option<usize>,
+    /// Authenticate the requests to both endpoints, see [`SseServerConfig::with_authn`]
+    pub authn: Option<AuthnLayer>,
+    /// Compress the event streams of the clients accepting it, and decompress their messages
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    pub compression: Option<CompressionConfig>,
+}
+
+impl Default for SseServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::LOCALHOST, 80, 0, 0)),
+            sse_path: "/sse".to_string(),
+            post_path: "/message".to_string(),
+            ct: CancellationToken::new(),
+            sse_keep_alive: None,
+            cors: None,
+            session_idle_timeout: None,
+            max_sessions: None,
+            authn: None,
+            #[cfg(feature = "compression")]
+            compression: None,
+        }
+    }
+}
+
+impl SseServerConfig {
+    /// Let the browsers of `origins` open the event stream and post messages
+    pub fn with_allowed_origins<I>(self, origins: I) -> Self
+    where
+        I: IntoIterator<Item = HeaderValue>,
+    {
+        self.with_cors(
+            CorsLayer::new()
+                .allow_origin(AllowOrigin::list(origins))
+                .allow_methods([Method::GET, Method::POST])
+                .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION]),
+        )
+    }
+
+    /// Use `cors` as is, for the policies [`SseServerConfig::with_allowed_origins`] can't express
+    pub fn with_cors(mut self, cors: CorsLayer) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+
+    /// Reject the clients `authn` doesn't authenticate, the others get their
+    /// [`Identity`] in the extensions of their messages and in their session
+    pub fn with_authn(mut self, authn: AuthnLayer) -> Self {
+        self.authn = Some(authn);
+        self
+    }
+
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    pub fn with_compression(mut self, compression: CompressionConfig) -> Self {
+        self.compression = Some(compression);
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -230,10 +361,7 @@ impl SseServer {
     pub async fn serve(bind: SocketAddr) -> io::Result<Self> {
         Self::serve_with_config(SseServerConfig {
             bind,
-            sse_path: "/sse".to_string(),
-            post_path: "/message".to_string(),
-            ct: CancellationToken::new(),
-            sse_keep_alive: None,
+            ..Default::default()
         })
         .await
     }
@@ -259,14 +387,25 @@ impl SseServer {
     /// Warning: This function creates a new SseServer instance with the provided configuration.
     /// `App.post_path` may be incorrect if using `Router` as an embedded router.
     pub fn new(config: SseServerConfig) -> (SseServer, Router) {
-        let (app, transport_rx) = App::new(
-            config.post_path.clone(),
-            config.sse_keep_alive.unwrap_or(DEFAULT_AUTO_PING_INTERVAL),
-        );
-        let router = Router::new()
+        let (app, transport_rx) = App::new(&config);
+        let mut router = Router::new()
             .route(&config.sse_path, get(sse_handler))
             .route(&config.post_path, post(post_event_handler))
             .with_state(app);
+        #[cfg(feature = "compression")]
+        if let Some(compression) = config.compression {
+            router = router.layer(axum::middleware::from_fn_with_state(
+                compression,
+                crate::transport::common::compression::compression,
+            ));
+        }
+        // inside CORS, the preflights and the headers of rejections are handled by CORS
+        if let Some(authn) = config.authn.clone() {
+            router = router.layer(authn);
+        }
+        if let Some(cors) = config.cors.clone() {
+            router = router.layer(cors);
+        }
 
         let server = SseServer {
             transport_rx,
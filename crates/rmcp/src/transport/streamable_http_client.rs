@@ -195,6 +195,7 @@ impl<C: StreamableHttpClient + Default> StreamableHttpClientWorker<C> {
                 uri: url.into(),
                 retry_config: Arc::new(ExponentialBackoff::default()),
                 channel_buffer_capacity: 16,
+                batch_window: None,
             },
         }
     }
@@ -237,6 +238,64 @@ impl<C: StreamableHttpClient> StreamableHttpClientWorker<C> {
     }
 }
 
+impl<C: StreamableHttpClient> StreamableHttpClientWorker<C> {
+    /// Add the requests and notifications sent within `window` to `batch`, returning the
+    /// message which ended it early
+    async fn collect_batch(
+        context: &mut super::worker::WorkerContext<Self>,
+        batch: &mut Vec<WorkerSendRequest<Self>>,
+        window: Duration,
+    ) -> Result<Option<WorkerSendRequest<Self>>, WorkerQuitReason> {
+        if !batch
+            .iter()
+            .all(|send_request| is_batchable(&send_request.message))
+        {
+            return Ok(None);
+        }
+        let deadline = tokio::time::Instant::now() + window;
+        loop {
+            let send_request =
+                match tokio::time::timeout_at(deadline, context.recv_from_handler()).await {
+                    Ok(send_request) => send_request?,
+                    Err(_elapsed) => return Ok(None),
+                };
+            if !is_batchable(&send_request.message) {
+                return Ok(Some(send_request));
+            }
+            batch.push(send_request);
+        }
+    }
+}
+
+fn is_batchable(message: &ClientJsonRpcMessage) -> bool {
+    matches!(
+        message,
+        ClientJsonRpcMessage::Request(_) | ClientJsonRpcMessage::Notification(_)
+    )
+}
+
+/// The message to post for `batch`, a batch request unless there is a single message, and
+/// the responders of its messages in order
+#[allow(clippy::type_complexity)]
+fn into_batch_message<W: Worker<Role = RoleClient>>(
+    mut batch: Vec<WorkerSendRequest<W>>,
+) -> (
+    ClientJsonRpcMessage,
+    Vec<tokio::sync::oneshot::Sender<Result<(), W::Error>>>,
+) {
+    if batch.len() == 1 {
+        let WorkerSendRequest { message, responder } = batch.remove(0);
+        return (message, vec![responder]);
+    }
+    let mut items = Vec::with_capacity(batch.len());
+    let mut responders = Vec::with_capacity(batch.len());
+    for WorkerSendRequest { message, responder } in batch {
+        items.extend(message.into_batch_request_item().ok());
+        responders.push(responder);
+    }
+    (ClientJsonRpcMessage::BatchRequest(items), responders)
+}
+
 impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
     type Role = RoleClient;
     type Error = StreamableHttpError<C::Error>;
@@ -340,6 +399,8 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
             StreamResult(Result<(), StreamableHttpError<E>>),
         }
         let mut streams = tokio::task::JoinSet::new();
+        // the message which ended the last batch
+        let mut pending = None::<WorkerSendRequest<Self>>;
         match self
             .client
             .get_stream(config.uri.clone(), session_id.clone(), None, None)
@@ -375,36 +436,44 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
             }
         }
         loop {
-            let event = tokio::select! {
-                _ = transport_task_ct.cancelled() => {
-                    tracing::debug!("cancelled");
-                    return Err(WorkerQuitReason::Cancelled);
-                }
-                message = context.recv_from_handler() => {
-                    let message = message?;
-                    Event::ClientMessage(message)
-                },
-                message = sse_worker_rx.recv() => {
-                    let Some(message) = message else {
-                        tracing::trace!("transport dropped, exiting");
-                        return Err(WorkerQuitReason::HandlerTerminated);
-                    };
-                    Event::ServerMessage(message)
-                },
-                terminated_stream = streams.join_next(), if !streams.is_empty() => {
-                    match terminated_stream {
-                        Some(result) => {
-                            Event::StreamResult(result.map_err(StreamableHttpError::TokioJoinError).and_then(std::convert::identity))
-                        }
-                        None => {
-                            continue
+            let event = if let Some(send_request) = pending.take() {
+                Event::ClientMessage(send_request)
+            } else {
+                tokio::select! {
+                    _ = transport_task_ct.cancelled() => {
+                        tracing::debug!("cancelled");
+                        return Err(WorkerQuitReason::Cancelled);
+                    }
+                    message = context.recv_from_handler() => {
+                        let message = message?;
+                        Event::ClientMessage(message)
+                    },
+                    message = sse_worker_rx.recv() => {
+                        let Some(message) = message else {
+                            tracing::trace!("transport dropped, exiting");
+                            return Err(WorkerQuitReason::HandlerTerminated);
+                        };
+                        Event::ServerMessage(message)
+                    },
+                    terminated_stream = streams.join_next(), if !streams.is_empty() => {
+                        match terminated_stream {
+                            Some(result) => {
+                                Event::StreamResult(result.map_err(StreamableHttpError::TokioJoinError).and_then(std::convert::identity))
+                            }
+                            None => {
+                                continue
+                            }
                         }
                     }
                 }
             };
             match event {
                 Event::ClientMessage(send_request) => {
-                    let WorkerSendRequest { message, responder } = send_request;
+                    let mut batch = vec![send_request];
+                    if let Some(window) = config.batch_window {
+                        pending = Self::collect_batch(&mut context, &mut batch, window).await?;
+                    }
+                    let (message, responders) = into_batch_message(batch);
                     let response = self
                         .client
                         .post_message(config.uri.clone(), message, Some(session_id.clone()), None)
@@ -438,7 +507,21 @@ impl<C: StreamableHttpClient> Worker for StreamableHttpClientWorker<C> {
                             Ok(())
                         }
                     };
-                    let _ = responder.send(send_result);
+                    // the error itself goes to the first message of the batch
+                    let mut responders = responders.into_iter();
+                    let first = responders.next();
+                    for responder in responders {
+                        let result = match &send_result {
+                            Ok(()) => Ok(()),
+                            Err(error) => Err(StreamableHttpError::Io(std::io::Error::other(
+                                error.to_string(),
+                            ))),
+                        };
+                        let _ = responder.send(result);
+                    }
+                    if let Some(responder) = first {
+                        let _ = responder.send(send_result);
+                    }
                 }
                 Event::ServerMessage(json_rpc_message) => {
                     // send the message to the handler
@@ -470,6 +553,9 @@ pub struct StreamableHttpClientTransportConfig {
     pub uri: Arc<str>,
     pub retry_config: Arc<dyn SseRetryPolicy>,
     pub channel_buffer_capacity: usize,
+    /// Post the requests and notifications sent within this window after the first one in
+    /// one batch, see [`with_batch_window`](Self::with_batch_window)
+    pub batch_window: Option<Duration>,
 }
 
 impl StreamableHttpClientTransportConfig {
@@ -479,6 +565,15 @@ impl StreamableHttpClientTransportConfig {
             ..Default::default()
         }
     }
+
+    /// Coalesce the requests and notifications sent within `window` into one post
+    ///
+    /// The first message waits for at most `window`, a response to the server ends the batch
+    /// early. The server answers the requests of a batch with one batch.
+    pub fn with_batch_window(mut self, window: Duration) -> Self {
+        self.batch_window = Some(window);
+        self
+    }
 }
 
 impl Default for StreamableHttpClientTransportConfig {
@@ -487,6 +582,7 @@ impl Default for StreamableHttpClientTransportConfig {
             uri: "localhost".into(),
             retry_config: Arc::new(ExponentialBackoff::default()),
             channel_buffer_capacity: 16,
+            batch_window: None,
         }
     }
 }
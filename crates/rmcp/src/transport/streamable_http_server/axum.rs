@@ -22,12 +22,18 @@ use tokio_util::sync::CancellationToken;
 use tracing::Instrument;
 
 use super::session::{EventId, SessionHandle, SessionWorker, StreamableHttpMessageReceiver};
+#[cfg(feature = "compression")]
+use crate::transport::common::compression::CompressionConfig;
 use crate::{
     RoleServer, Service,
-    model::ClientJsonRpcMessage,
-    transport::common::{
-        axum::{DEFAULT_AUTO_PING_INTERVAL, SessionId, session_id},
-        http_header::{HEADER_LAST_EVENT_ID, HEADER_SESSION_ID},
+    model::{ClientJsonRpcMessage, JsonRpcBatchRequestItem},
+    service::Identity,
+    transport::{
+        authn::AuthnLayer,
+        common::{
+            axum::{DEFAULT_AUTO_PING_INTERVAL, SessionId, session_id},
+            http_header::{HEADER_LAST_EVENT_ID, HEADER_SESSION_ID},
+        },
     },
 };
 type SessionManager = Arc<tokio::sync::RwLock<HashMap<SessionId, SessionHandle>>>;
@@ -70,6 +76,16 @@ fn receiver_as_stream(
     })
 }
 
+fn has_request(message: &ClientJsonRpcMessage) -> bool {
+    match message {
+        ClientJsonRpcMessage::Request(_) => true,
+        ClientJsonRpcMessage::BatchRequest(items) => items
+            .iter()
+            .any(|item| matches!(item, JsonRpcBatchRequestItem::Request(_))),
+        _ => false,
+    }
+}
+
 async fn post_handler(
     State(app): State<App>,
     parts: Parts,
@@ -89,9 +105,15 @@ async fn post_handler(
             session.clone()
         };
         // inject request part
+        if let Some(identity) = parts.extensions.get::<Identity>().cloned() {
+            message.insert_extension(identity);
+        }
         message.insert_extension(parts);
         match &message {
-            ClientJsonRpcMessage::Request(_) | ClientJsonRpcMessage::BatchRequest(_) => {
+            // a batch of notifications is only accepted
+            ClientJsonRpcMessage::Request(_) | ClientJsonRpcMessage::BatchRequest(_)
+                if has_request(&message) =>
+            {
                 let receiver = handle.establish_request_wise_channel().await.map_err(|e| {
                     (
                         StatusCode::INTERNAL_SERVER_ERROR,
@@ -135,6 +157,9 @@ async fn post_handler(
         // expect initialize message
         let session_id = session_id();
         // inject request part
+        if let Some(identity) = parts.extensions.get::<Identity>().cloned() {
+            message.insert_extension(identity);
+        }
         message.insert_extension(parts);
         let (session, transport) =
             super::session::create_session(session_id.clone(), Default::default());
@@ -250,6 +275,12 @@ pub struct StreamableHttpServerConfig {
     pub path: String,
     pub ct: CancellationToken,
     pub sse_keep_alive: Option<Duration>,
+    /// Authenticate every request to the endpoint, see [`StreamableHttpServerConfig::with_authn`]
+    pub authn: Option<AuthnLayer>,
+    /// Compress the responses of the clients accepting it, and decompress their messages
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    pub compression: Option<CompressionConfig>,
 }
 impl Default for StreamableHttpServerConfig {
     fn default() -> Self {
@@ -258,10 +289,29 @@ impl Default for StreamableHttpServerConfig {
             path: "/".to_string(),
             ct: CancellationToken::new(),
             sse_keep_alive: None,
+            authn: None,
+            #[cfg(feature = "compression")]
+            compression: None,
         }
     }
 }
 
+impl StreamableHttpServerConfig {
+    /// Reject the clients `authn` doesn't authenticate, the others get their
+    /// [`Identity`] in the extensions of their messages and in their session
+    pub fn with_authn(mut self, authn: AuthnLayer) -> Self {
+        self.authn = Some(authn);
+        self
+    }
+
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    pub fn with_compression(mut self, compression: CompressionConfig) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+}
+
 #[derive(Debug)]
 pub struct StreamableHttpServer {
     transport_rx: tokio::sync::mpsc::UnboundedReceiver<SessionWorker>,
@@ -300,12 +350,22 @@ impl StreamableHttpServer {
     pub fn new(config: StreamableHttpServerConfig) -> (StreamableHttpServer, Router) {
         let (app, transport_rx) =
             App::new(config.sse_keep_alive.unwrap_or(DEFAULT_AUTO_PING_INTERVAL));
-        let router = Router::new()
+        let mut router = Router::new()
             .route(
                 &config.path,
                 get(get_handler).post(post_handler).delete(delete_handler),
             )
             .with_state(app);
+        #[cfg(feature = "compression")]
+        if let Some(compression) = config.compression {
+            router = router.layer(axum::middleware::from_fn_with_state(
+                compression,
+                crate::transport::common::compression::compression,
+            ));
+        }
+        if let Some(authn) = config.authn.clone() {
+            router = router.layer(authn);
+        }
 
         let server = StreamableHttpServer {
             transport_rx,
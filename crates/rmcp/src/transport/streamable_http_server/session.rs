@@ -65,7 +65,8 @@ impl std::str::FromStr for EventId {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if let Some((index, request_id)) = s.split_once("/") {
             let index = usize::from_str(index).map_err(EventIdParseError::InvalidIndex)?;
-            let request_id = u64::from_str(request_id).map_err(EventIdParseError::InvalidIndex)?;
+            let request_id =
+                u64::from_str(request_id).map_err(EventIdParseError::InvalidNumericRequestId)?;
             Ok(EventId {
                 http_request_id: Some(request_id),
                 index,
@@ -124,11 +125,21 @@ impl CachedTx {
         });
     }
 
-    async fn sync(&mut self, index: usize) -> Result<(), SessionError> {
+    /// End the stream once its pending messages are received, keeping the cache for resuming
+    fn close(&mut self) {
+        let (tx, _) = tokio::sync::mpsc::channel(1);
+        self.tx = tx;
+    }
+
+    /// Replay the cached messages after `last_index`, or all of them
+    async fn sync(&mut self, last_index: Option<usize>) -> Result<(), SessionError> {
         let Some(front) = self.cache.front() else {
             return Ok(());
         };
-        let sync_index = index.saturating_sub(front.event_id.index);
+        let sync_index = match last_index {
+            Some(index) => (index + 1).saturating_sub(front.event_id.index),
+            None => 0,
+        };
         if sync_index > self.cache.len() {
             // invalid index
             return Err(SessionError::InvalidEventId);
@@ -148,6 +159,8 @@ impl CachedTx {
 struct HttpRequestWise {
     resources: HashSet<ResourceKey>,
     tx: CachedTx,
+    // every request answered, kept for the client to resume
+    completed: bool,
 }
 
 type HttpRequestId = u64;
@@ -162,6 +175,8 @@ pub struct SessionWorker {
     next_http_request_id: HttpRequestId,
     tx_router: HashMap<HttpRequestId, HttpRequestWise>,
     resource_router: HashMap<ResourceKey, HttpRequestId>,
+    // the completed request wise channels, oldest first
+    completed: VecDeque<HttpRequestId>,
     common: CachedTx,
     event_rx: Receiver<SessionEvent>,
     session_config: SessionConfig,
@@ -218,13 +233,38 @@ impl SessionWorker {
             tracing::trace!(?resource, http_request_id, "unregister resource");
             if let Some(channel) = self.tx_router.get_mut(&http_request_id) {
                 channel.resources.remove(resource);
-                if channel.resources.is_empty() {
-                    tracing::debug!(http_request_id, "close http request wise channel");
-                    self.tx_router.remove(&http_request_id);
+                let pending = channel
+                    .resources
+                    .iter()
+                    .any(|resource| matches!(resource, ResourceKey::McpRequestId(_)));
+                if !pending {
+                    self.complete_request_wise_channel(http_request_id);
                 }
             }
         }
     }
+    /// Every request of the channel is answered, end its stream
+    ///
+    /// The last completed channels stay resumable, for a client losing the connection before
+    /// the responses reached it.
+    fn complete_request_wise_channel(&mut self, http_request_id: HttpRequestId) {
+        let Some(channel) = self.tx_router.get_mut(&http_request_id) else {
+            return;
+        };
+        // the progress tokens of the answered requests
+        for resource in channel.resources.drain() {
+            self.resource_router.remove(&resource);
+        }
+        tracing::debug!(http_request_id, "close http request wise channel");
+        channel.tx.close();
+        channel.completed = true;
+        self.completed.push_back(http_request_id);
+        if self.completed.len() > self.session_config.channel_capacity {
+            if let Some(expired) = self.completed.pop_front() {
+                self.tx_router.remove(&expired);
+            }
+        }
+    }
     fn register_resource(&mut self, resource: ResourceKey, http_request_id: HttpRequestId) {
         tracing::trace!(?resource, http_request_id, "register resource");
         if let Some(channel) = self.tx_router.get_mut(&http_request_id) {
@@ -242,7 +282,7 @@ impl SessionWorker {
             ResourceKey::McpRequestId(request.id.clone()),
             http_request_id,
         );
-        if let Some(progress_token) = request.request.get_meta().get_progress_token() {
+        if let Some(progress_token) = request.request.get_meta().progress_token() {
             self.register_resource(
                 ResourceKey::ProgressToken(progress_token.clone()),
                 http_request_id,
@@ -274,6 +314,7 @@ impl SessionWorker {
             HttpRequestWise {
                 resources: Default::default(),
                 tx: CachedTx::new(tx, Some(http_request_id)),
+                completed: false,
             },
         );
         tracing::debug!(http_request_id, "establish new request wise channel");
@@ -353,9 +394,28 @@ impl SessionWorker {
                     OutboundChannel::Common
                 }
             }
-            ServerJsonRpcMessage::BatchRequest(_) | ServerJsonRpcMessage::BatchResponse(_) => {
-                // the server side should never yield a batch request or response now
-                unreachable!("server side won't yield batch request or response")
+            // the responses of a batch request, which was posted in one http request
+            ServerJsonRpcMessage::BatchResponse(items) => {
+                let http_request_id = items.iter().find_map(|item| {
+                    let id = match item {
+                        crate::model::JsonRpcBatchResponseItem::Response(response) => &response.id,
+                        crate::model::JsonRpcBatchResponseItem::Error(error) => &error.id,
+                    };
+                    self.resource_router
+                        .get(&ResourceKey::McpRequestId(id.clone()))
+                });
+                if let Some(id) = http_request_id {
+                    OutboundChannel::RequestWise {
+                        id: *id,
+                        close: false,
+                    }
+                } else {
+                    OutboundChannel::Common
+                }
+            }
+            ServerJsonRpcMessage::BatchRequest(_) => {
+                // the server side should never yield a batch request now
+                unreachable!("server side won't yield batch request")
             }
         }
     }
@@ -392,9 +452,12 @@ impl SessionWorker {
                 let channel = tokio::sync::mpsc::channel(self.session_config.channel_capacity);
                 let (tx, rx) = channel;
                 request_wise.tx.tx = tx;
-                let index = last_event_id.index;
                 // sync messages after index
-                request_wise.tx.sync(index).await?;
+                request_wise.tx.sync(Some(last_event_id.index)).await?;
+                if request_wise.completed {
+                    // the replayed messages end the stream
+                    request_wise.tx.close();
+                }
                 Ok(StreamableHttpMessageReceiver {
                     http_request_id: Some(http_request_id),
                     inner: rx,
@@ -404,9 +467,8 @@ impl SessionWorker {
                 let channel = tokio::sync::mpsc::channel(self.session_config.channel_capacity);
                 let (tx, rx) = channel;
                 self.common.tx = tx;
-                let index = last_event_id.index;
                 // sync messages after index
-                self.common.sync(index).await?;
+                self.common.sync(Some(last_event_id.index)).await?;
                 Ok(StreamableHttpMessageReceiver {
                     http_request_id: None,
                     inner: rx,
@@ -414,6 +476,17 @@ impl SessionWorker {
             }
         }
     }
+    async fn establish_common_channel(
+        &mut self,
+    ) -> Result<StreamableHttpMessageReceiver, SessionError> {
+        let (tx, rx) = tokio::sync::mpsc::channel(self.session_config.channel_capacity);
+        self.common.tx = tx;
+        self.common.sync(None).await?;
+        Ok(StreamableHttpMessageReceiver {
+            http_request_id: None,
+            inner: rx,
+        })
+    }
 }
 
 enum SessionEvent {
@@ -428,6 +501,9 @@ enum SessionEvent {
         id: HttpRequestId,
         responder: oneshot::Sender<Result<(), SessionError>>,
     },
+    EstablishCommonChannel {
+        responder: oneshot::Sender<Result<StreamableHttpMessageReceiver, SessionError>>,
+    },
     Resume {
         last_event_id: EventId,
         responder: oneshot::Sender<Result<StreamableHttpMessageReceiver, SessionError>>,
@@ -524,13 +600,7 @@ impl SessionHandle {
     ) -> Result<StreamableHttpMessageReceiver, SessionError> {
         let (tx, rx) = tokio::sync::oneshot::channel();
         self.event_tx
-            .send(SessionEvent::Resume {
-                last_event_id: EventId {
-                    http_request_id: None,
-                    index: 0,
-                },
-                responder: tx,
-            })
+            .send(SessionEvent::EstablishCommonChannel { responder: tx })
             .await
             .map_err(|_| SessionError::SessionServiceTerminated)?;
         rx.await
@@ -641,36 +711,37 @@ impl Worker for SessionWorker {
             match event {
                 InnerEvent::FromHandler(WorkerSendRequest { message, responder }) => {
                     // catch response
-                    match &message {
+                    let answered = match &message {
                         crate::model::JsonRpcMessage::Response(json_rpc_response) => {
-                            let request_id = json_rpc_response.id.clone();
-                            self.unregister_resource(&ResourceKey::McpRequestId(request_id));
+                            vec![json_rpc_response.id.clone()]
                         }
                         crate::model::JsonRpcMessage::Error(json_rpc_error) => {
-                            let request_id = json_rpc_error.id.clone();
-                            self.unregister_resource(&ResourceKey::McpRequestId(request_id));
+                            vec![json_rpc_error.id.clone()]
                         }
                         // unlikely happen
                         crate::model::JsonRpcMessage::BatchResponse(
                             json_rpc_batch_response_items,
-                        ) => {
-                            for item in json_rpc_batch_response_items {
-                                let request_id = match item {
-                                    crate::model::JsonRpcBatchResponseItem::Response(
-                                        json_rpc_response,
-                                    ) => json_rpc_response.id.clone(),
-                                    crate::model::JsonRpcBatchResponseItem::Error(
-                                        json_rpc_error,
-                                    ) => json_rpc_error.id.clone(),
-                                };
-                                self.unregister_resource(&ResourceKey::McpRequestId(request_id));
-                            }
-                        }
+                        ) => json_rpc_batch_response_items
+                            .iter()
+                            .map(|item| match item {
+                                crate::model::JsonRpcBatchResponseItem::Response(
+                                    json_rpc_response,
+                                ) => json_rpc_response.id.clone(),
+                                crate::model::JsonRpcBatchResponseItem::Error(json_rpc_error) => {
+                                    json_rpc_error.id.clone()
+                                }
+                            })
+                            .collect(),
                         _ => {
                             // no need to unregister resource
+                            vec![]
                         }
-                    }
+                    };
+                    // the response goes through the channel of its request, closed afterwards
                     let handle_result = self.handle_server_message(message).await;
+                    for request_id in answered {
+                        self.unregister_resource(&ResourceKey::McpRequestId(request_id));
+                    }
                     let _ = responder.send(handle_result);
                 }
                 InnerEvent::FromHttpService(SessionEvent::ClientMessage {
@@ -717,6 +788,10 @@ impl Worker for SessionWorker {
                     let _handle_result = self.tx_router.remove(&id);
                     let _ = responder.send(Ok(()));
                 }
+                InnerEvent::FromHttpService(SessionEvent::EstablishCommonChannel { responder }) => {
+                    let handle_result = self.establish_common_channel().await;
+                    let _ = responder.send(handle_result);
+                }
                 InnerEvent::FromHttpService(SessionEvent::Resume {
                     last_event_id,
                     responder,
@@ -775,6 +850,7 @@ pub fn create_session(
         id,
         tx_router: HashMap::new(),
         resource_router: HashMap::new(),
+        completed: VecDeque::new(),
         common,
         event_rx,
         session_config: config.clone(),
@@ -0,0 +1,299 @@
+//! TCP transport, with TLS behind the `tls` feature
+//!
+//! The messages are framed as newline-delimited JSON, as with stdio. The server serves every
+//! connection with a service of its own, made by a factory closure.
+//!
+//! ```rust,ignore
+//! // server
+//! let ct = tcp::serve_server("127.0.0.1:8000".parse()?, Counter::new).await?;
+//!
+//! // client
+//! let client = ().serve(tcp::connect_client("127.0.0.1:8000").await?).await?;
+//! ```
+//!
+//! With TLS, the server is given a [`tls::TlsServerConfig`] and the client connects with
+//! [`connect_client_tls`]:
+//!
+//! ```rust,ignore
+//! let tls = TlsServerBuilder::from_pem(&cert_pem, &key_pem)?.build()?;
+//! let ct = TcpServer::bind_with_config(TcpServerConfig::new(addr).with_tls(tls))
+//!     .await?
+//!     .with_service(Counter::new);
+//!
+//! let tls = TlsClientBuilder::new().with_root_pem(&ca_pem)?.build()?;
+//! let client = ().serve(tcp::connect_client_tls(addr, "localhost", &tls).await?).await?;
+//! ```
+use std::{
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+};
+use tokio_util::sync::CancellationToken;
+
+#[cfg(feature = "tls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+pub mod tls;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TcpTransportError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    /// The TLS handshake failed for another reason than a certificate
+    #[cfg(feature = "tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    #[error("TLS handshake failed: {0}")]
+    Handshake(#[source] io::Error),
+    /// The certificate of the peer was rejected, or the peer rejected ours
+    #[cfg(feature = "tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    #[error("Certificate error: {0}")]
+    Certificate(#[source] rustls::Error),
+    #[cfg(feature = "tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    #[error("Invalid TLS configuration: {0}")]
+    Config(#[source] rustls::Error),
+    #[cfg(feature = "tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    #[error("Invalid PEM: {0}")]
+    Pem(#[from] rustls::pki_types::pem::Error),
+    #[cfg(feature = "tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    #[error("Invalid server name: {0}")]
+    InvalidServerName(String),
+}
+
+/// A TCP connection, over TLS or not
+#[derive(Debug)]
+pub enum TcpConnection {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    Tls(Box<tokio_rustls::TlsStream<TcpStream>>),
+}
+
+impl TcpConnection {
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.tcp_stream().peer_addr()
+    }
+
+    pub fn tcp_stream(&self) -> &TcpStream {
+        match self {
+            Self::Plain(stream) => stream,
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => stream.get_ref().0,
+        }
+    }
+}
+
+impl AsyncRead for TcpConnection {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream.as_mut()).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for TcpConnection {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream.as_mut()).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream.as_mut()).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "tls")]
+            Self::Tls(stream) => Pin::new(stream.as_mut()).poll_shutdown(cx),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TcpServerConfig {
+    pub addr: SocketAddr,
+    /// Serve TLS instead of plain TCP
+    #[cfg(feature = "tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    pub tls: Option<tls::TlsServerConfig>,
+    pub ct: CancellationToken,
+}
+
+impl TcpServerConfig {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            #[cfg(feature = "tls")]
+            tls: None,
+            ct: CancellationToken::new(),
+        }
+    }
+
+    #[cfg(feature = "tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    pub fn with_tls(mut self, tls: tls::TlsServerConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+}
+
+/// Accepts the connections of the TCP clients
+#[derive(Debug)]
+pub struct TcpServer {
+    listener: TcpListener,
+    pub config: TcpServerConfig,
+}
+
+impl TcpServer {
+    pub async fn bind(addr: SocketAddr) -> io::Result<Self> {
+        Self::bind_with_config(TcpServerConfig::new(addr)).await
+    }
+
+    pub async fn bind_with_config(config: TcpServerConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(config.addr).await?;
+        Ok(Self { listener, config })
+    }
+
+    /// The address the server listens on, with the port the OS picked when bound to port 0
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Wait for the next client, and complete its TLS handshake if any
+    pub async fn accept(&mut self) -> Result<TcpConnection, TcpTransportError> {
+        let (stream, _addr) = self.listener.accept().await?;
+        self.handshake(stream).await
+    }
+
+    #[cfg(feature = "tls")]
+    async fn handshake(&self, stream: TcpStream) -> Result<TcpConnection, TcpTransportError> {
+        match &self.config.tls {
+            Some(tls) => tls.accept(stream).await,
+            None => Ok(TcpConnection::Plain(stream)),
+        }
+    }
+
+    #[cfg(not(feature = "tls"))]
+    async fn handshake(&self, stream: TcpStream) -> Result<TcpConnection, TcpTransportError> {
+        Ok(TcpConnection::Plain(stream))
+    }
+
+    /// Serve every client with a service made by `service_provider`, until the returned token
+    /// is cancelled
+    ///
+    /// The TLS handshakes are made on the task of their connection, a slow client doesn't hold
+    /// back the others.
+    #[cfg(feature = "server")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+    pub fn with_service<S, F>(self, service_provider: F) -> CancellationToken
+    where
+        S: crate::Service<crate::RoleServer>,
+        F: Fn() -> S + Send + 'static,
+    {
+        use std::sync::Arc;
+
+        use crate::service::ServiceExt;
+        let ct = self.config.ct.clone();
+        let accept_ct = ct.clone();
+        let server = Arc::new(self);
+        tokio::spawn(async move {
+            loop {
+                let accepted = tokio::select! {
+                    accepted = server.listener.accept() => accepted,
+                    _ = accept_ct.cancelled() => break,
+                };
+                let (stream, addr) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        tracing::error!("fail to accept tcp client: {e}");
+                        continue;
+                    }
+                };
+                let service = service_provider();
+                let ct = accept_ct.child_token();
+                let server = server.clone();
+                tokio::spawn(async move {
+                    let connection = match server.handshake(stream).await {
+                        Ok(connection) => connection,
+                        Err(e) => {
+                            tracing::warn!(%addr, "fail to handshake with tcp client: {e}");
+                            return;
+                        }
+                    };
+                    match service.serve_with_ct(connection, ct).await {
+                        Ok(running) => {
+                            let _ = running.waiting().await;
+                        }
+                        Err(e) => tracing::warn!(%addr, "fail to initialize tcp service: {e}"),
+                    }
+                });
+            }
+            tracing::debug!(addr = %server.config.addr, "tcp server cancelled");
+        });
+        ct
+    }
+
+    pub fn cancel(&self) {
+        self.config.ct.cancel();
+    }
+}
+
+/// Serve every client connecting at `addr` with a service made by `service_provider`, until the
+/// returned token is cancelled
+#[cfg(feature = "server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+pub async fn serve_server<S, F>(
+    addr: SocketAddr,
+    service_provider: F,
+) -> io::Result<CancellationToken>
+where
+    S: crate::Service<crate::RoleServer>,
+    F: Fn() -> S + Send + 'static,
+{
+    Ok(TcpServer::bind(addr).await?.with_service(service_provider))
+}
+
+/// Connect to the server at `addr` over plain TCP
+pub async fn connect_client(addr: impl ToSocketAddrs) -> Result<TcpConnection, TcpTransportError> {
+    let stream = TcpStream::connect(addr).await?;
+    Ok(TcpConnection::Plain(stream))
+}
+
+/// Connect to the server at `addr` over TLS, checking its certificate against `server_name`
+/// unless the verification is disabled in `tls`
+#[cfg(feature = "tls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+pub async fn connect_client_tls(
+    addr: impl ToSocketAddrs,
+    server_name: &str,
+    tls: &tls::TlsClientConfig,
+) -> Result<TcpConnection, TcpTransportError> {
+    let stream = TcpStream::connect(addr).await?;
+    tls.connect(stream, server_name).await
+}
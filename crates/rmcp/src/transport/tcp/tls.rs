@@ -0,0 +1,311 @@
+//! The rustls layer of the TCP transport, with the `ring` crypto provider
+use std::sync::Arc;
+
+use rustls::{
+    DigitallySignedStruct, RootCertStore, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::CryptoProvider,
+    pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime, pem::PemObject},
+    server::WebPkiClientVerifier,
+};
+use tokio::net::TcpStream;
+
+use super::{TcpConnection, TcpTransportError};
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+fn certificates_from_pem(pem: &[u8]) -> Result<Vec<CertificateDer<'static>>, TcpTransportError> {
+    Ok(CertificateDer::pem_slice_iter(pem).collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Tell the certificate errors from the other handshake failures
+fn handshake_error(error: std::io::Error) -> TcpTransportError {
+    use rustls::{AlertDescription, Error};
+    let rustls_error = error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<Error>());
+    match rustls_error {
+        Some(
+            e @ (Error::InvalidCertificate(_)
+            | Error::NoCertificatesPresented
+            | Error::AlertReceived(
+                AlertDescription::BadCertificate
+                | AlertDescription::UnsupportedCertificate
+                | AlertDescription::CertificateRevoked
+                | AlertDescription::CertificateExpired
+                | AlertDescription::CertificateUnknown
+                | AlertDescription::UnknownCA
+                | AlertDescription::CertificateRequired,
+            )),
+        ) => TcpTransportError::Certificate(e.clone()),
+        _ => TcpTransportError::Handshake(error),
+    }
+}
+
+/// The TLS setup of a [`TcpServer`](super::TcpServer), made by a [`TlsServerBuilder`]
+#[derive(Clone)]
+pub struct TlsServerConfig {
+    acceptor: tokio_rustls::TlsAcceptor,
+}
+
+impl std::fmt::Debug for TlsServerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsServerConfig").finish_non_exhaustive()
+    }
+}
+
+impl TlsServerConfig {
+    pub fn from_rustls(config: Arc<rustls::ServerConfig>) -> Self {
+        Self {
+            acceptor: tokio_rustls::TlsAcceptor::from(config),
+        }
+    }
+
+    pub(crate) async fn accept(
+        &self,
+        stream: TcpStream,
+    ) -> Result<TcpConnection, TcpTransportError> {
+        let stream = self
+            .acceptor
+            .accept(stream)
+            .await
+            .map_err(handshake_error)?;
+        Ok(TcpConnection::Tls(Box::new(stream.into())))
+    }
+}
+
+#[derive(Debug)]
+pub struct TlsServerBuilder {
+    cert_chain: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+    client_roots: Option<RootCertStore>,
+}
+
+impl TlsServerBuilder {
+    pub fn new(cert_chain: Vec<CertificateDer<'static>>, key: PrivateKeyDer<'static>) -> Self {
+        Self {
+            cert_chain,
+            key,
+            client_roots: None,
+        }
+    }
+
+    /// The certificate chain and the private key of the server, PEM encoded
+    pub fn from_pem(cert_pem: &[u8], key_pem: &[u8]) -> Result<Self, TcpTransportError> {
+        Ok(Self::new(
+            certificates_from_pem(cert_pem)?,
+            PrivateKeyDer::from_pem_slice(key_pem)?,
+        ))
+    }
+
+    /// Require the clients to present a certificate signed by one of `roots`
+    pub fn with_client_auth(mut self, roots: RootCertStore) -> Self {
+        self.client_roots = Some(roots);
+        self
+    }
+
+    pub fn build(self) -> Result<TlsServerConfig, TcpTransportError> {
+        let provider = provider();
+        let builder = rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(TcpTransportError::Config)?;
+        let builder = match self.client_roots {
+            Some(roots) => {
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .build()
+                        .map_err(|e| {
+                            TcpTransportError::Config(rustls::Error::General(e.to_string()))
+                        })?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+        let config = builder
+            .with_single_cert(self.cert_chain, self.key)
+            .map_err(TcpTransportError::Config)?;
+        Ok(TlsServerConfig::from_rustls(Arc::new(config)))
+    }
+}
+
+/// The TLS setup of a client, made by a [`TlsClientBuilder`]
+#[derive(Clone)]
+pub struct TlsClientConfig {
+    connector: tokio_rustls::TlsConnector,
+}
+
+impl std::fmt::Debug for TlsClientConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsClientConfig").finish_non_exhaustive()
+    }
+}
+
+impl TlsClientConfig {
+    pub fn from_rustls(config: Arc<rustls::ClientConfig>) -> Self {
+        Self {
+            connector: tokio_rustls::TlsConnector::from(config),
+        }
+    }
+
+    pub(crate) async fn connect(
+        &self,
+        stream: TcpStream,
+        server_name: &str,
+    ) -> Result<TcpConnection, TcpTransportError> {
+        let server_name = ServerName::try_from(server_name.to_owned())
+            .map_err(|_| TcpTransportError::InvalidServerName(server_name.to_owned()))?;
+        let stream = self
+            .connector
+            .connect(server_name, stream)
+            .await
+            .map_err(handshake_error)?;
+        Ok(TcpConnection::Tls(Box::new(stream.into())))
+    }
+}
+
+#[derive(Debug)]
+pub struct TlsClientBuilder {
+    roots: RootCertStore,
+    client_auth: Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>,
+    verify_server: bool,
+}
+
+impl Default for TlsClientBuilder {
+    fn default() -> Self {
+        Self {
+            roots: RootCertStore::empty(),
+            client_auth: None,
+            verify_server: true,
+        }
+    }
+}
+
+impl TlsClientBuilder {
+    /// A client trusting no certificate yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_roots(mut self, roots: RootCertStore) -> Self {
+        self.roots = roots;
+        self
+    }
+
+    pub fn with_root_certificate(
+        mut self,
+        certificate: CertificateDer<'static>,
+    ) -> Result<Self, TcpTransportError> {
+        self.roots
+            .add(certificate)
+            .map_err(TcpTransportError::Certificate)?;
+        Ok(self)
+    }
+
+    /// Trust the PEM encoded certificates
+    pub fn with_root_pem(mut self, pem: &[u8]) -> Result<Self, TcpTransportError> {
+        for certificate in certificates_from_pem(pem)? {
+            self = self.with_root_certificate(certificate)?;
+        }
+        Ok(self)
+    }
+
+    /// Present a certificate to a server requiring client authentication
+    pub fn with_client_auth(
+        mut self,
+        cert_chain: Vec<CertificateDer<'static>>,
+        key: PrivateKeyDer<'static>,
+    ) -> Self {
+        self.client_auth = Some((cert_chain, key));
+        self
+    }
+
+    pub fn with_client_auth_pem(
+        self,
+        cert_pem: &[u8],
+        key_pem: &[u8],
+    ) -> Result<Self, TcpTransportError> {
+        Ok(self.with_client_auth(
+            certificates_from_pem(cert_pem)?,
+            PrivateKeyDer::from_pem_slice(key_pem)?,
+        ))
+    }
+
+    /// Accept any certificate from the server, which leaves the connection open to
+    /// man-in-the-middle attacks. Only meant for tests and local development
+    pub fn danger_accept_invalid_certs(mut self) -> Self {
+        self.verify_server = false;
+        self
+    }
+
+    pub fn build(self) -> Result<TlsClientConfig, TcpTransportError> {
+        let provider = provider();
+        let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(TcpTransportError::Config)?;
+        let builder = if self.verify_server {
+            builder.with_root_certificates(self.roots)
+        } else {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(NoServerVerification(provider)))
+        };
+        let config = match self.client_auth {
+            Some((cert_chain, key)) => builder
+                .with_client_auth_cert(cert_chain, key)
+                .map_err(TcpTransportError::Config)?,
+            None => builder.with_no_client_auth(),
+        };
+        Ok(TlsClientConfig::from_rustls(Arc::new(config)))
+    }
+}
+
+/// Accepts any server certificate, still checking the handshake signatures
+#[derive(Debug)]
+struct NoServerVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
@@ -0,0 +1,229 @@
+//! Local transports, over a unix domain socket or a named pipe on windows
+//!
+//! The messages are framed as newline-delimited JSON, as with stdio. The server serves every
+//! connection with a service of its own, made by a factory closure.
+//!
+//! ```rust,ignore
+//! // server
+//! let ct = unix_socket::serve_server("/tmp/counter.sock", Counter::new)?;
+//!
+//! // client
+//! let client = ().serve(unix_socket::connect_client("/tmp/counter.sock").await?).await?;
+//! ```
+//!
+//! On windows the path is the name of the pipe, such as `\\.\pipe\counter`.
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use tokio_util::sync::CancellationToken;
+
+/// A connection accepted by the server
+#[cfg(unix)]
+pub type LocalSocketStream = tokio::net::UnixStream;
+/// A connection accepted by the server
+#[cfg(windows)]
+pub type LocalSocketStream = tokio::net::windows::named_pipe::NamedPipeServer;
+
+/// The connection of a client to the server
+#[cfg(unix)]
+pub type LocalSocketClientStream = tokio::net::UnixStream;
+/// The connection of a client to the server
+#[cfg(windows)]
+pub type LocalSocketClientStream = tokio::net::windows::named_pipe::NamedPipeClient;
+
+#[derive(Debug, Clone)]
+pub struct UnixSocketConfig {
+    pub path: PathBuf,
+    /// The permissions of the socket file, such as `0o600` to only let its owner connect.
+    /// Ignored for named pipes
+    pub mode: Option<u32>,
+    pub ct: CancellationToken,
+}
+
+impl UnixSocketConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: None,
+            ct: CancellationToken::new(),
+        }
+    }
+
+    pub fn with_mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+}
+
+/// Accepts the connections of the local clients
+///
+/// On unix the socket file is removed when the server is dropped, and a stale socket file left
+/// by a server that didn't stop cleanly is replaced on bind.
+#[derive(Debug)]
+pub struct UnixSocketServer {
+    #[cfg(unix)]
+    listener: tokio::net::UnixListener,
+    #[cfg(windows)]
+    next_instance: tokio::net::windows::named_pipe::NamedPipeServer,
+    pub config: UnixSocketConfig,
+}
+
+impl UnixSocketServer {
+    pub fn bind(path: impl Into<PathBuf>) -> io::Result<Self> {
+        Self::bind_with_config(UnixSocketConfig::new(path))
+    }
+
+    #[cfg(unix)]
+    pub fn bind_with_config(config: UnixSocketConfig) -> io::Result<Self> {
+        use std::os::unix::fs::PermissionsExt;
+        let listener = match tokio::net::UnixListener::bind(&config.path) {
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse && is_stale(&config.path) => {
+                tracing::debug!(path = %config.path.display(), "replace stale socket file");
+                std::fs::remove_file(&config.path)?;
+                tokio::net::UnixListener::bind(&config.path)?
+            }
+            result => result?,
+        };
+        if let Some(mode) = config.mode {
+            std::fs::set_permissions(&config.path, std::fs::Permissions::from_mode(mode))?;
+        }
+        Ok(Self { listener, config })
+    }
+
+    #[cfg(windows)]
+    pub fn bind_with_config(config: UnixSocketConfig) -> io::Result<Self> {
+        let next_instance = tokio::net::windows::named_pipe::ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&config.path)?;
+        Ok(Self {
+            next_instance,
+            config,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.config.path
+    }
+
+    /// Wait for the next client
+    #[cfg(unix)]
+    pub async fn accept(&mut self) -> io::Result<LocalSocketStream> {
+        let (stream, _addr) = self.listener.accept().await?;
+        Ok(stream)
+    }
+
+    /// Wait for the next client
+    #[cfg(windows)]
+    pub async fn accept(&mut self) -> io::Result<LocalSocketStream> {
+        self.next_instance.connect().await?;
+        // the next client connects to a new instance of the pipe
+        let next_instance =
+            tokio::net::windows::named_pipe::ServerOptions::new().create(&self.config.path)?;
+        Ok(std::mem::replace(&mut self.next_instance, next_instance))
+    }
+
+    /// Serve every client with a service made by `service_provider`, until the returned token
+    /// is cancelled
+    #[cfg(feature = "server")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+    pub fn with_service<S, F>(mut self, service_provider: F) -> CancellationToken
+    where
+        S: crate::Service<crate::RoleServer>,
+        F: Fn() -> S + Send + 'static,
+    {
+        use crate::service::ServiceExt;
+        let ct = self.config.ct.clone();
+        let accept_ct = ct.clone();
+        tokio::spawn(async move {
+            loop {
+                let stream = tokio::select! {
+                    stream = self.accept() => stream,
+                    _ = accept_ct.cancelled() => break,
+                };
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        tracing::error!(path = %self.path().display(), "fail to accept client: {e}");
+                        continue;
+                    }
+                };
+                let service = service_provider();
+                let ct = accept_ct.child_token();
+                tokio::spawn(async move {
+                    match service.serve_with_ct(stream, ct).await {
+                        Ok(running) => {
+                            let _ = running.waiting().await;
+                        }
+                        Err(e) => tracing::warn!("fail to initialize local socket service: {e}"),
+                    }
+                });
+            }
+            tracing::debug!(path = %self.path().display(), "local socket server cancelled");
+        });
+        ct
+    }
+
+    pub fn cancel(&self) {
+        self.config.ct.cancel();
+    }
+}
+
+#[cfg(unix)]
+impl Drop for UnixSocketServer {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.config.path) {
+            tracing::debug!(path = %self.config.path.display(), "fail to remove socket file: {e}");
+        }
+    }
+}
+
+/// A socket file no server is listening on
+#[cfg(unix)]
+fn is_stale(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    let is_socket = std::fs::symlink_metadata(path)
+        .map(|metadata| metadata.file_type().is_socket())
+        .unwrap_or(false);
+    is_socket
+        && matches!(
+            std::os::unix::net::UnixStream::connect(path),
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused
+        )
+}
+
+/// Serve every client connecting at `path` with a service made by `service_provider`, until the
+/// returned token is cancelled
+#[cfg(feature = "server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+pub fn serve_server<S, F>(
+    path: impl Into<PathBuf>,
+    service_provider: F,
+) -> io::Result<CancellationToken>
+where
+    S: crate::Service<crate::RoleServer>,
+    F: Fn() -> S + Send + 'static,
+{
+    Ok(UnixSocketServer::bind(path)?.with_service(service_provider))
+}
+
+/// Connect to the server at `path`
+#[cfg(unix)]
+pub async fn connect_client(path: impl AsRef<Path>) -> io::Result<LocalSocketClientStream> {
+    tokio::net::UnixStream::connect(path).await
+}
+
+/// Connect to the server at `path`, waiting for a free instance of the pipe
+#[cfg(windows)]
+pub async fn connect_client(path: impl AsRef<Path>) -> io::Result<LocalSocketClientStream> {
+    /// All the instances of the pipe are busy
+    const ERROR_PIPE_BUSY: i32 = 231;
+    loop {
+        match tokio::net::windows::named_pipe::ClientOptions::new().open(path.as_ref()) {
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) => {}
+            result => return result,
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+}
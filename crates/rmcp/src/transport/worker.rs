@@ -3,7 +3,7 @@ use std::borrow::Cow;
 use tokio_util::sync::CancellationToken;
 use tracing::{Instrument, Level};
 
-use super::{IntoTransport, Transport};
+use super::{IntoTransport, Transport, TransportStatus};
 use crate::service::{RxJsonRpcMessage, ServiceRole, TxJsonRpcMessage};
 
 #[derive(Debug, thiserror::Error)]
@@ -52,6 +52,10 @@ pub trait Worker: Sized + Send + 'static {
     fn config(&self) -> WorkerConfig {
         WorkerConfig::default()
     }
+    /// The health of the connection, see [`Transport::status`]
+    fn status(&self) -> Option<tokio::sync::watch::Receiver<TransportStatus>> {
+        None
+    }
 }
 
 pub struct WorkerSendRequest<W: Worker> {
@@ -65,6 +69,7 @@ pub struct WorkerTransport<W: Worker> {
     join_handle: Option<tokio::task::JoinHandle<Result<(), WorkerQuitReason>>>,
     _drop_guard: tokio_util::sync::DropGuard,
     ct: CancellationToken,
+    status: Option<tokio::sync::watch::Receiver<TransportStatus>>,
 }
 
 pub struct WorkerConfig {
@@ -97,6 +102,7 @@ impl<W: Worker> WorkerTransport<W> {
     }
     pub fn spawn_with_ct(worker: W, transport_task_ct: CancellationToken) -> Self {
         let config = worker.config();
+        let status = worker.status();
         let worker_name = config.name;
         let (to_transport_tx, from_handler_rx) =
             tokio::sync::mpsc::channel::<WorkerSendRequest<W>>(config.channel_buffer_capacity);
@@ -140,6 +146,7 @@ impl<W: Worker> WorkerTransport<W> {
             join_handle: Some(join_handle),
             ct: transport_task_ct.clone(),
             _drop_guard: transport_task_ct.drop_guard(),
+            status,
         }
     }
 }
@@ -205,4 +212,7 @@ impl<W: Worker> Transport<W::Role> for WorkerTransport<W> {
             Ok(())
         }
     }
+    fn status(&self) -> Option<tokio::sync::watch::Receiver<TransportStatus>> {
+        self.status.clone()
+    }
 }
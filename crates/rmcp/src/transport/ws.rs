@@ -1 +1,391 @@
-// Maybe we don't really need a ws implementation?
+//! MCP over WebSocket, one JSON-RPC message per text frame
+//!
+//! [`WebSocketWorker`] runs over any websocket, as a [`Worker`] it converts into a transport, so
+//! `serve` and `serve_client` take it as they are.
+//!
+//! ```rust,ignore
+//! // client
+//! let client = ().serve(ws::connect("ws://127.0.0.1:8000/mcp").await?).await?;
+//!
+//! // server, mounted on an axum route
+//! let router = Router::new().route(
+//!     "/mcp",
+//!     get(|upgrade: WebSocketUpgrade| async move {
+//!         ws::serve_upgrade(upgrade, Counter::new(), WebSocketConfig::default())
+//!     }),
+//! );
+//! ```
+use std::{marker::PhantomData, time::Duration};
+
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use thiserror::Error;
+use tokio::time::{Instant, Interval};
+
+use super::worker::{Worker, WorkerConfig, WorkerContext, WorkerQuitReason, WorkerSendRequest};
+use crate::service::{RxJsonRpcMessage, ServiceRole};
+
+/// Normal closure
+const CLOSE_NORMAL: u16 = 1000;
+/// A message too big to process
+const CLOSE_MESSAGE_TOO_BIG: u16 = 1009;
+
+#[derive(Error, Debug)]
+pub enum WebSocketError {
+    #[error("Transport channel closed")]
+    TransportChannelClosed,
+    #[error("Tokio join error: {0}")]
+    TokioJoinError(#[from] tokio::task::JoinError),
+    #[error("Serialize error: {0}")]
+    Serialize(#[from] serde_json::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Message of {size} bytes exceeds the limit of {max} bytes")]
+    MessageTooLarge { size: usize, max: usize },
+    #[error("WebSocket error: {0}")]
+    WebSocket(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+#[derive(Debug, Clone)]
+pub struct WebSocketConfig {
+    /// Ping the peer at this interval, closing the connection if it didn't answer the previous
+    /// ping, `None` to never ping
+    pub ping_interval: Option<Duration>,
+    /// The largest message sent or received, in bytes. A larger incoming message closes the
+    /// connection with the `1009` code
+    pub max_message_size: usize,
+    pub channel_buffer_capacity: usize,
+}
+
+impl WebSocketConfig {
+    pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(30);
+    pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            ping_interval: Some(Self::DEFAULT_PING_INTERVAL),
+            max_message_size: Self::DEFAULT_MAX_MESSAGE_SIZE,
+            channel_buffer_capacity: 16,
+        }
+    }
+}
+
+/// A frame of a websocket, as the transport sees it
+#[derive(Debug)]
+pub enum WebSocketFrame {
+    Text(String),
+    Binary,
+    Ping,
+    Pong,
+    Close(Option<(u16, String)>),
+}
+
+/// The message type of a websocket implementation
+pub trait WebSocketMessage: Send + 'static {
+    fn text(text: String) -> Self;
+    fn ping() -> Self;
+    fn close(code: u16, reason: &'static str) -> Self;
+    fn into_frame(self) -> WebSocketFrame;
+}
+
+#[cfg(feature = "transport-ws")]
+impl WebSocketMessage for tokio_tungstenite::tungstenite::Message {
+    fn text(text: String) -> Self {
+        Self::Text(text.into())
+    }
+    fn ping() -> Self {
+        Self::Ping(Default::default())
+    }
+    fn close(code: u16, reason: &'static str) -> Self {
+        Self::Close(Some(tokio_tungstenite::tungstenite::protocol::CloseFrame {
+            code: code.into(),
+            reason: reason.into(),
+        }))
+    }
+    fn into_frame(self) -> WebSocketFrame {
+        match self {
+            Self::Text(text) => WebSocketFrame::Text(text.as_str().to_owned()),
+            Self::Binary(_) | Self::Frame(_) => WebSocketFrame::Binary,
+            Self::Ping(_) => WebSocketFrame::Ping,
+            Self::Pong(_) => WebSocketFrame::Pong,
+            Self::Close(frame) => WebSocketFrame::Close(
+                frame.map(|frame| (frame.code.into(), frame.reason.as_str().to_owned())),
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "transport-ws-axum")]
+impl WebSocketMessage for axum::extract::ws::Message {
+    fn text(text: String) -> Self {
+        Self::Text(text.into())
+    }
+    fn ping() -> Self {
+        Self::Ping(Default::default())
+    }
+    fn close(code: u16, reason: &'static str) -> Self {
+        Self::Close(Some(axum::extract::ws::CloseFrame {
+            code,
+            reason: reason.into(),
+        }))
+    }
+    fn into_frame(self) -> WebSocketFrame {
+        match self {
+            Self::Text(text) => WebSocketFrame::Text(text.as_str().to_owned()),
+            Self::Binary(_) => WebSocketFrame::Binary,
+            Self::Ping(_) => WebSocketFrame::Ping,
+            Self::Pong(_) => WebSocketFrame::Pong,
+            Self::Close(frame) => WebSocketFrame::Close(
+                frame.map(|frame| (frame.code, frame.reason.as_str().to_owned())),
+            ),
+        }
+    }
+}
+
+/// Carries the messages of a `R` over the websocket `S` of `M` messages
+///
+/// The pings of the peer are answered by the websocket implementation. A close frame from the
+/// peer ends the transport, so the service stops as if the peer disconnected.
+pub struct WebSocketWorker<R, S, M> {
+    socket: S,
+    config: WebSocketConfig,
+    marker: PhantomData<fn() -> (R, M)>,
+}
+
+impl<R, S, M> WebSocketWorker<R, S, M> {
+    pub fn new(socket: S, config: WebSocketConfig) -> Self {
+        Self {
+            socket,
+            config,
+            marker: PhantomData,
+        }
+    }
+}
+
+async fn tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+impl<R, S, M, E> WebSocketWorker<R, S, M>
+where
+    S: Stream<Item = Result<M, E>> + Sink<M, Error = E> + Send + Unpin + 'static,
+    M: WebSocketMessage,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    async fn close(&mut self, code: u16, reason: &'static str) {
+        if let Err(e) = self.socket.send(M::close(code, reason)).await {
+            tracing::debug!("fail to send close frame: {e}");
+        }
+        let _ = self.socket.close().await;
+    }
+}
+
+impl<R, S, M, E> Worker for WebSocketWorker<R, S, M>
+where
+    R: ServiceRole,
+    S: Stream<Item = Result<M, E>> + Sink<M, Error = E> + Send + Unpin + 'static,
+    M: WebSocketMessage,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    type Error = WebSocketError;
+    type Role = R;
+    fn err_closed() -> Self::Error {
+        WebSocketError::TransportChannelClosed
+    }
+    fn err_join(e: tokio::task::JoinError) -> Self::Error {
+        WebSocketError::TokioJoinError(e)
+    }
+    fn config(&self) -> WorkerConfig {
+        WorkerConfig {
+            name: Some("WebSocketWorker".into()),
+            channel_buffer_capacity: self.config.channel_buffer_capacity,
+        }
+    }
+    async fn run(mut self, mut context: WorkerContext<Self>) -> Result<(), WorkerQuitReason> {
+        enum Event<W: Worker, M, E> {
+            Frame(Option<Result<M, E>>),
+            Send(Result<WorkerSendRequest<W>, WorkerQuitReason>),
+            Ping,
+            Cancelled,
+        }
+        let ct = context.cancellation_token.clone();
+        let max_message_size = self.config.max_message_size;
+        let mut keepalive = self
+            .config
+            .ping_interval
+            .map(|period| tokio::time::interval_at(Instant::now() + period, period));
+        let mut awaiting_pong = false;
+        loop {
+            let event: Event<Self, M, E> = tokio::select! {
+                frame = self.socket.next() => Event::Frame(frame),
+                request = context.recv_from_handler() => Event::Send(request),
+                _ = tick(&mut keepalive) => Event::Ping,
+                _ = ct.cancelled() => Event::Cancelled,
+            };
+            match event {
+                Event::Frame(None) => {
+                    tracing::debug!("websocket terminated");
+                    return Err(WorkerQuitReason::TransportClosed);
+                }
+                Event::Frame(Some(Err(e))) => {
+                    return Err(WorkerQuitReason::fatal(e.to_string(), "receive frame"));
+                }
+                Event::Frame(Some(Ok(message))) => match message.into_frame() {
+                    WebSocketFrame::Text(text) if text.len() > max_message_size => {
+                        tracing::warn!(size = text.len(), max_message_size, "message too big");
+                        self.close(CLOSE_MESSAGE_TOO_BIG, "message too big").await;
+                        return Err(WorkerQuitReason::fatal(
+                            format!("message of {} bytes is too big", text.len()),
+                            "receive frame",
+                        ));
+                    }
+                    WebSocketFrame::Text(text) => {
+                        match serde_json::from_str::<RxJsonRpcMessage<R>>(&text) {
+                            Ok(message) => context.send_to_handler(message).await?,
+                            Err(e) => tracing::warn!("failed to deserialize message: {e}"),
+                        }
+                    }
+                    WebSocketFrame::Binary => {
+                        tracing::warn!("ignore binary frame, messages are sent as text");
+                    }
+                    WebSocketFrame::Ping => {}
+                    WebSocketFrame::Pong => awaiting_pong = false,
+                    WebSocketFrame::Close(frame) => {
+                        tracing::debug!(?frame, "websocket closed by peer");
+                        let _ = self.socket.close().await;
+                        return Err(WorkerQuitReason::TransportClosed);
+                    }
+                },
+                Event::Send(Err(quit)) => {
+                    self.close(CLOSE_NORMAL, "").await;
+                    return Err(quit);
+                }
+                Event::Send(Ok(WorkerSendRequest { message, responder })) => {
+                    let text = match serde_json::to_string(&message) {
+                        Ok(text) if text.len() > max_message_size => {
+                            let _ = responder.send(Err(WebSocketError::MessageTooLarge {
+                                size: text.len(),
+                                max: max_message_size,
+                            }));
+                            continue;
+                        }
+                        Ok(text) => text,
+                        Err(e) => {
+                            let _ = responder.send(Err(e.into()));
+                            continue;
+                        }
+                    };
+                    if let Err(e) = self.socket.send(M::text(text)).await {
+                        let error = e.to_string();
+                        let _ = responder.send(Err(WebSocketError::WebSocket(Box::new(e))));
+                        return Err(WorkerQuitReason::fatal(error, "send message"));
+                    }
+                    let _ = responder.send(Ok(()));
+                }
+                Event::Ping => {
+                    if awaiting_pong {
+                        self.close(CLOSE_NORMAL, "ping timeout").await;
+                        return Err(WorkerQuitReason::fatal(
+                            "no pong received for the last ping",
+                            "keepalive",
+                        ));
+                    }
+                    self.socket
+                        .send(M::ping())
+                        .await
+                        .map_err(WorkerQuitReason::fatal_context("send ping"))?;
+                    awaiting_pong = true;
+                }
+                Event::Cancelled => {
+                    self.close(CLOSE_NORMAL, "").await;
+                    return Err(WorkerQuitReason::Cancelled);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "transport-ws", feature = "client"))]
+pub type WebSocketClientWorker = WebSocketWorker<
+    crate::RoleClient,
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+    tokio_tungstenite::tungstenite::Message,
+>;
+
+/// Connect to the websocket MCP server at `request`, an url or a http request
+#[cfg(all(feature = "transport-ws", feature = "client"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "transport-ws", feature = "client"))))]
+pub async fn connect(
+    request: impl tokio_tungstenite::tungstenite::client::IntoClientRequest + Unpin,
+) -> Result<WebSocketClientWorker, WebSocketError> {
+    connect_with_config(request, WebSocketConfig::default()).await
+}
+
+#[cfg(all(feature = "transport-ws", feature = "client"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "transport-ws", feature = "client"))))]
+pub async fn connect_with_config(
+    request: impl tokio_tungstenite::tungstenite::client::IntoClientRequest + Unpin,
+    config: WebSocketConfig,
+) -> Result<WebSocketClientWorker, WebSocketError> {
+    // small frames like pings must not wait for the ack of the previous one
+    let (socket, _response) = tokio_tungstenite::connect_async_with_config(request, None, true)
+        .await
+        .map_err(|e| WebSocketError::WebSocket(Box::new(e)))?;
+    Ok(WebSocketWorker::new(socket, config))
+}
+
+/// Accept a websocket MCP client on `stream`, once the http upgrade is done
+#[cfg(all(feature = "transport-ws", feature = "server"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "transport-ws", feature = "server"))))]
+pub async fn accept<T>(
+    stream: T,
+    config: WebSocketConfig,
+) -> Result<
+    WebSocketWorker<
+        crate::RoleServer,
+        tokio_tungstenite::WebSocketStream<T>,
+        tokio_tungstenite::tungstenite::Message,
+    >,
+    WebSocketError,
+>
+where
+    T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin + 'static,
+{
+    let socket = tokio_tungstenite::accept_async(stream)
+        .await
+        .map_err(|e| WebSocketError::WebSocket(Box::new(e)))?;
+    Ok(WebSocketWorker::new(socket, config))
+}
+
+/// Answer an axum websocket upgrade, serving `service` on the upgraded connection
+#[cfg(all(feature = "transport-ws-axum", feature = "server"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "transport-ws-axum", feature = "server")))
+)]
+pub fn serve_upgrade<S>(
+    upgrade: axum::extract::ws::WebSocketUpgrade,
+    service: S,
+    config: WebSocketConfig,
+) -> axum::response::Response
+where
+    S: crate::Service<crate::RoleServer>,
+{
+    use crate::ServiceExt;
+    upgrade.on_upgrade(move |socket| async move {
+        match service.serve(WebSocketWorker::new(socket, config)).await {
+            Ok(running) => {
+                if let Err(e) = running.waiting().await {
+                    tracing::error!("websocket service join error: {e}");
+                }
+            }
+            Err(e) => tracing::warn!("fail to initialize websocket service: {e}"),
+        }
+    })
+}
@@ -89,6 +89,7 @@ impl ClientHandler for TestClientHandler {
             },
             model: "test-model".to_string(),
             stop_reason: Some(CreateMessageResult::STOP_REASON_END_TURN.to_string()),
+            meta: None,
         })
     }
 
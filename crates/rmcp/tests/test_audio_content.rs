@@ -0,0 +1,107 @@
+//cargo test --test test_audio_content --features "client server macros"
+
+use rmcp::{
+    ServiceExt,
+    model::{
+        CallToolRequestParam, Content, CreateMessageResult, GetPromptResult, PromptMessageContent,
+        PromptMessageRole, RawAudioContent, RawContent, Role, SamplingMessage,
+    },
+    tool,
+};
+use serde_json::json;
+
+const TONE: &[u8] = include_bytes!("test_audio_content/tone.wav");
+
+#[derive(Debug, Clone, Default)]
+pub struct AudioServer;
+
+#[tool(tool_box)]
+impl AudioServer {
+    #[tool(description = "Play a tone")]
+    async fn tone(&self) -> Content {
+        Content::audio(TONE, "audio/wav")
+    }
+}
+
+fn decoded(content: &RawContent) -> Vec<u8> {
+    let audio = content.as_audio().expect("audio content");
+    assert_eq!(audio.mime_type, "audio/wav");
+    audio.decode().expect("valid base64")
+}
+
+#[test]
+fn test_audio_content_serde() -> anyhow::Result<()> {
+    let content = Content::audio(TONE, "audio/wav");
+    let value = serde_json::to_value(&content)?;
+    assert_eq!(value["type"], "audio");
+    assert_eq!(value["mimeType"], "audio/wav");
+    assert!(value.get("annotations").is_none());
+
+    let round_tripped: Content = serde_json::from_value(value)?;
+    assert_eq!(round_tripped, content);
+    assert_eq!(decoded(&round_tripped), TONE);
+    Ok(())
+}
+
+#[test]
+fn test_audio_in_prompt_messages() -> anyhow::Result<()> {
+    let result = GetPromptResult::builder()
+        .user_text("Transcribe this")
+        .audio(PromptMessageRole::User, TONE, "audio/wav")
+        .build();
+    let value = serde_json::to_value(&result)?;
+    assert_eq!(value["messages"][1]["content"]["type"], "audio");
+
+    let round_tripped: GetPromptResult = serde_json::from_value(value)?;
+    assert_eq!(round_tripped, result);
+    let PromptMessageContent::Audio { audio } = &round_tripped.messages[1].content else {
+        panic!("expected audio content");
+    };
+    assert_eq!(audio.decode()?, TONE);
+    Ok(())
+}
+
+#[test]
+fn test_audio_in_sampling_messages() -> anyhow::Result<()> {
+    let data = RawAudioContent::new(TONE, "audio/wav").data;
+    let result: CreateMessageResult = serde_json::from_value(json!({
+        "model": "speech-model",
+        "role": "assistant",
+        "content": {
+            "type": "audio",
+            "data": data,
+            "mimeType": "audio/wav"
+        }
+    }))?;
+    assert_eq!(result.message.role, Role::Assistant);
+    assert_eq!(decoded(&result.message.content), TONE);
+
+    let message = SamplingMessage {
+        role: Role::User,
+        content: Content::audio(TONE, "audio/wav"),
+    };
+    let round_tripped: SamplingMessage = serde_json::from_value(serde_json::to_value(&message)?)?;
+    assert_eq!(round_tripped, message);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_audio_tool_result() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        AudioServer.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "tone".into(),
+            arguments: None,
+        })
+        .await?;
+    assert_eq!(result.content.len(), 1);
+    assert_eq!(decoded(&result.content[0]), TONE);
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,352 @@
+//cargo test --test test_auth --features "auth client server macros transport-streamable-http-client transport-streamable-http-server"
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use axum::{
+    Form, Json, Router,
+    extract::{Query, Request, State},
+    http::{StatusCode, header::AUTHORIZATION},
+    middleware::{Next, from_fn_with_state},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+};
+use oauth2::{PkceCodeChallenge, PkceCodeVerifier};
+use rmcp::{
+    ServiceExt, tool,
+    transport::{
+        StreamableHttpClientTransport, StreamableHttpServer,
+        auth::{
+            AuthClient, AuthError, AuthorizationCallback, AuthorizationHandler,
+            AuthorizationManager, CredentialStore, InMemoryCredentialStore,
+        },
+        streamable_http_client::StreamableHttpClientTransportConfig,
+        streamable_http_server::axum::StreamableHttpServerConfig,
+    },
+};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+const REDIRECT_URI: &str = "http://localhost:8080/callback";
+
+#[derive(Debug, Clone, Default)]
+pub struct Echo;
+
+#[tool(tool_box)]
+impl Echo {
+    #[tool(description = "Echo the text")]
+    async fn echo(&self, #[tool(param)] text: String) -> String {
+        text
+    }
+}
+
+/// An authorization server protecting an MCP server on `/mcp`
+#[derive(Debug, Default)]
+struct MockAuthorizationServer {
+    base: String,
+    /// the code challenges of the issued codes
+    codes: HashMap<String, String>,
+    access_tokens: HashSet<String>,
+    refresh_tokens: HashSet<String>,
+    issued: usize,
+    refresh_grants: usize,
+}
+
+type Mock = Arc<Mutex<MockAuthorizationServer>>;
+
+impl MockAuthorizationServer {
+    fn issue_tokens(&mut self) -> Value {
+        self.issued += 1;
+        let access_token = format!("access-{}", self.issued);
+        let refresh_token = format!("refresh-{}", self.issued);
+        self.access_tokens.insert(access_token.clone());
+        self.refresh_tokens.insert(refresh_token.clone());
+        json!({
+            "access_token": access_token,
+            "token_type": "bearer",
+            "expires_in": 3600,
+            "refresh_token": refresh_token,
+        })
+    }
+}
+
+async fn protected_resource(State(mock): State<Mock>) -> Json<Value> {
+    let base = mock.lock().unwrap().base.clone();
+    Json(json!({ "resource": format!("{base}/mcp"), "authorization_servers": [base] }))
+}
+
+async fn authorization_server(State(mock): State<Mock>) -> Json<Value> {
+    let base = mock.lock().unwrap().base.clone();
+    Json(json!({
+        "issuer": base,
+        "authorization_endpoint": format!("{base}/authorize"),
+        "token_endpoint": format!("{base}/token"),
+        "registration_endpoint": format!("{base}/register"),
+        "code_challenge_methods_supported": ["S256"],
+    }))
+}
+
+async fn register(Json(request): Json<Value>) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::CREATED,
+        Json(json!({
+            "client_id": "registered-client",
+            "client_name": request["client_name"],
+            "redirect_uris": request["redirect_uris"],
+        })),
+    )
+}
+
+async fn authorize(
+    State(mock): State<Mock>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    assert_eq!(query["client_id"], "registered-client");
+    assert_eq!(query["code_challenge_method"], "S256");
+    let mut mock = mock.lock().unwrap();
+    let code = format!("code-{}", mock.codes.len());
+    mock.codes
+        .insert(code.clone(), query["code_challenge"].clone());
+    Redirect::temporary(&format!(
+        "{}?code={code}&state={}",
+        query["redirect_uri"], query["state"]
+    ))
+    .into_response()
+}
+
+async fn token(State(mock): State<Mock>, Form(form): Form<Vec<(String, String)>>) -> Response {
+    let form = form.into_iter().collect::<HashMap<_, _>>();
+    let mut mock = mock.lock().unwrap();
+    let invalid_grant = || {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "invalid_grant" })),
+        )
+            .into_response()
+    };
+    match form["grant_type"].as_str() {
+        "authorization_code" => {
+            let Some(challenge) = mock.codes.remove(&form["code"]) else {
+                return invalid_grant();
+            };
+            let verifier = PkceCodeVerifier::new(form["code_verifier"].clone());
+            if PkceCodeChallenge::from_code_verifier_sha256(&verifier).as_str() != challenge {
+                return invalid_grant();
+            }
+            Json(mock.issue_tokens()).into_response()
+        }
+        "refresh_token" => {
+            mock.refresh_grants += 1;
+            if !mock.refresh_tokens.remove(&form["refresh_token"]) {
+                return invalid_grant();
+            }
+            Json(mock.issue_tokens()).into_response()
+        }
+        _ => invalid_grant(),
+    }
+}
+
+async fn require_token(State(mock): State<Mock>, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| mock.lock().unwrap().access_tokens.contains(token));
+    if authorized {
+        next.run(request).await
+    } else {
+        StatusCode::UNAUTHORIZED.into_response()
+    }
+}
+
+async fn serve() -> anyhow::Result<(SocketAddr, Mock, CancellationToken)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let mock = Mock::default();
+    mock.lock().unwrap().base = format!("http://{addr}");
+    let (server, mcp) = StreamableHttpServer::new(StreamableHttpServerConfig {
+        bind: addr,
+        path: "/mcp".to_string(),
+        ..Default::default()
+    });
+    let ct = server.with_service(|| Echo);
+    let router = Router::new()
+        .route(
+            "/.well-known/oauth-protected-resource/mcp",
+            get(protected_resource),
+        )
+        .route(
+            "/.well-known/oauth-authorization-server",
+            get(authorization_server),
+        )
+        .route("/register", post(register))
+        .route("/authorize", get(authorize))
+        .route("/token", post(token))
+        .with_state(mock.clone())
+        .merge(mcp.layer(from_fn_with_state(mock.clone(), require_token)));
+    let shutdown = ct.clone();
+    tokio::spawn(async move {
+        axum::serve(listener, router)
+            .with_graceful_shutdown(async move { shutdown.cancelled().await })
+            .await
+    });
+    Ok((addr, mock, ct))
+}
+
+/// Plays the user agent, following the authorization url to the redirect, counting the
+/// authorizations
+fn user_agent(authorizations: Arc<AtomicUsize>) -> impl AuthorizationHandler {
+    move |authorization_url: String| {
+        let authorizations = authorizations.clone();
+        async move {
+            authorizations.fetch_add(1, Ordering::SeqCst);
+            let client = reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()?;
+            let response = client.get(authorization_url).send().await?;
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .ok_or(AuthError::AuthorizationFailed("no redirect".to_string()))?;
+            let redirect = reqwest::Url::parse(location)?;
+            let query = redirect.query_pairs().collect::<HashMap<_, _>>();
+            Ok::<_, AuthError>(AuthorizationCallback {
+                code: query["code"].to_string(),
+                state: query["state"].to_string(),
+            })
+        }
+    }
+}
+
+async fn authorized_manager(
+    addr: SocketAddr,
+    store: InMemoryCredentialStore,
+    authorizations: Arc<AtomicUsize>,
+) -> anyhow::Result<AuthorizationManager> {
+    let mut manager = AuthorizationManager::new(format!("http://{addr}/mcp")).await?;
+    manager.with_credential_store(store);
+    manager.with_authorization_handler(user_agent(authorizations));
+    manager.authorize(&["mcp"], REDIRECT_URI).await?;
+    Ok(manager)
+}
+
+fn transport(
+    addr: SocketAddr,
+    manager: AuthorizationManager,
+) -> StreamableHttpClientTransport<AuthClient<reqwest::Client>> {
+    StreamableHttpClientTransport::with_client(
+        AuthClient::new(reqwest::Client::default(), manager),
+        StreamableHttpClientTransportConfig::with_uri(format!("http://{addr}/mcp")),
+    )
+}
+
+#[tokio::test]
+async fn test_initial_authorization() -> anyhow::Result<()> {
+    let (addr, _mock, ct) = serve().await?;
+    let store = InMemoryCredentialStore::new();
+    let authorizations = Arc::new(AtomicUsize::new(0));
+    let manager = authorized_manager(addr, store.clone(), authorizations.clone()).await?;
+    assert_eq!(authorizations.load(Ordering::SeqCst), 1);
+
+    let stored = store.load().await?.expect("the credentials are stored");
+    assert_eq!(stored.client_id, "registered-client");
+    assert!(stored.token_response.is_some());
+
+    let client = ().serve(transport(addr, manager)).await?;
+    assert_eq!(client.list_all_tools().await?.len(), 1);
+    client.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refresh_on_unauthorized() -> anyhow::Result<()> {
+    let (addr, mock, ct) = serve().await?;
+    let store = InMemoryCredentialStore::new();
+    let authorizations = Arc::new(AtomicUsize::new(0));
+    let manager = authorized_manager(addr, store.clone(), authorizations.clone()).await?;
+    let client = ().serve(transport(addr, manager)).await?;
+
+    // the access token expires on the server
+    mock.lock().unwrap().access_tokens.clear();
+    assert_eq!(client.list_all_tools().await?.len(), 1);
+    assert_eq!(mock.lock().unwrap().refresh_grants, 1);
+    assert_eq!(authorizations.load(Ordering::SeqCst), 1);
+
+    // the refreshed tokens are stored
+    let stored = store.load().await?.and_then(|stored| stored.token_response);
+    let access_token = serde_json::to_value(stored)?["access_token"].clone();
+    assert_eq!(access_token, "access-2");
+    client.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reauthorize_revoked_tokens() -> anyhow::Result<()> {
+    let (addr, mock, ct) = serve().await?;
+    let authorizations = Arc::new(AtomicUsize::new(0));
+    let manager =
+        authorized_manager(addr, InMemoryCredentialStore::new(), authorizations.clone()).await?;
+    let client = ().serve(transport(addr, manager)).await?;
+
+    {
+        let mut mock = mock.lock().unwrap();
+        mock.access_tokens.clear();
+        mock.refresh_tokens.clear();
+    }
+    assert_eq!(client.list_all_tools().await?.len(), 1);
+    assert_eq!(mock.lock().unwrap().refresh_grants, 1);
+    assert_eq!(authorizations.load(Ordering::SeqCst), 2);
+    client.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stored_credentials() -> anyhow::Result<()> {
+    let (addr, _mock, ct) = serve().await?;
+    let store = InMemoryCredentialStore::new();
+    let authorizations = Arc::new(AtomicUsize::new(0));
+    authorized_manager(addr, store.clone(), authorizations.clone()).await?;
+
+    // a new run of the application authorizes with the stored tokens
+    let manager = authorized_manager(addr, store, authorizations.clone()).await?;
+    assert_eq!(authorizations.load(Ordering::SeqCst), 1);
+    let client = ().serve(transport(addr, manager)).await?;
+    assert_eq!(client.list_all_tools().await?.len(), 1);
+    client.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_logout() -> anyhow::Result<()> {
+    let (addr, _mock, ct) = serve().await?;
+    let store = InMemoryCredentialStore::new();
+    let authorizations = Arc::new(AtomicUsize::new(0));
+    let mut manager = authorized_manager(addr, store.clone(), authorizations.clone()).await?;
+
+    manager.logout().await?;
+    assert!(store.load().await?.is_none());
+    assert!(manager.get_access_token().await.is_err());
+
+    // the registered client authorizes through the handler again
+    manager.authorize(&["mcp"], REDIRECT_URI).await?;
+    assert_eq!(authorizations.load(Ordering::SeqCst), 2);
+    let stored = store.load().await?.expect("the credentials are stored");
+    assert_eq!(stored.client_id, "registered-client");
+    let client = ().serve(transport(addr, manager)).await?;
+    assert_eq!(client.list_all_tools().await?.len(), 1);
+    client.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
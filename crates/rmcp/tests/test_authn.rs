@@ -0,0 +1,222 @@
+//cargo test --test test_authn --features "client server macros transport-sse-server transport-sse-client transport-streamable-http-server transport-streamable-http-client __reqwest"
+use std::net::SocketAddr;
+
+use axum::{Router, http::HeaderMap};
+use reqwest::{StatusCode, header};
+use rmcp::{
+    RoleClient, ServiceExt,
+    handler::server::tool::{Extension, Session},
+    model::CallToolRequestParam,
+    service::{Identity, RunningService},
+    tool,
+    transport::{
+        HttpClient, SseClientTransport, SseServer, StreamableHttpClientTransport,
+        StreamableHttpServer,
+        authn::{ApiKeyValidator, AuthnError, AuthnLayer, bearer_token},
+        sse_client::SseClientConfig,
+        sse_server::SseServerConfig,
+        streamable_http_client::StreamableHttpClientTransportConfig,
+        streamable_http_server::axum::StreamableHttpServerConfig,
+    },
+};
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Clone, Default)]
+pub struct Whoami;
+
+#[tool(tool_box)]
+impl Whoami {
+    #[tool(description = "Tell who the client is")]
+    async fn whoami(
+        &self,
+        Session(session): Session<Identity>,
+        Extension(request): Extension<Identity>,
+    ) -> String {
+        assert_eq!(session, request);
+        session.subject
+    }
+}
+
+fn api_keys() -> AuthnLayer {
+    AuthnLayer::new(
+        ApiKeyValidator::new()
+            .with_key("alice-key", Identity::new("alice"))
+            .with_key("bob-key", Identity::new("bob")),
+    )
+}
+
+async fn serve(router: Router, ct: CancellationToken) -> anyhow::Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        axum::serve(listener, router)
+            .with_graceful_shutdown(async move { ct.cancelled().await })
+            .await
+    });
+    Ok(addr)
+}
+
+async fn serve_sse(authn: AuthnLayer) -> anyhow::Result<(SocketAddr, CancellationToken)> {
+    let (server, router) = SseServer::new(SseServerConfig::default().with_authn(authn));
+    let ct = server.with_service(|| Whoami);
+    Ok((serve(router, ct.clone()).await?, ct))
+}
+
+async fn serve_streamable_http(
+    authn: AuthnLayer,
+) -> anyhow::Result<(SocketAddr, CancellationToken)> {
+    let (server, router) =
+        StreamableHttpServer::new(StreamableHttpServerConfig::default().with_authn(authn));
+    let ct = server.with_service(|| Whoami);
+    Ok((serve(router, ct.clone()).await?, ct))
+}
+
+async fn whoami(client: &RunningService<RoleClient, ()>) -> anyhow::Result<String> {
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "whoami".into(),
+            arguments: None,
+        })
+        .await?;
+    Ok(result.content[0].as_text().unwrap().text.clone())
+}
+
+fn initialize() -> serde_json::Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": { "name": "test", "version": "0.0.0" }
+        }
+    })
+}
+
+#[tokio::test]
+async fn test_sse_identity() -> anyhow::Result<()> {
+    let (addr, ct) = serve_sse(api_keys()).await?;
+    let transport = SseClientTransport::start_with_client(
+        HttpClient::builder().bearer_token("alice-key").build()?,
+        SseClientConfig {
+            sse_endpoint: format!("http://{addr}/sse").into(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    let client = ().serve(transport).await?;
+    assert_eq!(whoami(&client).await?, "alice");
+    client.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_streamable_http_identity() -> anyhow::Result<()> {
+    let (addr, ct) = serve_streamable_http(api_keys()).await?;
+    let connect = |key: &'static str| -> anyhow::Result<_> {
+        Ok(StreamableHttpClientTransport::with_client(
+            HttpClient::builder()
+                .header(
+                    header::HeaderName::from_static("x-api-key"),
+                    header::HeaderValue::from_static(key),
+                )
+                .build()?,
+            StreamableHttpClientTransportConfig::with_uri(format!("http://{addr}/")),
+        ))
+    };
+    let alice = ().serve(connect("alice-key")?).await?;
+    let bob = ().serve(connect("bob-key")?).await?;
+    assert_eq!(whoami(&alice).await?, "alice");
+    assert_eq!(whoami(&bob).await?, "bob");
+    alice.cancel().await?;
+    bob.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_and_invalid_credentials() -> anyhow::Result<()> {
+    let (sse_addr, sse_ct) = serve_sse(api_keys()).await?;
+    let (http_addr, http_ct) = serve_streamable_http(api_keys()).await?;
+    let client = reqwest::Client::new();
+
+    let requests = [
+        client.get(format!("http://{sse_addr}/sse")),
+        client
+            .post(format!("http://{sse_addr}/message?sessionId=unknown"))
+            .json(&initialize()),
+        client
+            .post(format!("http://{http_addr}/"))
+            .header(header::ACCEPT, "application/json, text/event-stream")
+            .json(&initialize()),
+    ];
+    for request in requests {
+        let missing = request.try_clone().unwrap().send().await?;
+        assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            missing.headers()[header::WWW_AUTHENTICATE],
+            "Bearer realm=\"mcp\""
+        );
+
+        let invalid = request.bearer_auth("mallory-key").send().await?;
+        assert_eq!(invalid.status(), StatusCode::UNAUTHORIZED);
+        let challenge = invalid.headers()[header::WWW_AUTHENTICATE].to_str()?;
+        assert!(challenge.contains("error=\"invalid_token\""), "{challenge}");
+    }
+    sse_ct.cancel();
+    http_ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_forbidden() -> anyhow::Result<()> {
+    // a validator checking the scopes of the identity too
+    let authn = AuthnLayer::new(|headers: &HeaderMap| {
+        let token = bearer_token(headers).map(str::to_owned);
+        async move {
+            match token.as_deref() {
+                None => Err(AuthnError::Missing),
+                Some("admin") => Ok(Identity::new("admin").with_scopes(["mcp"])),
+                Some("guest") => Err(AuthnError::Forbidden("missing scope mcp".into())),
+                Some(_) => Err(AuthnError::Invalid("unknown token".into())),
+            }
+        }
+    })
+    .with_resource_metadata("http://localhost/.well-known/oauth-protected-resource");
+    let (addr, ct) = serve_streamable_http(authn).await?;
+
+    let forbidden = reqwest::Client::new()
+        .post(format!("http://{addr}/"))
+        .header(header::ACCEPT, "application/json, text/event-stream")
+        .bearer_auth("guest")
+        .json(&initialize())
+        .send()
+        .await?;
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+    let challenge = forbidden.headers()[header::WWW_AUTHENTICATE].to_str()?;
+    assert!(
+        challenge.contains("error=\"insufficient_scope\""),
+        "{challenge}"
+    );
+    assert!(
+        challenge.contains(
+            "resource_metadata=\"http://localhost/.well-known/oauth-protected-resource\""
+        ),
+        "{challenge}"
+    );
+
+    let admin = ()
+        .serve(StreamableHttpClientTransport::with_client(
+            HttpClient::builder().bearer_token("admin").build()?,
+            StreamableHttpClientTransportConfig::with_uri(format!("http://{addr}/")),
+        ))
+        .await?;
+    assert_eq!(whoami(&admin).await?, "admin");
+    admin.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
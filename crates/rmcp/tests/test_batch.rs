@@ -0,0 +1,212 @@
+//cargo test --test test_batch --features "server"
+use std::time::Duration;
+
+use rmcp::{
+    Error as McpError, RoleServer, ServerHandler, ServiceExt,
+    model::{CallToolRequestParam, CallToolResult, ProgressNotificationParam},
+    service::RequestContext,
+};
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream, Lines, ReadHalf, WriteHalf},
+    sync::mpsc,
+};
+
+/// Reports the notifications it handles
+#[derive(Debug, Clone)]
+struct Server {
+    notified: mpsc::UnboundedSender<&'static str>,
+}
+
+impl ServerHandler for Server {
+    async fn on_roots_list_changed(&self) {
+        let _ = self.notified.send("roots");
+    }
+
+    async fn on_progress(&self, _params: ProgressNotificationParam) {
+        let _ = self.notified.send("progress");
+    }
+
+    async fn call_tool(
+        &self,
+        _request: CallToolRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        Ok(CallToolResult::success(vec![]))
+    }
+}
+
+/// A client writing raw JSON lines
+struct Script {
+    lines: Lines<BufReader<ReadHalf<DuplexStream>>>,
+    write: WriteHalf<DuplexStream>,
+}
+
+impl Script {
+    async fn send(&mut self, message: Value) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(&message)?;
+        line.push('\n');
+        self.write.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+
+    async fn answer(&mut self) -> anyhow::Result<Value> {
+        let line = tokio::time::timeout(Duration::from_secs(1), self.lines.next_line())
+            .await??
+            .expect("the server should answer");
+        Ok(serde_json::from_str(&line)?)
+    }
+}
+
+fn ping(id: u32) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": "ping" })
+}
+
+/// The ids of the responses of a batch, sorted
+fn batch_ids(answer: &Value) -> Vec<u64> {
+    let responses = answer.as_array().expect("a batch response");
+    let mut ids: Vec<u64> = responses
+        .iter()
+        .map(|response| response["id"].as_u64().expect("a numeric id"))
+        .collect();
+    ids.sort();
+    ids
+}
+
+async fn start() -> anyhow::Result<(Script, mpsc::UnboundedReceiver<&'static str>)> {
+    let (server_io, client_io) = tokio::io::duplex(4096);
+    let (notified, notified_rx) = mpsc::unbounded_channel();
+    let (read, write) = tokio::io::split(client_io);
+    let mut script = Script {
+        lines: BufReader::new(read).lines(),
+        write,
+    };
+    script
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": { "name": "script", "version": "0.0.0" },
+            },
+        }))
+        .await?;
+    tokio::spawn(async move {
+        let server = Server { notified }.serve(server_io).await?;
+        server.waiting().await?;
+        anyhow::Ok(())
+    });
+    let initialized = script.answer().await?;
+    assert_eq!(initialized["id"], 0);
+    Ok((script, notified_rx))
+}
+
+#[tokio::test]
+async fn test_initialized_in_a_batch() -> anyhow::Result<()> {
+    let (mut script, _notified) = start().await?;
+    script
+        .send(json!([
+            { "jsonrpc": "2.0", "method": "notifications/initialized" },
+            ping(1),
+        ]))
+        .await?;
+    assert_eq!(batch_ids(&script.answer().await?), [1]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_is_answered_with_one_batch() -> anyhow::Result<()> {
+    let (mut script, mut notified) = start().await?;
+    script
+        .send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+        .await?;
+    script
+        .send(json!([
+            ping(1),
+            { "jsonrpc": "2.0", "method": "notifications/roots/list_changed" },
+            ping(2),
+            {
+                "jsonrpc": "2.0",
+                "method": "notifications/progress",
+                "params": { "progressToken": 7, "progress": 1 },
+            },
+        ]))
+        .await?;
+    assert_eq!(batch_ids(&script.answer().await?), [1, 2]);
+
+    let mut handled = Vec::new();
+    for _ in 0..2 {
+        let notification = tokio::time::timeout(Duration::from_secs(1), notified.recv()).await?;
+        handled.extend(notification);
+    }
+    handled.sort();
+    assert_eq!(handled, ["progress", "roots"]);
+
+    // a request outside of a batch is answered on its own
+    script.send(ping(3)).await?;
+    assert_eq!(script.answer().await?["id"], 3);
+    Ok(())
+}
+
+/// The error code of the responses of a batch with `id`, `None` for a result
+fn batch_errors(answer: &Value, id: u64) -> Vec<Option<i64>> {
+    let responses = answer.as_array().expect("a batch response");
+    responses
+        .iter()
+        .filter(|response| response["id"] == id)
+        .map(|response| response["error"]["code"].as_i64())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_duplicate_ids_in_a_batch_are_rejected() -> anyhow::Result<()> {
+    let (mut script, _notified) = start().await?;
+    script
+        .send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+        .await?;
+    script.send(json!([ping(1), ping(1), ping(2)])).await?;
+    let answer = script.answer().await?;
+    assert_eq!(batch_ids(&answer), [1, 1, 2]);
+    // the first is run, the second is an invalid request
+    let mut errors = batch_errors(&answer, 1);
+    errors.sort();
+    assert_eq!(errors, [None, Some(-32600)]);
+    assert_eq!(batch_errors(&answer, 2), [None]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ids_in_flight_are_rejected_in_a_batch() -> anyhow::Result<()> {
+    let (mut script, _notified) = start().await?;
+    script
+        .send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+        .await?;
+    script
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": 5,
+            "method": "tools/call",
+            "params": { "name": "slow" },
+        }))
+        .await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    script.send(json!([ping(5), ping(6)])).await?;
+    let answer = script.answer().await?;
+    assert_eq!(batch_ids(&answer), [5, 6]);
+    assert_eq!(batch_errors(&answer, 5), [Some(-32600)]);
+    assert_eq!(batch_errors(&answer, 6), [None]);
+
+    // nothing else of the batch is left, its response isn't delayed
+    script.send(json!([ping(5)])).await?;
+    let answer = script.answer().await?;
+    assert_eq!(batch_errors(&answer, 5), [Some(-32600)]);
+
+    // the call in flight is still answered
+    let call = script.answer().await?;
+    assert_eq!(call["id"], 5);
+    assert!(call["result"].is_object());
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//cargo test --test test_call_tool_typed --features "client server macros"
+
+use rmcp::{
+    RoleClient, ServiceExt,
+    handler::server::wrapper::Json,
+    service::{RunningService, ToolCallError},
+    tool,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SumRequest {
+    pub a: i64,
+    pub b: i64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Sum {
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Calculator;
+
+#[tool(tool_box)]
+impl Calculator {
+    #[tool(description = "Sum two numbers, as structured content")]
+    async fn sum(&self, #[tool(aggr)] request: SumRequest) -> Json<Sum> {
+        Json(Sum {
+            total: request.a + request.b,
+        })
+    }
+
+    #[tool(description = "Sum two numbers, as json text only")]
+    async fn sum_text(&self, #[tool(aggr)] request: SumRequest) -> String {
+        serde_json::json!({ "total": request.a + request.b }).to_string()
+    }
+
+    #[tool(description = "Divide two numbers")]
+    async fn divide(&self, #[tool(aggr)] request: SumRequest) -> Result<String, String> {
+        if request.b == 0 {
+            return Err("division by zero".into());
+        }
+        Ok((request.a / request.b).to_string())
+    }
+
+    #[tool(description = "Greet someone")]
+    async fn hello(&self) -> String {
+        "hello".into()
+    }
+}
+
+async fn connect() -> anyhow::Result<RunningService<RoleClient, ()>> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        Calculator.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    Ok(().serve(client_transport).await?)
+}
+
+#[tokio::test]
+async fn test_call_tool_typed_results() -> anyhow::Result<()> {
+    let client = connect().await?;
+
+    let sum: Sum = client
+        .call_tool_typed("sum", &SumRequest { a: 1, b: 2 })
+        .await?;
+    assert_eq!(sum, Sum { total: 3 });
+
+    let sum: Sum = client
+        .call_tool_typed("sum_text", &SumRequest { a: 40, b: 2 })
+        .await?;
+    assert_eq!(sum, Sum { total: 42 });
+
+    let quotient: i64 = client
+        .call_tool_typed("divide", &SumRequest { a: 9, b: 3 })
+        .await?;
+    assert_eq!(quotient, 3);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_call_tool_typed_errors() -> anyhow::Result<()> {
+    let client = connect().await?;
+
+    let result = client
+        .call_tool_typed::<_, i64>("divide", &SumRequest { a: 1, b: 0 })
+        .await;
+    let Err(ToolCallError::Tool { text, result }) = result else {
+        panic!("expected a tool error, got {result:?}");
+    };
+    assert_eq!(text, "division by zero");
+    assert_eq!(result.is_error, Some(true));
+
+    // unit serializes to null and is sent without arguments, the text is not json
+    let result = client.call_tool_typed::<_, String>("hello", &()).await;
+    let Err(ToolCallError::Deserialize { result, .. }) = result else {
+        panic!("expected a deserialization error, got {result:?}");
+    };
+    assert_eq!(result.content[0].as_text().unwrap().text, "hello");
+
+    let result = client
+        .call_tool_typed::<_, String>("sum", &SumRequest { a: 1, b: 2 })
+        .await;
+    assert!(matches!(result, Err(ToolCallError::Deserialize { .. })));
+
+    let result = client.call_tool_typed::<_, Sum>("sum", &[1, 2]).await;
+    assert!(matches!(result, Err(ToolCallError::Serialize(_))));
+
+    let result = client.call_tool_typed::<_, Sum>("missing", &()).await;
+    assert!(matches!(result, Err(ToolCallError::Service(_))));
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,93 @@
+//cargo test --test test_child_process_builder --features "client transport-child-process"
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use rmcp::transport::{ChildProcessBuilder, StderrLines, StderrPolicy};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn next_line(lines: &mut StderrLines) -> Option<String> {
+    tokio::time::timeout(TIMEOUT, lines.next_line())
+        .await
+        .expect("stderr line in time")
+        .expect("read stderr")
+}
+
+fn shell(script: &str) -> ChildProcessBuilder {
+    ChildProcessBuilder::new("/bin/sh").arg("-c").arg(script)
+}
+
+#[tokio::test]
+async fn test_env_propagation() -> anyhow::Result<()> {
+    let (_process, lines) = shell(r#"echo "$RMCP_GREETING ${PATH:+inherited}" >&2"#)
+        .env("RMCP_GREETING", "hello")
+        .stderr(StderrPolicy::Capture)
+        .spawn()?;
+    let mut lines = lines.expect("captured stderr");
+    assert_eq!(
+        next_line(&mut lines).await.as_deref(),
+        Some("hello inherited")
+    );
+    assert_eq!(next_line(&mut lines).await, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_env_clear_and_current_dir() -> anyhow::Result<()> {
+    // not PATH, which the shell sets itself when it is missing
+    let (_process, lines) = shell(r#"echo "${HOME:-unset} $RMCP_KEPT" >&2; pwd -P >&2"#)
+        .env_clear()
+        .env("RMCP_KEPT", "kept")
+        .current_dir("/")
+        .stderr(StderrPolicy::Capture)
+        .spawn()?;
+    let mut lines = lines.expect("captured stderr");
+    assert_eq!(next_line(&mut lines).await.as_deref(), Some("unset kept"));
+    assert_eq!(next_line(&mut lines).await.as_deref(), Some("/"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stderr_lines_reach_callback() -> anyhow::Result<()> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (_process, lines) = shell("echo first >&2; echo second >&2")
+        .on_stderr(move |line| {
+            let _ = tx.send(line);
+        })
+        .spawn()?;
+    assert!(lines.is_none());
+    let mut received = Vec::new();
+    while let Some(line) = tokio::time::timeout(TIMEOUT, rx.recv()).await? {
+        received.push(line);
+    }
+    assert_eq!(received, ["first", "second"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_grace_period_lets_child_exit() -> anyhow::Result<()> {
+    // the child only reports once its stdin is closed, which a killed child can't do
+    let (process, lines) = shell("cat > /dev/null; echo stdin closed >&2")
+        .stderr(StderrPolicy::Capture)
+        .kill_grace_period(TIMEOUT)
+        .spawn()?;
+    let mut lines = lines.expect("captured stderr");
+    drop(process);
+    assert_eq!(next_line(&mut lines).await.as_deref(), Some("stdin closed"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_child_killed_after_grace_period() -> anyhow::Result<()> {
+    let (process, lines) = shell("echo started >&2; exec sleep 30")
+        .stderr(StderrPolicy::Capture)
+        .kill_grace_period(Duration::from_millis(100))
+        .spawn()?;
+    let mut lines = lines.expect("captured stderr");
+    assert_eq!(next_line(&mut lines).await.as_deref(), Some("started"));
+    drop(process);
+    // the stderr is closed once the child is killed
+    assert_eq!(next_line(&mut lines).await, None);
+    Ok(())
+}
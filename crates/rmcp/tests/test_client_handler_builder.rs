@@ -0,0 +1,139 @@
+//cargo test --test test_client_handler_builder --features "client server macros"
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rmcp::{
+    ServerHandler, ServiceExt,
+    handler::{
+        client::ClientHandlerBuilder,
+        server::tool::{ToolBoxItem, ToolRouter},
+    },
+    model::{
+        CallToolResult, ResourceUpdatedNotificationParam, ServerCapabilities, ServerInfo, Tool,
+    },
+    tool,
+};
+use tokio::sync::Notify;
+
+#[derive(Debug, Clone, Default)]
+pub struct PluginServer {
+    router: ToolRouter<PluginServer>,
+}
+
+#[tool]
+impl PluginServer {
+    #[tool(description = "Add two numbers")]
+    async fn sum(&self, #[tool(param)] a: i32, #[tool(param)] b: i32) -> String {
+        (a + b).to_string()
+    }
+}
+
+impl ServerHandler for PluginServer {
+    rmcp::tool_box!(@derive_router router);
+
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .build(),
+            ..Default::default()
+        }
+    }
+}
+
+/// The tools last listed by the client, and the uris of the updated resources
+#[derive(Debug, Clone, Default)]
+struct Cache {
+    tools: Arc<Mutex<Vec<String>>>,
+    updated: Arc<Mutex<Vec<String>>>,
+    changed: Arc<Notify>,
+}
+
+impl Cache {
+    fn tools(&self) -> Vec<String> {
+        self.tools.lock().unwrap().clone()
+    }
+
+    async fn wait_for(&self, done: impl Fn(&Self) -> bool) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !done(self) {
+                self.changed.notified().await;
+            }
+        })
+        .await
+        .expect("the cache should be refreshed");
+    }
+}
+
+#[tokio::test]
+async fn test_callbacks_refresh_the_cache() -> anyhow::Result<()> {
+    let server = PluginServer::default();
+    server
+        .router
+        .add(ToolBoxItem::new(PluginServer::sum_tool_attr(), |context| {
+            Box::pin(PluginServer::sum_tool_call(context))
+        }));
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let running_server = server.clone().serve(server_transport);
+
+    let cache = Cache::default();
+    let handler = ClientHandlerBuilder::new()
+        .on_tools_changed({
+            let cache = cache.clone();
+            move |peer| {
+                let cache = cache.clone();
+                async move {
+                    let tools = peer.list_all_tools().await.expect("list the tools");
+                    let mut names: Vec<String> =
+                        tools.into_iter().map(|tool| tool.name.into()).collect();
+                    names.sort();
+                    *cache.tools.lock().unwrap() = names;
+                    cache.changed.notify_one();
+                }
+            }
+        })
+        .on_resource_updated({
+            let cache = cache.clone();
+            move |_peer, uri| {
+                let cache = cache.clone();
+                async move {
+                    cache.updated.lock().unwrap().push(uri);
+                    cache.changed.notify_one();
+                }
+            }
+        })
+        .build();
+    let (running_server, client) = tokio::join!(running_server, handler.serve(client_transport));
+    let (running_server, client) = (running_server?, client?);
+
+    // the server notifies the clients which listed the tools
+    client.list_all_tools().await?;
+    server.router.route(
+        "noop",
+        Tool::new("", "Do nothing", serde_json::Map::new()),
+        |_| Box::pin(async { Ok(CallToolResult::success(vec![])) }),
+    );
+    cache
+        .wait_for(|cache| cache.tools() == ["noop", "sum"])
+        .await;
+
+    server.router.remove("sum");
+    cache.wait_for(|cache| cache.tools() == ["noop"]).await;
+
+    running_server
+        .notify_resource_updated(ResourceUpdatedNotificationParam {
+            uri: "file:///notes.md".into(),
+        })
+        .await?;
+    cache
+        .wait_for(|cache| *cache.updated.lock().unwrap() == ["file:///notes.md"])
+        .await;
+
+    client.cancel().await?;
+    running_server.cancel().await?;
+    Ok(())
+}
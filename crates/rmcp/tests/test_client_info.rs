@@ -0,0 +1,74 @@
+//cargo test --test test_client_info --features "client server macros"
+
+use rmcp::{
+    Peer, RoleServer, ServerHandler, ServiceExt,
+    model::{ClientCapabilities, ClientInfo, Implementation, ServerCapabilities, ServerInfo},
+    tool,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Server;
+
+#[tool(tool_box, default_build = false)]
+impl Server {
+    #[tool(description = "The info of the client calling the tool")]
+    async fn whoami(&self, peer: Peer<RoleServer>) -> String {
+        serde_json::to_string(peer.peer_info()).expect("failed to serialize the client info")
+    }
+}
+
+#[tool(tool_box)]
+impl ServerHandler for Server {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            instructions: Some("call whoami".into()),
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            server_info: Implementation::new("whoami-server", "2.0.0"),
+            ..Default::default()
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_custom_client_info() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        Server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let info = ClientInfo::new(Implementation::new("my-client", "1.2.3"))
+        .with_capabilities(
+            ClientCapabilities::builder()
+                .enable_roots()
+                .enable_roots_list_changed()
+                .build(),
+        )
+        .with_experimental(
+            "my-extension",
+            serde_json::json!({ "level": 2 })
+                .as_object()
+                .cloned()
+                .unwrap(),
+        );
+    let client = info.clone().serve(client_transport).await?;
+
+    // the client reads the server info after the handshake
+    let server_info = client.peer_info();
+    assert_eq!(server_info.instructions.as_deref(), Some("call whoami"));
+    assert_eq!(
+        server_info.server_info,
+        Implementation::new("whoami-server", "2.0.0")
+    );
+    assert!(server_info.capabilities.tools.is_some());
+    assert_eq!(server_info.protocol_version, info.protocol_version);
+
+    // the server saw the custom client info
+    let seen: ClientInfo = client.call_tool_typed("whoami", &()).await?;
+    assert_eq!(seen.client_info, Implementation::new("my-client", "1.2.3"));
+    assert!(seen.capabilities.supports_roots_list_changed());
+    assert!(seen.capabilities.supports_experimental("my-extension"));
+    assert_eq!(seen, info);
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//cargo test --test test_client_roots --features "client server"
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    RoleClient, RoleServer, ServerHandler, ServiceExt,
+    handler::{
+        client::{CallbackClientHandler, ClientHandlerBuilder, roots::RootsProvider},
+        server::{WithExtensions, roots::RootsWatcher},
+    },
+    model::{ClientCapabilities, ClientInfo, Root},
+    service::RunningService,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct FileServer {
+    roots_changed: Arc<AtomicUsize>,
+}
+
+impl ServerHandler for FileServer {
+    async fn on_roots_list_changed(&self) {
+        self.roots_changed.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn root(uri: &str) -> Root {
+    Root {
+        uri: uri.into(),
+        name: None,
+    }
+}
+
+async fn connect(
+    server: FileServer,
+    watcher: &RootsWatcher,
+    client: CallbackClientHandler,
+) -> anyhow::Result<(
+    RunningService<RoleServer, WithExtensions<FileServer>>,
+    RunningService<RoleClient, CallbackClientHandler>,
+)> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let (server, client) = tokio::join!(
+        server
+            .with_roots_watcher(watcher.clone())
+            .serve(server_transport),
+        client.serve(client_transport)
+    );
+    Ok((server?, client?))
+}
+
+#[tokio::test]
+async fn test_static_roots() -> anyhow::Result<()> {
+    let watcher = RootsWatcher::new();
+    let first_listing = watcher.changed();
+    let client = ClientHandlerBuilder::new()
+        .with_roots(vec![root("file:///project")])
+        .build();
+    let (server, client) = connect(FileServer::default(), &watcher, client).await?;
+
+    let capabilities = &server.peer().peer_info().capabilities;
+    assert!(capabilities.supports_roots());
+    assert!(!capabilities.supports_roots_list_changed());
+    let roots = tokio::time::timeout(Duration::from_secs(1), first_listing).await?;
+    assert_eq!(roots, [root("file:///project")]);
+
+    client.cancel().await?;
+    server.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dynamic_roots_notify_the_server() -> anyhow::Result<()> {
+    let watcher = RootsWatcher::new();
+    let first_listing = watcher.changed();
+    let (roots, provider) = RootsProvider::channel(vec![root("file:///project")]);
+    let client = ClientHandlerBuilder::new().with_roots(provider).build();
+    let (server, client) = connect(FileServer::default(), &watcher, client).await?;
+
+    assert!(
+        server
+            .peer()
+            .peer_info()
+            .capabilities
+            .supports_roots_list_changed()
+    );
+    let listed = tokio::time::timeout(Duration::from_secs(1), first_listing).await?;
+    assert_eq!(listed, [root("file:///project")]);
+
+    let next_listing = watcher.changed();
+    roots.send_replace(vec![root("file:///project"), root("file:///library")]);
+    let listed = tokio::time::timeout(Duration::from_secs(1), next_listing).await?;
+    assert_eq!(listed, [root("file:///project"), root("file:///library")]);
+
+    client.cancel().await?;
+    server.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_roots_change_not_notified_without_capability() -> anyhow::Result<()> {
+    let server = FileServer::default();
+    let roots_changed = server.roots_changed.clone();
+    let watcher = RootsWatcher::new();
+    let first_listing = watcher.changed();
+    let (roots, provider) = RootsProvider::channel(vec![root("file:///project")]);
+    // the roots are advertised, their changes aren't
+    let info = ClientInfo {
+        capabilities: ClientCapabilities::builder().enable_roots().build(),
+        ..Default::default()
+    };
+    let client = ClientHandlerBuilder::with_handler(info)
+        .with_roots(provider)
+        .build();
+    let (server, client) = connect(server, &watcher, client).await?;
+    tokio::time::timeout(Duration::from_secs(1), first_listing).await?;
+
+    roots.send_replace(vec![root("file:///library")]);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(roots_changed.load(Ordering::SeqCst), 0);
+    assert_eq!(watcher.current(), [root("file:///project")]);
+
+    // the server still lists the current roots
+    assert_eq!(watcher.refresh().await?, [root("file:///library")]);
+
+    client.cancel().await?;
+    server.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,137 @@
+//cargo test --test test_completion --features "client server"
+
+use rmcp::{
+    ServerHandler, ServiceExt,
+    handler::server::completion::CompletionRouter,
+    model::{
+        ArgumentInfo, CompleteRequestParam, CompletionInfo, PromptReference, Reference,
+        ResourceReference,
+    },
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct ReviewServer;
+
+impl ServerHandler for ReviewServer {}
+
+fn completions() -> CompletionRouter {
+    CompletionRouter::new()
+        .prompt_argument("code_review", "language", |value| async move {
+            Ok(CompletionInfo::matching(
+                ["go", "python", "ruby", "rust", "typescript"],
+                &value,
+            ))
+        })
+        .resource_argument("file:///logs/{day}", "day", |value| async move {
+            // more days than fit in one completion
+            Ok((1..=150)
+                .map(|day| format!("{day:03}"))
+                .filter(|day| day.starts_with(&value))
+                .collect::<Vec<_>>())
+        })
+}
+
+fn prompt_request(name: &str, argument: &str, value: &str) -> CompleteRequestParam {
+    CompleteRequestParam {
+        r#ref: Reference::Prompt(PromptReference { name: name.into() }),
+        argument: ArgumentInfo {
+            name: argument.into(),
+            value: value.into(),
+        },
+    }
+}
+
+fn resource_request(uri: &str, argument: &str, value: &str) -> CompleteRequestParam {
+    CompleteRequestParam {
+        r#ref: Reference::Resource(ResourceReference { uri: uri.into() }),
+        argument: ArgumentInfo {
+            name: argument.into(),
+            value: value.into(),
+        },
+    }
+}
+
+#[tokio::test]
+async fn test_prompt_and_resource_completions() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = ReviewServer.with_completions(completions());
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+    assert!(client.peer_info().capabilities.completions.is_some());
+
+    let result = client
+        .complete(prompt_request("code_review", "language", "r"))
+        .await?;
+    assert_eq!(result.completion.values, vec!["ruby", "rust"]);
+    assert_eq!(result.completion.total, Some(2));
+    assert_eq!(result.completion.has_more, Some(false));
+
+    // cut to the limit of the spec
+    let result = client
+        .complete(resource_request("file:///logs/{day}", "day", ""))
+        .await?;
+    assert_eq!(result.completion.values.len(), CompletionInfo::MAX_VALUES);
+    assert_eq!(result.completion.values[0], "001");
+    assert_eq!(result.completion.total, Some(150));
+    assert_eq!(result.completion.has_more, Some(true));
+
+    let result = client
+        .complete(resource_request("file:///logs/{day}", "day", "14"))
+        .await?;
+    assert_eq!(
+        result.completion.values,
+        vec![
+            "140", "141", "142", "143", "144", "145", "146", "147", "148", "149"
+        ]
+    );
+    assert_eq!(result.completion.has_more, Some(false));
+
+    // arguments without a provider complete to nothing
+    for request in [
+        prompt_request("code_review", "style", "r"),
+        prompt_request("summarize", "language", "r"),
+        resource_request("file:///{path}", "day", ""),
+    ] {
+        let result = client.complete(request).await?;
+        assert!(result.completion.values.is_empty());
+    }
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_complete_has_no_completions() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        ReviewServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+
+    let result = client
+        .complete(prompt_request("code_review", "language", "r"))
+        .await?;
+    assert!(result.completion.values.is_empty());
+    assert_eq!(result.completion.total, None);
+    assert_eq!(result.completion.has_more, None);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[test]
+fn test_completion_info_serialization() {
+    let completion = CompletionInfo::matching(["alpha", "beta"], "a");
+    assert_eq!(
+        serde_json::to_value(&completion).unwrap(),
+        serde_json::json!({ "values": ["alpha"], "total": 1, "hasMore": false })
+    );
+}
@@ -0,0 +1,211 @@
+//cargo test --test test_compression --features "client server macros transport-sse-server transport-sse-client transport-streamable-http-server transport-streamable-http-client __reqwest compression"
+use std::{
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use axum::{
+    Router,
+    body::Body,
+    extract::{Request, State},
+    http::header::CONTENT_ENCODING,
+    middleware::{Next, from_fn_with_state},
+    response::Response,
+};
+use futures::StreamExt;
+use rmcp::{
+    RoleClient, ServiceExt,
+    model::CallToolRequestParam,
+    service::RunningService,
+    tool,
+    transport::{
+        HttpClient, SseClientTransport, SseServer, StreamableHttpClientTransport,
+        StreamableHttpServer, common::compression::CompressionConfig, sse_client::SseClientConfig,
+        sse_server::SseServerConfig, streamable_http_client::StreamableHttpClientTransportConfig,
+        streamable_http_server::axum::StreamableHttpServerConfig,
+    },
+};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+const BLOB_SIZE: usize = 4 * 1024 * 1024;
+
+/// A base64 resource, as large tool results usually are
+fn blob(size: usize) -> String {
+    "cm1jcCBibG9i".chars().cycle().take(size).collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Blobs;
+
+#[tool(tool_box)]
+impl Blobs {
+    #[tool(description = "Return a blob of the size")]
+    async fn blob(&self, #[tool(param)] size: usize) -> String {
+        blob(size)
+    }
+
+    #[tool(description = "Measure the text")]
+    async fn length(&self, #[tool(param)] text: String) -> String {
+        text.len().to_string()
+    }
+}
+
+/// The bytes on the wire, outside of the compression
+#[derive(Debug, Clone, Default)]
+struct Wire {
+    received: Arc<AtomicUsize>,
+    sent: Arc<AtomicUsize>,
+    compressed_requests: Arc<AtomicUsize>,
+}
+
+async fn measure(State(wire): State<Wire>, request: Request, next: Next) -> Response {
+    if request.headers().contains_key(CONTENT_ENCODING) {
+        wire.compressed_requests.fetch_add(1, Ordering::SeqCst);
+    }
+    let (parts, body) = request.into_parts();
+    let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+    wire.received.fetch_add(body.len(), Ordering::SeqCst);
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    let (parts, body) = response.into_parts();
+    let sent = wire.sent.clone();
+    let body = body.into_data_stream().inspect(move |chunk| {
+        if let Ok(chunk) = chunk {
+            sent.fetch_add(chunk.len(), Ordering::SeqCst);
+        }
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+async fn serve(router: Router, ct: CancellationToken) -> anyhow::Result<(SocketAddr, Wire)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let wire = Wire::default();
+    let router = router.layer(from_fn_with_state(wire.clone(), measure));
+    tokio::spawn(async move {
+        axum::serve(listener, router)
+            .with_graceful_shutdown(async move { ct.cancelled().await })
+            .await
+    });
+    Ok((addr, wire))
+}
+
+async fn serve_streamable_http(
+    compression: Option<CompressionConfig>,
+) -> anyhow::Result<(SocketAddr, Wire, CancellationToken)> {
+    let (server, router) = StreamableHttpServer::new(StreamableHttpServerConfig {
+        compression,
+        ..Default::default()
+    });
+    let ct = server.with_service(|| Blobs);
+    let (addr, wire) = serve(router, ct.clone()).await?;
+    Ok((addr, wire, ct))
+}
+
+async fn connect_streamable_http(
+    addr: SocketAddr,
+) -> anyhow::Result<RunningService<RoleClient, ()>> {
+    let client = HttpClient::builder()
+        .compress_requests_above(1024)
+        .build()?;
+    Ok(()
+        .serve(StreamableHttpClientTransport::with_client(
+            client,
+            StreamableHttpClientTransportConfig::with_uri(format!("http://{addr}/")),
+        ))
+        .await?)
+}
+
+async fn call(
+    client: &RunningService<RoleClient, ()>,
+    name: &'static str,
+    arguments: rmcp::model::JsonObject,
+) -> anyhow::Result<String> {
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: name.into(),
+            arguments: Some(arguments.into()),
+        })
+        .await?;
+    Ok(result.content[0].as_text().unwrap().text.clone())
+}
+
+#[tokio::test]
+async fn test_streamable_http_compression() -> anyhow::Result<()> {
+    let (addr, wire, ct) = serve_streamable_http(Some(CompressionConfig::default())).await?;
+    let client = connect_streamable_http(addr).await?;
+
+    let result = call(&client, "blob", rmcp::object!({ "size": BLOB_SIZE })).await?;
+    assert!(result == blob(BLOB_SIZE));
+    let sent = wire.sent.load(Ordering::SeqCst);
+    assert!(sent < BLOB_SIZE / 10, "{sent} bytes sent");
+
+    let text = blob(1024 * 1024);
+    let length = call(&client, "length", rmcp::object!({ "text": text })).await?;
+    assert_eq!(length, "1048576");
+    assert!(wire.compressed_requests.load(Ordering::SeqCst) >= 1);
+    let received = wire.received.load(Ordering::SeqCst);
+    assert!(received < 1024 * 1024 / 10, "{received} bytes received");
+
+    client.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_uncompressed_server() -> anyhow::Result<()> {
+    let (addr, wire, ct) = serve_streamable_http(None).await?;
+    let client = HttpClient::builder().build()?;
+    let client = ()
+        .serve(StreamableHttpClientTransport::with_client(
+            client,
+            StreamableHttpClientTransportConfig::with_uri(format!("http://{addr}/")),
+        ))
+        .await?;
+
+    let result = call(&client, "blob", rmcp::object!({ "size": BLOB_SIZE })).await?;
+    assert!(result == blob(BLOB_SIZE));
+    // the client accepts compressed responses, the server doesn't send any
+    assert!(wire.sent.load(Ordering::SeqCst) > BLOB_SIZE);
+
+    client.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sse_compression() -> anyhow::Result<()> {
+    let (server, router) =
+        SseServer::new(SseServerConfig::default().with_compression(CompressionConfig::default()));
+    let ct = server.with_service(|| Blobs);
+    let (addr, wire) = serve(router, ct.clone()).await?;
+    let transport = SseClientTransport::start_with_client(
+        HttpClient::builder()
+            .compress_requests_above(1024)
+            .build()?,
+        SseClientConfig {
+            sse_endpoint: format!("http://{addr}/sse").into(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    let client = ().serve(transport).await?;
+
+    // the messages come through the long lived event stream, flushed one by one
+    let result = call(&client, "blob", rmcp::object!({ "size": BLOB_SIZE })).await?;
+    assert!(result == blob(BLOB_SIZE));
+    let text = blob(1024 * 1024);
+    let length = call(&client, "length", rmcp::object!({ "text": text })).await?;
+    assert_eq!(length, "1048576");
+
+    let sent = wire.sent.load(Ordering::SeqCst);
+    assert!(sent < BLOB_SIZE / 10, "{sent} bytes sent");
+    assert!(wire.compressed_requests.load(Ordering::SeqCst) >= 1);
+
+    client.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
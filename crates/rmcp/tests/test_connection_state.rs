@@ -0,0 +1,170 @@
+//cargo test --test test_connection_state --features "client server"
+use std::{sync::Mutex, time::Duration};
+
+use rmcp::{
+    RoleServer, ServerHandler, ServiceExt,
+    model::{Implementation, InitializeRequestParam, InitializeResult, ServerInfo},
+    service::{ConnectionState, QuitReason, RequestContext},
+    transport::async_rw::{AsyncRwTransport, CodecLimits},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    sync::{mpsc, oneshot, watch},
+};
+
+type ServerState = watch::Receiver<ConnectionState<RoleServer>>;
+
+/// Hands the state of its session over when the client initializes
+#[derive(Debug)]
+struct Server {
+    state: Mutex<Option<oneshot::Sender<ServerState>>>,
+}
+
+impl ServerHandler for Server {
+    async fn initialize(
+        &self,
+        _request: InitializeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<InitializeResult, rmcp::Error> {
+        let state = context.peer.state_watch();
+        assert!(matches!(*state.borrow(), ConnectionState::Initializing));
+        if let Some(tx) = self.state.lock().unwrap().take() {
+            let _ = tx.send(state);
+        }
+        Ok(self.get_info())
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            server_info: Implementation {
+                name: "state".into(),
+                version: "1.0.0".into(),
+            },
+            ..Default::default()
+        }
+    }
+}
+
+async fn wait_for(
+    state: &mut ServerState,
+    f: impl FnMut(&ConnectionState<RoleServer>) -> bool,
+) -> ConnectionState<RoleServer> {
+    tokio::time::timeout(Duration::from_secs(1), state.wait_for(f))
+        .await
+        .expect("the state should change")
+        .expect("the state should be kept")
+        .clone()
+}
+
+#[tokio::test]
+async fn test_server_session_lifecycle() -> anyhow::Result<()> {
+    let (server_io, client_io) = tokio::io::duplex(4096);
+    let (state_tx, state_rx) = oneshot::channel();
+    let server = Server {
+        state: Mutex::new(Some(state_tx)),
+    };
+    let (read, write) = tokio::io::split(server_io);
+    let transport = AsyncRwTransport::new_server(read, write).with_limits(CodecLimits {
+        max_invalid_messages: 1,
+        ..Default::default()
+    });
+
+    // a client writing the lines it is told to, and reading the answers
+    let (script_tx, mut script_rx) = mpsc::channel::<&'static str>(1);
+    let script = tokio::spawn(async move {
+        let (read, mut write) = tokio::io::split(client_io);
+        let mut lines = BufReader::new(read).lines();
+        let mut answers = Vec::new();
+        while let Some(line) = script_rx.recv().await {
+            write.write_all(line.as_bytes()).await?;
+            if line.contains("\"id\"") {
+                answers.extend(lines.next_line().await?);
+            }
+        }
+        anyhow::Ok(answers)
+    });
+
+    script_tx
+        .send(concat!(
+            r#"{"jsonrpc":"2.0","id":0,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{},"clientInfo":{"name":"script","version":"0.0.0"}}}"#,
+            "\n",
+        ))
+        .await?;
+    let running = server.serve(transport);
+    script_tx
+        .send("{\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}\n")
+        .await?;
+    let server = running.await?;
+    let mut state = state_rx.await?;
+
+    let ConnectionState::Ready(client_info) = wait_for(&mut state, |s| s.is_ready()).await else {
+        unreachable!()
+    };
+    assert_eq!(client_info.client_info.name, "script");
+
+    // one invalid line is skipped, the service keeps serving
+    script_tx
+        .send("garbage\n{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n")
+        .await?;
+    let degraded = wait_for(&mut state, |s| matches!(s, ConnectionState::Degraded(_))).await;
+    let ConnectionState::Degraded(reason) = degraded else {
+        unreachable!()
+    };
+    assert_eq!(reason, "skipped 1 lines that are no JSON-RPC message");
+
+    // the second one breaks the protocol
+    script_tx.send("{not json either\n").await?;
+    let closed = wait_for(&mut state, |s| s.is_closed()).await;
+    let ConnectionState::Closed(QuitReason::ProtocolError(error)) = closed else {
+        panic!("expected a protocol error, got {closed:?}");
+    };
+    assert_eq!(error.payload.as_deref(), Some("{not json either"));
+    assert!(matches!(
+        server.waiting().await?,
+        QuitReason::ProtocolError(_)
+    ));
+
+    drop(script_tx);
+    let answers = script.await??;
+    assert_eq!(answers.len(), 2);
+    assert!(answers[1].contains("\"id\":1"), "{}", answers[1]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_lifecycle() -> anyhow::Result<()> {
+    let (server_io, client_io) = tokio::io::duplex(4096);
+    let (state_tx, state_rx) = oneshot::channel();
+    let server = Server {
+        state: Mutex::new(Some(state_tx)),
+    };
+    let server = tokio::spawn(server.serve(server_io));
+    let client = ().serve(client_io).await?;
+    let server = server.await??;
+    let mut server_state = state_rx.await?;
+
+    let mut client_state = client.state_watch();
+    let ConnectionState::Ready(server_info) = client_state.borrow().clone() else {
+        panic!("the client should be ready once served");
+    };
+    assert_eq!(server_info.server_info.name, "state");
+    wait_for(&mut server_state, |s| s.is_ready()).await;
+
+    assert!(matches!(
+        client.shutdown_graceful(Duration::from_secs(1)).await?,
+        QuitReason::Graceful
+    ));
+    assert!(matches!(
+        *client_state.borrow_and_update(),
+        ConnectionState::Closed(QuitReason::Graceful)
+    ));
+
+    // the server reads the end of the transport
+    let closed = wait_for(&mut server_state, |s| s.is_closed()).await;
+    assert!(matches!(
+        closed,
+        ConnectionState::Closed(QuitReason::Closed)
+    ));
+    assert!(matches!(server.waiting().await?, QuitReason::Closed));
+    Ok(())
+}
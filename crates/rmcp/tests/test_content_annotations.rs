@@ -0,0 +1,99 @@
+//cargo test --test test_content_annotations
+
+use chrono::{DateTime, Utc};
+use rmcp::model::{
+    AnnotateAble, Annotations, CallToolResult, Content, ListResourcesResult, PromptMessage,
+    PromptMessageRole, RawResource, ResourceContents, Role,
+};
+
+// serialized by the TypeScript SDK, which keeps the order of the spec's fields
+const TEXT: &str =
+    r#"{"type":"text","text":"Hello","annotations":{"audience":["user"],"priority":0.9}}"#;
+const IMAGE: &str = r#"{"type":"image","data":"aGk=","mimeType":"image/png","annotations":{"audience":["user","assistant"],"priority":0.5,"lastModified":"2025-01-12T15:00:58Z"}}"#;
+const EMBEDDED_RESOURCE: &str = r#"{"type":"resource","resource":{"uri":"file:///notes.txt","mimeType":"text/plain","text":"notes"},"annotations":{"audience":["assistant"]}}"#;
+const RESOURCE: &str = r#"{"uri":"file:///notes.txt","name":"notes.txt","annotations":{"priority":1,"lastModified":"2025-01-12T15:00:58Z"}}"#;
+const PROMPT_MESSAGE: &str = r#"{"role":"user","content":{"type":"text","text":"Hi","annotations":{"audience":["assistant"],"priority":0.25}}}"#;
+
+fn last_modified() -> DateTime<Utc> {
+    "2025-01-12T15:00:58Z".parse().unwrap()
+}
+
+#[test]
+fn test_tool_result_annotations() -> anyhow::Result<()> {
+    let text = Content::text("Hello")
+        .with_audience(Role::User)
+        .with_priority(0.9);
+    assert_eq!(serde_json::to_string(&text)?, TEXT);
+    assert_eq!(serde_json::from_str::<Content>(TEXT)?, text);
+
+    let image = Content::image("aGk=", "image/png")
+        .with_audience([Role::User, Role::Assistant])
+        .with_priority(0.5)
+        .with_last_modified(last_modified());
+    assert_eq!(serde_json::to_string(&image)?, IMAGE);
+    assert_eq!(serde_json::from_str::<Content>(IMAGE)?, image);
+
+    let embedded = Content::resource(ResourceContents::TextResourceContents {
+        uri: "file:///notes.txt".into(),
+        mime_type: Some("text/plain".into()),
+        text: "notes".into(),
+    })
+    .with_audience(Role::Assistant);
+    assert_eq!(serde_json::to_string(&embedded)?, EMBEDDED_RESOURCE);
+
+    let result = CallToolResult {
+        is_error: None,
+        ..CallToolResult::success(vec![text, image, embedded])
+    };
+    let expected = format!(r#"{{"content":[{TEXT},{IMAGE},{EMBEDDED_RESOURCE}]}}"#);
+    assert_eq!(serde_json::to_string(&result)?, expected);
+    assert_eq!(serde_json::from_str::<CallToolResult>(&expected)?, result);
+    Ok(())
+}
+
+#[test]
+fn test_resource_annotations() -> anyhow::Result<()> {
+    let resource = RawResource::new("file:///notes.txt", "notes.txt")
+        .annotate(Annotations::for_resource(1.0, last_modified()));
+    assert_eq!(serde_json::to_string(&resource)?, RESOURCE);
+
+    let listed: ListResourcesResult =
+        serde_json::from_str(&format!(r#"{{"resources":[{RESOURCE}]}}"#))?;
+    assert_eq!(listed.resources, vec![resource]);
+    assert_eq!(listed.resources[0].last_modified(), Some(last_modified()));
+    Ok(())
+}
+
+#[test]
+fn test_prompt_message_annotations() -> anyhow::Result<()> {
+    let message = PromptMessage::new_text(PromptMessageRole::User, "Hi").annotate(
+        Annotations::new()
+            .with_audience(Role::Assistant)
+            .with_priority(0.25),
+    );
+    assert_eq!(serde_json::to_string(&message)?, PROMPT_MESSAGE);
+    let round_tripped: PromptMessage = serde_json::from_str(PROMPT_MESSAGE)?;
+    assert_eq!(round_tripped, message);
+    assert_eq!(
+        round_tripped.content.annotations().unwrap().audience,
+        Some(vec![Role::Assistant])
+    );
+
+    // without annotations nothing is added
+    let plain = PromptMessage::new_text(PromptMessageRole::User, "Hi");
+    assert_eq!(
+        serde_json::to_string(&plain)?,
+        r#"{"role":"user","content":{"type":"text","text":"Hi"}}"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_legacy_timestamp() -> anyhow::Result<()> {
+    let content: Content = serde_json::from_str(
+        r#"{"type":"text","text":"Hello","annotations":{"timestamp":"2025-01-12T15:00:58Z"}}"#,
+    )?;
+    assert_eq!(content.last_modified(), Some(last_modified()));
+    assert!(serde_json::to_string(&content)?.contains(r#""lastModified":"2025-01-12T15:00:58Z""#));
+    Ok(())
+}
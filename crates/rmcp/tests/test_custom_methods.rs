@@ -0,0 +1,256 @@
+//cargo test --test test_custom_methods --features "client server"
+use rmcp::{
+    ClientHandler, Peer, RoleClient, RoleServer, ServerHandler, ServiceError, ServiceExt,
+    model::{
+        ClientNotification, ClientRequest, CustomNotification, CustomRequest, CustomResult,
+        ErrorCode, ServerResult,
+    },
+    service::RequestContext,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::mpsc;
+
+/// Knows `future/echo` and reports the unknown notifications
+#[derive(Debug, Clone)]
+struct Server {
+    notified: mpsc::UnboundedSender<CustomNotification>,
+}
+
+impl ServerHandler for Server {
+    async fn on_custom_request(
+        &self,
+        request: CustomRequest,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CustomResult, rmcp::Error> {
+        if request.method != "future/echo" {
+            return Err(rmcp::Error::new(
+                ErrorCode::METHOD_NOT_FOUND,
+                request.method,
+                None,
+            ));
+        }
+        Ok(CustomResult(json!({ "echo": request.params })))
+    }
+
+    async fn on_custom_notification(&self, notification: CustomNotification) {
+        let _ = self.notified.send(notification);
+    }
+}
+
+#[tokio::test]
+async fn test_custom_methods() -> anyhow::Result<()> {
+    let (server_io, client_io) = tokio::io::duplex(4096);
+    let (notified, mut notified_rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let server = Server { notified }.serve(server_io).await?;
+        server.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_io).await?;
+
+    let result = client
+        .send_request(ClientRequest::CustomRequest(CustomRequest::new(
+            "future/echo",
+            Some(json!({ "say": "hi" })),
+        )))
+        .await?;
+    let ServerResult::CustomResult(CustomResult(echo)) = result else {
+        panic!("expected a custom result, got {result:?}");
+    };
+    assert_eq!(echo["echo"]["say"], "hi");
+    // the progress token set by the peer is sent in `_meta`
+    assert!(echo["echo"]["_meta"]["progressToken"].is_number(), "{echo}");
+
+    let error = client
+        .send_request(ClientRequest::CustomRequest(CustomRequest::new(
+            "future/unknown",
+            None,
+        )))
+        .await
+        .expect_err("the method is unknown");
+    let ServiceError::McpError(error) = error else {
+        panic!("expected an error response, got {error:?}");
+    };
+    assert_eq!(error.code, ErrorCode::METHOD_NOT_FOUND);
+    assert_eq!(error.message, "future/unknown");
+
+    client
+        .send_notification(ClientNotification::CustomNotification(
+            CustomNotification::new("notifications/future", Some(json!({ "step": 1 }))),
+        ))
+        .await?;
+    let notification = notified_rx.recv().await.expect("the notification");
+    assert_eq!(notification.method, "notifications/future");
+    assert_eq!(notification.params, Some(json!({ "step": 1 })));
+
+    // the session survived the unknown messages
+    client.list_all_tools().await?;
+    client.cancel().await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+struct DefaultServer;
+
+impl ServerHandler for DefaultServer {}
+
+#[tokio::test]
+async fn test_unknown_request_is_method_not_found_by_default() -> anyhow::Result<()> {
+    let (server_io, client_io) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let server = DefaultServer.serve(server_io).await?;
+        server.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_io).await?;
+    let error = client
+        .send_request(ClientRequest::CustomRequest(CustomRequest::new(
+            "future/method",
+            Some(json!([1, 2])),
+        )))
+        .await
+        .expect_err("the method is unknown");
+    assert!(
+        matches!(&error, ServiceError::McpError(error) if error.code == ErrorCode::METHOD_NOT_FOUND),
+        "{error:?}"
+    );
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_known_request_with_invalid_params_is_invalid_params() -> anyhow::Result<()> {
+    // a known method is never read as a custom one
+    let request = json!({ "method": "tools/call", "params": { "arguments": {} } });
+    assert!(serde_json::from_value::<CustomRequest>(request.clone()).is_err());
+    let notification = json!({ "method": "notifications/progress", "params": {} });
+    assert!(serde_json::from_value::<CustomNotification>(notification).is_err());
+
+    let (server_io, client_io) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let server = DefaultServer.serve(server_io).await?;
+        server.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_io).await?;
+    // a tools/call without the name of the tool
+    let error = client
+        .send_request(ClientRequest::CustomRequest(CustomRequest::new(
+            "tools/call",
+            Some(json!({ "arguments": {} })),
+        )))
+        .await
+        .expect_err("the params are invalid");
+    assert!(
+        matches!(&error, ServiceError::McpError(error) if error.code == ErrorCode::INVALID_PARAMS),
+        "{error:?}"
+    );
+
+    // the session survived the invalid request
+    client.list_all_tools().await?;
+    client.cancel().await?;
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Echo {
+    text: String,
+}
+
+/// Answers `x-demo/echo` sent by the server, and reports `x-demo/said`
+#[derive(Debug, Clone)]
+struct EchoClient {
+    peer: Option<Peer<RoleClient>>,
+    said: mpsc::UnboundedSender<Echo>,
+}
+
+impl ClientHandler for EchoClient {
+    async fn on_custom_request(
+        &self,
+        request: CustomRequest,
+        context: RequestContext<RoleClient>,
+    ) -> Result<CustomResult, rmcp::Error> {
+        if request.method != "x-demo/echo" {
+            return Err(rmcp::Error::new(
+                ErrorCode::METHOD_NOT_FOUND,
+                request.method,
+                None,
+            ));
+        }
+        // the `_meta` of the params is read in the meta of the request
+        assert!(context.meta.progress_token().is_some());
+        let echo: Echo = serde_json::from_value(request.params.unwrap_or_default())?;
+        Ok(CustomResult(serde_json::to_value(echo)?))
+    }
+
+    async fn on_custom_notification(&self, notification: CustomNotification) {
+        let echo = notification
+            .params
+            .filter(|_| notification.method == "x-demo/said")
+            .and_then(|params| serde_json::from_value(params).ok());
+        if let Some(echo) = echo {
+            let _ = self.said.send(echo);
+        }
+    }
+
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+}
+
+#[tokio::test]
+async fn test_x_demo_echo() -> anyhow::Result<()> {
+    let (server_io, client_io) = tokio::io::duplex(4096);
+    let (said, mut said_rx) = mpsc::unbounded_channel();
+    let (server, client) = tokio::join!(
+        DefaultServer.serve(server_io),
+        EchoClient { peer: None, said }.serve(client_io)
+    );
+    let (server, client) = (server?, client?);
+
+    let echo: Echo = server
+        .send_custom_request("x-demo/echo", Echo { text: "hi".into() })
+        .await?;
+    assert_eq!(echo, Echo { text: "hi".into() });
+
+    server
+        .send_custom_notification("x-demo/said", Echo { text: "bye".into() })
+        .await?;
+    assert_eq!(said_rx.recv().await, Some(Echo { text: "bye".into() }));
+
+    let error = server
+        .send_custom_request::<Echo>("x-demo/shout", Echo { text: "hi".into() })
+        .await
+        .expect_err("the client doesn't know the method");
+    assert!(
+        matches!(&error, ServiceError::McpError(error) if error.code == ErrorCode::METHOD_NOT_FOUND),
+        "{error:?}"
+    );
+
+    client.cancel().await?;
+    server.waiting().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_answers_method_not_found_by_default() -> anyhow::Result<()> {
+    let (server_io, client_io) = tokio::io::duplex(4096);
+    let (server, client) = tokio::join!(DefaultServer.serve(server_io), ().serve(client_io));
+    let (server, client) = (server?, client?);
+    let error = server
+        .send_custom_request::<Echo>("x-demo/echo", Echo { text: "hi".into() })
+        .await
+        .expect_err("the method is unknown");
+    assert!(
+        matches!(&error, ServiceError::McpError(error) if error.code == ErrorCode::METHOD_NOT_FOUND),
+        "{error:?}"
+    );
+    client.cancel().await?;
+    server.waiting().await?;
+    Ok(())
+}
@@ -1,4 +1,14 @@
-use rmcp::model::{JsonRpcResponse, ServerJsonRpcMessage, ServerResult};
+use rmcp::model::{
+    JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, PromptMessageContent, RawContent,
+    ServerJsonRpcMessage, ServerNotification, ServerRequest, ServerResult,
+};
+use serde_json::Value;
+
+fn fixture(name: &str) -> Value {
+    let json = std::fs::read(format!("tests/test_deserialization/{name}")).unwrap();
+    serde_json::from_slice(&json).unwrap()
+}
+
 #[test]
 fn test_tool_list_result() {
     let json = std::fs::read("tests/test_deserialization/tool_list_result.json").unwrap();
@@ -13,3 +23,73 @@ fn test_tool_list_result() {
         })
     ));
 }
+
+#[test]
+fn test_future_content_type() {
+    let json = fixture("future_call_tool_result.json");
+    let message: ServerJsonRpcMessage = serde_json::from_value(json.clone()).unwrap();
+    let ServerJsonRpcMessage::Response(JsonRpcResponse {
+        result: ServerResult::CallToolResult(result),
+        ..
+    }) = &message
+    else {
+        panic!("expected a tool call result, got {message:#?}");
+    };
+    // unknown fields of a known content are ignored
+    assert_eq!(
+        result.content[0].as_text().map(|text| text.text.as_str()),
+        Some("the clip of the talk")
+    );
+    let RawContent::Unknown(video) = &result.content[1].raw else {
+        panic!("expected an unknown content, got {:?}", result.content[1]);
+    };
+    assert_eq!(video["type"], "video");
+    assert!(result.content[1].annotations.is_some());
+
+    // the unknown content goes back untouched
+    let round_tripped = serde_json::to_value(&message).unwrap();
+    assert_eq!(
+        round_tripped["result"]["content"][1],
+        json["result"]["content"][1]
+    );
+}
+
+#[test]
+fn test_future_prompt_content_type() {
+    let json = fixture("future_prompt_result.json");
+    let message: ServerJsonRpcMessage = serde_json::from_value(json.clone()).unwrap();
+    let ServerJsonRpcMessage::Response(JsonRpcResponse {
+        result: ServerResult::GetPromptResult(result),
+        ..
+    }) = &message
+    else {
+        panic!("expected a prompt, got {message:#?}");
+    };
+    assert!(matches!(
+        result.messages[1].content,
+        PromptMessageContent::Unknown(_)
+    ));
+    assert_eq!(serde_json::to_value(&message).unwrap(), json);
+}
+
+#[test]
+fn test_future_methods() {
+    let Value::Array(messages) = fixture("future_messages.json") else {
+        panic!("expected an array of messages");
+    };
+    for json in messages {
+        let message: ServerJsonRpcMessage = serde_json::from_value(json.clone()).unwrap();
+        match &message {
+            ServerJsonRpcMessage::Notification(JsonRpcNotification {
+                notification: ServerNotification::CustomNotification(notification),
+                ..
+            }) => assert_eq!(notification.method, "notifications/future/changed"),
+            ServerJsonRpcMessage::Request(JsonRpcRequest {
+                request: ServerRequest::CustomRequest(request),
+                ..
+            }) => assert!(request.method.starts_with("future/")),
+            message => panic!("expected a custom message, got {message:#?}"),
+        }
+        assert_eq!(serde_json::to_value(&message).unwrap(), json);
+    }
+}
@@ -0,0 +1,181 @@
+//cargo test --test test_elicitation --features "client server macros"
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use rmcp::{
+    ClientHandler, Peer, RoleClient, RoleServer, ServiceExt,
+    model::{
+        CallToolRequestParam, CallToolResult, ClientCapabilities, ClientInfo, Content,
+        CreateElicitationRequestParam, CreateElicitationResult,
+    },
+    service::{ElicitationOutcome, RequestContext, ServiceError},
+    tool,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct Confirmation {
+    /// Whether to go ahead
+    confirm: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveServer;
+
+#[tool(tool_box)]
+impl ArchiveServer {
+    #[tool(description = "Delete an archive after asking the user")]
+    async fn delete_archive(
+        &self,
+        peer: Peer<RoleServer>,
+        #[tool(param)] name: String,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let outcome = peer
+            .elicit::<Confirmation>(format!("Delete the archive {name}?"))
+            .await?;
+        let text = match outcome {
+            ElicitationOutcome::Accepted(Confirmation { confirm: true }) => {
+                format!("deleted {name}")
+            }
+            ElicitationOutcome::Accepted(Confirmation { confirm: false }) => {
+                format!("kept {name}")
+            }
+            ElicitationOutcome::Declined => "declined".to_string(),
+            ElicitationOutcome::Cancelled => "cancelled".to_string(),
+        };
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+}
+
+/// Answers elicitations with a script of results
+#[derive(Clone, Default)]
+struct ScriptedClient {
+    answers: Arc<Mutex<VecDeque<CreateElicitationResult>>>,
+    requests: Arc<Mutex<Vec<CreateElicitationRequestParam>>>,
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl ClientHandler for ScriptedClient {
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            capabilities: ClientCapabilities::builder().enable_elicitation().build(),
+            ..Default::default()
+        }
+    }
+
+    async fn create_elicitation(
+        &self,
+        params: CreateElicitationRequestParam,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateElicitationResult, rmcp::Error> {
+        self.requests.lock().unwrap().push(params);
+        Ok(self
+            .answers
+            .lock()
+            .unwrap()
+            .pop_front()
+            .expect("no scripted answer left"))
+    }
+}
+
+fn delete_request() -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: "delete_archive".into(),
+        arguments: serde_json::json!({ "name": "2024.tar" })
+            .as_object()
+            .cloned()
+            .map(Into::into),
+    }
+}
+
+#[tokio::test]
+async fn test_elicitation_outcomes() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        ArchiveServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let handler = ScriptedClient::default();
+    handler.answers.lock().unwrap().extend([
+        CreateElicitationResult::accept(
+            serde_json::json!({ "confirm": true })
+                .as_object()
+                .cloned()
+                .unwrap(),
+        ),
+        CreateElicitationResult::decline(),
+        CreateElicitationResult::cancel(),
+        // not a `Confirmation`
+        CreateElicitationResult::accept(
+            serde_json::json!({ "confirm": "yes" })
+                .as_object()
+                .cloned()
+                .unwrap(),
+        ),
+    ]);
+    let requests = handler.requests.clone();
+    let client = handler.serve(client_transport).await?;
+
+    for expected in ["deleted 2024.tar", "declined", "cancelled"] {
+        let result = client.call_tool(delete_request()).await?;
+        assert_eq!(result.content, vec![Content::text(expected)]);
+    }
+    let Err(ServiceError::McpError(error)) = client.call_tool(delete_request()).await else {
+        panic!("content not matching the schema should fail");
+    };
+    assert!(error.message.contains("doesn't match the schema"));
+
+    let requests = std::mem::take(&mut *requests.lock().unwrap());
+    assert_eq!(requests.len(), 4);
+    assert_eq!(requests[0].message, "Delete the archive 2024.tar?");
+    let schema = &requests[0].requested_schema;
+    assert_eq!(schema["type"], "object");
+    assert_eq!(schema["properties"]["confirm"]["type"], "boolean");
+    assert_eq!(
+        schema["properties"]["confirm"]["description"],
+        "Whether to go ahead"
+    );
+    assert_eq!(schema["required"], serde_json::json!(["confirm"]));
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_elicitation_without_capability() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        ArchiveServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+
+    let Err(ServiceError::McpError(error)) = client.call_tool(delete_request()).await else {
+        panic!("elicitation should fail without the capability");
+    };
+    assert_eq!(error.message, "client doesn't support elicitation");
+    assert_eq!(error.data.unwrap()["capability"], "elicitation");
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,217 @@
+//cargo test --test test_elicitation_handler --features "client server macros"
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use rmcp::{
+    Peer, RoleServer, ServiceExt,
+    handler::client::{ClientHandlerBuilder, ElicitationHandler, ElicitationResponder},
+    model::{CallToolRequestParam, CallToolResult, Content, CreateElicitationResult},
+    service::{ElicitationOutcome, Service, ServiceError},
+    tool,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Class {
+    Economy,
+    Business,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct Booking {
+    /// How many seats to book
+    seats: u32,
+    class: Class,
+    /// Anything the crew should know
+    note: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FlightServer;
+
+#[tool(tool_box)]
+impl FlightServer {
+    #[tool(description = "Book a flight after asking the user for the details")]
+    async fn book_flight(
+        &self,
+        peer: Peer<RoleServer>,
+        #[tool(param)] flight: String,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let text = match peer.elicit::<Booking>(format!("Book {flight}?")).await? {
+            ElicitationOutcome::Accepted(booking) => format!(
+                "booked {} {:?} seats on {flight}{}",
+                booking.seats,
+                booking.class,
+                booking
+                    .note
+                    .map(|note| format!(" ({note})"))
+                    .unwrap_or_default()
+            ),
+            ElicitationOutcome::Declined => "declined".to_string(),
+            ElicitationOutcome::Cancelled => "cancelled".to_string(),
+        };
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+}
+
+enum Answer {
+    Accept(Value),
+    Decline,
+    Cancel,
+}
+
+/// Answers elicitations with a script, recording the requested fields
+#[derive(Clone, Default)]
+struct ScriptedResponder {
+    answers: Arc<Mutex<VecDeque<Answer>>>,
+    requests: Arc<Mutex<Vec<ElicitationResponder>>>,
+}
+
+impl ScriptedResponder {
+    fn answer(self, answer: Answer) -> Self {
+        self.answers.lock().unwrap().push_back(answer);
+        self
+    }
+}
+
+impl ElicitationHandler for ScriptedResponder {
+    async fn elicit(
+        &self,
+        request: ElicitationResponder,
+    ) -> Result<CreateElicitationResult, rmcp::Error> {
+        self.requests.lock().unwrap().push(request.clone());
+        let answer = self.answers.lock().unwrap().pop_front();
+        match answer.expect("no scripted answer left") {
+            Answer::Accept(value) => Ok(request.accept_value(&value)?),
+            Answer::Decline => Ok(request.decline()),
+            Answer::Cancel => Ok(request.cancel()),
+        }
+    }
+}
+
+fn book_flight() -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: "book_flight".into(),
+        arguments: json!({ "flight": "LH 454" })
+            .as_object()
+            .cloned()
+            .map(Into::into),
+    }
+}
+
+#[tokio::test]
+async fn test_elicitation_handler_answers() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        FlightServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let responder = ScriptedResponder::default()
+        .answer(Answer::Accept(json!({ "seats": 2, "class": "business" })))
+        .answer(Answer::Accept(
+            json!({ "seats": 1, "class": "economy", "note": "window" }),
+        ))
+        .answer(Answer::Decline)
+        .answer(Answer::Cancel)
+        // rejected before being sent
+        .answer(Answer::Accept(json!({ "seats": -1, "class": "first" })));
+    let requests = responder.requests.clone();
+    let client = ClientHandlerBuilder::new()
+        .with_elicitation(responder)
+        .build()
+        .serve(client_transport)
+        .await?;
+
+    // advertised on its own, the server refuses to elicit otherwise
+    assert!(
+        client
+            .service()
+            .get_info()
+            .capabilities
+            .elicitation
+            .is_some()
+    );
+    for expected in [
+        "booked 2 Business seats on LH 454",
+        "booked 1 Economy seats on LH 454 (window)",
+        "declined",
+        "cancelled",
+    ] {
+        let result = client.call_tool(book_flight()).await?;
+        assert_eq!(result.content, vec![Content::text(expected)]);
+    }
+    let Err(ServiceError::McpError(error)) = client.call_tool(book_flight()).await else {
+        panic!("content not matching the requested schema should be rejected");
+    };
+    assert!(error.message.contains("doesn't match the requested schema"));
+    assert!(error.message.contains("/seats"));
+    assert!(error.message.contains("/class"));
+
+    let requests = std::mem::take(&mut *requests.lock().unwrap());
+    assert_eq!(requests.len(), 5);
+    assert_eq!(requests[0].message(), "Book LH 454?");
+    let fields = requests[0].fields();
+    let mut names = fields
+        .iter()
+        .map(|field| field.name.as_str())
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    assert_eq!(names, ["class", "note", "seats"]);
+    let field = |name: &str| fields.iter().find(|field| field.name == name).unwrap();
+    assert_eq!(field("seats").kind.as_deref(), Some("integer"));
+    assert_eq!(
+        field("seats").description.as_deref(),
+        Some("How many seats to book")
+    );
+    assert!(field("seats").required);
+    assert_eq!(
+        field("class").options,
+        Some(vec![json!("economy"), json!("business")])
+    );
+    assert!(!field("note").required);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_elicitation_without_handler_is_refused() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        FlightServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = ClientHandlerBuilder::new()
+        .build()
+        .serve(client_transport)
+        .await?;
+    assert!(
+        client
+            .service()
+            .get_info()
+            .capabilities
+            .elicitation
+            .is_none()
+    );
+    let Err(ServiceError::McpError(error)) = client.call_tool(book_flight()).await else {
+        panic!("the server should not elicit without the capability");
+    };
+    assert!(error.message.contains("doesn't support elicitation"));
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,215 @@
+//cargo test --test test_experimental_capabilities --features "client server"
+use rmcp::{
+    ClientHandler, Peer, RoleClient, RoleServer, ServerHandler, ServiceError, ServiceExt,
+    model::{
+        ClientCapabilities, ClientInfo, CustomNotification, CustomRequest, CustomResult, ErrorCode,
+        ServerCapabilities, ServerInfo,
+    },
+    service::RequestContext,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::mpsc;
+
+/// The settings of the `myorg/batch-tools` extension
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchTools {
+    max_batch_size: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RunParams {
+    calls: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RunResult {
+    accepted: Vec<String>,
+    client_batch_size: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct BatchServer;
+
+impl ServerHandler for BatchServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .experimental("myorg/batch-tools", BatchTools { max_batch_size: 3 })
+                .build(),
+            ..Default::default()
+        }
+    }
+
+    async fn on_custom_request(
+        &self,
+        request: CustomRequest,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CustomResult, rmcp::Error> {
+        if request.method != "myorg/batch-tools/run" {
+            return Err(rmcp::Error::new(
+                ErrorCode::METHOD_NOT_FOUND,
+                request.method,
+                None,
+            ));
+        }
+        let params: RunParams = serde_json::from_value(request.params.unwrap_or_default())
+            .map_err(|error| rmcp::Error::invalid_params(error.to_string(), None))?;
+        // the server reads what the client advertised
+        let client_batch_size = context
+            .peer
+            .peer_capabilities()
+            .experimental_get::<BatchTools>("myorg/batch-tools")
+            .map(|settings| settings.max_batch_size);
+        context
+            .peer
+            .send_custom_notification(
+                "notifications/myorg/batch-tools/accepted",
+                json!({ "count": params.calls.len() }),
+            )
+            .await
+            .map_err(|error| rmcp::Error::internal_error(error.to_string(), None))?;
+        let result = RunResult {
+            accepted: params.calls,
+            client_batch_size,
+        };
+        Ok(CustomResult(serde_json::to_value(result)?))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BatchClient {
+    peer: Option<Peer<RoleClient>>,
+    notified: mpsc::UnboundedSender<CustomNotification>,
+}
+
+impl ClientHandler for BatchClient {
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            capabilities: ClientCapabilities::builder()
+                .enable_roots()
+                .experimental("myorg/batch-tools", BatchTools { max_batch_size: 2 })
+                .build(),
+            ..Default::default()
+        }
+    }
+
+    async fn on_custom_notification(&self, notification: CustomNotification) {
+        let _ = self.notified.send(notification);
+    }
+
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+}
+
+#[tokio::test]
+async fn test_peers_read_experimental_capabilities() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let (server, client) = tokio::join!(
+        BatchServer.serve(server_transport),
+        BatchClient {
+            peer: None,
+            notified: mpsc::unbounded_channel().0,
+        }
+        .serve(client_transport)
+    );
+    let (server, client) = (server?, client?);
+
+    assert_eq!(
+        client
+            .peer_capabilities()
+            .experimental_get::<BatchTools>("myorg/batch-tools"),
+        Some(BatchTools { max_batch_size: 3 })
+    );
+    assert!(client.peer_capabilities().supports_tools());
+    assert_eq!(
+        server
+            .peer_capabilities()
+            .experimental_get::<BatchTools>("myorg/batch-tools"),
+        Some(BatchTools { max_batch_size: 2 })
+    );
+    assert!(server.peer_capabilities().supports_roots());
+    assert_eq!(
+        server
+            .peer_capabilities()
+            .experimental_get::<BatchTools>("myorg/other"),
+        None
+    );
+
+    client.cancel().await?;
+    server.waiting().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_custom_request_under_extension_namespace() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        BatchServer.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let (notified, mut notified_rx) = mpsc::unbounded_channel();
+    let client = BatchClient {
+        peer: None,
+        notified,
+    }
+    .serve(client_transport)
+    .await?;
+
+    assert!(
+        client
+            .peer_capabilities()
+            .supports_experimental("myorg/batch-tools")
+    );
+    let result: RunResult = client
+        .send_custom_request(
+            "myorg/batch-tools/run",
+            RunParams {
+                calls: vec!["a".into(), "b".into()],
+            },
+        )
+        .await?;
+    assert_eq!(
+        result,
+        RunResult {
+            accepted: vec!["a".into(), "b".into()],
+            client_batch_size: Some(2),
+        }
+    );
+    let notification = notified_rx.recv().await.expect("the notification");
+    assert_eq!(
+        notification.method,
+        "notifications/myorg/batch-tools/accepted"
+    );
+    assert_eq!(notification.params, Some(json!({ "count": 2 })));
+
+    // a result of another shape is an unexpected response
+    let error = client
+        .send_custom_request::<Vec<String>>("myorg/batch-tools/run", json!({ "calls": [] }))
+        .await
+        .expect_err("the result is an object");
+    assert!(
+        matches!(error, ServiceError::UnexpectedResponse),
+        "{error:?}"
+    );
+
+    let error = client
+        .send_custom_request::<RunResult>("myorg/batch-tools/stop", ())
+        .await
+        .expect_err("the method is unknown");
+    assert!(
+        matches!(&error, ServiceError::McpError(error) if error.code == ErrorCode::METHOD_NOT_FOUND),
+        "{error:?}"
+    );
+
+    client.cancel().await?;
+    Ok(())
+}
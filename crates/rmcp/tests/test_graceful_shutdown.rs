@@ -0,0 +1,133 @@
+//cargo test --test test_graceful_shutdown --features "client server macros"
+
+use std::time::Duration;
+
+use rmcp::{
+    RoleClient, RoleServer, ServiceExt,
+    model::CallToolRequestParam,
+    service::{QuitReason, RunningService, ServiceError},
+    tool,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct SlowServer;
+
+#[tool(tool_box)]
+impl SlowServer {
+    #[tool(description = "Finish after a while")]
+    async fn slow(&self) -> String {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        "done".into()
+    }
+
+    #[tool(description = "Sleep for a long time")]
+    async fn hang(&self) -> String {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        "done".into()
+    }
+}
+
+async fn serve() -> anyhow::Result<(
+    RunningService<RoleServer, SlowServer>,
+    RunningService<RoleClient, ()>,
+)> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let (server, client) = tokio::join!(
+        SlowServer.serve(server_transport),
+        ().serve(client_transport)
+    );
+    Ok((server?, client?))
+}
+
+fn spawn_call(
+    client: &RunningService<RoleClient, ()>,
+    name: &'static str,
+) -> tokio::task::JoinHandle<Result<String, ServiceError>> {
+    let peer = client.peer().clone();
+    tokio::spawn(async move {
+        let result = peer
+            .call_tool(CallToolRequestParam {
+                name: name.into(),
+                arguments: None,
+            })
+            .await?;
+        Ok(result.content[0].as_text().unwrap().text.clone())
+    })
+}
+
+async fn wait_in_flight<S: rmcp::Service<R>, R: rmcp::service::ServiceRole>(
+    service: &RunningService<R, S>,
+    count: usize,
+) {
+    tokio::time::timeout(Duration::from_secs(1), async {
+        while service.in_flight_requests() != count {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("requests should be in flight");
+}
+
+#[tokio::test]
+async fn test_server_shutdown_drains_tool_calls() -> anyhow::Result<()> {
+    let (server, client) = serve().await?;
+    let call = spawn_call(&client, "slow");
+    wait_in_flight(&server, 1).await;
+
+    let quit_reason = server.shutdown_graceful(Duration::from_secs(5)).await?;
+    assert!(matches!(quit_reason, QuitReason::Graceful));
+    assert_eq!(call.await??, "done");
+
+    // the server closed the connection once the response was sent
+    let quit_reason = tokio::time::timeout(Duration::from_secs(1), client.waiting()).await??;
+    assert!(matches!(quit_reason, QuitReason::Closed));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_shutdown_rejects_new_requests() -> anyhow::Result<()> {
+    let (server, client) = serve().await?;
+    let first = spawn_call(&client, "slow");
+    wait_in_flight(&server, 1).await;
+
+    let shutdown = tokio::spawn(server.shutdown_graceful(Duration::from_secs(5)));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let second = spawn_call(&client, "slow").await?;
+    let Err(ServiceError::McpError(error)) = second else {
+        panic!("expected the request to be rejected, got {second:?}");
+    };
+    assert_eq!(error.message, "service is shutting down");
+
+    assert_eq!(first.await??, "done");
+    assert!(matches!(shutdown.await??, QuitReason::Graceful));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_shutdown_deadline() -> anyhow::Result<()> {
+    let (server, client) = serve().await?;
+    let call = spawn_call(&client, "hang");
+    wait_in_flight(&server, 1).await;
+
+    let quit_reason = tokio::time::timeout(
+        Duration::from_secs(1),
+        server.shutdown_graceful(Duration::from_millis(100)),
+    )
+    .await??;
+    assert!(matches!(quit_reason, QuitReason::Cancelled));
+    assert!(matches!(call.await?, Err(ServiceError::TransportClosed)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_shutdown_waits_for_responses() -> anyhow::Result<()> {
+    let (server, client) = serve().await?;
+    let call = spawn_call(&client, "slow");
+    wait_in_flight(&client, 1).await;
+
+    let quit_reason = client.shutdown_graceful(Duration::from_secs(5)).await?;
+    assert!(matches!(quit_reason, QuitReason::Graceful));
+    assert_eq!(call.await??, "done");
+    server.cancel().await?;
+    Ok(())
+}
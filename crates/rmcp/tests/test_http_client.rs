@@ -0,0 +1,211 @@
+//cargo test --test test_http_client --features "client server macros transport-sse-server transport-sse-client transport-streamable-http-server transport-streamable-http-client __reqwest"
+use std::{
+    net::SocketAddr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use axum::{
+    Router,
+    extract::{Request, State},
+    http::{HeaderMap, Method, header::AUTHORIZATION},
+    middleware::{Next, from_fn_with_state},
+    response::Response,
+};
+use reqwest::header::{HeaderName, HeaderValue};
+use rmcp::{
+    ServiceExt, tool,
+    transport::{
+        HttpClient, SseClientTransport, SseServer, StreamableHttpClientTransport,
+        StreamableHttpServer, sse_client::SseClientConfig, sse_server::SseServerConfig,
+        streamable_http_client::StreamableHttpClientTransportConfig,
+        streamable_http_server::axum::StreamableHttpServerConfig,
+    },
+};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+const API_KEY: HeaderName = HeaderName::from_static("x-api-key");
+
+#[derive(Debug, Clone, Default)]
+pub struct Echo;
+
+#[tool(tool_box)]
+impl Echo {
+    #[tool(description = "Echo the text")]
+    async fn echo(&self, #[tool(param)] text: String) -> String {
+        text
+    }
+}
+
+type Seen = Arc<Mutex<Vec<(Method, HeaderMap)>>>;
+
+async fn record(State(seen): State<Seen>, request: Request, next: Next) -> Response {
+    seen.lock()
+        .unwrap()
+        .push((request.method().clone(), request.headers().clone()));
+    next.run(request).await
+}
+
+/// Serve `router` on a local port, recording the method and the headers of every request
+fn serve(listener: TcpListener, router: Router, ct: CancellationToken) -> Seen {
+    let seen = Seen::default();
+    let router = router.layer(from_fn_with_state(seen.clone(), record));
+    tokio::spawn(async move {
+        axum::serve(listener, router)
+            .with_graceful_shutdown(async move { ct.cancelled().await })
+            .await
+    });
+    seen
+}
+
+async fn serve_sse() -> anyhow::Result<(SocketAddr, Seen, CancellationToken)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let (server, router) = SseServer::new(SseServerConfig {
+        bind: addr,
+        ..Default::default()
+    });
+    let ct = server.with_service(|| Echo);
+    let seen = serve(listener, router, ct.clone());
+    Ok((addr, seen, ct))
+}
+
+async fn serve_streamable_http() -> anyhow::Result<(SocketAddr, Seen, CancellationToken)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let (server, router) = StreamableHttpServer::new(StreamableHttpServerConfig {
+        bind: addr,
+        ..Default::default()
+    });
+    let ct = server.with_service(|| Echo);
+    let seen = serve(listener, router, ct.clone());
+    Ok((addr, seen, ct))
+}
+
+/// A client sending a new token with every request
+fn client_with_refreshed_tokens() -> anyhow::Result<HttpClient> {
+    let issued = Arc::new(AtomicUsize::new(0));
+    Ok(HttpClient::builder()
+        .header(API_KEY, HeaderValue::from_static("secret"))
+        .token_provider(move || {
+            let issued = issued.clone();
+            async move { format!("token-{}", issued.fetch_add(1, Ordering::SeqCst)) }
+        })
+        .timeout(Duration::from_secs(30))
+        .build()?)
+}
+
+/// Check the headers of every request, returning the methods of the requests
+fn check_headers(seen: &Seen) -> Vec<Method> {
+    let seen = seen.lock().unwrap();
+    let mut tokens = Vec::new();
+    for (method, headers) in seen.iter() {
+        assert_eq!(headers[&API_KEY], "secret", "{method}");
+        let token = headers[AUTHORIZATION].to_str().unwrap();
+        assert!(token.starts_with("Bearer token-"), "{method}: {token}");
+        tokens.push(token.to_owned());
+    }
+    tokens.sort();
+    tokens.dedup();
+    assert_eq!(tokens.len(), seen.len(), "every request gets a new token");
+    seen.iter().map(|(method, _)| method.clone()).collect()
+}
+
+#[tokio::test]
+async fn test_sse_client_headers() -> anyhow::Result<()> {
+    let (addr, seen, ct) = serve_sse().await?;
+    let transport = SseClientTransport::start_with_client(
+        client_with_refreshed_tokens()?,
+        SseClientConfig {
+            sse_endpoint: format!("http://{addr}/sse").into(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    let client = ().serve(transport).await?;
+    assert_eq!(client.list_all_tools().await?.len(), 1);
+    client.cancel().await?;
+
+    let methods = check_headers(&seen);
+    assert!(methods.contains(&Method::GET));
+    assert!(methods.contains(&Method::POST));
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_streamable_http_client_headers() -> anyhow::Result<()> {
+    let (addr, seen, ct) = serve_streamable_http().await?;
+    let transport = StreamableHttpClientTransport::with_client(
+        client_with_refreshed_tokens()?,
+        StreamableHttpClientTransportConfig::with_uri(format!("http://{addr}/")),
+    );
+    let client = ().serve(transport).await?;
+    assert_eq!(client.list_all_tools().await?.len(), 1);
+    client.cancel().await?;
+
+    // the session is deleted in the background
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !seen
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(method, _)| *method == Method::DELETE)
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+    let methods = check_headers(&seen);
+    assert!(methods.contains(&Method::GET));
+    assert!(methods.contains(&Method::POST));
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_basic_auth() -> anyhow::Result<()> {
+    let (addr, seen, ct) = serve_streamable_http().await?;
+    let http_client = HttpClient::builder().basic_auth("user", "pass").build()?;
+    let client = ()
+        .serve(StreamableHttpClientTransport::with_client(
+            http_client,
+            StreamableHttpClientTransportConfig::with_uri(format!("http://{addr}/")),
+        ))
+        .await?;
+    client.list_all_tools().await?;
+    client.cancel().await?;
+
+    for (_, headers) in seen.lock().unwrap().iter() {
+        assert_eq!(headers[AUTHORIZATION], "Basic dXNlcjpwYXNz");
+    }
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_proxy() -> anyhow::Result<()> {
+    // the host can't be resolved, the requests only get through the proxy
+    let (proxy_addr, seen, ct) = serve_streamable_http().await?;
+    let http_client = HttpClient::builder()
+        .proxy(format!("http://{proxy_addr}"))
+        .build()?;
+    let client = ()
+        .serve(StreamableHttpClientTransport::with_client(
+            http_client,
+            StreamableHttpClientTransportConfig::with_uri("http://mcp.invalid/"),
+        ))
+        .await?;
+    client.list_all_tools().await?;
+    client.cancel().await?;
+
+    for (_, headers) in seen.lock().unwrap().iter() {
+        assert_eq!(headers[axum::http::header::HOST], "mcp.invalid");
+    }
+    ct.cancel();
+    Ok(())
+}
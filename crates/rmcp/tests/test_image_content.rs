@@ -0,0 +1,119 @@
+//cargo test --test test_image_content --features "client server macros image-fs"
+
+use rmcp::{
+    ServiceExt,
+    model::{CallToolRequestParam, ImageContent, ImageEncoder, ImageError, RawImageContent},
+    tool,
+};
+
+const PIXEL: &[u8] = include_bytes!("test_image_content/pixel.png");
+const RED: &[u8] = include_bytes!("test_image_content/red.png");
+
+fn fixture(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/test_image_content")
+        .join(name)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImageServer;
+
+/// Serves the images of the fixtures
+#[tool(tool_box)]
+impl ImageServer {
+    #[tool(description = "A transparent pixel")]
+    async fn pixel(&self) -> Result<ImageContent, ImageError> {
+        ImageContent::from_bytes(PIXEL, "image/png")
+    }
+
+    #[tool(description = "A red square, read from its file")]
+    async fn red(&self) -> Result<ImageContent, ImageError> {
+        ImageContent::from_path(fixture("red.png")).await
+    }
+
+    #[tool(description = "A red square, larger than allowed")]
+    async fn too_large(&self) -> Result<ImageContent, ImageError> {
+        ImageEncoder::new()
+            .with_max_encoded_size(64)
+            .encode_file(fixture("red.png"))
+            .await
+    }
+}
+
+#[tokio::test]
+async fn test_image_from_path() -> anyhow::Result<()> {
+    let image = ImageContent::from_path(fixture("pixel.png")).await?;
+    assert_eq!(image.mime_type, "image/png");
+    assert_eq!(image.to_bytes()?, PIXEL);
+
+    // without magic bytes, the mime type comes from the extension
+    let dir = std::env::temp_dir().join(format!("rmcp-image-{}", std::process::id()));
+    tokio::fs::create_dir_all(&dir).await?;
+    let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="1" height="1"/>"#;
+    tokio::fs::write(dir.join("dot.svg"), svg).await?;
+    let image = ImageContent::from_path(dir.join("dot.svg")).await?;
+    assert_eq!(image.mime_type, "image/svg+xml");
+    assert_eq!(image.to_bytes()?, svg);
+
+    tokio::fs::write(dir.join("notes.txt"), "not an image").await?;
+    let error = ImageContent::from_path(dir.join("notes.txt"))
+        .await
+        .expect_err("the type is unknown");
+    assert!(matches!(error, ImageError::UnknownType), "{error}");
+    tokio::fs::remove_dir_all(&dir).await?;
+
+    let error = ImageContent::from_path(fixture("missing.png"))
+        .await
+        .expect_err("the file is missing");
+    assert!(matches!(error, ImageError::Io(_)), "{error}");
+    Ok(())
+}
+
+#[test]
+fn test_image_data_uri() -> anyhow::Result<()> {
+    let image = ImageContent::from_bytes(RED, "image/png")?;
+    let uri = image.to_data_uri();
+    let parsed: RawImageContent = uri.parse()?;
+    assert_eq!(parsed.to_bytes()?, RED);
+    assert_eq!(
+        serde_json::to_value(&parsed)?,
+        serde_json::to_value(&image)?
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_image_tool_results() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        ImageServer.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+
+    for (tool, fixture) in [("pixel", PIXEL), ("red", RED)] {
+        let result = client
+            .call_tool(CallToolRequestParam {
+                name: tool.into(),
+                arguments: None,
+            })
+            .await?;
+        assert_eq!(result.is_error, Some(false));
+        let image = result.content[0].as_image().expect("an image content");
+        assert_eq!(image.mime_type, "image/png");
+        assert_eq!(image.to_bytes()?, fixture);
+    }
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "too_large".into(),
+            arguments: None,
+        })
+        .await?;
+    assert_eq!(result.is_error, Some(true));
+    let text = &result.content[0].as_text().expect("an error text").text;
+    assert!(text.contains("more than the maximum of 64"), "{text}");
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//cargo test --test test_in_process --features "client server macros"
+use std::time::Duration;
+
+use rmcp::{
+    model::CallToolRequestParam,
+    service::ServiceError,
+    tool,
+    transport::in_process::{self, Fault, InProcessConfig, LinkConfig},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Echo;
+
+#[tool(tool_box)]
+impl Echo {
+    #[tool(description = "Echo the text")]
+    async fn echo(&self, #[tool(param)] text: String) -> String {
+        text
+    }
+}
+
+fn echo(text: &str) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: "echo".into(),
+        arguments: serde_json::json!({ "text": text })
+            .as_object()
+            .cloned()
+            .map(Into::into),
+    }
+}
+
+#[tokio::test]
+async fn test_round_trip() -> anyhow::Result<()> {
+    let (server, client) = in_process::serve_directly(Echo, ()).await?;
+    let result = client.call_tool(echo("hello")).await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "hello");
+
+    client.cancel().await?;
+    server.waiting().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lost_response_times_out() -> anyhow::Result<()> {
+    // the results of the tool calls never reach the client
+    let lose_tool_results = LinkConfig::new().with_injector(|message| {
+        if message["result"].get("content").is_some() {
+            Fault::Drop
+        } else {
+            Fault::Deliver
+        }
+    });
+    let config = InProcessConfig::default().to_client(lose_tool_results);
+    let (server, client) = in_process::serve_directly_with(config, Echo, ()).await?;
+
+    let timeout = Duration::from_millis(100);
+    let result = client.with_timeout(timeout).call_tool(echo("lost")).await;
+    let Err(ServiceError::Timeout { timeout: elapsed }) = result else {
+        panic!("expect a timeout, got {result:?}");
+    };
+    assert_eq!(elapsed, timeout);
+
+    // the other responses still arrive
+    assert_eq!(client.list_all_tools().await?.len(), 1);
+    client.cancel().await?;
+    server.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_latency() -> anyhow::Result<()> {
+    let latency = Duration::from_millis(100);
+    let config = InProcessConfig::default().to_client(LinkConfig::new().with_latency(latency));
+    let (server, client) = in_process::serve_directly_with(config, Echo, ()).await?;
+
+    let result = client
+        .with_timeout(latency / 2)
+        .call_tool(echo("slow"))
+        .await;
+    assert!(
+        matches!(result, Err(ServiceError::Timeout { .. })),
+        "{result:?}"
+    );
+
+    let start = tokio::time::Instant::now();
+    let result = client
+        .with_timeout(latency * 5)
+        .call_tool(echo("slow"))
+        .await?;
+    assert!(start.elapsed() >= latency);
+    assert_eq!(result.content[0].as_text().unwrap().text, "slow");
+
+    client.cancel().await?;
+    server.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ordering_under_delay() -> anyhow::Result<()> {
+    // every other request is held back, the ones after it wait behind it
+    let config = InProcessConfig::default().to_server(LinkConfig::new().with_injector(|message| {
+        match message["id"].as_u64() {
+            Some(id) if id % 2 == 0 => Fault::Delay(Duration::from_millis(20)),
+            _ => Fault::Deliver,
+        }
+    }));
+    let (server, client) = in_process::serve_directly_with(config, Echo, ()).await?;
+    let calls = (0..6).map(|index| {
+        let client = client.peer().clone();
+        async move { client.call_tool(echo(&index.to_string())).await }
+    });
+    for (index, result) in futures::future::join_all(calls)
+        .await
+        .into_iter()
+        .enumerate()
+    {
+        assert_eq!(
+            result?.content[0].as_text().unwrap().text,
+            index.to_string()
+        );
+    }
+    client.cancel().await?;
+    server.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,100 @@
+//cargo test --test test_initialize_validation --features "client server"
+
+use rmcp::{
+    ServerHandler, ServiceExt,
+    model::{ClientCapabilities, ClientInfo, ErrorCode, InitializeRequestParam, ProtocolVersion},
+    service::{ClientInitializeError, ServerInitializeError},
+};
+
+/// Only serves clients able to sample, with a recent protocol version
+#[derive(Debug, Clone, Default)]
+pub struct SamplingServer;
+
+impl ServerHandler for SamplingServer {
+    fn validate_initialize(&self, request: &InitializeRequestParam) -> Result<(), rmcp::Error> {
+        if !request.capabilities.supports_sampling() {
+            return Err(rmcp::Error::invalid_request(
+                "sampling is required",
+                Some(serde_json::json!({ "capability": "sampling" })),
+            ));
+        }
+        if request.protocol_version < ProtocolVersion::V_2025_03_26 {
+            return Err(rmcp::Error::invalid_request(
+                "protocol version is too old",
+                None,
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn client(capabilities: ClientCapabilities, protocol_version: ProtocolVersion) -> ClientInfo {
+    ClientInfo {
+        protocol_version,
+        capabilities,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_accepted_client() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let client = client(
+        ClientCapabilities::builder().enable_sampling().build(),
+        ProtocolVersion::V_2025_03_26,
+    );
+    let (server, client) = tokio::join!(
+        SamplingServer.serve(server_transport),
+        client.serve(client_transport)
+    );
+    let (server, client) = (server?, client?);
+    assert_eq!(server.protocol_version(), &ProtocolVersion::V_2025_03_26);
+    assert_eq!(client.protocol_version(), &ProtocolVersion::V_2025_03_26);
+    client.list_tools(None).await?;
+    client.cancel().await?;
+    server.waiting().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejected_for_missing_capability() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let client = client(ClientCapabilities::default(), ProtocolVersion::V_2025_03_26);
+    let (server, client) = tokio::join!(
+        SamplingServer.serve(server_transport),
+        client.serve(client_transport)
+    );
+
+    let Err(ServerInitializeError::InitializeFailed(error)) = server else {
+        panic!("the server should reject the client");
+    };
+    assert_eq!(error.message, "sampling is required");
+    let Err(ClientInitializeError::InitializeRejected(error)) = client else {
+        panic!("the client should be told why it was rejected");
+    };
+    assert_eq!(error.code, ErrorCode::INVALID_REQUEST);
+    assert_eq!(
+        error.data,
+        Some(serde_json::json!({ "capability": "sampling" }))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejected_for_old_protocol_version() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let client = client(
+        ClientCapabilities::builder().enable_sampling().build(),
+        ProtocolVersion::V_2024_11_05,
+    );
+    let (server, client) = tokio::join!(
+        SamplingServer.serve(server_transport),
+        client.serve(client_transport)
+    );
+    assert!(server.is_err());
+    let Err(ClientInitializeError::InitializeRejected(error)) = client else {
+        panic!("the client should be told why it was rejected");
+    };
+    assert_eq!(error.message, "protocol version is too old");
+    Ok(())
+}
@@ -0,0 +1,116 @@
+//cargo test --test test_keepalive --features "client server"
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rmcp::{
+    RoleServer, ServerHandler, ServiceExt,
+    model::{ClientRequest, RequestId, ServerInfo, ServerJsonRpcMessage, ServerResult},
+    service::{KeepaliveConfig, QuitReason},
+    transport::{Transport, async_rw::AsyncRwTransport},
+};
+use tokio::io::DuplexStream;
+
+#[derive(Debug, Clone, Default)]
+struct Server;
+
+impl ServerHandler for Server {}
+
+/// A server which completes the handshake and then never answers a request
+fn spawn_unresponsive_server(stream: DuplexStream) -> Arc<Mutex<Vec<RequestId>>> {
+    let ignored = Arc::new(Mutex::new(Vec::new()));
+    let recorded = ignored.clone();
+    tokio::spawn(async move {
+        let (read, write) = tokio::io::split(stream);
+        let mut transport = AsyncRwTransport::<RoleServer, _, _>::new_server(read, write);
+        while let Some(message) = transport.receive().await {
+            let Some((request, id)) = message.into_request() else {
+                continue;
+            };
+            match request {
+                ClientRequest::InitializeRequest(_) => {
+                    let response = ServerJsonRpcMessage::response(
+                        ServerResult::InitializeResult(ServerInfo::default()),
+                        id,
+                    );
+                    transport.send(response).await?;
+                }
+                _ => recorded.lock().unwrap().push(id),
+            }
+        }
+        anyhow::Ok(())
+    });
+    ignored
+}
+
+#[tokio::test]
+async fn test_keepalive_quits_when_peer_is_unresponsive() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let ignored = spawn_unresponsive_server(server_transport);
+
+    let client = ().serve(client_transport).await?;
+    client.enable_keepalive(Duration::from_millis(20), Duration::from_millis(20));
+
+    let quit_reason = tokio::time::timeout(Duration::from_secs(5), client.waiting()).await??;
+    assert!(matches!(quit_reason, QuitReason::Unresponsive));
+
+    let ignored = ignored.lock().unwrap().clone();
+    assert_eq!(
+        ignored.len(),
+        KeepaliveConfig::DEFAULT_MAX_FAILURES as usize
+    );
+    // pings use their own ids and leave the numbers to the normal requests
+    for id in ignored {
+        assert!(id.to_string().starts_with("keepalive-"), "{id}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_keepalive_calls_back_instead_of_cancelling() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    spawn_unresponsive_server(server_transport);
+
+    let client = ().serve(client_transport).await?;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let tx = Mutex::new(Some(tx));
+    client.enable_keepalive_with(
+        KeepaliveConfig::new(Duration::from_millis(20), Duration::from_millis(20))
+            .with_max_failures(2)
+            .on_unresponsive(move |failures| {
+                if let Some(tx) = tx.lock().unwrap().take() {
+                    let _ = tx.send(failures);
+                }
+            }),
+    );
+
+    let failures = tokio::time::timeout(Duration::from_secs(5), rx).await??;
+    assert_eq!(failures, 2);
+    assert!(!client.is_transport_closed());
+
+    let quit_reason = client.cancel().await?;
+    assert!(matches!(quit_reason, QuitReason::Cancelled));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_keepalive_keeps_responsive_peer() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        let server = Server.serve(server_transport).await?;
+        let keepalive =
+            server.enable_keepalive(Duration::from_millis(20), Duration::from_millis(100));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(!server.is_transport_closed());
+        keepalive.stop();
+        anyhow::Ok(server.cancel().await?)
+    });
+
+    let client = ().serve(client_transport).await?;
+    let quit_reason = server_handle.await??;
+    assert!(matches!(quit_reason, QuitReason::Cancelled));
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,104 @@
+//cargo test --test test_list_changed_notifier --features "client server"
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    ClientHandler, Peer, RoleClient, ServerHandler, ServiceExt,
+    handler::server::list_changed::ListChangedNotifier,
+    model::{ServerCapabilities, ServerInfo},
+};
+
+const INTERVAL: Duration = Duration::from_millis(50);
+
+/// Advertises changing tools and prompts, but not resources
+#[derive(Debug, Clone, Default)]
+pub struct ChangingServer;
+
+impl ServerHandler for ChangingServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_prompts()
+                .enable_prompts_list_changed()
+                .enable_resources()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .build(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct CountingClient {
+    tools: Arc<AtomicUsize>,
+    prompts: Arc<AtomicUsize>,
+    resources: Arc<AtomicUsize>,
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl ClientHandler for CountingClient {
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    async fn on_tool_list_changed(&self) {
+        self.tools.fetch_add(1, Ordering::SeqCst);
+    }
+
+    async fn on_prompt_list_changed(&self) {
+        self.prompts.fetch_add(1, Ordering::SeqCst);
+    }
+
+    async fn on_resource_list_changed(&self) {
+        self.resources.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn test_list_changes_are_coalesced() -> anyhow::Result<()> {
+    let notifier = ListChangedNotifier::with_interval(INTERVAL);
+    // nothing to notify before a client connects
+    notifier.mark_tools_changed();
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = ChangingServer.with_list_changed_notifier(notifier.clone());
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let handler = CountingClient::default();
+    let counts = handler.clone();
+    let client = handler.serve(client_transport).await?;
+
+    for _ in 0..10 {
+        notifier.mark_tools_changed();
+    }
+    notifier.mark_prompts_changed();
+    notifier.mark_prompts_changed();
+    // not advertised, skipped
+    notifier.mark_resources_changed();
+    tokio::time::sleep(INTERVAL * 4).await;
+    assert_eq!(counts.tools.load(Ordering::SeqCst), 1);
+    assert_eq!(counts.prompts.load(Ordering::SeqCst), 1);
+    assert_eq!(counts.resources.load(Ordering::SeqCst), 0);
+
+    // a change after the window is sent again
+    notifier.mark_tools_changed();
+    tokio::time::sleep(INTERVAL * 4).await;
+    assert_eq!(counts.tools.load(Ordering::SeqCst), 2);
+    assert_eq!(counts.prompts.load(Ordering::SeqCst), 1);
+
+    client.cancel().await?;
+    Ok(())
+}
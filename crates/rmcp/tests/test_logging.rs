@@ -1,15 +1,23 @@
-// cargo test --features "server client" --package rmcp test_logging
+// cargo test --features "server client macros" --package rmcp test_logging
 mod common;
 
-use std::sync::{Arc, Mutex};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use common::handlers::{TestClientHandler, TestServer};
 use rmcp::{
-    ServiceExt,
-    model::{LoggingLevel, LoggingMessageNotificationParam, SetLevelRequestParam},
+    ClientHandler, RoleClient, ServerHandler, ServiceExt,
+    handler::server::{logging::LoggingSupport, tool::Extension},
+    model::{
+        CallToolRequestParam, LoggingLevel, LoggingMessageNotificationParam, SetLevelRequestParam,
+    },
+    service::Peer,
+    tool,
 };
 use serde_json::json;
-use tokio::sync::Notify;
+use tokio::sync::{Notify, mpsc};
 
 #[tokio::test]
 async fn test_logging_spec_compliance() -> anyhow::Result<()> {
@@ -349,3 +357,129 @@ async fn test_logging_optional_fields() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[derive(Debug, Clone, Default)]
+pub struct LogServer;
+
+#[tool(tool_box)]
+impl LogServer {
+    #[tool(description = "Log a message at several levels")]
+    async fn work(&self, Extension(logging): Extension<LoggingSupport>) -> String {
+        for level in [
+            LoggingLevel::Debug,
+            LoggingLevel::Info,
+            LoggingLevel::Warning,
+            LoggingLevel::Error,
+        ] {
+            logging
+                .log(level, "work", serde_json::json!({ "level": level }))
+                .await
+                .unwrap();
+        }
+        "done".to_string()
+    }
+}
+
+#[derive(Clone)]
+struct LogClient {
+    messages: mpsc::UnboundedSender<LoggingMessageNotificationParam>,
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl ClientHandler for LogClient {
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    async fn on_logging_message(&self, params: LoggingMessageNotificationParam) {
+        let _ = self.messages.send(params);
+    }
+}
+
+/// Receive `count` messages and return their levels, notifications may be handled out of order
+async fn receive_levels(
+    messages: &mut mpsc::UnboundedReceiver<LoggingMessageNotificationParam>,
+    count: usize,
+) -> anyhow::Result<Vec<LoggingLevel>> {
+    let mut levels = Vec::new();
+    for _ in 0..count {
+        let message = tokio::time::timeout(Duration::from_secs(5), messages.recv())
+            .await?
+            .expect("client dropped");
+        assert_eq!(message.logger.as_deref(), Some("work"));
+        levels.push(message.level);
+    }
+    levels.sort();
+    Ok(levels)
+}
+
+async fn assert_no_more_messages(
+    messages: &mut mpsc::UnboundedReceiver<LoggingMessageNotificationParam>,
+) {
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(messages.try_recv().is_err(), "a filtered message was sent");
+}
+
+#[tokio::test]
+async fn test_logging_threshold_and_set_level() -> anyhow::Result<()> {
+    let logging = LoggingSupport::new();
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = LogServer.with_logging(logging.clone());
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let (sender, mut messages) = mpsc::unbounded_channel();
+    let client = LogClient {
+        messages: sender,
+        peer: None,
+    }
+    .serve(client_transport)
+    .await?;
+    assert!(client.peer_info().capabilities.logging.is_some());
+
+    let work = || CallToolRequestParam {
+        name: "work".into(),
+        arguments: None,
+    };
+
+    // info and above before the client sets a level
+    client.call_tool(work()).await?;
+    assert_eq!(
+        receive_levels(&mut messages, 3).await?,
+        vec![
+            LoggingLevel::Info,
+            LoggingLevel::Warning,
+            LoggingLevel::Error
+        ]
+    );
+    assert_no_more_messages(&mut messages).await;
+
+    client
+        .set_level(SetLevelRequestParam {
+            level: LoggingLevel::Error,
+        })
+        .await?;
+    assert_eq!(logging.level(), LoggingLevel::Error);
+    client.call_tool(work()).await?;
+    assert_eq!(
+        receive_levels(&mut messages, 1).await?,
+        vec![LoggingLevel::Error]
+    );
+    assert_no_more_messages(&mut messages).await;
+
+    client
+        .set_level(SetLevelRequestParam {
+            level: LoggingLevel::Debug,
+        })
+        .await?;
+    client.call_tool(work()).await?;
+    assert_eq!(receive_levels(&mut messages, 4).await?.len(), 4);
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//cargo test --test test_logging_tracing_layer --features "client server macros tracing-layer"
+
+use std::time::Duration;
+
+use rmcp::{
+    ClientHandler, RoleClient, ServerHandler, ServiceExt,
+    handler::server::logging::{LoggingLayer, LoggingSupport},
+    model::{
+        CallToolRequestParam, LoggingLevel, LoggingMessageNotificationParam, SetLevelRequestParam,
+    },
+    service::Peer,
+    tool,
+};
+use tokio::sync::mpsc;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[derive(Debug, Clone, Default)]
+pub struct FileServer;
+
+#[tool(tool_box)]
+impl FileServer {
+    #[tool(description = "Read a file")]
+    async fn read(&self) -> String {
+        tracing::debug!(target: "file_server", "opening file");
+        tracing::info!(target: "file_server", path = "notes.txt", size = 12, "file read");
+        "hello world!".to_string()
+    }
+}
+
+#[derive(Clone)]
+struct LogClient {
+    messages: mpsc::UnboundedSender<LoggingMessageNotificationParam>,
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl ClientHandler for LogClient {
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    async fn on_logging_message(&self, params: LoggingMessageNotificationParam) {
+        let _ = self.messages.send(params);
+    }
+}
+
+async fn receive(
+    messages: &mut mpsc::UnboundedReceiver<LoggingMessageNotificationParam>,
+) -> anyhow::Result<LoggingMessageNotificationParam> {
+    Ok(
+        tokio::time::timeout(Duration::from_secs(5), messages.recv())
+            .await?
+            .expect("client dropped"),
+    )
+}
+
+#[tokio::test]
+async fn test_tracing_events_forwarded_to_client() -> anyhow::Result<()> {
+    let logging = LoggingSupport::new();
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::registry().with(LoggingLayer::new(logging.clone())),
+    )?;
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = FileServer.with_logging(logging.clone());
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let (sender, mut messages) = mpsc::unbounded_channel();
+    let client = LogClient {
+        messages: sender,
+        peer: None,
+    }
+    .serve(client_transport)
+    .await?;
+    let read = || CallToolRequestParam {
+        name: "read".into(),
+        arguments: None,
+    };
+
+    // the debug event is below the default level
+    client.call_tool(read()).await?;
+    let message = receive(&mut messages).await?;
+    assert_eq!(message.level, LoggingLevel::Info);
+    assert_eq!(message.logger.as_deref(), Some("file_server"));
+    assert_eq!(
+        message.data,
+        serde_json::json!({ "message": "file read", "path": "notes.txt", "size": 12 })
+    );
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(messages.try_recv().is_err());
+
+    client
+        .set_level(SetLevelRequestParam {
+            level: LoggingLevel::Debug,
+        })
+        .await?;
+    client.call_tool(read()).await?;
+    let mut levels = vec![
+        receive(&mut messages).await?.level,
+        receive(&mut messages).await?.level,
+    ];
+    levels.sort();
+    assert_eq!(levels, vec![LoggingLevel::Debug, LoggingLevel::Info]);
+
+    client.cancel().await?;
+    Ok(())
+}
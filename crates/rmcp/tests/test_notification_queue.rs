@@ -0,0 +1,317 @@
+//cargo test --test test_notification_queue --features "server"
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rmcp::{
+    RoleServer, ServerHandler, ServiceExt,
+    model::{
+        ClientInfo, ClientJsonRpcMessage, JsonRpcMessage, NumberOrString, PingRequest,
+        ProgressNotification, ProgressNotificationParam, ProgressToken, ServerJsonRpcMessage,
+        ServerNotification,
+    },
+    service::{
+        NotificationQueueConfig, Observed, OverflowPolicy, RunningService, ServiceError,
+        ServiceObserver, WithNotificationQueue, serve_directly,
+    },
+    transport::Transport,
+};
+use tokio::sync::{Semaphore, mpsc};
+
+#[derive(Debug, Clone)]
+struct Server;
+
+impl ServerHandler for Server {}
+
+/// Records the messages in the order they are sent, and completes the sends one permit at a time
+struct SlowTransport {
+    sent: Arc<Mutex<Vec<ServerJsonRpcMessage>>>,
+    gate: Arc<Semaphore>,
+    rx: mpsc::UnboundedReceiver<ClientJsonRpcMessage>,
+}
+
+impl Transport<RoleServer> for SlowTransport {
+    type Error = std::io::Error;
+
+    fn send(
+        &mut self,
+        item: ServerJsonRpcMessage,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        self.sent.lock().unwrap().push(item);
+        let gate = self.gate.clone();
+        async move {
+            gate.acquire().await.expect("gate closed").forget();
+            Ok(())
+        }
+    }
+
+    fn receive(&mut self) -> impl Future<Output = Option<ClientJsonRpcMessage>> + Send {
+        self.rx.recv()
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Fails every send
+struct BrokenTransport {
+    rx: mpsc::UnboundedReceiver<ClientJsonRpcMessage>,
+}
+
+impl Transport<RoleServer> for BrokenTransport {
+    type Error = std::io::Error;
+
+    fn send(
+        &mut self,
+        _item: ServerJsonRpcMessage,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        std::future::ready(Err(std::io::Error::other("broken pipe")))
+    }
+
+    fn receive(&mut self) -> impl Future<Output = Option<ClientJsonRpcMessage>> + Send {
+        self.rx.recv()
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+fn progress_notification(token: u32, progress: u32) -> ServerNotification {
+    ProgressNotification {
+        method: Default::default(),
+        params: ProgressNotificationParam {
+            progress_token: ProgressToken::from(token),
+            progress: progress.into(),
+            total: None,
+            message: None,
+        },
+        extensions: Default::default(),
+    }
+    .into()
+}
+
+#[derive(Debug, Clone, Default)]
+struct Recorder {
+    max_depth: Arc<Mutex<usize>>,
+    dropped: Arc<Mutex<Vec<String>>>,
+}
+
+impl ServiceObserver for Recorder {
+    fn on_notification_queue_depth(&self, depth: usize) {
+        let mut max_depth = self.max_depth.lock().unwrap();
+        *max_depth = (*max_depth).max(depth);
+    }
+
+    fn on_notification_dropped(&self, method: &str) {
+        self.dropped.lock().unwrap().push(method.to_owned());
+    }
+}
+
+struct Harness {
+    server: RunningService<RoleServer, Observed<WithNotificationQueue<Server>>>,
+    sent: Arc<Mutex<Vec<ServerJsonRpcMessage>>>,
+    gate: Arc<Semaphore>,
+    client_tx: mpsc::UnboundedSender<ClientJsonRpcMessage>,
+    recorder: Recorder,
+}
+
+impl Harness {
+    async fn new(config: NotificationQueueConfig) -> Self {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let gate = Arc::new(Semaphore::new(0));
+        let (client_tx, rx) = mpsc::unbounded_channel();
+        let transport = SlowTransport {
+            sent: sent.clone(),
+            gate: gate.clone(),
+            rx,
+        };
+        let recorder = Recorder::default();
+        let service = Server
+            .with_notification_queue(config)
+            .with_observer(recorder.clone());
+        let server = serve_directly(service, transport, ClientInfo::default()).await;
+        Self {
+            server,
+            sent,
+            gate,
+            client_tx,
+            recorder,
+        }
+    }
+
+    async fn progress(&self, token: u32, progress: u32) -> anyhow::Result<()> {
+        self.server
+            .queue_notification(progress_notification(token, progress))
+            .await?;
+        Ok(())
+    }
+
+    /// Wait until `count` messages were handed to the transport
+    async fn wait_sent(&self, count: usize) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while self.sent.lock().unwrap().len() < count {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the messages should be sent");
+    }
+
+    /// The token and progress of the notifications sent, the responses as `(u32::MAX, id)`
+    fn sent(&self) -> Vec<(u32, u32)> {
+        let sent = self.sent.lock().unwrap();
+        sent.iter()
+            .map(|message| match message {
+                JsonRpcMessage::Notification(notification) => match &notification.notification {
+                    ServerNotification::ProgressNotification(progress) => {
+                        match &progress.params.progress_token.0 {
+                            NumberOrString::Number(token) => {
+                                (*token as u32, progress.params.progress as u32)
+                            }
+                            token => panic!("unexpected token {token:?}"),
+                        }
+                    }
+                    notification => panic!("unexpected notification {notification:?}"),
+                },
+                JsonRpcMessage::Response(response) => match &response.id {
+                    NumberOrString::Number(id) => (u32::MAX, *id as u32),
+                    id => panic!("unexpected id {id:?}"),
+                },
+                message => panic!("unexpected message {message:?}"),
+            })
+            .collect()
+    }
+
+    /// Send one notification which holds the transport, and the others while it is stuck
+    async fn overflow(&self, notifications: &[(u32, u32)]) -> anyhow::Result<()> {
+        self.progress(0, 0).await?;
+        self.wait_sent(1).await;
+        for (token, progress) in notifications {
+            self.progress(*token, *progress).await?;
+        }
+        Ok(())
+    }
+
+    async fn finish(self, expected: usize) -> anyhow::Result<Vec<(u32, u32)>> {
+        self.gate.add_permits(1024);
+        self.wait_sent(expected).await;
+        // nothing else comes
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let sent = self.sent();
+        self.server.cancel().await?;
+        Ok(sent)
+    }
+}
+
+#[tokio::test]
+async fn test_block() -> anyhow::Result<()> {
+    let harness = Harness::new(NotificationQueueConfig::new(2)).await;
+    harness.overflow(&[(0, 1), (0, 2)]).await?;
+    // the queue is full, the next notification waits for room
+    let blocked = harness.progress(0, 3);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), blocked)
+            .await
+            .is_err()
+    );
+    harness.gate.add_permits(1);
+    tokio::time::timeout(Duration::from_secs(1), harness.progress(0, 3)).await??;
+
+    assert_eq!(*harness.recorder.max_depth.lock().unwrap(), 2);
+    assert!(harness.recorder.dropped.lock().unwrap().is_empty());
+    let sent = harness.finish(4).await?;
+    assert_eq!(sent, [(0, 0), (0, 1), (0, 2), (0, 3)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_drop_oldest() -> anyhow::Result<()> {
+    let config = NotificationQueueConfig::new(2).with_overflow(OverflowPolicy::DropOldest);
+    let harness = Harness::new(config).await;
+    harness.overflow(&[(0, 1), (0, 2), (0, 3), (0, 4)]).await?;
+
+    assert_eq!(
+        *harness.recorder.dropped.lock().unwrap(),
+        ["notifications/progress", "notifications/progress"]
+    );
+    let sent = harness.finish(3).await?;
+    assert_eq!(sent, [(0, 0), (0, 3), (0, 4)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_drop_newest() -> anyhow::Result<()> {
+    let config = NotificationQueueConfig::new(2).with_overflow(OverflowPolicy::DropNewest);
+    let harness = Harness::new(config).await;
+    harness.overflow(&[(0, 1), (0, 2), (0, 3), (0, 4)]).await?;
+
+    assert_eq!(harness.recorder.dropped.lock().unwrap().len(), 2);
+    let sent = harness.finish(3).await?;
+    assert_eq!(sent, [(0, 0), (0, 1), (0, 2)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_coalesce_progress() -> anyhow::Result<()> {
+    let config = NotificationQueueConfig::new(2).with_overflow(OverflowPolicy::CoalesceProgress);
+    let harness = Harness::new(config).await;
+    // consecutive progress of the same token is merged, of another token is queued
+    harness
+        .overflow(&[(1, 1), (1, 2), (1, 3), (2, 1), (2, 2)])
+        .await?;
+    // the queue is full, progress of the first token isn't the last one anymore
+    let blocked = harness.progress(1, 4);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), blocked)
+            .await
+            .is_err()
+    );
+
+    assert_eq!(harness.recorder.dropped.lock().unwrap().len(), 3);
+    let sent = harness.finish(3).await?;
+    assert_eq!(sent, [(0, 0), (1, 3), (2, 2)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_responses_are_never_dropped() -> anyhow::Result<()> {
+    let config = NotificationQueueConfig::new(1).with_overflow(OverflowPolicy::DropNewest);
+    let harness = Harness::new(config).await;
+    harness.overflow(&[(0, 1), (0, 2)]).await?;
+    let ping = PingRequest {
+        method: Default::default(),
+        extensions: Default::default(),
+    };
+    harness.client_tx.send(JsonRpcMessage::request(
+        ping.into(),
+        NumberOrString::Number(7),
+    ))?;
+    // the response doesn't wait for the transport, the queued notification goes right before it
+    harness.wait_sent(3).await;
+    assert_eq!(harness.sent(), [(0, 0), (0, 1), (u32::MAX, 7)]);
+
+    let sent = harness.finish(3).await?;
+    assert_eq!(sent, [(0, 0), (0, 1), (u32::MAX, 7)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_notification_reports_write_errors() -> anyhow::Result<()> {
+    let (_client_tx, rx) = mpsc::unbounded_channel();
+    let server = serve_directly(Server, BrokenTransport { rx }, ClientInfo::default()).await;
+    // sent right away, the error writing it is returned
+    let error = server
+        .send_notification(progress_notification(0, 1))
+        .await
+        .expect_err("the write should fail");
+    assert!(matches!(error, ServiceError::TransportSend(_)));
+    // queued, the error is only logged
+    server
+        .queue_notification(progress_notification(0, 2))
+        .await?;
+    server.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//cargo test --test test_pagination --features "client server"
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use futures::StreamExt;
+use rmcp::{
+    Error as McpError, RoleClient, RoleServer, ServerHandler, ServiceExt,
+    model::{JsonObject, ListPromptsResult, ListToolsResult, PaginatedRequestParam, Prompt, Tool},
+    service::{RequestContext, RunningService, ServiceError},
+};
+
+/// Serves three pages of tools, and prompts whose cursor never changes
+#[derive(Debug, Clone, Default)]
+pub struct PagedServer {
+    prompt_pages: Arc<AtomicUsize>,
+}
+
+fn tool(name: &str) -> Tool {
+    Tool::new(name.to_owned(), "a paged tool", Arc::new(JsonObject::new()))
+}
+
+impl ServerHandler for PagedServer {
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        let cursor = request.and_then(|request| request.cursor);
+        let (tools, next_cursor) = match cursor.as_deref() {
+            None => (vec![tool("a"), tool("b")], Some("page-2")),
+            Some("page-2") => (vec![tool("c")], Some("page-3")),
+            Some("page-3") => (vec![tool("d"), tool("e")], None),
+            Some(cursor) => {
+                return Err(McpError::invalid_params(
+                    format!("unknown cursor {cursor}"),
+                    None,
+                ));
+            }
+        };
+        Ok(ListToolsResult {
+            tools,
+            next_cursor: next_cursor.map(Into::into),
+            meta: None,
+        })
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        self.prompt_pages.fetch_add(1, Ordering::SeqCst);
+        Ok(ListPromptsResult {
+            prompts: vec![Prompt::new("loop")],
+            next_cursor: Some("again".into()),
+            meta: None,
+        })
+    }
+}
+
+async fn connect(server: PagedServer) -> anyhow::Result<RunningService<RoleClient, ()>> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    Ok(().serve(client_transport).await?)
+}
+
+fn names(tools: &[Tool]) -> Vec<&str> {
+    tools.iter().map(|tool| tool.name.as_ref()).collect()
+}
+
+#[tokio::test]
+async fn test_list_all_follows_cursors() -> anyhow::Result<()> {
+    let client = connect(PagedServer::default()).await?;
+
+    let tools = client.list_all_tools().await?;
+    assert_eq!(names(&tools), ["a", "b", "c", "d", "e"]);
+
+    let tools = client
+        .list_tools_stream(3)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(names(&tools), ["a", "b", "c", "d", "e"]);
+
+    // the items of the first pages come before the page cap is reached
+    let mut stream = Box::pin(client.list_tools_stream(2));
+    for name in ["a", "b", "c"] {
+        assert_eq!(stream.next().await.unwrap()?.name, name);
+    }
+    let error = stream.next().await.unwrap().unwrap_err();
+    assert!(
+        matches!(
+            error,
+            ServiceError::TooManyPages {
+                method: "tools/list",
+                pages: 2
+            }
+        ),
+        "{error}"
+    );
+    assert!(stream.next().await.is_none());
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_list_all_detects_cursor_loops() -> anyhow::Result<()> {
+    let server = PagedServer::default();
+    let prompt_pages = server.prompt_pages.clone();
+    let client = connect(server).await?;
+
+    let error = client.list_all_prompts().await.unwrap_err();
+    let ServiceError::RepeatedCursor { method, cursor } = error else {
+        panic!("expected a repeated cursor, got {error}");
+    };
+    assert_eq!((method, cursor.as_str()), ("prompts/list", "again"));
+    assert_eq!(prompt_pages.load(Ordering::SeqCst), 2);
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+//cargo test --test test_peer_timeout --features "client server macros"
+
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    ServiceExt,
+    model::CallToolRequestParam,
+    service::{MessageDirection, ServiceError, ServiceObserver},
+    tool,
+};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Clone, Default)]
+pub struct SlowServer {
+    cancelled: Arc<AtomicBool>,
+}
+
+#[tool(tool_box)]
+impl SlowServer {
+    #[tool(description = "Take a long time, unless cancelled")]
+    async fn slow(&self, ct: CancellationToken) -> String {
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(10)) => "done".into(),
+            _ = ct.cancelled() => {
+                self.cancelled.store(true, Ordering::SeqCst);
+                "cancelled".into()
+            }
+        }
+    }
+
+    #[tool(description = "Answer after a short while")]
+    async fn quick(&self) -> String {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        "done".into()
+    }
+}
+
+/// The notifications received by the server
+#[derive(Debug, Clone, Default)]
+struct Notifications(Arc<Mutex<Vec<String>>>);
+
+impl ServiceObserver for Notifications {
+    fn on_notification(&self, method: &str, direction: MessageDirection) {
+        if direction == MessageDirection::Inbound {
+            self.0.lock().unwrap().push(method.to_owned());
+        }
+    }
+}
+
+fn call(name: &'static str) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: None,
+    }
+}
+
+#[tokio::test]
+async fn test_timeout_cancels_the_request() -> anyhow::Result<()> {
+    let server = SlowServer::default();
+    let cancelled = server.cancelled.clone();
+    let notifications = Notifications::default();
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let (server, client) = tokio::join!(
+        server
+            .with_observer(notifications.clone())
+            .serve(server_transport),
+        ().serve(client_transport)
+    );
+    let (server, client) = (server?, client?);
+
+    let fast = client.with_timeout(Duration::from_millis(100));
+    assert_eq!(fast.timeout(), Some(Duration::from_millis(100)));
+    assert_eq!(client.timeout(), None);
+
+    let result = fast.call_tool(call("slow")).await;
+    let Err(ServiceError::Timeout { timeout }) = result else {
+        panic!("expected a timeout, got {result:?}");
+    };
+    assert_eq!(timeout, Duration::from_millis(100));
+
+    // the server is told to stop working on the request
+    tokio::time::timeout(Duration::from_secs(1), async {
+        while !cancelled.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+    assert!(
+        notifications
+            .0
+            .lock()
+            .unwrap()
+            .contains(&"notifications/cancelled".to_owned())
+    );
+
+    // requests in time and through the original peer are unaffected
+    fast.call_tool(call("quick")).await?;
+    let result = client
+        .with_timeout(Duration::from_secs(5))
+        .without_timeout()
+        .call_tool(call("quick"))
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "done");
+
+    client.cancel().await?;
+    server.cancel().await?;
+    Ok(())
+}
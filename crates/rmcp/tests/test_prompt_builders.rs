@@ -0,0 +1,71 @@
+use rmcp::model::{
+    EmbeddedResource, GetPromptResult, ListPromptsResult, Prompt, PromptMessage, PromptMessageRole,
+    RawImageContent,
+};
+use serde_json::Value;
+
+/// A result as the TypeScript SDK writes it
+fn fixture(name: &str) -> Value {
+    let json = std::fs::read(format!("tests/test_prompt_builders/{name}")).unwrap();
+    serde_json::from_slice(&json).unwrap()
+}
+
+#[test]
+fn test_get_prompt_result_builders() {
+    let result = GetPromptResult::builder()
+        .description("A review of main.rs")
+        .message(PromptMessage::user("Review this file"))
+        .message(PromptMessage::user(EmbeddedResource::text(
+            "file:///src/main.rs",
+            "text/x-rust",
+            "fn main() {}",
+        )))
+        .message(PromptMessage::user(EmbeddedResource::blob(
+            "file:///docs/spec.pdf",
+            "application/pdf",
+            b"%PDF-1.7",
+        )))
+        .message(PromptMessage::user(RawImageContent {
+            data: "iVBORw0KGgo=".into(),
+            mime_type: "image/png".into(),
+        }))
+        .message(PromptMessage::assistant("Looks good to me".to_owned()))
+        .build();
+    let fixture = fixture("get_prompt_result.json");
+    assert_eq!(serde_json::to_value(&result).unwrap(), fixture);
+    assert_eq!(
+        serde_json::from_value::<GetPromptResult>(fixture).unwrap(),
+        result
+    );
+
+    // the same image, encoded by the constructor
+    assert_eq!(
+        PromptMessage::new_image(
+            PromptMessageRole::User,
+            b"\x89PNG\r\n\x1a\n",
+            "image/png",
+            None
+        ),
+        result.messages[3]
+    );
+}
+
+#[test]
+fn test_list_prompts_result_builders() {
+    let result = ListPromptsResult {
+        prompts: vec![
+            Prompt::new("review")
+                .with_description("Review a file")
+                .arg("path", "file to read", true)
+                .arg("focus", "what to look at first", false),
+            Prompt::new("greet"),
+        ],
+        ..Default::default()
+    };
+    let fixture = fixture("list_prompts_result.json");
+    assert_eq!(serde_json::to_value(&result).unwrap(), fixture);
+    assert_eq!(
+        serde_json::from_value::<ListPromptsResult>(fixture).unwrap(),
+        result
+    );
+}
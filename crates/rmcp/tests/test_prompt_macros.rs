@@ -0,0 +1,177 @@
+//cargo test --test test_prompt_macros --features "client server"
+
+use rmcp::{
+    ClientHandler, Peer, RoleClient, ServiceExt,
+    model::{
+        CallToolRequestParam, ClientInfo, ErrorCode, GetPromptRequestParam, PromptMessage,
+        PromptMessageContent, PromptMessageRole,
+    },
+    prompt,
+    service::ServiceError,
+    tool,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct MixedServer;
+
+#[tool(tool_box)]
+impl MixedServer {
+    #[tool(description = "Echo the input")]
+    async fn echo(&self, #[tool(param)] text: String) -> String {
+        text
+    }
+
+    /// Greet someone by name
+    #[prompt]
+    async fn greet(
+        &self,
+        /// the name to greet
+        name: String,
+        #[prompt(description = "an optional greeting word")] greeting: Option<String>,
+    ) -> String {
+        format!("{}, {name}!", greeting.as_deref().unwrap_or("Hello"))
+    }
+
+    #[prompt(name = "code-review", description = "Review a code snippet")]
+    fn code_review(&self, code: String) -> Vec<PromptMessage> {
+        vec![
+            PromptMessage::new_text(PromptMessageRole::Assistant, "I'm a code reviewer."),
+            PromptMessage::new_text(PromptMessageRole::User, code),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct DummyClientHandler {
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl ClientHandler for DummyClientHandler {
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo::default()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+}
+
+#[test]
+fn test_prompt_attr() {
+    let attr = MixedServer::greet_prompt_attr();
+    assert_eq!(attr.name, "greet");
+    assert_eq!(attr.description.as_deref(), Some("Greet someone by name"));
+    let arguments = attr.arguments.expect("arguments should be derived");
+    assert_eq!(arguments.len(), 2);
+    assert_eq!(arguments[0].name, "name");
+    assert_eq!(
+        arguments[0].description.as_deref(),
+        Some("the name to greet")
+    );
+    assert_eq!(arguments[0].required, Some(true));
+    assert_eq!(arguments[1].name, "greeting");
+    assert_eq!(arguments[1].required, Some(false));
+
+    let attr = MixedServer::code_review_prompt_attr();
+    assert_eq!(attr.name, "code-review");
+    assert_eq!(attr.description.as_deref(), Some("Review a code snippet"));
+}
+
+#[tokio::test]
+async fn test_mixed_tools_and_prompts() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+
+    let server_handle = tokio::spawn(async move {
+        MixedServer.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let capabilities = &client.peer_info().capabilities;
+    assert!(capabilities.tools.is_some());
+    assert!(capabilities.prompts.is_some());
+
+    let tools = client.list_all_tools().await?;
+    assert_eq!(tools.len(), 1);
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "echo".into(),
+            arguments: Some(
+                serde_json::json!({ "text": "hi" })
+                    .as_object()
+                    .unwrap()
+                    .clone()
+                    .into(),
+            ),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "hi");
+
+    let mut prompts = client.list_all_prompts().await?;
+    prompts.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(
+        prompts.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(),
+        ["code-review", "greet"]
+    );
+
+    let result = client
+        .get_prompt(GetPromptRequestParam {
+            name: "greet".into(),
+            arguments: Some(
+                serde_json::json!({ "name": "Ferris" })
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+            ),
+        })
+        .await?;
+    assert_eq!(result.messages.len(), 1);
+    assert!(matches!(
+        &result.messages[0].content,
+        PromptMessageContent::Text { text, .. } if text == "Hello, Ferris!"
+    ));
+
+    let result = client
+        .get_prompt(GetPromptRequestParam {
+            name: "code-review".into(),
+            arguments: Some(
+                serde_json::json!({ "code": "fn main() {}" })
+                    .as_object()
+                    .unwrap()
+                    .clone(),
+            ),
+        })
+        .await?;
+    assert_eq!(result.messages.len(), 2);
+
+    let missing = client
+        .get_prompt(GetPromptRequestParam {
+            name: "missing".into(),
+            arguments: None,
+        })
+        .await;
+    assert!(missing.is_err());
+
+    let result = client
+        .get_prompt(GetPromptRequestParam {
+            name: "greet".into(),
+            arguments: None,
+        })
+        .await;
+    let Err(ServiceError::McpError(error)) = result else {
+        panic!("expected an invalid params error, got {result:?}");
+    };
+    assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+    assert_eq!(error.data, Some(serde_json::json!({ "argument": "name" })));
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
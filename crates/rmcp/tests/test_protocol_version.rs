@@ -0,0 +1,138 @@
+//cargo test --test test_protocol_version --features "client server"
+use std::borrow::Cow;
+
+use rmcp::{
+    RoleServer, ServerHandler, ServiceExt,
+    model::{
+        CallToolRequestParam, CallToolResult, ClientInfo, Content, ErrorCode, ProtocolVersion,
+        RawContent, ServerCapabilities, ServerInfo,
+    },
+    service::{ClientInitializeError, RequestContext, ServerInitializeError},
+};
+use serde_json::json;
+
+/// Answers every tool call with structured content and an audio content
+#[derive(Debug, Clone)]
+struct Server;
+
+impl ServerHandler for Server {
+    async fn call_tool(
+        &self,
+        _request: CallToolRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let mut result = CallToolResult::structured(json!({ "answer": 42 }));
+        result.content.push(Content::audio(*b"RIFF", "audio/wav"));
+        Ok(result)
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            ..Default::default()
+        }
+    }
+}
+
+fn client(protocol_version: ProtocolVersion) -> ClientInfo {
+    ClientInfo {
+        protocol_version,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_each_supported_version() -> anyhow::Result<()> {
+    for version in ProtocolVersion::SUPPORTED {
+        let (server_transport, client_transport) = tokio::io::duplex(4096);
+        let (server, client) = tokio::join!(
+            Server.serve(server_transport),
+            client(version.clone()).serve(client_transport)
+        );
+        let (server, client) = (server?, client?);
+        assert_eq!(server.protocol_version(), version);
+        assert_eq!(client.protocol_version(), version);
+
+        let result = client
+            .call_tool(CallToolRequestParam {
+                name: "answer".into(),
+                arguments: None,
+            })
+            .await?;
+        assert_eq!(
+            result.structured_content.is_some(),
+            version.supports_structured_content(),
+            "{version}"
+        );
+        let has_audio = result
+            .content
+            .iter()
+            .any(|content| matches!(content.raw, RawContent::Audio(_)));
+        assert_eq!(has_audio, version.supports_audio_content(), "{version}");
+        // the text fallback of the structured content is always there
+        assert!(result.content[0].as_text().is_some());
+
+        client.cancel().await?;
+        server.waiting().await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_client_requests_2025_06_18() -> anyhow::Result<()> {
+    assert_eq!(ProtocolVersion::LATEST.as_str(), "2025-06-18");
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let (server, client) = tokio::join!(
+        Server.serve(server_transport),
+        ClientInfo::default().serve(client_transport)
+    );
+    let (server, client) = (server?, client?);
+    assert_eq!(server.protocol_version().as_str(), "2025-06-18");
+    assert_eq!(client.protocol_version().as_str(), "2025-06-18");
+    client.cancel().await?;
+    server.waiting().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_later_version_gets_the_latest() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let requested = ProtocolVersion::Other(Cow::Borrowed("2099-01-01"));
+    let (server, client) = tokio::join!(
+        Server.serve(server_transport),
+        client(requested).serve(client_transport)
+    );
+    let (server, client) = (server?, client?);
+    assert_eq!(server.protocol_version(), &ProtocolVersion::LATEST);
+    assert_eq!(client.protocol_version(), &ProtocolVersion::LATEST);
+    client.cancel().await?;
+    server.waiting().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unsupported_version_is_rejected() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let requested = ProtocolVersion::Other(Cow::Borrowed("1.0.0"));
+    let (server, client) = tokio::join!(
+        Server.serve(server_transport),
+        client(requested.clone()).serve(client_transport)
+    );
+    let Err(ServerInitializeError::UnsupportedProtocolVersion(version)) = server else {
+        panic!("the server should reject the version");
+    };
+    assert_eq!(version, requested);
+    let Err(ClientInitializeError::InitializeRejected(error)) = client else {
+        panic!("the client should be told why it was rejected");
+    };
+    assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+    assert_eq!(error.message, "Unsupported protocol version");
+    assert_eq!(
+        error.data,
+        Some(json!({
+            "supported": ["2024-11-05", "2025-03-26", "2025-06-18"],
+            "requested": "1.0.0",
+        }))
+    );
+    Ok(())
+}
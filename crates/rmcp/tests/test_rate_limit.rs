@@ -0,0 +1,139 @@
+//cargo test --test test_rate_limit --features "client server macros"
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    RoleClient, ServerHandler, ServiceExt,
+    handler::server::rate_limit::RateLimits,
+    model::{CallToolRequestParam, ErrorCode},
+    service::{RunningService, ServiceError},
+    tool,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct EchoServer {
+    roots_changed: Arc<AtomicUsize>,
+}
+
+#[tool(tool_box, default_build = false)]
+impl EchoServer {
+    #[tool(description = "Echo the input")]
+    async fn echo(&self, #[tool(param)] text: String) -> String {
+        text
+    }
+}
+
+#[tool(tool_box)]
+impl ServerHandler for EchoServer {
+    async fn on_roots_list_changed(&self) {
+        self.roots_changed.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+async fn connect(
+    server: EchoServer,
+    limits: &RateLimits,
+) -> anyhow::Result<RunningService<RoleClient, ()>> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = server.with_rate_limits(limits.clone());
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    Ok(().serve(client_transport).await?)
+}
+
+async fn echo(client: &RunningService<RoleClient, ()>) -> Result<(), ServiceError> {
+    client
+        .call_tool(CallToolRequestParam {
+            name: "echo".into(),
+            arguments: Some(rmcp::object!({ "text": "hi" }).into()),
+        })
+        .await
+        .map(drop)
+}
+
+/// The milliseconds to wait before retrying a rejected request
+fn retry_after_ms(result: Result<(), ServiceError>) -> u64 {
+    let Err(ServiceError::McpError(error)) = result else {
+        panic!("expected the request to be rate limited, got {result:?}");
+    };
+    assert_eq!(error.code, ErrorCode::RATE_LIMITED);
+    error.data.as_ref().unwrap()["retryAfterMs"]
+        .as_u64()
+        .expect("retryAfterMs should be a number")
+}
+
+#[tokio::test]
+async fn test_rate_limit_rejects_then_refills() -> anyhow::Result<()> {
+    let limits = RateLimits::new().tools_call(2, Duration::from_millis(200));
+    let client = connect(EchoServer::default(), &limits).await?;
+
+    echo(&client).await?;
+    echo(&client).await?;
+    let retry_after = retry_after_ms(echo(&client).await);
+    assert!(retry_after > 0 && retry_after <= 100, "{retry_after}");
+
+    // other methods have no limit
+    for _ in 0..5 {
+        client.list_tools(None).await?;
+    }
+
+    tokio::time::sleep(Duration::from_millis(retry_after)).await;
+    echo(&client).await?;
+    assert!(echo(&client).await.is_err());
+
+    // a full period refills the whole bucket
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    echo(&client).await?;
+    echo(&client).await?;
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rate_limit_is_per_session() -> anyhow::Result<()> {
+    let limits = RateLimits::new().tools_call(1, Duration::from_secs(60));
+    let first = connect(EchoServer::default(), &limits).await?;
+    let second = connect(EchoServer::default(), &limits).await?;
+
+    echo(&first).await?;
+    assert!(echo(&first).await.is_err());
+    echo(&second).await?;
+    assert!(echo(&second).await.is_err());
+
+    first.cancel().await?;
+    second.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rate_limit_never_drops_notifications() -> anyhow::Result<()> {
+    let server = EchoServer::default();
+    let roots_changed = server.roots_changed.clone();
+    let limits = RateLimits::new().other_requests(1, Duration::from_secs(60));
+    let client = connect(server, &limits).await?;
+
+    // the initialize request is not counted
+    client.list_tools(None).await?;
+    let retry_after = retry_after_ms(client.list_tools(None).await.map(drop));
+    assert!(retry_after > 59_000, "{retry_after}");
+
+    for _ in 0..10 {
+        client.notify_roots_list_changed().await?;
+    }
+    tokio::time::timeout(Duration::from_secs(1), async {
+        while roots_changed.load(Ordering::SeqCst) < 10 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,170 @@
+//cargo test --test test_reconnect --features "client server macros transport-reconnect transport-async-rw"
+use std::{
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    RoleServer, ServiceExt,
+    service::{RetryPolicy, RxJsonRpcMessage, TxJsonRpcMessage},
+    tool,
+    transport::{
+        ConnectionState, ReconnectPolicy, ReconnectingTransport, Transport,
+        async_rw::AsyncRwTransport, reconnect::InFlightPolicy,
+    },
+};
+use tokio::net::{TcpListener, TcpStream};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default)]
+struct Echo;
+
+#[tool(tool_box)]
+impl Echo {
+    #[tool(description = "Echo the text")]
+    async fn echo(&self, #[tool(param)] text: String) -> String {
+        text
+    }
+}
+
+/// Closes the connection on the `limit`th message, without handling it
+struct DropAfter<T> {
+    inner: T,
+    received: usize,
+    limit: usize,
+}
+
+impl<T: Transport<RoleServer>> Transport<RoleServer> for DropAfter<T> {
+    type Error = T::Error;
+
+    fn send(
+        &mut self,
+        item: TxJsonRpcMessage<RoleServer>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        self.inner.send(item)
+    }
+
+    async fn receive(&mut self) -> Option<RxJsonRpcMessage<RoleServer>> {
+        let message = self.inner.receive().await?;
+        self.received += 1;
+        (self.received < self.limit).then_some(message)
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        self.inner.close().await
+    }
+}
+
+/// Serve an echo on every connection, dropped on its `limit`th message
+async fn dropping_server(limit: usize, max_connections: usize) -> (SocketAddr, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let accepted = connections.clone();
+    tokio::spawn(async move {
+        for _ in 0..max_connections {
+            let Ok((stream, _)) = listener.accept().await else {
+                break;
+            };
+            accepted.fetch_add(1, Ordering::SeqCst);
+            let (read, write) = stream.into_split();
+            let transport = DropAfter {
+                inner: AsyncRwTransport::new_server(read, write),
+                received: 0,
+                limit,
+            };
+            tokio::spawn(async move {
+                if let Ok(server) = Echo.serve(transport).await {
+                    let _ = server.waiting().await;
+                }
+            });
+        }
+    });
+    (addr, connections)
+}
+
+fn connect(
+    addr: SocketAddr,
+    policy: ReconnectPolicy,
+) -> (
+    ReconnectingTransport<impl rmcp::transport::reconnect::Connector>,
+    tokio::sync::watch::Receiver<ConnectionState>,
+) {
+    ReconnectingTransport::with_connector(
+        move || async move {
+            let (read, write) = TcpStream::connect(addr).await?.into_split();
+            Ok::<_, std::io::Error>(AsyncRwTransport::new_client(read, write))
+        },
+        policy,
+    )
+}
+
+fn fast_policy() -> ReconnectPolicy {
+    ReconnectPolicy::new().with_retry(
+        RetryPolicy::new().with_backoff(Duration::from_millis(10), Duration::from_millis(50)),
+    )
+}
+
+#[tokio::test]
+async fn test_replay_idempotent_requests() -> anyhow::Result<()> {
+    // initialize, initialized, one answered request, then the next one is lost
+    let (addr, connections) = dropping_server(4, usize::MAX).await;
+    let (transport, state) = connect(
+        addr,
+        fast_policy().with_in_flight(InFlightPolicy::ReplayIdempotent),
+    );
+    let client = ().serve(transport).await?;
+    for _ in 0..5 {
+        let tools = tokio::time::timeout(TIMEOUT, client.list_tools(Default::default())).await??;
+        assert_eq!(tools.tools.len(), 1);
+    }
+    assert!(connections.load(Ordering::SeqCst) >= 4);
+    assert_eq!(*state.borrow(), ConnectionState::Connected);
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fail_in_flight_requests() -> anyhow::Result<()> {
+    let (addr, connections) = dropping_server(4, usize::MAX).await;
+    let (transport, mut state) = connect(addr, fast_policy());
+    let client = ().serve(transport).await?;
+
+    tokio::time::timeout(TIMEOUT, client.list_tools(Default::default())).await??;
+    let lost = tokio::time::timeout(TIMEOUT, client.list_tools(Default::default())).await?;
+    assert!(lost.is_err(), "the request on the lost connection fails");
+
+    // the next request goes to the new connection
+    tokio::time::timeout(TIMEOUT, client.list_tools(Default::default())).await??;
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+    tokio::time::timeout(
+        TIMEOUT,
+        state.wait_for(|state| *state == ConnectionState::Connected),
+    )
+    .await??;
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_give_up() -> anyhow::Result<()> {
+    // the server goes away with the first connection
+    let (addr, _) = dropping_server(3, 1).await;
+    let (transport, mut state) = connect(addr, fast_policy().with_max_retries(2));
+    let client = ().serve(transport).await?;
+
+    let lost = tokio::time::timeout(TIMEOUT, client.list_tools(Default::default())).await?;
+    assert!(lost.is_err());
+    tokio::time::timeout(
+        TIMEOUT,
+        state.wait_for(|state| *state == ConnectionState::GaveUp),
+    )
+    .await??;
+    tokio::time::timeout(TIMEOUT, client.waiting()).await??;
+    Ok(())
+}
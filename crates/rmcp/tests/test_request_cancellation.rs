@@ -0,0 +1,135 @@
+//cargo test --test test_request_cancellation --features "client server macros"
+
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    ServiceExt,
+    model::{CallToolRequest, CallToolRequestMethod, CallToolRequestParam, ClientRequest},
+    service::{MessageDirection, PeerRequestOptions, ServiceError, ServiceObserver},
+    tool,
+};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Clone, Default)]
+pub struct SlowServer {
+    cancelled: Arc<AtomicBool>,
+}
+
+#[tool(tool_box)]
+impl SlowServer {
+    #[tool(description = "Take a long time, unless cancelled")]
+    async fn slow(&self, ct: CancellationToken) -> String {
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(10)) => "done".into(),
+            _ = ct.cancelled() => {
+                self.cancelled.store(true, Ordering::SeqCst);
+                "cancelled".into()
+            }
+        }
+    }
+}
+
+/// The notifications received by the server
+#[derive(Debug, Clone, Default)]
+struct Notifications(Arc<Mutex<Vec<String>>>);
+
+impl ServiceObserver for Notifications {
+    fn on_notification(&self, method: &str, direction: MessageDirection) {
+        if direction == MessageDirection::Inbound {
+            self.0.lock().unwrap().push(method.to_owned());
+        }
+    }
+}
+
+fn slow_call() -> ClientRequest {
+    ClientRequest::CallToolRequest(CallToolRequest {
+        method: CallToolRequestMethod,
+        params: CallToolRequestParam {
+            name: "slow".into(),
+            arguments: None,
+        },
+        extensions: Default::default(),
+    })
+}
+
+#[tokio::test]
+async fn test_cancel_and_abort_requests() -> anyhow::Result<()> {
+    let server = SlowServer::default();
+    let cancelled = server.cancelled.clone();
+    let notifications = Notifications::default();
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let (server, client) = tokio::join!(
+        server
+            .with_observer(notifications.clone())
+            .serve(server_transport),
+        ().serve(client_transport)
+    );
+    let (server, client) = (server?, client?);
+
+    // cancel while another task awaits the response
+    let handle = client
+        .send_cancellable_request(slow_call(), PeerRequestOptions::no_options())
+        .await?;
+    let canceller = handle.canceller();
+    let response = tokio::spawn(handle.await_response());
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    canceller.cancel(Some("not needed".into())).await?;
+    let result = tokio::time::timeout(Duration::from_secs(1), response).await??;
+    let Err(ServiceError::Cancelled { reason }) = result else {
+        panic!("expected a cancellation, got {result:?}");
+    };
+    assert_eq!(reason.as_deref(), Some("not needed"));
+
+    // the server side token fired
+    tokio::time::timeout(Duration::from_secs(1), async {
+        while !cancelled.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+    let sent = |notifications: &Notifications| {
+        notifications
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|method| *method == "notifications/cancelled")
+            .count()
+    };
+    assert_eq!(sent(&notifications), 1);
+
+    // abort only stops waiting
+    cancelled.store(false, Ordering::SeqCst);
+    let handle = client
+        .send_cancellable_request(slow_call(), PeerRequestOptions::no_options())
+        .await?;
+    let canceller = handle.canceller();
+    let response = tokio::spawn(handle.await_response());
+    canceller.abort().await?;
+    let result = tokio::time::timeout(Duration::from_secs(1), response).await??;
+    assert!(matches!(
+        result,
+        Err(ServiceError::Cancelled { reason: None })
+    ));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!cancelled.load(Ordering::SeqCst));
+    assert_eq!(sent(&notifications), 1);
+
+    // dropping a handle leaves the request running
+    let handle = client
+        .send_cancellable_request(slow_call(), PeerRequestOptions::no_options())
+        .await?;
+    drop(handle);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(sent(&notifications), 1);
+
+    client.cancel().await?;
+    server.cancel().await?;
+    Ok(())
+}
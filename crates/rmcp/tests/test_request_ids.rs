@@ -0,0 +1,172 @@
+//cargo test --test test_request_ids --features "client server"
+
+use std::sync::{Arc, Mutex};
+
+use rmcp::{
+    Error as McpError, RoleServer, ServerHandler, ServiceExt, model::RequestId,
+    service::RequestContext,
+};
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream, ReadHalf, WriteHalf};
+
+/// A server remembering the ids of the pings it answered
+#[derive(Debug, Clone, Default)]
+struct IdServer {
+    ids: Arc<Mutex<Vec<RequestId>>>,
+}
+
+impl ServerHandler for IdServer {
+    async fn ping(&self, context: RequestContext<RoleServer>) -> Result<(), McpError> {
+        self.ids.lock().unwrap().push(context.id);
+        Ok(())
+    }
+}
+
+/// One end of a session, writing and reading raw messages
+struct RawPeer {
+    reader: BufReader<ReadHalf<DuplexStream>>,
+    writer: WriteHalf<DuplexStream>,
+}
+
+impl RawPeer {
+    fn new(transport: DuplexStream) -> Self {
+        let (reader, writer) = tokio::io::split(transport);
+        RawPeer {
+            reader: BufReader::new(reader),
+            writer,
+        }
+    }
+
+    async fn send(&mut self, message: Value) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(&message)?;
+        line.push(b'\n');
+        self.writer.write_all(&line).await?;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> anyhow::Result<Value> {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).await?;
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    async fn request(&mut self, message: Value) -> anyhow::Result<Value> {
+        self.send(message).await?;
+        self.receive().await
+    }
+}
+
+#[tokio::test]
+async fn test_server_echoes_request_ids() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = IdServer::default();
+    let ids = server.ids.clone();
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let mut client = RawPeer::new(client_transport);
+
+    let response = client
+        .request(json!({
+            "jsonrpc": "2.0",
+            "id": "init",
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": { "name": "raw", "version": "1.0" }
+            }
+        }))
+        .await?;
+    assert_eq!(response["id"], json!("init"), "{response}");
+    // a null id on a notification is tolerated
+    client
+        .send(json!({ "jsonrpc": "2.0", "id": null, "method": "notifications/initialized" }))
+        .await?;
+
+    let sent = [
+        json!("ping-1"),
+        json!("2"),
+        json!(2),
+        json!(-3),
+        json!(9_007_199_254_740_993_i64),
+    ];
+    for id in &sent {
+        let response = client
+            .request(json!({ "jsonrpc": "2.0", "id": id, "method": "ping" }))
+            .await?;
+        assert_eq!(
+            response,
+            json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+        );
+    }
+    let received = ids.lock().unwrap().clone();
+    assert_eq!(
+        received,
+        sent.map(|id| serde_json::from_value::<RequestId>(id).unwrap())
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_echoes_request_ids() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let client = tokio::spawn(async move {
+        let client = ().serve(client_transport).await?;
+        let tools = client.list_all_tools().await?;
+        // answer the pings until the server is gone
+        client.waiting().await?;
+        anyhow::Ok(tools)
+    });
+    let mut server = RawPeer::new(server_transport);
+
+    let initialize = server.receive().await?;
+    assert_eq!(initialize["method"], "initialize", "{initialize}");
+    server
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": initialize["id"],
+            "result": {
+                "protocolVersion": "2025-03-26",
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "raw", "version": "1.0" }
+            }
+        }))
+        .await?;
+    let initialized = server.receive().await?;
+    assert_eq!(initialized["method"], "notifications/initialized");
+
+    // the server picks string ids, the client answers with the same ids
+    let sent = [json!("srv-1"), json!("0"), json!(0)];
+    for id in &sent {
+        server
+            .send(json!({ "jsonrpc": "2.0", "id": id, "method": "ping" }))
+            .await?;
+    }
+    let mut responses = Vec::new();
+    let mut listed = false;
+    while responses.len() < sent.len() || !listed {
+        let message = server.receive().await?;
+        if message["method"] == "tools/list" {
+            // and correlates the answers to its own requests by their ids
+            server
+                .send(json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "result": { "tools": [] }
+                }))
+                .await?;
+            listed = true;
+        } else {
+            responses.push(message);
+        }
+    }
+    assert_eq!(
+        responses,
+        sent.map(|id| json!({ "jsonrpc": "2.0", "id": id, "result": {} }))
+    );
+    drop(server);
+    assert!(client.await??.is_empty());
+    Ok(())
+}
@@ -0,0 +1,91 @@
+//cargo test --test test_request_progress --features "client server macros"
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rmcp::{
+    ServiceExt, handler::server::tool::ToolProgress, model::CallToolRequestParam,
+    service::PeerRequestOptions, tool,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportServer;
+
+#[tool(tool_box)]
+impl ExportServer {
+    #[tool(description = "Export the data, reporting the progress")]
+    async fn export(&self, progress: ToolProgress) -> String {
+        for (done, message) in [
+            (0.0, "started"),
+            (50.0, "halfway"),
+            (30.0, "late"),
+            (100.0, "done"),
+        ] {
+            progress
+                .report(done, Some(100.0), Some(message.into()))
+                .await
+                .expect("failed to report progress");
+        }
+        // reported once the request is answered
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let _ = progress.report(101.0, Some(100.0), None).await;
+        });
+        "exported".into()
+    }
+}
+
+#[tokio::test]
+async fn test_progress_callback_per_request() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        ExportServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let options = PeerRequestOptions::no_options().with_progress({
+        let received = received.clone();
+        move |params| {
+            let message = params.message.unwrap_or_default();
+            received.lock().unwrap().push((params.progress, message));
+        }
+    });
+    let params = CallToolRequestParam {
+        name: "export".into(),
+        arguments: None,
+    };
+    let result = client.call_tool_with_options(params, options).await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "exported");
+
+    // the progress before the response, in order, and never after it
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        *received.lock().unwrap(),
+        [
+            (0.0, "started".to_owned()),
+            (50.0, "halfway".to_owned()),
+            (100.0, "done".to_owned())
+        ]
+    );
+
+    // requests without a callback are unaffected
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "export".into(),
+            arguments: None,
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "exported");
+    assert_eq!(received.lock().unwrap().len(), 3);
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,138 @@
+//cargo test --test test_resource_link --features "client server macros"
+
+use rmcp::{
+    RoleServer, ServerHandler, ServiceExt,
+    model::{
+        CallToolRequestParam, CallToolResult, Content, GetPromptResult, PromptMessageContent,
+        PromptMessageRole, RawResource, ReadResourceRequestParam, ReadResourceResult,
+        ResourceContents,
+    },
+    service::RequestContext,
+    tool,
+};
+use serde_json::json;
+
+fn report_link() -> RawResource {
+    RawResource {
+        description: Some("The weekly report".into()),
+        mime_type: Some("text/markdown".into()),
+        ..RawResource::new("report://123", "report.md")
+    }
+}
+
+#[test]
+fn test_resource_link_serde() -> anyhow::Result<()> {
+    // the example of the specification
+    let fixture = json!({
+        "type": "resource_link",
+        "uri": "report://123",
+        "name": "report.md",
+        "description": "The weekly report",
+        "mimeType": "text/markdown",
+        "annotations": {
+            "audience": ["assistant"],
+            "priority": 0.5
+        }
+    });
+    let content: Content = serde_json::from_value(fixture.clone())?;
+    let link = content.as_resource_link().expect("a resource link");
+    assert_eq!(link, &report_link());
+    assert_eq!(content.annotations.as_ref().unwrap().priority, Some(0.5));
+    assert_eq!(serde_json::to_value(&content)?, fixture);
+
+    let content = Content::resource_link(report_link());
+    let value = serde_json::to_value(&content)?;
+    assert_eq!(value["type"], "resource_link");
+    assert!(value.get("size").is_none());
+    assert_eq!(serde_json::from_value::<Content>(value)?, content);
+    Ok(())
+}
+
+#[test]
+fn test_resource_link_in_prompt_messages() -> anyhow::Result<()> {
+    let result = GetPromptResult::builder()
+        .user_text("Summarize the report")
+        .resource_link(PromptMessageRole::User, report_link())
+        .build();
+    let value = serde_json::to_value(&result)?;
+    assert_eq!(
+        value["messages"][1]["content"],
+        json!({
+            "type": "resource_link",
+            "uri": "report://123",
+            "name": "report.md",
+            "description": "The weekly report",
+            "mimeType": "text/markdown"
+        })
+    );
+
+    let round_tripped: GetPromptResult = serde_json::from_value(value)?;
+    assert_eq!(round_tripped, result);
+    let PromptMessageContent::ResourceLink { link } = &round_tripped.messages[1].content else {
+        panic!("expected a resource link");
+    };
+    assert_eq!(link.raw, report_link());
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReportServer;
+
+#[tool(tool_box, default_build = false)]
+impl ReportServer {
+    #[tool(description = "Link the weekly report")]
+    async fn report(&self) -> CallToolResult {
+        CallToolResult::success(vec![Content::resource_link(report_link())])
+    }
+}
+
+#[tool(tool_box)]
+impl ServerHandler for ReportServer {
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, rmcp::Error> {
+        assert_eq!(request.uri, "report://123");
+        Ok(ReadResourceResult::text(
+            request.uri,
+            "text/markdown",
+            "# Weekly report",
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_read_linked_resource() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        ReportServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "report".into(),
+            arguments: None,
+        })
+        .await?;
+    let link = result.content[0]
+        .as_resource_link()
+        .expect("a resource link");
+    let read = client
+        .read_resource(ReadResourceRequestParam {
+            uri: link.uri.clone(),
+        })
+        .await?;
+    let [ResourceContents::TextResourceContents { text, .. }] = read.contents.as_slice() else {
+        panic!("expected one text resource, got {:?}", read.contents);
+    };
+    assert_eq!(text, "# Weekly report");
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//cargo test --test test_resource_macros --features "client server"
+
+use rmcp::{
+    ClientHandler, Peer, RoleClient, ServiceExt,
+    handler::server::resource::ResourceAttr,
+    model::{ClientInfo, ReadResourceRequestParam, ResourceContents},
+    resource, tool,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct ResourceServer;
+
+#[tool(tool_box)]
+impl ResourceServer {
+    #[tool(description = "Say hello")]
+    async fn hello(&self) -> String {
+        "hello".to_string()
+    }
+
+    /// The application configuration
+    #[resource(uri = "config://app", mime_type = "application/json")]
+    async fn config(&self) -> String {
+        r#"{"debug":true}"#.to_string()
+    }
+
+    /// A file in the workspace
+    #[resource(uri = "file:///{path}", mime_type = "text/plain")]
+    async fn file(&self, path: String) -> String {
+        format!("content of {path}")
+    }
+
+    #[resource(uri = "issue://{repo}/{number}", name = "issue")]
+    fn issue(&self, repo: String, number: u32) -> ResourceContents {
+        ResourceContents::text(
+            format!("{repo}#{number}"),
+            format!("issue://{repo}/{number}"),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct DummyClientHandler {
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl ClientHandler for DummyClientHandler {
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo::default()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+}
+
+fn text_of(contents: &ResourceContents) -> (&str, &str, Option<&str>) {
+    match contents {
+        ResourceContents::TextResourceContents {
+            uri,
+            mime_type,
+            text,
+        } => (uri.as_str(), text.as_str(), mime_type.as_deref()),
+        ResourceContents::BlobResourceContents { .. } => panic!("expected text contents"),
+    }
+}
+
+#[test]
+fn test_resource_attr() {
+    let ResourceAttr::Resource(resource) = ResourceServer::config_resource_attr() else {
+        panic!("config should be a static resource");
+    };
+    assert_eq!(resource.uri, "config://app");
+    assert_eq!(resource.name, "config");
+    assert_eq!(
+        resource.description.as_deref(),
+        Some("The application configuration")
+    );
+    assert_eq!(resource.mime_type.as_deref(), Some("application/json"));
+
+    let ResourceAttr::Template(template) = ResourceServer::file_resource_attr() else {
+        panic!("file should be a resource template");
+    };
+    assert_eq!(template.uri_template, "file:///{path}");
+    assert_eq!(
+        template.description.as_deref(),
+        Some("A file in the workspace")
+    );
+}
+
+#[tokio::test]
+async fn test_tools_and_resources() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+
+    let server_handle = tokio::spawn(async move {
+        ResourceServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let capabilities = &client.peer_info().capabilities;
+    assert!(capabilities.tools.is_some());
+    assert!(capabilities.resources.is_some());
+    assert_eq!(client.list_all_tools().await?.len(), 1);
+
+    let resources = client.list_all_resources().await?;
+    assert_eq!(resources.len(), 1);
+    assert_eq!(resources[0].uri, "config://app");
+
+    let mut templates = client.list_all_resource_templates().await?;
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(
+        templates
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect::<Vec<_>>(),
+        ["file", "issue"]
+    );
+
+    let result = client
+        .read_resource(ReadResourceRequestParam {
+            uri: "config://app".into(),
+        })
+        .await?;
+    assert_eq!(
+        text_of(&result.contents[0]),
+        (
+            "config://app",
+            r#"{"debug":true}"#,
+            Some("application/json")
+        )
+    );
+
+    let result = client
+        .read_resource(ReadResourceRequestParam {
+            uri: "file:///src/main.rs".into(),
+        })
+        .await?;
+    assert_eq!(
+        text_of(&result.contents[0]),
+        (
+            "file:///src/main.rs",
+            "content of src/main.rs",
+            Some("text/plain")
+        )
+    );
+
+    let result = client
+        .read_resource(ReadResourceRequestParam {
+            uri: "issue://rmcp/42".into(),
+        })
+        .await?;
+    assert_eq!(text_of(&result.contents[0]).1, "rmcp#42");
+
+    // number is not a valid u32
+    let invalid = client
+        .read_resource(ReadResourceRequestParam {
+            uri: "issue://rmcp/latest".into(),
+        })
+        .await;
+    assert!(invalid.is_err());
+
+    let missing = client
+        .read_resource(ReadResourceRequestParam {
+            uri: "unknown://resource".into(),
+        })
+        .await;
+    assert!(missing.is_err());
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
@@ -0,0 +1,139 @@
+//cargo test --test test_resource_subscriptions --features "client server"
+
+use std::time::Duration;
+
+use rmcp::{
+    ClientHandler, Peer, RoleClient, ServerHandler, ServiceExt,
+    handler::server::subscription::SubscriptionManager,
+    model::{ResourceUpdatedNotificationParam, SubscribeRequestParam, UnsubscribeRequestParam},
+    service::RunningService,
+};
+use tokio::sync::mpsc;
+
+const URI: &str = "file:///notes.txt";
+
+#[derive(Debug, Clone, Default)]
+pub struct NotesServer;
+
+impl ServerHandler for NotesServer {}
+
+#[derive(Clone)]
+struct UpdateClient {
+    updates: mpsc::UnboundedSender<String>,
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl ClientHandler for UpdateClient {
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    async fn on_resource_updated(&self, params: ResourceUpdatedNotificationParam) {
+        let _ = self.updates.send(params.uri);
+    }
+}
+
+async fn connect(
+    subscriptions: &SubscriptionManager,
+) -> anyhow::Result<(
+    RunningService<RoleClient, UpdateClient>,
+    mpsc::UnboundedReceiver<String>,
+)> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = NotesServer.with_subscriptions(subscriptions.clone());
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let (sender, updates) = mpsc::unbounded_channel();
+    let client = UpdateClient {
+        updates: sender,
+        peer: None,
+    }
+    .serve(client_transport)
+    .await?;
+    Ok((client, updates))
+}
+
+async fn receive(updates: &mut mpsc::UnboundedReceiver<String>) -> anyhow::Result<String> {
+    Ok(tokio::time::timeout(Duration::from_secs(5), updates.recv())
+        .await?
+        .expect("client dropped"))
+}
+
+async fn assert_no_update(updates: &mut mpsc::UnboundedReceiver<String>) {
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(updates.try_recv().is_err(), "unexpected update");
+}
+
+#[tokio::test]
+async fn test_subscribe_update_unsubscribe() -> anyhow::Result<()> {
+    let subscriptions = SubscriptionManager::new();
+    let (client, mut updates) = connect(&subscriptions).await?;
+    assert_eq!(
+        client
+            .peer_info()
+            .capabilities
+            .resources
+            .as_ref()
+            .and_then(|resources| resources.subscribe),
+        Some(true)
+    );
+
+    // subscribing twice is the same as once
+    for _ in 0..2 {
+        client
+            .subscribe(SubscribeRequestParam { uri: URI.into() })
+            .await?;
+    }
+    assert_eq!(subscriptions.subscriber_count(URI), 1);
+    assert_eq!(subscriptions.notify_updated("file:///other.txt").await, 0);
+    assert_eq!(subscriptions.notify_updated(URI).await, 1);
+    assert_eq!(receive(&mut updates).await?, URI);
+    assert_no_update(&mut updates).await;
+
+    client
+        .unsubscribe(UnsubscribeRequestParam { uri: URI.into() })
+        .await?;
+    assert_eq!(subscriptions.subscriber_count(URI), 0);
+    assert_eq!(subscriptions.notify_updated(URI).await, 0);
+    assert_no_update(&mut updates).await;
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_updates_fan_out_and_disconnected_sessions_are_dropped() -> anyhow::Result<()> {
+    let subscriptions = SubscriptionManager::new();
+    let (first, mut first_updates) = connect(&subscriptions).await?;
+    let (second, mut second_updates) = connect(&subscriptions).await?;
+    for client in [&first, &second] {
+        client
+            .subscribe(SubscribeRequestParam { uri: URI.into() })
+            .await?;
+    }
+    assert_eq!(subscriptions.subscriber_count(URI), 2);
+
+    assert_eq!(subscriptions.notify_updated(URI).await, 2);
+    assert_eq!(receive(&mut first_updates).await?, URI);
+    assert_eq!(receive(&mut second_updates).await?, URI);
+
+    // the session of the first client goes away with its subscriptions
+    first.cancel().await?;
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while subscriptions.subscriber_count(URI) != 1 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+    assert_eq!(subscriptions.notify_updated(URI).await, 1);
+    assert_eq!(receive(&mut second_updates).await?, URI);
+
+    second.cancel().await?;
+    Ok(())
+}
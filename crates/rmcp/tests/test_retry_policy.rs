@@ -0,0 +1,135 @@
+//cargo test --test test_retry_policy --features "client server macros"
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::FutureExt;
+use rmcp::{
+    RoleClient, ServiceExt,
+    model::{CallToolRequestParam, GetMethod, JsonRpcMessage, RequestId},
+    service::{RetryPolicy, RxJsonRpcMessage, ServiceError, TxJsonRpcMessage},
+    tool,
+    transport::{Transport, async_rw::AsyncRwTransport},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Server;
+
+#[tool(tool_box)]
+impl Server {
+    #[tool(description = "Say hello")]
+    async fn hello(&self) -> String {
+        "hello".into()
+    }
+}
+
+/// Fails the first request sent for each method, and records the requests sent
+struct FlakyTransport<T> {
+    inner: T,
+    failed: Arc<Mutex<Vec<String>>>,
+    sent: Arc<Mutex<Vec<(String, RequestId)>>>,
+}
+
+impl<T: Transport<RoleClient, Error = std::io::Error>> Transport<RoleClient> for FlakyTransport<T> {
+    type Error = std::io::Error;
+
+    fn send(
+        &mut self,
+        item: TxJsonRpcMessage<RoleClient>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        if let JsonRpcMessage::Request(request) = &item {
+            let method = request.request.method();
+            self.sent
+                .lock()
+                .unwrap()
+                .push((method.to_owned(), request.id.clone()));
+            let mut failed = self.failed.lock().unwrap();
+            if !failed.iter().any(|failed| failed == method) {
+                failed.push(method.to_owned());
+                let error = std::io::Error::new(std::io::ErrorKind::BrokenPipe, "flaky");
+                return futures::future::ready(Err(error)).left_future();
+            }
+        }
+        self.inner.send(item).right_future()
+    }
+
+    fn receive(&mut self) -> impl Future<Output = Option<RxJsonRpcMessage<RoleClient>>> + Send {
+        self.inner.receive()
+    }
+
+    fn close(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        self.inner.close()
+    }
+}
+
+fn hello() -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: "hello".into(),
+        arguments: None,
+    }
+}
+
+#[tokio::test]
+async fn test_retry_idempotent_requests() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        Server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let (read, write) = tokio::io::split(client_transport);
+    let failed = Arc::new(Mutex::new(vec!["initialize".to_owned()]));
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let transport = FlakyTransport {
+        inner: AsyncRwTransport::new_client(read, write),
+        failed: failed.clone(),
+        sent: sent.clone(),
+    };
+    let client = ().serve(transport).await?;
+    assert!(client.retry_policy().is_none());
+
+    let policy =
+        RetryPolicy::new().with_backoff(Duration::from_millis(10), Duration::from_millis(50));
+    let retrying = client.with_retry(policy.clone());
+
+    // the first attempt fails, the second one has a fresh id
+    let tools = retrying.list_tools(None).await?;
+    assert_eq!(tools.tools.len(), 1);
+    let attempts = sent.lock().unwrap().split_off(1);
+    let [(first, first_id), (second, second_id)] = &attempts[..] else {
+        panic!("expected two attempts, got {attempts:?}");
+    };
+    assert_eq!(
+        (first.as_str(), second.as_str()),
+        ("tools/list", "tools/list")
+    );
+    assert_ne!(first_id, second_id);
+
+    // tools/call isn't retried by default
+    let result = retrying.call_tool(hello()).await;
+    assert!(matches!(result, Err(ServiceError::TransportSend(_))));
+    assert_eq!(sent.lock().unwrap().len(), 2);
+
+    // unless it is marked safe
+    failed
+        .lock()
+        .unwrap()
+        .retain(|method| method != "tools/call");
+    let result = client
+        .with_retry(policy.allow_method("tools/call"))
+        .call_tool(hello())
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "hello");
+
+    // a peer without a policy fails at once
+    failed
+        .lock()
+        .unwrap()
+        .retain(|method| method != "tools/list");
+    let result = client.list_tools(None).await;
+    assert!(matches!(result, Err(ServiceError::TransportSend(_))));
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,139 @@
+//cargo test --test test_roots_watcher --features "client server macros"
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rmcp::{
+    ClientHandler, Peer, RoleClient, ServerHandler, ServiceExt,
+    handler::server::{roots::RootsWatcher, tool::Extension},
+    model::{CallToolRequestParam, ClientCapabilities, ClientInfo, Content, ListRootsResult, Root},
+    service::RequestContext,
+    tool,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct FileServer;
+
+#[tool(tool_box)]
+impl FileServer {
+    #[tool(description = "List the roots the server may access")]
+    async fn allowed_roots(&self, Extension(watcher): Extension<RootsWatcher>) -> String {
+        watcher
+            .current()
+            .into_iter()
+            .map(|root| root.uri)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+#[derive(Clone, Default)]
+struct RootsClient {
+    roots: Arc<Mutex<Vec<Root>>>,
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl ClientHandler for RootsClient {
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            capabilities: ClientCapabilities::builder()
+                .enable_roots()
+                .enable_roots_list_changed()
+                .build(),
+            ..Default::default()
+        }
+    }
+
+    async fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<ListRootsResult, rmcp::Error> {
+        Ok(ListRootsResult {
+            roots: self.roots.lock().unwrap().clone(),
+            meta: None,
+        })
+    }
+}
+
+fn root(uri: &str) -> Root {
+    Root {
+        uri: uri.into(),
+        name: None,
+    }
+}
+
+#[tokio::test]
+async fn test_roots_watcher_follows_client() -> anyhow::Result<()> {
+    let watcher = RootsWatcher::new();
+    let first_listing = watcher.changed();
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = FileServer.with_roots_watcher(watcher.clone());
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let handler = RootsClient::default();
+    *handler.roots.lock().unwrap() = vec![root("file:///project")];
+    let roots = handler.roots.clone();
+    let client = handler.serve(client_transport).await?;
+
+    // listed once the client is initialized
+    let listed = tokio::time::timeout(Duration::from_secs(5), first_listing).await?;
+    assert_eq!(listed, vec![root("file:///project")]);
+
+    // the client changes its roots mid-session
+    let next_listing = watcher.changed();
+    roots.lock().unwrap().push(root("file:///shared"));
+    client.notify_roots_list_changed().await?;
+    let listed = tokio::time::timeout(Duration::from_secs(5), next_listing).await?;
+    assert_eq!(
+        listed,
+        vec![root("file:///project"), root("file:///shared")]
+    );
+    assert_eq!(watcher.current(), listed);
+
+    // tools see the same roots
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "allowed_roots".into(),
+            arguments: None,
+        })
+        .await?;
+    assert_eq!(
+        result.content,
+        vec![Content::text("file:///project,file:///shared")]
+    );
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_roots_watcher_without_capability() -> anyhow::Result<()> {
+    let watcher = RootsWatcher::new();
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = FileServer.with_roots_watcher(watcher.clone());
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    // a client without roots is never asked for them
+    let client = ().serve(client_transport).await?;
+
+    assert_eq!(watcher.refresh().await?, vec![]);
+    assert!(watcher.current().is_empty());
+
+    client.cancel().await?;
+    Ok(())
+}
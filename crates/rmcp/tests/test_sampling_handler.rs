@@ -0,0 +1,104 @@
+//cargo test --test test_sampling_handler --features "client server macros"
+
+use rmcp::{
+    Peer, RoleServer, ServiceExt,
+    handler::client::{
+        ClientHandlerBuilder,
+        sampling::{ModelCandidate, StaticSamplingHandler},
+    },
+    model::{
+        CallToolRequestParam, CallToolResult, CreateMessageRequestParam, ModelHint,
+        ModelPreferences, SamplingMessage,
+    },
+    service::ServiceError,
+    tool,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct TitleServer;
+
+#[tool(tool_box)]
+impl TitleServer {
+    #[tool(description = "Suggest a title for a text with the client's model")]
+    async fn suggest_title(
+        &self,
+        peer: Peer<RoleServer>,
+        #[tool(param)] text: String,
+        #[tool(param)] model: Option<String>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let request = CreateMessageRequestParam::new(vec![SamplingMessage::user_text(text)], 20)
+            .with_system_prompt("Suggest a short title")
+            .with_model_preferences(ModelPreferences {
+                hints: model.map(|name| vec![ModelHint { name: Some(name) }]),
+                cost_priority: Some(0.8),
+                speed_priority: Some(0.2),
+                intelligence_priority: None,
+            });
+        let result = peer.create_message(request).await?;
+        let title = result.message.content.as_text().unwrap().text.clone();
+        Ok(CallToolResult::text(format!("{title} ({})", result.model)))
+    }
+}
+
+fn suggest_title(model: Option<&str>) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: "suggest_title".into(),
+        arguments: serde_json::json!({ "text": "rust sdk release notes", "model": model })
+            .as_object()
+            .cloned()
+            .map(Into::into),
+    }
+}
+
+#[tokio::test]
+async fn test_tool_samples_with_static_handler() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        TitleServer.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let sampling = StaticSamplingHandler::new("fallback")
+        .with_models([
+            ModelCandidate::new("large-model", 0.9, 0.3, 1.0),
+            ModelCandidate::new("small-model", 0.1, 0.9, 0.3),
+        ])
+        .reply("What's new in the SDK")
+        .reply("Release notes");
+    let client = ClientHandlerBuilder::new()
+        .with_sampling(sampling.clone())
+        .build()
+        .serve(client_transport)
+        .await?;
+
+    // the cheapest model without hints, the hinted one otherwise
+    let result = client.call_tool(suggest_title(None)).await?;
+    assert_eq!(
+        result.content[0].as_text().unwrap().text,
+        "What's new in the SDK (small-model)"
+    );
+    let result = client.call_tool(suggest_title(Some("large"))).await?;
+    assert_eq!(
+        result.content[0].as_text().unwrap().text,
+        "Release notes (large-model)"
+    );
+
+    let requests = sampling.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(
+        requests[0].system_prompt.as_deref(),
+        Some("Suggest a short title")
+    );
+    assert_eq!(
+        requests[0].messages[0].content.as_text().unwrap().text,
+        "rust sdk release notes"
+    );
+
+    // out of canned completions
+    let Err(ServiceError::McpError(error)) = client.call_tool(suggest_title(None)).await else {
+        panic!("sampling should fail without completions left");
+    };
+    assert_eq!(error.message, "no canned completion left");
+
+    client.cancel().await?;
+    Ok(())
+}
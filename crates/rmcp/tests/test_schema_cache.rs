@@ -0,0 +1,143 @@
+//cargo test --test test_schema_cache --features "server macros"
+use std::sync::Arc;
+
+use rmcp::{
+    handler::server::tool::{
+        SchemaCache, SchemaSettings, cached_schema_for_type_with, precompute_with,
+        schema_for_type_with,
+    },
+    model::JsonObject,
+    schemars, tool,
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct Polygon {
+    pub points: Vec<Point>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GeometryServer;
+
+#[tool(tool_box)]
+impl GeometryServer {
+    #[tool(description = "Count the points of a polygon", schema(inline_refs))]
+    async fn count(&self, #[tool(aggr)] polygon: Polygon) -> String {
+        polygon.points.len().to_string()
+    }
+}
+
+const INLINE: SchemaSettings = SchemaSettings::new().with_inline_refs(true);
+const STRIPPED: SchemaSettings = SchemaSettings::new().with_strip_formats(true);
+
+// a schema for each pair of type and settings
+fn schemas(cache: &SchemaCache) -> [Arc<JsonObject>; 4] {
+    [
+        cache.get_or_generate::<Point>(SchemaSettings::new()),
+        cache.get_or_generate::<Point>(STRIPPED),
+        cache.get_or_generate::<Polygon>(SchemaSettings::new()),
+        cache.get_or_generate::<Polygon>(INLINE),
+    ]
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_cache_shared_by_tasks() -> anyhow::Result<()> {
+    let cache = Arc::new(SchemaCache::new());
+    let tasks = (0..16)
+        .map(|_| {
+            let cache = cache.clone();
+            tokio::spawn(async move {
+                let mut seen = Vec::new();
+                for _ in 0..200 {
+                    seen.push(schemas(&cache));
+                    tokio::task::yield_now().await;
+                }
+                seen
+            })
+        })
+        .collect::<Vec<_>>();
+    let expected = schemas(&cache);
+    for task in tasks {
+        for seen in task.await? {
+            for (schema, expected) in seen.iter().zip(&expected) {
+                assert!(Arc::ptr_eq(schema, expected));
+            }
+        }
+    }
+    assert_eq!(cache.len(), 4);
+    assert_ne!(expected[2], expected[3]);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_bounded_cache_under_contention() -> anyhow::Result<()> {
+    let cache = Arc::new(SchemaCache::new().with_capacity(Some(2)));
+    let tasks = (0..16)
+        .map(|_| {
+            let cache = cache.clone();
+            tokio::spawn(async move {
+                for _ in 0..200 {
+                    let [point, _, polygon, inline] = schemas(&cache);
+                    assert_eq!(*point, schema_for_type_with::<Point>(SchemaSettings::new()));
+                    assert_eq!(
+                        *polygon,
+                        schema_for_type_with::<Polygon>(SchemaSettings::new())
+                    );
+                    assert_eq!(*inline, schema_for_type_with::<Polygon>(INLINE));
+                    assert!(cache.len() <= 2);
+                    tokio::task::yield_now().await;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for task in tasks {
+        task.await?;
+    }
+    assert_eq!(cache.len(), 2);
+
+    cache.set_capacity(Some(1));
+    assert_eq!(cache.len(), 1);
+    cache.set_capacity(None);
+    schemas(&cache);
+    assert_eq!(cache.len(), 4);
+    cache.clear();
+    assert!(cache.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_least_recently_used_is_evicted() {
+    let cache = SchemaCache::new().with_capacity(Some(2));
+    let point = cache.get_or_generate::<Point>(SchemaSettings::new());
+    let polygon = cache.get_or_generate::<Polygon>(SchemaSettings::new());
+    // the point is used again, the polygon is now the oldest
+    cache.get_or_generate::<Point>(SchemaSettings::new());
+    cache.get_or_generate::<Point>(STRIPPED);
+    assert!(Arc::ptr_eq(
+        &point,
+        &cache.get_or_generate::<Point>(SchemaSettings::new())
+    ));
+    assert!(!Arc::ptr_eq(
+        &polygon,
+        &cache.get_or_generate::<Polygon>(SchemaSettings::new())
+    ));
+}
+
+#[test]
+fn test_precompute() {
+    precompute_with::<Polygon>(INLINE);
+    let precomputed = cached_schema_for_type_with::<Polygon>(INLINE);
+    // the tool gets the precomputed schema
+    let tool = GeometryServer::count_tool_attr();
+    assert!(Arc::ptr_eq(&tool.input_schema, &precomputed));
+    assert!(Arc::ptr_eq(
+        &tool.input_schema,
+        &GeometryServer::count_tool_attr().input_schema
+    ));
+}
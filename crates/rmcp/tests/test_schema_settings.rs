@@ -0,0 +1,102 @@
+//cargo test --test test_schema_settings --features "server macros"
+use rmcp::{
+    Error as McpError,
+    handler::server::tool::{SchemaSettings, cached_schema_for_type_with},
+    model::*,
+    schemars, tool,
+};
+use serde::Deserialize;
+
+/// Size of a shirt.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Size {
+    Small,
+    Medium,
+    Large,
+}
+
+/// A line of an order.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct LineItem {
+    pub sku: String,
+    pub quantity: u32,
+    pub size: Size,
+    pub gift_note: Option<String>,
+}
+
+/// Order shirts.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct OrderRequest {
+    pub customer: String,
+    pub items: Vec<LineItem>,
+    pub express: Size,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShopServer;
+
+#[tool(tool_box)]
+impl ShopServer {
+    #[tool(description = "Order shirts")]
+    async fn order(&self, #[tool(aggr)] request: OrderRequest) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text(
+            request.customer,
+        )]))
+    }
+
+    #[tool(
+        description = "Order shirts, for clients which can't resolve references",
+        schema(inline_refs, strip_formats)
+    )]
+    async fn order_inline(
+        &self,
+        #[tool(aggr)] request: OrderRequest,
+    ) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text(
+            request.customer,
+        )]))
+    }
+}
+
+fn golden_schema(name: &str) -> serde_json::Value {
+    let json = std::fs::read(format!("tests/test_schema_settings/{name}.json")).unwrap();
+    serde_json::from_slice(&json).unwrap()
+}
+
+#[test]
+fn test_schema_with_references() {
+    let schema = cached_schema_for_type_with::<OrderRequest>(SchemaSettings::new());
+    assert_eq!(
+        serde_json::Value::Object((*schema).clone()),
+        golden_schema("order_request")
+    );
+}
+
+#[test]
+fn test_schema_with_inline_refs() {
+    let settings = SchemaSettings::new()
+        .with_inline_refs(true)
+        .with_strip_formats(true);
+    let schema = cached_schema_for_type_with::<OrderRequest>(settings);
+    assert_eq!(
+        serde_json::Value::Object((*schema).clone()),
+        golden_schema("order_request_inline")
+    );
+    let schema = serde_json::to_string(&*schema).unwrap();
+    assert!(!schema.contains("$ref"), "{schema}");
+    assert!(!schema.contains("uint32"), "{schema}");
+}
+
+#[test]
+fn test_tools_with_mixed_schema_settings() {
+    // the schemas of the same type, cached apart
+    let tools = [
+        ShopServer::order_tool_attr(),
+        ShopServer::order_inline_tool_attr(),
+    ];
+    let [order, order_inline] =
+        tools.map(|tool| serde_json::Value::Object((*tool.input_schema).clone()));
+    assert_eq!(order, golden_schema("order_request"));
+    assert_eq!(order_inline, golden_schema("order_request_inline"));
+}
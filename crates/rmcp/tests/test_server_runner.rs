@@ -0,0 +1,175 @@
+//cargo test --test test_server_runner --features "client server"
+use std::time::Duration;
+
+use rmcp::{
+    ClientHandler, RoleClient, ServerHandler, ServiceExt,
+    model::{ServerInfo, ServerNotification, ToolListChangedNotification},
+    service::{Peer, QuitReason, RunningService, ServerRunner},
+    transport::in_process,
+};
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone)]
+struct Server {
+    session: u64,
+}
+
+impl ServerHandler for Server {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            instructions: Some(format!("session {}", self.session)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Tells which client got `notifications/tools/list_changed`
+#[derive(Debug, Clone)]
+struct Client {
+    name: &'static str,
+    tx: mpsc::UnboundedSender<&'static str>,
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl Client {
+    fn new(name: &'static str, tx: &mpsc::UnboundedSender<&'static str>) -> Self {
+        Self {
+            name,
+            tx: tx.clone(),
+            peer: None,
+        }
+    }
+}
+
+impl ClientHandler for Client {
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    async fn on_tool_list_changed(&self) {
+        let _ = self.tx.send(self.name);
+    }
+}
+
+async fn connect(
+    runner: &ServerRunner<Server>,
+    client: Client,
+) -> anyhow::Result<RunningService<RoleClient, Client>> {
+    let (server_transport, client_transport) = in_process::pair();
+    let (session, client) = tokio::join!(
+        runner.serve(server_transport),
+        client.serve(client_transport)
+    );
+    session?;
+    Ok(client?)
+}
+
+fn runner() -> ServerRunner<Server> {
+    ServerRunner::with_session_info(|info| Server { session: info.id })
+}
+
+async fn wait_sessions(runner: &ServerRunner<Server>, count: usize) {
+    tokio::time::timeout(Duration::from_secs(1), async {
+        while runner.sessions().len() != count {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the sessions should end");
+}
+
+#[tokio::test]
+async fn test_broadcast_notification() -> anyhow::Result<()> {
+    let runner = runner();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut clients = Vec::new();
+    for name in ["first", "second", "third"] {
+        let client = connect(&runner, Client::new(name, &tx)).await?;
+        clients.push(client);
+    }
+
+    let sessions = runner.sessions();
+    assert_eq!(
+        sessions.iter().map(|s| s.info.id).collect::<Vec<_>>(),
+        [0, 1, 2]
+    );
+    // each session was made by the factory, with its own id
+    for (client, session) in clients.iter().zip(&sessions) {
+        let instructions = client.peer_info().instructions.clone();
+        assert_eq!(instructions, Some(format!("session {}", session.info.id)));
+    }
+
+    let failed = runner
+        .broadcast_notification(ServerNotification::ToolListChangedNotification(
+            ToolListChangedNotification {
+                method: Default::default(),
+                extensions: Default::default(),
+            },
+        ))
+        .await;
+    assert!(failed.is_empty(), "{failed:?}");
+    let mut notified = Vec::new();
+    for _ in 0..3 {
+        let name = tokio::time::timeout(Duration::from_secs(1), rx.recv()).await?;
+        notified.extend(name);
+    }
+    notified.sort();
+    assert_eq!(notified, ["first", "second", "third"]);
+
+    for client in clients {
+        client.cancel().await?;
+    }
+    wait_sessions(&runner, 0).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ended_sessions_leave_the_registry() -> anyhow::Result<()> {
+    let runner = runner();
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let first = connect(&runner, Client::new("first", &tx)).await?;
+    let second = connect(&runner, Client::new("second", &tx)).await?;
+    assert_eq!(runner.sessions().len(), 2);
+
+    first.cancel().await?;
+    wait_sessions(&runner, 1).await;
+    assert!(runner.session(0).is_none());
+    assert!(runner.session(1).is_some());
+
+    drop(second);
+    wait_sessions(&runner, 0).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_shutdown_all() -> anyhow::Result<()> {
+    let runner = runner();
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let mut clients = Vec::new();
+    for name in ["first", "second", "third"] {
+        clients.push(connect(&runner, Client::new(name, &tx)).await?);
+    }
+
+    let quit_reasons = runner.shutdown_all(Duration::from_secs(1)).await;
+    assert_eq!(quit_reasons.len(), 3);
+    for (_, quit_reason) in &quit_reasons {
+        assert!(
+            matches!(quit_reason, QuitReason::Graceful),
+            "{quit_reason:?}"
+        );
+    }
+    assert!(runner.sessions().is_empty());
+    for client in clients {
+        let quit_reason = tokio::time::timeout(Duration::from_secs(1), client.waiting()).await??;
+        assert!(matches!(quit_reason, QuitReason::Closed));
+    }
+
+    // no session is accepted anymore
+    let (server_transport, _client_transport) = in_process::pair();
+    assert!(runner.serve(server_transport).await.is_err());
+    Ok(())
+}
@@ -0,0 +1,151 @@
+//cargo test --test test_service_observer --features "client server macros"
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rmcp::{
+    ServiceExt,
+    model::{CallToolRequestParam, RequestId},
+    service::{MessageDirection, ServiceObserver},
+    tool,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Observed {
+    RequestStart(String, MessageDirection),
+    RequestEnd(String, MessageDirection, bool),
+    Notification(String, MessageDirection),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<Observed>>>);
+
+impl Recorder {
+    fn events(&self) -> Vec<Observed> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn count(&self, event: &Observed) -> usize {
+        self.events().iter().filter(|e| *e == event).count()
+    }
+
+    async fn wait_for(&self, len: usize) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while self.events().len() < len {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the observer should be called");
+    }
+}
+
+impl ServiceObserver for Recorder {
+    fn on_request_start(&self, method: &str, _id: &RequestId, direction: MessageDirection) {
+        let event = Observed::RequestStart(method.to_owned(), direction);
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn on_request_end(
+        &self,
+        method: &str,
+        _id: &RequestId,
+        direction: MessageDirection,
+        _duration: Duration,
+        ok: bool,
+    ) {
+        let event = Observed::RequestEnd(method.to_owned(), direction, ok);
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn on_notification(&self, method: &str, direction: MessageDirection) {
+        let event = Observed::Notification(method.to_owned(), direction);
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Server;
+
+#[tool(tool_box)]
+impl Server {
+    #[tool(description = "Always fail")]
+    async fn fail(&self) -> Result<rmcp::model::CallToolResult, rmcp::Error> {
+        Err(rmcp::Error::internal_error("failed", None))
+    }
+}
+
+fn start(method: &str, direction: MessageDirection) -> Observed {
+    Observed::RequestStart(method.to_owned(), direction)
+}
+
+fn end(method: &str, direction: MessageDirection, ok: bool) -> Observed {
+    Observed::RequestEnd(method.to_owned(), direction, ok)
+}
+
+fn notification(method: &str, direction: MessageDirection) -> Observed {
+    Observed::Notification(method.to_owned(), direction)
+}
+
+#[tokio::test]
+async fn test_observer_sees_scripted_session() -> anyhow::Result<()> {
+    use MessageDirection::{Inbound, Outbound};
+
+    let server_recorder = Recorder::default();
+    let client_recorder = Recorder::default();
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let (server, client) = tokio::join!(
+        Server
+            .with_observer(server_recorder.clone())
+            .serve(server_transport),
+        ().with_observer(client_recorder.clone())
+            .serve(client_transport)
+    );
+    let (server, client) = (server?, client?);
+
+    client.list_tools(None).await?;
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "fail".into(),
+            arguments: None,
+        })
+        .await;
+    assert!(result.is_err());
+    client.notify_roots_list_changed().await?;
+    server.notify_tool_list_changed().await?;
+
+    server_recorder.wait_for(9).await;
+    client_recorder.wait_for(9).await;
+
+    for (recorder, requests, notifications) in [
+        (&server_recorder, Inbound, [Inbound, Inbound, Outbound]),
+        (&client_recorder, Outbound, [Outbound, Outbound, Inbound]),
+    ] {
+        assert_eq!(recorder.events().len(), 9, "{:?}", recorder.events());
+        assert_eq!(recorder.count(&start("initialize", requests)), 1);
+        assert_eq!(recorder.count(&end("initialize", requests, true)), 1);
+        assert_eq!(recorder.count(&start("tools/list", requests)), 1);
+        assert_eq!(recorder.count(&end("tools/list", requests, true)), 1);
+        assert_eq!(recorder.count(&start("tools/call", requests)), 1);
+        assert_eq!(recorder.count(&end("tools/call", requests, false)), 1);
+        let [initialized, roots, tools] = notifications;
+        assert_eq!(
+            recorder.count(&notification("notifications/initialized", initialized)),
+            1
+        );
+        assert_eq!(
+            recorder.count(&notification("notifications/roots/list_changed", roots)),
+            1
+        );
+        assert_eq!(
+            recorder.count(&notification("notifications/tools/list_changed", tools)),
+            1
+        );
+    }
+
+    client.cancel().await?;
+    server.cancel().await?;
+    Ok(())
+}
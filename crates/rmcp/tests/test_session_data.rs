@@ -0,0 +1,139 @@
+//cargo test --test test_session_data --features "client server macros"
+
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    Peer, RoleClient, RoleServer, ServerHandler, ServiceExt,
+    handler::server::tool::Session,
+    model::CallToolRequestParam,
+    service::{RunningService, SessionData},
+    tool,
+};
+
+#[derive(Debug, Clone)]
+struct Identity(String);
+
+#[derive(Debug, Clone, Default)]
+struct Calls(Arc<AtomicUsize>);
+
+#[derive(Debug, Clone, Default)]
+pub struct AccountServer {
+    disconnected: Arc<Mutex<Vec<Option<String>>>>,
+}
+
+#[tool(tool_box, default_build = false)]
+impl AccountServer {
+    #[tool(description = "Log in for the rest of the session")]
+    async fn login(&self, peer: Peer<RoleServer>, #[tool(param)] name: String) -> String {
+        peer.session().insert(Identity(name.clone()));
+        name
+    }
+
+    #[tool(description = "Tell who is logged in")]
+    async fn whoami(&self, identity: Session<Identity>) -> String {
+        identity.0.0
+    }
+
+    #[tool(description = "Count the calls of the session")]
+    async fn count(&self, calls: Session<Calls>) -> String {
+        (calls.0.0.fetch_add(1, Ordering::SeqCst) + 1).to_string()
+    }
+}
+
+#[tool(tool_box)]
+impl ServerHandler for AccountServer {
+    async fn on_session_initialized(&self, session: SessionData) {
+        session.insert(Calls::default());
+    }
+
+    async fn on_disconnect(&self, session: SessionData) {
+        let identity = session.get::<Identity>().map(|identity| identity.0);
+        self.disconnected.lock().unwrap().push(identity);
+    }
+}
+
+async fn connect(server: AccountServer) -> anyhow::Result<RunningService<RoleClient, ()>> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    Ok(().serve(client_transport).await?)
+}
+
+async fn call(
+    client: &RunningService<RoleClient, ()>,
+    name: &'static str,
+    arguments: Option<rmcp::model::JsonObject>,
+) -> anyhow::Result<String> {
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: name.into(),
+            arguments: arguments.map(Into::into),
+        })
+        .await?;
+    Ok(result.content[0].as_text().unwrap().text.clone())
+}
+
+async fn login(client: &RunningService<RoleClient, ()>, name: &str) -> anyhow::Result<String> {
+    call(client, "login", Some(rmcp::object!({ "name": name }))).await
+}
+
+#[tokio::test]
+async fn test_sessions_do_not_share_data() -> anyhow::Result<()> {
+    let server = AccountServer::default();
+    let (alice, bob) = tokio::try_join!(connect(server.clone()), connect(server.clone()))?;
+
+    // nobody is logged in yet
+    assert!(call(&alice, "whoami", None).await.is_err());
+    tokio::try_join!(login(&alice, "alice"), login(&bob, "bob"))?;
+    let (alice_name, bob_name) =
+        tokio::try_join!(call(&alice, "whoami", None), call(&bob, "whoami", None))?;
+    assert_eq!(alice_name, "alice");
+    assert_eq!(bob_name, "bob");
+
+    // the values set up when initialized are per session too
+    for expected in ["1", "2", "3"] {
+        assert_eq!(call(&alice, "count", None).await?, expected);
+    }
+    assert_eq!(call(&bob, "count", None).await?, "1");
+
+    alice.cancel().await?;
+    tokio::time::timeout(Duration::from_secs(1), async {
+        while server.disconnected.lock().unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+    assert_eq!(
+        *server.disconnected.lock().unwrap(),
+        [Some("alice".to_owned())]
+    );
+    assert_eq!(call(&bob, "whoami", None).await?, "bob");
+    bob.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_data_dropped_on_disconnect() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let (server, client) = tokio::join!(
+        AccountServer::default().serve(server_transport),
+        ().serve(client_transport)
+    );
+    let (server, client) = (server?, client?);
+    login(&client, "carol").await?;
+    let session = server.peer().session().clone();
+    assert!(session.contains::<Identity>());
+
+    client.cancel().await?;
+    server.waiting().await?;
+    assert!(session.is_empty());
+    Ok(())
+}
@@ -0,0 +1,209 @@
+//cargo test --test test_sse_server --features "server macros transport-sse-server __reqwest"
+use std::{net::SocketAddr, time::Duration};
+
+use reqwest::{Method, StatusCode, header};
+use rmcp::{
+    tool,
+    transport::{SseServer, sse_server::SseServerConfig},
+};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+const ORIGIN: &str = "http://localhost:3000";
+
+#[derive(Debug, Clone, Default)]
+pub struct Echo;
+
+#[tool(tool_box)]
+impl Echo {
+    #[tool(description = "Echo the text")]
+    async fn echo(&self, #[tool(param)] text: String) -> String {
+        text
+    }
+}
+
+/// Serve [`Echo`] with `config` on a local port, returning its address
+async fn serve(config: SseServerConfig) -> anyhow::Result<(SocketAddr, CancellationToken)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let (server, router) = SseServer::new(SseServerConfig {
+        bind: addr,
+        ..config
+    });
+    let ct = server.with_service(|| Echo);
+    let shutdown = ct.clone();
+    tokio::spawn(async move {
+        axum::serve(listener, router)
+            .with_graceful_shutdown(async move { shutdown.cancelled().await })
+            .await
+    });
+    Ok((addr, ct))
+}
+
+/// Open the event stream, returning it with the endpoint to post the messages to
+async fn open_session(addr: SocketAddr) -> anyhow::Result<(reqwest::Response, String)> {
+    let mut response = reqwest::get(format!("http://{addr}/sse"))
+        .await?
+        .error_for_status()?;
+    let mut received = String::new();
+    loop {
+        let chunk = response.chunk().await?.expect("the stream ends early");
+        received.push_str(std::str::from_utf8(&chunk)?);
+        if let Some(endpoint) = received
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+        {
+            return Ok((response, format!("http://{addr}{endpoint}")));
+        }
+    }
+}
+
+async fn initialize(client: &reqwest::Client, endpoint: &str) -> anyhow::Result<()> {
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": { "name": "test", "version": "0.0.0" }
+        }
+    });
+    let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+    for message in [initialize, initialized] {
+        let status = client.post(endpoint).json(&message).send().await?.status();
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+    Ok(())
+}
+
+fn ping(id: u32) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": "ping" })
+}
+
+#[tokio::test]
+async fn test_cors_preflight() -> anyhow::Result<()> {
+    let config = SseServerConfig::default().with_allowed_origins([ORIGIN.parse()?]);
+    let (addr, ct) = serve(config).await?;
+    let client = reqwest::Client::new();
+
+    let preflight = |origin: &'static str| {
+        client
+            .request(Method::OPTIONS, format!("http://{addr}/message"))
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+            .send()
+    };
+    let allowed = preflight(ORIGIN).await?;
+    assert!(allowed.status().is_success());
+    assert_eq!(
+        allowed.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        ORIGIN
+    );
+
+    let refused = preflight("http://elsewhere.example").await?;
+    assert!(
+        !refused
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+    );
+
+    let stream = client
+        .get(format!("http://{addr}/sse"))
+        .header(header::ORIGIN, ORIGIN)
+        .send()
+        .await?;
+    assert_eq!(
+        stream.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        ORIGIN
+    );
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_idles_out() -> anyhow::Result<()> {
+    let idle_timeout = Duration::from_millis(300);
+    let (addr, ct) = serve(SseServerConfig {
+        session_idle_timeout: Some(idle_timeout),
+        ..Default::default()
+    })
+    .await?;
+    let client = reqwest::Client::new();
+    let (mut stream, endpoint) = open_session(addr).await?;
+    initialize(&client, &endpoint).await?;
+
+    // the messages of the client keep the session alive
+    for id in 1..4 {
+        tokio::time::sleep(idle_timeout / 2).await;
+        let status = client
+            .post(&endpoint)
+            .json(&ping(id))
+            .send()
+            .await?
+            .status();
+        assert_eq!(status, StatusCode::ACCEPTED);
+    }
+
+    tokio::time::sleep(idle_timeout * 2).await;
+    let expired = client.post(&endpoint).json(&ping(4)).send().await?;
+    assert_eq!(expired.status(), StatusCode::NOT_FOUND);
+    let body: Value = expired.json().await?;
+    assert_eq!(body["jsonrpc"], "2.0");
+    assert_eq!(body["id"], 4);
+    assert_eq!(body["error"]["code"], -32600);
+
+    // the event stream of the expired session ends
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while stream.chunk().await?.is_some() {}
+        anyhow::Ok(())
+    })
+    .await??;
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unknown_session() -> anyhow::Result<()> {
+    let (addr, ct) = serve(SseServerConfig::default()).await?;
+    let response = reqwest::Client::new()
+        .post(format!("http://{addr}/message?sessionId=unknown"))
+        .json(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+        .send()
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body: Value = response.json().await?;
+    assert_eq!(body["id"], Value::Null);
+    assert_eq!(body["error"]["code"], -32600);
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_sessions() -> anyhow::Result<()> {
+    let (addr, ct) = serve(SseServerConfig {
+        max_sessions: Some(1),
+        ..Default::default()
+    })
+    .await?;
+    let (first, _) = open_session(addr).await?;
+    let refused = reqwest::get(format!("http://{addr}/sse")).await?;
+    assert_eq!(refused.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    // the slot is free again once the first client is gone
+    drop(first);
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let response = reqwest::get(format!("http://{addr}/sse")).await?;
+            if response.status().is_success() {
+                return anyhow::Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await??;
+    ct.cancel();
+    Ok(())
+}
@@ -0,0 +1,230 @@
+//cargo test --test test_streamable_http --features "client server macros transport-streamable-http-server transport-streamable-http-client __reqwest"
+
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rmcp::{
+    ServiceExt,
+    handler::server::tool::ToolProgress,
+    model::CallToolRequestParam,
+    service::PeerRequestOptions,
+    tool,
+    transport::{
+        StreamableHttpClientTransport, StreamableHttpServer,
+        streamable_http_server::axum::StreamableHttpServerConfig,
+    },
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::Notify,
+};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Clone, Default)]
+pub struct Uploader {
+    release: Arc<Notify>,
+}
+
+#[tool(tool_box)]
+impl Uploader {
+    #[tool(description = "Upload a file, halting halfway until released")]
+    async fn upload(&self, progress: ToolProgress, #[tool(param)] name: String) -> String {
+        progress
+            .report(50.0, Some(100.0), Some("halfway".into()))
+            .await
+            .expect("failed to report progress");
+        self.release.notified().await;
+        format!("uploaded {name}")
+    }
+}
+
+/// Serve `uploader` on a local port, returning its address
+async fn serve(uploader: Uploader) -> anyhow::Result<(SocketAddr, CancellationToken)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let (server, router) = StreamableHttpServer::new(StreamableHttpServerConfig {
+        bind: addr,
+        ..Default::default()
+    });
+    let ct = server.with_service(move || uploader.clone());
+    let shutdown = ct.clone();
+    tokio::spawn(async move {
+        axum::serve(listener, router)
+            .with_graceful_shutdown(async move { shutdown.cancelled().await })
+            .await
+    });
+    Ok((addr, ct))
+}
+
+/// Forwards tcp connections to the server, dropping the open ones on [`CuttingProxy::cut`]
+struct CuttingProxy {
+    addr: SocketAddr,
+    current: Arc<Mutex<CancellationToken>>,
+}
+
+impl CuttingProxy {
+    async fn start(upstream: SocketAddr) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let current = Arc::new(Mutex::new(CancellationToken::new()));
+        let connections = current.clone();
+        tokio::spawn(async move {
+            while let Ok((mut inbound, _)) = listener.accept().await {
+                let ct = connections.lock().unwrap().clone();
+                tokio::spawn(async move {
+                    let mut outbound = TcpStream::connect(upstream).await?;
+                    tokio::select! {
+                        _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound) => {}
+                        _ = ct.cancelled() => {}
+                    }
+                    std::io::Result::Ok(())
+                });
+            }
+        });
+        Ok(Self { addr, current })
+    }
+
+    fn cut(&self) {
+        std::mem::replace(&mut *self.current.lock().unwrap(), CancellationToken::new()).cancel();
+    }
+}
+
+fn upload(name: &str) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: "upload".into(),
+        arguments: serde_json::json!({ "name": name })
+            .as_object()
+            .cloned()
+            .map(Into::into),
+    }
+}
+
+#[tokio::test]
+async fn test_tool_call_resumes_after_reconnect() -> anyhow::Result<()> {
+    let uploader = Uploader::default();
+    let release = uploader.release.clone();
+    let (server_addr, ct) = serve(uploader).await?;
+    let proxy = CuttingProxy::start(server_addr).await?;
+    let client = ()
+        .serve(StreamableHttpClientTransport::from_uri(format!(
+            "http://{}/",
+            proxy.addr
+        )))
+        .await?;
+
+    let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+    let options = PeerRequestOptions::no_options().with_progress(move |progress| {
+        let _ = progress_tx.send(progress.progress);
+    });
+    let peer = client.peer().clone();
+    let call = tokio::spawn(async move {
+        peer.call_tool_with_options(upload("report.pdf"), options)
+            .await
+    });
+
+    // drop every connection mid-call, the response comes through the resumed stream
+    assert_eq!(progress_rx.recv().await, Some(50.0));
+    proxy.cut();
+    release.notify_one();
+    let result = tokio::time::timeout(Duration::from_secs(10), call).await???;
+    assert_eq!(
+        result.content[0].as_text().unwrap().text,
+        "uploaded report.pdf"
+    );
+    // the events received before the cut are not replayed
+    assert!(progress_rx.try_recv().is_err());
+
+    // and the session goes on
+    release.notify_one();
+    let result = client.call_tool(upload("notes.txt")).await?;
+    assert_eq!(
+        result.content[0].as_text().unwrap().text,
+        "uploaded notes.txt"
+    );
+
+    client.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_lifecycle() -> anyhow::Result<()> {
+    let (addr, ct) = serve(Uploader::default()).await?;
+    let uri = format!("http://{addr}/");
+    let http = reqwest::Client::new();
+    let post = |body: serde_json::Value| {
+        http.post(&uri)
+            .header("Accept", "application/json, text/event-stream")
+            .json(&body)
+    };
+
+    // the initialize response is plain json, with the id of the new session
+    let response = post(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": { "name": "raw-http", "version": "0.0.0" },
+        },
+    }))
+    .send()
+    .await?;
+    assert_eq!(response.status(), 200);
+    let session_id = response
+        .headers()
+        .get("Mcp-Session-Id")
+        .expect("missing session id")
+        .to_str()?
+        .to_string();
+    let initialized: serde_json::Value = response.json().await?;
+    assert_eq!(initialized["id"], 1);
+
+    let response = post(serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/initialized",
+    }))
+    .header("Mcp-Session-Id", &session_id)
+    .send()
+    .await?;
+    assert_eq!(response.status(), 202);
+
+    // the response of a request is streamed on its own post, which ends with it
+    let response = post(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/list",
+    }))
+    .header("Mcp-Session-Id", &session_id)
+    .send()
+    .await?;
+    assert_eq!(
+        response.headers()["content-type"].to_str()?,
+        "text/event-stream"
+    );
+    let events = tokio::time::timeout(Duration::from_secs(5), response.text()).await??;
+    assert!(events.contains("0/0"));
+    assert!(events.contains(r#""name":"upload""#));
+
+    // tearing the session down
+    let response = http
+        .delete(&uri)
+        .header("Mcp-Session-Id", &session_id)
+        .send()
+        .await?;
+    assert_eq!(response.status(), 202);
+    let response = http
+        .get(&uri)
+        .header("Accept", "text/event-stream")
+        .header("Mcp-Session-Id", &session_id)
+        .send()
+        .await?;
+    assert_eq!(response.status(), 404);
+
+    ct.cancel();
+    Ok(())
+}
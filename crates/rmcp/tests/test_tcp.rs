@@ -0,0 +1,70 @@
+//cargo test --test test_tcp --features "client server macros transport-tcp"
+use std::sync::{
+    Arc,
+    atomic::{AtomicU32, Ordering},
+};
+
+use rmcp::{
+    ServiceExt,
+    model::CallToolRequestParam,
+    tool,
+    transport::tcp::{self, TcpServer},
+};
+
+/// A counter of its own for every connection
+#[derive(Debug, Clone, Default)]
+pub struct Counter {
+    count: Arc<AtomicU32>,
+}
+
+#[tool(tool_box)]
+impl Counter {
+    #[tool(description = "Increment the counter, returning its value")]
+    async fn increment(&self) -> String {
+        (self.count.fetch_add(1, Ordering::SeqCst) + 1).to_string()
+    }
+}
+
+fn increment() -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: "increment".into(),
+        arguments: None,
+    }
+}
+
+async fn call_increment(
+    client: &rmcp::service::RunningService<rmcp::RoleClient, ()>,
+) -> anyhow::Result<String> {
+    let result = client.call_tool(increment()).await?;
+    Ok(result.content[0].as_text().unwrap().text.clone())
+}
+
+#[tokio::test]
+async fn test_plaintext_round_trip() -> anyhow::Result<()> {
+    let server = TcpServer::bind("127.0.0.1:0".parse()?).await?;
+    let addr = server.local_addr()?;
+    let ct = server.with_service(Counter::default);
+
+    let first = ().serve(tcp::connect_client(addr).await?).await?;
+    let second = ().serve(tcp::connect_client(addr).await?).await?;
+    assert_eq!(first.list_all_tools().await?.len(), 1);
+    assert_eq!(call_increment(&first).await?, "1");
+    assert_eq!(call_increment(&first).await?, "2");
+    // every connection has a counter of its own
+    assert_eq!(call_increment(&second).await?, "1");
+
+    first.cancel().await?;
+    second.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_connect_refused() -> anyhow::Result<()> {
+    let server = TcpServer::bind("127.0.0.1:0".parse()?).await?;
+    let addr = server.local_addr()?;
+    drop(server);
+    let error = tcp::connect_client(addr).await.unwrap_err();
+    assert!(matches!(error, tcp::TcpTransportError::Io(_)));
+    Ok(())
+}
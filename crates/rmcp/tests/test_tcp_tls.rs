@@ -0,0 +1,133 @@
+//cargo test --test test_tcp_tls --features "client server macros tls"
+use std::net::SocketAddr;
+
+use rcgen::CertifiedKey;
+use rmcp::{
+    ServiceExt,
+    model::CallToolRequestParam,
+    tool,
+    transport::tcp::{
+        self, TcpServer, TcpServerConfig, TcpTransportError,
+        tls::{TlsClientBuilder, TlsServerBuilder, TlsServerConfig},
+    },
+};
+use rustls::{RootCertStore, pki_types::CertificateDer};
+
+#[derive(Debug, Clone, Default)]
+pub struct Echo;
+
+#[tool(tool_box)]
+impl Echo {
+    #[tool(description = "Echo the text")]
+    async fn echo(&self, #[tool(param)] text: String) -> String {
+        text
+    }
+}
+
+fn self_signed(name: &str) -> CertifiedKey {
+    rcgen::generate_simple_self_signed(vec![name.to_owned()]).expect("generate certificate")
+}
+
+async fn serve(tls: TlsServerConfig) -> anyhow::Result<SocketAddr> {
+    let config = TcpServerConfig::new("127.0.0.1:0".parse()?).with_tls(tls);
+    let server = TcpServer::bind_with_config(config).await?;
+    let addr = server.local_addr()?;
+    server.with_service(Echo::default);
+    Ok(addr)
+}
+
+fn server_tls(server: &CertifiedKey) -> anyhow::Result<TlsServerBuilder> {
+    Ok(TlsServerBuilder::from_pem(
+        server.cert.pem().as_bytes(),
+        server.key_pair.serialize_pem().as_bytes(),
+    )?)
+}
+
+async fn assert_echo(connection: tcp::TcpConnection) -> anyhow::Result<()> {
+    let client = ().serve(connection).await?;
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "echo".into(),
+            arguments: serde_json::json!({ "text": "over tls" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "over tls");
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tls_without_verification() -> anyhow::Result<()> {
+    let server = self_signed("localhost");
+    let addr = serve(server_tls(&server)?.build()?).await?;
+
+    let tls = TlsClientBuilder::new()
+        .danger_accept_invalid_certs()
+        .build()?;
+    assert_echo(tcp::connect_client_tls(addr, "localhost", &tls).await?).await
+}
+
+#[tokio::test]
+async fn test_tls_with_verification() -> anyhow::Result<()> {
+    let server = self_signed("localhost");
+    let addr = serve(server_tls(&server)?.build()?).await?;
+
+    let tls = TlsClientBuilder::new()
+        .with_root_pem(server.cert.pem().as_bytes())?
+        .build()?;
+    assert_echo(tcp::connect_client_tls(addr, "localhost", &tls).await?).await?;
+
+    // the certificate doesn't name the server
+    let error = tcp::connect_client_tls(addr, "example.com", &tls)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, TcpTransportError::Certificate(_)),
+        "{error}"
+    );
+
+    // the certificate isn't trusted
+    let untrusted = TlsClientBuilder::new()
+        .with_root_pem(self_signed("localhost").cert.pem().as_bytes())?
+        .build()?;
+    let error = tcp::connect_client_tls(addr, "localhost", &untrusted)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, TcpTransportError::Certificate(_)),
+        "{error}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tls_client_auth() -> anyhow::Result<()> {
+    let server = self_signed("localhost");
+    let client = self_signed("client");
+    let mut client_roots = RootCertStore::empty();
+    client_roots.add(CertificateDer::from(client.cert.der().to_vec()))?;
+    let addr = serve(
+        server_tls(&server)?
+            .with_client_auth(client_roots)
+            .build()?,
+    )
+    .await?;
+
+    let tls = TlsClientBuilder::new()
+        .with_root_pem(server.cert.pem().as_bytes())?
+        .with_client_auth_pem(
+            client.cert.pem().as_bytes(),
+            client.key_pair.serialize_pem().as_bytes(),
+        )?
+        .build()?;
+    assert_echo(tcp::connect_client_tls(addr, "localhost", &tls).await?).await
+}
+
+#[tokio::test]
+async fn test_invalid_pem() {
+    let error = TlsServerBuilder::from_pem(b"not a certificate", b"not a key").unwrap_err();
+    assert!(matches!(error, TcpTransportError::Pem(_)), "{error}");
+}
@@ -0,0 +1,122 @@
+//cargo test --test test_tool_cancellation --features "client server macros"
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    RoleClient, ServiceExt,
+    model::{CallToolRequestMethod, CallToolRequestParam, ClientRequest, Request},
+    service::{PeerRequestOptions, RequestHandle, RunningService},
+    tool,
+};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Clone, Default)]
+pub struct SleepServer {
+    started: Arc<Notify>,
+    observed_cancel: Arc<AtomicBool>,
+    dropped: Arc<AtomicBool>,
+}
+
+/// Sets the flag when the tool's future is dropped
+struct DropFlag(Arc<AtomicBool>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[tool(tool_box)]
+impl SleepServer {
+    #[tool(description = "Sleep until done or cancelled")]
+    async fn sleep(&self, ct: CancellationToken) -> String {
+        self.started.notify_one();
+        tokio::select! {
+            _ = ct.cancelled() => {
+                self.observed_cancel.store(true, Ordering::SeqCst);
+                "cancelled".into()
+            }
+            _ = tokio::time::sleep(Duration::from_secs(30)) => "done".into(),
+        }
+    }
+
+    #[tool(description = "Sleep ignoring cancellation", timeout_ms = 100)]
+    async fn stubborn_sleep(&self) -> String {
+        let _flag = DropFlag(self.dropped.clone());
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        "done".into()
+    }
+}
+
+async fn start_call(
+    client: &RunningService<RoleClient, ()>,
+    name: &'static str,
+) -> anyhow::Result<RequestHandle<RoleClient>> {
+    let request = ClientRequest::CallToolRequest(Request {
+        method: CallToolRequestMethod,
+        params: CallToolRequestParam {
+            name: name.into(),
+            arguments: None,
+        },
+        extensions: Default::default(),
+    });
+    Ok(client
+        .send_request_with_option(request, PeerRequestOptions::no_options())
+        .await?)
+}
+
+async fn wait_for(flag: &AtomicBool) -> anyhow::Result<()> {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !flag.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancel_sleeping_tool() -> anyhow::Result<()> {
+    let server = SleepServer::default();
+    let (started, observed_cancel, dropped) = (
+        server.started.clone(),
+        server.observed_cancel.clone(),
+        server.dropped.clone(),
+    );
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+
+    // a tool selecting on its token stops early
+    let handle = start_call(&client, "sleep").await?;
+    started.notified().await;
+    handle.cancel(Some("no longer needed".into())).await?;
+    wait_for(&observed_cancel).await?;
+
+    // a tool ignoring its token is dropped cleanly by its timeout
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "stubborn_sleep".into(),
+            arguments: None,
+        })
+        .await;
+    assert!(result.is_err());
+    wait_for(&dropped).await?;
+
+    // the server keeps serving other calls
+    let tools = client.list_all_tools().await?;
+    assert_eq!(tools.len(), 2);
+
+    client.cancel().await?;
+    Ok(())
+}
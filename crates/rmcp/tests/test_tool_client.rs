@@ -0,0 +1,148 @@
+//cargo test --test test_tool_client --features "client server macros"
+
+use rmcp::{
+    RoleClient, ServiceExt,
+    handler::server::wrapper::Json,
+    service::{RunningService, ToolCallError},
+    tool, tool_client,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SumRequest {
+    pub a: i64,
+    pub b: i64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Sum {
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Calculator;
+
+#[tool(tool_box)]
+impl Calculator {
+    /// Sum two numbers
+    #[tool]
+    async fn sum(&self, #[tool(aggr)] request: SumRequest) -> Json<Sum> {
+        Json(Sum {
+            total: request.a + request.b,
+        })
+    }
+
+    /// Scale a sum
+    #[tool(name = "scale_sum", rename_all = "camelCase")]
+    async fn scale(
+        &self,
+        #[tool(aggr)] request: SumRequest,
+        scale_factor: i64,
+        #[serde(rename = "off")] offset: Option<i64>,
+    ) -> Json<Sum> {
+        Json(Sum {
+            total: (request.a + request.b) * scale_factor + offset.unwrap_or_default(),
+        })
+    }
+
+    /// Count to three
+    #[tool]
+    async fn three(&self) -> String {
+        "3".into()
+    }
+
+    /// Greet someone
+    #[tool]
+    async fn hello(&self, name: String) -> String {
+        format!("hello {name}")
+    }
+
+    /// Fail
+    #[tool]
+    async fn fail(&self) -> Result<String, String> {
+        Err("failed on purpose".into())
+    }
+}
+
+#[tool_client]
+pub trait CalculatorClient {
+    /// Sum two numbers
+    async fn sum(&self, #[tool(aggr)] request: SumRequest) -> Sum;
+
+    /// Scale a sum
+    #[tool(name = "scale_sum", rename_all = "camelCase")]
+    async fn scale(
+        &self,
+        #[tool(aggr)] request: SumRequest,
+        scale_factor: i64,
+        #[serde(rename = "off")] offset: Option<i64>,
+    ) -> Sum;
+
+    /// Count to three
+    async fn three(&self) -> u8;
+
+    /// Greet someone, the text isn't json
+    async fn hello(&self, name: String) -> String;
+
+    /// Fail
+    async fn fail(&self) -> String;
+
+    /// Not a tool of the server
+    async fn missing(&self);
+}
+
+async fn connect() -> anyhow::Result<RunningService<RoleClient, ()>> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        Calculator.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    Ok(().serve(client_transport).await?)
+}
+
+#[tokio::test]
+async fn test_tool_client_round_trip() -> anyhow::Result<()> {
+    let client = connect().await?;
+
+    let sum = client.sum(SumRequest { a: 1, b: 2 }).await?;
+    assert_eq!(sum, Sum { total: 3 });
+
+    let scaled = client.scale(SumRequest { a: 1, b: 2 }, 10, Some(4)).await?;
+    assert_eq!(scaled, Sum { total: 34 });
+    let scaled = client.scale(SumRequest { a: 1, b: 2 }, 2, None).await?;
+    assert_eq!(scaled, Sum { total: 6 });
+
+    assert_eq!(client.three().await?, 3);
+
+    // the trait is implemented for the peer itself
+    let peer = client.peer().clone();
+    assert_eq!(peer.sum(SumRequest { a: 2, b: 2 }).await?, Sum { total: 4 });
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tool_client_errors() -> anyhow::Result<()> {
+    let client = connect().await?;
+
+    // a mismatched result carries the raw payload
+    let result = client.hello("world".into()).await;
+    let Err(ToolCallError::Deserialize { result, .. }) = result else {
+        panic!("expected a deserialization error, got {result:?}");
+    };
+    assert_eq!(result.content[0].as_text().unwrap().text, "hello world");
+
+    let result = client.fail().await;
+    let Err(ToolCallError::Tool { text, .. }) = result else {
+        panic!("expected a tool error, got {result:?}");
+    };
+    assert_eq!(text, "failed on purpose");
+
+    let result = client.missing().await;
+    assert!(matches!(result, Err(ToolCallError::Service(_))));
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,175 @@
+//cargo test --test test_tool_concurrency --features "client server macros"
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    RoleClient, ServerHandler, ServiceExt,
+    handler::server::tool::{ConcurrencyPolicy, ToolConcurrency},
+    model::{CallToolRequestMethod, CallToolRequestParam, ClientRequest, Request},
+    service::{PeerRequestOptions, RunningService, ServiceError},
+    tool,
+};
+
+#[derive(Debug, Default)]
+struct Gauge {
+    running: AtomicUsize,
+    max: AtomicUsize,
+    calls: AtomicUsize,
+}
+
+impl Gauge {
+    async fn measure(&self) {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseServer {
+    query: Arc<Gauge>,
+    limited: Arc<Gauge>,
+    report: Arc<Gauge>,
+}
+
+#[tool(tool_box)]
+impl DatabaseServer {
+    #[tool(description = "Query the database")]
+    async fn query(&self) -> String {
+        self.query.measure().await;
+        "done".into()
+    }
+
+    #[tool(description = "Query the database, two at a time", max_concurrent = 2)]
+    async fn limited(&self) -> String {
+        self.limited.measure().await;
+        "done".into()
+    }
+
+    // the limits of the tools are shared by every server, the tests use one tool each
+    #[tool(description = "Build a report, two at a time", max_concurrent = 2)]
+    async fn report(&self) -> String {
+        self.report.measure().await;
+        "done".into()
+    }
+}
+
+async fn serve(
+    server: impl rmcp::Service<rmcp::RoleServer>,
+) -> anyhow::Result<RunningService<RoleClient, ()>> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    Ok(().serve(client_transport).await?)
+}
+
+fn call_param(name: &'static str) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: None,
+    }
+}
+
+async fn burst(
+    client: &RunningService<RoleClient, ()>,
+    name: &'static str,
+    calls: usize,
+) -> Vec<Result<(), ServiceError>> {
+    let calls = (0..calls).map(|_| async { client.call_tool(call_param(name)).await.map(drop) });
+    futures::future::join_all(calls).await
+}
+
+#[tokio::test]
+async fn test_server_limit_queues_calls() -> anyhow::Result<()> {
+    let server = DatabaseServer::default();
+    let query = server.query.clone();
+    let client = serve(server.with_tool_concurrency(ToolConcurrency::new(4))).await?;
+
+    let results = burst(&client, "query", 32).await;
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(query.calls.load(Ordering::SeqCst), 32);
+    assert_eq!(query.max.load(Ordering::SeqCst), 4);
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tool_limit_queues_calls() -> anyhow::Result<()> {
+    let server = DatabaseServer::default();
+    let limited = server.limited.clone();
+    let client = serve(server).await?;
+
+    let results = burst(&client, "limited", 16).await;
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(limited.calls.load(Ordering::SeqCst), 16);
+    assert_eq!(limited.max.load(Ordering::SeqCst), 2);
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reject_policy_fails_fast() -> anyhow::Result<()> {
+    let server = DatabaseServer::default();
+    let report = server.report.clone();
+    let client = serve(server.with_tool_concurrency(
+        ToolConcurrency::unlimited().with_policy(ConcurrencyPolicy::Reject),
+    ))
+    .await?;
+
+    let results = burst(&client, "report", 8).await;
+    let rejected = results
+        .iter()
+        .filter(|result| match result {
+            Err(ServiceError::McpError(error)) => error.message == "too many concurrent tool calls",
+            _ => false,
+        })
+        .count();
+    assert!(rejected > 0);
+    assert_eq!(rejected + report.calls.load(Ordering::SeqCst), 8);
+    assert_eq!(report.max.load(Ordering::SeqCst), 2);
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancelled_queued_call_never_runs() -> anyhow::Result<()> {
+    let server = DatabaseServer::default();
+    let query = server.query.clone();
+    let client = serve(server.with_tool_concurrency(ToolConcurrency::new(1))).await?;
+
+    let running = {
+        let peer = client.peer().clone();
+        tokio::spawn(async move { peer.call_tool(call_param("query")).await })
+    };
+    while query.calls.load(Ordering::SeqCst) == 0 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    let queued = client
+        .send_cancellable_request(
+            ClientRequest::CallToolRequest(Request {
+                method: CallToolRequestMethod,
+                params: call_param("query"),
+                extensions: Default::default(),
+            }),
+            PeerRequestOptions::no_options(),
+        )
+        .await?;
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    queued.cancel(Some("no longer needed".into())).await?;
+
+    running.await??;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(query.calls.load(Ordering::SeqCst), 1);
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,154 @@
+//cargo test --test test_tool_deprecated --features "client server"
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use rmcp::{
+    ClientHandler, Peer, RoleClient, ServerHandler, ServiceExt,
+    model::{
+        CallToolRequestParam, ClientInfo, LoggingLevel, LoggingMessageNotificationParam,
+        ServerCapabilities, ServerInfo,
+    },
+    tool,
+};
+use tokio::sync::Notify;
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchServer {
+    info_calls: Arc<AtomicUsize>,
+}
+
+#[tool(tool_box, default_build = false)]
+impl SearchServer {
+    #[tool(description = "Search documents", deprecated = "use search_v2 instead")]
+    async fn search(&self, #[tool(param)] query: String) -> String {
+        format!("v1: {query}")
+    }
+
+    #[tool(description = "Search documents")]
+    async fn search_v2(&self, #[tool(param)] query: String) -> String {
+        format!("v2: {query}")
+    }
+}
+
+#[tool(tool_box)]
+impl ServerHandler for SearchServer {
+    fn get_info(&self) -> ServerInfo {
+        self.info_calls.fetch_add(1, Ordering::SeqCst);
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_logging()
+                .enable_tools()
+                .build(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct LoggingClient {
+    messages: Arc<Mutex<Vec<LoggingMessageNotificationParam>>>,
+    received: Arc<Notify>,
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl ClientHandler for LoggingClient {
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo::default()
+    }
+
+    async fn on_logging_message(&self, params: LoggingMessageNotificationParam) {
+        self.messages.lock().unwrap().push(params);
+        self.received.notify_one();
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+}
+
+#[test]
+fn test_deprecated_tool_annotations() {
+    let tool = SearchServer::search_tool_attr();
+    assert_eq!(
+        tool.annotations.unwrap().deprecated.as_deref(),
+        Some("use search_v2 instead")
+    );
+    assert!(SearchServer::search_v2_tool_attr().annotations.is_none());
+}
+
+#[tokio::test]
+async fn test_deprecated_tool_warning() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server = SearchServer::default();
+    let info_calls = server.info_calls.clone();
+    let server_handle = tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+
+    let handler = LoggingClient::default();
+    let messages = handler.messages.clone();
+    let received = handler.received.clone();
+    let client = handler.serve(client_transport).await?;
+
+    let tools = client.list_all_tools().await?;
+    let search = tools.iter().find(|tool| tool.name == "search").unwrap();
+    assert_eq!(
+        search.annotations.as_ref().unwrap().deprecated.as_deref(),
+        Some("use search_v2 instead")
+    );
+    let info_calls_before = info_calls.load(Ordering::SeqCst);
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "search".into(),
+            arguments: serde_json::json!({ "query": "rust" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "v1: rust");
+
+    // notifications may be handled after the response
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while messages.lock().unwrap().is_empty() {
+            received.notified().await;
+        }
+    })
+    .await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "search_v2".into(),
+            arguments: serde_json::json!({ "query": "rust" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "v2: rust");
+
+    // the logging capability is looked up for the first call only
+    assert_eq!(info_calls.load(Ordering::SeqCst), info_calls_before + 1);
+
+    let messages = std::mem::take(&mut *messages.lock().unwrap());
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].level, LoggingLevel::Warning);
+    assert_eq!(messages[0].data["tool"], "search");
+    assert_eq!(
+        messages[0].data["message"],
+        "tool `search` is deprecated: use search_v2 instead"
+    );
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//cargo test --test test_tool_empty_arguments --features "server macros"
+
+use rmcp::{ServiceExt, model::EmptyObject, tool};
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+#[derive(Debug, Clone, Default)]
+pub struct PingServer;
+
+#[tool(tool_box)]
+impl PingServer {
+    #[tool(description = "Answer with pong")]
+    async fn ping(&self) -> String {
+        "pong".to_string()
+    }
+
+    #[tool(description = "Greet someone, or everyone")]
+    async fn greet(&self, #[tool(param)] name: Option<String>) -> String {
+        format!("hello {}", name.as_deref().unwrap_or("everyone"))
+    }
+
+    #[tool(description = "Answer with pong, taking an empty object")]
+    async fn empty(&self, #[tool(aggr)] _empty: EmptyObject) -> String {
+        "pong".to_string()
+    }
+}
+
+/// A client writing raw messages, like the clients which send `"arguments": null`
+struct RawClient {
+    reader: BufReader<tokio::io::ReadHalf<tokio::io::DuplexStream>>,
+    writer: tokio::io::WriteHalf<tokio::io::DuplexStream>,
+}
+
+impl RawClient {
+    async fn connect() -> anyhow::Result<Self> {
+        let (server_transport, client_transport) = tokio::io::duplex(4096);
+        tokio::spawn(async move {
+            PingServer.serve(server_transport).await?.waiting().await?;
+            anyhow::Ok(())
+        });
+        let (reader, writer) = tokio::io::split(client_transport);
+        let mut client = RawClient {
+            reader: BufReader::new(reader),
+            writer,
+        };
+        client
+            .request(json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": {},
+                    "clientInfo": { "name": "raw", "version": "1.0" }
+                }
+            }))
+            .await?;
+        client
+            .send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await?;
+        Ok(client)
+    }
+
+    async fn send(&mut self, message: Value) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(&message)?;
+        line.push(b'\n');
+        self.writer.write_all(&line).await?;
+        Ok(())
+    }
+
+    async fn request(&mut self, message: Value) -> anyhow::Result<Value> {
+        self.send(message).await?;
+        let mut line = String::new();
+        self.reader.read_line(&mut line).await?;
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    /// The text of the result of calling `name` with `params` merged into the request params
+    async fn call(&mut self, id: u32, name: &str, params: Value) -> anyhow::Result<String> {
+        let mut request_params = json!({ "name": name });
+        request_params
+            .as_object_mut()
+            .unwrap()
+            .extend(params.as_object().unwrap().clone());
+        let response = self
+            .request(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": "tools/call",
+                "params": request_params
+            }))
+            .await?;
+        let result = &response["result"];
+        assert_eq!(result["isError"], false, "{response}");
+        Ok(result["content"][0]["text"].as_str().unwrap().to_owned())
+    }
+}
+
+#[tokio::test]
+async fn test_no_param_tool_arguments() -> anyhow::Result<()> {
+    let mut client = RawClient::connect().await?;
+    let shapes = [
+        json!({}),
+        json!({ "arguments": null }),
+        json!({ "arguments": {} }),
+        json!({ "arguments": { "unexpected": true } }),
+    ];
+    for (id, shape) in (1..).zip(shapes) {
+        assert_eq!(client.call(id, "ping", shape.clone()).await?, "pong");
+        assert_eq!(client.call(id + 100, "empty", shape).await?, "pong");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_optional_param_tool_arguments() -> anyhow::Result<()> {
+    let mut client = RawClient::connect().await?;
+    assert_eq!(client.call(1, "greet", json!({})).await?, "hello everyone");
+    assert_eq!(
+        client
+            .call(2, "greet", json!({ "arguments": null }))
+            .await?,
+        "hello everyone"
+    );
+    assert_eq!(
+        client
+            .call(3, "greet", json!({ "arguments": { "name": "ferris" } }))
+            .await?,
+        "hello ferris"
+    );
+    Ok(())
+}
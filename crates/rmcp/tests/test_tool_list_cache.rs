@@ -0,0 +1,106 @@
+//cargo test --test test_tool_list_cache --features "client server macros"
+use std::sync::Arc;
+
+use rmcp::{
+    ServiceExt,
+    handler::server::tool::{cached_schema_for_type, cached_tools},
+    schemars, tool,
+};
+use serde::{Deserialize, de::DeserializeOwned};
+
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct Celsius {
+    pub degrees: f64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct Fahrenheit {
+    pub degrees: f64,
+    pub rounded: bool,
+}
+
+/// A generic server lists its tools with the generated `list_tools_inner`
+#[derive(Debug, Clone, Default)]
+pub struct StoreServer<T> {
+    _value: std::marker::PhantomData<fn() -> T>,
+}
+
+#[tool(tool_box)]
+impl<T> StoreServer<T>
+where
+    T: schemars::JsonSchema + DeserializeOwned + std::fmt::Debug + Send + Sync + 'static,
+{
+    /// Store a value
+    #[tool]
+    async fn store(&self, #[tool(aggr)] value: T) -> String {
+        format!("{value:?}")
+    }
+
+    #[tool(description = "Forget every value")]
+    async fn clear(&self) -> String {
+        "cleared".into()
+    }
+}
+
+#[tokio::test]
+async fn test_generic_server_tools_are_built_once() -> anyhow::Result<()> {
+    for _ in 0..2 {
+        let (server_transport, client_transport) = tokio::io::duplex(4096);
+        tokio::spawn(async move {
+            StoreServer::<Celsius>::default()
+                .serve(server_transport)
+                .await?
+                .waiting()
+                .await?;
+            anyhow::Ok(())
+        });
+        let client = ().serve(client_transport).await?;
+        let mut tools = client.list_all_tools().await?;
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(tools[0].name, "clear");
+        assert_eq!(tools[1].name, "store");
+        assert_eq!(tools[1].description.as_deref(), Some("Store a value"));
+        assert_eq!(*tools[1].input_schema, *cached_schema_for_type::<Celsius>());
+        client.cancel().await?;
+    }
+
+    // the tools of the server were built by the first listing
+    let tools = cached_tools::<StoreServer<Celsius>>(|| unreachable!("the tools are cached"));
+    let store = tools
+        .iter()
+        .find(|tool| tool.name == "store")
+        .expect("the store tool");
+    assert!(Arc::ptr_eq(
+        &store.input_schema,
+        &cached_schema_for_type::<Celsius>()
+    ));
+    // borrowed from the doc comment
+    assert!(matches!(
+        store.description,
+        Some(std::borrow::Cow::Borrowed("Store a value"))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_generic_server_tools_by_type() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        StoreServer::<Fahrenheit>::default()
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+    let tools = client.list_all_tools().await?;
+    let store = tools
+        .iter()
+        .find(|tool| tool.name == "store")
+        .expect("the store tool");
+    // not the tools of `StoreServer<Celsius>`
+    assert_eq!(*store.input_schema, *cached_schema_for_type::<Fahrenheit>());
+    client.cancel().await?;
+    Ok(())
+}
@@ -56,4 +56,44 @@ mod tests {
         assert_eq!(annotations.title.as_ref().unwrap(), "Annotated Tool");
         assert_eq!(annotations.read_only_hint, Some(true));
     }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct FileServer {}
+
+    // only the tool attributes are checked
+    #[allow(dead_code)]
+    impl FileServer {
+        #[tool(
+            description = "Read a file",
+            title = "Read File",
+            icon = "https://example.com/file.svg",
+            icon = "data:image/png;base64,iVBORw0KGgo="
+        )]
+        pub async fn read_file(&self, #[tool(param)] path: String) -> String {
+            path
+        }
+    }
+
+    #[test]
+    fn test_title_and_icons() {
+        let tool = FileServer::read_file_tool_attr();
+        assert_eq!(tool.title.as_deref(), Some("Read File"));
+        assert_eq!(tool.display_title(), "Read File");
+
+        let value = serde_json::to_value(&tool).unwrap();
+        assert_eq!(value["title"], "Read File");
+        assert_eq!(
+            value["icons"],
+            serde_json::json!([
+                { "src": "https://example.com/file.svg" },
+                { "src": "data:image/png;base64,iVBORw0KGgo=" }
+            ])
+        );
+
+        // the tools without them serialize as before
+        let value =
+            serde_json::to_value(AnnotatedServer::direct_annotated_tool_tool_attr()).unwrap();
+        assert!(value.get("title").is_none());
+        assert!(value.get("icons").is_none());
+    }
 }
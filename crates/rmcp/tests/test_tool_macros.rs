@@ -4,7 +4,7 @@ use std::sync::Arc;
 
 use rmcp::{
     ClientHandler, Peer, RoleClient, ServerHandler, ServiceExt,
-    handler::server::tool::ToolCallContext,
+    handler::server::{tool::ToolCallContext, wrapper::Json},
     model::{CallToolRequestParam, ClientInfo},
     tool,
 };
@@ -26,6 +26,7 @@ impl ServerHandler for Server {
         let tcc = ToolCallContext::new(self, request, context);
         match tcc.name() {
             "get-weather" => Self::get_weather_tool_call(tcc).await,
+            "default_param" => Self::default_param_tool_call(tcc).await,
             _ => Err(rmcp::Error::invalid_params("method not found", None)),
         }
     }
@@ -48,6 +49,22 @@ impl Server {
     async fn optional_param(&self, #[tool(param)] city: Option<String>) -> String {
         city.unwrap_or_default()
     }
+
+    #[tool(description = "Described Parameter")]
+    async fn described_param(
+        &self,
+        #[tool(param, description = "number of items to fetch")] count: u32,
+        /// the city to query
+        #[tool(param)]
+        city: String,
+    ) -> String {
+        format!("{count} {city}")
+    }
+
+    #[tool(description = "Default Parameter")]
+    async fn default_param(&self, #[tool(param, default = "42")] limit: usize) -> String {
+        limit.to_string()
+    }
 }
 
 // define generic service trait
@@ -127,6 +144,41 @@ async fn test_tool_macros_with_optional_param() {
     assert_eq!(attr_type.as_str().unwrap(), "string");
 }
 
+#[tokio::test]
+async fn test_tool_macros_with_default_param() {
+    let _attr = Server::default_param_tool_attr();
+    let limit = _attr
+        .input_schema
+        .get("properties")
+        .unwrap()
+        .get("limit")
+        .unwrap();
+    assert_eq!(limit.get("default").and_then(|v| v.as_u64()), Some(42));
+    let required = _attr
+        .input_schema
+        .get("required")
+        .and_then(|required| required.as_array());
+    assert!(required.is_none_or(|required| !required.iter().any(|r| r.as_str() == Some("limit"))));
+}
+
+#[tokio::test]
+async fn test_tool_macros_with_described_param() {
+    let _attr = Server::described_param_tool_attr();
+    let properties = _attr.input_schema.get("properties").unwrap();
+    let description = |name: &str| {
+        properties
+            .get(name)
+            .and_then(|property| property.get("description"))
+            .and_then(|description| description.as_str())
+            .map(ToOwned::to_owned)
+    };
+    assert_eq!(
+        description("count").as_deref(),
+        Some("number of items to fetch")
+    );
+    assert_eq!(description("city").as_deref(), Some("the city to query"));
+}
+
 impl GetWeatherRequest {}
 
 // Struct defined for testing optional field schema generation
@@ -286,7 +338,8 @@ async fn test_optional_i64_field_with_null_input() -> anyhow::Result<()> {
                 })
                 .as_object()
                 .unwrap()
-                .clone(),
+                .clone()
+                .into(),
             ),
         })
         .await?;
@@ -314,7 +367,8 @@ async fn test_optional_i64_field_with_null_input() -> anyhow::Result<()> {
                 })
                 .as_object()
                 .unwrap()
-                .clone(),
+                .clone()
+                .into(),
             ),
         })
         .await?;
@@ -335,3 +389,1395 @@ async fn test_optional_i64_field_with_null_input() -> anyhow::Result<()> {
     server_handle.await??;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_default_param_with_empty_arguments() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+
+    let server = Server::default();
+    let server_handle = tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "default_param".into(),
+            arguments: Some(serde_json::Map::new().into()),
+        })
+        .await?;
+
+    let result_text = result
+        .content
+        .first()
+        .and_then(|content| content.raw.as_text())
+        .map(|text| text.text.as_str())
+        .expect("Expected text content");
+    assert_eq!(result_text, "42");
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WeatherReport {
+    pub city: String,
+    pub temperature: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OutputSchemaServer {}
+
+#[tool(tool_box)]
+impl OutputSchemaServer {
+    #[tool(description = "Get the weather report of a city")]
+    async fn weather_report(&self, #[tool(param)] city: String) -> Json<WeatherReport> {
+        Json(WeatherReport {
+            city,
+            temperature: 20.0,
+        })
+    }
+
+    #[tool(description = "Explicit output schema", output = WeatherReport)]
+    async fn explicit_output(&self) -> String {
+        "{}".to_string()
+    }
+
+    #[tool(description = "No output schema")]
+    async fn no_output(&self) -> String {
+        String::new()
+    }
+}
+
+#[tokio::test]
+async fn test_tool_output_schema() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+
+    let server = OutputSchemaServer::default();
+    let server_handle = tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let tools = client.list_all_tools().await?;
+    let tool_json = |name: &str| {
+        let tool = tools
+            .iter()
+            .find(|tool| tool.name == name)
+            .expect("tool should be listed");
+        serde_json::to_value(tool).expect("tool should be serializable")
+    };
+    for name in ["weather_report", "explicit_output"] {
+        let output_schema = tool_json(name)
+            .get("outputSchema")
+            .cloned()
+            .expect("outputSchema should be advertised");
+        assert!(output_schema["properties"].get("temperature").is_some());
+    }
+    assert!(tool_json("no_output").get("outputSchema").is_none());
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MutableCounter {
+    count: i32,
+}
+
+#[tool(tool_box, mutability = lock)]
+impl MutableCounter {
+    #[tool(description = "Increment the counter")]
+    fn increment(&mut self) -> String {
+        self.count += 1;
+        self.count.to_string()
+    }
+
+    #[tool(description = "Get the counter value")]
+    async fn get(&self) -> String {
+        self.count.to_string()
+    }
+
+    #[tool(description = "Get the counter value from a clone")]
+    fn snapshot(self) -> String {
+        self.count.to_string()
+    }
+}
+
+#[tokio::test]
+async fn test_mutable_tool_receiver() -> anyhow::Result<()> {
+    use rmcp::handler::server::tool::Locked;
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+
+    let server = Locked::new(MutableCounter::default());
+    let server_handle = tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let call = |name: &'static str| {
+        client.call_tool(CallToolRequestParam {
+            name: name.into(),
+            arguments: None,
+        })
+    };
+    let text = |result: rmcp::model::CallToolResult| {
+        result.content[0].as_text().map(|text| text.text.clone())
+    };
+    assert_eq!(text(call("increment").await?).as_deref(), Some("1"));
+    assert_eq!(text(call("increment").await?).as_deref(), Some("2"));
+    assert_eq!(text(call("get").await?).as_deref(), Some("2"));
+    assert_eq!(text(call("snapshot").await?).as_deref(), Some("2"));
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Pagination {
+    pub page: u32,
+    pub per_page: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FlattenServer;
+
+#[tool(tool_box)]
+impl FlattenServer {
+    #[tool(description = "Search with pagination")]
+    async fn search(
+        &self,
+        #[tool(aggr)] pagination: Pagination,
+        #[tool(param)] query: String,
+    ) -> String {
+        format!("{query}:{}:{}", pagination.page, pagination.per_page)
+    }
+
+    #[tool(description = "Param conflicting with a flattened field")]
+    async fn conflicting(
+        &self,
+        #[tool(aggr)] pagination: Pagination,
+        #[tool(param)] page: u32,
+    ) -> String {
+        format!("{page}:{}", pagination.page)
+    }
+}
+
+#[tokio::test]
+async fn test_flattened_aggregated_param() -> anyhow::Result<()> {
+    let schema = serde_json::Value::Object(
+        FlattenServer::search_tool_attr()
+            .input_schema
+            .as_ref()
+            .clone(),
+    );
+    for property in ["page", "per_page", "query"] {
+        assert!(
+            schema["properties"].get(property).is_some(),
+            "schema should contain {property}"
+        );
+    }
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        FlattenServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let arguments = serde_json::json!({ "query": "rust", "page": 2, "per_page": 10 })
+        .as_object()
+        .cloned();
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "search".into(),
+            arguments: arguments.clone().map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "rust:2:10");
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "conflicting".into(),
+            arguments: arguments.map(Into::into),
+        })
+        .await;
+    assert!(result.is_err());
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AdminServer {
+    admin: Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl AdminServer {
+    fn is_admin_enabled(&self) -> bool {
+        self.admin.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+#[tool(tool_box)]
+impl AdminServer {
+    #[tool(description = "Always available")]
+    async fn status(&self) -> String {
+        "ok".to_string()
+    }
+
+    #[tool(description = "Only available to admins", enabled_if = self.is_admin_enabled())]
+    async fn shutdown(&self) -> String {
+        "shutting down".to_string()
+    }
+}
+
+#[tokio::test]
+async fn test_tool_enabled_if() -> anyhow::Result<()> {
+    let server = AdminServer::default();
+    let admin = server.admin.clone();
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let tools = client.list_all_tools().await?;
+    assert_eq!(
+        tools.iter().map(|t| &*t.name).collect::<Vec<_>>(),
+        ["status"]
+    );
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "shutdown".into(),
+            arguments: None,
+        })
+        .await;
+    assert!(result.is_err());
+
+    admin.store(true, std::sync::atomic::Ordering::SeqCst);
+    let mut tools = client.list_all_tools().await?;
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(
+        tools.iter().map(|t| &*t.name).collect::<Vec<_>>(),
+        ["shutdown", "status"]
+    );
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "shutdown".into(),
+            arguments: None,
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "shutting down");
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RenameServer;
+
+#[tool(tool_box)]
+impl RenameServer {
+    #[tool(description = "Snake case keys", rename_all = "snake_case")]
+    async fn snake(
+        &self,
+        #[tool(param)] user_name: String,
+        #[tool(param)] max_count: u32,
+    ) -> String {
+        format!("{user_name}:{max_count}")
+    }
+
+    #[tool(description = "Camel case keys", rename_all = "camelCase")]
+    async fn camel(
+        &self,
+        #[tool(param)] user_name: String,
+        #[tool(param)] max_count: u32,
+    ) -> String {
+        format!("{user_name}:{max_count}")
+    }
+
+    #[tool(description = "Kebab case keys", rename_all = "kebab-case")]
+    async fn kebab(
+        &self,
+        #[tool(param)] user_name: String,
+        #[tool(param)] max_count: u32,
+    ) -> String {
+        format!("{user_name}:{max_count}")
+    }
+}
+
+#[tokio::test]
+async fn test_tool_rename_all() -> anyhow::Result<()> {
+    let cases = [
+        (RenameServer::snake_tool_attr(), ["user_name", "max_count"]),
+        (RenameServer::camel_tool_attr(), ["userName", "maxCount"]),
+        (RenameServer::kebab_tool_attr(), ["user-name", "max-count"]),
+    ];
+    for (tool, keys) in &cases {
+        let schema = serde_json::Value::Object(tool.input_schema.as_ref().clone());
+        for key in keys {
+            assert!(
+                schema["properties"].get(key).is_some(),
+                "schema of {} should contain {key}",
+                tool.name
+            );
+        }
+    }
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        RenameServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    for (tool, [user_name, max_count]) in &cases {
+        let result = client
+            .call_tool(CallToolRequestParam {
+                name: tool.name.clone(),
+                arguments: serde_json::json!({ *user_name: "ferris", *max_count: 3 })
+                    .as_object()
+                    .cloned()
+                    .map(Into::into),
+            })
+            .await?;
+        assert_eq!(result.content[0].as_text().unwrap().text, "ferris:3");
+    }
+
+    // the rust field names are not accepted once renamed
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "camel".into(),
+            arguments: serde_json::json!({ "user_name": "ferris", "max_count": 3 })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await;
+    assert!(result.is_err());
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CfgServer;
+
+#[tool(tool_box)]
+impl CfgServer {
+    #[tool(description = "Always available")]
+    async fn stable(&self) -> String {
+        "stable".to_string()
+    }
+
+    #[cfg(feature = "client")]
+    #[tool(name = "gated", description = "Compiled with the client feature")]
+    async fn gated_on(&self) -> String {
+        "on".to_string()
+    }
+
+    #[cfg(not(feature = "client"))]
+    #[tool(name = "gated", description = "Compiled without the client feature")]
+    async fn gated_off(&self) -> String {
+        "off".to_string()
+    }
+
+    #[cfg(any())]
+    #[tool(description = "Never compiled")]
+    async fn removed(&self) -> String {
+        "removed".to_string()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CfgGenericServer<T: Send + Sync + 'static> {
+    _marker: std::marker::PhantomData<T>,
+}
+
+#[tool(tool_box)]
+impl<T: Send + Sync + 'static> CfgGenericServer<T> {
+    #[tool(description = "Always available")]
+    async fn stable(&self) -> String {
+        "stable".to_string()
+    }
+
+    #[cfg(any())]
+    #[tool(description = "Never compiled")]
+    async fn removed(&self) -> String {
+        "removed".to_string()
+    }
+}
+
+#[tokio::test]
+async fn test_cfg_gated_tools() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        CfgServer.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let mut tools = client.list_all_tools().await?;
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(
+        tools.iter().map(|t| &*t.name).collect::<Vec<_>>(),
+        ["gated", "stable"]
+    );
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "gated".into(),
+            arguments: None,
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "on");
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "removed".into(),
+            arguments: None,
+        })
+        .await;
+    assert!(result.is_err());
+
+    client.cancel().await?;
+    server_handle.await??;
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        CfgGenericServer::<()>::default()
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+    let tools = client.list_all_tools().await?;
+    assert_eq!(
+        tools.iter().map(|t| &*t.name).collect::<Vec<_>>(),
+        ["stable"]
+    );
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct FsServer {
+    base_dir: String,
+}
+
+impl FsServer {
+    fn describe_read_file(&self) -> String {
+        format!("Read a file under {}", self.base_dir)
+    }
+}
+
+#[tool(tool_box)]
+impl FsServer {
+    #[tool(description = "Read a file", description_fn = Self::describe_read_file)]
+    async fn read_file(&self, #[tool(param)] path: String) -> String {
+        format!("{}/{path}", self.base_dir)
+    }
+}
+
+#[tokio::test]
+async fn test_tool_description_fn() -> anyhow::Result<()> {
+    // the static attr keeps the schema and the fallback description
+    let attr = FsServer::read_file_tool_attr();
+    assert_eq!(attr.description.as_deref(), Some("Read a file"));
+    assert!(attr.input_schema.get("properties").is_some());
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        FsServer {
+            base_dir: "/srv/data".to_string(),
+        }
+        .serve(server_transport)
+        .await?
+        .waiting()
+        .await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let tools = client.list_all_tools().await?;
+    assert_eq!(tools.len(), 1);
+    assert_eq!(
+        tools[0].description.as_deref(),
+        Some("Read a file under /srv/data")
+    );
+    assert_eq!(tools[0].input_schema, attr.input_schema);
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AliasServer;
+
+#[tool(tool_box)]
+impl AliasServer {
+    #[tool(name = "search", description = "Search documents", aliases = ["find", "query"])]
+    async fn search(&self, #[tool(param)] text: String) -> String {
+        format!("results for {text}")
+    }
+
+    #[tool(
+        description = "Look up a document",
+        aliases = ["get"],
+        advertise_aliases = true
+    )]
+    async fn lookup(&self, #[tool(param)] id: u32) -> String {
+        format!("document {id}")
+    }
+}
+
+#[tokio::test]
+async fn test_tool_aliases() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        AliasServer.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let mut tools = client.list_all_tools().await?;
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(
+        tools.iter().map(|t| &*t.name).collect::<Vec<_>>(),
+        ["get", "lookup", "search"]
+    );
+    assert_eq!(tools[0].description, tools[1].description);
+
+    for name in ["search", "find", "query"] {
+        let result = client
+            .call_tool(CallToolRequestParam {
+                name: name.into(),
+                arguments: serde_json::json!({ "text": "rust" })
+                    .as_object()
+                    .cloned()
+                    .map(Into::into),
+            })
+            .await?;
+        assert_eq!(
+            result.content[0].as_text().unwrap().text,
+            "results for rust"
+        );
+    }
+    for name in ["lookup", "get"] {
+        let result = client
+            .call_tool(CallToolRequestParam {
+                name: name.into(),
+                arguments: serde_json::json!({ "id": 7 })
+                    .as_object()
+                    .cloned()
+                    .map(Into::into),
+            })
+            .await?;
+        assert_eq!(result.content[0].as_text().unwrap().text, "document 7");
+    }
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PagedServer;
+
+#[tool(tool_box, page_size = 3)]
+impl PagedServer {
+    #[tool(description = "Tool a")]
+    async fn tool_a(&self) {}
+    #[tool(description = "Tool b")]
+    async fn tool_b(&self) {}
+    #[tool(description = "Tool c")]
+    async fn tool_c(&self) {}
+    #[tool(description = "Tool d")]
+    async fn tool_d(&self) {}
+    #[tool(description = "Tool e")]
+    async fn tool_e(&self) {}
+    #[tool(description = "Tool f")]
+    async fn tool_f(&self) {}
+    #[tool(description = "Tool g")]
+    async fn tool_g(&self) {}
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PagedGenericServer<T: Send + Sync + 'static> {
+    _marker: std::marker::PhantomData<T>,
+}
+
+#[tool(tool_box, page_size = 2)]
+impl<T: Send + Sync + 'static> PagedGenericServer<T> {
+    #[tool(description = "Tool a")]
+    async fn tool_a(&self) {}
+    #[tool(description = "Tool b")]
+    async fn tool_b(&self) {}
+    #[tool(description = "Tool c")]
+    async fn tool_c(&self) {}
+    #[tool(description = "Tool d")]
+    async fn tool_d(&self) {}
+    #[tool(description = "Tool e")]
+    async fn tool_e(&self) {}
+}
+
+// walk the pages one by one, returning the size of each page and all listed names
+async fn walk_tool_pages(client: &Peer<RoleClient>) -> anyhow::Result<(Vec<usize>, Vec<String>)> {
+    let mut page_sizes = Vec::new();
+    let mut names = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = client
+            .list_tools(Some(rmcp::model::PaginatedRequestParam { cursor }))
+            .await?;
+        page_sizes.push(page.tools.len());
+        names.extend(page.tools.into_iter().map(|tool| tool.name.into_owned()));
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    Ok((page_sizes, names))
+}
+
+#[tokio::test]
+async fn test_tool_pagination() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        PagedServer.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let (page_sizes, names) = walk_tool_pages(client.peer()).await?;
+    assert_eq!(page_sizes, [3, 3, 1]);
+    assert_eq!(
+        names,
+        [
+            "tool_a", "tool_b", "tool_c", "tool_d", "tool_e", "tool_f", "tool_g"
+        ]
+    );
+
+    let invalid = client
+        .list_tools(Some(rmcp::model::PaginatedRequestParam {
+            cursor: Some("not a cursor".into()),
+        }))
+        .await;
+    assert!(invalid.is_err());
+
+    client.cancel().await?;
+    server_handle.await??;
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        PagedGenericServer::<()>::default()
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let (page_sizes, names) = walk_tool_pages(client.peer()).await?;
+    assert_eq!(page_sizes, [2, 2, 1]);
+    assert_eq!(names, ["tool_a", "tool_b", "tool_c", "tool_d", "tool_e"]);
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct Pool {
+    name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpClient;
+
+#[derive(Debug, Clone, Default)]
+pub struct DbServer;
+
+#[tool(tool_box)]
+impl DbServer {
+    #[tool(description = "Run a query on the pool")]
+    async fn query(
+        &self,
+        rmcp::handler::server::tool::Extension(pool): rmcp::handler::server::tool::Extension<Pool>,
+        #[tool(param)] sql: String,
+    ) -> String {
+        format!("{}: {sql}", pool.name)
+    }
+
+    #[tool(description = "Fetch a url")]
+    async fn fetch(
+        &self,
+        _client: rmcp::handler::server::tool::Extension<HttpClient>,
+        #[tool(param)] url: String,
+    ) -> String {
+        url
+    }
+}
+
+#[tokio::test]
+async fn test_tool_extension() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        DbServer
+            .with_extension(Pool {
+                name: "main".to_string(),
+            })
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "query".into(),
+            arguments: serde_json::json!({ "sql": "select 1" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "main: select 1");
+
+    let missing = client
+        .call_tool(CallToolRequestParam {
+            name: "fetch".into(),
+            arguments: serde_json::json!({ "url": "https://example.com" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await;
+    let Err(rmcp::ServiceError::McpError(error)) = missing else {
+        panic!("expected an error for the missing extension");
+    };
+    assert!(error.message.contains("HttpClient"));
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+pub trait Storage: Send + Sync + 'static {
+    type Error;
+    fn get(&self, key: &str) -> Result<String, Self::Error>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage;
+
+impl Storage for MemoryStorage {
+    type Error = String;
+    fn get(&self, key: &str) -> Result<String, Self::Error> {
+        match key {
+            "greeting" => Ok("hello".to_string()),
+            _ => Err(format!("missing key `{key}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StorageServer<S, const N: usize> {
+    storage: S,
+}
+
+#[tool(tool_box)]
+impl<S, const N: usize> StorageServer<S, N>
+where
+    S: Storage,
+    S::Error: std::fmt::Display,
+{
+    #[tool(description = "Read at most N characters of a key")]
+    async fn read(&self, #[tool(param)] key: String) -> String {
+        match self.storage.get(&key) {
+            Ok(value) => value.chars().take(N).collect(),
+            Err(error) => format!("error: {error}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_tool_where_clause() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        StorageServer::<_, 4> {
+            storage: MemoryStorage,
+        }
+        .serve(server_transport)
+        .await?
+        .waiting()
+        .await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "read".into(),
+            arguments: serde_json::json!({ "key": "greeting" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "hell");
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "read".into(),
+            arguments: serde_json::json!({ "key": "other" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(
+        result.content[0].as_text().unwrap().text,
+        "error: missing key `other`"
+    );
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+static COUNTED_SCHEMAS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[derive(JsonSchema)]
+struct CountedRequestSchema {
+    #[allow(dead_code)]
+    value: u32,
+}
+
+// counts how many times its schema is generated
+#[derive(Deserialize)]
+pub struct CountedRequest {
+    value: u32,
+}
+
+impl JsonSchema for CountedRequest {
+    fn schema_name() -> String {
+        CountedRequestSchema::schema_name()
+    }
+
+    fn json_schema(generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        COUNTED_SCHEMAS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        CountedRequestSchema::json_schema(generator)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CountingServer<T: Send + Sync + 'static> {
+    disabled: bool,
+    _marker: std::marker::PhantomData<T>,
+}
+
+#[tool(tool_box)]
+impl<T: Send + Sync + 'static> CountingServer<T> {
+    #[tool(description = "Double a value", enabled_if = !self.disabled)]
+    async fn double(&self, #[tool(aggr)] request: CountedRequest) -> String {
+        (request.value * 2).to_string()
+    }
+
+    #[tool(name = "triple", description = "Triple a value")]
+    async fn triple_value(&self, #[tool(param)] value: u32) -> String {
+        (value * 3).to_string()
+    }
+}
+
+#[tokio::test]
+async fn test_dispatch_skips_schema() -> anyhow::Result<()> {
+    assert_eq!(CountingServer::<()>::double_tool_name(), "double");
+    assert_eq!(CountingServer::<()>::triple_value_tool_name(), "triple");
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        CountingServer::<()>::default()
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    for value in 0..3 {
+        let result = client
+            .call_tool(CallToolRequestParam {
+                name: "double".into(),
+                arguments: serde_json::json!({ "value": value })
+                    .as_object()
+                    .cloned()
+                    .map(Into::into),
+            })
+            .await?;
+        assert_eq!(
+            result.content[0].as_text().unwrap().text,
+            (value * 2).to_string()
+        );
+    }
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "triple".into(),
+            arguments: serde_json::json!({ "value": 2 })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "6");
+    assert_eq!(
+        COUNTED_SCHEMAS.load(std::sync::atomic::Ordering::SeqCst),
+        0,
+        "dispatch must not build the tool schema"
+    );
+
+    client.list_all_tools().await?;
+    assert!(COUNTED_SCHEMAS.load(std::sync::atomic::Ordering::SeqCst) > 0);
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[tool(description = "Join two words")]
+fn join(#[tool(param)] left: String, #[tool(param)] right: String) -> String {
+    format!("{left} {right}")
+}
+
+#[tool(name = "shout", description = "Uppercase a sentence")]
+async fn shout_sentence(#[tool(aggr)] request: GetWeatherRequest) -> String {
+    format!("{} {}", request.city, request.date).to_uppercase()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FreeFnServer;
+
+impl FreeFnServer {
+    fn tool_box() -> &'static rmcp::handler::server::tool::ToolBox<Self> {
+        static TOOL_BOX: std::sync::OnceLock<rmcp::handler::server::tool::ToolBox<FreeFnServer>> =
+            std::sync::OnceLock::new();
+        TOOL_BOX.get_or_init(|| rmcp::tool_box!(fns: [join, shout_sentence]))
+    }
+}
+
+impl ServerHandler for FreeFnServer {
+    rmcp::tool_box!(@derive);
+}
+
+#[tokio::test]
+async fn test_free_fn_tools() -> anyhow::Result<()> {
+    assert_eq!(join_tool_attr().name, "join");
+    assert_eq!(shout_sentence_tool_name(), "shout");
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        FreeFnServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let mut tools = client.list_all_tools().await?;
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(
+        tools.iter().map(|t| &*t.name).collect::<Vec<_>>(),
+        ["join", "shout"]
+    );
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "join".into(),
+            arguments: serde_json::json!({ "left": "hello", "right": "world" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "hello world");
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "shout".into(),
+            arguments: serde_json::json!({ "city": "paris", "date": "today" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "PARIS TODAY");
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum LookupError {
+    NotFound(String),
+    Backend(String),
+}
+
+// a missing key is reported to the model, a backend failure is a protocol error
+fn lookup_error(error: LookupError) -> Result<rmcp::model::CallToolResult, rmcp::Error> {
+    match error {
+        LookupError::NotFound(key) => Ok(rmcp::model::CallToolResult::error(vec![
+            rmcp::model::Content::text(format!("no entry for `{key}`")),
+        ])),
+        LookupError::Backend(reason) => Err(rmcp::Error::internal_error(reason, None)),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LookupServer;
+
+#[tool(tool_box, on_error = lookup_error)]
+impl LookupServer {
+    fn get(key: &str) -> Result<String, LookupError> {
+        match key {
+            "answer" => Ok("42".to_string()),
+            "crash" => Err(LookupError::Backend("backend unavailable".to_string())),
+            _ => Err(LookupError::NotFound(key.to_string())),
+        }
+    }
+
+    fn strict_error(error: LookupError) -> Result<rmcp::model::CallToolResult, rmcp::Error> {
+        Err(rmcp::Error::invalid_params(format!("{error:?}"), None))
+    }
+
+    #[tool(description = "Look up a key")]
+    async fn lookup(&self, #[tool(param)] key: String) -> Result<String, LookupError> {
+        Self::get(&key)
+    }
+
+    #[tool(description = "Look up a key which must exist", on_error = Self::strict_error)]
+    async fn strict_lookup(&self, #[tool(param)] key: String) -> Result<String, LookupError> {
+        Self::get(&key)
+    }
+
+    #[tool(description = "Not affected by the default mapper")]
+    async fn ping(&self) -> String {
+        "pong".to_string()
+    }
+}
+
+#[tokio::test]
+async fn test_tool_on_error() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        LookupServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+    let lookup = |name: &'static str, key: &str| CallToolRequestParam {
+        name: name.into(),
+        arguments: serde_json::json!({ "key": key })
+            .as_object()
+            .cloned()
+            .map(Into::into),
+    };
+
+    let result = client.call_tool(lookup("lookup", "answer")).await?;
+    assert_eq!(result.is_error, Some(false));
+    assert_eq!(result.content[0].as_text().unwrap().text, "42");
+
+    // recoverable error, surfaced as tool output
+    let result = client.call_tool(lookup("lookup", "question")).await?;
+    assert_eq!(result.is_error, Some(true));
+    assert_eq!(
+        result.content[0].as_text().unwrap().text,
+        "no entry for `question`"
+    );
+
+    // hard error, surfaced as a protocol error
+    let Err(rmcp::ServiceError::McpError(error)) =
+        client.call_tool(lookup("lookup", "crash")).await
+    else {
+        panic!("expected a protocol error");
+    };
+    assert_eq!(error.code, rmcp::model::ErrorCode::INTERNAL_ERROR);
+    assert_eq!(error.message, "backend unavailable");
+
+    // the tool's own mapper overrides the default one
+    let Err(rmcp::ServiceError::McpError(error)) =
+        client.call_tool(lookup("strict_lookup", "question")).await
+    else {
+        panic!("expected a protocol error");
+    };
+    assert_eq!(error.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "ping".into(),
+            arguments: None,
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "pong");
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BorrowedQuery<'a> {
+    /// The text to search for
+    pub text: &'a str,
+    #[serde(borrow)]
+    pub language: std::borrow::Cow<'a, str>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BorrowingServer;
+
+#[tool(tool_box)]
+impl BorrowingServer {
+    #[tool(description = "Search borrowing the arguments")]
+    async fn search(&self, #[tool(aggr)] query: BorrowedQuery<'_>) -> String {
+        // both fields point into the arguments of the request
+        let borrowed = matches!(query.language, std::borrow::Cow::Borrowed(_));
+        format!("{}:{}:{borrowed}", query.text, query.language)
+    }
+
+    #[tool(description = "Search with a destructured borrowing query")]
+    fn search_sync(
+        &self,
+        #[tool(aggr)] BorrowedQuery { text, language }: BorrowedQuery<'_>,
+    ) -> String {
+        format!("{text}:{language}")
+    }
+}
+
+#[tokio::test]
+async fn test_tool_borrowed_params() -> anyhow::Result<()> {
+    let schema = BorrowingServer::search_tool_attr().input_schema;
+    assert_eq!(schema["title"], "BorrowedQuery");
+    assert_eq!(
+        schema["properties"]["text"]["description"],
+        "The text to search for"
+    );
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        BorrowingServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "search".into(),
+            arguments: serde_json::json!({ "text": "fn main", "language": "rust" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(
+        result.content[0].as_text().unwrap().text,
+        "fn main:rust:true"
+    );
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "search_sync".into(),
+            arguments: serde_json::json!({ "text": "def", "language": "python" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "def:python");
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "search".into(),
+            arguments: serde_json::json!({ "text": 1 })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await;
+    assert!(result.is_err());
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceServer {
+    read_only: bool,
+}
+
+#[tool(tool_box = file_tools, default_build = false)]
+impl WorkspaceServer {
+    #[tool(description = "Read a file")]
+    async fn read_file(&self, #[tool(param)] path: String) -> String {
+        format!("read {path}")
+    }
+
+    #[tool(description = "Write a file", enabled_if = !self.read_only)]
+    async fn write_file(&self, #[tool(param)] path: String) -> String {
+        format!("wrote {path}")
+    }
+}
+
+#[tool(tool_box = git_tools, default_build = false)]
+impl WorkspaceServer {
+    #[tool(description = "Show the git status", aliases = ["st"])]
+    async fn status(&self) -> String {
+        "clean".to_string()
+    }
+}
+
+impl WorkspaceServer {
+    rmcp::tool_box!(combine WorkspaceServer { file_tools, git_tools } tool_box);
+}
+
+impl ServerHandler for WorkspaceServer {
+    rmcp::tool_box!(@derive);
+}
+
+#[tokio::test]
+async fn test_combined_tool_boxes() -> anyhow::Result<()> {
+    use rmcp::handler::server::tool::tool_names_are_unique;
+    assert_eq!(
+        WorkspaceServer::file_tools_tool_names(),
+        ["read_file", "write_file"]
+    );
+    assert!(tool_names_are_unique(&[&["read"], &["write", "status"]]));
+    assert!(!tool_names_are_unique(&[&["read"], &["write", "read"]]));
+    assert!(!tool_names_are_unique(&[&["read", "read"]]));
+
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        WorkspaceServer { read_only: true }
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = DummyClientHandler::default()
+        .serve(client_transport)
+        .await?;
+
+    // each tool keeps the `enabled_if` of its own box
+    let mut tools = client.list_all_tools().await?;
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(
+        tools.iter().map(|t| &*t.name).collect::<Vec<_>>(),
+        ["read_file", "status"]
+    );
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "read_file".into(),
+            arguments: serde_json::json!({ "path": "Cargo.toml" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "read Cargo.toml");
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "st".into(),
+            arguments: None,
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "clean");
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "write_file".into(),
+            arguments: serde_json::json!({ "path": "Cargo.toml" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await;
+    assert!(result.is_err());
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
@@ -0,0 +1,7 @@
+//cargo test --test test_tool_macros_ui --features "server macros"
+
+#[test]
+fn test_tool_macros_ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
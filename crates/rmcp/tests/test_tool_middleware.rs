@@ -0,0 +1,205 @@
+//cargo test --test test_tool_middleware --features "client server macros"
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rmcp::{
+    RoleClient, ServerHandler, ServiceExt,
+    handler::server::tool::{ToolCallContext, ToolMiddleware, TracingMiddleware},
+    model::{CallToolRequestParam, CallToolResult, Content},
+    service::{RunningService, Service},
+    tool,
+};
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+#[derive(Debug, Clone, Default)]
+pub struct EchoServer {
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+#[tool(tool_box)]
+impl EchoServer {
+    #[tool(description = "Echo the text")]
+    async fn echo(&self, #[tool(param)] text: String) -> String {
+        self.calls.lock().unwrap().push(text.clone());
+        text
+    }
+
+    #[tool(description = "Delete everything")]
+    async fn delete_all(&self) -> String {
+        self.calls.lock().unwrap().push("delete_all".into());
+        "deleted".into()
+    }
+}
+
+/// A generic server goes through the generated `call_tool_inner`
+#[derive(Debug, Clone, Default)]
+pub struct GenericEchoServer<T> {
+    prefix: T,
+}
+
+#[tool(tool_box)]
+impl<T: std::fmt::Display + Send + Sync + 'static> GenericEchoServer<T> {
+    #[tool(description = "Echo the text with a prefix")]
+    async fn echo(&self, #[tool(param)] text: String) -> String {
+        format!("{}{text}", self.prefix)
+    }
+}
+
+struct Recorder {
+    label: &'static str,
+    log: Log,
+}
+
+impl<S> ToolMiddleware<S> for Recorder {
+    fn before(&self, context: &ToolCallContext<'_, S>) -> Result<(), rmcp::Error> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("{} before {}", self.label, context.name()));
+        Ok(())
+    }
+
+    fn after(&self, name: &str, result: &mut Result<CallToolResult, rmcp::Error>, _: Duration) {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("{} after {name} ok={}", self.label, result.is_ok()));
+    }
+}
+
+struct Allowlist(&'static [&'static str]);
+
+impl<S> ToolMiddleware<S> for Allowlist {
+    fn before(&self, context: &ToolCallContext<'_, S>) -> Result<(), rmcp::Error> {
+        if self.0.contains(&context.name()) {
+            Ok(())
+        } else {
+            Err(rmcp::Error::invalid_request(
+                format!("tool `{}` is not allowed", context.name()),
+                None,
+            ))
+        }
+    }
+}
+
+struct Shout;
+
+impl<S> ToolMiddleware<S> for Shout {
+    fn after(&self, _: &str, result: &mut Result<CallToolResult, rmcp::Error>, _: Duration) {
+        if let Ok(result) = result {
+            let text = result.content[0].as_text().unwrap().text.to_uppercase();
+            result.content = vec![Content::text(text)];
+        }
+    }
+}
+
+async fn serve<S: Service<rmcp::RoleServer>>(
+    server: S,
+) -> anyhow::Result<RunningService<RoleClient, ()>> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    Ok(().serve(client_transport).await?)
+}
+
+async fn call(
+    client: &RunningService<RoleClient, ()>,
+    name: &'static str,
+    arguments: serde_json::Value,
+) -> Result<CallToolResult, rmcp::service::ServiceError> {
+    client
+        .call_tool(CallToolRequestParam {
+            name: name.into(),
+            arguments: arguments.as_object().cloned().map(Into::into),
+        })
+        .await
+}
+
+fn recorder(label: &'static str, log: &Log) -> Recorder {
+    Recorder {
+        label,
+        log: log.clone(),
+    }
+}
+
+#[tokio::test]
+async fn test_tool_middleware_order() -> anyhow::Result<()> {
+    let log = Log::default();
+    let server = EchoServer::default()
+        .with_tool_middleware(TracingMiddleware)
+        .with_tool_middleware(recorder("outer", &log))
+        .with_tool_middleware(recorder("middle", &log))
+        .with_tool_middleware(recorder("inner", &log));
+    let client = serve(server).await?;
+
+    let result = call(&client, "echo", serde_json::json!({ "text": "hi" })).await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "hi");
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "outer before echo",
+            "middle before echo",
+            "inner before echo",
+            "inner after echo ok=true",
+            "middle after echo ok=true",
+            "outer after echo ok=true",
+        ]
+    );
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tool_middleware_short_circuit() -> anyhow::Result<()> {
+    let log = Log::default();
+    let server = EchoServer::default();
+    let calls = server.calls.clone();
+    let server = server
+        .with_tool_middleware(recorder("outer", &log))
+        .with_tool_middleware(Allowlist(&["echo"]))
+        .with_tool_middleware(recorder("inner", &log));
+    let client = serve(server).await?;
+
+    let error = call(&client, "delete_all", serde_json::json!({}))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("not allowed"));
+    // the tool and the middlewares inside the allowlist never ran
+    assert!(calls.lock().unwrap().is_empty());
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["outer before delete_all", "outer after delete_all ok=false"]
+    );
+
+    call(&client, "echo", serde_json::json!({ "text": "hi" })).await?;
+    assert_eq!(*calls.lock().unwrap(), ["hi"]);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tool_middleware_rewrite() -> anyhow::Result<()> {
+    let log = Log::default();
+    let server = GenericEchoServer { prefix: "> " }
+        .with_tool_middleware(recorder("outer", &log))
+        .with_tool_middleware(Shout);
+    let client = serve(server).await?;
+
+    let result = call(&client, "echo", serde_json::json!({ "text": "hi" })).await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "> HI");
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["outer before echo", "outer after echo ok=true"]
+    );
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//cargo test --test test_tool_progress --features "client server"
+
+use std::sync::{Arc, Mutex};
+
+use rmcp::{
+    ClientHandler, Peer, RoleClient, ServiceExt,
+    handler::server::tool::ToolProgress,
+    model::{CallToolRequestParam, ClientInfo, ProgressNotificationParam},
+    tool,
+};
+use tokio::sync::Notify;
+
+#[derive(Debug, Clone, Default)]
+pub struct ShellServer;
+
+#[tool(tool_box)]
+impl ShellServer {
+    #[tool(description = "Run a number of steps, reporting each of them")]
+    async fn run(&self, #[tool(param)] steps: u32, progress: ToolProgress) -> String {
+        let progress = progress.strict();
+        for step in 1..=steps {
+            progress
+                .report(
+                    step.into(),
+                    Some(steps.into()),
+                    Some(format!("step {step}")),
+                )
+                .await
+                .expect("failed to report progress");
+        }
+        format!("ran {steps} steps")
+    }
+}
+
+#[derive(Clone, Default)]
+struct ProgressClient {
+    progress: Arc<Mutex<Vec<ProgressNotificationParam>>>,
+    received: Arc<Notify>,
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl ClientHandler for ProgressClient {
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo::default()
+    }
+
+    async fn on_progress(&self, params: ProgressNotificationParam) {
+        self.progress.lock().unwrap().push(params);
+        self.received.notify_one();
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+}
+
+#[tokio::test]
+async fn test_tool_progress() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn(async move {
+        ShellServer.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+
+    let handler = ProgressClient::default();
+    let progress = handler.progress.clone();
+    let received = handler.received.clone();
+    let client = handler.serve(client_transport).await?;
+
+    // the progress argument is extracted from the context, not advertised as a parameter
+    let tools = client.list_all_tools().await?;
+    let properties = tools[0].input_schema["properties"].as_object().unwrap();
+    assert_eq!(properties.keys().collect::<Vec<_>>(), ["steps"]);
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "run".into(),
+            arguments: serde_json::json!({ "steps": 3 })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "ran 3 steps");
+
+    // notifications may be handled after the response
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while progress.lock().unwrap().len() < 3 {
+            received.notified().await;
+        }
+    })
+    .await?;
+
+    let progress = std::mem::take(&mut *progress.lock().unwrap());
+    assert_eq!(
+        progress
+            .iter()
+            .map(|params| (params.progress, params.total, params.message.as_deref()))
+            .collect::<Vec<_>>(),
+        [
+            (1.0, Some(3.0), Some("step 1")),
+            (2.0, Some(3.0), Some("step 2")),
+            (3.0, Some(3.0), Some("step 3")),
+        ]
+    );
+    assert!(
+        progress
+            .iter()
+            .all(|params| params.progress_token == progress[0].progress_token)
+    );
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
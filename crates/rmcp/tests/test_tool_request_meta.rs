@@ -0,0 +1,110 @@
+//cargo test --test test_tool_request_meta --features "client server macros"
+
+use rmcp::{
+    ServerHandler, ServiceExt,
+    handler::server::tool::RequestMeta,
+    model::{
+        CallToolRequestMethod, CallToolRequestParam, ClientCapabilities, ClientInfo, ClientRequest,
+        Implementation, Meta, ProtocolVersion, Request, ServerInfo, ServerResult,
+    },
+    service::PeerRequestOptions,
+    tool,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct WhoAmIServer;
+
+#[tool(tool_box, default_build = false)]
+impl WhoAmIServer {
+    #[tool(description = "Describe the caller")]
+    async fn whoami(&self, meta: RequestMeta) -> String {
+        serde_json::json!({
+            "client": meta.client_info().name,
+            "protocolVersion": meta.protocol_version(),
+            "requestId": meta.request_id(),
+            "traceId": meta.meta().get::<String>("traceId"),
+        })
+        .to_string()
+    }
+}
+
+#[tool(tool_box)]
+impl ServerHandler for WhoAmIServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: ProtocolVersion::V_2024_11_05,
+            ..Default::default()
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_request_meta() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        WhoAmIServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = ClientInfo {
+        protocol_version: ProtocolVersion::V_2025_03_26,
+        capabilities: ClientCapabilities::default(),
+        client_info: Implementation {
+            name: "meta-test-client".into(),
+            version: "1.0.0".into(),
+        },
+    }
+    .serve(client_transport)
+    .await?;
+
+    // `RequestMeta` isn't taken for a param
+    let tools = client.list_all_tools().await?;
+    assert!(
+        tools[0]
+            .input_schema
+            .get("properties")
+            .is_none_or(|p| p.as_object().unwrap().is_empty())
+    );
+
+    let mut meta = Meta::new();
+    meta.insert("traceId", "4bf92f3577b34da6")?;
+    let request = ClientRequest::CallToolRequest(Request {
+        method: CallToolRequestMethod,
+        params: CallToolRequestParam {
+            name: "whoami".into(),
+            arguments: None,
+        },
+        extensions: Default::default(),
+    });
+    let handle = client
+        .send_request_with_option(
+            request,
+            PeerRequestOptions {
+                meta: Some(meta),
+                ..Default::default()
+            },
+        )
+        .await?;
+    let request_id = serde_json::to_value(&handle.id)?;
+    let ServerResult::CallToolResult(result) = handle.await_response().await? else {
+        panic!("expected a call tool result");
+    };
+    let caller: serde_json::Value =
+        serde_json::from_str(&result.content[0].as_text().unwrap().text)?;
+    assert_eq!(
+        caller,
+        serde_json::json!({
+            "client": "meta-test-client",
+            // the server only supports the older version
+            "protocolVersion": "2024-11-05",
+            "requestId": request_id,
+            "traceId": "4bf92f3577b34da6",
+        })
+    );
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,61 @@
+//cargo test --test test_tool_request_schema --features "server macros"
+use rmcp::{
+    Error as McpError, handler::server::tool::cached_schema_for_type, model::*, schemars, tool,
+};
+use serde::Deserialize;
+
+/// Ordering applied to search hits.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Relevance,
+    Recent,
+}
+
+/// Search the document index.
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SearchRequest {
+    /// Full-text query to match against documents.
+    pub query: String,
+    /// Maximum number of hits to return.
+    pub limit: Option<u32>,
+    /// How to order the hits.
+    pub sort: SortOrder,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchServer;
+
+#[tool(tool_box)]
+impl SearchServer {
+    #[tool(description = "Search documents")]
+    async fn search(
+        &self,
+        #[tool(aggr)] request: SearchRequest,
+    ) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text(request.query)]))
+    }
+}
+
+fn golden_schema() -> serde_json::Value {
+    let json = std::fs::read("tests/test_tool_request_schema/search_request.json").unwrap();
+    serde_json::from_slice(&json).unwrap()
+}
+
+#[test]
+fn test_doc_comments_in_request_schema() {
+    let schema = cached_schema_for_type::<SearchRequest>();
+    assert_eq!(
+        serde_json::Value::Object((*schema).clone()),
+        golden_schema()
+    );
+}
+
+#[test]
+fn test_doc_comments_in_tool_input_schema() {
+    let tool = SearchServer::search_tool_attr();
+    assert_eq!(
+        serde_json::Value::Object((*tool.input_schema).clone()),
+        golden_schema()
+    );
+}
@@ -0,0 +1,209 @@
+//cargo test --test test_tool_router --features "client server macros"
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use rmcp::{
+    ClientHandler, Peer, RoleClient, ServerHandler, ServiceExt,
+    handler::server::tool::{
+        FromToolCallContextPart, Parameters, ToolBoxItem, ToolCallContext, ToolRouter,
+    },
+    model::{
+        CallToolRequestParam, CallToolResult, ClientInfo, Content, ServerCapabilities, ServerInfo,
+        Tool,
+    },
+    tool,
+};
+use tokio::sync::Notify;
+
+#[derive(Debug, Clone, Default)]
+pub struct PluginServer {
+    router: ToolRouter<PluginServer>,
+}
+
+#[tool]
+impl PluginServer {
+    #[tool(description = "Add two numbers")]
+    async fn sum(&self, #[tool(param)] a: i32, #[tool(param)] b: i32) -> String {
+        (a + b).to_string()
+    }
+}
+
+impl PluginServer {
+    fn new() -> Self {
+        let server = Self::default();
+        server
+            .router
+            .add(ToolBoxItem::new(Self::sum_tool_attr(), |context| {
+                Box::pin(Self::sum_tool_call(context))
+            }));
+        server
+    }
+
+    /// A tool loaded at runtime, greeting with a configured prefix
+    fn load_greeting(&self, name: &'static str, prefix: &'static str) {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" } },
+        });
+        self.router.route(
+            name,
+            Tool::new("", "Greet someone", schema.as_object().cloned().unwrap()),
+            move |context: ToolCallContext<'_, PluginServer>| {
+                Box::pin(async move {
+                    let (Parameters(args), _) =
+                        Parameters::<serde_json::Value>::from_tool_call_context_part(context)?;
+                    Ok(CallToolResult::success(vec![Content::text(format!(
+                        "{prefix}, {}!",
+                        args["name"].as_str().unwrap_or("stranger")
+                    ))]))
+                })
+            },
+        );
+    }
+}
+
+impl ServerHandler for PluginServer {
+    rmcp::tool_box!(@derive_router router);
+
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .build(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct ListChangedClient {
+    list_changed: Arc<AtomicUsize>,
+    received: Arc<Notify>,
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl ClientHandler for ListChangedClient {
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo::default()
+    }
+
+    async fn on_tool_list_changed(&self) {
+        self.list_changed.fetch_add(1, Ordering::SeqCst);
+        self.received.notify_one();
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+}
+
+fn tool_names(tools: &[Tool]) -> Vec<&str> {
+    let mut names: Vec<&str> = tools.iter().map(|tool| &*tool.name).collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_router_merge_and_remove() {
+    let server = PluginServer::new();
+    let plugins = ToolRouter::<PluginServer>::new();
+    plugins.route(
+        "noop",
+        Tool::new("", "Do nothing", serde_json::Map::new()),
+        |_| Box::pin(async { Ok(CallToolResult::success(vec![])) }),
+    );
+    server.router.merge(plugins.clone());
+    assert!(plugins.list().is_empty());
+    assert_eq!(tool_names(&server.router.list()), ["noop", "sum"]);
+
+    let removed = server.router.remove("noop").unwrap();
+    assert_eq!(removed.name, "noop");
+    assert!(server.router.remove("noop").is_none());
+    assert!(!server.router.contains("noop"));
+    assert!(server.router.contains("sum"));
+}
+
+#[tokio::test]
+async fn test_tool_router() -> anyhow::Result<()> {
+    let server = PluginServer::new();
+    server.load_greeting("hello", "Hello");
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let server_handle = tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.serve(server_transport).await?.waiting().await?;
+            anyhow::Ok(())
+        }
+    });
+
+    let handler = ListChangedClient::default();
+    let list_changed = handler.list_changed.clone();
+    let received = handler.received.clone();
+    let client = handler.serve(client_transport).await?;
+
+    let tools = client.list_all_tools().await?;
+    assert_eq!(tool_names(&tools), ["hello", "sum"]);
+
+    // static and dynamic tools are called through the same router
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "sum".into(),
+            arguments: serde_json::json!({ "a": 1, "b": 2 })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "3");
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "hello".into(),
+            arguments: serde_json::json!({ "name": "rmcp" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "Hello, rmcp!");
+
+    // the client listed the tools, so it's told about changes
+    let wait_list_changed = |count: usize| {
+        let list_changed = list_changed.clone();
+        let received = received.clone();
+        tokio::time::timeout(std::time::Duration::from_secs(5), async move {
+            while list_changed.load(Ordering::SeqCst) < count {
+                received.notified().await;
+            }
+        })
+    };
+    server.router.remove("hello");
+    wait_list_changed(1).await?;
+    let tools = client.list_all_tools().await?;
+    assert_eq!(tool_names(&tools), ["sum"]);
+    assert!(
+        client
+            .call_tool(CallToolRequestParam {
+                name: "hello".into(),
+                arguments: None,
+            })
+            .await
+            .is_err()
+    );
+
+    server.load_greeting("bonjour", "Bonjour");
+    wait_list_changed(2).await?;
+    let tools = client.list_all_tools().await?;
+    assert_eq!(tool_names(&tools), ["bonjour", "sum"]);
+
+    client.cancel().await?;
+    server_handle.await??;
+    Ok(())
+}
@@ -0,0 +1,152 @@
+//cargo test --test test_tool_sampling --features "client server macros"
+
+use std::sync::{Arc, Mutex};
+
+use rmcp::{
+    ClientHandler, Peer, RoleClient, RoleServer, ServiceExt,
+    model::{
+        CallToolRequestParam, CallToolResult, ClientCapabilities, ClientInfo, Content,
+        CreateMessageRequestParam, CreateMessageResult, ModelHint, ModelPreferences,
+        SamplingMessage,
+    },
+    service::{RequestContext, ServiceError},
+    tool,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct SummaryServer;
+
+#[tool(tool_box)]
+impl SummaryServer {
+    #[tool(description = "Summarize a text with the client's model")]
+    async fn summarize(
+        &self,
+        peer: Peer<RoleServer>,
+        #[tool(param)] text: String,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let request = CreateMessageRequestParam::new(vec![SamplingMessage::user_text(text)], 100)
+            .with_system_prompt("You summarize texts in one sentence")
+            .with_model_preferences(ModelPreferences {
+                hints: Some(vec![ModelHint {
+                    name: Some("claude-3-haiku".into()),
+                }]),
+                cost_priority: None,
+                speed_priority: Some(1.0),
+                intelligence_priority: None,
+            });
+        let result = peer.create_message(request).await?;
+        Ok(CallToolResult::success(vec![result.message.content]))
+    }
+}
+
+/// Answers sampling requests without a model
+#[derive(Clone, Default)]
+struct MockSamplingClient {
+    requests: Arc<Mutex<Vec<CreateMessageRequestParam>>>,
+    peer: Option<Peer<RoleClient>>,
+}
+
+impl ClientHandler for MockSamplingClient {
+    fn get_peer(&self) -> Option<Peer<RoleClient>> {
+        self.peer.clone()
+    }
+
+    fn set_peer(&mut self, peer: Peer<RoleClient>) {
+        self.peer = Some(peer);
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            capabilities: ClientCapabilities::builder().enable_sampling().build(),
+            ..Default::default()
+        }
+    }
+
+    async fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+        _context: RequestContext<rmcp::RoleClient>,
+    ) -> Result<CreateMessageResult, rmcp::Error> {
+        let text = params.messages[0].content.as_text().unwrap().text.clone();
+        self.requests.lock().unwrap().push(params);
+        Ok(CreateMessageResult {
+            model: "mock-model".into(),
+            stop_reason: Some(CreateMessageResult::STOP_REASON_END_TURN.into()),
+            message: SamplingMessage::assistant_text(format!(
+                "a text of {} words",
+                text.split_whitespace().count()
+            )),
+            meta: None,
+        })
+    }
+}
+
+fn summarize_request() -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: "summarize".into(),
+        arguments: serde_json::json!({ "text": "the quick brown fox jumps" })
+            .as_object()
+            .cloned()
+            .map(Into::into),
+    }
+}
+
+#[tokio::test]
+async fn test_sampling_from_tool() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        SummaryServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let handler = MockSamplingClient::default();
+    let requests = handler.requests.clone();
+    let client = handler.serve(client_transport).await?;
+
+    let result = client.call_tool(summarize_request()).await?;
+    assert_eq!(result.content, vec![Content::text("a text of 5 words")]);
+
+    let requests = std::mem::take(&mut *requests.lock().unwrap());
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].system_prompt.as_deref(),
+        Some("You summarize texts in one sentence")
+    );
+    assert_eq!(requests[0].max_tokens, 100);
+    let preferences = requests[0].model_preferences.as_ref().unwrap();
+    assert_eq!(preferences.speed_priority, Some(1.0));
+    assert_eq!(
+        preferences.hints.as_ref().unwrap()[0].name.as_deref(),
+        Some("claude-3-haiku")
+    );
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sampling_without_capability() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        SummaryServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    // a client without the sampling capability
+    let client = ().serve(client_transport).await?;
+
+    let Err(ServiceError::McpError(error)) = client.call_tool(summarize_request()).await else {
+        panic!("sampling should fail without the capability");
+    };
+    assert_eq!(error.message, "client doesn't support sampling");
+    assert_eq!(error.data.unwrap()["capability"], "sampling");
+
+    client.cancel().await?;
+    Ok(())
+}
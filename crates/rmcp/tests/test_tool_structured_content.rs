@@ -0,0 +1,119 @@
+//cargo test --test test_tool_structured_content --features "client server macros schema-validation"
+
+use rmcp::{
+    ServiceExt,
+    handler::server::wrapper::Json,
+    model::{CallToolRequestParam, CallToolResult},
+    tool,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Forecast {
+    pub city: String,
+    pub days: Vec<Day>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Day {
+    pub high: f64,
+    pub low: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ForecastServer;
+
+#[tool(tool_box)]
+impl ForecastServer {
+    #[tool(description = "Forecast the weather of a city")]
+    async fn forecast(&self, #[tool(param)] city: String) -> Result<Json<Forecast>, String> {
+        if city.is_empty() {
+            return Err("unknown city".into());
+        }
+        Ok(Json(Forecast {
+            city,
+            days: vec![
+                Day {
+                    high: 21.5,
+                    low: 12.0,
+                },
+                Day {
+                    high: 19.0,
+                    low: 11.5,
+                },
+            ],
+        }))
+    }
+
+    #[tool(description = "Return content not matching its schema", output = Forecast)]
+    async fn broken_forecast(&self) -> CallToolResult {
+        CallToolResult::structured(serde_json::json!({ "city": 42 }))
+    }
+}
+
+#[tokio::test]
+async fn test_structured_content() -> anyhow::Result<()> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        ForecastServer
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "forecast".into(),
+            arguments: serde_json::json!({ "city": "Oslo" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.is_error, Some(false));
+    let structured = result.structured_content.clone().unwrap();
+    // older clients read the same json from the text block
+    let fallback: serde_json::Value =
+        serde_json::from_str(&result.content[0].as_text().unwrap().text)?;
+    assert_eq!(fallback, structured);
+    let forecast: Forecast = serde_json::from_value(structured)?;
+    assert_eq!(forecast.city, "Oslo");
+    assert_eq!(forecast.days.len(), 2);
+    assert_eq!(
+        forecast.days[0],
+        Day {
+            high: 21.5,
+            low: 12.0
+        }
+    );
+
+    // errors stay plain content
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "forecast".into(),
+            arguments: serde_json::json!({ "city": "" })
+                .as_object()
+                .cloned()
+                .map(Into::into),
+        })
+        .await?;
+    assert_eq!(result.is_error, Some(true));
+    assert!(result.structured_content.is_none());
+    assert_eq!(result.content[0].as_text().unwrap().text, "unknown city");
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "broken_forecast".into(),
+            arguments: None,
+        })
+        .await;
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("structured content doesn't match the output schema"));
+
+    client.cancel().await?;
+    Ok(())
+}
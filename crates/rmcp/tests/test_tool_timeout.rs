@@ -0,0 +1,119 @@
+//cargo test --test test_tool_timeout --features "client server macros"
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    RoleClient, ServerHandler, ServiceExt,
+    model::CallToolRequestParam,
+    service::{RunningService, ServiceError},
+    tool,
+};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Clone, Default)]
+pub struct SlowServer {
+    cleaned_up: Arc<AtomicBool>,
+}
+
+#[tool(tool_box)]
+impl SlowServer {
+    #[tool(description = "Sleep past its deadline", timeout_ms = 100)]
+    async fn hang(&self, ct: CancellationToken) -> String {
+        let cleaned_up = self.cleaned_up.clone();
+        tokio::spawn(async move {
+            ct.cancelled().await;
+            cleaned_up.store(true, Ordering::SeqCst);
+        });
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        "done".into()
+    }
+
+    #[tool(description = "Finish just under its deadline", timeout_ms = 1000)]
+    async fn almost(&self) -> String {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        "done".into()
+    }
+
+    #[tool(description = "Sleep without a timeout of its own")]
+    async fn nap(&self) -> String {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        "done".into()
+    }
+}
+
+async fn serve(
+    server: impl rmcp::Service<rmcp::RoleServer>,
+) -> anyhow::Result<RunningService<RoleClient, ()>> {
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        server.serve(server_transport).await?.waiting().await?;
+        anyhow::Ok(())
+    });
+    Ok(().serve(client_transport).await?)
+}
+
+async fn call(
+    client: &RunningService<RoleClient, ()>,
+    name: &'static str,
+) -> Result<String, ServiceError> {
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: name.into(),
+            arguments: None,
+        })
+        .await?;
+    Ok(result.content[0].as_text().unwrap().text.clone())
+}
+
+fn assert_timed_out(result: Result<String, ServiceError>, tool: &str, timeout_ms: u64) {
+    let Err(ServiceError::McpError(error)) = result else {
+        panic!("expected a timeout error, got {result:?}");
+    };
+    assert_eq!(error.message, "tool timed out");
+    let data = error.data.unwrap();
+    assert_eq!(data["tool"], tool);
+    assert_eq!(data["timeout_ms"], timeout_ms);
+    assert!(data["elapsed_ms"].as_u64().unwrap() >= timeout_ms);
+}
+
+#[tokio::test]
+async fn test_tool_timeout() -> anyhow::Result<()> {
+    let server = SlowServer::default();
+    let cleaned_up = server.cleaned_up.clone();
+    let client = serve(server).await?;
+
+    assert_timed_out(call(&client, "hang").await, "hang", 100);
+    // the request's cancellation token fires with the timeout
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !cleaned_up.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+
+    assert_eq!(call(&client, "almost").await?, "done");
+    // no default timeout was installed
+    assert_eq!(call(&client, "nap").await?, "done");
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_tool_timeout() -> anyhow::Result<()> {
+    let server = SlowServer::default().with_tool_timeout(Duration::from_millis(100));
+    let client = serve(server).await?;
+
+    assert_timed_out(call(&client, "nap").await, "nap", 100);
+    // a tool's own timeout takes precedence over the default
+    assert_eq!(call(&client, "almost").await?, "done");
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//cargo test --test test_tool_validation --features "client server macros client-schema-validation"
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use rmcp::{
+    ServiceExt,
+    model::{CallToolRequestParam, RequestId},
+    service::{MessageDirection, ServiceError, ServiceObserver},
+    tool,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MoveRequest {
+    pub from: Point,
+    pub to: Point,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Server;
+
+#[tool(tool_box)]
+impl Server {
+    #[tool(description = "Sum two numbers")]
+    async fn sum(&self, a: i64, b: i64) -> String {
+        (a + b).to_string()
+    }
+
+    #[tool(description = "Move a point")]
+    async fn travel(&self, #[tool(aggr)] request: MoveRequest) -> String {
+        (request.to.x - request.from.x + request.to.y - request.from.y).to_string()
+    }
+}
+
+/// Counts the tool calls received by the server
+#[derive(Debug, Clone, Default)]
+struct ToolCalls(Arc<AtomicUsize>);
+
+impl ServiceObserver for ToolCalls {
+    fn on_request_start(&self, method: &str, _id: &RequestId, direction: MessageDirection) {
+        if method == "tools/call" && direction == MessageDirection::Inbound {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+fn call(name: &'static str, arguments: serde_json::Value) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: arguments.as_object().cloned().map(Into::into),
+    }
+}
+
+#[tokio::test]
+async fn test_tool_arguments_validated_locally() -> anyhow::Result<()> {
+    let calls = ToolCalls::default();
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    let observer = calls.clone();
+    tokio::spawn(async move {
+        Server
+            .with_observer(observer)
+            .serve(server_transport)
+            .await?
+            .waiting()
+            .await?;
+        anyhow::Ok(())
+    });
+    let client = ().serve(client_transport).await?;
+    client.enable_tool_validation();
+    // the clones of the peer share the session
+    assert!(client.peer().clone().is_tool_validation_enabled());
+
+    // a missing required field fails without reaching the server
+    let result = client.call_tool(call("sum", json!({ "a": 1 }))).await;
+    let Err(ServiceError::SchemaViolation(violation)) = result else {
+        panic!("expected a schema violation, got {result:?}");
+    };
+    assert_eq!(violation.tool, "sum");
+    assert_eq!(violation.errors.len(), 1);
+    assert_eq!(violation.errors[0].path, "");
+    assert!(violation.errors[0].message.contains("\"b\""));
+    assert_eq!(calls.0.load(Ordering::SeqCst), 0);
+
+    // nested types are checked through the `$ref`s of the schema
+    let result = client
+        .call_tool(call(
+            "travel",
+            json!({ "from": { "x": 0, "y": 0 }, "to": { "x": "far", "y": 1 } }),
+        ))
+        .await;
+    let Err(ServiceError::SchemaViolation(violation)) = result else {
+        panic!("expected a schema violation, got {result:?}");
+    };
+    assert_eq!(violation.errors[0].path, "/to/x");
+    assert_eq!(calls.0.load(Ordering::SeqCst), 0);
+
+    // valid calls, and calls of unknown tools, are sent
+    let result = client
+        .call_tool(call("sum", json!({ "a": 1, "b": 2 })))
+        .await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "3");
+    let result = client.call_tool(call("missing", json!({}))).await;
+    assert!(matches!(result, Err(ServiceError::McpError(_))));
+    assert_eq!(calls.0.load(Ordering::SeqCst), 2);
+
+    // without validation the invalid call reaches the server
+    client.disable_tool_validation();
+    let result = client.call_tool(call("sum", json!({ "a": 1 }))).await;
+    assert!(matches!(result, Err(ServiceError::McpError(_))));
+    assert_eq!(calls.0.load(Ordering::SeqCst), 3);
+
+    client.cancel().await?;
+    Ok(())
+}
@@ -0,0 +1,33 @@
+//cargo test --test test_transport_conformance --features "client server"
+use futures::{SinkExt, channel::mpsc};
+use rmcp::{
+    model::{ClientJsonRpcMessage, ServerJsonRpcMessage},
+    transport::{conformance::check_transport, in_process},
+};
+
+#[tokio::test]
+async fn test_in_process() -> anyhow::Result<()> {
+    check_transport(|| async { in_process::pair() }).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_async_rw() -> anyhow::Result<()> {
+    check_transport(|| async { tokio::io::duplex(4096) }).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sink_stream() -> anyhow::Result<()> {
+    // a message bus carrying the messages themselves
+    check_transport(|| async {
+        let (to_server, from_client) = mpsc::channel::<ClientJsonRpcMessage>(16);
+        let (to_client, from_server) = mpsc::channel::<ServerJsonRpcMessage>(16);
+        (
+            (to_client.sink_map_err(std::io::Error::other), from_client),
+            (to_server.sink_map_err(std::io::Error::other), from_server),
+        )
+    })
+    .await?;
+    Ok(())
+}
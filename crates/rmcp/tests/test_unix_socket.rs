@@ -0,0 +1,104 @@
+//cargo test --test test_unix_socket --features "client server macros transport-unix-socket"
+#![cfg(unix)]
+
+use std::{
+    os::unix::fs::PermissionsExt,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+
+use rmcp::{
+    ServiceExt,
+    model::CallToolRequestParam,
+    tool,
+    transport::unix_socket::{self, UnixSocketConfig, UnixSocketServer},
+};
+
+/// A counter of its own for every connection
+#[derive(Debug, Clone, Default)]
+pub struct Counter {
+    count: Arc<AtomicU32>,
+}
+
+#[tool(tool_box)]
+impl Counter {
+    #[tool(description = "Increment the counter, returning its value")]
+    async fn increment(&self) -> String {
+        // let the other client interleave its calls
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        (self.count.fetch_add(1, Ordering::SeqCst) + 1).to_string()
+    }
+}
+
+fn socket_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("rmcp-{name}-{}.sock", std::process::id()))
+}
+
+fn increment() -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: "increment".into(),
+        arguments: None,
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_clients() -> anyhow::Result<()> {
+    let path = socket_path("concurrent");
+    let server = UnixSocketServer::bind_with_config(UnixSocketConfig::new(&path).with_mode(0o600))?;
+    assert_eq!(
+        std::fs::metadata(&path)?.permissions().mode() & 0o777,
+        0o600
+    );
+    let ct = server.with_service(Counter::default);
+
+    let first = ().serve(unix_socket::connect_client(&path).await?).await?;
+    let second = ().serve(unix_socket::connect_client(&path).await?).await?;
+    let count = |client: &rmcp::service::RunningService<rmcp::RoleClient, ()>, times: usize| {
+        let peer = client.peer().clone();
+        async move {
+            let mut last = String::new();
+            for _ in 0..times {
+                let result = peer.call_tool(increment()).await?;
+                last = result.content[0].as_text().unwrap().text.clone();
+            }
+            anyhow::Ok(last)
+        }
+    };
+    let (first_count, second_count) = tokio::try_join!(count(&first, 3), count(&second, 5))?;
+    assert_eq!(first_count, "3");
+    assert_eq!(second_count, "5");
+
+    first.cancel().await?;
+    second.cancel().await?;
+
+    // the socket file goes away with the server
+    ct.cancel();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while path.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stale_socket_file_is_replaced() -> anyhow::Result<()> {
+    let path = socket_path("stale");
+    // left over by a server that didn't clean up
+    let stale = std::os::unix::net::UnixListener::bind(&path)?;
+    drop(stale);
+    assert!(path.exists());
+
+    let ct = unix_socket::serve_server(&path, Counter::default)?;
+    let client = ().serve(unix_socket::connect_client(&path).await?).await?;
+    let result = client.call_tool(increment()).await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "1");
+
+    client.cancel().await?;
+    ct.cancel();
+    Ok(())
+}
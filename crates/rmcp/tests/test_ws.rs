@@ -0,0 +1,111 @@
+//cargo test --test test_ws --features "client server macros transport-ws-axum"
+
+use std::{net::SocketAddr, time::Duration};
+
+use axum::{Router, extract::ws::WebSocketUpgrade, routing::get};
+use rmcp::{
+    ServiceExt,
+    model::CallToolRequestParam,
+    service::QuitReason,
+    tool,
+    transport::ws::{self, WebSocketConfig},
+};
+use tokio::net::TcpListener;
+
+#[derive(Debug, Clone, Default)]
+pub struct Echo;
+
+#[tool(tool_box)]
+impl Echo {
+    #[tool(description = "Repeat the text")]
+    fn echo(&self, #[tool(param)] text: String) -> String {
+        text
+    }
+}
+
+fn echo(text: &str) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: "echo".into(),
+        arguments: serde_json::json!({ "text": text })
+            .as_object()
+            .cloned()
+            .map(Into::into),
+    }
+}
+
+async fn serve_axum(config: WebSocketConfig) -> anyhow::Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let router = Router::new().route(
+        "/mcp",
+        get(move |upgrade: WebSocketUpgrade| {
+            let config = config.clone();
+            async move { ws::serve_upgrade(upgrade, Echo, config) }
+        }),
+    );
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    Ok(addr)
+}
+
+#[tokio::test]
+async fn test_tool_call_over_axum_websocket() -> anyhow::Result<()> {
+    let addr = serve_axum(WebSocketConfig::default()).await?;
+    let client = ().serve(ws::connect(format!("ws://{addr}/mcp")).await?).await?;
+    assert!(client.peer_info().capabilities.tools.is_some());
+
+    let tools = client.list_all_tools().await?;
+    assert_eq!(tools[0].name, "echo");
+    let result = client.call_tool(echo("over websocket")).await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "over websocket");
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_close_frame_stops_server() -> anyhow::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await?;
+        let transport = ws::accept(stream, WebSocketConfig::default()).await?;
+        let quit_reason = Echo.serve(transport).await?.waiting().await?;
+        anyhow::Ok(quit_reason)
+    });
+
+    // keepalive pings are answered by the server
+    let config = WebSocketConfig {
+        ping_interval: Some(Duration::from_millis(20)),
+        ..Default::default()
+    };
+    let client = ().serve(ws::connect_with_config(format!("ws://{addr}"), config).await?).await?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let result = client.call_tool(echo("still alive")).await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "still alive");
+
+    // the client closing the connection ends the server cleanly
+    client.cancel().await?;
+    let quit_reason = tokio::time::timeout(Duration::from_secs(5), server).await???;
+    assert!(matches!(quit_reason, QuitReason::Closed));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_oversized_message_closes_connection() -> anyhow::Result<()> {
+    let addr = serve_axum(WebSocketConfig {
+        max_message_size: 512,
+        ..Default::default()
+    })
+    .await?;
+    let client = ().serve(ws::connect(format!("ws://{addr}/mcp")).await?).await?;
+    let result = client.call_tool(echo("small")).await?;
+    assert_eq!(result.content[0].as_text().unwrap().text, "small");
+
+    let text = "x".repeat(1024);
+    let result =
+        tokio::time::timeout(Duration::from_secs(5), client.call_tool(echo(&text))).await?;
+    assert!(result.is_err());
+    let quit_reason = tokio::time::timeout(Duration::from_secs(5), client.waiting()).await??;
+    assert!(matches!(quit_reason, QuitReason::Closed));
+    Ok(())
+}
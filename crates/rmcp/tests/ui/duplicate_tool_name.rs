@@ -0,0 +1,21 @@
+use rmcp::tool;
+
+#[derive(Debug, Clone)]
+pub struct Server;
+
+#[tool(tool_box)]
+impl Server {
+    #[tool(name = "query")]
+    async fn first(&self) -> String {
+        String::new()
+    }
+
+    #[tool(name = "query")]
+    async fn second(&self) -> String {
+        String::new()
+    }
+}
+
+fn main() {
+    let _ = Server;
+}
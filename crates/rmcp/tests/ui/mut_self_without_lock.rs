@@ -0,0 +1,16 @@
+use rmcp::tool;
+
+#[derive(Debug, Clone, Default)]
+pub struct Counter;
+
+#[tool(tool_box)]
+impl Counter {
+    #[tool(description = "Increment the counter")]
+    fn increment(&mut self) -> String {
+        String::new()
+    }
+}
+
+fn main() {
+    let _ = Counter;
+}
@@ -0,0 +1,132 @@
+//! A client answering the elicitations of a server by prompting in the terminal
+//!
+//! cargo run -p mcp-client-examples --example clients_elicitation -- <file to delete>
+use anyhow::Result;
+use rmcp::{
+    Error as McpError,
+    handler::client::{
+        ClientHandlerBuilder, ElicitationHandler, ElicitationResponder,
+        elicitation::{ElicitationError, ElicitationField},
+    },
+    model::{CallToolRequestParam, CreateElicitationResult, JsonObject},
+    service::ServiceExt,
+    transport::{ConfigureCommandExt, TokioChildProcess},
+};
+use serde_json::Value;
+use tokio::process::Command;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+/// Asks the user for each field of the requested form on stdin
+struct TerminalPrompt;
+
+impl ElicitationHandler for TerminalPrompt {
+    async fn elicit(
+        &self,
+        request: ElicitationResponder,
+    ) -> Result<CreateElicitationResult, McpError> {
+        println!("The server asks: {}", request.message());
+        match read_line("[a]nswer, [d]ecline or [c]ancel?")
+            .await?
+            .as_str()
+        {
+            "d" | "decline" => return Ok(request.decline()),
+            "a" | "answer" => {}
+            _ => return Ok(request.cancel()),
+        }
+        loop {
+            let mut content = JsonObject::new();
+            for field in request.fields() {
+                if let Some(value) = prompt_field(&field).await? {
+                    content.insert(field.name, value);
+                }
+            }
+            match request.accept(content) {
+                Ok(result) => return Ok(result),
+                Err(ElicitationError::Invalid { errors }) => {
+                    println!("Invalid answer: {}", errors.join(", "));
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+}
+
+/// The value of `field` typed by the user, none if left empty
+async fn prompt_field(field: &ElicitationField) -> Result<Option<Value>, McpError> {
+    let mut question = field.title.clone().unwrap_or_else(|| field.name.clone());
+    if let Some(description) = &field.description {
+        question.push_str(&format!(" ({description})"));
+    }
+    if let Some(options) = &field.options {
+        question.push_str(&format!(" {}", Value::from(options.clone())));
+    }
+    if !field.required {
+        question.push_str(" [optional]");
+    }
+    let answer = read_line(&question).await?;
+    if answer.is_empty() {
+        return Ok(None);
+    }
+    let value = match field.kind.as_deref() {
+        Some("boolean") => Value::Bool(matches!(answer.as_str(), "y" | "yes" | "true")),
+        Some("integer" | "number") => {
+            serde_json::from_str(&answer).unwrap_or(Value::String(answer))
+        }
+        _ => Value::String(answer),
+    };
+    Ok(Some(value))
+}
+
+async fn read_line(question: &str) -> Result<String, McpError> {
+    println!("{question}");
+    tokio::task::spawn_blocking(|| {
+        let mut line = String::new();
+        std::io::stdin()
+            .read_line(&mut line)
+            .map(|_| line.trim().to_string())
+    })
+    .await
+    .map_err(|error| McpError::internal_error(error.to_string(), None))?
+    .map_err(|error| McpError::internal_error(error.to_string(), None))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| format!("info,{}=debug", env!("CARGO_CRATE_NAME")).into()),
+        )
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .init();
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "elicitation-example.txt".to_string());
+
+    let client = ClientHandlerBuilder::new()
+        .with_elicitation(TerminalPrompt)
+        .build()
+        .serve(TokioChildProcess::new(Command::new("cargo").configure(
+            |cmd| {
+                cmd.args([
+                    "run",
+                    "-q",
+                    "-p",
+                    "mcp-server-examples",
+                    "--example",
+                    "servers_elicitation",
+                ]);
+            },
+        ))?)
+        .await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "delete_file".into(),
+            arguments: serde_json::json!({ "path": path }).as_object().cloned(),
+        })
+        .await?;
+    tracing::info!("Tool result: {result:#?}");
+    client.cancel().await?;
+    Ok(())
+}
@@ -40,7 +40,7 @@ async fn main() -> Result<()> {
     let tool_result = service
         .call_tool(CallToolRequestParam {
             name: "echo".into(),
-            arguments: Some(object!({ "message": "hi from rmcp" })),
+            arguments: Some(object!({ "message": "hi from rmcp" }).into()),
         })
         .await?;
     tracing::info!("Tool result for echo: {tool_result:#?}");
@@ -49,7 +49,7 @@ async fn main() -> Result<()> {
     let tool_result = service
         .call_tool(CallToolRequestParam {
             name: "longRunningOperation".into(),
-            arguments: Some(object!({ "duration": 3, "steps": 1 })),
+            arguments: Some(object!({ "duration": 3, "steps": 1 }).into()),
         })
         .await?;
     tracing::info!("Tool result for longRunningOperation: {tool_result:#?}");
@@ -83,7 +83,7 @@ async fn main() -> Result<()> {
     let prompt = service
         .get_prompt(GetPromptRequestParam {
             name: "complex_prompt".into(),
-            arguments: Some(object!({ "temperature": "0.5", "style": "formal" })),
+            arguments: Some(object!({ "temperature": "0.5", "style": "formal" }).into()),
         })
         .await?;
     tracing::info!("Prompt - complex: {prompt:#?}");
@@ -40,7 +40,7 @@ async fn main() -> Result<()> {
     let tool_result = client
         .call_tool(CallToolRequestParam {
             name: "increment".into(),
-            arguments: serde_json::json!({}).as_object().cloned(),
+            arguments: serde_json::json!({}).as_object().cloned().map(Into::into),
         })
         .await?;
     tracing::info!("Tool result: {tool_result:#?}");
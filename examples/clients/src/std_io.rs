@@ -39,7 +39,10 @@ async fn main() -> Result<()> {
     let tool_result = service
         .call_tool(CallToolRequestParam {
             name: "git_status".into(),
-            arguments: serde_json::json!({ "repo_path": "." }).as_object().cloned(),
+            arguments: serde_json::json!({ "repo_path": "." })
+                .as_object()
+                .cloned()
+                .map(Into::into),
         })
         .await?;
     tracing::info!("Tool result: {tool_result:#?}");
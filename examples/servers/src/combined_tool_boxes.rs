@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use rmcp::{
+    ServerHandler, ServiceExt,
+    model::{ServerCapabilities, ServerInfo},
+    tool,
+    transport::stdio,
+};
+use tracing_subscriber::{self, EnvFilter};
+
+/// A workspace server whose tools are split across three impl blocks
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    root: PathBuf,
+}
+
+#[tool(tool_box = file_tools, default_build = false)]
+impl Workspace {
+    #[tool(description = "List the entries of a directory of the workspace")]
+    async fn list_dir(&self, #[tool(param)] path: String) -> String {
+        match std::fs::read_dir(self.root.join(path)) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Err(error) => format!("failed to read directory: {error}"),
+        }
+    }
+
+    #[tool(description = "Read a file of the workspace")]
+    async fn read_file(&self, #[tool(param)] path: String) -> String {
+        std::fs::read_to_string(self.root.join(path))
+            .unwrap_or_else(|error| format!("failed to read file: {error}"))
+    }
+}
+
+#[tool(tool_box = git_tools, default_build = false)]
+impl Workspace {
+    #[tool(description = "Show the working tree status")]
+    async fn git_status(&self) -> String {
+        let output = std::process::Command::new("git")
+            .arg("status")
+            .arg("--short")
+            .current_dir(&self.root)
+            .output();
+        match output {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).into_owned(),
+            Err(error) => format!("failed to run git: {error}"),
+        }
+    }
+}
+
+#[tool(tool_box = workspace_tools, default_build = false)]
+impl Workspace {
+    #[tool(description = "Show the root of the workspace")]
+    fn root(&self) -> String {
+        self.root.display().to_string()
+    }
+}
+
+impl Workspace {
+    rmcp::tool_box!(combine Workspace { file_tools, git_tools, workspace_tools } tool_box);
+}
+
+impl ServerHandler for Workspace {
+    rmcp::tool_box!(@derive);
+
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            instructions: Some("Browse the files and the git status of a workspace".into()),
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            ..Default::default()
+        }
+    }
+}
+
+/// npx @modelcontextprotocol/inspector cargo run -p mcp-server-examples --example servers_combined_tool_boxes
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::DEBUG.into()))
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+
+    let workspace = Workspace {
+        root: std::env::current_dir()?,
+    };
+    let service = workspace.serve(stdio()).await.inspect_err(|e| {
+        tracing::error!("serving error: {:?}", e);
+    })?;
+
+    service.waiting().await?;
+    Ok(())
+}
@@ -90,6 +90,7 @@ impl ServerHandler for Counter {
                 .build(),
             server_info: Implementation::from_build_env(),
             instructions: Some("This server provides a counter tool that can increment and decrement values. The counter starts at 0 and can be modified using the 'increment' and 'decrement' tools. Use 'get_value' to check the current count.".to_string()),
+            meta: None,
         }
     }
 
@@ -104,6 +105,7 @@ impl ServerHandler for Counter {
                 self._create_resource_text("memo://insights", "memo-name"),
             ],
             next_cursor: None,
+            meta: None,
         })
     }
 
@@ -117,12 +119,14 @@ impl ServerHandler for Counter {
                 let cwd = "/Users/to/some/path/";
                 Ok(ReadResourceResult {
                     contents: vec![ResourceContents::text(cwd, uri)],
+                    meta: None,
                 })
             }
             "memo://insights" => {
                 let memo = "Business Intelligence Memo\n\nAnalysis has revealed 5 key insights ...";
                 Ok(ReadResourceResult {
                     contents: vec![ResourceContents::text(memo, uri)],
+                    meta: None,
                 })
             }
             _ => Err(McpError::resource_not_found(
@@ -141,15 +145,14 @@ impl ServerHandler for Counter {
     ) -> Result<ListPromptsResult, McpError> {
         Ok(ListPromptsResult {
             next_cursor: None,
-            prompts: vec![Prompt::new(
-                "example_prompt",
-                Some("This is an example prompt that takes one required argument, message"),
-                Some(vec![PromptArgument {
-                    name: "message".to_string(),
-                    description: Some("A message to put in the prompt".to_string()),
-                    required: Some(true),
-                }]),
-            )],
+            prompts: vec![
+                Prompt::new("example_prompt")
+                    .with_description(
+                        "This is an example prompt that takes one required argument, message",
+                    )
+                    .arg("message", "A message to put in the prompt", true),
+            ],
+            meta: None,
         })
     }
 
@@ -174,9 +177,10 @@ impl ServerHandler for Counter {
                         role: PromptMessageRole::User,
                         content: PromptMessageContent::text(prompt),
                     }],
+                    meta: None,
                 })
             }
-            _ => Err(McpError::invalid_params("prompt not found", None)),
+            _ => Err(McpError::prompt_not_found(&name)),
         }
     }
 
@@ -188,6 +192,7 @@ impl ServerHandler for Counter {
         Ok(ListResourceTemplatesResult {
             next_cursor: None,
             resource_templates: Vec::new(),
+            meta: None,
         })
     }
 
@@ -0,0 +1,75 @@
+//! A server asking the user to confirm before deleting a file, with `elicitation/create`
+//!
+//! npx @modelcontextprotocol/inspector cargo run -p mcp-server-examples --example servers_elicitation
+use anyhow::Result;
+use rmcp::{
+    Error as McpError, Peer, RoleServer, ServerHandler, ServiceExt,
+    model::{CallToolResult, Content, ServerCapabilities, ServerInfo},
+    schemars,
+    service::ElicitationOutcome,
+    tool,
+    transport::stdio,
+};
+use tracing_subscriber::{self, EnvFilter};
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct Confirmation {
+    #[schemars(description = "Delete the file for good")]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileCleaner;
+
+#[tool(tool_box, default_build = false)]
+impl FileCleaner {
+    #[tool(description = "Delete a file, once the user confirmed it")]
+    async fn delete_file(
+        &self,
+        peer: Peer<RoleServer>,
+        #[tool(param)]
+        #[schemars(description = "path of the file to delete")]
+        path: String,
+    ) -> Result<CallToolResult, McpError> {
+        let outcome = peer
+            .elicit::<Confirmation>(format!("Delete {path}? This can't be undone."))
+            .await?;
+        let text = match outcome {
+            ElicitationOutcome::Accepted(Confirmation { confirm: true }) => {
+                std::fs::remove_file(&path)
+                    .map_err(|error| McpError::internal_error(error.to_string(), None))?;
+                format!("deleted {path}")
+            }
+            ElicitationOutcome::Accepted(Confirmation { confirm: false })
+            | ElicitationOutcome::Declined => format!("the user chose to keep {path}"),
+            ElicitationOutcome::Cancelled => "the user dismissed the confirmation".to_string(),
+        };
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+}
+
+#[tool(tool_box)]
+impl ServerHandler for FileCleaner {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            instructions: Some("Deletes files after asking the user for confirmation".into()),
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            ..Default::default()
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::DEBUG.into()))
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+
+    let service = FileCleaner.serve(stdio()).await.inspect_err(|e| {
+        tracing::error!("serving error: {:?}", e);
+    })?;
+    service.waiting().await?;
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! A server exposing one file as a resource and pushing `notifications/resources/updated`
+//! to the subscribed clients whenever it changes on disk
+//!
+//! cargo run -p mcp-server-examples --example servers_file_subscriptions -- notes.txt
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use rmcp::{
+    Error as McpError, RoleServer, ServerHandler, ServiceExt,
+    handler::server::subscription::SubscriptionManager, model::*, service::RequestContext,
+    transport::stdio,
+};
+use serde_json::json;
+use tokio::sync::mpsc;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Clone)]
+struct FileServer {
+    path: PathBuf,
+}
+
+impl FileServer {
+    fn uri(&self) -> String {
+        format!("file://{}", self.path.display())
+    }
+}
+
+impl ServerHandler for FileServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder().enable_resources().build(),
+            server_info: Implementation::from_build_env(),
+            instructions: Some(format!(
+                "Subscribe to {} to be told when the file changes",
+                self.uri()
+            )),
+            ..Default::default()
+        }
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(ListResourcesResult {
+            resources: vec![RawResource::new(self.uri(), name).no_annotation()],
+            next_cursor: None,
+            meta: None,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        ReadResourceRequestParam { uri }: ReadResourceRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        if uri != self.uri() {
+            return Err(McpError::resource_not_found(
+                "resource_not_found",
+                Some(json!({ "uri": uri })),
+            ));
+        }
+        let text = std::fs::read_to_string(&self.path)
+            .map_err(|error| McpError::internal_error(error.to_string(), None))?;
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(text, uri)],
+            meta: None,
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::DEBUG.into()))
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+
+    let path = std::env::args()
+        .nth(1)
+        .context("usage: servers_file_subscriptions <file>")?;
+    let path = std::fs::canonicalize(path)?;
+    let server = FileServer { path: path.clone() };
+    let uri = server.uri();
+
+    // editors often replace a file instead of writing it, so watch its directory
+    let (changes_tx, mut changes_rx) = mpsc::unbounded_channel();
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                let _ = changes_tx.send(event.paths);
+            }
+            _ => {}
+        })?;
+    let directory = path.parent().context("the file has no parent directory")?;
+    watcher.watch(directory, RecursiveMode::NonRecursive)?;
+
+    let subscriptions = SubscriptionManager::new();
+    tokio::spawn({
+        let subscriptions = subscriptions.clone();
+        async move {
+            while let Some(paths) = changes_rx.recv().await {
+                if paths.contains(&path) {
+                    let notified = subscriptions.notify_updated(&uri).await;
+                    tracing::info!(%uri, notified, "file changed");
+                }
+            }
+        }
+    });
+
+    let service = server
+        .with_subscriptions(subscriptions)
+        .serve(stdio())
+        .await
+        .inspect_err(|e| {
+            tracing::error!("serving error: {:?}", e);
+        })?;
+    service.waiting().await?;
+    Ok(())
+}
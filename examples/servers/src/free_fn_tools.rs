@@ -0,0 +1,97 @@
+use anyhow::Result;
+use rmcp::{
+    Error as McpError, RoleServer, ServerHandler, ServiceExt,
+    handler::server::tool::{ToolBox, ToolCallContext},
+    model::{
+        CallToolRequestParam, CallToolResult, Content, ListToolsResult, PaginatedRequestParam,
+        ServerCapabilities, ServerInfo,
+    },
+    schemars,
+    service::RequestContext,
+    tool, tool_box,
+    transport::stdio,
+};
+use tracing_subscriber::{self, EnvFilter};
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct DivRequest {
+    #[schemars(description = "the dividend")]
+    pub a: f64,
+    #[schemars(description = "the divisor")]
+    pub b: f64,
+}
+
+#[tool(description = "Calculate the sum of two numbers")]
+fn sum(#[tool(param)] a: i32, #[tool(param)] b: i32) -> String {
+    (a + b).to_string()
+}
+
+#[tool(description = "Calculate the product of two numbers")]
+fn mul(#[tool(param)] a: i32, #[tool(param)] b: i32) -> String {
+    (a * b).to_string()
+}
+
+#[tool(description = "Divide two numbers")]
+async fn div(#[tool(aggr)] DivRequest { a, b }: DivRequest) -> Result<CallToolResult, McpError> {
+    if b == 0.0 {
+        return Err(McpError::invalid_params("division by zero", None));
+    }
+    Ok(CallToolResult::success(vec![Content::text(
+        (a / b).to_string(),
+    )]))
+}
+
+/// A server without state, all of its tools are free functions
+#[derive(Debug, Clone)]
+pub struct Arithmetic;
+
+impl Arithmetic {
+    fn tool_box() -> &'static ToolBox<Self> {
+        static TOOL_BOX: std::sync::OnceLock<ToolBox<Arithmetic>> = std::sync::OnceLock::new();
+        TOOL_BOX.get_or_init(|| tool_box!(fns: [sum, mul, div]))
+    }
+}
+
+impl ServerHandler for Arithmetic {
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let context = ToolCallContext::new(self, request, context);
+        Self::tool_box().call(context).await
+    }
+
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Self::tool_box().list_page(self, request)
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            instructions: Some("A calculator built from free function tools".into()),
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            ..Default::default()
+        }
+    }
+}
+
+/// npx @modelcontextprotocol/inspector cargo run -p mcp-server-examples --example servers_free_fn_tools
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::DEBUG.into()))
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+
+    let service = Arithmetic.serve(stdio()).await.inspect_err(|e| {
+        tracing::error!("serving error: {:?}", e);
+    })?;
+
+    service.waiting().await?;
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! A prompt server made with the builders only: the prompts are listed with their arguments, and
+//! a prompt mixes text, an embedded file and an image; the server and the client run in the same
+//! process
+//!
+//! cargo run -p mcp-server-examples --example servers_prompt_builders
+use anyhow::Result;
+use rmcp::{
+    Error as McpError, RoleServer, ServerHandler, model::*, service::RequestContext,
+    transport::in_process,
+};
+use serde_json::json;
+
+/// A 1x1 transparent png
+const PIXEL: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x00, 0x01, 0x00, 0x00,
+    0x05, 0x00, 0x01, 0x0d, 0x0a, 0x2d, 0xb4, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae,
+    0x42, 0x60, 0x82,
+];
+
+#[derive(Debug, Clone)]
+struct ReviewServer;
+
+impl ServerHandler for ReviewServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder().enable_prompts().build(),
+            ..Default::default()
+        }
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        Ok(ListPromptsResult {
+            prompts: vec![
+                Prompt::new("review")
+                    .with_description("Review a file, along with a screenshot of its output")
+                    .arg("path", "file to read", true)
+                    .arg("focus", "what to look at first", false),
+            ],
+            ..Default::default()
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        if request.name != "review" {
+            return Err(McpError::prompt_not_found(&request.name));
+        }
+        let arguments = request.arguments.unwrap_or_default();
+        let path = arguments
+            .get("path")
+            .and_then(|path| path.as_str())
+            .ok_or_else(|| McpError::invalid_params("missing path", None))?;
+        let focus = arguments
+            .get("focus")
+            .and_then(|focus| focus.as_str())
+            .unwrap_or("correctness");
+        Ok(GetPromptResult::builder()
+            .description(format!("A review of {path}"))
+            .message(PromptMessage::user(format!(
+                "Review this file, focusing on {focus}."
+            )))
+            .message(PromptMessage::user(EmbeddedResource::text(
+                format!("file://{path}"),
+                "text/x-rust",
+                "fn main() {\n    println!(\"hello\");\n}\n",
+            )))
+            .message(PromptMessage::new_image(
+                PromptMessageRole::User,
+                PIXEL,
+                "image/png",
+                None,
+            ))
+            .message(PromptMessage::assistant(
+                "I'll go through the file and check it against the output.",
+            ))
+            .build())
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let (server, client) = in_process::serve_directly(ReviewServer, ()).await?;
+
+    for prompt in client.list_all_prompts().await? {
+        println!("{}", serde_json::to_string_pretty(&prompt)?);
+    }
+    let prompt = client
+        .get_prompt(GetPromptRequestParam {
+            name: "review".into(),
+            arguments: Some(object(json!({ "path": "/src/main.rs" }))),
+        })
+        .await?;
+    println!("{}", serde_json::to_string_pretty(&prompt)?);
+
+    client.cancel().await?;
+    server.waiting().await?;
+    Ok(())
+}
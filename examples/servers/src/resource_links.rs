@@ -0,0 +1,141 @@
+//! A tool writing a report to a file and returning a `resource_link` to it, which the client
+//! then reads with `resources/read`; the server and the client run in the same process
+//!
+//! cargo run -p mcp-server-examples --example servers_resource_links
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use anyhow::{Context, Result};
+use rmcp::{
+    Error as McpError, RoleServer, ServerHandler, model::*, service::RequestContext, tool,
+    transport::in_process,
+};
+use serde_json::json;
+
+const REPORT_SCHEME: &str = "report://";
+
+#[derive(Debug, Clone)]
+struct ReportServer {
+    directory: PathBuf,
+    written: Arc<AtomicUsize>,
+}
+
+impl ReportServer {
+    fn path(&self, uri: &str) -> Option<PathBuf> {
+        let id: usize = uri.strip_prefix(REPORT_SCHEME)?.parse().ok()?;
+        Some(self.directory.join(format!("{id}.md")))
+    }
+}
+
+#[tool(tool_box, default_build = false)]
+impl ReportServer {
+    #[tool(description = "Write a report and return a link to it")]
+    async fn write_report(
+        &self,
+        #[tool(param)] title: String,
+        #[tool(param)] body: String,
+    ) -> Result<CallToolResult, McpError> {
+        let id = self.written.fetch_add(1, Ordering::SeqCst) + 1;
+        let uri = format!("{REPORT_SCHEME}{id}");
+        let text = format!("# {title}\n\n{body}\n");
+        let size = text.len() as u32;
+        tokio::fs::write(self.directory.join(format!("{id}.md")), text)
+            .await
+            .map_err(|error| McpError::internal_error(error.to_string(), None))?;
+        let link = RawResource {
+            description: Some(format!("The report \"{title}\"")),
+            mime_type: Some("text/markdown".into()),
+            size: Some(size),
+            ..RawResource::new(uri, format!("report-{id}.md"))
+        };
+        Ok(CallToolResult::success(vec![
+            Content::text(format!("Wrote report {id}")),
+            Content::resource_link(link),
+        ]))
+    }
+}
+
+#[tool(tool_box)]
+impl ServerHandler for ReportServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .build(),
+            server_info: Implementation::from_build_env(),
+            ..Default::default()
+        }
+    }
+
+    async fn read_resource(
+        &self,
+        ReadResourceRequestParam { uri }: ReadResourceRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let not_found =
+            || McpError::resource_not_found("resource_not_found", Some(json!({ "uri": uri })));
+        let path = self.path(&uri).ok_or_else(not_found)?;
+        let text = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|_| not_found())?;
+        Ok(ReadResourceResult::text(uri, "text/markdown", text))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let directory = std::env::temp_dir().join(format!("rmcp-reports-{}", std::process::id()));
+    tokio::fs::create_dir_all(&directory).await?;
+    let server = ReportServer {
+        directory: directory.clone(),
+        written: Default::default(),
+    };
+
+    let (server, client) = in_process::serve_directly(server, ()).await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "write_report".into(),
+            arguments: Some(
+                object(json!({
+                    "title": "Weekly status",
+                    "body": "Everything shipped on time."
+                }))
+                .into(),
+            ),
+        })
+        .await?;
+    let link = result
+        .content
+        .iter()
+        .find_map(|content| content.as_resource_link())
+        .context("the tool returned no resource link")?;
+    println!(
+        "linked {} ({}, {} bytes)",
+        link.uri,
+        link.mime_type.as_deref().unwrap_or("unknown type"),
+        link.size.unwrap_or_default()
+    );
+
+    let report = client
+        .read_resource(ReadResourceRequestParam {
+            uri: link.uri.clone(),
+        })
+        .await?;
+    for contents in report.contents {
+        if let ResourceContents::TextResourceContents { text, .. } = contents {
+            println!("{text}");
+        }
+    }
+
+    client.cancel().await?;
+    server.waiting().await?;
+    tokio::fs::remove_dir_all(directory).await?;
+    Ok(())
+}
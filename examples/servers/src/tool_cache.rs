@@ -0,0 +1,134 @@
+//! A client keeping a cached list of the tools of a server in sync, re-listing them on
+//! `notifications/tools/list_changed`; the server and the client run in the same process
+//!
+//! cargo run -p mcp-server-examples --example servers_tool_cache
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use rmcp::{
+    ServerHandler, ServiceExt,
+    handler::{
+        client::ClientHandlerBuilder,
+        server::tool::{ToolBoxItem, ToolRouter},
+    },
+    model::*,
+    tool,
+};
+use tokio::sync::Notify;
+
+#[derive(Debug, Clone, Default)]
+struct PluginServer {
+    router: ToolRouter<PluginServer>,
+}
+
+#[tool]
+impl PluginServer {
+    #[tool(description = "Echo the input")]
+    async fn echo(&self, #[tool(param)] text: String) -> String {
+        text
+    }
+}
+
+impl ServerHandler for PluginServer {
+    rmcp::tool_box!(@derive_router router);
+
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .build(),
+            server_info: Implementation::from_build_env(),
+            ..Default::default()
+        }
+    }
+}
+
+/// The tools of the server as last listed
+#[derive(Debug, Clone, Default)]
+struct ToolCache {
+    tools: Arc<Mutex<Vec<Tool>>>,
+    refreshed: Arc<Notify>,
+}
+
+impl ToolCache {
+    fn replace(&self, tools: Vec<Tool>) {
+        *self.tools.lock().unwrap() = tools;
+    }
+
+    fn names(&self) -> Vec<String> {
+        let tools = self.tools.lock().unwrap();
+        tools.iter().map(|tool| tool.name.to_string()).collect()
+    }
+
+    async fn wait_refreshed(&self) -> Result<()> {
+        tokio::time::timeout(Duration::from_secs(5), self.refreshed.notified()).await?;
+        Ok(())
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let server = PluginServer::default();
+    server.router.add(ToolBoxItem::new(
+        PluginServer::echo_tool_attr(),
+        |context| Box::pin(PluginServer::echo_tool_call(context)),
+    ));
+    let (server_transport, client_transport) = tokio::io::duplex(4096);
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.serve(server_transport).await?.waiting().await?;
+            anyhow::Ok(())
+        }
+    });
+
+    let cache = ToolCache::default();
+    let client = ClientHandlerBuilder::new()
+        .on_tools_changed({
+            let cache = cache.clone();
+            move |peer| {
+                let cache = cache.clone();
+                async move {
+                    match peer.list_all_tools().await {
+                        Ok(tools) => cache.replace(tools),
+                        Err(error) => tracing::warn!(%error, "failed to refresh the tools"),
+                    }
+                    cache.refreshed.notify_one();
+                }
+            }
+        })
+        .build()
+        .serve(client_transport)
+        .await?;
+
+    cache.replace(client.list_all_tools().await?);
+    println!("tools: {:?}", cache.names());
+
+    // a plugin is loaded at runtime
+    let schema = serde_json::json!({ "type": "object", "properties": {} });
+    server.router.route(
+        "now",
+        Tool::new("", "Tell the time", object(schema)),
+        |_| {
+            Box::pin(async {
+                Ok(CallToolResult::text(format!(
+                    "{:?}",
+                    std::time::SystemTime::now()
+                )))
+            })
+        },
+    );
+    cache.wait_refreshed().await?;
+    println!("tools: {:?}", cache.names());
+
+    server.router.remove("echo");
+    cache.wait_refreshed().await?;
+    println!("tools: {:?}", cache.names());
+
+    client.cancel().await?;
+    Ok(())
+}
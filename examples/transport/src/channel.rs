@@ -0,0 +1,80 @@
+//! MCP over a pair of tokio mpsc channels, the way it goes over any message bus
+use common::calculator::Calculator;
+use rmcp::{
+    RoleClient, RoleServer, ServiceExt,
+    service::{RxJsonRpcMessage, ServiceRole, TxJsonRpcMessage},
+    transport::{Transport, conformance::check_transport},
+};
+use tokio::sync::mpsc;
+
+mod common;
+
+/// One end of the bus
+pub struct ChannelTransport<R: ServiceRole> {
+    tx: Option<mpsc::UnboundedSender<TxJsonRpcMessage<R>>>,
+    rx: mpsc::UnboundedReceiver<RxJsonRpcMessage<R>>,
+}
+
+impl<R: ServiceRole> Transport<R> for ChannelTransport<R> {
+    type Error = std::io::Error;
+
+    fn send(
+        &mut self,
+        item: TxJsonRpcMessage<R>,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        // queued right away rather than in the future, so the messages keep the order of the
+        // calls; the channel is unbounded, a bounded bus would wait for room in the future
+        let sent = match &self.tx {
+            Some(tx) => tx.send(item).map_err(|_| "the peer is gone"),
+            None => Err("the transport is closed"),
+        };
+        std::future::ready(
+            sent.map_err(|reason| std::io::Error::new(std::io::ErrorKind::BrokenPipe, reason)),
+        )
+    }
+
+    fn receive(&mut self) -> impl Future<Output = Option<RxJsonRpcMessage<R>>> + Send {
+        // cancel safe: a message leaves the channel only when the future completes
+        self.rx.recv()
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        // dropping the sender ends the input of the peer
+        self.tx = None;
+        self.rx.close();
+        Ok(())
+    }
+}
+
+fn pair() -> (ChannelTransport<RoleServer>, ChannelTransport<RoleClient>) {
+    let (to_server, from_client) = mpsc::unbounded_channel();
+    let (to_client, from_server) = mpsc::unbounded_channel();
+    (
+        ChannelTransport {
+            tx: Some(to_client),
+            rx: from_client,
+        },
+        ChannelTransport {
+            tx: Some(to_server),
+            rx: from_server,
+        },
+    )
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    check_transport(|| async { pair() }).await?;
+    println!("the channel transport passed the conformance checks");
+
+    let (server_transport, client_transport) = pair();
+    let (server, client) = tokio::join!(
+        Calculator.serve(server_transport),
+        ().serve(client_transport)
+    );
+    let (server, client) = (server?, client?);
+    let tools = client.list_all_tools().await?;
+    println!("{tools:#?}");
+    client.cancel().await?;
+    server.waiting().await?;
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use rmcp::tool_client;
+
+use crate::params::{Sum, SumRequest};
+
+/// The tools of [`Calculator`](crate::server::Calculator), implemented for `Peer<RoleClient>`
+#[tool_client]
+pub trait CalculatorClient {
+    /// Calculate the sum of two numbers
+    async fn sum(&self, #[tool(aggr)] request: SumRequest) -> Sum;
+
+    /// Subtract two numbers
+    #[tool(name = "sub")]
+    async fn subtract(&self, minuend: i64, subtrahend: i64) -> i64;
+
+    /// The largest number the calculator handles
+    async fn max(&self) -> i64;
+}
@@ -0,0 +1,4 @@
+//! A server and a typed client sharing the parameters of the tools, see `main.rs`
+pub mod client;
+pub mod params;
+pub mod server;
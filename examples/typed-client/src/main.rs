@@ -0,0 +1,30 @@
+//! A server and a client sharing the parameters of the tools, the client calling them
+//! through a `#[tool_client]` trait; both run in the same process
+//!
+//! cargo run -p typed-client-example
+use anyhow::Result;
+use rmcp::transport::in_process;
+use typed_client_example::{
+    client::CalculatorClient,
+    params::{Sum, SumRequest},
+    server::Calculator,
+};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let (server, client) = in_process::serve_directly(Calculator, ()).await?;
+
+    let sum = client.sum(SumRequest { a: 1, b: 2 }).await?;
+    assert_eq!(sum, Sum { total: 3 });
+    println!("1 + 2 = {}", sum.total);
+
+    let difference = client.subtract(10, 4).await?;
+    println!("10 - 4 = {difference}");
+
+    let max = client.max().await?;
+    println!("the numbers go up to {max}");
+
+    client.cancel().await?;
+    server.waiting().await?;
+    Ok(())
+}
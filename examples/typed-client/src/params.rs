@@ -0,0 +1,16 @@
+//! The arguments and the results of the tools, used on both sides
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SumRequest {
+    /// the left hand side number
+    pub a: i64,
+    /// the right hand side number
+    pub b: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Sum {
+    pub total: i64,
+}
@@ -0,0 +1,29 @@
+use rmcp::{handler::server::wrapper::Json, tool};
+
+use crate::params::{Sum, SumRequest};
+
+#[derive(Debug, Clone, Default)]
+pub struct Calculator;
+
+#[tool(tool_box, description = "A simple calculator")]
+impl Calculator {
+    /// Calculate the sum of two numbers
+    #[tool]
+    async fn sum(&self, #[tool(aggr)] request: SumRequest) -> Json<Sum> {
+        Json(Sum {
+            total: request.a + request.b,
+        })
+    }
+
+    /// Subtract two numbers
+    #[tool(name = "sub")]
+    async fn subtract(&self, minuend: i64, subtrahend: i64) -> String {
+        (minuend - subtrahend).to_string()
+    }
+
+    /// The largest number the calculator handles
+    #[tool]
+    async fn max(&self) -> String {
+        i64::MAX.to_string()
+    }
+}